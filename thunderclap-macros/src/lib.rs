@@ -190,6 +190,7 @@ fn impl_has_visibility_macro(ast: syn::DeriveInput) -> TokenStream {
             let crate_name = find_crate_name(&ast.attrs)
                 .unwrap_or_else(|| syn::Ident::new("thunderclap", proc_macro2::Span::call_site()));
             let mut vis_ident = None;
+            let mut layout_ident = None;
             let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
             let name = &ast.ident;
 
@@ -197,18 +198,20 @@ fn impl_has_visibility_macro(ast: syn::DeriveInput) -> TokenStream {
                 syn::Fields::Named(fields) => {
                     for field in fields.named.iter() {
                         if let Some(ref ident) = field.ident {
-                            if chk_attrs_is_visibility(&field.attrs) {
+                            if vis_ident.is_none() && chk_attrs_is_visibility(&field.attrs) {
                                 vis_ident = IdentOrIndex::Ident(ident.clone()).into();
-                                break;
+                            } else if layout_ident.is_none() && chk_attrs_is_layout(&field.attrs) {
+                                layout_ident = IdentOrIndex::Ident(ident.clone()).into();
                             }
                         }
                     }
                 }
                 syn::Fields::Unnamed(fields) => {
                     for (i, field) in fields.unnamed.iter().enumerate() {
-                        if chk_attrs_is_visibility(&field.attrs) {
+                        if vis_ident.is_none() && chk_attrs_is_visibility(&field.attrs) {
                             vis_ident = IdentOrIndex::Index(i.into()).into();
-                            break;
+                        } else if layout_ident.is_none() && chk_attrs_is_layout(&field.attrs) {
+                            layout_ident = IdentOrIndex::Index(i.into()).into();
                         }
                     }
                 }
@@ -223,6 +226,29 @@ fn impl_has_visibility_macro(ast: syn::DeriveInput) -> TokenStream {
                     IdentOrIndex::Index(index) => quote! { self.#index },
                 };
 
+                // If the widget is layable, then the layout may also impose a visibility and a clip.
+                let (visibility, layout_clip) = match layout_ident {
+                    Some(IdentOrIndex::Ident(layout)) => (
+                        quote! { #ident.combine(self.#layout.visibility()) },
+                        quote! {
+                            #[inline]
+                            fn layout_clip(&self) -> Option<#crate_name::geom::AbsoluteRect> {
+                                self.#layout.clip()
+                            }
+                        },
+                    ),
+                    Some(IdentOrIndex::Index(layout)) => (
+                        quote! { #ident.combine(self.#layout.visibility()) },
+                        quote! {
+                            #[inline]
+                            fn layout_clip(&self) -> Option<#crate_name::geom::AbsoluteRect> {
+                                self.#layout.clip()
+                            }
+                        },
+                    ),
+                    None => (quote! { #ident }, quote! {}),
+                };
+
                 {
                     quote!{
                         impl #impl_generics #crate_name::base::HasVisibility for #name #ty_generics #where_clause {
@@ -233,8 +259,10 @@ fn impl_has_visibility_macro(ast: syn::DeriveInput) -> TokenStream {
 
                             #[inline]
                             fn visibility(&self) -> #crate_name::base::Visibility {
-                                #ident
+                                #visibility
                             }
+
+                            #layout_clip
                         }
                     }
                 }.into()
@@ -575,6 +603,18 @@ fn chk_attrs_is_size(attrs: &[syn::Attribute]) -> bool {
     false
 }
 
+/// Declares a widget composed of a tree of other widgets, along with its data and event handlers.
+///
+/// # Order of the children
+///
+/// Every widget in the tree becomes a field of the generated struct, declared in tree order (parents before their
+/// children, earlier siblings before later ones). Fields are drawn in declaration order and updated in reverse, so
+/// parents are drawn beneath their children, later siblings are drawn above earlier ones, and the widget drawn on top
+/// receives events first.
+///
+/// This is a breaking change from earlier versions, which declared the fields in reverse tree order (drawing the first
+/// widget of the tree on top and updating it first). Trees relying on the old order should reorder their overlapping
+/// siblings.
 #[proc_macro]
 pub fn rooftop(stream: TokenStream) -> TokenStream {
     let data = syn::parse_macro_input!(stream as RooftopData);
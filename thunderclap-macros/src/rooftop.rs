@@ -357,9 +357,10 @@ impl RooftopData {
             })
             .collect();
 
+        // Declared in tree order so that parents are drawn beneath their children
+        // and later siblings are drawn above earlier ones (and hence receive events first).
         let widgets_as_fields: Vec<proc_macro2::TokenStream> = flattened_nodes
             .iter()
-            .rev()
             .map(|node| {
                 let name = &node.var_name;
                 let type_name = &node.type_name;
@@ -377,6 +377,15 @@ fn painter_decl(
                         use thunderclap::{base::Resizable, ui::core::CoreWidget};
                         self.set_size(self.painter.size_hint(self.derive_state()));
                     }
+
+                    fn painted_state(
+                        &mut self,
+                    ) -> Option<(String, Vec<thunderclap::reclutch::display::DisplayCommand>)> {
+                        use thunderclap::ui::core::CoreWidget;
+                        let state = self.derive_state();
+                        let formatted = format!("{:?}", state);
+                        Some((formatted, self.painter.draw(state)))
+                    }
                 }
             }
         }
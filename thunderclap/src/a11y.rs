@@ -0,0 +1,49 @@
+//! Accessibility support, such as announcements to screen readers.
+//!
+//! Announcements behave like the live regions of the web: messages (e.g. validation errors, toasts or status
+//! changes) are read out by the screen reader without moving the focus. Widgets and apps make announcements through
+//! [`UpdateAuxiliary::announce`](../base/trait.UpdateAuxiliary.html#method.announce):
+//!
+//! ```ignore
+//! if !valid {
+//!     aux.announce(Announcement::assertive("The date must be in the future"));
+//! }
+//! ```
+//!
+//! `app::App` passes announcements on to its `ShellIntegration`, and from there to the backend by default. Since winit
+//! has no accessibility support, `WinitBackend` drops them; apps bridging to the accessibility API of the platform
+//! provide their own shell integration.
+
+/// How urgently an announcement is read out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Politeness {
+    /// Read out once the screen reader is idle, without interrupting the user (e.g. status changes).
+    Polite,
+    /// Read out immediately, interrupting whatever is being read (e.g. errors which need attention).
+    Assertive,
+}
+
+impl Default for Politeness {
+    fn default() -> Self {
+        Politeness::Polite
+    }
+}
+
+/// Message to be read out by screen readers, without moving the focus.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Announcement {
+    pub message: String,
+    pub politeness: Politeness,
+}
+
+impl Announcement {
+    /// Creates an announcement read out once the screen reader is idle.
+    pub fn polite(message: impl Into<String>) -> Self {
+        Announcement { message: message.into(), politeness: Politeness::Polite }
+    }
+
+    /// Creates an announcement read out immediately.
+    pub fn assertive(message: impl Into<String>) -> Self {
+        Announcement { message: message.into(), politeness: Politeness::Assertive }
+    }
+}
@@ -0,0 +1,337 @@
+//! Diagnostics of a running app, for attaching to bug reports.
+//!
+//! The event loop keeps a snapshot of the app (widget tree, metrics and a summary of the last frame) up to date, which
+//! `dump_diagnostics` writes to a single plain text report.
+//!
+//! If the app opts into it (see `AppOptions::diagnose_widget_states`), the summary of a frame also lists the state
+//! each widget was drawn with and what it drew, by kind of display command. These are taken from the painters of the
+//! widgets which were drawn, which paints every widget a second time each frame.
+
+use {
+    super::{GAux, UAux},
+    crate::{
+        base::{self, HasVisibility, WidgetChildren},
+        draw::HasTheme,
+        geom::*,
+    },
+    reclutch::display::{Color, DisplayCommand, Size},
+    std::{
+        collections::VecDeque,
+        fmt::Write as _,
+        fs, io,
+        path::Path,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+const REPORT_HEADER: &str = "thunderclap-diagnostics 1";
+
+/// Number of recent metrics kept for the report.
+const METRICS_CAPACITY: usize = 64;
+/// Number of recent slow widgets kept for the report.
+const SLOW_WIDGETS_CAPACITY: usize = 32;
+
+pub(super) type DynWidget =
+    dyn WidgetChildren<UpdateAux = UAux, GraphicalAux = GAux, DisplayObject = DisplayCommand>;
+
+struct WidgetRecord {
+    depth: usize,
+    name: &'static str,
+    bounds: AbsoluteRect,
+    visibility: base::Visibility,
+    /// State drawn by the painter of the widget, if it's drawn by a painter.
+    state: Option<String>,
+    /// Number of display commands drawn by the painter of the widget, by kind.
+    commands: Vec<(String, usize)>,
+}
+
+#[derive(Default)]
+struct FrameSummary {
+    number: u64,
+    draw_time: Duration,
+    background: Option<Color>,
+    widgets_drawn: usize,
+    commands: usize,
+}
+
+/// Returns the kind of a display command, which is the name of its variant (e.g. `Item` or `Save`).
+fn command_kind(command: &DisplayCommand) -> String {
+    let formatted = format!("{:?}", command);
+    formatted.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_string()
+}
+
+/// Counts display commands by kind, in the order each kind first appears.
+fn count_commands(commands: &[DisplayCommand]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for kind in commands.iter().map(command_kind) {
+        match counts.iter_mut().find(|(counted, _)| *counted == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts
+}
+
+struct Diagnostics {
+    started: Option<Instant>,
+    app_name: String,
+    theme: String,
+    window_size: Size,
+    scale: f32,
+    focused: bool,
+    cursor: AbsolutePoint,
+    /// Whether the states and display commands of widgets are captured.
+    widget_states: bool,
+    /// Recent metrics, with the time they were recorded.
+    metrics: VecDeque<(Instant, base::Metric)>,
+    /// Recent widgets which exceeded their budget, with the time they were reported.
+    slow_widgets: VecDeque<(Instant, base::SlowWidget)>,
+    frame: FrameSummary,
+    widgets: Vec<WidgetRecord>,
+}
+
+lazy_static::lazy_static! {
+    static ref DIAGNOSTICS: Mutex<Diagnostics> = Mutex::new(Diagnostics {
+        started: None,
+        app_name: String::new(),
+        theme: String::new(),
+        window_size: Size::zero(),
+        scale: 1.0,
+        focused: true,
+        cursor: AbsolutePoint::zero(),
+        widget_states: false,
+        metrics: VecDeque::new(),
+        slow_widgets: VecDeque::new(),
+        frame: Default::default(),
+        widgets: Vec::new(),
+    });
+}
+
+pub(super) fn start_session(
+    app_name: &str,
+    theme: &str,
+    window_size: Size,
+    scale: f32,
+    widget_states: bool,
+) {
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap();
+    diagnostics.widget_states = widget_states;
+    diagnostics.started = Some(Instant::now());
+    diagnostics.app_name = app_name.to_string();
+    diagnostics.theme = theme.to_string();
+    diagnostics.window_size = window_size;
+    diagnostics.scale = scale;
+}
+
+pub(super) fn record_metric(metric: base::Metric) {
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap();
+    if diagnostics.metrics.len() == METRICS_CAPACITY {
+        diagnostics.metrics.pop_front();
+    }
+    diagnostics.metrics.push_back((Instant::now(), metric));
+}
+
+/// Keeps the widgets which exceeded their budget (see `base::set_widget_budget`) for the report.
+pub(super) fn record_slow_widgets(slow_widgets: &[base::SlowWidget]) {
+    if slow_widgets.is_empty() {
+        return;
+    }
+
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap();
+    let now = Instant::now();
+    for slow in slow_widgets {
+        if diagnostics.slow_widgets.len() == SLOW_WIDGETS_CAPACITY {
+            diagnostics.slow_widgets.pop_front();
+        }
+        diagnostics.slow_widgets.push_back((now, slow.clone()));
+    }
+}
+
+pub(super) fn set_window(window_size: Size, scale: f32, focused: bool, cursor: AbsolutePoint) {
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap();
+    diagnostics.window_size = window_size;
+    diagnostics.scale = scale;
+    diagnostics.focused = focused;
+    diagnostics.cursor = cursor;
+}
+
+/// Captures the widget tree and a summary of the frame which was just drawn.
+///
+/// If the states of widgets are captured, the painter of each widget which was drawn is asked for its state and
+/// display commands once more (see `draw::HasTheme::painted_state`), since the commands drawn are only held by the
+/// graphics display.
+pub(super) fn capture_frame(roots: &mut [&mut DynWidget], background: Color, draw_time: Duration) {
+    fn walk(widget: &mut DynWidget, depth: usize, states: bool, records: &mut Vec<WidgetRecord>) {
+        let visibility = widget.visibility();
+        let painted = if states && visibility.renders() { widget.painted_state() } else { None };
+        let (state, commands) = match painted {
+            Some((state, commands)) => (Some(state), count_commands(&commands)),
+            None => (None, Vec::new()),
+        };
+        records.push(WidgetRecord {
+            depth,
+            name: widget.widget_name(),
+            bounds: widget.abs_bounds(),
+            visibility,
+            state,
+            commands,
+        });
+        for child in widget.children_mut() {
+            walk(child, depth + 1, states, records);
+        }
+    }
+
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap();
+    let diagnostics = &mut *diagnostics;
+
+    diagnostics.widgets.clear();
+    for root in roots.iter_mut() {
+        walk(*root, 0, diagnostics.widget_states, &mut diagnostics.widgets);
+    }
+
+    let drawn = diagnostics.widgets.iter().filter(|record| record.visibility.renders());
+    diagnostics.frame = FrameSummary {
+        number: diagnostics.frame.number + 1,
+        draw_time,
+        background: Some(background),
+        widgets_drawn: drawn.clone().count(),
+        commands: drawn.flat_map(|record| record.commands.iter()).map(|(_, count)| count).sum(),
+    };
+}
+
+/// Writes a diagnostics report of the running app to `path`, for users to attach to bug reports.
+///
+/// The report is a single plain text file containing the name of the app and its theme, the state of the window, the
+/// most recent usage metrics (see `base::Metric`), the widgets which most recently exceeded their budget (if the
+/// watchdog is enabled, see `base::set_widget_budget`), a summary of the last frame drawn, and the widget tree with the
+/// bounds and visibility of each widget. If `AppOptions::diagnose_widget_states` is set, each widget also lists the
+/// state it was drawn with and how many display commands of each kind it drew.
+///
+/// The states include the content of widgets (e.g. the text of a text area, but not of a password box, which is
+/// redacted), so users should be given a chance to look over a report with states before it's shared.
+///
+/// This can be called from anywhere (e.g. from a "Report a bug" button, or a panic hook); the report reflects the app
+/// as of the last frame drawn.
+pub fn dump_diagnostics(path: impl AsRef<Path>) -> io::Result<()> {
+    let report = {
+        let diagnostics = match DIAGNOSTICS.lock() {
+            Ok(diagnostics) => diagnostics,
+            // a panic while the snapshot was being captured shouldn't prevent reporting that panic
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        diagnostics.report()
+    };
+    fs::write(path, report)
+}
+
+impl Diagnostics {
+    fn report(&self) -> String {
+        let mut report = String::new();
+        // writing into a `String` can't fail
+        let _ = self.write_report(&mut report);
+        report
+    }
+
+    fn write_report(&self, report: &mut String) -> std::fmt::Result {
+        let now = Instant::now();
+        let since_start =
+            |instant: Instant| self.started.map(|started| instant.duration_since(started));
+
+        writeln!(report, "{}", REPORT_HEADER)?;
+        writeln!(report, "thunderclap {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(report, "os: {} ({})", std::env::consts::OS, std::env::consts::ARCH)?;
+
+        writeln!(report, "\n[app]")?;
+        writeln!(report, "name: {}", self.app_name)?;
+        writeln!(report, "theme: {}", self.theme)?;
+        writeln!(report, "uptime: {:?}", since_start(now).unwrap_or_default())?;
+        writeln!(
+            report,
+            "window: {}x{} pixels (scale {}), {}",
+            self.window_size.width,
+            self.window_size.height,
+            self.scale,
+            if self.focused { "focused" } else { "unfocused" }
+        )?;
+        writeln!(report, "cursor: {}, {}", self.cursor.x, self.cursor.y)?;
+
+        writeln!(report, "\n[metrics]")?;
+        for (instant, metric) in &self.metrics {
+            writeln!(report, "{:?}: {:?}", since_start(*instant).unwrap_or_default(), metric)?;
+        }
+
+        writeln!(report, "\n[slow widgets]")?;
+        for (instant, slow) in &self.slow_widgets {
+            writeln!(report, "{:?}: {:?}", since_start(*instant).unwrap_or_default(), slow)?;
+        }
+
+        writeln!(report, "\n[last frame]")?;
+        writeln!(report, "number: {}", self.frame.number)?;
+        writeln!(report, "draw time: {:?}", self.frame.draw_time)?;
+        if let Some(background) = self.frame.background {
+            writeln!(
+                report,
+                "background: {:.3}, {:.3}, {:.3}, {:.3}",
+                background.red, background.green, background.blue, background.alpha
+            )?;
+        }
+        writeln!(report, "widgets: {}, drawn: {}", self.widgets.len(), self.frame.widgets_drawn)?;
+        writeln!(report, "display commands: {}", self.frame.commands)?;
+
+        writeln!(report, "\n[widgets]")?;
+        for record in &self.widgets {
+            writeln!(
+                report,
+                "{:indent$}{} at {}, {} size {}x{} ({:?})",
+                "",
+                record.name,
+                record.bounds.origin.x,
+                record.bounds.origin.y,
+                record.bounds.size.width,
+                record.bounds.size.height,
+                record.visibility,
+                indent = record.depth * 2
+            )?;
+            if let Some(state) = &record.state {
+                writeln!(report, "{:indent$}state: {}", "", state, indent = record.depth * 2 + 2)?;
+            }
+            if !record.commands.is_empty() {
+                let kinds: Vec<String> = record
+                    .commands
+                    .iter()
+                    .map(|(kind, count)| format!("{} {}", kind, count))
+                    .collect();
+                writeln!(
+                    report,
+                    "{:indent$}commands: {}",
+                    "",
+                    kinds.join(", "),
+                    indent = record.depth * 2 + 2
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_counted_by_kind() {
+        let commands = [
+            DisplayCommand::Save,
+            DisplayCommand::Clear(Color::new(0.0, 0.0, 0.0, 1.0)),
+            DisplayCommand::Restore,
+            DisplayCommand::Save,
+            DisplayCommand::Restore,
+        ];
+        assert_eq!(
+            count_commands(&commands),
+            vec![("Save".to_string(), 2), ("Clear".to_string(), 1), ("Restore".to_string(), 2)]
+        );
+    }
+}
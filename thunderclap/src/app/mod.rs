@@ -0,0 +1,863 @@
+use {
+    crate::{
+        a11y,
+        base::{self, Resizable, UpdateAuxiliary},
+        draw,
+        error::AppError,
+        geom::*,
+        recovery, ui,
+    },
+    reclutch::{
+        display::{
+            self, skia, Color, CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, GraphicsDisplayStroke, Size, Vector,
+        },
+        event::RcEventQueue,
+        prelude::*,
+    },
+};
+
+mod diagnostics;
+#[cfg(feature = "app")]
+mod winit;
+
+pub use self::diagnostics::dump_diagnostics;
+#[cfg(feature = "app")]
+pub use self::winit::WinitBackend;
+
+/// Creates an application with a given theme and root widget.
+/// The application uses the Skia OpenGL graphics backend, in a window created through winit.
+/// Small details of app creation can be controlled with `AppOptions`.
+#[cfg(feature = "app")]
+pub fn create<R, T, TF, RF>(
+    theme: TF,
+    root: RF,
+    opts: AppOptions,
+) -> Result<App<R, WinitBackend>, AppError>
+where
+    R: base::WidgetChildren<UpdateAux = UAux, GraphicalAux = GAux, DisplayObject = DisplayCommand>,
+    T: draw::Theme,
+    TF: FnOnce(&mut GAux, &mut dyn GraphicsDisplay) -> T,
+    RF: FnOnce(&mut UAux, &T) -> R,
+{
+    create_with_backend(theme, root, opts)
+}
+
+/// Creates an application with a given theme and root widget, running on a given windowing backend.
+/// This is how alternative backends (e.g. SDL2, or a kiosk platform without a window manager) are plugged in;
+/// see [`Backend`](trait.Backend.html).
+pub fn create_with_backend<B, R, T, TF, RF>(
+    theme: TF,
+    root: RF,
+    opts: AppOptions,
+) -> Result<App<R, B>, AppError>
+where
+    B: Backend,
+    R: base::WidgetChildren<UpdateAux = UAux, GraphicalAux = GAux, DisplayObject = DisplayCommand>,
+    T: draw::Theme,
+    TF: FnOnce(&mut GAux, &mut dyn GraphicsDisplay) -> T,
+    RF: FnOnce(&mut UAux, &T) -> R,
+{
+    let created = std::time::Instant::now();
+    let (backend, mut display) = B::create(&opts)?;
+    base::set_widget_budget(opts.widget_budget);
+
+    let g_aux = GAux { scale: backend.scale_factor() };
+    let mut u_aux = UAux {
+        window_queue: RcEventQueue::new(),
+        input_grabs: Default::default(),
+        tooltips: Default::default(),
+        focus_chain: Default::default(),
+        toasts: Default::default(),
+        overlays: Default::default(),
+        cursor: Default::default(),
+        cursor_icon: Default::default(),
+        animating: false,
+        attention: None,
+        taskbar_progress: Default::default(),
+        text_input: None,
+        window_regions: Vec::new(),
+        window_actions: Vec::new(),
+        announcements: Vec::new(),
+        metrics: None,
+        g_aux,
+    };
+
+    let theme = theme(&mut u_aux.g_aux, &mut display);
+    diagnostics::start_session(
+        &opts.name,
+        theme.name(),
+        opts.window_size,
+        u_aux.g_aux.scale,
+        opts.diagnose_widget_states,
+    );
+    let root = root(&mut u_aux, &theme);
+
+    let mut toasts: ui::ToastManagerWidget<UAux, GAux> = ui::WidgetConstructor::construct(
+        <ui::ToastManager as ui::WidgetConstructor<UAux, GAux>>::from_theme(&theme),
+        &theme,
+        &mut u_aux,
+    );
+    toasts.set_size(opts.window_size / u_aux.g_aux.scale);
+    u_aux.toasts = toasts.handle();
+
+    let overlays = ui::OverlayManager::new(&u_aux);
+    u_aux.overlays = overlays.handle();
+
+    let mut app = App {
+        root,
+        toasts,
+        overlays,
+        background: opts.background,
+        recovery: opts.recovery,
+        shell: Box::new(DefaultShell),
+        slow_widgets: None,
+        u_aux,
+        display,
+        backend,
+        size: opts.window_size,
+        resize_border: if opts.frameless { Some(opts.resize_border) } else { None },
+        snap_shortcuts: opts.frameless && opts.snapping,
+        created,
+
+        command_group_pre: CommandGroup::new(),
+        command_group_post: CommandGroup::new(),
+        command_group_slow: CommandGroup::new(),
+    };
+
+    for _ in 0..opts.warmup {
+        app.root.update(&mut app.u_aux);
+        app.root.draw(&mut app.display, &mut app.u_aux.g_aux);
+    }
+
+    Ok(app)
+}
+
+/// Event produced by a [`Backend`](trait.Backend.html), translated from a native event.
+#[derive(Debug, Clone)]
+pub enum BackendEvent {
+    /// Input to be passed to widgets, in logical coordinates.
+    /// Mouse presses and releases also clear the focus before being passed on.
+    Window(base::WindowEvent),
+    /// The window should be redrawn, as requested by `Backend::request_redraw`.
+    Redraw,
+    /// The window was resized; the new size is in physical pixels.
+    Resized(Size),
+    /// The scale factor changed, along with the size of the window in physical pixels.
+    ScaleFactorChanged(f32, Size),
+    /// The window gained or lost focus.
+    Focused(bool),
+    /// The user asked to close the window.
+    CloseRequested,
+    /// The window was snapped to part of the screen, or is no longer snapped, either by the user or through
+    /// `WindowAction::Snap` and `WindowAction::Restore`.
+    Snapped(Option<base::WindowSnap>),
+    /// All pending native events have been handled.
+    EventsCleared,
+}
+
+/// What the event loop of a backend should do once an event has been handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoopControl {
+    /// Wait until the next native event.
+    Wait,
+    /// Continue immediately, even if there aren't any native events.
+    Poll,
+    /// Exit the event loop, and with it the app.
+    Exit,
+}
+
+/// Windowing backend which an app runs on; responsible for creating the window and its graphics surface, and for
+/// translating native events.
+///
+/// [`WinitBackend`](struct.WinitBackend.html) is the default backend, enabled by the `app` feature.
+/// Alternative backends only need the `app-core` feature, and are used with
+/// [`create_with_backend`](fn.create_with_backend.html).
+pub trait Backend: Sized + 'static {
+    /// Native event, passed on to the callback given to `App::start`.
+    type RawEvent;
+
+    /// Creates the window from the given options, along with a graphics display drawing into it.
+    fn create(opts: &AppOptions) -> Result<(Self, skia::SkiaGraphicsDisplay), AppError>;
+    /// Returns the ratio of physical pixels to logical pixels.
+    fn scale_factor(&self) -> f32;
+    /// Runs the event loop, never returning.
+    ///
+    /// Every native event which translates into a `BackendEvent` is passed to `handler`, along with the native event
+    /// itself (if it can be passed on); native events which don't translate are ignored.
+    fn run<F>(self, handler: F) -> !
+    where
+        F: 'static + FnMut(&mut Self, BackendEvent, Option<Self::RawEvent>) -> LoopControl;
+    /// Requests that the window be redrawn, which is reported as `BackendEvent::Redraw`.
+    fn request_redraw(&self);
+    /// Shows the frame drawn into the graphics display (e.g. by swapping buffers).
+    fn present(&mut self);
+    /// Changes the icon of the mouse cursor while it's over the window.
+    fn set_cursor_icon(&self, icon: base::CursorIcon);
+    /// Starts moving the window with the mouse, until the left mouse button is released. Does nothing by default.
+    fn begin_window_drag(&mut self) {}
+    /// Starts resizing the window from an edge with the mouse, until the left mouse button is released.
+    /// Does nothing by default.
+    fn begin_window_resize(&mut self, _edge: base::ResizeEdge) {}
+    /// Minimizes, maximizes, restores or snaps the window. `WindowAction::Close` is handled by the app, and never
+    /// passed on. Changes to snapping should be reported as `BackendEvent::Snapped`. Does nothing by default.
+    fn window_action(&mut self, _action: base::WindowAction) {}
+    /// Requests the attention of the user. Does nothing by default.
+    fn request_attention(&self, _attention: base::UserAttention) {}
+    /// Shows progress on the taskbar button or dock icon of the window. Does nothing by default.
+    fn set_taskbar_progress(&self, _progress: base::TaskbarProgress) {}
+    /// Announces a message to screen readers. Does nothing by default.
+    fn announce(&self, _announcement: a11y::Announcement) {}
+    /// Forwards the text input requested by the focused text widget to the input method (e.g. showing an on-screen
+    /// keyboard suited to the hints), or ends text input if `None`. Does nothing by default.
+    fn set_text_input(&self, _request: Option<base::TextInputRequest>) {}
+}
+
+/// Integration with the desktop shell, for feedback shown outside of the window.
+///
+/// The default integration (`DefaultShell`) only covers what the backend supports;
+/// apps can provide their own to support more platforms (e.g. taskbar progress on Windows).
+pub trait ShellIntegration<B: Backend> {
+    /// Requests the attention of the user, while the window isn't focused.
+    fn request_attention(&mut self, backend: &B, attention: base::UserAttention);
+    /// Shows progress on the taskbar button or dock icon of the window.
+    fn set_taskbar_progress(&mut self, backend: &B, progress: base::TaskbarProgress);
+    /// Announces a message to screen readers, through the accessibility API of the platform.
+    /// Passes the announcement on to the backend by default.
+    fn announce(&mut self, backend: &B, announcement: a11y::Announcement) {
+        backend.announce(announcement);
+    }
+}
+
+/// Shell integration supported by the backend (e.g. bouncing the dock icon on macOS, for `WinitBackend`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultShell;
+
+impl<B: Backend> ShellIntegration<B> for DefaultShell {
+    #[inline]
+    fn request_attention(&mut self, backend: &B, attention: base::UserAttention) {
+        backend.request_attention(attention);
+    }
+
+    #[inline]
+    fn set_taskbar_progress(&mut self, backend: &B, progress: base::TaskbarProgress) {
+        backend.set_taskbar_progress(progress);
+    }
+}
+
+/// Settings on how an app should be created.
+#[derive(Debug, Clone)]
+pub struct AppOptions {
+    /// The name of the application; usually translates to the window title.
+    pub name: String,
+    /// The number warmup cycles (i.e. the amount of times `update` and `draw` should be called offscreen).
+    pub warmup: u32,
+    /// The background color of the window.
+    pub background: Color,
+    /// Initial size of the app window.
+    pub window_size: Size,
+    /// Crash recovery session, autosaved while the app runs and closed when the window is closed.
+    pub recovery: Option<recovery::Recovery>,
+    /// Whether the window is created without the decorations of the window manager (i.e. title bar and borders),
+    /// for apps drawing their own chrome (see `ui::TitleBar` and `UpdateAuxiliary::declare_window_region`).
+    pub frameless: bool,
+    /// Width of the band along the edges of a frameless window which resizes it, in logical pixels.
+    pub resize_border: f32,
+    /// Whether the user can snap a frameless window to halves and quarters of the screen, by dragging it against
+    /// the left or right edge of the screen or with the logo key and the arrow keys (see `base::WindowSnap`).
+    /// Decorated windows are snapped by the window manager, if at all.
+    pub snapping: bool,
+    /// Time budgets for each widget, if the watchdog should check them (see `base::set_widget_budget`).
+    /// Widgets exceeding their budget are passed to `App::slow_widgets`, listed in the diagnostics report (see
+    /// `dump_diagnostics`) and outlined in the window until the next frame.
+    pub widget_budget: Option<base::WidgetBudget>,
+    /// Whether the diagnostics report (see `dump_diagnostics`) lists the state each widget was drawn with and the
+    /// display commands it drew. This paints every widget a second time each frame, so it's best left disabled unless
+    /// a report is needed.
+    pub diagnose_widget_states: bool,
+}
+
+impl Default for AppOptions {
+    fn default() -> Self {
+        AppOptions {
+            name: "Thunderclap App".into(),
+            warmup: 2,
+            background: Color::new(1.0, 1.0, 1.0, 1.0),
+            window_size: Size::new(500.0, 500.0),
+            recovery: None,
+            frameless: false,
+            resize_border: 6.0,
+            snapping: true,
+            widget_budget: None,
+            diagnose_widget_states: false,
+        }
+    }
+}
+
+/// Thunderclap/Reclutch based application.
+pub struct App<R, B>
+where
+    R: base::WidgetChildren<UpdateAux = UAux, GraphicalAux = GAux, DisplayObject = DisplayCommand>,
+    B: Backend,
+{
+    /// Root widget.
+    pub root: R,
+    /// Overlay showing toast notifications above the root widget.
+    /// Toasts can also be shown through `UAux::toasts`.
+    pub toasts: ui::ToastManagerWidget<UAux, GAux>,
+    /// Floating widgets (menus, tooltips, dialogs, dropdowns) above the root widget and the toasts.
+    /// Overlays can also be shown through `UAux::overlays`.
+    pub overlays: ui::OverlayManager<UAux, GAux>,
+    /// Background color.
+    pub background: Color,
+    /// Crash recovery session.
+    pub recovery: Option<recovery::Recovery>,
+    /// Integration with the desktop shell, which attention requests and taskbar progress are passed to.
+    pub shell: Box<dyn ShellIntegration<B>>,
+    /// Receives the widgets which exceeded their budget, if the watchdog is enabled (see `AppOptions::widget_budget`)
+    /// and the application handles them (e.g. by logging them).
+    pub slow_widgets: Option<Box<dyn FnMut(&base::SlowWidget)>>,
+    /// Update auxiliary.
+    pub u_aux: UAux,
+    /// Graphics display (Skia backend).
+    pub display: skia::SkiaGraphicsDisplay,
+    /// Windowing backend.
+    pub backend: B,
+    size: Size,
+    /// Width of the resize border, if the window is frameless.
+    resize_border: Option<f32>,
+    /// Whether the snapping shortcuts are handled.
+    snap_shortcuts: bool,
+    /// When the app was created, for measuring the time to the first frame.
+    created: std::time::Instant,
+
+    command_group_pre: CommandGroup,
+    command_group_post: CommandGroup,
+    /// Outlines of the widgets which exceeded their budget.
+    command_group_slow: CommandGroup,
+}
+
+impl<R, B> App<R, B>
+where
+    R: base::WidgetChildren<UpdateAux = UAux, GraphicalAux = GAux, DisplayObject = DisplayCommand>,
+    B: Backend,
+{
+    /// Starts the event loop.
+    ///
+    /// `f` is given every native event which the app handles, and can override what the event loop does next.
+    pub fn start<F>(self, mut f: F) -> !
+    where
+        F: 'static + FnMut(&B::RawEvent) -> Option<LoopControl>,
+        R: 'static,
+    {
+        let App {
+            mut root,
+            mut toasts,
+            mut overlays,
+            background,
+            recovery,
+            mut shell,
+            mut slow_widgets,
+            mut u_aux,
+            mut display,
+            backend,
+            mut size,
+            resize_border,
+            snap_shortcuts,
+            created,
+
+            mut command_group_pre,
+            mut command_group_post,
+            mut command_group_slow,
+        } = self;
+
+        let mut cursor_icon = base::CursorIcon::Default;
+        let mut taskbar_progress = base::TaskbarProgress::None;
+        let mut text_input = None;
+        let mut window_regions = Vec::new();
+        let mut maximized = false;
+        let mut snap = None;
+        let mut focused = true;
+        let mut first_frame = true;
+        let mut escape_cascade = base::EscapeCascade::default();
+        // bounds of the widgets which exceeded their budget since the last frame
+        let mut slow_bounds = Vec::new();
+
+        backend.run(move |backend, event, raw_event| {
+            let mut control = LoopControl::Wait;
+            let mut key_press = None;
+
+            match event {
+                BackendEvent::EventsCleared => {
+                    if let Some(recovery) = &recovery {
+                        // Recovery is best-effort; a failed autosave is retried on the next interval.
+                        let _ = recovery.autosave();
+                    }
+                    backend.request_redraw();
+                }
+                BackendEvent::Redraw => {
+                    let draw_start = std::time::Instant::now();
+
+                    if display.size().0 != size.width as _ || display.size().1 != size.height as _ {
+                        display.resize((size.width as _, size.height as _)).unwrap();
+                    }
+
+                    command_group_pre.push(
+                        &mut display,
+                        &[
+                            DisplayCommand::Save,
+                            DisplayCommand::Clear(background),
+                            DisplayCommand::Scale(Vector::new(
+                                u_aux.g_aux.scale,
+                                u_aux.g_aux.scale,
+                            )),
+                        ],
+                        display::ZOrder(std::i32::MIN),
+                        false,
+                        None,
+                    );
+
+                    base::invoke_draw(&mut root, &mut display, &mut u_aux.g_aux);
+                    base::invoke_draw(&mut toasts, &mut display, &mut u_aux.g_aux);
+                    overlays.draw(&mut display, &mut u_aux.g_aux);
+
+                    if base::widget_budget().is_some() {
+                        let mut builder = DisplayListBuilder::new();
+                        for bounds in slow_bounds.drain(..) {
+                            builder.push_rectangle(
+                                bounds.inflate(1.0, 1.0).cast_unit(),
+                                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                                    thickness: 2.0,
+                                    color: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+                                    ..Default::default()
+                                }),
+                                None,
+                            );
+                        }
+                        command_group_slow.repaint();
+                        command_group_slow.push(
+                            &mut display,
+                            &builder.build(),
+                            display::ZOrder(std::i32::MAX - 1),
+                            false,
+                            None,
+                        );
+                    }
+
+                    command_group_post.push(
+                        &mut display,
+                        &[DisplayCommand::Restore],
+                        display::ZOrder(std::i32::MAX),
+                        false,
+                        None,
+                    );
+
+                    display.present(None).unwrap();
+
+                    backend.present();
+
+                    diagnostics::set_window(size, u_aux.g_aux.scale, focused, u_aux.cursor);
+                    let mut widgets: Vec<&mut diagnostics::DynWidget> =
+                        vec![&mut root, &mut toasts];
+                    widgets.extend(overlays.widgets_mut());
+                    diagnostics::capture_frame(&mut widgets, background, draw_start.elapsed());
+
+                    // slow draws are outlined in the next frame
+                    let slow = base::take_slow_widgets();
+                    slow_bounds.extend(slow.iter().map(|slow| slow.bounds));
+                    diagnostics::record_slow_widgets(&slow);
+                    if let Some(slow_widgets) = &mut slow_widgets {
+                        slow.iter().for_each(|slow| slow_widgets(slow));
+                    }
+
+                    if first_frame {
+                        first_frame = false;
+                        u_aux.record_metric(base::Metric::TimeToFirstFrame(created.elapsed()));
+                    }
+                }
+                BackendEvent::CloseRequested => {
+                    if let Some(recovery) = &recovery {
+                        let _ = recovery.close();
+                    }
+                    control = LoopControl::Exit;
+                }
+                BackendEvent::Snapped(snapped) => {
+                    snap = snapped;
+                    if snap.is_some() {
+                        maximized = false;
+                    }
+                    u_aux.emit_window_event(base::WindowEvent::Snap(snap));
+                }
+                BackendEvent::ScaleFactorChanged(scale, window_size) => {
+                    u_aux.g_aux.scale = scale;
+                    size = window_size;
+                    toasts.set_size(size / u_aux.g_aux.scale);
+
+                    command_group_pre.repaint();
+                }
+                BackendEvent::Resized(window_size) => {
+                    size = window_size;
+                    toasts.set_size(size / u_aux.g_aux.scale);
+                }
+                BackendEvent::Focused(true) => {
+                    if !focused {
+                        focused = true;
+                        u_aux.emit_window_event(base::WindowEvent::Activate);
+                    }
+                }
+                BackendEvent::Focused(false) => {
+                    if focused {
+                        focused = false;
+                        u_aux.emit_window_event(base::WindowEvent::Deactivate);
+                    }
+                }
+                BackendEvent::Window(event) => {
+                    match &event {
+                        base::WindowEvent::MousePress(press) => {
+                            u_aux.emit_window_event(base::WindowEvent::ClearFocus);
+
+                            // regions are those declared during the previous update pass
+                            let (position, button, _) = *press.get();
+                            if button == base::MouseButton::Left {
+                                let frame = window_frame(
+                                    resize_border,
+                                    maximized,
+                                    size / u_aux.g_aux.scale,
+                                );
+                                match window_region_at(&window_regions, frame, position) {
+                                    Some(base::WindowRegion::Caption) => {
+                                        backend.begin_window_drag()
+                                    }
+                                    Some(base::WindowRegion::Resize(edge)) => {
+                                        backend.begin_window_resize(edge)
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        base::WindowEvent::MouseRelease(_) => {
+                            u_aux.emit_window_event(base::WindowEvent::ClearFocus);
+                        }
+                        base::WindowEvent::KeyPress(press) if snap_shortcuts => {
+                            let (key, modifiers) = *press.get();
+                            if modifiers.logo {
+                                if let Some(action) =
+                                    base::WindowSnap::shortcut(key, snap, maximized)
+                                {
+                                    // the shortcut isn't passed on to widgets
+                                    press.consume();
+                                    u_aux.window_actions.push(action);
+                                }
+                            }
+                        }
+                        base::WindowEvent::MouseMove(_)
+                        | base::WindowEvent::PenPress(_)
+                        | base::WindowEvent::PenMove(_)
+                        | base::WindowEvent::PenRelease(_) => {
+                            if let Some(position) = event.cursor_position() {
+                                u_aux.cursor = position;
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    match &event {
+                        base::WindowEvent::KeyPress(press)
+                            if press.get().0 == base::KeyInput::Escape && !press.is_consumed() =>
+                        {
+                            // widgets receive escape one stage at a time instead of the press itself
+                            u_aux.emit_window_event(escape_cascade.begin());
+                        }
+                        base::WindowEvent::KeyPress(press) => {
+                            key_press = Some(press.clone());
+                            u_aux.emit_window_event(event);
+                        }
+                        _ => u_aux.emit_window_event(event),
+                    }
+                }
+            }
+
+            if let Some(raw_event) = &raw_event {
+                if let Some(cf) = f(raw_event) {
+                    control = cf;
+                }
+            }
+
+            u_aux.cursor_icon = base::CursorIcon::Default;
+            u_aux.animating = false;
+            u_aux.text_input = None;
+            u_aux.window_regions.clear();
+
+            u_aux.focus_chain.begin_pass();
+
+            // The overlays and toasts are above the root, hence they receive events first.
+            overlays.update(&mut u_aux);
+            toasts.update(&mut u_aux);
+            root.update(&mut u_aux);
+
+            // keys which no widget consumed move the focus, once the widgets have handled them
+            if let Some(press) = key_press.filter(|press| !press.is_consumed()) {
+                let (key, modifiers) = *press.get();
+                if let Some(id) = u_aux.focus_chain.navigate(key, modifiers) {
+                    u_aux.emit_window_event(base::WindowEvent::ClearFocus);
+                    u_aux.emit_window_event(base::WindowEvent::Focus(base::ConsumableEvent::new(
+                        id,
+                    )));
+                    if control == LoopControl::Wait {
+                        control = LoopControl::Poll;
+                    }
+                }
+            }
+
+            let slow = base::take_slow_widgets();
+            slow_bounds.extend(slow.iter().map(|slow| slow.bounds));
+            diagnostics::record_slow_widgets(&slow);
+            if let Some(slow_widgets) = &mut slow_widgets {
+                slow.iter().for_each(|slow| slow_widgets(slow));
+            }
+
+            // an unhandled stage of the escape cascade is followed by the next one on another pass
+            if let Some(event) = escape_cascade.advance() {
+                u_aux.emit_window_event(event);
+                if control == LoopControl::Wait {
+                    control = LoopControl::Poll;
+                }
+            }
+
+            std::mem::swap(&mut window_regions, &mut u_aux.window_regions);
+
+            // polling keeps the event loop running, which updates and redraws every frame
+            if u_aux.animating && control == LoopControl::Wait {
+                control = LoopControl::Poll;
+            }
+
+            if u_aux.cursor_icon == base::CursorIcon::Default {
+                let frame = window_frame(resize_border, maximized, size / u_aux.g_aux.scale);
+                if let Some(base::WindowRegion::Resize(edge)) =
+                    window_region_at(&window_regions, frame, u_aux.cursor)
+                {
+                    u_aux.cursor_icon = edge.cursor_icon();
+                }
+            }
+
+            if u_aux.cursor_icon != cursor_icon {
+                cursor_icon = u_aux.cursor_icon;
+                backend.set_cursor_icon(cursor_icon);
+            }
+
+            if u_aux.text_input != text_input {
+                text_input = u_aux.text_input;
+                backend.set_text_input(text_input);
+            }
+
+            if let Some(attention) = u_aux.attention.take() {
+                if !focused {
+                    shell.request_attention(backend, attention);
+                }
+            }
+
+            if u_aux.taskbar_progress != taskbar_progress {
+                taskbar_progress = u_aux.taskbar_progress;
+                shell.set_taskbar_progress(backend, taskbar_progress);
+            }
+
+            for announcement in u_aux.announcements.drain(..) {
+                shell.announce(backend, announcement);
+            }
+
+            for action in u_aux.window_actions.drain(..) {
+                match action {
+                    base::WindowAction::Close => {
+                        if let Some(recovery) = &recovery {
+                            let _ = recovery.close();
+                        }
+                        control = LoopControl::Exit;
+                    }
+                    base::WindowAction::Maximize => maximized = true,
+                    base::WindowAction::Restore | base::WindowAction::Snap(_) => maximized = false,
+                    base::WindowAction::Minimize => {}
+                }
+                if action != base::WindowAction::Close {
+                    backend.window_action(action);
+                }
+            }
+
+            control
+        })
+    }
+}
+
+/// Returns the rectangle of a frameless window and the width of its resize border, unless it's maximized.
+fn window_frame(
+    resize_border: Option<f32>,
+    maximized: bool,
+    size: Size,
+) -> Option<(AbsoluteRect, f32)> {
+    resize_border
+        .filter(|_| !maximized)
+        .map(|border| (AbsoluteRect::new(Default::default(), size.cast_unit()), border))
+}
+
+/// Returns the behavior of the window at `point`, from the regions declared by widgets and the resize border of
+/// the window `frame`, if any.
+///
+/// Client regions take precedence over resize regions (including the border), which take precedence over captions.
+fn window_region_at(
+    regions: &[(base::WindowRegion, AbsoluteRect)],
+    frame: Option<(AbsoluteRect, f32)>,
+    point: AbsolutePoint,
+) -> Option<base::WindowRegion> {
+    let mut found = frame
+        .and_then(|(rect, border)| base::ResizeEdge::hit_test(rect, border, point))
+        .map(base::WindowRegion::Resize);
+    for &(region, rect) in regions.iter().filter(|(_, rect)| rect.contains(point)) {
+        match region {
+            base::WindowRegion::Client => return None,
+            base::WindowRegion::Resize(_) => found = Some(region),
+            base::WindowRegion::Caption => {
+                if found.is_none() {
+                    found = Some(region);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Rudimentary update auxiliary.
+pub struct UAux {
+    pub window_queue: RcEventQueue<base::WindowEvent>,
+    pub input_grabs: base::InputGrabs,
+    pub tooltips: base::TooltipCoordinator,
+    pub focus_chain: base::FocusChain,
+    pub toasts: ui::ToastHandle,
+    pub overlays: ui::OverlayHandle<UAux, GAux>,
+    pub cursor: AbsolutePoint,
+    /// Cursor icon requested during the current update pass.
+    pub cursor_icon: base::CursorIcon,
+    /// Whether an animation frame was requested during the current update pass.
+    pub animating: bool,
+    /// Attention requested since the last update pass, if any.
+    pub attention: Option<base::UserAttention>,
+    /// Progress shown on the taskbar button or dock icon.
+    pub taskbar_progress: base::TaskbarProgress,
+    /// Text input requested during the current update pass, if any.
+    pub text_input: Option<base::TextInputRequest>,
+    /// Regions of the window declared during the current update pass.
+    pub window_regions: Vec<(base::WindowRegion, AbsoluteRect)>,
+    /// Window actions requested since the last update pass.
+    pub window_actions: Vec<base::WindowAction>,
+    /// Announcements made since the last update pass, in order.
+    pub announcements: Vec<a11y::Announcement>,
+    /// Receives usage metrics recorded by widgets, if the application collects them.
+    pub metrics: Option<Box<dyn base::MetricsRecorder>>,
+    pub g_aux: GAux,
+}
+
+impl UAux {
+    /// Passes `event` through the input grabs and the tooltip coordinator, then emits it into the window queue.
+    pub fn emit_window_event(&mut self, event: base::WindowEvent) {
+        self.input_grabs.intercept(&event);
+        self.tooltips.observe(&event);
+        self.window_queue.emit_owned(event);
+    }
+}
+
+impl base::UpdateAuxiliary for UAux {
+    #[inline]
+    fn window_queue(&self) -> &RcEventQueue<base::WindowEvent> {
+        &self.window_queue
+    }
+
+    #[inline]
+    fn window_queue_mut(&mut self) -> &mut RcEventQueue<base::WindowEvent> {
+        &mut self.window_queue
+    }
+
+    #[inline]
+    fn input_grabs(&self) -> &base::InputGrabs {
+        &self.input_grabs
+    }
+
+    #[inline]
+    fn tooltips(&self) -> &base::TooltipCoordinator {
+        &self.tooltips
+    }
+
+    #[inline]
+    fn focus_chain(&self) -> &base::FocusChain {
+        &self.focus_chain
+    }
+
+    #[inline]
+    fn set_cursor_icon(&mut self, icon: base::CursorIcon) {
+        self.cursor_icon = icon;
+    }
+
+    #[inline]
+    fn request_animation_frame(&mut self) {
+        self.animating = true;
+    }
+
+    fn request_attention(&mut self, attention: base::UserAttention) {
+        // a critical request takes precedence over an informational one in the same pass
+        if self.attention != Some(base::UserAttention::Critical) {
+            self.attention = Some(attention);
+        }
+    }
+
+    #[inline]
+    fn set_taskbar_progress(&mut self, progress: base::TaskbarProgress) {
+        self.taskbar_progress = progress;
+    }
+
+    #[inline]
+    fn request_text_input(&mut self, request: base::TextInputRequest) {
+        self.text_input = Some(request);
+    }
+
+    #[inline]
+    fn declare_window_region(&mut self, region: base::WindowRegion, rect: AbsoluteRect) {
+        self.window_regions.push((region, rect));
+    }
+
+    #[inline]
+    fn request_window_action(&mut self, action: base::WindowAction) {
+        self.window_actions.push(action);
+    }
+
+    #[inline]
+    fn announce(&mut self, announcement: a11y::Announcement) {
+        self.announcements.push(announcement);
+    }
+
+    #[inline]
+    fn record_metric(&mut self, metric: base::Metric) {
+        diagnostics::record_metric(metric);
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record(metric);
+        }
+    }
+
+    #[inline]
+    fn graphical(&self) -> &dyn base::GraphicalAuxiliary {
+        &self.g_aux
+    }
+
+    #[inline]
+    fn graphical_mut(&mut self) -> &mut dyn base::GraphicalAuxiliary {
+        &mut self.g_aux
+    }
+}
+
+/// Rudimentary graphical auxiliary.
+pub struct GAux {
+    pub scale: f32,
+}
+
+impl base::GraphicalAuxiliary for GAux {
+    #[inline]
+    fn scaling(&self) -> f32 {
+        self.scale
+    }
+}
@@ -0,0 +1,563 @@
+//! Default backend, built on winit (through glutin).
+
+use {
+    super::{AppOptions, Backend, BackendEvent, LoopControl},
+    crate::{base, error::AppError, geom::*},
+    glutin::{
+        dpi::{PhysicalPosition, PhysicalSize},
+        event::{self, DeviceEvent, Event, WindowEvent},
+        event_loop::{ControlFlow, EventLoop},
+        monitor::MonitorHandle,
+        window::{Window, WindowBuilder},
+        ContextBuilder, PossiblyCurrent, WindowedContext,
+    },
+    reclutch::display::{skia, Point, Rect, Size, Vector},
+};
+
+/// Distance scrolled per line (i.e. mouse wheel notch), in logical pixels.
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+/// Smallest size a window can be resized to through a resize region, in physical pixels.
+const MIN_WINDOW_SIZE: f64 = 64.0;
+/// Distance from the left or right edge of the screen within which a dragged window is snapped, in logical pixels.
+const SNAP_DISTANCE: f32 = 8.0;
+
+fn convert_modifiers(modifiers: event::ModifiersState) -> base::KeyModifiers {
+    base::KeyModifiers {
+        shift: modifiers.shift(),
+        ctrl: modifiers.ctrl(),
+        alt: modifiers.alt(),
+        logo: modifiers.logo(),
+    }
+}
+
+fn convert_cursor_icon(icon: base::CursorIcon) -> glutin::window::CursorIcon {
+    use glutin::window::CursorIcon;
+    match icon {
+        base::CursorIcon::Default => CursorIcon::Default,
+        base::CursorIcon::Pointer => CursorIcon::Hand,
+        base::CursorIcon::Text => CursorIcon::Text,
+        base::CursorIcon::Move => CursorIcon::Move,
+        base::CursorIcon::NotAllowed => CursorIcon::NotAllowed,
+        base::CursorIcon::Grab => CursorIcon::Grab,
+        base::CursorIcon::Grabbing => CursorIcon::Grabbing,
+        base::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
+        base::CursorIcon::ResizeVertical => CursorIcon::NsResize,
+        base::CursorIcon::ResizeNwSe => CursorIcon::NwseResize,
+        base::CursorIcon::ResizeNeSw => CursorIcon::NeswResize,
+    }
+}
+
+/// Move or resize of the window in progress.
+///
+/// winit can't hand moving and resizing over to the window manager, so it's emulated from the movement of the cursor.
+struct FrameDrag {
+    /// Edge being dragged, or `None` if the window is being moved.
+    edge: Option<base::ResizeEdge>,
+    /// Position of the cursor on the screen when the drag started.
+    cursor: PhysicalPosition<f64>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+}
+
+/// Backend running a desktop window through winit, drawn into with OpenGL.
+///
+/// The only shell integration supported is bouncing the dock icon on macOS to request attention.
+/// Of text input requests, only the area is forwarded (placing the IME candidate window below it); winit has no
+/// notion of input hints or on-screen keyboards.
+pub struct WinitBackend {
+    /// OpenGL context/window.
+    pub context: WindowedContext<PossiblyCurrent>,
+    event_loop: Option<EventLoop<()>>,
+    scale: f32,
+    modifiers: base::KeyModifiers,
+    cursor: AbsolutePoint,
+    /// Position of the cursor relative to the window, in physical pixels.
+    physical_cursor: PhysicalPosition<f64>,
+    frame_drag: Option<FrameDrag>,
+    /// Whether windows dragged against the edge of the screen are snapped.
+    snapping: bool,
+    snap: Option<base::WindowSnap>,
+    /// Position and size of the window before it was snapped.
+    unsnapped: Option<(PhysicalPosition<i32>, PhysicalSize<u32>)>,
+    /// Change to snapping which has yet to be reported.
+    snap_changed: Option<Option<base::WindowSnap>>,
+}
+
+impl WinitBackend {
+    /// Returns the window.
+    #[inline]
+    pub fn window(&self) -> &Window {
+        self.context.window()
+    }
+
+    /// Returns the position of the cursor on the screen, if the position of the window is known.
+    fn screen_cursor(&self) -> Option<PhysicalPosition<f64>> {
+        let origin = self.context.window().inner_position().ok()?;
+        Some(PhysicalPosition::new(
+            origin.x as f64 + self.physical_cursor.x,
+            origin.y as f64 + self.physical_cursor.y,
+        ))
+    }
+
+    /// Returns the bounds of the monitor containing `point` on the screen, or of the monitor the window is on,
+    /// in physical pixels.
+    fn monitor_rect(&self, point: Option<Point>) -> Rect {
+        let window = self.context.window();
+        let rect = |monitor: MonitorHandle| {
+            let (position, size) = (monitor.position(), monitor.size());
+            Rect::new(
+                Point::new(position.x as _, position.y as _),
+                Size::new(size.width as _, size.height as _),
+            )
+        };
+        point
+            .and_then(|point| window.available_monitors().map(rect).find(|r| r.contains(point)))
+            .unwrap_or_else(|| rect(window.current_monitor()))
+    }
+
+    fn set_snap(&mut self, snap: Option<base::WindowSnap>) {
+        if snap != self.snap {
+            self.snap = snap;
+            self.snap_changed = Some(snap);
+        }
+    }
+
+    /// Moves and resizes the window to fill part of `screen`, remembering where it was unless already snapped.
+    ///
+    /// The inner size is used for the outer size, since only frameless windows are expected to be snapped.
+    fn snap_to(&mut self, snap: base::WindowSnap, screen: Rect) {
+        let window = self.context.window();
+        if self.snap.is_none() {
+            if let Ok(position) = window.outer_position() {
+                self.unsnapped = Some((position, window.inner_size()));
+            }
+        }
+
+        let rect = snap.rect(screen);
+        window.set_maximized(false);
+        window.set_outer_position(PhysicalPosition::new(rect.min_x() as f64, rect.min_y() as f64));
+        window.set_inner_size(PhysicalSize::new(rect.size.width as f64, rect.size.height as f64));
+        self.set_snap(Some(snap));
+    }
+
+    /// Returns a snapped window to where it was before being snapped.
+    fn unsnap(&mut self) {
+        if let Some((position, size)) = self.unsnapped.take() {
+            let window = self.context.window();
+            window.set_outer_position(position);
+            window.set_inner_size(size);
+        }
+        self.set_snap(None);
+    }
+
+    fn begin_frame_drag(&mut self, edge: Option<base::ResizeEdge>) {
+        let cursor = match self.screen_cursor() {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let window = self.context.window();
+        let (mut position, mut size) = match window.outer_position() {
+            Ok(position) => (position, window.inner_size()),
+            Err(_) => return,
+        };
+
+        if self.snap.is_some() {
+            // moving a snapped window restores its size, keeping the cursor at the same proportion across it;
+            // resizing it keeps the snapped position and size as the starting point
+            if let (None, Some((_, unsnapped))) = (edge, self.unsnapped) {
+                let ratio = (cursor.x - position.x as f64) / size.width as f64;
+                position.x = (cursor.x - ratio * unsnapped.width as f64) as i32;
+                size = unsnapped;
+                window.set_outer_position(position);
+                window.set_inner_size(size);
+            }
+            self.unsnapped = None;
+            self.set_snap(None);
+        }
+
+        self.frame_drag = Some(FrameDrag { edge, cursor, position, size });
+    }
+
+    /// Snaps the window if it was dragged against the left or right edge of the screen.
+    fn end_frame_drag(&mut self) {
+        let drag = match self.frame_drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        if drag.edge.is_some() || !self.snapping {
+            return;
+        }
+
+        if let Some(cursor) = self.screen_cursor() {
+            let point = Point::new(cursor.x as _, cursor.y as _);
+            let screen = self.monitor_rect(Some(point));
+            if let Some(snap) = base::WindowSnap::at_edge(screen, SNAP_DISTANCE * self.scale, point)
+            {
+                self.snap_to(snap, screen);
+            }
+        }
+    }
+
+    /// Moves or resizes the window to follow the cursor.
+    fn update_frame_drag(&self) {
+        let (drag, cursor) = match (&self.frame_drag, self.screen_cursor()) {
+            (Some(drag), Some(cursor)) => (drag, cursor),
+            _ => return,
+        };
+        let window = self.context.window();
+        let (dx, dy) = (cursor.x - drag.cursor.x, cursor.y - drag.cursor.y);
+        let (x, y) = (drag.position.x as f64, drag.position.y as f64);
+        let (width, height) = (drag.size.width as f64, drag.size.height as f64);
+
+        let edge = match drag.edge {
+            Some(edge) => edge,
+            None => {
+                window.set_outer_position(PhysicalPosition::new(x + dx, y + dy));
+                return;
+            }
+        };
+
+        let (left, right, top, bottom) = match edge {
+            base::ResizeEdge::Left => (true, false, false, false),
+            base::ResizeEdge::Right => (false, true, false, false),
+            base::ResizeEdge::Top => (false, false, true, false),
+            base::ResizeEdge::Bottom => (false, false, false, true),
+            base::ResizeEdge::TopLeft => (true, false, true, false),
+            base::ResizeEdge::TopRight => (false, true, true, false),
+            base::ResizeEdge::BottomLeft => (true, false, false, true),
+            base::ResizeEdge::BottomRight => (false, true, false, true),
+        };
+
+        // dragging the left or top edge moves the window as well, keeping the opposite edge in place
+        let new_width = if left {
+            width - dx
+        } else if right {
+            width + dx
+        } else {
+            width
+        }
+        .max(MIN_WINDOW_SIZE);
+        let new_height = if top {
+            height - dy
+        } else if bottom {
+            height + dy
+        } else {
+            height
+        }
+        .max(MIN_WINDOW_SIZE);
+
+        if left || top {
+            window.set_outer_position(PhysicalPosition::new(
+                if left { x + width - new_width } else { x },
+                if top { y + height - new_height } else { y },
+            ));
+        }
+        window.set_inner_size(PhysicalSize::new(new_width, new_height));
+    }
+
+    fn translate(&mut self, event: &Event<()>) -> Option<BackendEvent> {
+        let modifiers = self.modifiers;
+        Some(match event {
+            Event::MainEventsCleared => BackendEvent::EventsCleared,
+            Event::RedrawRequested(..) => BackendEvent::Redraw,
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                BackendEvent::CloseRequested
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { scale_factor: hidpi_factor, .. },
+                ..
+            } => {
+                self.scale = *hidpi_factor as _;
+                let window_size = self.context.window().inner_size();
+                BackendEvent::ScaleFactorChanged(
+                    self.scale,
+                    Size::new(window_size.width as _, window_size.height as _),
+                )
+            }
+            Event::WindowEvent { event: WindowEvent::Resized(window_size), .. } => {
+                BackendEvent::Resized(Size::new(window_size.width as _, window_size.height as _))
+            }
+            Event::DeviceEvent { event: DeviceEvent::ModifiersChanged(key_modifiers), .. } => {
+                self.modifiers = convert_modifiers(*key_modifiers);
+                return None;
+            }
+            Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
+                self.physical_cursor = *position;
+                self.update_frame_drag();
+
+                let position = position.to_logical::<f64>(self.scale as f64);
+                let position = Point::new(position.x as _, position.y as _);
+
+                self.cursor = position.cast_unit();
+
+                BackendEvent::Window(base::WindowEvent::MouseMove(base::ConsumableEvent::new((
+                    position.cast_unit(),
+                    modifiers,
+                ))))
+            }
+            Event::WindowEvent { event: WindowEvent::MouseInput { state, button, .. }, .. } => {
+                let mouse_button = match button {
+                    event::MouseButton::Left => base::MouseButton::Left,
+                    event::MouseButton::Middle => base::MouseButton::Middle,
+                    event::MouseButton::Right => base::MouseButton::Right,
+                    _ => base::MouseButton::Left,
+                };
+
+                if *state == event::ElementState::Released && *button == event::MouseButton::Left {
+                    self.end_frame_drag();
+                }
+
+                BackendEvent::Window(match state {
+                    event::ElementState::Pressed => base::WindowEvent::MousePress(
+                        base::ConsumableEvent::new((self.cursor, mouse_button, modifiers)),
+                    ),
+                    event::ElementState::Released => base::WindowEvent::MouseRelease(
+                        base::ConsumableEvent::new((self.cursor, mouse_button, modifiers)),
+                    ),
+                })
+            }
+            Event::WindowEvent { event: WindowEvent::MouseWheel { delta, .. }, .. } => {
+                let delta = match delta {
+                    event::MouseScrollDelta::LineDelta(x, y) => {
+                        Vector::new(*x, *y) * SCROLL_LINE_HEIGHT
+                    }
+                    event::MouseScrollDelta::PixelDelta(delta) => {
+                        let delta = glutin::dpi::Position::from(*delta)
+                            .to_logical::<f64>(self.scale as f64);
+                        Vector::new(delta.x as _, delta.y as _)
+                    }
+                };
+
+                BackendEvent::Window(base::WindowEvent::MouseScroll(base::ConsumableEvent::new((
+                    self.cursor,
+                    delta,
+                    modifiers,
+                ))))
+            }
+            Event::WindowEvent {
+                event: WindowEvent::Touch(event::Touch { phase, location, force: Some(force), .. }),
+                ..
+            } => {
+                // Only touches which report force are treated as pen input.
+                let position = location.to_logical::<f64>(self.scale as f64);
+                let position = AbsolutePoint::new(position.x as _, position.y as _);
+
+                let pen = base::PenData {
+                    pressure: force.normalized() as _,
+                    // winit doesn't report the azimuth, so all the tilt is on the X axis.
+                    tilt: match force {
+                        event::Force::Calibrated { altitude_angle: Some(altitude), .. } => {
+                            (std::f32::consts::FRAC_PI_2 - *altitude as f32, 0.0)
+                        }
+                        _ => (0.0, 0.0),
+                    },
+                    ..Default::default()
+                };
+
+                self.cursor = position;
+
+                BackendEvent::Window(match phase {
+                    event::TouchPhase::Started => base::WindowEvent::PenPress(
+                        base::ConsumableEvent::new((position, pen, modifiers)),
+                    ),
+                    event::TouchPhase::Moved => base::WindowEvent::PenMove(
+                        base::ConsumableEvent::new((position, pen, modifiers)),
+                    ),
+                    event::TouchPhase::Ended | event::TouchPhase::Cancelled => {
+                        base::WindowEvent::PenRelease(base::ConsumableEvent::new((
+                            position, pen, modifiers,
+                        )))
+                    }
+                })
+            }
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter(character), .. } => {
+                BackendEvent::Window(base::WindowEvent::TextInput(base::ConsumableEvent::new(
+                    *character,
+                )))
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            event::KeyboardInput {
+                                virtual_keycode: Some(virtual_keycode), state, ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                let key_input: base::KeyInput = (*virtual_keycode).into();
+
+                BackendEvent::Window(match state {
+                    event::ElementState::Pressed => base::WindowEvent::KeyPress(
+                        base::ConsumableEvent::new((key_input, modifiers)),
+                    ),
+                    event::ElementState::Released => base::WindowEvent::KeyRelease(
+                        base::ConsumableEvent::new((key_input, modifiers)),
+                    ),
+                })
+            }
+            Event::WindowEvent { event: WindowEvent::Focused(focused), .. } => {
+                BackendEvent::Focused(*focused)
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl Backend for WinitBackend {
+    /// Native winit event; events borrowing from winit (i.e. scale factor changes) aren't passed on.
+    type RawEvent = Event<'static, ()>;
+
+    fn create(opts: &AppOptions) -> Result<(Self, skia::SkiaGraphicsDisplay), AppError> {
+        let event_loop = EventLoop::new();
+
+        let hidpi_factor = event_loop.primary_monitor().scale_factor();
+
+        let wb = WindowBuilder::new()
+            .with_title(opts.name.clone())
+            .with_decorations(!opts.frameless)
+            .with_inner_size(
+                glutin::dpi::PhysicalSize::new(
+                    opts.window_size.width as f64,
+                    opts.window_size.width as f64,
+                )
+                .to_logical::<f64>(hidpi_factor),
+            );
+
+        let context =
+            ContextBuilder::new().with_vsync(true).build_windowed(wb, &event_loop).unwrap();
+
+        let context = unsafe { context.make_current().unwrap() };
+
+        let display =
+            skia::SkiaGraphicsDisplay::new_gl_framebuffer(&skia::SkiaOpenGlFramebuffer {
+                framebuffer_id: 0,
+                size: (opts.window_size.width as _, opts.window_size.height as _),
+            })?;
+
+        Ok((
+            WinitBackend {
+                context,
+                event_loop: Some(event_loop),
+                scale: hidpi_factor as _,
+                modifiers: base::KeyModifiers {
+                    shift: false,
+                    ctrl: false,
+                    alt: false,
+                    logo: false,
+                },
+                cursor: Default::default(),
+                physical_cursor: PhysicalPosition::new(0.0, 0.0),
+                frame_drag: None,
+                snapping: opts.snapping,
+                snap: None,
+                unsnapped: None,
+                snap_changed: None,
+            },
+            display,
+        ))
+    }
+
+    #[inline]
+    fn scale_factor(&self) -> f32 {
+        self.scale
+    }
+
+    fn run<F>(mut self, mut handler: F) -> !
+    where
+        F: 'static + FnMut(&mut Self, BackendEvent, Option<Self::RawEvent>) -> LoopControl,
+    {
+        let event_loop = self.event_loop.take().unwrap();
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = match self.translate(&event) {
+                Some(translated) => {
+                    let mut control = handler(&mut self, translated, event.to_static());
+                    // snapping caused by the event (or by a window action requested while handling it) is reported
+                    // right after it
+                    while control != LoopControl::Exit {
+                        match self.snap_changed.take() {
+                            Some(snap) => {
+                                control = handler(&mut self, BackendEvent::Snapped(snap), None)
+                            }
+                            None => break,
+                        }
+                    }
+
+                    match control {
+                        LoopControl::Wait => ControlFlow::Wait,
+                        LoopControl::Poll => ControlFlow::Poll,
+                        LoopControl::Exit => ControlFlow::Exit,
+                    }
+                }
+                None => ControlFlow::Wait,
+            };
+        })
+    }
+
+    #[inline]
+    fn request_redraw(&self) {
+        self.context.window().request_redraw();
+    }
+
+    #[inline]
+    fn present(&mut self) {
+        self.context.swap_buffers().unwrap();
+    }
+
+    #[inline]
+    fn set_cursor_icon(&self, icon: base::CursorIcon) {
+        self.context.window().set_cursor_icon(convert_cursor_icon(icon));
+    }
+
+    #[inline]
+    fn begin_window_drag(&mut self) {
+        self.begin_frame_drag(None);
+    }
+
+    #[inline]
+    fn begin_window_resize(&mut self, edge: base::ResizeEdge) {
+        self.begin_frame_drag(Some(edge));
+    }
+
+    fn window_action(&mut self, action: base::WindowAction) {
+        match action {
+            base::WindowAction::Minimize => self.context.window().set_minimized(true),
+            base::WindowAction::Maximize => {
+                // unsnapping first means restoring the window later returns it to where it was before snapping
+                if self.snap.is_some() {
+                    self.unsnap();
+                }
+                self.context.window().set_maximized(true);
+            }
+            base::WindowAction::Restore if self.snap.is_some() => self.unsnap(),
+            base::WindowAction::Restore => self.context.window().set_maximized(false),
+            base::WindowAction::Snap(snap) => {
+                let screen = self.monitor_rect(None);
+                self.snap_to(snap, screen);
+            }
+            base::WindowAction::Close => {}
+        }
+    }
+
+    fn set_text_input(&self, request: Option<base::TextInputRequest>) {
+        if let Some(request) = request {
+            let area = request.area;
+            self.context
+                .window()
+                .set_ime_position(glutin::dpi::LogicalPosition::new(area.min_x(), area.max_y()));
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn request_attention(&self, attention: base::UserAttention) {
+        #[cfg(target_os = "macos")]
+        {
+            use glutin::platform::macos::{RequestUserAttentionType, WindowExtMacOS};
+            self.context.window().request_user_attention(match attention {
+                base::UserAttention::Informational => RequestUserAttentionType::Informational,
+                base::UserAttention::Critical => RequestUserAttentionType::Critical,
+            });
+        }
+    }
+}
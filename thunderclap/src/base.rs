@@ -1,17 +1,21 @@
 use {
-    crate::{draw, geom::*},
+    crate::{a11y, draw, geom::*},
     reclutch::{
-        display::{Color, CommandGroup, DisplayClip, DisplayCommand, GraphicsDisplay, Rect, Size},
+        display::{
+            Color, CommandGroup, DisplayClip, DisplayCommand, GraphicsDisplay, Point, Rect, Size,
+            Vector,
+        },
         event::RcEventQueue,
         prelude::*,
         verbgraph,
         widget::Widget,
     },
     std::{
-        cell::RefCell,
+        cell::{Cell, RefCell},
         collections::{HashMap, HashSet},
-        rc::Rc,
-        sync::Mutex,
+        rc::{Rc, Weak},
+        sync::{Arc, Mutex, MutexGuard},
+        time::{Duration, Instant},
     },
 };
 
@@ -112,6 +116,11 @@ pub trait WidgetChildren:
     > {
         Vec::new()
     }
+
+    /// Returns the name of the type of the widget (see `widget_kind`), e.g. for diagnostics.
+    fn widget_name(&self) -> &'static str {
+        widget_kind::<Self>()
+    }
 }
 
 /// Implemented by widgets that can be repainted.
@@ -189,12 +198,52 @@ impl Default for Visibility {
     }
 }
 
+impl Visibility {
+    /// Returns `true` if the widget should be rendered.
+    #[inline]
+    pub fn renders(self) -> bool {
+        self == Visibility::Normal || self == Visibility::Static
+    }
+
+    /// Returns `true` if the widget should receive updates.
+    #[inline]
+    pub fn updates(self) -> bool {
+        self == Visibility::Normal || self == Visibility::Invisible
+    }
+
+    /// Combines two visibilities, yielding the most restrictive of the two.
+    /// For example, `Invisible` combined with `Static` is `None`.
+    pub fn combine(self, other: Visibility) -> Visibility {
+        match (self.renders() && other.renders(), self.updates() && other.updates()) {
+            (true, true) => Visibility::Normal,
+            (false, true) => Visibility::Invisible,
+            (true, false) => Visibility::Static,
+            (false, false) => Visibility::None,
+        }
+    }
+}
+
 /// Implemented by widgets which are capable of tracking visibility.
 pub trait HasVisibility {
     /// Changes the widget visibility.
     fn set_visibility(&mut self, visibility: Visibility);
     /// Returns the widget visibility.
     fn visibility(&self) -> Visibility;
+
+    /// Returns the rectangle imposed by a layout which the widget (and its children) are clipped to when drawn,
+    /// if any (see `LayoutClip`).
+    fn layout_clip(&self) -> Option<AbsoluteRect> {
+        None
+    }
+}
+
+thread_local! {
+    // Returned by the default accessors of `UpdateAuxiliary`, leaked once per thread so that they can be borrowed
+    // from any auxiliary.
+    static DETACHED_INPUT_GRABS: &'static InputGrabs = Box::leak(Box::new(InputGrabs::default()));
+    static DETACHED_TOOLTIPS: &'static TooltipCoordinator =
+        Box::leak(Box::new(TooltipCoordinator::default()));
+    static DETACHED_FOCUS_CHAIN: &'static FocusChain = Box::leak(Box::new(FocusChain::default()));
 }
 
 /// Trait required for any type passed as the `UpdateAux` type (seen as `U` in the widget type parameters)
@@ -204,6 +253,71 @@ pub trait UpdateAuxiliary: 'static {
     fn window_queue(&self) -> &RcEventQueue<WindowEvent>;
     /// Returns the queue where window events (`WindowEvent`) are emitted, mutably.
     fn window_queue_mut(&mut self) -> &mut RcEventQueue<WindowEvent>;
+    /// Returns the input grabs which window events should be passed through (see `InputGrabs::intercept`) before being emitted.
+    ///
+    /// By default, returns a stack shared by the thread which no events are passed through, so grabs have no effect.
+    fn input_grabs(&self) -> &InputGrabs {
+        DETACHED_INPUT_GRABS.with(|grabs| *grabs)
+    }
+    /// Returns the coordinator of tooltips in the window, which window events should be passed through
+    /// (see `TooltipCoordinator::observe`) before being emitted.
+    ///
+    /// By default, returns a coordinator shared by the thread which observes no events, so tooltips aren't hidden by
+    /// presses.
+    fn tooltips(&self) -> &TooltipCoordinator {
+        DETACHED_TOOLTIPS.with(|tooltips| *tooltips)
+    }
+    /// Returns the order in which the keyboard moves focus between the widgets of the window.
+    ///
+    /// By default, returns a chain shared by the thread which is never navigated, so the keyboard doesn't move focus.
+    fn focus_chain(&self) -> &FocusChain {
+        DETACHED_FOCUS_CHAIN.with(|chain| *chain)
+    }
+    /// Requests the icon of the mouse cursor.
+    ///
+    /// The request only lasts for the current update pass; widgets should re-request the icon
+    /// every update for as long as it applies (e.g. while hovered). If nothing requests an icon,
+    /// `CursorIcon::Default` is shown. Does nothing by default.
+    fn set_cursor_icon(&mut self, _icon: CursorIcon) {}
+    /// Requests that the widgets be updated and redrawn again as soon as possible, rather than once there is input,
+    /// so that an animation advances every frame.
+    ///
+    /// As with `set_cursor_icon`, the request only lasts for the current update pass; widgets should re-request it
+    /// every update for as long as they're animating. Does nothing by default.
+    ///
+    /// The same applies to anything which changes without input: widgets waiting on time (e.g. a debounce or a decay)
+    /// or on other threads (e.g. image loaders, tile providers or video decoders) should request frames until they
+    /// settle, since otherwise they're only updated once there is input.
+    fn request_animation_frame(&mut self) {}
+    /// Requests the attention of the user (e.g. by flashing the taskbar button or bouncing the dock icon),
+    /// typically when a long operation finishes while the window isn't focused.
+    ///
+    /// The request is ignored if the window is focused. Does nothing by default.
+    fn request_attention(&mut self, _attention: UserAttention) {}
+    /// Shows the progress of a long operation on the taskbar button or dock icon of the window, such that it can be
+    /// followed while the window isn't focused. The progress remains until changed.
+    ///
+    /// Does nothing by default.
+    fn set_taskbar_progress(&mut self, _progress: TaskbarProgress) {}
+    /// Requests text input from the input method of the platform, on behalf of the focused text widget.
+    ///
+    /// As with `set_cursor_icon`, the request only lasts for the current update pass; text widgets should re-request
+    /// it every update while focused. Does nothing by default.
+    fn request_text_input(&mut self, _request: TextInputRequest) {}
+    /// Declares a region of the window which behaves as part of the window frame (e.g. dragging a custom title bar
+    /// moves the window), for apps drawing their own window chrome.
+    ///
+    /// As with `set_cursor_icon`, the declaration only lasts for the current update pass; widgets should re-declare
+    /// their regions every update. Does nothing by default.
+    fn declare_window_region(&mut self, _region: WindowRegion, _rect: AbsoluteRect) {}
+    /// Requests that the window be minimized, maximized, restored or closed (e.g. by a custom title bar).
+    /// Does nothing by default.
+    fn request_window_action(&mut self, _action: WindowAction) {}
+    /// Announces a message to screen readers without moving the focus (see the `a11y` module).
+    /// Does nothing by default.
+    fn announce(&mut self, _announcement: a11y::Announcement) {}
+    /// Records a usage metric, for the application to collect. Does nothing by default.
+    fn record_metric(&mut self, _metric: Metric) {}
     /// Returns the respective graphical auxiliary.
     fn graphical(&self) -> &dyn GraphicalAuxiliary;
     /// Returns the respective graphical auxiliary mutably.
@@ -226,11 +340,15 @@ pub fn invoke_update<U: UpdateAuxiliary, G>(
     >,
     aux: &mut U,
 ) {
+    let budget = widget_budget().map(|budget| budget.update);
     // Iterate in reverse because most visually forefront widgets should get events first.
     for child in widget.children_mut().into_iter().rev() {
-        match child.visibility() {
-            Visibility::Static | Visibility::None => {}
-            _ => child.update(aux),
+        match (child.visibility(), budget) {
+            (Visibility::Static, _) | (Visibility::None, _) => {}
+            (_, Some(budget)) => {
+                watch(child, WidgetPhase::Update, budget, |child| child.update(aux))
+            }
+            (_, None) => child.update(aux),
         }
     }
 }
@@ -282,66 +400,1119 @@ impl<T> ConsumableEvent<T> {
         }
     }
 
-    /// Returns the inner event data regardless of consumption.
-    #[inline(always)]
-    pub fn get(&self) -> &T {
-        &self.0.data
+    /// Returns the inner event data regardless of consumption.
+    #[inline(always)]
+    pub fn get(&self) -> &T {
+        &self.0.data
+    }
+
+    /// Consumes the event without inspecting the data.
+    /// Returns `true` if the event hadn't been consumed prior to this.
+    pub fn consume(&self) -> bool {
+        self.0.marker.replace(false)
+    }
+
+    /// Returns `true` if the event has been consumed.
+    #[inline]
+    pub fn is_consumed(&self) -> bool {
+        !*self.0.marker.borrow()
+    }
+}
+
+impl<T> Clone for ConsumableEvent<T> {
+    fn clone(&self) -> Self {
+        ConsumableEvent(self.0.clone())
+    }
+}
+
+/// An event related to the window, e.g. input.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum WindowEvent {
+    /// The user pressed a mouse button.
+    #[event_key(mouse_press)]
+    MousePress(ConsumableEvent<(AbsolutePoint, MouseButton, KeyModifiers)>),
+    /// The user released a mouse button.
+    /// This event complements `MousePress`, which means it realistically can only
+    /// be emitted after `MousePress` has been emitted.
+    #[event_key(mouse_release)]
+    MouseRelease(ConsumableEvent<(AbsolutePoint, MouseButton, KeyModifiers)>),
+    /// The user moved the cursor.
+    #[event_key(mouse_move)]
+    MouseMove(ConsumableEvent<(AbsolutePoint, KeyModifiers)>),
+    /// The user scrolled with a mouse wheel or touchpad, by a delta in logical pixels at the cursor position.
+    /// Scrolling up or left yields positive deltas.
+    #[event_key(mouse_scroll)]
+    MouseScroll(ConsumableEvent<(AbsolutePoint, Vector, KeyModifiers)>),
+    /// The user pressed a pen/stylus onto the surface.
+    #[event_key(pen_press)]
+    PenPress(ConsumableEvent<(AbsolutePoint, PenData, KeyModifiers)>),
+    /// The user lifted a pen/stylus from the surface.
+    /// Similar to `MouseRelease`, this complements `PenPress`.
+    #[event_key(pen_release)]
+    PenRelease(ConsumableEvent<(AbsolutePoint, PenData, KeyModifiers)>),
+    /// The user moved a pen/stylus across the surface.
+    #[event_key(pen_move)]
+    PenMove(ConsumableEvent<(AbsolutePoint, PenData, KeyModifiers)>),
+    /// Emitted when a text input is received.
+    #[event_key(text_input)]
+    TextInput(ConsumableEvent<char>),
+    /// Emitted when a key is pressed.
+    #[event_key(key_press)]
+    KeyPress(ConsumableEvent<(KeyInput, KeyModifiers)>),
+    /// Emitted when a key is released.
+    #[event_key(key_release)]
+    KeyRelease(ConsumableEvent<(KeyInput, KeyModifiers)>),
+    /// Emitted in place of `KeyPress` when the escape key is pressed, once for each stage of the cascade in turn
+    /// until a widget consumes it (see `EscapeCascade`), so that one press only ever does one thing.
+    #[event_key(escape)]
+    Escape(ConsumableEvent<EscapeStage>),
+    /// Emitted immediately before an event which is capable of changing focus.
+    /// If implementing a focus-able widget, to handle this event, simply clear
+    /// the local "focused" flag (which should ideally be stored as `draw::state::InteractionState`).
+    #[event_key(clear_focus)]
+    ClearFocus,
+    /// Emitted to focus the widget owning the `FocusEntry` with the given ID, e.g. when the user presses tab
+    /// (see `FocusChain::navigate`). Always preceded by `ClearFocus`.
+    #[event_key(focus)]
+    Focus(ConsumableEvent<u64>),
+    /// Emitted when the window loses focus, e.g. when the user switches to another window.
+    /// Focus within the window is kept, but widgets should render as inactive
+    /// (see `draw::state::InteractionState::INACTIVE`) until `Activate` is emitted.
+    #[event_key(deactivate)]
+    Deactivate,
+    /// Emitted when the window regains focus after `Deactivate`.
+    #[event_key(activate)]
+    Activate,
+    /// Emitted when the window has been snapped to part of the screen, or `None` when it's no longer snapped
+    /// (e.g. restored or dragged away). The root layout can adapt to the arrangement, such as collapsing a sidebar
+    /// while the window only fills half of the screen.
+    #[event_key(snap)]
+    Snap(Option<WindowSnap>),
+}
+
+impl WindowEvent {
+    /// Returns the cursor position of a mouse or pen event, otherwise `None`.
+    pub fn cursor_position(&self) -> Option<AbsolutePoint> {
+        match self {
+            WindowEvent::MousePress(ev) | WindowEvent::MouseRelease(ev) => Some(ev.get().0),
+            WindowEvent::MouseMove(ev) => Some(ev.get().0),
+            WindowEvent::MouseScroll(ev) => Some(ev.get().0),
+            WindowEvent::PenPress(ev) | WindowEvent::PenRelease(ev) | WindowEvent::PenMove(ev) => {
+                Some(ev.get().0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Consumes the inner event data (see `ConsumableEvent::consume`).
+    /// Returns `false` if the event was already consumed or isn't consumable (e.g. `ClearFocus`).
+    pub fn consume(&self) -> bool {
+        match self {
+            WindowEvent::MousePress(ev) | WindowEvent::MouseRelease(ev) => ev.consume(),
+            WindowEvent::MouseMove(ev) => ev.consume(),
+            WindowEvent::MouseScroll(ev) => ev.consume(),
+            WindowEvent::PenPress(ev) | WindowEvent::PenRelease(ev) | WindowEvent::PenMove(ev) => {
+                ev.consume()
+            }
+            WindowEvent::TextInput(ev) => ev.consume(),
+            WindowEvent::KeyPress(ev) | WindowEvent::KeyRelease(ev) => ev.consume(),
+            WindowEvent::Escape(ev) => ev.consume(),
+            WindowEvent::Focus(ev) => ev.consume(),
+            WindowEvent::ClearFocus
+            | WindowEvent::Deactivate
+            | WindowEvent::Activate
+            | WindowEvent::Snap(_) => false,
+        }
+    }
+}
+
+/// Handle to a region which captures mouse input, created by `InputGrabs::grab`.
+///
+/// The grab is released when this is dropped.
+#[derive(Debug)]
+pub struct InputGrab(Rc<GrabRegion>);
+
+#[derive(Debug)]
+struct GrabRegion {
+    region: Cell<AbsoluteRect>,
+    transparent: Cell<bool>,
+    opacity: Cell<f32>,
+}
+
+impl GrabRegion {
+    /// Returns `true` if the grab currently intercepts mouse input.
+    fn intercepts(&self) -> bool {
+        !self.transparent.get() && self.opacity.get() > 0.0
+    }
+}
+
+impl InputGrab {
+    /// Changes the region in which mouse input is still delivered.
+    #[inline]
+    pub fn set_region(&self, region: AbsoluteRect) {
+        self.0.region.set(region);
+    }
+
+    /// Returns the region in which mouse input is still delivered.
+    #[inline]
+    pub fn region(&self) -> AbsoluteRect {
+        self.0.region.get()
+    }
+
+    /// Changes whether the grab lets all mouse input through to the widgets beneath it, while staying on the stack.
+    ///
+    /// This is useful for overlays which are only decorative at times, such as a scrim which is disabled.
+    #[inline]
+    pub fn set_input_transparent(&self, transparent: bool) {
+        self.0.transparent.set(transparent);
+    }
+
+    /// Returns `true` if the grab lets all mouse input through.
+    #[inline]
+    pub fn is_input_transparent(&self) -> bool {
+        self.0.transparent.get()
+    }
+
+    /// Changes the opacity of the overlay which owns the grab, from `0.0` to `1.0`.
+    ///
+    /// A grab with an opacity of zero (e.g. an overlay which has faded out) lets all mouse input through.
+    #[inline]
+    pub fn set_opacity(&self, opacity: f32) {
+        self.0.opacity.set(opacity);
+    }
+
+    /// Returns the opacity of the overlay which owns the grab.
+    #[inline]
+    pub fn opacity(&self) -> f32 {
+        self.0.opacity.get()
+    }
+}
+
+/// A window-wide stack of input grabs, used to intercept window events before they reach any widget.
+///
+/// While at least one grab is alive, mouse events falling outside the region of the most recent grab
+/// are consumed as they're emitted, meaning widgets outside of that region (e.g. beneath a modal dialog)
+/// won't respond to them. The owner of the grab can still read those events through `ConsumableEvent::get`.
+///
+/// Grabs which are input transparent or have an opacity of zero are skipped, so that the events are
+/// intercepted by the grab beneath them, if any.
+///
+/// Cloning `InputGrabs` yields another handle to the same stack.
+#[derive(Debug, Default, Clone)]
+pub struct InputGrabs(Rc<RefCell<Vec<Weak<GrabRegion>>>>);
+
+impl InputGrabs {
+    /// Creates an empty stack of input grabs.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Pushes a new grab atop the stack, only letting mouse events within `region` through.
+    pub fn grab(&self, region: AbsoluteRect) -> InputGrab {
+        let region = Rc::new(GrabRegion {
+            region: Cell::new(region),
+            transparent: Cell::new(false),
+            opacity: Cell::new(1.0),
+        });
+        self.0.borrow_mut().push(Rc::downgrade(&region));
+        InputGrab(region)
+    }
+
+    /// Returns `true` if there is at least one alive grab which intercepts mouse input.
+    pub fn is_grabbed(&self) -> bool {
+        self.top().is_some()
+    }
+
+    fn top(&self) -> Option<AbsoluteRect> {
+        let mut grabs = self.0.borrow_mut();
+        grabs.retain(|grab| grab.upgrade().is_some());
+        grabs
+            .iter()
+            .rev()
+            .filter_map(|grab| grab.upgrade())
+            .find(|grab| grab.intercepts())
+            .map(|grab| grab.region.get())
+    }
+
+    /// Consumes `event` if it's a mouse event outside the region of the topmost grab which intercepts mouse input.
+    /// This should be invoked on every window event before it's emitted.
+    ///
+    /// Returns `true` if the event was intercepted.
+    pub fn intercept(&self, event: &WindowEvent) -> bool {
+        match (self.top(), event.cursor_position()) {
+            (Some(region), Some(pos)) if !region.contains(pos) => event.consume(),
+            _ => false,
+        }
+    }
+}
+
+/// Stage of the escape cascade, in the order the stages are tried (see `WindowEvent::Escape`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EscapeStage {
+    /// Closing the topmost popup (e.g. a menu, a dropdown or a picker).
+    Popup,
+    /// Cancelling the current drag or edit.
+    Cancel,
+    /// Clearing the selection.
+    Selection,
+    /// Closing a dismissable dialog.
+    Dialog,
+}
+
+impl EscapeStage {
+    /// Returns the stage tried after this one, or `None` if this is the last stage.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            EscapeStage::Popup => Some(EscapeStage::Cancel),
+            EscapeStage::Cancel => Some(EscapeStage::Selection),
+            EscapeStage::Selection => Some(EscapeStage::Dialog),
+            EscapeStage::Dialog => None,
+        }
+    }
+}
+
+/// Dispatches presses of the escape key one stage at a time, so that widgets don't fight over the key.
+///
+/// Rather than emitting the `KeyPress`, `begin` is emitted; after each update, the event returned by `advance`
+/// (if any) is emitted, until a widget has consumed the current stage or the last stage went unhandled.
+#[derive(Debug, Default)]
+pub struct EscapeCascade(Option<ConsumableEvent<EscapeStage>>);
+
+impl EscapeCascade {
+    /// Starts a cascade from its first stage, abandoning any cascade in progress.
+    /// Returns the event to emit.
+    #[inline]
+    pub fn begin(&mut self) -> WindowEvent {
+        self.begin_at(EscapeStage::Popup)
+    }
+
+    /// Returns the event to emit for the next stage if the current stage went unhandled, otherwise ends the cascade.
+    /// This should be invoked after the widgets have been updated.
+    pub fn advance(&mut self) -> Option<WindowEvent> {
+        let stage = self.0.take().filter(|event| !event.is_consumed())?.get().next()?;
+        Some(self.begin_at(stage))
+    }
+
+    fn begin_at(&mut self, stage: EscapeStage) -> WindowEvent {
+        let event = ConsumableEvent::new(stage);
+        self.0 = Some(event.clone());
+        WindowEvent::Escape(event)
+    }
+}
+
+bitflags::bitflags! {
+    /// Interactions which close an overlay popup (e.g. a menu, a dropdown or a picker).
+    ///
+    /// All popups share the same default policy, `DismissPolicy::all()`, while a pinned popup uses
+    /// `DismissPolicy::empty()` and stays open until it's closed explicitly.
+    pub struct DismissPolicy: u32 {
+        /// A press (of a mouse button or pen) outside the popup.
+        const OUTSIDE_PRESS = 1;
+        /// The escape key.
+        const ESCAPE = 1 << 1;
+        /// The window losing focus.
+        const DEACTIVATE = 1 << 2;
+        /// A scroll outside the popup, which may move the widget it's anchored to.
+        const SCROLL = 1 << 3;
+    }
+}
+
+impl Default for DismissPolicy {
+    #[inline]
+    fn default() -> Self {
+        DismissPolicy::all()
+    }
+}
+
+impl DismissPolicy {
+    /// Returns `true` if `event` should close a popup occupying `region`, according to this policy.
+    ///
+    /// Events are only inspected, not consumed, so this is unaffected by input grabs; the exception is the popup
+    /// stage of `WindowEvent::Escape`, which is consumed so that one press only closes one popup.
+    pub fn dismisses(self, event: &WindowEvent, region: AbsoluteRect) -> bool {
+        match event {
+            WindowEvent::MousePress(ev) => {
+                self.contains(DismissPolicy::OUTSIDE_PRESS) && !region.contains(ev.get().0)
+            }
+            WindowEvent::PenPress(ev) => {
+                self.contains(DismissPolicy::OUTSIDE_PRESS) && !region.contains(ev.get().0)
+            }
+            WindowEvent::MouseScroll(ev) => {
+                self.contains(DismissPolicy::SCROLL) && !region.contains(ev.get().0)
+            }
+            WindowEvent::Escape(ev) => {
+                self.contains(DismissPolicy::ESCAPE)
+                    && ev.with(|stage| *stage == EscapeStage::Popup).is_some()
+            }
+            WindowEvent::Deactivate => self.contains(DismissPolicy::DEACTIVATE),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TooltipCoordinatorState {
+    delay: Duration,
+    grace: Duration,
+    next_id: u64,
+    /// Hovered tooltip, when it was hovered, and whether it was hidden by a press or scroll.
+    active: Option<(u64, Instant, bool)>,
+    /// Until when tooltips are shown without delay, after one was last shown.
+    warm_until: Option<Instant>,
+}
+
+impl TooltipCoordinatorState {
+    fn is_shown(&self, id: u64, now: Instant) -> bool {
+        match self.active {
+            Some((active, hovered_at, hidden)) if active == id && !hidden => {
+                self.warm_until.map_or(false, |warm_until| hovered_at <= warm_until)
+                    || now - hovered_at >= self.delay
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Window-wide coordinator of tooltips, giving them standard desktop behaviour.
+///
+/// A tooltip is shown after its owner has been hovered for a delay. Once a tooltip has been shown,
+/// moving the cursor onto another owner within a grace period (e.g. along a toolbar) shows its tooltip
+/// immediately. Any press of a button or key, or any scroll (see `TooltipCoordinator::observe`), hides the tooltip
+/// until its owner is hovered again.
+///
+/// Cloning `TooltipCoordinator` yields another handle to the same coordinator.
+#[derive(Debug, Clone)]
+pub struct TooltipCoordinator(Rc<RefCell<TooltipCoordinatorState>>);
+
+impl Default for TooltipCoordinator {
+    fn default() -> Self {
+        TooltipCoordinator::new(Duration::from_millis(500), Duration::from_millis(300))
+    }
+}
+
+impl TooltipCoordinator {
+    /// Creates a coordinator which shows tooltips after `delay`, or immediately within `grace` of one being shown.
+    pub fn new(delay: Duration, grace: Duration) -> Self {
+        TooltipCoordinator(Rc::new(RefCell::new(TooltipCoordinatorState {
+            delay,
+            grace,
+            next_id: 0,
+            active: None,
+            warm_until: None,
+        })))
+    }
+
+    /// Registers a new tooltip, which is hidden until its owner is hovered.
+    pub fn tooltip(&self) -> Tooltip {
+        let mut state = self.0.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+        Tooltip { coordinator: self.clone(), id }
+    }
+
+    /// Hides the shown tooltip, if any, and cancels the grace period.
+    pub fn hide_all(&self) {
+        let mut state = self.0.borrow_mut();
+        if let Some((_, _, ref mut hidden)) = state.active {
+            *hidden = true;
+        }
+        state.warm_until = None;
+    }
+
+    /// Hides all tooltips if `event` is a press or a scroll.
+    /// This should be invoked on every window event before it's emitted.
+    pub fn observe(&self, event: &WindowEvent) {
+        match event {
+            WindowEvent::MousePress(_)
+            | WindowEvent::MouseScroll(_)
+            | WindowEvent::PenPress(_)
+            | WindowEvent::KeyPress(_)
+            | WindowEvent::Escape(_) => self.hide_all(),
+            _ => {}
+        }
+    }
+}
+
+/// Handle to a tooltip managed by a `TooltipCoordinator`, created by `TooltipCoordinator::tooltip`.
+///
+/// The owner of the tooltip reports hovering with `hover` and `unhover`, and should check `is_shown`
+/// every update (repainting when it changes), since tooltips appear as time passes. While the owner is hovered
+/// and the tooltip isn't shown yet, it should request animation frames so that the tooltip appears without input.
+#[derive(Debug)]
+pub struct Tooltip {
+    coordinator: TooltipCoordinator,
+    id: u64,
+}
+
+impl Tooltip {
+    /// Notes that the owner of the tooltip is hovered, replacing any other hovered tooltip.
+    pub fn hover(&self) {
+        let mut state = self.coordinator.0.borrow_mut();
+        let now = Instant::now();
+        match state.active {
+            Some((id, _, _)) if id == self.id => {}
+            Some((id, _, _)) => {
+                if state.is_shown(id, now) {
+                    state.warm_until = Some(now + state.grace);
+                }
+                state.active = Some((self.id, now, false));
+            }
+            None => state.active = Some((self.id, now, false)),
+        }
+    }
+
+    /// Notes that the owner of the tooltip is no longer hovered.
+    pub fn unhover(&self) {
+        let mut state = self.coordinator.0.borrow_mut();
+        let now = Instant::now();
+        if state.is_shown(self.id, now) {
+            state.warm_until = Some(now + state.grace);
+        }
+        if let Some((id, _, _)) = state.active {
+            if id == self.id {
+                state.active = None;
+            }
+        }
+    }
+
+    /// Returns `true` if the tooltip should currently be shown.
+    pub fn is_shown(&self) -> bool {
+        self.coordinator.0.borrow().is_shown(self.id, Instant::now())
+    }
+}
+
+impl Drop for Tooltip {
+    fn drop(&mut self) {
+        self.unhover();
+    }
+}
+
+#[derive(Debug)]
+struct FocusEntryState {
+    id: u64,
+    tab_index: Cell<Option<u32>>,
+    group: Cell<Option<u64>>,
+    focusable: Cell<bool>,
+    /// Update pass during which the entry was last refreshed (see `FocusEntry::set_focusable`).
+    refreshed: Cell<u64>,
+}
+
+#[derive(Debug, Default)]
+struct FocusChainState {
+    next_id: u64,
+    /// Current update pass (see `FocusChain::begin_pass`).
+    pass: u64,
+    /// Entries in the order they were registered.
+    entries: Vec<Weak<FocusEntryState>>,
+    focused: Option<u64>,
+    /// Member of each group which was focused last.
+    last_focused: HashMap<u64, u64>,
+}
+
+impl FocusChainState {
+    /// Returns the focusable entries grouped into tab stops, in tab order.
+    /// A stop is either a single entry outside of any group, or all the members of a group.
+    fn stops(&mut self) -> Vec<(Option<u64>, Vec<u64>)> {
+        self.entries.retain(|entry| entry.upgrade().is_some());
+        let pass = self.pass;
+        let mut order: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.upgrade())
+            .filter(|entry| entry.focusable.get() && entry.refreshed.get() == pass)
+            .collect();
+        // stable, so that entries without a tab index stay in the order they were registered
+        order.sort_by_key(|entry| entry.tab_index.get().map_or((1, 0), |index| (0, index)));
+
+        let mut stops: Vec<(Option<u64>, Vec<u64>)> = Vec::new();
+        for entry in order {
+            match entry.group.get() {
+                Some(group) => {
+                    if let Some(stop) = stops.iter_mut().find(|stop| stop.0 == Some(group)) {
+                        stop.1.push(entry.id);
+                    } else {
+                        stops.push((Some(group), vec![entry.id]));
+                    }
+                }
+                None => stops.push((None, vec![entry.id])),
+            }
+        }
+        stops
+    }
+}
+
+/// Window-wide order in which the keyboard moves the focus between widgets.
+///
+/// Each widget which can be focused from the keyboard registers a `FocusEntry`. Tab and Shift+Tab move between
+/// tab stops; entries with an explicit tab index come first, in ascending order, followed by the rest in the order
+/// they were registered (which is the order they were built in). All the members of a focus group form a single stop,
+/// which is entered at the member focused last, and the arrow keys cycle within the group, like in a radio cluster
+/// or a toolbar.
+///
+/// Keys which widgets leave unconsumed are passed to `navigate`, after which the focus is moved by emitting
+/// `WindowEvent::ClearFocus` followed by `WindowEvent::Focus`.
+///
+/// Hidden widgets are skipped: widgets refresh their entry every update, and since hidden subtrees aren't updated,
+/// entries which weren't refreshed since `begin_pass` are left out of the chain.
+///
+/// Tab indices and focus groups are properties of the widgets and layouts, so in `rooftop!` they're set like any
+/// other property, e.g. `Button(tab_index=Some(1))` or `HStack(focus_group=true)`.
+///
+/// Cloning `FocusChain` yields another handle to the same chain.
+#[derive(Debug, Default, Clone)]
+pub struct FocusChain(Rc<RefCell<FocusChainState>>);
+
+impl FocusChain {
+    /// Creates an empty focus chain.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a new entry, which can be focused as soon as it's created.
+    pub fn entry(&self) -> FocusEntry {
+        let mut state = self.0.borrow_mut();
+        let entry = Rc::new(FocusEntryState {
+            id: state.next_id,
+            tab_index: Cell::new(None),
+            group: Cell::new(None),
+            focusable: Cell::new(true),
+            refreshed: Cell::new(state.pass),
+        });
+        state.next_id += 1;
+        state.entries.push(Rc::downgrade(&entry));
+        FocusEntry { chain: self.clone(), inner: entry }
+    }
+
+    /// Starts an update pass. Entries which aren't refreshed with `FocusEntry::set_focusable` before the next call to
+    /// `navigate` are skipped, as the widgets owning them weren't updated (e.g. they're in a hidden subtree).
+    ///
+    /// This should be invoked before every update pass; a chain which never starts a pass keeps every entry.
+    pub fn begin_pass(&self) {
+        self.0.borrow_mut().pass += 1;
+    }
+
+    /// Creates a new focus group, which entries join through `FocusEntry::set_group`.
+    pub fn group(&self) -> u64 {
+        let mut state = self.0.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+        id
+    }
+
+    /// Returns the ID of the focused entry, if any.
+    pub fn focused(&self) -> Option<u64> {
+        self.0.borrow().focused
+    }
+
+    /// Returns the ID of the entry which `key` moves the focus to, if any.
+    ///
+    /// Tab and Shift+Tab move to the next and previous tab stop, and the arrow keys move to the next (right and down)
+    /// and previous (left and up) member of the focused group. Both wrap around.
+    pub fn navigate(&self, key: KeyInput, modifiers: KeyModifiers) -> Option<u64> {
+        if modifiers.ctrl || modifiers.alt || modifiers.logo {
+            return None;
+        }
+
+        let mut state = self.0.borrow_mut();
+        let stops = state.stops();
+        let current = state.focused.and_then(|focused| {
+            stops.iter().position(|stop| stop.1.contains(&focused)).map(|stop| (stop, focused))
+        });
+
+        match key {
+            KeyInput::Tab if !stops.is_empty() => {
+                let stop = match current {
+                    Some((stop, _)) if modifiers.shift => (stop + stops.len() - 1) % stops.len(),
+                    Some((stop, _)) => (stop + 1) % stops.len(),
+                    None if modifiers.shift => stops.len() - 1,
+                    None => 0,
+                };
+                let (group, members) = &stops[stop];
+                Some(
+                    group
+                        .and_then(|group| state.last_focused.get(&group).copied())
+                        .filter(|last| members.contains(last))
+                        .unwrap_or(members[0]),
+                )
+            }
+            KeyInput::Left | KeyInput::Up | KeyInput::Right | KeyInput::Down
+                if !modifiers.shift =>
+            {
+                let (stop, focused) = current?;
+                let (group, members) = &stops[stop];
+                if group.is_none() {
+                    return None;
+                }
+                let index = members.iter().position(|&member| member == focused)?;
+                Some(match key {
+                    KeyInput::Left | KeyInput::Up => {
+                        members[(index + members.len() - 1) % members.len()]
+                    }
+                    _ => members[(index + 1) % members.len()],
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Handle to an entry of a `FocusChain`, created by `FocusChain::entry`.
+///
+/// The owner of the entry reports gaining and losing focus with `set_focused`, and should keep the tab index,
+/// group and focusability up to date every update; an entry whose focusability wasn't refreshed during the current
+/// update pass is skipped (see `FocusChain::begin_pass`). The entry is removed from the chain when this is dropped.
+#[derive(Debug)]
+pub struct FocusEntry {
+    chain: FocusChain,
+    inner: Rc<FocusEntryState>,
+}
+
+impl FocusEntry {
+    /// Returns the ID of the entry, as carried by `WindowEvent::Focus`.
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// Changes the explicit tab index of the entry, or `None` to follow the order of registration.
+    #[inline]
+    pub fn set_tab_index(&self, tab_index: Option<u32>) {
+        self.inner.tab_index.set(tab_index);
+    }
+
+    /// Returns the explicit tab index of the entry.
+    #[inline]
+    pub fn tab_index(&self) -> Option<u32> {
+        self.inner.tab_index.get()
+    }
+
+    /// Changes the focus group the entry is a member of (see `FocusChain::group`), if any.
+    #[inline]
+    pub fn set_group(&self, group: Option<u64>) {
+        self.inner.group.set(group);
+    }
+
+    /// Returns the focus group the entry is a member of, if any.
+    #[inline]
+    pub fn group(&self) -> Option<u64> {
+        self.inner.group.get()
+    }
+
+    /// Changes whether the entry can be focused from the keyboard; disabled and hidden widgets are skipped.
+    /// This also refreshes the entry for the current update pass.
+    #[inline]
+    pub fn set_focusable(&self, focusable: bool) {
+        self.inner.focusable.set(focusable);
+        self.inner.refreshed.set(self.chain.0.borrow().pass);
+    }
+
+    /// Returns `true` if the entry can be focused from the keyboard.
+    #[inline]
+    pub fn is_focusable(&self) -> bool {
+        self.inner.focusable.get()
+    }
+
+    /// Notes that the owner of the entry gained or lost focus, however it happened.
+    pub fn set_focused(&self, focused: bool) {
+        let mut state = self.chain.0.borrow_mut();
+        if focused {
+            state.focused = Some(self.id());
+            if let Some(group) = self.group() {
+                state.last_focused.insert(group, self.id());
+            }
+        } else if state.focused == Some(self.id()) {
+            state.focused = None;
+        }
+    }
+}
+
+impl Drop for FocusEntry {
+    fn drop(&mut self) {
+        self.set_focused(false);
+    }
+}
+
+// Most of these are copied from `winit`.
+// We can't reuse the `winit` types because `winit` is an optional dependency (app feature).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyModifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+/// Button on a mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Additional information reported by a pen/stylus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenData {
+    /// Pressure applied, normalized from `0.0` (none) to `1.0` (maximum).
+    pub pressure: f32,
+    /// Tilt of the pen along the X and Y axes, in radians.
+    /// `(0.0, 0.0)` means the pen is perpendicular to the surface.
+    pub tilt: (f32, f32),
+    /// Whether the eraser end of the pen is in use.
+    pub eraser: bool,
+    /// Whether the barrel button of the pen is held.
+    pub barrel: bool,
+}
+
+impl Default for PenData {
+    fn default() -> Self {
+        PenData { pressure: 1.0, tilt: (0.0, 0.0), eraser: false, barrel: false }
+    }
+}
+
+/// Icon of the mouse cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    Default,
+    /// Usually a hand; indicates a link or clickable element.
+    Pointer,
+    Text,
+    Move,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    /// Resizing to the left or right.
+    ResizeHorizontal,
+    /// Resizing up or down.
+    ResizeVertical,
+    /// Resizing along the top-left to bottom-right diagonal.
+    ResizeNwSe,
+    /// Resizing along the top-right to bottom-left diagonal.
+    ResizeNeSw,
+}
+
+impl Default for CursorIcon {
+    fn default() -> Self {
+        CursorIcon::Default
+    }
+}
+
+/// How urgently the attention of the user is requested (see `UpdateAuxiliary::request_attention`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserAttention {
+    /// Briefly draws attention to the window.
+    Informational,
+    /// Draws attention to the window until it's focused.
+    Critical,
+}
+
+/// Progress shown on the taskbar button or dock icon of a window (see `UpdateAuxiliary::set_taskbar_progress`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskbarProgress {
+    /// No progress is shown.
+    None,
+    /// An operation is ongoing, with unknown progress.
+    Indeterminate,
+    /// Progress of an ongoing operation, from `0.0` to `1.0`.
+    Normal(f32),
+    /// Progress of an operation which is paused.
+    Paused(f32),
+    /// Progress of an operation which failed.
+    Error(f32),
+}
+
+impl Default for TaskbarProgress {
+    fn default() -> Self {
+        TaskbarProgress::None
+    }
+}
+
+/// Edge or corner of the window which is dragged to resize it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ResizeEdge {
+    /// Returns the cursor icon shown over the edge.
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            ResizeEdge::Left | ResizeEdge::Right => CursorIcon::ResizeHorizontal,
+            ResizeEdge::Top | ResizeEdge::Bottom => CursorIcon::ResizeVertical,
+            ResizeEdge::TopLeft | ResizeEdge::BottomRight => CursorIcon::ResizeNwSe,
+            ResizeEdge::TopRight | ResizeEdge::BottomLeft => CursorIcon::ResizeNeSw,
+        }
+    }
+
+    /// Returns the edge of `rect` within `border` of `point`, if any. Corners take precedence over the sides.
+    pub fn hit_test(rect: AbsoluteRect, border: f32, point: AbsolutePoint) -> Option<Self> {
+        if !rect.contains(point) {
+            return None;
+        }
+
+        let left = point.x < rect.min_x() + border;
+        let right = point.x >= rect.max_x() - border;
+        let top = point.y < rect.min_y() + border;
+        let bottom = point.y >= rect.max_y() - border;
+        Some(match (left, right, top, bottom) {
+            (true, _, true, _) => ResizeEdge::TopLeft,
+            (_, true, true, _) => ResizeEdge::TopRight,
+            (true, _, _, true) => ResizeEdge::BottomLeft,
+            (_, true, _, true) => ResizeEdge::BottomRight,
+            (true, _, _, _) => ResizeEdge::Left,
+            (_, true, _, _) => ResizeEdge::Right,
+            (_, _, true, _) => ResizeEdge::Top,
+            (_, _, _, true) => ResizeEdge::Bottom,
+            _ => return None,
+        })
+    }
+}
+
+/// Behavior of a region of the window declared through `UpdateAuxiliary::declare_window_region`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowRegion {
+    /// Pressing the region moves the window, as a title bar would.
+    Caption,
+    /// Pressing the region resizes the window from an edge or corner.
+    Resize(ResizeEdge),
+    /// Interactive content within a caption (e.g. the buttons of a title bar), which takes precedence over
+    /// overlapping caption and resize regions.
+    Client,
+}
+
+/// Change to the state of the window requested through `UpdateAuxiliary::request_window_action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowAction {
+    Minimize,
+    Maximize,
+    /// Restores the window from being maximized or snapped.
+    Restore,
+    /// Snaps the window to part of the screen; restoring it returns it to where it was before snapping.
+    Snap(WindowSnap),
+    /// Closes the window, as if closed by the user through the window manager.
+    Close,
+}
+
+/// Part of the screen which a window is snapped to, either by a shortcut (see `app::AppOptions::snap_shortcuts`) or
+/// by dragging it against an edge of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowSnap {
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl WindowSnap {
+    /// Returns the part of `screen` which the window fills.
+    pub fn rect(self, screen: Rect) -> Rect {
+        let half = screen.size / 2.0;
+        let (x, y) = (screen.min_x(), screen.min_y());
+        let (mid_x, mid_y) = (x + half.width, y + half.height);
+        match self {
+            WindowSnap::Left => Rect::new(screen.origin, Size::new(half.width, screen.size.height)),
+            WindowSnap::Right => {
+                Rect::new(Point::new(mid_x, y), Size::new(half.width, screen.size.height))
+            }
+            WindowSnap::TopLeft => Rect::new(screen.origin, half),
+            WindowSnap::TopRight => Rect::new(Point::new(mid_x, y), half),
+            WindowSnap::BottomLeft => Rect::new(Point::new(x, mid_y), half),
+            WindowSnap::BottomRight => Rect::new(Point::new(mid_x, mid_y), half),
+        }
+    }
+
+    /// Returns `true` if the window fills half of the screen, rather than a quarter.
+    #[inline]
+    pub fn is_half(self) -> bool {
+        match self {
+            WindowSnap::Left | WindowSnap::Right => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the snap of a window dropped at `point` after being dragged, if `point` is within `distance` of the
+    /// left or right edge of `screen`. The top and bottom quarters of the edges snap to the corners.
+    pub fn at_edge(screen: Rect, distance: f32, point: Point) -> Option<Self> {
+        if !screen.contains(point) {
+            return None;
+        }
+
+        let left = point.x < screen.min_x() + distance;
+        let right = point.x >= screen.max_x() - distance;
+        let corner = screen.size.height / 4.0;
+        let top = point.y < screen.min_y() + corner;
+        let bottom = point.y >= screen.max_y() - corner;
+        Some(match (left, right, top, bottom) {
+            (true, _, true, _) => WindowSnap::TopLeft,
+            (_, true, true, _) => WindowSnap::TopRight,
+            (true, _, _, true) => WindowSnap::BottomLeft,
+            (_, true, _, true) => WindowSnap::BottomRight,
+            (true, _, _, _) => WindowSnap::Left,
+            (_, true, _, _) => WindowSnap::Right,
+            _ => return None,
+        })
+    }
+
+    /// Returns the window action performed by the snapping shortcut for `key` (an arrow key, pressed along with the
+    /// logo key), given how the window is currently snapped or whether it's maximized.
+    ///
+    /// Left and right snap to the halves of the screen, and up and down then narrow a half to a quarter; moving
+    /// away from a half restores the window, while up and down on an unsnapped window maximize and minimize it.
+    pub fn shortcut(key: KeyInput, current: Option<Self>, maximized: bool) -> Option<WindowAction> {
+        use WindowSnap::*;
+        let snap = |snap| Some(WindowAction::Snap(snap));
+        if maximized {
+            return if key == KeyInput::Down { Some(WindowAction::Restore) } else { None };
+        }
+        match (current, key) {
+            (None, KeyInput::Left) => snap(Left),
+            (None, KeyInput::Right) => snap(Right),
+            (None, KeyInput::Up) => Some(WindowAction::Maximize),
+            (None, KeyInput::Down) => Some(WindowAction::Minimize),
+            (Some(Left), KeyInput::Up) => snap(TopLeft),
+            (Some(Left), KeyInput::Down) => snap(BottomLeft),
+            (Some(Left), KeyInput::Right) | (Some(Right), KeyInput::Left) => {
+                Some(WindowAction::Restore)
+            }
+            (Some(Right), KeyInput::Up) => snap(TopRight),
+            (Some(Right), KeyInput::Down) => snap(BottomRight),
+            (Some(TopLeft), KeyInput::Right) => snap(TopRight),
+            (Some(TopRight), KeyInput::Left) => snap(TopLeft),
+            (Some(BottomLeft), KeyInput::Right) => snap(BottomRight),
+            (Some(BottomRight), KeyInput::Left) => snap(BottomLeft),
+            (Some(TopLeft), KeyInput::Down) | (Some(BottomLeft), KeyInput::Up) => snap(Left),
+            (Some(TopRight), KeyInput::Down) | (Some(BottomRight), KeyInput::Up) => snap(Right),
+            (Some(TopLeft), KeyInput::Up) | (Some(TopRight), KeyInput::Up) => {
+                Some(WindowAction::Maximize)
+            }
+            (Some(BottomLeft), KeyInput::Down) | (Some(BottomRight), KeyInput::Down) => {
+                Some(WindowAction::Minimize)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Kind of text expected by a text widget (see `InputHints`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputPurpose {
+    Text,
+    /// Numbers, including a sign, decimal separator and exponent.
+    Numeric,
+    Email,
+    Url,
+    /// Text which shouldn't be shown, remembered or predicted by the input method.
+    Password,
+}
+
+/// Hints on the text expected by a text widget, forwarded to the input method of the platform (e.g. to pick the
+/// layout of an on-screen keyboard) while the widget is focused, and used by the widget to reject characters which
+/// can't be part of the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InputHints {
+    pub purpose: InputPurpose,
+    /// Whether the input method may autocorrect and predict the text.
+    pub autocorrect: bool,
+}
+
+impl InputHints {
+    /// Hints for plain text, with autocorrection.
+    pub const TEXT: InputHints = InputHints { purpose: InputPurpose::Text, autocorrect: true };
+    /// Hints for numbers.
+    pub const NUMERIC: InputHints =
+        InputHints { purpose: InputPurpose::Numeric, autocorrect: false };
+    /// Hints for email addresses.
+    pub const EMAIL: InputHints = InputHints { purpose: InputPurpose::Email, autocorrect: false };
+    /// Hints for URLs.
+    pub const URL: InputHints = InputHints { purpose: InputPurpose::Url, autocorrect: false };
+    /// Hints for passwords.
+    pub const PASSWORD: InputHints =
+        InputHints { purpose: InputPurpose::Password, autocorrect: false };
+
+    /// Returns these hints without autocorrection.
+    #[inline]
+    pub fn without_autocorrect(self) -> Self {
+        InputHints { autocorrect: false, ..self }
+    }
+
+    /// Returns `true` if a typed character can be part of the text.
+    pub fn accepts(&self, c: char) -> bool {
+        match self.purpose {
+            InputPurpose::Numeric => c.is_ascii_digit() || "+-.,eE".contains(c),
+            InputPurpose::Email | InputPurpose::Url => !c.is_whitespace(),
+            InputPurpose::Text | InputPurpose::Password => true,
+        }
+    }
+}
+
+impl Default for InputHints {
+    fn default() -> Self {
+        InputHints::TEXT
     }
 }
 
-impl<T> Clone for ConsumableEvent<T> {
-    fn clone(&self) -> Self {
-        ConsumableEvent(self.0.clone())
+/// Text input requested by a focused text widget (see `UpdateAuxiliary::request_text_input`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextInputRequest {
+    pub hints: InputHints,
+    /// Area of the text being edited, which the input method should avoid covering (e.g. with its candidates).
+    pub area: AbsoluteRect,
+}
+
+impl From<Option<f32>> for TaskbarProgress {
+    /// Converts the value of a progress bar, which is `None` while indeterminate.
+    fn from(progress: Option<f32>) -> Self {
+        match progress {
+            Some(progress) => TaskbarProgress::Normal(progress.max(0.0).min(1.0)),
+            None => TaskbarProgress::Indeterminate,
+        }
     }
 }
 
-/// An event related to the window, e.g. input.
-#[derive(Event, Debug, Clone, PartialEq)]
-pub enum WindowEvent {
-    /// The user pressed a mouse button.
-    #[event_key(mouse_press)]
-    MousePress(ConsumableEvent<(AbsolutePoint, MouseButton, KeyModifiers)>),
-    /// The user released a mouse button.
-    /// This event complements `MousePress`, which means it realistically can only
-    /// be emitted after `MousePress` has been emitted.
-    #[event_key(mouse_release)]
-    MouseRelease(ConsumableEvent<(AbsolutePoint, MouseButton, KeyModifiers)>),
-    /// The user moved the cursor.
-    #[event_key(mouse_move)]
-    MouseMove(ConsumableEvent<(AbsolutePoint, KeyModifiers)>),
-    /// Emitted when a text input is received.
-    #[event_key(text_input)]
-    TextInput(ConsumableEvent<char>),
-    /// Emitted when a key is pressed.
-    #[event_key(key_press)]
-    KeyPress(ConsumableEvent<(KeyInput, KeyModifiers)>),
-    /// Emitted when a key is released.
-    #[event_key(key_release)]
-    KeyRelease(ConsumableEvent<(KeyInput, KeyModifiers)>),
-    /// Emitted immediately before an event which is capable of changing focus.
-    /// If implementing a focus-able widget, to handle this event, simply clear
-    /// the local "focused" flag (which should ideally be stored as `draw::state::InteractionState`).
-    #[event_key(clear_focus)]
-    ClearFocus,
+/// Usage metric of the interface, recorded through `UpdateAuxiliary::record_metric`.
+///
+/// Metrics never carry user content (e.g. text or cursor positions); only what happened, and to which kind of
+/// widget. Products can then collect UX metrics without compromising the privacy of their users.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// A widget was activated by the user (e.g. a button was clicked), with the name of its type (see `widget_kind`).
+    WidgetActivated(&'static str),
+    /// A dialog was opened.
+    DialogOpened,
+    /// A dialog was closed.
+    DialogClosed,
+    /// The first frame was presented, with the time taken since the app was created.
+    TimeToFirstFrame(Duration),
 }
 
-// Most of these are copied from `winit`.
-// We can't reuse the `winit` types because `winit` is an optional dependency (app feature).
+/// Receiver of usage metrics, implemented by the application (e.g. to collect them over a session).
+pub trait MetricsRecorder {
+    /// Records a metric.
+    fn record(&mut self, metric: Metric);
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct KeyModifiers {
-    pub shift: bool,
-    pub ctrl: bool,
-    pub alt: bool,
-    pub logo: bool,
+impl<F: FnMut(Metric)> MetricsRecorder for F {
+    #[inline]
+    fn record(&mut self, metric: Metric) {
+        (self)(metric)
+    }
 }
 
-/// Button on a mouse.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum MouseButton {
-    Left,
-    Middle,
-    Right,
+/// Returns the name of a widget type without its path or type parameters, e.g. `"ButtonWidget"`.
+pub fn widget_kind<W: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<W>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
 }
 
 // Previously: `std::mem::transmute::<KeyInput>(virtual_key)`.
@@ -544,11 +1715,77 @@ keyboard_enum! {
     }
 }
 
+/// Visibility imposed on a widget by the layout it has been pushed into.
+///
+/// This is shared between the layout and the widget; the layout changes it and the widget
+/// combines it with its own visibility (see `Visibility::combine`).
+#[derive(Debug, Clone, Default)]
+pub struct LayoutVisibility(Rc<Cell<Visibility>>);
+
+impl LayoutVisibility {
+    /// Changes the visibility imposed on the widget.
+    #[inline]
+    pub fn set(&self, visibility: Visibility) {
+        self.0.set(visibility);
+    }
+
+    /// Returns the visibility imposed on the widget.
+    #[inline]
+    pub fn get(&self) -> Visibility {
+        self.0.get()
+    }
+}
+
+/// Rectangle imposed on a widget by the layout it has been pushed into, outside of which the widget isn't drawn.
+///
+/// This lets layouts show part of a widget, e.g. while sliding it in or out of view.
+/// Like `LayoutVisibility`, this is shared between the layout and the widget.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutClip(Rc<Cell<Option<AbsoluteRect>>>);
+
+impl LayoutClip {
+    /// Changes the clip imposed on the widget; `None` to draw the widget entirely.
+    #[inline]
+    pub fn set(&self, clip: Option<AbsoluteRect>) {
+        self.0.set(clip);
+    }
+
+    /// Returns the clip imposed on the widget.
+    #[inline]
+    pub fn get(&self) -> Option<AbsoluteRect> {
+        self.0.get()
+    }
+}
+
+/// Focus group imposed on a widget by the layout it has been pushed into (see `FocusChain`), such that a stack of
+/// buttons can be traversed with the arrow keys.
+///
+/// Like `LayoutVisibility`, this is shared between the layout and the widget.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutFocusGroup(Rc<Cell<Option<u64>>>);
+
+impl LayoutFocusGroup {
+    /// Changes the focus group imposed on the widget; `None` to leave it outside of any group.
+    #[inline]
+    pub fn set(&self, group: Option<u64>) {
+        self.0.set(group);
+    }
+
+    /// Returns the focus group imposed on the widget.
+    #[inline]
+    pub fn get(&self) -> Option<u64> {
+        self.0.get()
+    }
+}
+
 /// Information about a parent layout with a queue which receives updated rectangles.
 #[derive(Debug)]
 pub struct WidgetLayoutEventsInner {
     pub id: u64,
     pub evq: reclutch::event::bidir_single::Secondary<AbsoluteRect, AbsoluteRect>,
+    pub visibility: LayoutVisibility,
+    pub clip: LayoutClip,
+    pub focus_group: LayoutFocusGroup,
 }
 
 /// Helper layout over `WidgetLayoutEventsInner`; optionally stores information about a parent layout.
@@ -588,6 +1825,21 @@ impl WidgetLayoutEvents {
     pub fn receive(&mut self) -> Option<AbsoluteRect> {
         self.0.as_mut().and_then(|inner| inner.evq.retrieve_newest())
     }
+
+    /// Returns the visibility imposed by the layout, or `Visibility::Normal` if there is no layout.
+    pub fn visibility(&self) -> Visibility {
+        self.0.as_ref().map(|inner| inner.visibility.get()).unwrap_or_default()
+    }
+
+    /// Returns the clip imposed by the layout, if any.
+    pub fn clip(&self) -> Option<AbsoluteRect> {
+        self.0.as_ref().and_then(|inner| inner.clip.get())
+    }
+
+    /// Returns the focus group imposed by the layout, if any.
+    pub fn focus_group(&self) -> Option<u64> {
+        self.0.as_ref().and_then(|inner| inner.focus_group.get())
+    }
 }
 
 /// Widget that is capable of listening to layout events.
@@ -679,6 +1931,366 @@ macro_rules! observe {
     };
 }
 
+/// Queue of updates to the items of a view (e.g. the rows of a `TreeTable` or the values of a `Sparkline`) which the
+/// view applies as a batch once per update, rather than one at a time.
+///
+/// Intended for data streaming in faster than frames are drawn (e.g. thousands of telemetry samples per second);
+/// however many updates are pushed between two frames, the view relays out and repaints once. Every clone of a feed
+/// pushes into the same queue, and feeds can be sent to other threads (e.g. one reading from a socket).
+///
+/// Updates are applied on the next update of the view, which happens as input arrives or animation frames are
+/// requested; apps streaming data without input should request animation frames while streaming.
+pub struct UpdateFeed<T> {
+    queue: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> Clone for UpdateFeed<T> {
+    fn clone(&self) -> Self {
+        UpdateFeed { queue: self.queue.clone() }
+    }
+}
+
+impl<T> Default for UpdateFeed<T> {
+    fn default() -> Self {
+        UpdateFeed { queue: Default::default() }
+    }
+}
+
+impl<T> std::fmt::Debug for UpdateFeed<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("UpdateFeed").field("pending", &self.len()).finish()
+    }
+}
+
+impl<T> UpdateFeed<T> {
+    /// Creates an empty feed.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Queues an update.
+    pub fn push(&self, update: T) {
+        self.lock().push(update);
+    }
+
+    /// Queues several updates, in order.
+    pub fn extend(&self, updates: impl IntoIterator<Item = T>) {
+        self.lock().extend(updates);
+    }
+
+    /// Returns the number of updates queued.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no updates are queued.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes and returns every update queued, in the order they were pushed.
+    pub fn drain(&self) -> Vec<T> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<Vec<T>> {
+        self.queue.lock().unwrap()
+    }
+}
+
+/// Transition of a [`StateMachine`](struct.StateMachine.html) from one state to another, triggered by an event.
+pub struct Transition<S, E, C> {
+    from: S,
+    to: S,
+    trigger: Box<dyn Fn(&E) -> bool>,
+    guard: Option<Box<dyn Fn(&C, &E) -> bool>>,
+    action: Option<Box<dyn FnMut(&mut C, &E)>>,
+}
+
+impl<S, E, C> Transition<S, E, C> {
+    /// Creates a transition from `from` to `to`, taken for events which satisfy `trigger`.
+    pub fn new(from: S, to: S, trigger: impl Fn(&E) -> bool + 'static) -> Self {
+        Transition { from, to, trigger: Box::new(trigger), guard: None, action: None }
+    }
+
+    /// Only takes the transition if `guard` is satisfied, given the context and the event.
+    pub fn guard(mut self, guard: impl Fn(&C, &E) -> bool + 'static) -> Self {
+        self.guard = Some(Box::new(guard));
+        self
+    }
+
+    /// Invokes `action` when the transition is taken, between the exit action of the previous state and the
+    /// entry action of the next state.
+    pub fn action(mut self, action: impl FnMut(&mut C, &E) + 'static) -> Self {
+        self.action = Some(Box::new(action));
+        self
+    }
+}
+
+impl<S: std::fmt::Debug, E, C> std::fmt::Debug for Transition<S, E, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Transition")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("guarded", &self.guard.is_some())
+            .finish()
+    }
+}
+
+/// Typed finite state machine, for widgets with complex interactions (e.g. drag-and-drop, text selection or resize
+/// handles), as an alternative to juggling flags.
+///
+/// `S` is the type of the states, `E` is the type of the events fed to the machine, and `C` is the context which
+/// guards and actions operate on (typically some of the widget's fields).
+///
+/// Transitions are checked in the order they're added; the first one leaving the current state whose trigger and
+/// guard are satisfied is taken. Exit and entry actions aren't invoked for transitions from a state to itself.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Drag { Idle, Pressed, Dragging }
+///
+/// let mut machine = StateMachine::new(Drag::Idle)
+///     .on(Drag::Idle, DragEvent::Press, Drag::Pressed)
+///     .transition(
+///         Transition::new(Drag::Pressed, Drag::Dragging, |ev| ev == &DragEvent::Move)
+///             .guard(|ctx: &DragContext, _| ctx.distance() > 4.0),
+///     )
+///     .on(Drag::Dragging, DragEvent::Release, Drag::Idle)
+///     .on(Drag::Pressed, DragEvent::Release, Drag::Idle)
+///     .on_exit(Drag::Dragging, |ctx| ctx.drop_payload());
+///
+/// machine.handle(&mut ctx, &DragEvent::Press);
+/// ```
+pub struct StateMachine<S, E, C = ()> {
+    state: S,
+    transitions: Vec<Transition<S, E, C>>,
+    entry: Vec<(S, Box<dyn FnMut(&mut C)>)>,
+    exit: Vec<(S, Box<dyn FnMut(&mut C)>)>,
+}
+
+impl<S: Clone + PartialEq, E, C> StateMachine<S, E, C> {
+    /// Creates a state machine in the `initial` state, without any transitions.
+    pub fn new(initial: S) -> Self {
+        StateMachine {
+            state: initial,
+            transitions: Vec::new(),
+            entry: Vec::new(),
+            exit: Vec::new(),
+        }
+    }
+
+    /// Adds a transition.
+    pub fn transition(mut self, transition: Transition<S, E, C>) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Adds an unguarded transition from `from` to `to`, taken for events equal to `event`.
+    pub fn on(self, from: S, event: E, to: S) -> Self
+    where
+        E: PartialEq + 'static,
+    {
+        self.transition(Transition::new(from, to, move |ev| ev == &event))
+    }
+
+    /// Invokes `action` whenever `state` is entered.
+    pub fn on_entry(mut self, state: S, action: impl FnMut(&mut C) + 'static) -> Self {
+        self.entry.push((state, Box::new(action)));
+        self
+    }
+
+    /// Invokes `action` whenever `state` is left.
+    pub fn on_exit(mut self, state: S, action: impl FnMut(&mut C) + 'static) -> Self {
+        self.exit.push((state, Box::new(action)));
+        self
+    }
+
+    /// Returns the current state.
+    #[inline]
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns `true` if the current state is `state`.
+    #[inline]
+    pub fn is(&self, state: &S) -> bool {
+        self.state == *state
+    }
+
+    /// Feeds an event to the machine, taking the first applicable transition (if any).
+    /// Returns the previous state if a transition was taken.
+    pub fn handle(&mut self, ctx: &mut C, event: &E) -> Option<S> {
+        let state = &self.state;
+        let transition = self.transitions.iter_mut().find(|transition| {
+            transition.from == *state
+                && (transition.trigger)(event)
+                && transition.guard.as_ref().map_or(true, |guard| guard(ctx, event))
+        })?;
+
+        let (previous, next) = (self.state.clone(), transition.to.clone());
+        let changed = previous != next;
+
+        if changed {
+            for (_, action) in self.exit.iter_mut().filter(|(state, _)| *state == previous) {
+                action(ctx);
+            }
+        }
+
+        if let Some(action) = &mut transition.action {
+            action(ctx, event);
+        }
+
+        self.state = next;
+
+        if changed {
+            let current = &self.state;
+            for (_, action) in self.entry.iter_mut().filter(|(state, _)| state == current) {
+                action(ctx);
+            }
+        }
+
+        Some(previous)
+    }
+
+    /// Moves to `state`, invoking the exit action of the current state and the entry action of `state`.
+    pub fn go_to(&mut self, ctx: &mut C, state: S) {
+        if self.state == state {
+            return;
+        }
+
+        let previous = std::mem::replace(&mut self.state, state);
+        for (_, action) in self.exit.iter_mut().filter(|(state, _)| *state == previous) {
+            action(ctx);
+        }
+        let current = &self.state;
+        for (_, action) in self.entry.iter_mut().filter(|(state, _)| state == current) {
+            action(ctx);
+        }
+    }
+
+    /// Moves to `state` without invoking any actions.
+    #[inline]
+    pub fn reset(&mut self, state: S) {
+        self.state = state;
+    }
+}
+
+impl<S: std::fmt::Debug, E, C> std::fmt::Debug for StateMachine<S, E, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("state", &self.state)
+            .field("transitions", &self.transitions)
+            .finish()
+    }
+}
+
+/// Number of slow widget reports kept until they're taken with `take_slow_widgets`.
+const SLOW_WIDGETS_CAPACITY: usize = 256;
+
+/// Time budgets for a single widget, checked by the watchdog (see `set_widget_budget`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetBudget {
+    /// Time a widget may spend in `update`, excluding the time spent updating its children.
+    pub update: Duration,
+    /// Time a widget may spend in `draw`.
+    pub draw: Duration,
+}
+
+impl Default for WidgetBudget {
+    #[inline]
+    fn default() -> Self {
+        WidgetBudget { update: Duration::from_millis(2), draw: Duration::from_millis(4) }
+    }
+}
+
+/// Which pass of a widget exceeded its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WidgetPhase {
+    Update,
+    Draw,
+}
+
+/// A widget which exceeded its budget during an update or draw pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowWidget {
+    /// Name of the type of the widget (see `WidgetChildren::widget_name`).
+    pub name: &'static str,
+    /// Bounds of the widget at the time, to find it on screen.
+    pub bounds: AbsoluteRect,
+    pub phase: WidgetPhase,
+    /// Time spent by the widget itself, excluding its children.
+    pub elapsed: Duration,
+    /// The budget which was exceeded.
+    pub budget: Duration,
+}
+
+#[derive(Default)]
+struct Watchdog {
+    budget: Option<WidgetBudget>,
+    /// Time spent in watched widgets nested in the one currently being watched.
+    nested: Duration,
+    slow: Vec<SlowWidget>,
+}
+
+thread_local! {
+    static WATCHDOG: RefCell<Watchdog> = RefCell::new(Watchdog::default());
+}
+
+/// Enables the watchdog on the current thread, which times the `update` and `draw` of every widget propagated to by
+/// `invoke_update` and `invoke_draw` against `budget`, or disables it with `None` (the default).
+///
+/// Widgets exceeding the budget are reported through `take_slow_widgets`, which helps find the widget responsible for
+/// dropped frames. Timing every widget has a small cost of its own, so this is best left disabled in release builds.
+pub fn set_widget_budget(budget: Option<WidgetBudget>) {
+    WATCHDOG.with(|watchdog| watchdog.borrow_mut().budget = budget);
+}
+
+/// Returns the budget the watchdog is checking on the current thread, if it's enabled.
+pub fn widget_budget() -> Option<WidgetBudget> {
+    WATCHDOG.with(|watchdog| watchdog.borrow().budget)
+}
+
+/// Returns the widgets which exceeded their budget since this was last called, oldest first.
+///
+/// Only the most recent reports are kept, so this should be called after every pass.
+pub fn take_slow_widgets() -> Vec<SlowWidget> {
+    WATCHDOG.with(|watchdog| std::mem::take(&mut watchdog.borrow_mut().slow))
+}
+
+/// Runs `pass` on `widget`, reporting it if the time spent by the widget itself exceeds `budget`.
+fn watch<W: WidgetChildren + ?Sized>(
+    widget: &mut W,
+    phase: WidgetPhase,
+    budget: Duration,
+    pass: impl FnOnce(&mut W),
+) {
+    let outer = WATCHDOG.with(|watchdog| std::mem::take(&mut watchdog.borrow_mut().nested));
+    let start = Instant::now();
+    pass(widget);
+    let elapsed = start.elapsed();
+
+    WATCHDOG.with(|watchdog| {
+        let mut watchdog = watchdog.borrow_mut();
+        // time spent in watched children is reported against them, not against this widget
+        let nested = std::mem::replace(&mut watchdog.nested, outer + elapsed);
+        let elapsed = elapsed.checked_sub(nested).unwrap_or_default();
+        if elapsed > budget {
+            if watchdog.slow.len() == SLOW_WIDGETS_CAPACITY {
+                watchdog.slow.remove(0);
+            }
+            watchdog.slow.push(SlowWidget {
+                name: widget.widget_name(),
+                bounds: widget.abs_bounds(),
+                phase,
+                elapsed,
+                budget,
+            });
+        }
+    });
+}
+
 lazy_static::lazy_static! {
     // Frame counter used by `invoke_draw`, resets back to 0 after 60 frames.
     // This is used to only clean up `CLIP_LIST` every 60 frames.
@@ -698,14 +2310,29 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
     aux: &mut G,
     clip_list: &mut HashMap<usize, (CommandGroup, CommandGroup)>,
     checked: &mut Option<HashSet<usize>>,
+    inherited_clip: Option<AbsoluteRect>,
 ) {
+    // A clip imposed by a layout also applies to the children of the widget.
+    let layout_clip = match (inherited_clip, widget.layout_clip()) {
+        (Some(a), Some(b)) => Some(
+            a.intersection(&b).unwrap_or_else(|| AbsoluteRect::new(a.origin, Default::default())),
+        ),
+        (a, b) => a.or(b),
+    };
+
     if widget.visibility() != Visibility::Invisible && widget.visibility() != Visibility::None {
         // we're not dereferencing the pointer so it's fine... right?
         #[allow(clippy::cast_ptr_alignment)]
         let id = widget as *const _ as *const usize as _;
         let (clip, restore) =
             clip_list.entry(id).or_insert_with(|| (CommandGroup::new(), CommandGroup::new()));
-        let clip_rect = widget.abs_bounds();
+        let clip_rect = match layout_clip {
+            Some(layout_clip) => widget
+                .abs_bounds()
+                .intersection(&layout_clip)
+                .unwrap_or_else(|| AbsoluteRect::new(layout_clip.origin, Default::default())),
+            None => widget.abs_bounds(),
+        };
         clip.repaint();
         restore.repaint();
         // later on when partial repainting is implemented, this plays an important role in
@@ -726,7 +2353,12 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
             None,
         );
 
-        widget.draw(display, aux);
+        match widget_budget() {
+            Some(budget) => {
+                watch(widget, WidgetPhase::Draw, budget.draw, |widget| widget.draw(display, aux))
+            }
+            None => widget.draw(display, aux),
+        }
 
         restore.push(
             display,
@@ -742,7 +2374,7 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
     }
 
     for child in widget.children_mut() {
-        invoke_draw_impl(child, display, aux, clip_list, checked);
+        invoke_draw_impl(child, display, aux, clip_list, checked, layout_clip);
     }
 }
 
@@ -752,7 +2384,7 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
 ///
 /// Extra processing steps:
 /// - Skip if widget visibility is `Invisible` or `None`.
-/// - Clip to absolute widget bounds.
+/// - Clip to absolute widget bounds, and to the clip imposed by the layout of the widget or of its ancestors.
 /// - Add widget position to auxiliary tracer.
 pub fn invoke_draw<U, G: GraphicalAuxiliary>(
     widget: &mut dyn WidgetChildren<
@@ -770,7 +2402,7 @@ pub fn invoke_draw<U, G: GraphicalAuxiliary>(
     // To do so, gather information on which widget ptrs have been maintained.
     let mut checked = if *draw_counter >= 60 { Some(HashSet::new()) } else { None };
 
-    invoke_draw_impl(widget, display, aux, &mut clip_list, &mut checked);
+    invoke_draw_impl(widget, display, aux, &mut clip_list, &mut checked, None);
 
     // Perform cleanup (checked is only contains a value if on 60th frame).
     if let Some(checked) = checked {
@@ -791,3 +2423,161 @@ pub fn color_from_urgba(r: u8, g: u8, b: u8, a: f32) -> Color {
 pub fn sharp_align(rect: Rect) -> Rect {
     rect.round_in().inflate(0.5, 0.5)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_MODIFIERS: KeyModifiers =
+        KeyModifiers { shift: false, ctrl: false, alt: false, logo: false };
+
+    #[test]
+    fn tab_skips_entries_of_hidden_subtrees() {
+        let chain = FocusChain::new();
+        let before = chain.entry();
+        // inside a container which is hidden, so it isn't updated and its entries aren't refreshed
+        let hidden = [chain.entry(), chain.entry()];
+        let after = chain.entry();
+
+        chain.begin_pass();
+        before.set_focusable(true);
+        after.set_focusable(true);
+
+        before.set_focused(true);
+        assert_eq!(chain.navigate(KeyInput::Tab, NO_MODIFIERS), Some(after.id()));
+        before.set_focused(false);
+        after.set_focused(true);
+        assert_eq!(chain.navigate(KeyInput::Tab, NO_MODIFIERS), Some(before.id()));
+
+        // once the container is shown again, its entries are refreshed along with the rest
+        chain.begin_pass();
+        for entry in &hidden {
+            entry.set_focusable(true);
+        }
+        before.set_focusable(true);
+        after.set_focusable(true);
+
+        after.set_focused(false);
+        before.set_focused(true);
+        assert_eq!(chain.navigate(KeyInput::Tab, NO_MODIFIERS), Some(hidden[0].id()));
+    }
+
+    #[test]
+    fn tab_skips_unfocusable_entries() {
+        let chain = FocusChain::new();
+        let entries = [chain.entry(), chain.entry(), chain.entry()];
+
+        chain.begin_pass();
+        entries[0].set_focusable(true);
+        entries[1].set_focusable(false);
+        entries[2].set_focusable(true);
+
+        entries[0].set_focused(true);
+        assert_eq!(chain.navigate(KeyInput::Tab, NO_MODIFIERS), Some(entries[2].id()));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Drag {
+        Idle,
+        Pressed,
+        Dragging,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum DragEvent {
+        Press,
+        Move(f32),
+        Release,
+    }
+
+    #[derive(Debug, Default)]
+    struct DragContext {
+        distance: f32,
+        log: Vec<&'static str>,
+    }
+
+    fn drag_machine() -> StateMachine<Drag, DragEvent, DragContext> {
+        StateMachine::new(Drag::Idle)
+            .on(Drag::Idle, DragEvent::Press, Drag::Pressed)
+            .transition(
+                Transition::new(Drag::Pressed, Drag::Pressed, |ev: &DragEvent| {
+                    matches!(ev, DragEvent::Move(_))
+                })
+                .guard(|ctx: &DragContext, _| ctx.distance <= 4.0)
+                .action(|ctx, ev| {
+                    if let DragEvent::Move(delta) = ev {
+                        ctx.distance += delta;
+                    }
+                }),
+            )
+            .transition(
+                Transition::new(Drag::Pressed, Drag::Dragging, |ev: &DragEvent| {
+                    matches!(ev, DragEvent::Move(_))
+                })
+                .action(|ctx: &mut DragContext, _| ctx.log.push("start drag")),
+            )
+            .on(Drag::Pressed, DragEvent::Release, Drag::Idle)
+            .on(Drag::Dragging, DragEvent::Release, Drag::Idle)
+            .on_entry(Drag::Dragging, |ctx| ctx.log.push("enter dragging"))
+            .on_exit(Drag::Pressed, |ctx| ctx.log.push("exit pressed"))
+            .on_exit(Drag::Dragging, |ctx| ctx.log.push("exit dragging"))
+    }
+
+    #[test]
+    fn state_machine_guard_rejects_transition() {
+        let mut machine = drag_machine();
+        let mut ctx = DragContext::default();
+
+        machine.handle(&mut ctx, &DragEvent::Press);
+        // within the threshold, the move is absorbed by the guarded self-transition
+        assert_eq!(machine.handle(&mut ctx, &DragEvent::Move(3.0)), Some(Drag::Pressed));
+        assert!(machine.is(&Drag::Pressed));
+        assert_eq!(machine.handle(&mut ctx, &DragEvent::Move(3.0)), Some(Drag::Pressed));
+        assert!(machine.is(&Drag::Pressed));
+        assert!(ctx.log.is_empty());
+
+        // beyond the threshold the guard rejects it, so the next transition is taken
+        assert_eq!(machine.handle(&mut ctx, &DragEvent::Move(3.0)), Some(Drag::Pressed));
+        assert!(machine.is(&Drag::Dragging));
+
+        // no transition leaves `Idle` on a move
+        machine.reset(Drag::Idle);
+        assert_eq!(machine.handle(&mut ctx, &DragEvent::Move(1.0)), None);
+        assert!(machine.is(&Drag::Idle));
+    }
+
+    #[test]
+    fn state_machine_runs_exit_action_then_entry_actions() {
+        let mut machine = drag_machine();
+        let mut ctx = DragContext { distance: 5.0, log: Vec::new() };
+
+        machine.handle(&mut ctx, &DragEvent::Press);
+        machine.handle(&mut ctx, &DragEvent::Move(1.0));
+        assert_eq!(ctx.log, ["exit pressed", "start drag", "enter dragging"]);
+
+        ctx.log.clear();
+        machine.handle(&mut ctx, &DragEvent::Release);
+        assert_eq!(ctx.log, ["exit dragging"]);
+
+        // moving to the current state doesn't invoke any actions
+        ctx.log.clear();
+        machine.go_to(&mut ctx, Drag::Idle);
+        assert!(ctx.log.is_empty());
+        machine.go_to(&mut ctx, Drag::Dragging);
+        assert_eq!(ctx.log, ["enter dragging"]);
+    }
+
+    #[test]
+    fn state_machine_actions_mutate_context() {
+        let mut machine = drag_machine();
+        let mut ctx = DragContext::default();
+
+        machine.handle(&mut ctx, &DragEvent::Press);
+        machine.handle(&mut ctx, &DragEvent::Move(1.5));
+        machine.handle(&mut ctx, &DragEvent::Move(2.0));
+        assert_eq!(ctx.distance, 3.5);
+
+        // self-transitions don't invoke exit or entry actions
+        assert!(ctx.log.is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! Animated raster images, such as animated GIFs.
+
+use {
+    super::ImageResource,
+    reclutch::display::{GraphicsDisplay, Size},
+    std::{rc::Rc, time::Duration},
+};
+
+#[cfg(feature = "extra-widgets")]
+use {
+    crate::error::AnimationError,
+    reclutch::display::{RasterImageFormat, RasterImageInfo, ResourceData, SharedData},
+    std::sync::Arc,
+};
+
+/// Shortest delay of a frame; shorter delays (typically `0`) are shown for `DEFAULT_DELAY` instead, as browsers do.
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// A single frame of an animated image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedFrame {
+    /// Entire image shown for the frame (i.e. already composited over the previous frames).
+    pub image: ImageResource,
+    /// How long the frame is shown for.
+    pub delay: Duration,
+}
+
+struct AnimatedImageInner {
+    frames: Vec<AnimatedFrame>,
+    /// Duration of a single loop of the animation.
+    duration: Duration,
+}
+
+/// An image made of a sequence of frames, which loops.
+///
+/// Like `ImageResource`, every clone shares the same frames, and each frame is only uploaded to the graphics display
+/// once; widgets showing the same animation should be given clones rather than decoding the animation again.
+#[derive(Clone)]
+pub struct AnimatedImage(Rc<AnimatedImageInner>);
+
+impl AnimatedImage {
+    /// Creates an animated image from its frames.
+    pub fn new(frames: Vec<AnimatedFrame>) -> Self {
+        let frames: Vec<_> = frames
+            .into_iter()
+            .map(|frame| AnimatedFrame {
+                delay: if frame.delay < MIN_DELAY { DEFAULT_DELAY } else { frame.delay },
+                ..frame
+            })
+            .collect();
+        let duration = frames.iter().map(|frame| frame.delay).sum();
+        AnimatedImage(Rc::new(AnimatedImageInner { frames, duration }))
+    }
+
+    /// Decodes an animated GIF.
+    ///
+    /// Every frame is composited over the previous frames according to its disposal method, so the frames can be shown
+    /// on their own. Static GIFs yield a single frame.
+    #[cfg(feature = "extra-widgets")]
+    pub fn decode_gif(data: &[u8]) -> Result<Self, AnimationError> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(data)?;
+        let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+
+        let mut canvas = vec![0u8; width * height * 4];
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame()? {
+            let previous = if frame.dispose == gif::DisposalMethod::Previous {
+                Some(canvas.clone())
+            } else {
+                None
+            };
+
+            // the frame may only cover part of the canvas
+            let (left, top) = (frame.left as usize, frame.top as usize);
+            let frame_width = frame.width as usize;
+            for y in 0..(frame.height as usize).min(height.saturating_sub(top)) {
+                for x in 0..frame_width.min(width.saturating_sub(left)) {
+                    let source = (y * frame_width + x) * 4;
+                    let pixel = match frame.buffer.get(source..source + 4) {
+                        Some(pixel) => pixel,
+                        None => continue,
+                    };
+                    // transparent pixels let the previous frames show through
+                    if pixel[3] != 0 {
+                        let target = ((top + y) * width + left + x) * 4;
+                        canvas[target..target + 4].copy_from_slice(pixel);
+                    }
+                }
+            }
+
+            frames.push(AnimatedFrame {
+                image: ImageResource::raw(
+                    ResourceData::Data(SharedData::RefCount(Arc::new(canvas.clone()))),
+                    RasterImageInfo {
+                        size: (width as u32, height as u32),
+                        format: RasterImageFormat::Rgba8,
+                    },
+                ),
+                // GIF delays are in hundredths of a second
+                delay: Duration::from_millis(frame.delay as u64 * 10),
+            });
+
+            match frame.dispose {
+                gif::DisposalMethod::Background => {
+                    for y in top..(top + frame.height as usize).min(height) {
+                        let row = y * width;
+                        let end = (left + frame_width).min(width);
+                        if left < end {
+                            for byte in &mut canvas[(row + left) * 4..(row + end) * 4] {
+                                *byte = 0;
+                            }
+                        }
+                    }
+                }
+                gif::DisposalMethod::Previous => {
+                    if let Some(previous) = previous {
+                        canvas = previous;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if frames.is_empty() {
+            Err(AnimationError::NoFrames)
+        } else {
+            Ok(AnimatedImage::new(frames))
+        }
+    }
+
+    /// Returns the frames of the animation.
+    #[inline]
+    pub fn frames(&self) -> &[AnimatedFrame] {
+        &self.0.frames
+    }
+
+    /// Returns the duration of a single loop of the animation.
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.0.duration
+    }
+
+    /// Returns the size of the frames in pixels, if known.
+    pub fn size(&self) -> Option<Size> {
+        self.0.frames.first().and_then(|frame| frame.image.size())
+    }
+
+    /// Returns the index of the frame shown `elapsed` after the animation started, looping the animation.
+    pub fn frame_at(&self, elapsed: Duration) -> Option<usize> {
+        if self.0.frames.is_empty() {
+            return None;
+        }
+
+        let duration = self.0.duration.as_secs_f64();
+        let mut position = if duration > 0.0 { elapsed.as_secs_f64() % duration } else { 0.0 };
+        for (i, frame) in self.0.frames.iter().enumerate() {
+            let delay = frame.delay.as_secs_f64();
+            if position < delay {
+                return Some(i);
+            }
+            position -= delay;
+        }
+        Some(self.0.frames.len() - 1)
+    }
+
+    /// Removes the uploaded frames from `display`, for all the clones of `self`.
+    pub fn unload(&self, display: &mut dyn GraphicsDisplay) {
+        for frame in &self.0.frames {
+            frame.image.unload(display);
+        }
+    }
+}
+
+impl PartialEq for AnimatedImage {
+    fn eq(&self, other: &AnimatedImage) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for AnimatedImage {}
+
+impl std::fmt::Debug for AnimatedImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AnimatedImage")
+            .field("frames", &self.0.frames.len())
+            .field("duration", &self.0.duration)
+            .finish()
+    }
+}
@@ -0,0 +1,193 @@
+//! Auditing of the color contrast of themes, against the WCAG 2 contrast requirements.
+//!
+//! A theme declares the pairs of colors its painters draw over each other through `Theme::color_pairs` (by default,
+//! the pairs of its [`ColorScheme`](../struct.ColorScheme.html)), then `audit` checks the contrast ratio of each
+//! pair against the minimum for the pair's usage.
+
+use {
+    super::{ColorScheme, Theme},
+    reclutch::display::Color,
+    std::fmt,
+};
+
+/// How a color is used over another, which determines the minimum contrast required.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorUsage {
+    /// Body text.
+    Text,
+    /// Text at least 18pt, or 14pt and bold.
+    LargeText,
+    /// Graphics required to understand the interface, such as icons, borders of controls and focus indicators.
+    Graphic,
+}
+
+/// WCAG conformance level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContrastLevel {
+    AA,
+    AAA,
+}
+
+impl ColorUsage {
+    /// Returns the minimum contrast ratio required for this usage at a conformance level.
+    pub fn minimum_ratio(self, level: ContrastLevel) -> f32 {
+        match (self, level) {
+            (ColorUsage::Text, ContrastLevel::AA) => 4.5,
+            (ColorUsage::Text, ContrastLevel::AAA) => 7.0,
+            (ColorUsage::LargeText, ContrastLevel::AA) => 3.0,
+            (ColorUsage::LargeText, ContrastLevel::AAA) => 4.5,
+            (ColorUsage::Graphic, _) => 3.0,
+        }
+    }
+}
+
+/// Color drawn over another color by a theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorPair {
+    /// Describes where the pair is used, e.g. `"button text"`.
+    pub name: String,
+    pub foreground: Color,
+    /// Color behind `foreground`; its opacity is ignored.
+    pub background: Color,
+    pub usage: ColorUsage,
+}
+
+impl ColorPair {
+    pub fn new(
+        name: impl Into<String>,
+        foreground: Color,
+        background: Color,
+        usage: ColorUsage,
+    ) -> Self {
+        ColorPair { name: name.into(), foreground, background, usage }
+    }
+
+    /// Returns the contrast ratio of the pair, with the foreground composited over the background.
+    #[inline]
+    pub fn contrast_ratio(&self) -> f32 {
+        contrast_ratio(self.foreground, self.background)
+    }
+}
+
+/// Color pair with insufficient contrast, as reported by `audit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastViolation {
+    pub pair: ColorPair,
+    /// Contrast ratio of the pair.
+    pub ratio: f32,
+    /// Minimum contrast ratio required.
+    pub minimum: f32,
+}
+
+impl fmt::Display for ContrastViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: contrast ratio {:.2}:1, below {:.1}:1",
+            self.pair.name, self.ratio, self.minimum
+        )
+    }
+}
+
+/// Composites a (possibly translucent) color over an opaque background.
+pub fn composite(color: Color, background: Color) -> Color {
+    let a = color.alpha;
+    Color::new(
+        color.red * a + background.red * (1.0 - a),
+        color.green * a + background.green * (1.0 - a),
+        color.blue * a + background.blue * (1.0 - a),
+        1.0,
+    )
+}
+
+/// Returns the relative luminance of a color, as defined by WCAG 2, ignoring its opacity.
+pub fn relative_luminance(color: Color) -> f32 {
+    let channel = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.red) + 0.7152 * channel(color.green) + 0.0722 * channel(color.blue)
+}
+
+/// Returns the contrast ratio of a color over a background, from 1 (no contrast) to 21 (black on white).
+///
+/// The foreground is composited over the background, whose opacity is ignored.
+pub fn contrast_ratio(foreground: Color, background: Color) -> f32 {
+    let a = relative_luminance(composite(foreground, background));
+    let b = relative_luminance(background);
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Returns the pairs of colors of a color scheme which are meant to be drawn over each other.
+pub fn scheme_pairs(scheme: &ColorScheme) -> Vec<ColorPair> {
+    let focus = composite(scheme.focus, scheme.background);
+    vec![
+        ColorPair::new(
+            "text over outset controls",
+            scheme.over_control_outset,
+            scheme.control_outset,
+            ColorUsage::Text,
+        ),
+        ColorPair::new(
+            "text over inset controls",
+            scheme.over_control_inset,
+            scheme.control_inset,
+            ColorUsage::Text,
+        ),
+        ColorPair::new("text over primary", scheme.over_primary, scheme.primary, ColorUsage::Text),
+        ColorPair::new("text over error", scheme.over_error, scheme.error, ColorUsage::Text),
+        ColorPair::new("text over focus", scheme.over_focus, focus, ColorUsage::Text),
+        ColorPair::new(
+            "selected text",
+            scheme.over_text_selection,
+            scheme.text_selection,
+            ColorUsage::Text,
+        ),
+        ColorPair::new(
+            "selected list items",
+            scheme.over_list_selection,
+            scheme.list_selection,
+            ColorUsage::Text,
+        ),
+        ColorPair::new(
+            "selected table rows",
+            scheme.over_table_selection,
+            scheme.table_selection,
+            ColorUsage::Text,
+        ),
+        ColorPair::new(
+            "inactive selections",
+            scheme.over_inactive_selection,
+            scheme.inactive_selection,
+            ColorUsage::Text,
+        ),
+        ColorPair::new("error over background", scheme.error, scheme.background, ColorUsage::Text),
+        ColorPair::new(
+            "primary over background",
+            scheme.primary,
+            scheme.background,
+            ColorUsage::Graphic,
+        ),
+        ColorPair::new("focus indicator", scheme.focus, scheme.background, ColorUsage::Graphic),
+    ]
+}
+
+/// Checks every color pair declared by a theme, returning the pairs with insufficient contrast.
+pub fn audit(theme: &dyn Theme, level: ContrastLevel) -> Vec<ContrastViolation> {
+    theme
+        .color_pairs()
+        .into_iter()
+        .filter_map(|pair| {
+            let ratio = pair.contrast_ratio();
+            let minimum = pair.usage.minimum_ratio(level);
+            if ratio < minimum {
+                Some(ContrastViolation { pair, ratio, minimum })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
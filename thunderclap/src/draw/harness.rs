@@ -0,0 +1,302 @@
+//! Systematic checking of painters across combinations of states.
+//!
+//! A [`StateMatrix`](struct.StateMatrix.html) is built from a base state and sets of variations (e.g. hovered,
+//! pressed and focused, crossed with checked and unchecked), then `check_painter` draws every combination, checking
+//! that each draws something and stays within the painter's `paint_hint`. The drawings are also arranged in a
+//! contact sheet, which can be rendered to an image to catch visual theme regressions.
+
+use {
+    super::{state::InteractionState, Painter},
+    crate::geom::*,
+    reclutch::display::{
+        Color, DisplayCommand, DisplayListBuilder, GraphicsDisplayPaint, Rect, Size, Vector,
+    },
+    std::fmt,
+};
+
+/// Tolerance for painting outside of the paint hint, to allow for anti-aliasing.
+const PAINT_TOLERANCE: f32 = 0.5;
+
+/// Labelled combinations of a state, to be drawn by a painter.
+#[derive(Debug, Clone)]
+pub struct StateMatrix<S> {
+    cases: Vec<(String, S)>,
+}
+
+impl<S: Clone> StateMatrix<S> {
+    /// Creates a matrix with a single case.
+    pub fn new(label: impl Into<String>, state: S) -> Self {
+        StateMatrix { cases: vec![(label.into(), state)] }
+    }
+
+    /// Crosses every case with a set of variations, each of which modifies a copy of the case.
+    /// Labels are joined with `" / "`, e.g. `"hovered / checked"`.
+    pub fn vary(self, variations: &[(&str, &dyn Fn(&mut S))]) -> Self {
+        let mut cases = Vec::with_capacity(self.cases.len() * variations.len());
+        for (label, state) in self.cases {
+            for (name, vary) in variations {
+                let mut state = state.clone();
+                vary(&mut state);
+                cases.push((format!("{} / {}", label, name), state));
+            }
+        }
+        StateMatrix { cases }
+    }
+
+    /// Crosses every case with the normal, hovered, pressed, focused and disabled interaction states.
+    ///
+    /// `interaction` and `disabled` return the interaction state and the disabled flag of a state (typically its
+    /// `interaction` and `data.disabled` fields); only the disabled case is disabled.
+    pub fn vary_interaction(
+        self,
+        interaction: impl Fn(&mut S) -> &mut InteractionState,
+        disabled: impl Fn(&mut S) -> &mut bool,
+    ) -> Self {
+        let (interaction, is_disabled) = (&interaction, &disabled);
+        let set = |flags: InteractionState, off: bool| {
+            move |state: &mut S| {
+                *interaction(state) = flags;
+                *is_disabled(state) = off;
+            }
+        };
+        let (normal, hovered, pressed, focused, disabled) = (
+            set(InteractionState::empty(), false),
+            set(InteractionState::HOVERED, false),
+            set(InteractionState::HOVERED | InteractionState::PRESSED, false),
+            set(InteractionState::FOCUSED, false),
+            set(InteractionState::empty(), true),
+        );
+        self.vary(&[
+            ("normal", &normal),
+            ("hovered", &hovered),
+            ("pressed", &pressed),
+            ("focused", &focused),
+            ("disabled", &disabled),
+        ])
+    }
+
+    /// Adds a single case.
+    pub fn case(mut self, label: impl Into<String>, state: S) -> Self {
+        self.cases.push((label.into(), state));
+        self
+    }
+
+    /// Returns the labelled cases.
+    #[inline]
+    pub fn cases(&self) -> &[(String, S)] {
+        &self.cases
+    }
+}
+
+/// An invariant broken by a painter when drawing a case.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// Nothing was drawn.
+    Empty,
+    /// Something was drawn outside of the paint hint.
+    OutsidePaintHint { painted: Rect, paint_hint: Rect },
+    /// The size hint is negative or not finite.
+    InvalidSizeHint(Size),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Empty => write!(f, "nothing was drawn"),
+            Violation::OutsidePaintHint { painted, paint_hint } => {
+                write!(f, "painted {:?}, outside of paint hint {:?}", painted, paint_hint)
+            }
+            Violation::InvalidSizeHint(size) => write!(f, "invalid size hint {:?}", size),
+        }
+    }
+}
+
+/// Result of drawing a single case.
+#[derive(Debug, Clone)]
+pub struct CaseReport {
+    pub label: String,
+    /// Bounds the case was drawn in.
+    pub rect: AbsoluteRect,
+    /// Bounds of everything drawn, if anything was drawn.
+    pub painted: Option<Rect>,
+    pub violations: Vec<Violation>,
+    /// Commands drawn by the painter.
+    pub commands: Vec<DisplayCommand>,
+}
+
+/// Layout of the contact sheet of a [`PainterReport`](struct.PainterReport.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactSheet {
+    /// Number of cases in each row.
+    pub columns: usize,
+    /// Space around each case.
+    pub padding: f32,
+    pub background: Color,
+    /// Color of the outline of cases which broke an invariant.
+    pub violation: Color,
+}
+
+impl Default for ContactSheet {
+    fn default() -> Self {
+        ContactSheet {
+            columns: 4,
+            padding: 16.0,
+            background: Color::new(1.0, 1.0, 1.0, 1.0),
+            violation: Color::new(1.0, 0.0, 0.0, 1.0),
+        }
+    }
+}
+
+/// Result of drawing every case of a state matrix.
+#[derive(Debug, Clone)]
+pub struct PainterReport {
+    pub cases: Vec<CaseReport>,
+    /// Every case drawn in a grid, in the order of the matrix, ready to be pushed into a display.
+    /// Cases which broke an invariant are outlined.
+    pub sheet: Vec<DisplayCommand>,
+    /// Size of the contact sheet.
+    pub sheet_size: Size,
+}
+
+impl PainterReport {
+    /// Returns `true` if no case broke an invariant.
+    pub fn is_ok(&self) -> bool {
+        self.cases.iter().all(|case| case.violations.is_empty())
+    }
+
+    /// Returns the labels of the cases which broke an invariant, with the broken invariants.
+    pub fn violations(&self) -> Vec<(&str, &Violation)> {
+        self.cases
+            .iter()
+            .flat_map(|case| {
+                case.violations.iter().map(move |violation| (case.label.as_str(), violation))
+            })
+            .collect()
+    }
+
+    /// Panics with a list of the broken invariants, if any; intended for use in tests.
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            let violations: Vec<_> = self
+                .violations()
+                .into_iter()
+                .map(|(label, violation)| format!("  {}: {}", label, violation))
+                .collect();
+            panic!("painter broke invariants:\n{}", violations.join("\n"));
+        }
+    }
+}
+
+/// Returns the bounds of everything drawn by a list of commands (ignoring clips), if anything is drawn.
+pub fn painted_bounds(commands: &[DisplayCommand]) -> Option<Rect> {
+    commands
+        .iter()
+        .filter(|command| match command {
+            DisplayCommand::Item(..) | DisplayCommand::BackdropFilter(..) => true,
+            _ => false,
+        })
+        .filter_map(DisplayCommand::bounds)
+        .fold(None, |bounds: Option<Rect>, rect| {
+            Some(bounds.map_or(rect, |bounds| bounds.union(&rect)))
+        })
+}
+
+/// Draws every case of `matrix` with `painter`, checking that each case draws something within the paint hint
+/// and has a valid size hint, and arranges the drawings in a contact sheet.
+///
+/// `rect` returns the bounds a state is drawn in (typically its `rect` field).
+pub fn check_painter<S: Clone>(
+    painter: &mut dyn Painter<S>,
+    matrix: &StateMatrix<S>,
+    rect: impl Fn(&S) -> AbsoluteRect,
+    sheet: ContactSheet,
+) -> PainterReport {
+    let cases: Vec<CaseReport> = matrix
+        .cases()
+        .iter()
+        .map(|(label, state)| {
+            let rect = rect(state);
+            let size_hint = painter.size_hint(state.clone());
+            let commands = painter.draw(state.clone());
+            let painted = painted_bounds(&commands);
+            let paint_hint: Rect = painter.paint_hint(rect.cast_unit()).cast_unit();
+
+            let mut violations = Vec::new();
+            if !(size_hint.width >= 0.0 && size_hint.height >= 0.0)
+                || !size_hint.width.is_finite()
+                || !size_hint.height.is_finite()
+            {
+                violations.push(Violation::InvalidSizeHint(size_hint));
+            }
+            match painted {
+                None => violations.push(Violation::Empty),
+                Some(painted) => {
+                    if !paint_hint.inflate(PAINT_TOLERANCE, PAINT_TOLERANCE).contains_rect(&painted)
+                    {
+                        violations.push(Violation::OutsidePaintHint { painted, paint_hint });
+                    }
+                }
+            }
+
+            CaseReport { label: label.clone(), rect, painted, violations, commands }
+        })
+        .collect();
+
+    let (sheet, sheet_size) = contact_sheet(&cases, painter, sheet);
+    PainterReport { cases, sheet, sheet_size }
+}
+
+/// Arranges the cases in a grid of equally sized cells, each fitting the largest paint hint.
+fn contact_sheet<S>(
+    cases: &[CaseReport],
+    painter: &dyn Painter<S>,
+    sheet: ContactSheet,
+) -> (Vec<DisplayCommand>, Size) {
+    let paint_hints: Vec<Rect> =
+        cases.iter().map(|case| painter.paint_hint(case.rect.cast_unit()).cast_unit()).collect();
+    let cell = paint_hints.iter().fold(Size::zero(), |cell, hint| {
+        Size::new(cell.width.max(hint.size.width), cell.height.max(hint.size.height))
+    }) + Size::new(sheet.padding, sheet.padding) * 2.0;
+
+    let columns = sheet.columns.max(1).min(cases.len().max(1));
+    let rows = (cases.len() + columns - 1) / columns;
+    let size = Size::new(cell.width * columns as f32, cell.height * rows as f32);
+
+    let mut builder = DisplayListBuilder::new();
+    builder.push_rectangle(
+        Rect::new(Default::default(), size),
+        GraphicsDisplayPaint::Fill(sheet.background.into()),
+        None,
+    );
+    let mut commands = builder.build();
+
+    for (i, (case, hint)) in cases.iter().zip(&paint_hints).enumerate() {
+        let origin = Vector::new(
+            (i % columns) as f32 * cell.width + sheet.padding,
+            (i / columns) as f32 * cell.height + sheet.padding,
+        );
+        // Moves the paint hint of the case to the top-left of its cell.
+        let offset = origin - hint.origin.to_vector();
+
+        commands.push(DisplayCommand::Save);
+        commands.push(DisplayCommand::Translate(offset));
+        commands.extend(case.commands.iter().cloned());
+        commands.push(DisplayCommand::Restore);
+
+        if !case.violations.is_empty() {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_rectangle(
+                hint.translate(offset).inflate(2.0, 2.0),
+                GraphicsDisplayPaint::Stroke(reclutch::display::GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: sheet.violation.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+            commands.extend(builder.build());
+        }
+    }
+
+    (commands, size)
+}
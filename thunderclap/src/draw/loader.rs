@@ -0,0 +1,411 @@
+//! Asynchronous loading and caching of images from files and remote servers.
+//!
+//! An [`ImageLoader`](struct.ImageLoader.html) fetches encoded images on worker threads through its providers, and
+//! keeps the images in a memory cache, evicting the least recently used once the cache exceeds its budget. Widgets
+//! show a loaded image through an [`ImageHandle`](struct.ImageHandle.html), showing a placeholder until it resolves.
+
+use {
+    super::ImageResource,
+    crate::error::ImageLoadError,
+    reclutch::display::{GraphicsDisplay, ResourceData, SharedData},
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        fmt, fs, io,
+        path::PathBuf,
+        rc::Rc,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+    },
+};
+
+/// Location of an image to load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ImageSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl fmt::Display for ImageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageSource::File(path) => write!(f, "{}", path.display()),
+            ImageSource::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Fetches encoded images for an image loader.
+///
+/// Providers are shared by the worker threads of the loader, so `fetch` may block.
+pub trait ImageProvider: Send + Sync {
+    /// Returns `true` if the provider can fetch the image at `source`.
+    fn provides(&self, source: &ImageSource) -> bool;
+    /// Fetches the encoded image (e.g. PNG or JPEG) at `source`.
+    fn fetch(&self, source: &ImageSource) -> Result<Vec<u8>, ImageLoadError>;
+}
+
+/// Provides images from files on the local filesystem.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileImageProvider;
+
+impl ImageProvider for FileImageProvider {
+    fn provides(&self, source: &ImageSource) -> bool {
+        if let ImageSource::File(_) = source {
+            true
+        } else {
+            false
+        }
+    }
+
+    fn fetch(&self, source: &ImageSource) -> Result<Vec<u8>, ImageLoadError> {
+        let path = match source {
+            ImageSource::File(path) => path,
+            _ => return Err(ImageLoadError::Unsupported(source.to_string())),
+        };
+        fs::read(path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ImageLoadError::NotFound(path.display().to_string()),
+            _ => ImageLoadError::Fetch(err.to_string()),
+        })
+    }
+}
+
+/// Provides images from `http://` and `https://` URLs by invoking a (blocking) download function.
+///
+/// The download function can use any HTTP client to download the encoded image, for example:
+/// ```ignore
+/// HttpImageProvider::new(|url| download(url).map_err(|err| ImageLoadError::Fetch(err.to_string())))
+/// ```
+pub struct HttpImageProvider {
+    download: Box<dyn Fn(&str) -> Result<Vec<u8>, ImageLoadError> + Send + Sync>,
+}
+
+impl HttpImageProvider {
+    /// Creates a provider which invokes `download` to obtain the encoded image at a URL.
+    pub fn new<F>(download: F) -> Self
+    where
+        F: Fn(&str) -> Result<Vec<u8>, ImageLoadError> + Send + Sync + 'static,
+    {
+        HttpImageProvider { download: Box::new(download) }
+    }
+}
+
+impl ImageProvider for HttpImageProvider {
+    fn provides(&self, source: &ImageSource) -> bool {
+        match source {
+            ImageSource::Url(url) => url.starts_with("http://") || url.starts_with("https://"),
+            _ => false,
+        }
+    }
+
+    fn fetch(&self, source: &ImageSource) -> Result<Vec<u8>, ImageLoadError> {
+        match source {
+            ImageSource::Url(url) => (self.download)(url),
+            _ => Err(ImageLoadError::Unsupported(source.to_string())),
+        }
+    }
+}
+
+impl fmt::Debug for HttpImageProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpImageProvider").finish()
+    }
+}
+
+/// Status of an image requested from an image loader.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadStatus {
+    Loading,
+    Ready(ImageResource),
+    Failed(ImageLoadError),
+}
+
+struct CacheEntry {
+    status: LoadStatus,
+    /// Estimated memory used by the image once decoded.
+    bytes: usize,
+    /// Value of the loader clock when the image was last requested or shown.
+    last_used: u64,
+}
+
+struct LoaderInner {
+    requests: mpsc::Sender<ImageSource>,
+    results: mpsc::Receiver<(ImageSource, Result<Vec<u8>, ImageLoadError>)>,
+    cache: HashMap<ImageSource, CacheEntry>,
+    /// Memory the cached images may use before the least recently used are evicted.
+    budget: usize,
+    used: usize,
+    clock: u64,
+    /// Images removed from the cache which may still be uploaded to a display.
+    evicted: Vec<ImageResource>,
+}
+
+impl LoaderInner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn request(&mut self, source: ImageSource) {
+        let last_used = self.tick();
+        self.cache.insert(
+            source.clone(),
+            CacheEntry { status: LoadStatus::Loading, bytes: 0, last_used },
+        );
+        // Sending only fails if all the workers have panicked.
+        let _ = self.requests.send(source);
+    }
+
+    /// Moves the images which finished loading (or failed to) into the cache.
+    fn poll(&mut self) {
+        let results: Vec<_> = self.results.try_iter().collect();
+        for (source, result) in results {
+            let (status, bytes) = match result {
+                Ok(data) => {
+                    let encoded = data.len();
+                    let image = ImageResource::encoded(ResourceData::Data(SharedData::RefCount(
+                        Arc::new(data),
+                    )));
+                    let bytes = image
+                        .size()
+                        .map_or(encoded, |size| size.width as usize * size.height as usize * 4);
+                    (LoadStatus::Ready(image), bytes)
+                }
+                Err(err) => (LoadStatus::Failed(err), 0),
+            };
+
+            let last_used = self.tick();
+            if let Some(old) =
+                self.cache.insert(source.clone(), CacheEntry { status, bytes, last_used })
+            {
+                self.discard(old);
+            }
+            self.used += bytes;
+            self.evict(&source);
+        }
+    }
+
+    /// Evicts the least recently used images until the cache is within budget, sparing `keep`.
+    fn evict(&mut self, keep: &ImageSource) {
+        while self.used > self.budget {
+            let oldest = self
+                .cache
+                .iter()
+                .filter(|(source, entry)| *source != keep && matches_ready(&entry.status))
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(source, _)| source.clone());
+            match oldest.and_then(|source| self.cache.remove(&source)) {
+                Some(entry) => self.discard(entry),
+                None => break,
+            }
+        }
+    }
+
+    fn discard(&mut self, entry: CacheEntry) {
+        self.used -= entry.bytes;
+        if let LoadStatus::Ready(image) = entry.status {
+            self.evicted.push(image);
+        }
+    }
+
+    fn unload_evicted(&mut self, display: &mut dyn GraphicsDisplay) {
+        // Images still held elsewhere (e.g. by a handle) are unloaded once they're no longer shared.
+        self.evicted.retain(|image| {
+            if Rc::strong_count(&image.0) == 1 {
+                image.unload(display);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+fn matches_ready(status: &LoadStatus) -> bool {
+    if let LoadStatus::Ready(_) = status {
+        true
+    } else {
+        false
+    }
+}
+
+/// Loads images on a pool of worker threads, and caches them in memory.
+///
+/// Images are cached by source, so requesting the same image twice only loads it once. Once the estimated memory used
+/// by the decoded images exceeds the budget, the least recently used images are evicted from the cache; images which
+/// are still shown through a handle remain loaded until the handle is dropped.
+///
+/// The provider of each image is the first which `provides` its source. Encoded images are fetched on the worker
+/// threads; decoding is left to the graphics display, which decodes images as they're uploaded.
+///
+/// Cloning a loader shares the same cache and workers, which exit once the loader and all of its handles are dropped.
+#[derive(Clone)]
+pub struct ImageLoader(Rc<RefCell<LoaderInner>>);
+
+impl ImageLoader {
+    /// Creates a loader with `threads` worker threads fetching images through `providers`, caching up to `budget`
+    /// bytes of decoded images.
+    pub fn new(threads: usize, budget: usize, providers: Vec<Box<dyn ImageProvider>>) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<ImageSource>();
+        let (result_sender, results) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let providers = Arc::new(providers);
+
+        for _ in 0..threads.max(1) {
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            let providers = providers.clone();
+            thread::spawn(move || loop {
+                // The lock is only held while waiting for a request, so other workers can fetch meanwhile.
+                let source = match request_receiver.lock().unwrap().recv() {
+                    Ok(source) => source,
+                    Err(_) => break,
+                };
+                let result = match providers.iter().find(|provider| provider.provides(&source)) {
+                    Some(provider) => provider.fetch(&source),
+                    None => Err(ImageLoadError::Unsupported(source.to_string())),
+                };
+                if result_sender.send((source, result)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        ImageLoader(Rc::new(RefCell::new(LoaderInner {
+            requests: request_sender,
+            results,
+            cache: HashMap::new(),
+            budget,
+            used: 0,
+            clock: 0,
+            evicted: Vec::new(),
+        })))
+    }
+
+    /// Returns a handle to the image at `source`, starting to load it if it isn't cached.
+    pub fn load(&self, source: ImageSource) -> ImageHandle {
+        let mut inner = self.0.borrow_mut();
+        let clock = inner.tick();
+        match inner.cache.get_mut(&source) {
+            Some(entry) => entry.last_used = clock,
+            None => inner.request(source.clone()),
+        }
+
+        ImageHandle(Rc::new(HandleInner {
+            source,
+            loader: self.0.clone(),
+            status: RefCell::new(LoadStatus::Loading),
+        }))
+    }
+
+    /// Removes the image at `source` from the cache (e.g. if the file has changed), so it's loaded again when next
+    /// requested. Existing handles keep the image they've resolved.
+    pub fn forget(&self, source: &ImageSource) {
+        let mut inner = self.0.borrow_mut();
+        if let Some(entry) = inner.cache.remove(source) {
+            inner.discard(entry);
+        }
+    }
+
+    /// Returns the estimated memory used by the cached images, in bytes.
+    pub fn used(&self) -> usize {
+        self.0.borrow().used
+    }
+
+    /// Removes the images evicted from the cache from `display`, once they're no longer shown.
+    pub fn unload_evicted(&self, display: &mut dyn GraphicsDisplay) {
+        self.0.borrow_mut().unload_evicted(display);
+    }
+}
+
+impl fmt::Debug for ImageLoader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.0.borrow();
+        f.debug_struct("ImageLoader")
+            .field("cached", &inner.cache.len())
+            .field("used", &inner.used)
+            .field("budget", &inner.budget)
+            .finish()
+    }
+}
+
+struct HandleInner {
+    source: ImageSource,
+    loader: Rc<RefCell<LoaderInner>>,
+    /// Status as of the last poll; once resolved, the handle keeps the image even if it's evicted from the cache.
+    status: RefCell<LoadStatus>,
+}
+
+/// Handle to an image requested from an [`ImageLoader`](struct.ImageLoader.html).
+///
+/// The handle resolves once the image finishes loading (or fails to), as observed by `status`.
+#[derive(Clone)]
+pub struct ImageHandle(Rc<HandleInner>);
+
+impl ImageHandle {
+    /// Returns the source of the image.
+    pub fn source(&self) -> &ImageSource {
+        &self.0.source
+    }
+
+    /// Returns the status of the image, receiving any images which have finished loading.
+    pub fn status(&self) -> LoadStatus {
+        if *self.0.status.borrow() == LoadStatus::Loading {
+            let mut loader = self.0.loader.borrow_mut();
+            loader.poll();
+            let clock = loader.tick();
+            let status = match loader.cache.get_mut(&self.0.source) {
+                Some(entry) => {
+                    entry.last_used = clock;
+                    entry.status.clone()
+                }
+                // forgotten before it resolved
+                None => {
+                    loader.request(self.0.source.clone());
+                    LoadStatus::Loading
+                }
+            };
+            *self.0.status.borrow_mut() = status;
+        }
+
+        self.0.status.borrow().clone()
+    }
+
+    /// Returns the image, if it has loaded.
+    pub fn image(&self) -> Option<ImageResource> {
+        match self.status() {
+            LoadStatus::Ready(image) => Some(image),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the image is still loading.
+    ///
+    /// The loader doesn't wake the app once an image is loaded, so widgets showing the image should request animation
+    /// frames while it's loading (see `base::UpdateAuxiliary::request_animation_frame`).
+    pub fn is_loading(&self) -> bool {
+        self.status() == LoadStatus::Loading
+    }
+
+    /// Removes the images evicted from the cache of the loader from `display`, once they're no longer shown.
+    pub fn unload_evicted(&self, display: &mut dyn GraphicsDisplay) {
+        self.0.loader.borrow_mut().unload_evicted(display);
+    }
+}
+
+impl PartialEq for ImageHandle {
+    fn eq(&self, other: &ImageHandle) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ImageHandle {}
+
+impl fmt::Debug for ImageHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ImageHandle")
+            .field("source", &self.0.source)
+            .field("status", &*self.0.status.borrow())
+            .finish()
+    }
+}
@@ -1,10 +1,20 @@
 //! Simple theme framework based on Flutter.
 
+pub mod animated;
+pub mod audit;
+pub mod harness;
+pub mod loader;
 pub mod state;
+pub mod style;
 
 use {
     crate::{base, geom::*},
-    reclutch::display::{Color, DisplayCommand, FontInfo, ResourceReference, Size},
+    reclutch::display::{
+        Color, DisplayClip, DisplayCommand, DisplayListBuilder, Filter, FontInfo, GraphicsDisplay,
+        GraphicsDisplayPaint, ImageData, Point, RasterImageInfo, Rect, ResourceData,
+        ResourceDescriptor, ResourceReference, SharedData, Size, Vector,
+    },
+    std::{cell::Cell, rc::Rc},
 };
 
 /// Implemented by types which are capable of changing themes.
@@ -65,11 +75,176 @@ pub fn weaken(color: Color, amount: f32, contrast: ThemeContrast) -> Color {
     }
 }
 
+/// Mixes two colors, from `a` (`factor` of 0) to `b` (`factor` of 1).
+pub fn mix(a: Color, b: Color, factor: f32) -> Color {
+    use reclutch::palette::Mix;
+    Color::from_linear(a.into_linear().mix(&b.into_linear(), factor))
+}
+
 /// Returns the color with a different opacity.
 pub fn with_opacity(color: Color, opacity: f32) -> Color {
     Color::new(color.red, color.green, color.blue, opacity)
 }
 
+/// Pushes the soft shadow cast by a rounded rectangle raised `elevation` logical pixels above the surface beneath it.
+/// Higher elevations cast larger, more diffuse shadows, offset further downwards.
+///
+/// The shadow should be pushed before the rectangle itself, and extends up to `shadow_extent(elevation)` beyond it.
+pub fn push_drop_shadow(
+    builder: &mut DisplayListBuilder,
+    rect: Rect,
+    radii: [f32; 4],
+    elevation: f32,
+    color: Color,
+) {
+    if elevation <= 0.0 {
+        return;
+    }
+
+    builder.push_round_rectangle(
+        rect.translate(Vector::new(0.0, elevation / 2.0)),
+        radii,
+        GraphicsDisplayPaint::Fill(color.into()),
+        Some(Filter::Blur(elevation * 1.25, elevation * 1.25)),
+    );
+}
+
+/// Returns how far the shadow pushed by `push_drop_shadow` extends beyond the rectangle casting it, at most.
+#[inline]
+pub fn shadow_extent(elevation: f32) -> f32 {
+    elevation.max(0.0) * 3.0
+}
+
+/// Returns `commands` clipped to the largest circle centered within `rect`, such as to crop an image to a circle.
+pub fn clip_circle(rect: Rect, commands: Vec<DisplayCommand>) -> Vec<DisplayCommand> {
+    let diameter = rect.size.width.min(rect.size.height);
+    let circle = Rect::new(
+        rect.center() - Vector::new(diameter, diameter) / 2.0,
+        Size::new(diameter, diameter),
+    );
+
+    let mut clipped = Vec::with_capacity(commands.len() + 3);
+    clipped.push(DisplayCommand::Save);
+    clipped.push(DisplayCommand::Clip(DisplayClip::RoundRectangle {
+        rect: circle,
+        radii: [diameter / 2.0; 4],
+    }));
+    clipped.extend(commands);
+    clipped.push(DisplayCommand::Restore);
+    clipped
+}
+
+/// Display commands recorded for replaying elsewhere, such as scaled down in a [`Minimap`](../ui/struct.Minimap.html).
+///
+/// Like `AnimatedImage`, every clone shares the same commands, and snapshots are only equal to their clones.
+#[derive(Clone)]
+pub struct CommandSnapshot(Rc<Vec<DisplayCommand>>);
+
+impl CommandSnapshot {
+    /// Records `commands`, which are positioned relative to the origin.
+    pub fn new(commands: Vec<DisplayCommand>) -> Self {
+        CommandSnapshot(Rc::new(commands))
+    }
+
+    /// Returns the recorded commands.
+    #[inline]
+    pub fn commands(&self) -> &[DisplayCommand] {
+        &self.0
+    }
+
+    /// Returns the recorded commands scaled by `scale`, with the origin moved to `origin`.
+    pub fn replay_scaled(&self, origin: Point, scale: f32) -> Vec<DisplayCommand> {
+        let mut commands = Vec::with_capacity(self.0.len() + 4);
+        commands.push(DisplayCommand::Save);
+        commands.push(DisplayCommand::Translate(origin.to_vector()));
+        commands.push(DisplayCommand::Scale(Vector::new(scale, scale)));
+        commands.extend(self.0.iter().cloned());
+        commands.push(DisplayCommand::Restore);
+        commands
+    }
+}
+
+impl Default for CommandSnapshot {
+    fn default() -> Self {
+        CommandSnapshot::new(Vec::new())
+    }
+}
+
+impl PartialEq for CommandSnapshot {
+    fn eq(&self, other: &CommandSnapshot) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::fmt::Debug for CommandSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("CommandSnapshot").field(&self.0.len()).finish()
+    }
+}
+
+/// Icon shown alongside the label of a control, such as a button.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Icon {
+    /// Raster image, scaled to the icon size.
+    Raster(ImageResource),
+    /// Vector drawing, recorded in a square of the given side (e.g. the view box of an SVG icon), scaled to the icon
+    /// size.
+    Vector(CommandSnapshot, f32),
+}
+
+impl Icon {
+    /// Returns the uploaded resource of a raster icon, uploading it to `display` if it hasn't been already.
+    ///
+    /// Returns `None` for vector icons, and for raster icons which failed to load.
+    pub fn load(&self, display: &mut dyn GraphicsDisplay) -> Option<ResourceReference> {
+        match self {
+            Icon::Raster(image) => image.load(display),
+            Icon::Vector(..) => None,
+        }
+    }
+
+    /// Returns the commands which draw the icon into `rect`, with `resource` being the uploaded resource of a raster
+    /// icon (see `load`). Raster icons which haven't been loaded aren't drawn.
+    pub fn draw(&self, rect: Rect, resource: Option<ResourceReference>) -> Vec<DisplayCommand> {
+        match self {
+            Icon::Raster(_) => {
+                let mut builder = DisplayListBuilder::new();
+                if let Some(resource) = resource {
+                    builder.push_image(None, rect, resource, None);
+                }
+                builder.build()
+            }
+            Icon::Vector(snapshot, side) => {
+                let scale =
+                    if *side > 0.0 { rect.size.width.min(rect.size.height) / side } else { 1.0 };
+                snapshot.replay_scaled(rect.origin, scale)
+            }
+        }
+    }
+}
+
+/// Side of the label which an icon is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconPlacement {
+    Before,
+    After,
+}
+
+impl Default for IconPlacement {
+    fn default() -> Self {
+        IconPlacement::Before
+    }
+}
+
+/// Dimensions of icons shown alongside labels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IconMetrics {
+    /// Width and height of icons.
+    pub size: f32,
+    /// Space between an icon and its label.
+    pub spacing: f32,
+}
+
 /// A consistent palette of colors used throughout the UI.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ColorScheme {
@@ -85,6 +260,14 @@ pub struct ColorScheme {
     pub control_outset: Color,
     /// A control which is "inset", such as a text box.
     pub control_inset: Color,
+    /// Background of selected text.
+    pub text_selection: Color,
+    /// Background of selected items of lists, such as suggestions or files.
+    pub list_selection: Color,
+    /// Background of selected rows of tables.
+    pub table_selection: Color,
+    /// Background of selections in any context while the window is inactive.
+    pub inactive_selection: Color,
     /// A color which appears clearly over `error`.
     pub over_error: Color,
     /// A color which appears clearly over `focus`.
@@ -95,6 +278,14 @@ pub struct ColorScheme {
     pub over_control_outset: Color,
     /// A color which appears clearly over `control_inset`.
     pub over_control_inset: Color,
+    /// A color which appears clearly over `text_selection`.
+    pub over_text_selection: Color,
+    /// A color which appears clearly over `list_selection`.
+    pub over_list_selection: Color,
+    /// A color which appears clearly over `table_selection`.
+    pub over_table_selection: Color,
+    /// A color which appears clearly over `inactive_selection`.
+    pub over_inactive_selection: Color,
 }
 
 /// A single typeface in 2 weights and italics.
@@ -164,6 +355,241 @@ pub struct Typography {
     pub button: TypefaceStyle,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImageStatus {
+    Unloaded,
+    Loaded(ResourceReference),
+    Failed,
+}
+
+struct ImageResourceInner {
+    data: ResourceData,
+    /// Pixel layout of raw images; `None` for encoded images.
+    raw: Option<RasterImageInfo>,
+    size: Option<(u32, u32)>,
+    status: Cell<ImageStatus>,
+}
+
+/// A raster image which can be shared between widgets and painters.
+///
+/// The image isn't decoded or uploaded to the graphics display until it's first loaded (typically when it's first drawn),
+/// after which the same resource is used by every clone of the `ImageResource`, until it's unloaded.
+#[derive(Clone)]
+pub struct ImageResource(Rc<ImageResourceInner>);
+
+impl ImageResource {
+    /// Creates an image from encoded data (e.g. PNG or JPEG).
+    ///
+    /// The size of PNG, JPEG, GIF and BMP images is read from the header of in-memory data without decoding;
+    /// the size of other images is unknown.
+    pub fn encoded(data: ResourceData) -> Self {
+        let size = match data {
+            ResourceData::Data(SharedData::RefCount(ref bytes)) => encoded_image_size(bytes),
+            ResourceData::Data(SharedData::Static(bytes)) => encoded_image_size(bytes),
+            _ => None,
+        };
+        ImageResource(Rc::new(ImageResourceInner {
+            data,
+            raw: None,
+            size,
+            status: Cell::new(ImageStatus::Unloaded),
+        }))
+    }
+
+    /// Creates an image from raw pixel data.
+    pub fn raw(data: ResourceData, info: RasterImageInfo) -> Self {
+        ImageResource(Rc::new(ImageResourceInner {
+            data,
+            size: Some(info.size),
+            raw: Some(info),
+            status: Cell::new(ImageStatus::Unloaded),
+        }))
+    }
+
+    /// Returns the size of the image in pixels, if known.
+    pub fn size(&self) -> Option<Size> {
+        self.0.size.map(|(width, height)| Size::new(width as f32, height as f32))
+    }
+
+    /// Returns the uploaded resource, uploading the image to `display` if it hasn't been already.
+    ///
+    /// Returns `None` if the image failed to load, in which case loading isn't attempted again.
+    pub fn load(&self, display: &mut dyn GraphicsDisplay) -> Option<ResourceReference> {
+        if let ImageStatus::Unloaded = self.0.status.get() {
+            let image = match self.0.raw {
+                Some(ref info) => ImageData::Raw(self.0.data.clone(), info.clone()),
+                None => ImageData::Encoded(self.0.data.clone()),
+            };
+            self.0.status.set(match display.new_resource(ResourceDescriptor::Image(image)) {
+                Ok(resource) => ImageStatus::Loaded(resource),
+                Err(_) => ImageStatus::Failed,
+            });
+        }
+
+        self.resource()
+    }
+
+    /// Returns the uploaded resource, if the image has been loaded.
+    pub fn resource(&self) -> Option<ResourceReference> {
+        match self.0.status.get() {
+            ImageStatus::Loaded(resource) => Some(resource),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the image failed to load.
+    pub fn is_failed(&self) -> bool {
+        self.0.status.get() == ImageStatus::Failed
+    }
+
+    /// Removes the uploaded resource from `display`, for all the clones of `self`.
+    ///
+    /// The image will be uploaded again the next time it's loaded.
+    pub fn unload(&self, display: &mut dyn GraphicsDisplay) {
+        if let ImageStatus::Loaded(resource) = self.0.status.get() {
+            display.remove_resource(resource);
+        }
+        self.0.status.set(ImageStatus::Unloaded);
+    }
+}
+
+impl PartialEq for ImageResource {
+    fn eq(&self, other: &ImageResource) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ImageResource {}
+
+impl std::fmt::Debug for ImageResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ImageResource")
+            .field("size", &self.0.size)
+            .field("status", &self.0.status.get())
+            .finish()
+    }
+}
+
+/// A raw raster image whose pixels are replaced over time, such as the frames of a video or a camera feed.
+///
+/// Updates are only uploaded to the graphics display by `upload` (typically when the image is next drawn), so when
+/// the image is updated several times between draws, only the most recent pixels are uploaded.
+/// The previous resource is removed from the display once it has been replaced.
+#[derive(Default)]
+pub struct UpdatableImage {
+    pending: Option<(ResourceData, RasterImageInfo)>,
+    resource: Option<(ResourceReference, (u32, u32))>,
+}
+
+impl UpdatableImage {
+    /// Creates an image without any pixels.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Replaces the pixels of the image, superseding any update which hasn't been uploaded yet.
+    pub fn update(&mut self, data: ResourceData, info: RasterImageInfo) {
+        self.pending = Some((data, info));
+    }
+
+    /// Returns `true` if the image has been updated since it was last uploaded.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Uploads the most recent update to `display`, replacing the previous resource.
+    ///
+    /// Returns `None` if the image hasn't been updated, otherwise whether the upload succeeded.
+    /// If the upload failed, the previous resource is kept.
+    pub fn upload(&mut self, display: &mut dyn GraphicsDisplay) -> Option<bool> {
+        let (data, info) = self.pending.take()?;
+        let size = info.size;
+        match display.new_resource(ResourceDescriptor::Image(ImageData::Raw(data, info))) {
+            Ok(resource) => {
+                if let Some((old, _)) = self.resource.replace((resource, size)) {
+                    display.remove_resource(old);
+                }
+                Some(true)
+            }
+            Err(_) => Some(false),
+        }
+    }
+
+    /// Returns the uploaded resource, if any.
+    #[inline]
+    pub fn resource(&self) -> Option<ResourceReference> {
+        self.resource.map(|(resource, _)| resource)
+    }
+
+    /// Returns the size of the uploaded image in pixels, if any.
+    pub fn size(&self) -> Option<Size> {
+        self.resource.map(|(_, (width, height))| Size::new(width as f32, height as f32))
+    }
+
+    /// Removes the uploaded resource from `display`, and discards any update which hasn't been uploaded.
+    pub fn unload(&mut self, display: &mut dyn GraphicsDisplay) {
+        self.pending = None;
+        if let Some((resource, _)) = self.resource.take() {
+            display.remove_resource(resource);
+        }
+    }
+}
+
+impl std::fmt::Debug for UpdatableImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("UpdatableImage")
+            .field("pending", &self.is_pending())
+            .field("resource", &self.resource)
+            .finish()
+    }
+}
+
+/// Reads the size of a PNG, JPEG, GIF or BMP image from its header.
+fn encoded_image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| Some(u16::from_be_bytes([*data.get(i)?, *data.get(i + 1)?]) as u32);
+    let le16 = |i: usize| Some(u16::from_le_bytes([*data.get(i)?, *data.get(i + 1)?]) as u32);
+    let bytes4 = |i: usize| -> Option<[u8; 4]> {
+        Some([*data.get(i)?, *data.get(i + 1)?, *data.get(i + 2)?, *data.get(i + 3)?])
+    };
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // the IHDR chunk is always first
+        Some((u32::from_be_bytes(bytes4(16)?), u32::from_be_bytes(bytes4(20)?)))
+    } else if data.starts_with(b"GIF8") {
+        Some((le16(6)?, le16(8)?))
+    } else if data.starts_with(b"BM") {
+        // height is negative for top-down bitmaps
+        let width = i32::from_le_bytes(bytes4(18)?);
+        let height = i32::from_le_bytes(bytes4(22)?);
+        Some((width.abs() as u32, height.abs() as u32))
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        // walk the segments until a start-of-frame
+        let mut i = 2;
+        loop {
+            while *data.get(i)? != 0xFF {
+                i += 1;
+            }
+            while *data.get(i)? == 0xFF {
+                i += 1;
+            }
+            let marker = *data.get(i)?;
+            i += 1;
+            match marker {
+                0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                    return Some((be16(i + 5)?, be16(i + 3)?));
+                }
+                // markers without a payload
+                0x01 | 0xD0..=0xD9 => {}
+                _ => i += be16(i)? as usize,
+            }
+        }
+    } else {
+        None
+    }
+}
+
 /// The "contrast" mode of a theme, i.e. light or dark.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ThemeContrast {
@@ -180,6 +606,10 @@ pub struct ThemeData {
     pub typography: Typography,
     /// Contras mode of the theme.
     pub contrast: ThemeContrast,
+    /// Dimensions of icons in controls.
+    pub icons: IconMetrics,
+    /// Property overrides of style classes, resolved by painters.
+    pub styles: style::StyleSheet,
 }
 
 /// Factory to create colors or `Painter`s which paint widgets with a specific visual theme.
@@ -192,8 +622,106 @@ pub trait Theme {
     fn text_area(&self) -> Box<dyn Painter<state::TextAreaState>>;
     /// Constructs a painter for a scroll bar.
     fn scroll_bar(&self) -> Box<dyn Painter<state::ScrollBarState>>;
+    /// Constructs a painter for a modal dialog.
+    fn dialog(&self) -> Box<dyn Painter<state::DialogState>>;
+    /// Constructs a painter for a sketch canvas.
+    fn sketch_canvas(&self) -> Box<dyn Painter<state::SketchCanvasState>>;
+    /// Constructs a painter for an annotation canvas.
+    fn annotation_canvas(&self) -> Box<dyn Painter<state::AnnotationCanvasState>>;
+    /// Constructs a painter for a toast notification.
+    fn toast(&self) -> Box<dyn Painter<state::ToastState>>;
+    /// Constructs a painter for a collapsible panel.
+    fn collapsible(&self) -> Box<dyn Painter<state::CollapsibleState>>;
+    /// Constructs a painter for an image cropper.
+    fn cropper(&self) -> Box<dyn Painter<state::CropperState>>;
+    /// Constructs a painter for a split pane.
+    fn split_pane(&self) -> Box<dyn Painter<state::SplitPaneState>>;
+    /// Constructs a painter for a dock.
+    fn dock(&self) -> Box<dyn Painter<state::DockState>>;
+    /// Constructs a painter for a tile view.
+    fn tile_view(&self) -> Box<dyn Painter<state::TileViewState>>;
+    /// Constructs a painter for a video surface.
+    fn video_surface(&self) -> Box<dyn Painter<state::VideoSurfaceState>>;
+    /// Constructs a painter for a surface showing external frames.
+    fn surface(&self) -> Box<dyn Painter<state::SurfaceState>>;
+    /// Constructs a painter for an audio level meter.
+    fn level_meter(&self) -> Box<dyn Painter<state::LevelMeterState>>;
+    /// Constructs a painter for a waveform.
+    fn waveform(&self) -> Box<dyn Painter<state::WaveformState>>;
+    /// Constructs a painter for a date picker.
+    fn date_picker(&self) -> Box<dyn Painter<state::DatePickerState>>;
+    /// Constructs a painter for a file browser.
+    fn file_browser(&self) -> Box<dyn Painter<state::FileBrowserState>>;
+    /// Constructs a painter for an image.
+    fn image(&self) -> Box<dyn Painter<state::ImageState>>;
+    /// Constructs a painter for a clipboard history picker.
+    fn clipboard_history_picker(&self) -> Box<dyn Painter<state::ClipboardHistoryPickerState>>;
+    /// Constructs a painter for a radial menu.
+    fn radial_menu(&self) -> Box<dyn Painter<state::RadialMenuState>>;
+    /// Constructs a painter for a badge.
+    fn badge(&self) -> Box<dyn Painter<state::BadgeState>>;
+    /// Constructs a painter for a chip input.
+    fn chip_input(&self) -> Box<dyn Painter<state::ChipInputState>>;
+    /// Constructs a painter for a knob.
+    fn knob(&self) -> Box<dyn Painter<state::KnobState>>;
+    /// Constructs a painter for a gauge.
+    fn gauge(&self) -> Box<dyn Painter<state::GaugeState>>;
+    /// Constructs a painter for a chart.
+    fn chart(&self) -> Box<dyn Painter<state::ChartState>>;
+    /// Constructs a painter for a sparkline.
+    fn sparkline(&self) -> Box<dyn Painter<state::SparklineState>>;
+    /// Constructs a painter for a pagination control.
+    fn pagination(&self) -> Box<dyn Painter<state::PaginationState>>;
+    /// Constructs a painter for a tree table.
+    fn tree_table(&self) -> Box<dyn Painter<state::TreeTableState>>;
+    /// Constructs a painter for a property grid.
+    fn property_grid(&self) -> Box<dyn Painter<state::PropertyGridState>>;
+    /// Constructs a painter for a swatch grid.
+    fn swatch_grid(&self) -> Box<dyn Painter<state::SwatchGridState>>;
+    /// Constructs a painter for a carousel.
+    fn carousel(&self) -> Box<dyn Painter<state::CarouselState>>;
+    /// Constructs a painter for a floating action button.
+    fn fab(&self) -> Box<dyn Painter<state::FabState>>;
+    /// Constructs a painter for a masked input.
+    fn masked_input(&self) -> Box<dyn Painter<state::MaskedInputState>>;
+    /// Constructs a painter for an autocomplete field.
+    fn auto_complete(&self) -> Box<dyn Painter<state::AutoCompleteState>>;
+    /// Constructs a painter for a title bar.
+    fn title_bar(&self) -> Box<dyn Painter<state::TitleBarState>>;
+    /// Constructs a painter for a minimap.
+    fn minimap(&self) -> Box<dyn Painter<state::MinimapState>>;
+    /// Constructs a painter for a wizard.
+    fn wizard(&self) -> Box<dyn Painter<state::WizardState>>;
+    /// Constructs a painter for a card.
+    fn card(&self) -> Box<dyn Painter<state::CardState>>;
+    /// Constructs a painter for a group box.
+    fn group_box(&self) -> Box<dyn Painter<state::GroupBoxState>>;
+    /// Constructs a painter for an avatar.
+    fn avatar(&self) -> Box<dyn Painter<state::AvatarState>>;
+    /// Constructs a painter for a search box.
+    fn search_box(&self) -> Box<dyn Painter<state::SearchBoxState>>;
+    /// Constructs a painter for a password box.
+    fn password_box(&self) -> Box<dyn Painter<state::PasswordBoxState>>;
+    /// Constructs a painter for a code editor.
+    fn code_editor(&self) -> Box<dyn Painter<state::CodeEditorState>>;
+    /// Constructs a painter for a log view.
+    fn log_view(&self) -> Box<dyn Painter<state::LogViewState>>;
+    /// Constructs a painter for a QR code or barcode.
+    #[cfg(feature = "extra-widgets")]
+    fn qr_code(&self) -> Box<dyn Painter<state::QrCodeState>>;
 
     fn data(&self) -> &ThemeData;
+
+    /// Returns the name of the theme, e.g. for diagnostics. By default, this is the path of the type.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Returns the pairs of colors which painters draw over each other, for auditing their contrast (see
+    /// `audit::audit`). By default, these are the pairs of the color scheme.
+    fn color_pairs(&self) -> Vec<audit::ColorPair> {
+        audit::scheme_pairs(&self.data().scheme)
+    }
 }
 
 /// Implemented by types which have an inner `Themed` (but usually widgets with
@@ -203,6 +731,13 @@ pub trait HasTheme {
     fn theme(&mut self) -> &mut dyn Themed;
     /// *Possibly* invokes `size_hint` on the inner `Painter` and applies it.
     fn resize_from_theme(&mut self);
+    /// Returns the current state given to the inner `Painter` (formatted with `Debug`) and the display commands it
+    /// draws for that state, for diagnostics.
+    ///
+    /// Returns `None` by default, for types which aren't drawn by a `Painter`.
+    fn painted_state(&mut self) -> Option<(String, Vec<DisplayCommand>)> {
+        None
+    }
 }
 
 impl<T> Themed for Box<dyn Painter<T>> {
@@ -3,13 +3,18 @@
 //! These are simply the fields relevant to rendering, existing only
 //! in the scope of the `draw` method.
 
-use crate::{geom::*, ui};
+use {
+    crate::{draw::TextStyle, geom::*, ui},
+    reclutch::display::Color,
+};
 
 /// Visually relevant states of a [`Button`](../ui/struct.Button.html).
 #[derive(Debug, Clone)]
 pub struct ButtonState {
     pub rect: AbsoluteRect,
     pub data: ui::Button,
+    /// Uploaded resource of a raster icon, or `None` if there is none (or it failed to load).
+    pub icon: Option<reclutch::display::ResourceReference>,
     pub interaction: InteractionState,
 }
 
@@ -18,14 +23,34 @@ bitflags::bitflags! {
         const HOVERED = 1;
         const PRESSED = 1 << 1;
         const FOCUSED = 1 << 2;
+        /// Set alongside `FOCUSED` when focus should be visibly indicated (e.g. with a focus ring).
+        ///
+        /// Focus acquired with a pointer isn't visible, since the user already knows where they clicked;
+        /// it becomes visible once the keyboard is used while focused.
+        const FOCUS_VISIBLE = 1 << 3;
+        /// Set while the window is inactive, i.e. while another window has the focus of the OS.
+        ///
+        /// Focused widgets stay focused, but carets are hidden and selections are drawn with the inactive
+        /// selection colors of the theme.
+        const INACTIVE = 1 << 4;
+    }
+}
+
+impl InteractionState {
+    /// Returns `true` if focused, and focus should be visibly indicated.
+    #[inline]
+    pub fn is_focus_visible(self) -> bool {
+        self.contains(InteractionState::FOCUSED | InteractionState::FOCUS_VISIBLE)
     }
 }
 
 /// Visually relevant states of a [`Checkbox`](../ui/struct.Checkbox.html).
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CheckboxState {
     pub rect: AbsoluteRect,
     pub data: ui::Checkbox,
+    /// Uploaded resource of a raster icon, or `None` if there is none (or it failed to load).
+    pub icon: Option<reclutch::display::ResourceReference>,
     pub interaction: InteractionState,
 }
 
@@ -50,3 +75,745 @@ pub struct ScrollBarState {
     pub scroll_bar: AbsoluteRect,
     pub interaction: InteractionState,
 }
+
+/// Visually relevant states of a [`Dialog`](../ui/struct.Dialog.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DialogState {
+    pub rect: AbsoluteRect,
+    /// Rectangle of the content panel.
+    pub panel: AbsoluteRect,
+    pub data: ui::Dialog,
+}
+
+/// Visually relevant states of a [`SketchCanvas`](../ui/struct.SketchCanvas.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SketchCanvasState {
+    pub rect: AbsoluteRect,
+    pub data: ui::SketchCanvas,
+}
+
+/// Visually relevant states of an [`AnnotationCanvas`](../ui/struct.AnnotationCanvas.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationCanvasState {
+    pub rect: AbsoluteRect,
+    pub data: ui::AnnotationCanvas,
+}
+
+/// Visually relevant states of a [`Toast`](../ui/struct.Toast.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToastState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Toast,
+    /// Opacity of the toast, from `0.0` to `1.0`; used when sliding in or out.
+    pub opacity: f32,
+}
+
+/// Visually relevant states of a [`Collapsible`](../ui/struct.Collapsible.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollapsibleState {
+    /// Rectangle of the header.
+    pub rect: AbsoluteRect,
+    pub data: ui::Collapsible,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`Cropper`](../ui/struct.Cropper.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CropperState {
+    pub rect: AbsoluteRect,
+    /// Rectangle the image is displayed in.
+    pub image: AbsoluteRect,
+    /// Crop rectangle in absolute coordinates.
+    pub crop: AbsoluteRect,
+    pub data: ui::Cropper,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`SplitPane`](../ui/struct.SplitPane.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitPaneState {
+    pub rect: AbsoluteRect,
+    /// Rectangle of the divider.
+    pub divider: AbsoluteRect,
+    pub data: ui::SplitPane,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a tab group within a [`Dock`](../ui/struct.Dock.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockGroupState {
+    /// Rectangle of the entire group, including the tab bar.
+    pub rect: AbsoluteRect,
+    pub tab_bar: AbsoluteRect,
+    /// Title and rectangle of each tab.
+    pub tabs: Vec<(reclutch::display::DisplayText, AbsoluteRect)>,
+    pub active: usize,
+    pub floating: bool,
+}
+
+/// Visually relevant states of a [`Dock`](../ui/struct.Dock.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Dock,
+    pub groups: Vec<DockGroupState>,
+    /// Rectangles of the dividers between docked groups.
+    pub dividers: Vec<AbsoluteRect>,
+    /// Area a dragged tab would occupy if dropped.
+    pub preview: Option<AbsoluteRect>,
+    /// Title and rectangle of the tab being dragged.
+    pub dragging: Option<(reclutch::display::DisplayText, AbsoluteRect)>,
+}
+
+/// Image drawn for a single tile of a [`TileView`](../ui/struct.TileView.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileImage {
+    pub resource: reclutch::display::ResourceReference,
+    /// Portion of the image to draw, in image pixels, or `None` for the entire image.
+    pub source: Option<reclutch::display::Rect>,
+    pub rect: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`TileView`](../ui/struct.TileView.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileViewState {
+    pub rect: AbsoluteRect,
+    pub data: ui::TileView,
+    pub tiles: Vec<TileImage>,
+    /// Position of each marker in `data.markers`, in absolute coordinates.
+    pub markers: Vec<AbsolutePoint>,
+}
+
+/// Frame drawn by a [`VideoSurface`](../ui/struct.VideoSurface.html) or a [`Surface`](../ui/struct.Surface.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoFrameState {
+    pub resource: reclutch::display::ResourceReference,
+    /// Portion of the frame to draw, in frame pixels, or `None` for the entire frame.
+    pub source: Option<reclutch::display::Rect>,
+    pub rect: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`VideoSurface`](../ui/struct.VideoSurface.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoSurfaceState {
+    pub rect: AbsoluteRect,
+    pub data: ui::VideoSurface,
+    pub frame: Option<VideoFrameState>,
+}
+
+/// Visually relevant states of a [`Surface`](../ui/struct.Surface.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Surface,
+    pub frame: Option<VideoFrameState>,
+}
+
+/// Visually relevant states of a [`LevelMeter`](../ui/struct.LevelMeter.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelMeterState {
+    pub rect: AbsoluteRect,
+    pub data: ui::LevelMeter,
+    pub levels: Vec<ui::ChannelLevel>,
+}
+
+/// Visually relevant states of a [`Waveform`](../ui/struct.Waveform.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Waveform,
+    /// Minimum and maximum sample of each column of pixels, starting from the left edge.
+    pub columns: Vec<(f32, f32)>,
+    /// Area covered by the selection, if any.
+    pub selection: Option<AbsoluteRect>,
+}
+
+/// A single day in the grid of a [`DatePicker`](../ui/struct.DatePicker.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayCellState {
+    pub date: ui::Date,
+    pub rect: AbsoluteRect,
+    /// Whether the day is in the shown month, rather than an adjacent month.
+    pub in_month: bool,
+    /// Whether the day lies within the selectable range.
+    pub enabled: bool,
+}
+
+/// Visually relevant states of a [`DatePicker`](../ui/struct.DatePicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatePickerState {
+    pub rect: AbsoluteRect,
+    pub data: ui::DatePicker,
+    /// Area of the title showing the month and year.
+    pub title: AbsoluteRect,
+    /// Navigation buttons; previous year, previous month, next month and next year.
+    pub navigation: [AbsoluteRect; 4],
+    /// Whether each navigation button can be used.
+    pub navigation_enabled: [bool; 4],
+    /// Column headers, with the weekday in days since Monday.
+    pub weekdays: Vec<(u32, AbsoluteRect)>,
+    pub days: Vec<DayCellState>,
+    pub hovered: Option<ui::Date>,
+    pub interaction: InteractionState,
+}
+
+/// A directory in the tree of a [`FileBrowser`](../ui/struct.FileBrowser.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTreeRowState {
+    pub name: reclutch::display::DisplayText,
+    pub depth: usize,
+    /// Whether the directory is expanded, or `None` if it's known to have no subdirectories.
+    pub expanded: Option<bool>,
+    /// Whether this is the listed directory.
+    pub current: bool,
+    pub rect: AbsoluteRect,
+}
+
+/// An entry in the list of a [`FileBrowser`](../ui/struct.FileBrowser.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRowState {
+    pub name: reclutch::display::DisplayText,
+    pub is_dir: bool,
+    /// Size of files, in bytes.
+    pub size: Option<u64>,
+    /// Byte ranges of `name` to highlight (e.g. characters matched by a search).
+    pub matched: Vec<std::ops::Range<usize>>,
+    pub selected: bool,
+    pub hovered: bool,
+    pub rect: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`FileBrowser`](../ui/struct.FileBrowser.html).
+///
+/// Only the rows which are at least partially visible are included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileBrowserState {
+    pub rect: AbsoluteRect,
+    pub data: ui::FileBrowser,
+    pub tree_rect: AbsoluteRect,
+    pub list_rect: AbsoluteRect,
+    pub tree: Vec<FileTreeRowState>,
+    pub entries: Vec<FileRowState>,
+    /// Message shown in place of the entries (e.g. an error).
+    pub message: Option<reclutch::display::DisplayText>,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of an [`Image`](../ui/struct.Image.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Image,
+    /// Uploaded image resource, or `None` if the image hasn't been loaded (or failed to load).
+    pub resource: Option<reclutch::display::ResourceReference>,
+    /// Portion of the image to draw, in image pixels, or `None` for the entire image.
+    pub source: Option<reclutch::display::Rect>,
+    /// Rectangles the image is drawn into; more than one when tiled.
+    pub destinations: Vec<AbsoluteRect>,
+    /// Whether the image is still being loaded by an image loader.
+    pub loading: bool,
+}
+
+/// An entry listed by a [`ClipboardHistoryPicker`](../ui/struct.ClipboardHistoryPicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardRowState {
+    /// Shortened, single line preview of the entry.
+    pub text: reclutch::display::DisplayText,
+    pub selected: bool,
+    pub hovered: bool,
+    pub rect: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`ClipboardHistoryPicker`](../ui/struct.ClipboardHistoryPicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardHistoryPickerState {
+    pub rect: AbsoluteRect,
+    pub data: ui::ClipboardHistoryPicker,
+    /// Rectangle of the popup, which only spans the listed entries.
+    pub panel: AbsoluteRect,
+    /// Visible entries.
+    pub rows: Vec<ClipboardRowState>,
+}
+
+/// Item of a radial menu, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialMenuItemState {
+    pub label: reclutch::display::DisplayText,
+    /// Angle of the clockwise edge of the sector of the item, in radians clockwise from the positive x-axis.
+    pub start_angle: f32,
+    /// Angle of the counter-clockwise edge of the sector of the item.
+    pub end_angle: f32,
+    pub selected: bool,
+    pub enabled: bool,
+}
+
+/// Visually relevant states of a [`RadialMenu`](../ui/struct.RadialMenu.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialMenuState {
+    pub rect: AbsoluteRect,
+    pub data: ui::RadialMenu,
+    /// Center of the ring.
+    pub center: AbsolutePoint,
+    pub items: Vec<RadialMenuItemState>,
+}
+
+/// Visually relevant states of a [`Badge`](../ui/struct.Badge.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadgeState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Badge,
+}
+
+/// Visually relevant states of a single chip in a [`ChipInput`](../ui/struct.ChipInput.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipState {
+    pub text: String,
+    pub rect: AbsoluteRect,
+    /// Bounds of the button which removes the chip.
+    pub remove: AbsoluteRect,
+    pub remove_hovered: bool,
+}
+
+/// Visually relevant states of a [`ChipInput`](../ui/struct.ChipInput.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipInputState {
+    pub rect: AbsoluteRect,
+    pub data: ui::ChipInput,
+    pub chips: Vec<ChipState>,
+    /// Bounds of the text field following the chips.
+    pub field: AbsoluteRect,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`Knob`](../ui/struct.Knob.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnobState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Knob,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`Gauge`](../ui/struct.Gauge.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaugeState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Gauge,
+    /// Value indicated by the needle.
+    pub value: f32,
+}
+
+/// Visually relevant states of a [`Chart`](../ui/struct.Chart.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Chart,
+    pub series: Vec<ui::ChartSeries>,
+    pub axes: ui::ChartAxes,
+    /// Index of the series and of the point under the cursor.
+    pub hovered: Option<(usize, usize)>,
+}
+
+/// Visually relevant states of a [`Sparkline`](../ui/struct.Sparkline.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparklineState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Sparkline,
+    /// Values shown, from oldest to most recent.
+    pub values: Vec<f32>,
+    /// Range of values spanning the height of the sparkline.
+    pub range: (f32, f32),
+}
+
+/// Swatch of a swatch grid, as shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwatchState {
+    pub color: Color,
+    pub rect: AbsoluteRect,
+    pub hovered: bool,
+    pub selected: bool,
+}
+
+/// Visually relevant states of a [`SwatchGrid`](../ui/struct.SwatchGrid.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwatchGridState {
+    pub rect: AbsoluteRect,
+    pub data: ui::SwatchGrid,
+    pub swatches: Vec<SwatchState>,
+}
+
+/// Visually relevant states of a [`Carousel`](../ui/struct.Carousel.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CarouselState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Carousel,
+    /// Rectangle of the previous arrow, if shown.
+    pub previous: Option<AbsoluteRect>,
+    /// Rectangle of the next arrow, if shown.
+    pub next: Option<AbsoluteRect>,
+    /// Rectangle of the dot of each page, if the page indicator is shown.
+    pub dots: Vec<AbsoluteRect>,
+    pub pages: usize,
+    /// Page position shown; fractional while sliding between pages.
+    pub offset: f32,
+    pub hovered: Option<ui::CarouselPart>,
+}
+
+/// Action of the speed-dial menu of a floating action button, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FabActionState {
+    pub button: AbsoluteRect,
+    /// Rectangle of the label, which is empty if the action has no label.
+    pub label: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`Fab`](../ui/struct.Fab.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FabState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Fab,
+    pub button: AbsoluteRect,
+    /// Actions of the speed-dial menu, if expanded.
+    pub actions: Vec<FabActionState>,
+    pub hovered: Option<ui::FabPart>,
+    pub pressed: Option<ui::FabPart>,
+}
+
+/// Cell of a table, as formatted by the renderer of its column (see [`CellRenderer`](../ui/enum.CellRenderer.html)).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableCellState {
+    Text(String),
+    /// Progress bar filled by a fraction from `0` to `1`, and its label.
+    Progress(f32, String),
+    /// Icon and the text following it.
+    IconText(String, String),
+    /// Check box, and whether it's checked.
+    Check(bool),
+}
+
+/// Row of a tree table, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeTableRowState {
+    pub rect: AbsoluteRect,
+    /// Number of ancestors of the node.
+    pub depth: usize,
+    /// Whether the node is expanded, or `None` if it has no children.
+    pub expanded: Option<bool>,
+    /// Each cell, in column order.
+    pub cells: Vec<TableCellState>,
+    /// Number of columns spanned by each cell, in column order; cells covered by a preceding cell span none.
+    pub spans: Vec<usize>,
+    pub selected: bool,
+    pub hovered: bool,
+    /// Whether the row is frozen, staying in place while the other rows are scrolled.
+    pub frozen: bool,
+}
+
+/// Filter cell of a tree table, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableFilterState {
+    pub rect: AbsoluteRect,
+    pub text: String,
+    /// Whether the filter is being typed into.
+    pub focused: bool,
+}
+
+/// Visually relevant states of a [`TreeTable`](../ui/struct.TreeTable.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeTableState {
+    pub rect: AbsoluteRect,
+    pub data: ui::TreeTable,
+    /// Rectangle of each column, including the header.
+    pub columns: Vec<AbsoluteRect>,
+    /// Filter cell of each column, or empty if the filter row isn't shown.
+    pub filters: Vec<TableFilterState>,
+    /// Rectangle of the rows, below the header.
+    pub body: AbsoluteRect,
+    /// Rectangle which the columns that aren't frozen are clipped to.
+    pub scroll_columns: AbsoluteRect,
+    /// Rectangle which the rows that aren't frozen are clipped to.
+    pub scroll_body: AbsoluteRect,
+    /// Rows in view; the frozen rows, followed by the rest of the rows in view.
+    pub rows: Vec<TreeTableRowState>,
+    pub interaction: InteractionState,
+}
+
+/// Row of a property grid, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyRowState {
+    /// Rectangle of the name.
+    pub name: AbsoluteRect,
+    /// Rectangle of the value and its editor.
+    pub value: AbsoluteRect,
+    /// Rectangles of the decrement and increment buttons, for spinboxes.
+    pub stepper: Option<(AbsoluteRect, AbsoluteRect)>,
+    pub hovered: Option<ui::PropertyPart>,
+    /// Text typed so far, if the value is being edited as text.
+    pub editing: Option<String>,
+}
+
+/// Visually relevant states of a [`PropertyGrid`](../ui/struct.PropertyGrid.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyGridState {
+    pub rect: AbsoluteRect,
+    pub data: ui::PropertyGrid,
+    /// One row for each property.
+    pub rows: Vec<PropertyRowState>,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`Pagination`](../ui/struct.Pagination.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaginationState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Pagination,
+    /// Items shown and their rectangles.
+    pub items: Vec<(ui::PaginationItem, AbsoluteRect)>,
+    /// Index of the hovered item.
+    pub hovered: Option<usize>,
+}
+
+/// Visually relevant states of a [`Wizard`](../ui/struct.Wizard.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WizardState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Wizard,
+    /// Rectangle of the step indicator.
+    pub indicator: AbsoluteRect,
+    /// Buttons shown and their rectangles.
+    pub buttons: Vec<(ui::WizardButton, AbsoluteRect)>,
+    pub hovered: Option<ui::WizardButton>,
+}
+
+/// Visually relevant states of a [`Card`](../ui/struct.Card.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Card,
+    /// Rectangle of the header, if shown.
+    pub header: Option<AbsoluteRect>,
+    /// Rectangle of the footer, if shown.
+    pub footer: Option<AbsoluteRect>,
+}
+
+/// Visually relevant states of a [`GroupBox`](../ui/struct.GroupBox.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupBoxState {
+    pub rect: AbsoluteRect,
+    pub data: ui::GroupBox,
+    /// Rectangle of the frame; its top border runs through the middle of the title.
+    pub frame: AbsoluteRect,
+}
+
+/// Visually relevant states of an [`Avatar`](../ui/struct.Avatar.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvatarState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Avatar,
+    /// Uploaded picture, or `None` to show the initials.
+    pub resource: Option<reclutch::display::ResourceReference>,
+    /// Square portion of the picture to draw, in image pixels, or `None` for the entire picture.
+    pub source: Option<reclutch::display::Rect>,
+    /// Whether the picture is still being loaded by an image loader.
+    pub loading: bool,
+}
+
+/// A suggestion listed by a [`SearchBox`](../ui/struct.SearchBox.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestionState {
+    pub text: String,
+    pub rect: AbsoluteRect,
+    pub selected: bool,
+    pub hovered: bool,
+}
+
+/// Visually relevant states of a [`SearchBox`](../ui/struct.SearchBox.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchBoxState {
+    pub rect: AbsoluteRect,
+    pub data: ui::SearchBox,
+    /// Rectangle of the clear button, if shown.
+    pub clear: Option<AbsoluteRect>,
+    pub clear_hovered: bool,
+    /// Rectangle of the popup of suggestions, if open.
+    pub popup: Option<AbsoluteRect>,
+    pub suggestions: Vec<SuggestionState>,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`PasswordBox`](../ui/struct.PasswordBox.html).
+///
+/// As with the password box, the password (including the displayed text, which is the password once revealed) is
+/// redacted from the `Debug` output.
+#[derive(Clone, PartialEq)]
+pub struct PasswordBoxState {
+    pub rect: AbsoluteRect,
+    pub data: ui::PasswordBox,
+    /// Text shown in the field; the password masked with bullets, unless revealed.
+    pub display_text: String,
+    /// Byte index of the cursor in `display_text`.
+    pub cursor: usize,
+    /// Rectangle of the reveal toggle.
+    pub reveal: AbsoluteRect,
+    pub reveal_hovered: bool,
+    /// Strength of the password from 0 to 1, if measured.
+    pub strength: Option<f32>,
+    pub interaction: InteractionState,
+}
+
+impl std::fmt::Debug for PasswordBoxState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PasswordBoxState")
+            .field("rect", &self.rect)
+            .field("data", &self.data)
+            .field("display_text", &format_args!("<redacted>"))
+            .field("cursor", &self.cursor)
+            .field("reveal", &self.reveal)
+            .field("reveal_hovered", &self.reveal_hovered)
+            .field("strength", &self.strength)
+            .field("interaction", &self.interaction)
+            .finish()
+    }
+}
+
+/// Visually relevant states of a [`MaskedInput`](../ui/struct.MaskedInput.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskedInputState {
+    pub rect: AbsoluteRect,
+    pub data: ui::MaskedInput,
+    /// Text shown in the field; the value formatted with the literals of the mask, and placeholders for unfilled slots.
+    pub display_text: String,
+    /// Byte length of the start of `display_text` which has been filled, after which only placeholders and literals
+    /// remain.
+    pub filled: usize,
+    /// Byte index of the cursor in `display_text`.
+    pub cursor: usize,
+    /// Whether every slot of the mask is filled.
+    pub complete: bool,
+    pub interaction: InteractionState,
+}
+
+/// A completion listed in the popup of an [`AutoComplete`](../ui/struct.AutoComplete.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionState {
+    pub text: String,
+    pub rect: AbsoluteRect,
+    /// Byte ranges of the characters matching the typed text.
+    pub ranges: Vec<std::ops::Range<usize>>,
+    pub selected: bool,
+    pub hovered: bool,
+}
+
+/// Visually relevant states of an [`AutoComplete`](../ui/struct.AutoComplete.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoCompleteState {
+    pub rect: AbsoluteRect,
+    pub data: ui::AutoComplete,
+    /// Rest of the inline completion after the typed text, if shown.
+    pub inline: Option<String>,
+    /// Rectangle of the popup of completions, if open.
+    pub popup: Option<AbsoluteRect>,
+    pub completions: Vec<CompletionState>,
+    pub interaction: InteractionState,
+}
+
+/// A button of a [`TitleBar`](../ui/struct.TitleBar.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowButtonState {
+    pub button: ui::WindowButton,
+    pub rect: AbsoluteRect,
+    pub hovered: bool,
+    pub pressed: bool,
+}
+
+/// Visually relevant states of a [`TitleBar`](../ui/struct.TitleBar.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleBarState {
+    pub rect: AbsoluteRect,
+    pub data: ui::TitleBar,
+    pub buttons: Vec<WindowButtonState>,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`Minimap`](../ui/struct.Minimap.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinimapState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Minimap,
+    /// Rectangle which the content is scaled into.
+    pub content: AbsoluteRect,
+    /// Rectangle of the viewport within the content.
+    pub viewport: AbsoluteRect,
+    pub interaction: InteractionState,
+}
+
+/// A line in view of a [`CodeEditor`](../ui/struct.CodeEditor.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeLineState {
+    /// Line number, from one.
+    pub number: usize,
+    pub text: String,
+    /// Tokens covering the whole line, in order.
+    pub tokens: Vec<ui::Token>,
+    /// Rectangle of the line, starting at its first character.
+    pub rect: AbsoluteRect,
+    /// Whether the line contains a cursor.
+    pub current: bool,
+}
+
+/// Visually relevant states of a [`CodeEditor`](../ui/struct.CodeEditor.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeEditorState {
+    pub rect: AbsoluteRect,
+    pub data: ui::CodeEditor,
+    /// Rectangle of the gutter showing the line numbers.
+    pub gutter: AbsoluteRect,
+    /// Rectangle in which the code is shown.
+    pub viewport: AbsoluteRect,
+    pub lines: Vec<CodeLineState>,
+    /// Rectangles of the cursors in view, while focused.
+    pub cursors: Vec<AbsoluteRect>,
+    /// Width of each character.
+    pub advance: f32,
+    pub interaction: InteractionState,
+}
+
+/// Span of a line shown in a [`LogView`](../ui/struct.LogView.html), with its colors resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogSpanState {
+    pub text: String,
+    /// Index of the first character of the span within the line.
+    pub column: usize,
+    pub foreground: Color,
+    pub background: Option<Color>,
+    pub style: TextStyle,
+}
+
+/// Line shown in a [`LogView`](../ui/struct.LogView.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLineState {
+    pub spans: Vec<LogSpanState>,
+    /// Rectangle of the line, starting at its first character.
+    pub rect: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`LogView`](../ui/struct.LogView.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogViewState {
+    pub rect: AbsoluteRect,
+    pub data: ui::LogView,
+    /// Lines in view.
+    pub lines: Vec<LogLineState>,
+    /// Rectangle of the scroll thumb, if the lines overflow.
+    pub thumb: Option<AbsoluteRect>,
+    /// Width of each character.
+    pub advance: f32,
+}
+
+/// Visually relevant states of a [`QrCode`](../ui/struct.QrCode.html).
+#[cfg(feature = "extra-widgets")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrCodeState {
+    pub rect: AbsoluteRect,
+    pub data: ui::QrCode,
+    pub modules: std::rc::Rc<ui::CodeModules>,
+}
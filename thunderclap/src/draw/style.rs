@@ -0,0 +1,116 @@
+//! Style classes, through which themes define variants of widgets without new painters.
+//!
+//! Widgets which support classes are tagged with class names (e.g. `"danger"` or `"compact"`) in their `classes`
+//! field. A theme maps class names to property overrides in the [`StyleSheet`](struct.StyleSheet.html) of its
+//! `ThemeData`, and its painters resolve the properties they draw against the classes of the widget:
+//!
+//! ```ignore
+//! let background = styles.color("button", &state.data.classes, "background", state.data.background);
+//! ```
+//!
+//! Properties which aren't overridden by any class fall back to the widget's own data.
+
+use reclutch::display::Color;
+
+/// Value of a style property.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StyleValue {
+    Color(Color),
+    /// A dimension or a factor, such as padding.
+    Number(f32),
+}
+
+/// Property overrides applying to widgets tagged with a class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+    pub class: String,
+    /// Kind of widget the rule is restricted to (e.g. `"button"`), or `None` for every widget with the class.
+    pub widget: Option<String>,
+    /// Overridden properties by name.
+    pub properties: Vec<(String, StyleValue)>,
+}
+
+impl StyleRule {
+    /// Creates a rule without overrides, applying to every widget with `class`.
+    pub fn new(class: impl Into<String>) -> Self {
+        StyleRule { class: class.into(), widget: None, properties: Vec::new() }
+    }
+
+    /// Restricts the rule to a kind of widget.
+    pub fn widget(mut self, widget: impl Into<String>) -> Self {
+        self.widget = Some(widget.into());
+        self
+    }
+
+    /// Overrides a color property.
+    pub fn color(mut self, property: impl Into<String>, color: Color) -> Self {
+        self.properties.push((property.into(), StyleValue::Color(color)));
+        self
+    }
+
+    /// Overrides a numeric property.
+    pub fn number(mut self, property: impl Into<String>, value: f32) -> Self {
+        self.properties.push((property.into(), StyleValue::Number(value)));
+        self
+    }
+
+    /// Returns `true` if the rule applies to a kind of widget tagged with `classes`.
+    pub fn applies(&self, widget: &str, classes: &[String]) -> bool {
+        self.widget.as_ref().map_or(true, |w| w == widget) && classes.contains(&self.class)
+    }
+
+    /// Returns the value of a property, if the rule overrides it.
+    pub fn get(&self, property: &str) -> Option<StyleValue> {
+        self.properties.iter().rev().find(|(name, _)| name == property).map(|(_, value)| *value)
+    }
+}
+
+/// Ordered list of style rules of a theme.
+///
+/// When several rules applying to a widget override the same property, the last one wins, regardless of the order of
+/// the widget's classes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StyleSheet {
+    pub rules: Vec<StyleRule>,
+}
+
+impl StyleSheet {
+    /// Creates an empty style sheet.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends a rule, taking precedence over the existing rules.
+    pub fn rule(mut self, rule: StyleRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Resolves a property of a kind of widget tagged with `classes`, or `None` if no class overrides it.
+    pub fn resolve(&self, widget: &str, classes: &[String], property: &str) -> Option<StyleValue> {
+        if classes.is_empty() {
+            return None;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| rule.applies(widget, classes))
+            .find_map(|rule| rule.get(property))
+    }
+
+    /// Resolves a color property, falling back to `default` if it isn't overridden (or isn't a color).
+    pub fn color(&self, widget: &str, classes: &[String], property: &str, default: Color) -> Color {
+        match self.resolve(widget, classes, property) {
+            Some(StyleValue::Color(color)) => color,
+            _ => default,
+        }
+    }
+
+    /// Resolves a numeric property, falling back to `default` if it isn't overridden (or isn't a number).
+    pub fn number(&self, widget: &str, classes: &[String], property: &str, default: f32) -> f32 {
+        match self.resolve(widget, classes, property) {
+            Some(StyleValue::Number(value)) => value,
+            _ => default,
+        }
+    }
+}
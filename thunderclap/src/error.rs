@@ -1,7 +1,7 @@
 #[allow(unused_imports)]
 use {reclutch::error, thiserror::Error};
 
-#[cfg(feature = "app")]
+#[cfg(feature = "app-core")]
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("{0}")]
@@ -18,3 +18,72 @@ pub enum ThemeError {
     #[error("{0}")]
     FontError(#[from] error::FontError),
 }
+
+#[cfg(feature = "core-widgets")]
+#[derive(Error, Debug)]
+pub enum AnnotationError {
+    #[error("unknown annotation kind \"{0}\" on line {1}")]
+    UnknownKind(String, usize),
+    #[error("malformed annotation on line {0}")]
+    Malformed(usize),
+}
+
+#[cfg(feature = "core-widgets")]
+#[derive(Error, Debug)]
+pub enum DockError {
+    #[error("unknown dock node kind \"{0}\" on line {1}")]
+    UnknownKind(String, usize),
+    #[error("malformed dock node on line {0}")]
+    Malformed(usize),
+    #[error("dock layout ended unexpectedly")]
+    UnexpectedEnd,
+}
+
+#[cfg(feature = "core-widgets")]
+#[derive(Error, Debug)]
+pub enum TileError {
+    #[error("no tile found at \"{0}\"")]
+    NotFound(String),
+    #[error("failed to fetch tile: {0}")]
+    Fetch(String),
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ImageLoadError {
+    #[error("no image provider for \"{0}\"")]
+    Unsupported(String),
+    #[error("no image found at \"{0}\"")]
+    NotFound(String),
+    #[error("failed to fetch image: {0}")]
+    Fetch(String),
+}
+
+#[cfg(feature = "extra-widgets")]
+#[derive(Error, Debug)]
+pub enum EncodeError {
+    #[error("{0}")]
+    QrError(#[from] qrcode::types::QrError),
+    #[error("character {0:?} cannot be encoded")]
+    InvalidCharacter(char),
+}
+
+#[cfg(feature = "extra-widgets")]
+#[derive(Error, Debug)]
+pub enum AnimationError {
+    #[error("{0}")]
+    GifError(#[from] gif::DecodingError),
+    #[error("animated image has no frames")]
+    NoFrames,
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintError {
+    #[error("constraint has already been added")]
+    Duplicate,
+    #[error("required constraint cannot be satisfied")]
+    Unsatisfiable,
+    #[error("constraint has not been added")]
+    Unknown,
+    #[error("constraint solver failed: {0}")]
+    Internal(&'static str),
+}
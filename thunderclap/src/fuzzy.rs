@@ -0,0 +1,186 @@
+//! Fuzzy text matching, shared by searchable widgets so that results are ranked consistently.
+//!
+//! A pattern matches a candidate if every character of the pattern appears in the candidate in order (not necessarily
+//! adjacently). Matching is case-insensitive unless the pattern contains an uppercase character ("smart case").
+//! Among the possible alignments, the one with the highest score is chosen, favouring characters which
+//! are consecutive, or which start a word (e.g. after a space, `_`, `-` or `/`, or at a camel-case hump).
+
+use std::ops::Range;
+
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP: i32 = -1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+/// Factor applied to the bonus of the character matching the first pattern character.
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+const NONE: i32 = std::i32::MIN / 2;
+
+/// Result of successfully matching a pattern against a candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Quality of the match; higher is better. Only comparable between matches of the same pattern.
+    pub score: i32,
+    /// Byte ranges of the matched characters within the candidate, merged where adjacent.
+    /// Intended for highlighting the matched characters.
+    pub ranges: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Delimiter,
+    Other,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_whitespace() || "/\\_-.:,;|".contains(c) {
+            CharClass::Delimiter
+        } else {
+            CharClass::Other
+        }
+    }
+}
+
+/// Bonus of matching a character of class `current`, preceded by a character of class `previous`.
+fn bonus(previous: CharClass, current: CharClass) -> i32 {
+    match (previous, current) {
+        (_, CharClass::Delimiter) => 0,
+        (CharClass::Delimiter, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (CharClass::Lower, CharClass::Digit) | (CharClass::Upper, CharClass::Digit) => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn fold_case(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Matches `pattern` against `candidate`, returning `None` if it doesn't match.
+///
+/// An empty pattern matches every candidate, with a score of zero and no ranges.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let pattern: Vec<char> = pattern.chars().map(|c| fold_case(c, case_sensitive)).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, ranges: Vec::new() });
+    }
+
+    let (offsets, chars): (Vec<usize>, Vec<char>) = candidate.char_indices().unzip();
+    let (n, m) = (pattern.len(), chars.len());
+
+    // Quickly reject candidates which don't contain the pattern as a subsequence.
+    {
+        let mut remaining = pattern.iter().peekable();
+        for &c in &chars {
+            if remaining.peek() == Some(&&fold_case(c, case_sensitive)) {
+                remaining.next();
+            }
+        }
+        if remaining.peek().is_some() {
+            return None;
+        }
+    }
+
+    let mut previous = CharClass::Delimiter;
+    let bonuses: Vec<i32> = chars
+        .iter()
+        .map(|&c| {
+            let class = CharClass::of(c);
+            let bonus = bonus(previous, class);
+            previous = class;
+            bonus
+        })
+        .collect();
+    let folded: Vec<char> = chars.iter().map(|&c| fold_case(c, case_sensitive)).collect();
+
+    // `matched[i][j]`: best score of `pattern[..=i]` with `pattern[i]` matched at `candidate[j]`.
+    // `best[i][j]`: best score of `pattern[..=i]` within `candidate[..=j]`.
+    let mut matched = vec![vec![NONE; m]; n];
+    let mut best = vec![vec![NONE; m]; n];
+    for i in 0..n {
+        for j in i..m {
+            if folded[j] == pattern[i] {
+                matched[i][j] = if i == 0 {
+                    SCORE_MATCH + bonuses[j] * FIRST_CHAR_MULTIPLIER
+                } else {
+                    let consecutive =
+                        matched[i - 1][j - 1].saturating_add(bonuses[j].max(BONUS_CONSECUTIVE));
+                    let after_gap = best[i - 1][j - 1].saturating_add(bonuses[j]);
+                    SCORE_MATCH + consecutive.max(after_gap)
+                };
+            }
+
+            let skipped = if j > 0 { best[i][j - 1].saturating_add(PENALTY_GAP) } else { NONE };
+            best[i][j] = matched[i][j].max(skipped);
+        }
+    }
+
+    // Trace back the alignment which produced the best score.
+    let mut positions = vec![0; n];
+    let mut j = m - 1;
+    let mut forced = false;
+    for i in (0..n).rev() {
+        if !forced {
+            while matched[i][j] <= NONE / 2 || best[i][j] != matched[i][j] {
+                j -= 1;
+            }
+        }
+        positions[i] = j;
+
+        if i > 0 {
+            forced = matched[i - 1][j - 1] > NONE / 2
+                && matched[i][j]
+                    == SCORE_MATCH + matched[i - 1][j - 1] + bonuses[j].max(BONUS_CONSECUTIVE);
+            j -= 1;
+        }
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for &position in &positions {
+        let start = offsets[position];
+        let end = start + chars[position].len_utf8();
+        match ranges.last_mut() {
+            Some(range) if range.end == start => range.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+
+    Some(FuzzyMatch { score: best[n - 1][m - 1], ranges })
+}
+
+/// Matches `pattern` against a list of items, returning the matching items sorted from best to worst match.
+///
+/// Matches with equal scores are ordered by the length of their key (shortest first), then by their original order.
+pub fn fuzzy_filter<T, I, F>(pattern: &str, items: I, key: F) -> Vec<(T, FuzzyMatch)>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> &str,
+{
+    let mut matches: Vec<(T, FuzzyMatch, usize)> = items
+        .into_iter()
+        .filter_map(|item| {
+            let text = key(&item);
+            let length = text.len();
+            fuzzy_match(pattern, text).map(|m| (item, m, length))
+        })
+        .collect();
+
+    // sort_by is stable, preserving the original order of otherwise equal matches
+    matches.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.2.cmp(&b.2)));
+    matches.into_iter().map(|(item, m, _)| (item, m)).collect()
+}
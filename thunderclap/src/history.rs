@@ -0,0 +1,135 @@
+//! Undoable snapshots of widget state, such as the arrangement of the children of a container.
+//!
+//! Widgets implementing [`Snapshot`](trait.Snapshot.html) can capture part of their state as a plain value and later
+//! be restored to it. Containers capture the arrangement of their children (e.g. the docked panels of a `Dock` or the
+//! divider of a `SplitPane`), but not the children themselves, which stay owned by the app.
+//!
+//! An [`UndoStack`](struct.UndoStack.html) records a snapshot after every edit, so that layout editing in design-type
+//! apps can be undone and redone:
+//!
+//! ```ignore
+//! let mut history = UndoStack::new(dock.snapshot());
+//!
+//! // whenever the user has edited the layout (e.g. on `DockEvent::Relocate` and `DockEvent::Resize`)
+//! history.record(&dock);
+//!
+//! // on ctrl+z
+//! history.undo(&mut dock);
+//! ```
+//!
+//! To undo edits spanning several widgets as one stack, implement `Snapshot` for a type which borrows all of them,
+//! with a tuple of their snapshots as the snapshot.
+
+/// Widget whose state can be captured and restored.
+pub trait Snapshot {
+    /// Captured state; a plain value which doesn't refer back to the widget.
+    type Snapshot: Clone + PartialEq;
+
+    /// Captures the current state.
+    fn snapshot(&self) -> Self::Snapshot;
+    /// Restores a previously captured state. This isn't considered an edit by the user, so no events are emitted.
+    fn restore_snapshot(&mut self, snapshot: Self::Snapshot);
+}
+
+/// Undo and redo history of snapshots.
+///
+/// The stack always holds the current snapshot, which edits are recorded on top of; undoing replaces it with the
+/// snapshot before it, and redoing with the one after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoStack<T: Clone + PartialEq> {
+    current: T,
+    undo: Vec<T>,
+    redo: Vec<T>,
+    limit: Option<usize>,
+}
+
+impl<T: Clone + PartialEq> UndoStack<T> {
+    /// Creates an empty history starting from `initial`.
+    pub fn new(initial: T) -> Self {
+        UndoStack { current: initial, undo: Vec::new(), redo: Vec::new(), limit: None }
+    }
+
+    /// Limits how many snapshots can be undone, forgetting the oldest beyond the limit.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self.trim();
+        self
+    }
+
+    /// Records a snapshot following an edit, making it the current snapshot and clearing the redo history.
+    ///
+    /// Returns `false`, recording nothing, if the snapshot is the same as the current snapshot (e.g. a divider was
+    /// dragged and released where it started).
+    pub fn push(&mut self, snapshot: T) -> bool {
+        if snapshot == self.current {
+            return false;
+        }
+        self.undo.push(std::mem::replace(&mut self.current, snapshot));
+        self.redo.clear();
+        self.trim();
+        true
+    }
+
+    /// Records a snapshot of `widget` following an edit (see `push`).
+    #[inline]
+    pub fn record<W: Snapshot<Snapshot = T>>(&mut self, widget: &W) -> bool {
+        self.push(widget.snapshot())
+    }
+
+    /// Restores `widget` to the snapshot before the current one. Returns `false` if there is nothing to undo.
+    pub fn undo<W: Snapshot<Snapshot = T>>(&mut self, widget: &mut W) -> bool {
+        match self.undo.pop() {
+            Some(snapshot) => {
+                self.redo.push(std::mem::replace(&mut self.current, snapshot));
+                widget.restore_snapshot(self.current.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restores `widget` to the most recently undone snapshot. Returns `false` if there is nothing to redo.
+    pub fn redo<W: Snapshot<Snapshot = T>>(&mut self, widget: &mut W) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                self.undo.push(std::mem::replace(&mut self.current, snapshot));
+                widget.restore_snapshot(self.current.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` if there is a snapshot which can be undone.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is an undone snapshot which can be redone.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns the current snapshot.
+    #[inline]
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Forgets the undo and redo history, keeping the current snapshot.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit {
+            if self.undo.len() > limit {
+                let excess = self.undo.len() - limit;
+                self.undo.drain(..excess);
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Locale-aware formatting of numbers, dates and times.
+//!
+//! Widgets which display numbers or dates take a [`Locale`](struct.Locale.html) in their data, which defaults to the
+//! active locale of the thread (see `set_locale`). Only the Gregorian calendar is supported.
+//!
+//! For testing layouts before translations exist, `Locale::pseudo` is a pseudo-locale which lengthens and accents text
+//! passed through `Locale::localize_text` (e.g. `Save` becomes `[Šàvé···]`). Text which is truncated, or which was
+//! never passed through the locale, stands out.
+
+use {crate::ui::Date, std::cell::RefCell};
+
+/// Order of the fields in a numeric date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateOrder {
+    /// e.g. `2024-01-31`.
+    YearMonthDay,
+    /// e.g. `31/01/2024`.
+    DayMonthYear,
+    /// e.g. `01/31/2024`.
+    MonthDayYear,
+}
+
+/// Whether times are shown on a 12-hour or 24-hour clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HourCycle {
+    /// e.g. `1:05 PM`, with the given markers before and after noon.
+    H12(&'static str, &'static str),
+    /// e.g. `13:05`.
+    H24,
+}
+
+/// Conventions used to format numbers, dates and times for a region and language.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    /// BCP 47 language tag, e.g. `"en-US"`.
+    pub tag: &'static str,
+    /// Names of the months, starting from January.
+    pub month_names: [&'static str; 12],
+    /// Abbreviated names of the weekdays, starting from Monday.
+    pub weekday_names: [&'static str; 7],
+    /// Digits from zero to nine.
+    pub digits: [char; 10],
+    pub decimal_separator: char,
+    /// Separator between groups of thousands, if they're separated.
+    pub group_separator: Option<char>,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+    pub hour_cycle: HourCycle,
+    /// Day which weeks start on, in days since Monday.
+    pub first_weekday: u32,
+    /// Whether text runs from right to left, which widgets reflect by mirroring their layout.
+    pub right_to_left: bool,
+    /// Whether text is pseudo-localized by `localize_text`.
+    pub pseudo: bool,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::en_us()
+    }
+}
+
+const ASCII_DIGITS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const ENGLISH_WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+impl Locale {
+    /// English, as used in the United States.
+    pub fn en_us() -> Self {
+        Locale {
+            tag: "en-US",
+            month_names: ENGLISH_MONTHS,
+            weekday_names: ENGLISH_WEEKDAYS,
+            digits: ASCII_DIGITS,
+            decimal_separator: '.',
+            group_separator: Some(','),
+            date_order: DateOrder::MonthDayYear,
+            date_separator: '/',
+            hour_cycle: HourCycle::H12("AM", "PM"),
+            first_weekday: 6,
+            right_to_left: false,
+            pseudo: false,
+        }
+    }
+
+    /// English, as used in the United Kingdom.
+    pub fn en_gb() -> Self {
+        Locale {
+            tag: "en-GB",
+            date_order: DateOrder::DayMonthYear,
+            hour_cycle: HourCycle::H24,
+            first_weekday: 0,
+            ..Locale::en_us()
+        }
+    }
+
+    /// German, as used in Germany.
+    pub fn de_de() -> Self {
+        Locale {
+            tag: "de-DE",
+            month_names: [
+                "Januar",
+                "Februar",
+                "M\u{e4}rz",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            weekday_names: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+            digits: ASCII_DIGITS,
+            decimal_separator: ',',
+            group_separator: Some('.'),
+            date_order: DateOrder::DayMonthYear,
+            date_separator: '.',
+            hour_cycle: HourCycle::H24,
+            first_weekday: 0,
+            right_to_left: false,
+            pseudo: false,
+        }
+    }
+
+    /// Arabic, as used in Egypt; written right to left, with Arabic-Indic digits.
+    pub fn ar_eg() -> Self {
+        Locale {
+            tag: "ar-EG",
+            month_names: [
+                "\u{64a}\u{646}\u{627}\u{64a}\u{631}",
+                "\u{641}\u{628}\u{631}\u{627}\u{64a}\u{631}",
+                "\u{645}\u{627}\u{631}\u{633}",
+                "\u{623}\u{628}\u{631}\u{64a}\u{644}",
+                "\u{645}\u{627}\u{64a}\u{648}",
+                "\u{64a}\u{648}\u{646}\u{64a}\u{648}",
+                "\u{64a}\u{648}\u{644}\u{64a}\u{648}",
+                "\u{623}\u{63a}\u{633}\u{637}\u{633}",
+                "\u{633}\u{628}\u{62a}\u{645}\u{628}\u{631}",
+                "\u{623}\u{643}\u{62a}\u{648}\u{628}\u{631}",
+                "\u{646}\u{648}\u{641}\u{645}\u{628}\u{631}",
+                "\u{62f}\u{64a}\u{633}\u{645}\u{628}\u{631}",
+            ],
+            weekday_names: [
+                "\u{646}", "\u{62b}", "\u{631}", "\u{62e}", "\u{62c}", "\u{633}", "\u{62d}",
+            ],
+            digits: [
+                '\u{660}', '\u{661}', '\u{662}', '\u{663}', '\u{664}', '\u{665}', '\u{666}',
+                '\u{667}', '\u{668}', '\u{669}',
+            ],
+            decimal_separator: '\u{66b}',
+            group_separator: Some('\u{66c}'),
+            date_order: DateOrder::DayMonthYear,
+            date_separator: '/',
+            hour_cycle: HourCycle::H12("\u{635}", "\u{645}"),
+            first_weekday: 5,
+            right_to_left: true,
+            pseudo: false,
+        }
+    }
+
+    /// Pseudo-locale (`en-XA`) based on `en_us`, which pseudo-localizes text, including the names of months and
+    /// weekdays.
+    pub fn pseudo() -> Self {
+        Locale {
+            tag: "en-XA",
+            month_names: [
+                "[\u{134}\u{e0}\u{f1}\u{fb}\u{e0}\u{155}\u{fd}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{191}\u{e9}\u{180}\u{155}\u{fb}\u{e0}\u{155}\u{fd}\u{b7}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{1e3e}\u{e0}\u{155}\u{e7}\u{125}\u{b7}\u{b7}\u{b7}]",
+                "[\u{c0}\u{1e55}\u{155}\u{ee}\u{13c}\u{b7}\u{b7}\u{b7}]",
+                "[\u{1e3e}\u{e0}\u{fd}\u{b7}\u{b7}]",
+                "[\u{134}\u{fb}\u{f1}\u{e9}\u{b7}\u{b7}\u{b7}]",
+                "[\u{134}\u{fb}\u{13c}\u{fd}\u{b7}\u{b7}\u{b7}]",
+                "[\u{c0}\u{fb}\u{11f}\u{fb}\u{161}\u{163}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{160}\u{e9}\u{1e55}\u{163}\u{e9}\u{1e3f}\u{180}\u{e9}\u{155}\u{b7}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{d6}\u{e7}\u{163}\u{f6}\u{180}\u{e9}\u{155}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{d1}\u{f6}\u{1e7d}\u{e9}\u{1e3f}\u{180}\u{e9}\u{155}\u{b7}\u{b7}\u{b7}\u{b7}\u{b7}]",
+                "[\u{110}\u{e9}\u{e7}\u{e9}\u{1e3f}\u{180}\u{e9}\u{155}\u{b7}\u{b7}\u{b7}\u{b7}\u{b7}]",
+            ],
+            weekday_names: [
+                "[\u{1e3e}\u{f6}\u{b7}\u{b7}]",
+                "[\u{162}\u{fb}\u{b7}\u{b7}]",
+                "[\u{174}\u{e9}\u{b7}\u{b7}]",
+                "[\u{162}\u{125}\u{b7}\u{b7}]",
+                "[\u{191}\u{155}\u{b7}\u{b7}]",
+                "[\u{160}\u{e0}\u{b7}\u{b7}]",
+                "[\u{160}\u{fb}\u{b7}\u{b7}]",
+            ],
+            pseudo: true,
+            ..Locale::en_us()
+        }
+    }
+
+    /// Returns the name of a month, from `1` to `12`.
+    pub fn month_name(&self, month: u32) -> &'static str {
+        self.month_names[(month.max(1) as usize - 1).min(11)]
+    }
+
+    /// Returns the abbreviated name of a weekday, in days since Monday.
+    #[inline]
+    pub fn weekday_name(&self, weekday: u32) -> &'static str {
+        self.weekday_names[weekday as usize % 7]
+    }
+
+    /// Replaces the ASCII digits in `text` with the digits of the locale.
+    pub fn localize_digits(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| c.to_digit(10).map_or(c, |digit| self.digits[digit as usize]))
+            .collect()
+    }
+
+    /// Returns text to be shown to the user; pseudo-localized (see `pseudo_localize`) if this is a pseudo-locale.
+    ///
+    /// Widgets pass their built-in text (such as placeholders and button labels) through the active locale when
+    /// created; applications should do the same with their own text to have it pseudo-localized.
+    pub fn localize_text(&self, text: &str) -> String {
+        if self.pseudo {
+            pseudo_localize(text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Formats an integer, separating groups of thousands (e.g. `1,234,567`).
+    pub fn format_integer(&self, value: i64) -> String {
+        let digits = (value as i128).abs().to_string();
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{}{}", sign, self.group_digits(&digits))
+    }
+
+    /// Formats a number with a fixed number of decimal places (e.g. `1,234.50`).
+    pub fn format_decimal(&self, value: f64, precision: usize) -> String {
+        let text = format!("{:.*}", precision, value.abs());
+        let (integer, fraction) = match text.find('.') {
+            Some(point) => (&text[..point], Some(&text[point + 1..])),
+            None => (&text[..], None),
+        };
+
+        // The sign is omitted if the number rounds to zero, so that e.g. `-0.001` isn't shown as `-0.00`.
+        let negative = value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0');
+        let mut formatted = if negative { "-".to_string() } else { String::new() };
+        formatted.push_str(&self.group_digits(integer));
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(&self.localize_digits(fraction));
+        }
+        formatted
+    }
+
+    /// Separates groups of thousands in a string of ASCII digits, and localizes the digits.
+    fn group_digits(&self, digits: &str) -> String {
+        let mut text = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                text.extend(self.group_separator);
+            }
+            text.push(digit);
+        }
+        self.localize_digits(&text)
+    }
+
+    /// Formats a date numerically, in the order of the locale (e.g. `01/31/2024`).
+    pub fn format_date(&self, date: Date) -> String {
+        let (year, month, day) =
+            (format!("{:04}", date.year), format!("{:02}", date.month), format!("{:02}", date.day));
+        let fields = match self.date_order {
+            DateOrder::YearMonthDay => [year, month, day],
+            DateOrder::DayMonthYear => [day, month, year],
+            DateOrder::MonthDayYear => [month, day, year],
+        };
+        self.localize_digits(&fields.join(&self.date_separator.to_string()))
+    }
+
+    /// Formats a date with the name of the month (e.g. `31 January 2024`, or `January 31, 2024` in the United States).
+    pub fn format_long_date(&self, date: Date) -> String {
+        let text = match self.date_order {
+            DateOrder::MonthDayYear => {
+                format!("{} {}, {}", self.month_name(date.month), date.day, date.year)
+            }
+            _ => format!("{} {} {}", date.day, self.month_name(date.month), date.year),
+        };
+        self.localize_digits(&text)
+    }
+
+    /// Formats a month and year (e.g. `January 2024`), as shown in the title of a calendar.
+    pub fn format_month_year(&self, year: i32, month: u32) -> String {
+        self.localize_digits(&format!("{} {}", self.month_name(month), year))
+    }
+
+    /// Formats a time of day, optionally with seconds (e.g. `1:05 PM`, or `13:05` on a 24-hour clock).
+    pub fn format_time(&self, hour: u32, minute: u32, second: Option<u32>) -> String {
+        let seconds = second.map_or_else(String::new, |second| format!(":{:02}", second));
+        let text = match self.hour_cycle {
+            HourCycle::H12(am, pm) => {
+                let twelve = if hour % 12 == 0 { 12 } else { hour % 12 };
+                format!(
+                    "{}:{:02}{} {}",
+                    twelve,
+                    minute,
+                    seconds,
+                    if hour % 24 < 12 { am } else { pm }
+                )
+            }
+            HourCycle::H24 => format!("{:02}:{:02}{}", hour % 24, minute, seconds),
+        };
+        self.localize_digits(&text)
+    }
+}
+
+/// Returns the accented counterpart of an ASCII letter, or the character itself.
+fn accent(c: char) -> char {
+    const LOWER: [char; 26] = [
+        '\u{e0}', '\u{180}', '\u{e7}', '\u{111}', '\u{e9}', '\u{192}', '\u{11f}', '\u{125}',
+        '\u{ee}', '\u{135}', '\u{137}', '\u{13c}', '\u{1e3f}', '\u{f1}', '\u{f6}', '\u{1e55}',
+        '\u{24b}', '\u{155}', '\u{161}', '\u{163}', '\u{fb}', '\u{1e7d}', '\u{175}', '\u{1e8b}',
+        '\u{fd}', '\u{17e}',
+    ];
+    const UPPER: [char; 26] = [
+        '\u{c0}', '\u{181}', '\u{c7}', '\u{110}', '\u{c9}', '\u{191}', '\u{11e}', '\u{124}',
+        '\u{ce}', '\u{134}', '\u{136}', '\u{13b}', '\u{1e3e}', '\u{d1}', '\u{d6}', '\u{1e54}',
+        '\u{24a}', '\u{154}', '\u{160}', '\u{162}', '\u{db}', '\u{1e7c}', '\u{174}', '\u{1e8a}',
+        '\u{dd}', '\u{17d}',
+    ];
+    match c {
+        'a'..='z' => LOWER[(c as u8 - b'a') as usize],
+        'A'..='Z' => UPPER[(c as u8 - b'A') as usize],
+        _ => c,
+    }
+}
+
+/// Pseudo-localizes text; accenting its letters, lengthening it and enclosing it in brackets (e.g. `Save` becomes
+/// `[Šàvé···]`).
+///
+/// The text is lengthened by about half (more than most translations from English), and the brackets make truncation
+/// visible on both ends.
+pub fn pseudo_localize(text: &str) -> String {
+    let length = text.chars().count();
+    let padding = if length == 0 { 0 } else { length / 2 + 1 };
+
+    let mut localized = String::with_capacity(text.len() * 2 + padding * 2 + 2);
+    localized.push('[');
+    localized.extend(text.chars().map(accent));
+    localized.extend(std::iter::repeat('\u{b7}').take(padding));
+    localized.push(']');
+    localized
+}
+
+thread_local! {
+    static ACTIVE_LOCALE: RefCell<Locale> = RefCell::new(Locale::default());
+}
+
+/// Returns the active locale of the current thread; `Locale::en_us` unless changed with `set_locale`.
+pub fn locale() -> Locale {
+    ACTIVE_LOCALE.with(|locale| locale.borrow().clone())
+}
+
+/// Changes the active locale of the current thread.
+///
+/// This only affects widgets created afterwards (and other formatting which uses the active locale, such as
+/// `ui::format_size`); existing widgets keep the locale in their data.
+pub fn set_locale(locale: Locale) {
+    ACTIVE_LOCALE.with(|active| *active.borrow_mut() = locale);
+}
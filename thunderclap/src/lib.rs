@@ -12,17 +12,24 @@ pub use thunderclap_macros::{
     rooftop, widget, DropNotifier, HasVisibility, LayableWidget, Movable, Repaintable, Resizable,
 };
 
-pub use paste;
+pub use {cassowary, paste};
 
+pub mod a11y;
 #[macro_use]
 pub mod base;
 pub mod draw;
 pub mod error;
+pub mod fuzzy;
 pub mod geom;
+pub mod history;
+#[cfg(feature = "core-widgets")]
+pub mod i18n;
+pub mod placement;
+pub mod recovery;
 #[cfg(feature = "core-widgets")]
 pub mod ui;
 
-#[cfg(feature = "app")]
+#[cfg(feature = "app-core")]
 pub mod app;
 #[cfg(feature = "default-themes")]
 pub mod themes;
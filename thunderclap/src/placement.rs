@@ -0,0 +1,208 @@
+//! Positioning of overlays (e.g. popups, dropdowns and tooltips) next to the widget they're anchored to.
+//!
+//! An overlay is placed against a side of its anchor, aligned along that side, and kept within the bounds of the
+//! window. If it doesn't fit on the preferred side, it's flipped to the opposite side when there's more room there.
+//! Callouts can also have an arrow pointing from the overlay to the anchor, which follows the anchor as the overlay
+//! is shifted to stay in bounds.
+
+use {crate::geom::*, reclutch::display::Size};
+
+/// Side of an anchor which an overlay is placed against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Returns the side across from this side.
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// Returns `true` for the top and bottom sides.
+    #[inline]
+    pub fn is_vertical(self) -> bool {
+        self == Side::Top || self == Side::Bottom
+    }
+}
+
+/// Alignment of an overlay along the side of its anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Align {
+    /// Left or top edges aligned.
+    Start,
+    Center,
+    /// Right or bottom edges aligned.
+    End,
+}
+
+/// How an overlay is placed relative to its anchor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Preferred side of the anchor.
+    pub side: Side,
+    pub align: Align,
+    /// Gap between the anchor and the overlay (including the arrow, if any).
+    pub offset: f32,
+    /// Minimum distance between the overlay and the edges of the bounds.
+    pub margin: f32,
+    /// Whether the overlay may be flipped to the opposite side when it doesn't fit on the preferred side.
+    pub flip: bool,
+    /// Length of the arrow pointing to the anchor, if any.
+    pub arrow: Option<f32>,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            side: Side::Bottom,
+            align: Align::Start,
+            offset: 4.0,
+            margin: 4.0,
+            flip: true,
+            arrow: None,
+        }
+    }
+}
+
+/// Result of placing an overlay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placed {
+    /// Rectangle of the overlay, excluding the arrow.
+    pub rect: AbsoluteRect,
+    /// Side of the anchor the overlay was placed against, which differs from the preferred side if it was flipped.
+    pub side: Side,
+    /// Base of the arrow (on the edge of the overlay facing the anchor) and its tip, if the placement has an arrow.
+    pub arrow: Option<(AbsolutePoint, AbsolutePoint)>,
+}
+
+impl Placement {
+    /// Places an overlay of a given size against `anchor`, within `bounds` (typically the window).
+    pub fn place(&self, anchor: AbsoluteRect, size: Size, bounds: AbsoluteRect) -> Placed {
+        let bounds = bounds.inflate(-self.margin, -self.margin);
+        let gap = self.offset.max(self.arrow.unwrap_or(0.0));
+
+        // room between the anchor and the edge of the bounds on a side
+        let room = |side: Side| match side {
+            Side::Top => anchor.min_y() - bounds.min_y(),
+            Side::Bottom => bounds.max_y() - anchor.max_y(),
+            Side::Left => anchor.min_x() - bounds.min_x(),
+            Side::Right => bounds.max_x() - anchor.max_x(),
+        };
+        let needed = |side: Side| gap + if side.is_vertical() { size.height } else { size.width };
+
+        let side = if self.flip
+            && room(self.side) < needed(self.side)
+            && room(self.side.opposite()) > room(self.side)
+        {
+            self.side.opposite()
+        } else {
+            self.side
+        };
+
+        let x = match side {
+            Side::Left => anchor.min_x() - gap - size.width,
+            Side::Right => anchor.max_x() + gap,
+            _ => align(self.align, anchor.min_x(), anchor.max_x(), size.width),
+        };
+        let y = match side {
+            Side::Top => anchor.min_y() - gap - size.height,
+            Side::Bottom => anchor.max_y() + gap,
+            _ => align(self.align, anchor.min_y(), anchor.max_y(), size.height),
+        };
+
+        // shifted to stay in bounds, favouring the top-left edges if it's larger than the bounds
+        let x = x.min(bounds.max_x() - size.width).max(bounds.min_x());
+        let y = y.min(bounds.max_y() - size.height).max(bounds.min_y());
+        let rect = AbsoluteRect::new(AbsolutePoint::new(x, y), size.cast_unit());
+
+        let arrow = self.arrow.map(|length| {
+            // the arrow stays on the overlay, pointing at the center of the anchor where possible
+            let center = anchor.center();
+            let (cx, cy) = (
+                center.x.max(rect.min_x() + length).min(rect.max_x() - length),
+                center.y.max(rect.min_y() + length).min(rect.max_y() - length),
+            );
+            match side {
+                Side::Top => (
+                    AbsolutePoint::new(cx, rect.max_y()),
+                    AbsolutePoint::new(cx, rect.max_y() + length),
+                ),
+                Side::Bottom => (
+                    AbsolutePoint::new(cx, rect.min_y()),
+                    AbsolutePoint::new(cx, rect.min_y() - length),
+                ),
+                Side::Left => (
+                    AbsolutePoint::new(rect.max_x(), cy),
+                    AbsolutePoint::new(rect.max_x() + length, cy),
+                ),
+                Side::Right => (
+                    AbsolutePoint::new(rect.min_x(), cy),
+                    AbsolutePoint::new(rect.min_x() - length, cy),
+                ),
+            }
+        });
+
+        Placed { rect, side, arrow }
+    }
+}
+
+/// Returns the start of a span of `length` aligned within `start..end`.
+fn align(align: Align, start: f32, end: f32, length: f32) -> f32 {
+    match align {
+        Align::Start => start,
+        Align::Center => (start + end - length) / 2.0,
+        Align::End => end - length,
+    }
+}
+
+/// Keeps an overlay placed against an anchor which can move, only re-placing it when something changed.
+///
+/// The owner of the overlay calls `update` every update with the current anchor, size and bounds,
+/// and repositions the overlay whenever a new placement is returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchoredPlacement {
+    pub placement: Placement,
+    inputs: Option<(AbsoluteRect, Size, AbsoluteRect, Placement)>,
+    placed: Option<Placed>,
+}
+
+impl AnchoredPlacement {
+    /// Creates an anchored placement which hasn't been placed yet.
+    pub fn new(placement: Placement) -> Self {
+        AnchoredPlacement { placement, inputs: None, placed: None }
+    }
+
+    /// Places the overlay again if the anchor, size, bounds or placement changed since the last update,
+    /// returning the new placement.
+    pub fn update(
+        &mut self,
+        anchor: AbsoluteRect,
+        size: Size,
+        bounds: AbsoluteRect,
+    ) -> Option<Placed> {
+        let inputs = (anchor, size, bounds, self.placement);
+        if self.inputs == Some(inputs) {
+            return None;
+        }
+
+        self.inputs = Some(inputs);
+        let placed = self.placement.place(anchor, size, bounds);
+        self.placed = Some(placed);
+        Some(placed)
+    }
+
+    /// Returns the last placement, if placed.
+    #[inline]
+    pub fn placed(&self) -> Option<Placed> {
+        self.placed
+    }
+}
@@ -0,0 +1,318 @@
+//! Crash recovery of unsaved content.
+//!
+//! While an app is running, content worth recovering (typically the text of text areas, but any state which can be
+//! written as a string) is stored under a key in a [`Recovery`](struct.Recovery.html), and periodically autosaved to a
+//! recovery directory. A lock file is kept in the directory for the duration of the session, and removed when the
+//! session is closed cleanly. If the lock file is still present when the directory is next opened, the previous
+//! session ended unexpectedly and its content is offered for recovery.
+//!
+//! Only one session may use a recovery directory at a time; a second instance of an app sharing the directory
+//! would see the lock file of the first as an unclean shutdown.
+
+use {
+    indexmap::IndexMap,
+    std::{
+        cell::RefCell,
+        fs, io,
+        path::PathBuf,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+
+const LOCK_FILE: &str = "session.lock";
+const SNAPSHOT_FILE: &str = "recovery.dat";
+const SNAPSHOT_TEMP_FILE: &str = "recovery.dat.tmp";
+const SNAPSHOT_HEADER: &str = "thunderclap-recovery 1";
+
+#[derive(Debug)]
+struct RecoveryInner {
+    directory: PathBuf,
+    unclean: bool,
+    /// Content recovered from the previous session, not yet restored or discarded.
+    recovered: IndexMap<String, String>,
+    /// Content of the current session.
+    entries: IndexMap<String, String>,
+    dirty: bool,
+    interval: Duration,
+    last_save: Instant,
+    closed: bool,
+}
+
+/// Shared handle to the crash recovery state of a session.
+///
+/// Cloning the handle shares the same state. The recovery snapshot is only written by `save` and `autosave`;
+/// `autosave` is called periodically by `app::App` when the recovery is given in `AppOptions`, which also closes the
+/// session when the window is closed.
+#[derive(Debug, Clone)]
+pub struct Recovery(Rc<RefCell<RecoveryInner>>);
+
+impl PartialEq for Recovery {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Recovery {
+    /// Starts a session in `directory`, creating it if needed.
+    ///
+    /// If the previous session in the directory ended without being closed, its content becomes available through
+    /// `recovered`. A snapshot which can't be read is treated as empty (the shutdown is still reported as unclean).
+    pub fn open(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        let lock = directory.join(LOCK_FILE);
+        let unclean = lock.exists();
+        let recovered = if unclean {
+            fs::read_to_string(directory.join(SNAPSHOT_FILE))
+                .ok()
+                .and_then(|snapshot| parse_snapshot(&snapshot))
+                .unwrap_or_default()
+        } else {
+            IndexMap::new()
+        };
+
+        fs::write(&lock, format!("{}", std::process::id()))?;
+
+        Ok(Recovery(Rc::new(RefCell::new(RecoveryInner {
+            directory,
+            unclean,
+            recovered,
+            entries: IndexMap::new(),
+            dirty: false,
+            interval: Duration::from_secs(30),
+            last_save: Instant::now(),
+            closed: false,
+        }))))
+    }
+
+    /// Returns a handle to the content stored under `key`, for use by a single widget.
+    pub fn key(&self, key: impl Into<String>) -> RecoveryKey {
+        RecoveryKey { recovery: self.clone(), key: key.into() }
+    }
+
+    /// Returns `true` if the previous session in the directory ended without being closed (e.g. it crashed).
+    #[inline]
+    pub fn was_unclean(&self) -> bool {
+        self.0.borrow().unclean
+    }
+
+    /// Returns `true` if there's recovered content which hasn't been restored or discarded.
+    #[inline]
+    pub fn has_recovered(&self) -> bool {
+        !self.0.borrow().recovered.is_empty()
+    }
+
+    /// Returns the keys of the recovered content which hasn't been restored or discarded, in the order they were
+    /// first stored.
+    pub fn recovered_keys(&self) -> Vec<String> {
+        self.0.borrow().recovered.keys().cloned().collect()
+    }
+
+    /// Returns the recovered content of `key`, without restoring it.
+    pub fn recovered(&self, key: &str) -> Option<String> {
+        self.0.borrow().recovered.get(key).cloned()
+    }
+
+    /// Removes and returns the recovered content of `key`, to be put back into the widget it belongs to.
+    pub fn restore(&self, key: &str) -> Option<String> {
+        let mut inner = self.0.borrow_mut();
+        let content = inner.recovered.shift_remove(key);
+        if content.is_some() {
+            inner.dirty = true;
+        }
+        content
+    }
+
+    /// Discards all the recovered content (e.g. when the user declines to restore it).
+    pub fn discard_recovered(&self) {
+        let mut inner = self.0.borrow_mut();
+        if !inner.recovered.is_empty() {
+            inner.recovered.clear();
+            inner.dirty = true;
+        }
+    }
+
+    /// Stores the content of `key` for the current session, replacing the previous content.
+    pub fn store(&self, key: &str, content: &str) {
+        let mut inner = self.0.borrow_mut();
+        if inner.entries.get(key).map(String::as_str) != Some(content) {
+            inner.entries.insert(key.to_string(), content.to_string());
+            inner.dirty = true;
+        }
+    }
+
+    /// Removes the content of `key` from the current session (e.g. once it's been saved by the app).
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.0.borrow_mut();
+        if inner.entries.shift_remove(key).is_some() {
+            inner.dirty = true;
+        }
+    }
+
+    /// Removes all the content of the current session.
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        if !inner.entries.is_empty() {
+            inner.entries.clear();
+            inner.dirty = true;
+        }
+    }
+
+    /// Returns the minimum time between autosaves.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.0.borrow().interval
+    }
+
+    /// Changes the minimum time between autosaves (30 seconds by default).
+    #[inline]
+    pub fn set_interval(&self, interval: Duration) {
+        self.0.borrow_mut().interval = interval;
+    }
+
+    /// Saves the snapshot if anything changed and the autosave interval has elapsed since the last save,
+    /// returning whether it was saved.
+    pub fn autosave(&self) -> io::Result<bool> {
+        let due = {
+            let mut inner = self.0.borrow_mut();
+            let due = inner.dirty && !inner.closed && inner.last_save.elapsed() >= inner.interval;
+            if due {
+                // A failed autosave waits for the next interval rather than being retried immediately.
+                inner.last_save = Instant::now();
+            }
+            due
+        };
+        if due {
+            self.save()?;
+        }
+        Ok(due)
+    }
+
+    /// Saves the snapshot immediately.
+    ///
+    /// Content recovered from the previous session is saved along with the current session until it's restored or
+    /// discarded, so that it survives another crash (content of the current session is recovered in its place if both
+    /// have the same key).
+    pub fn save(&self) -> io::Result<()> {
+        let mut inner = self.0.borrow_mut();
+        if inner.closed {
+            return Ok(());
+        }
+
+        let snapshot = inner.directory.join(SNAPSHOT_FILE);
+        if inner.entries.is_empty() && inner.recovered.is_empty() {
+            remove_if_exists(&snapshot)?;
+        } else {
+            // The snapshot is replaced atomically so that a crash mid-write leaves the previous snapshot intact.
+            let temp = inner.directory.join(SNAPSHOT_TEMP_FILE);
+            fs::write(&temp, write_snapshot(inner.recovered.iter().chain(inner.entries.iter())))?;
+            fs::rename(&temp, &snapshot)?;
+        }
+
+        inner.dirty = false;
+        inner.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Ends the session cleanly, removing the snapshot and lock file. Nothing is saved after the session is closed.
+    ///
+    /// Content which was recovered but neither restored nor discarded is lost.
+    pub fn close(&self) -> io::Result<()> {
+        let mut inner = self.0.borrow_mut();
+        if inner.closed {
+            return Ok(());
+        }
+        inner.closed = true;
+
+        remove_if_exists(&inner.directory.join(SNAPSHOT_FILE))?;
+        remove_if_exists(&inner.directory.join(LOCK_FILE))
+    }
+}
+
+/// Handle to the content of a single key in a [`Recovery`](struct.Recovery.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryKey {
+    recovery: Recovery,
+    key: String,
+}
+
+impl RecoveryKey {
+    /// Returns the session this key belongs to.
+    #[inline]
+    pub fn recovery(&self) -> &Recovery {
+        &self.recovery
+    }
+
+    /// Returns the key.
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Stores the content of this key for the current session.
+    #[inline]
+    pub fn store(&self, content: &str) {
+        self.recovery.store(&self.key, content)
+    }
+
+    /// Returns the recovered content of this key, without restoring it.
+    #[inline]
+    pub fn recovered(&self) -> Option<String> {
+        self.recovery.recovered(&self.key)
+    }
+
+    /// Removes and returns the recovered content of this key.
+    #[inline]
+    pub fn restore(&self) -> Option<String> {
+        self.recovery.restore(&self.key)
+    }
+}
+
+fn remove_if_exists(path: &std::path::Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Each entry is written as a line containing the byte lengths of the key and content, followed by the key and content.
+fn write_snapshot<'a>(entries: impl Iterator<Item = (&'a String, &'a String)>) -> String {
+    let mut snapshot = format!("{}\n", SNAPSHOT_HEADER);
+    for (key, content) in entries {
+        snapshot.push_str(&format!("{} {}\n{}{}\n", key.len(), content.len(), key, content));
+    }
+    snapshot
+}
+
+fn parse_snapshot(snapshot: &str) -> Option<IndexMap<String, String>> {
+    let header = format!("{}\n", SNAPSHOT_HEADER);
+    if !snapshot.starts_with(&header) {
+        return None;
+    }
+
+    let mut rest = &snapshot[header.len()..];
+    let mut entries = IndexMap::new();
+    while !rest.is_empty() {
+        let line_end = rest.find('\n')?;
+        let mut lengths = rest[..line_end].split(' ').map(|length| length.parse::<usize>().ok());
+        let key_length = lengths.next()??;
+        let content_length = lengths.next()??;
+
+        let key_start = line_end + 1;
+        let content_start = key_start.checked_add(key_length)?;
+        let end = content_start.checked_add(content_length)?;
+        let key = rest.get(key_start..content_start)?;
+        let content = rest.get(content_start..end)?;
+        entries.insert(key.to_string(), content.to_string());
+
+        rest = rest.get(end..)?;
+        if !rest.starts_with('\n') {
+            return None;
+        }
+        rest = &rest[1..];
+    }
+    Some(entries)
+}
@@ -5,6 +5,9 @@ use crate::draw::ThemeData;
 mod dynamic;
 mod primer;
 
+#[cfg(test)]
+pub(crate) use self::primer::testing;
+
 /// GitHub's "Primer" theme, based off the CSS widgets.
 pub struct Primer {
     data: ThemeData,
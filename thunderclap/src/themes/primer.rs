@@ -7,11 +7,12 @@ use {
         geom::*,
     },
     reclutch::display::{
-        self, Color, DisplayCommand, DisplayListBuilder, Filter, FontInfo, Gradient,
-        GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, Rect, ResourceData,
+        self, Color, DisplayClip, DisplayCommand, DisplayListBuilder, Filter, FontInfo, Gradient,
+        GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, Point, Rect, ResourceData,
         ResourceDescriptor, ResourceReference, SharedData, Size, StyleColor, TextDisplayItem,
         Vector, VectorPath, VectorPathBuilder,
     },
+    std::ops::Range,
 };
 
 fn check_mark_icon(rect: Rect) -> VectorPath {
@@ -27,38 +28,201 @@ fn check_mark_icon(rect: Rect) -> VectorPath {
     builder.build()
 }
 
+/// Chevron pointing right when collapsed and down when expanded.
+fn chevron_icon(center: Point, half: f32, expanded: bool) -> VectorPath {
+    let mut builder = VectorPathBuilder::new();
+
+    if expanded {
+        builder.move_to(center + Size::new(-half, -half / 2.0));
+        builder.line_to(center + Size::new(0.0, half / 2.0));
+        builder.line_to(center + Size::new(half, -half / 2.0));
+    } else {
+        builder.move_to(center + Size::new(-half / 2.0, -half));
+        builder.line_to(center + Size::new(half / 2.0, 0.0));
+        builder.line_to(center + Size::new(-half / 2.0, half));
+    }
+
+    builder.build()
+}
+
+/// Outline of an eye, with the corners at `center` plus and minus `half` horizontally.
+fn eye_icon(center: Point, half: f32) -> VectorPath {
+    const SEGMENTS: usize = 8;
+
+    let mut builder = VectorPathBuilder::new();
+
+    builder.move_to(center - Vector::new(half, 0.0));
+    // upper lid from left to right, then lower lid back from right to left
+    for &(from, to, lid) in &[(-1.0f32, 1.0f32, -1.0f32), (1.0, -1.0, 1.0)] {
+        for i in 1..=SEGMENTS {
+            let x = from + (to - from) * i as f32 / SEGMENTS as f32;
+            builder.line_to(center + Vector::new(x * half, lid * (1.0 - x * x) * half * 0.6));
+        }
+    }
+
+    builder.build()
+}
+
+/// Arc around `center`, from angle `start` to angle `end` (in radians, clockwise from the positive x-axis).
+fn arc_path(center: Point, radius: f32, start: f32, end: f32) -> VectorPath {
+    let mut builder = VectorPathBuilder::new();
+
+    // enough segments to look smooth at typical sizes
+    let segments = ((end - start).abs() / std::f32::consts::PI * 24.0).ceil().max(1.0) as usize;
+    let point = |angle: f32| center + Size::new(angle.cos() * radius, angle.sin() * radius);
+    builder.move_to(point(start));
+    for i in 1..=segments {
+        builder.line_to(point(start + (end - start) * i as f32 / segments as f32));
+    }
+
+    builder.build()
+}
+
+/// Splits a text item into consecutive items, such that the byte `ranges` of the text are drawn with a different font and color.
+///
+/// Used to highlight characters of item labels, such as those matched by a search.
+fn highlight_text_item(
+    item: TextDisplayItem,
+    ranges: &[Range<usize>],
+    font: (ResourceReference, FontInfo),
+    color: StyleColor,
+) -> Vec<TextDisplayItem> {
+    let text = match item.text {
+        display::DisplayText::Simple(ref text) if !ranges.is_empty() => text.clone(),
+        _ => return vec![item],
+    };
+
+    let mut items = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut x = item.bottom_left.x;
+    let mut push_segment = |range: Range<usize>, highlighted: bool| {
+        let segment = match text.get(range) {
+            Some(segment) if !segment.is_empty() => segment,
+            _ => return,
+        };
+        let mut segment_item = TextDisplayItem {
+            text: segment.to_string().into(),
+            bottom_left: Point::new(x, item.bottom_left.y),
+            ..item.clone()
+        };
+        if highlighted {
+            segment_item.font = font.0;
+            segment_item.font_info = font.1.clone();
+            segment_item.color = color.clone();
+        }
+        x += segment_item.bounds().map(|bounds| bounds.size.width).unwrap_or(0.0);
+        items.push(segment_item);
+    };
+
+    let mut end = 0;
+    for range in ranges {
+        push_segment(end..range.start, false);
+        push_segment(range.clone(), true);
+        end = range.end;
+    }
+    push_segment(end..text.len(), false);
+
+    items
+}
+
+/// Wraps display commands such that they're clipped to a rectangle.
+fn clipped(rect: Rect, commands: Vec<DisplayCommand>) -> Vec<DisplayCommand> {
+    let mut clipped = Vec::with_capacity(commands.len() + 3);
+    clipped.push(DisplayCommand::Save);
+    clipped.push(DisplayCommand::Clip(DisplayClip::Rectangle { rect, antialias: true }));
+    clipped.extend(commands);
+    clipped.push(DisplayCommand::Restore);
+    clipped
+}
+
+/// Lays out a label of `text` size beside an optional icon (its size, spacing and placement), in a row starting at
+/// `origin` and centered vertically within `height`. Returns the top-left of the label and the rectangle of the icon.
+fn icon_label_layout(
+    origin: Point,
+    height: f32,
+    text: Size,
+    icon: Option<(f32, f32, draw::IconPlacement)>,
+) -> (Point, Option<Rect>) {
+    let text_y = origin.y + (height - text.height) / 2.0;
+    match icon {
+        Some((size, spacing, placement)) => {
+            let icon_y = origin.y + (height - size) / 2.0;
+            let spacing = if text.width > 0.0 { spacing } else { 0.0 };
+            let (text_x, icon_x) = match placement {
+                draw::IconPlacement::Before => (origin.x + size + spacing, origin.x),
+                draw::IconPlacement::After => (origin.x, origin.x + text.width + spacing),
+            };
+            (
+                Point::new(text_x, text_y),
+                Some(Rect::new(Point::new(icon_x, icon_y), Size::new(size, size))),
+            )
+        }
+        None => (Point::new(origin.x, text_y), None),
+    }
+}
+
+/// Returns the size of a label of `text` size beside an optional icon (see `icon_label_layout`).
+fn icon_label_size(text: Size, icon: Option<(f32, f32, draw::IconPlacement)>) -> Size {
+    match icon {
+        Some((size, spacing, _)) => Size::new(
+            text.width + size + if text.width > 0.0 { spacing } else { 0.0 },
+            text.height.max(size),
+        ),
+        None => text,
+    }
+}
+
+/// Draws `icon` into `rect`, covered by `tint` if any (e.g. to fade it out while disabled).
+fn icon_commands(
+    icon: &draw::Icon,
+    rect: Rect,
+    resource: Option<ResourceReference>,
+    tint: Option<Color>,
+) -> Vec<DisplayCommand> {
+    let mut commands = icon.draw(rect, resource);
+    if let Some(tint) = tint {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(tint.into()), None);
+        commands.extend(builder.build());
+    }
+    commands
+}
+
+/// Regular, italic, bold and bold italic fonts of the theme.
+fn font_data() -> [std::sync::Arc<Vec<u8>>; 4] {
+    [
+        std::sync::Arc::new(include_bytes!("assets/Inter-Regular.ttf").to_vec()),
+        std::sync::Arc::new(include_bytes!("assets/Inter-Italic.ttf").to_vec()),
+        std::sync::Arc::new(include_bytes!("assets/Inter-SemiBold.ttf").to_vec()),
+        std::sync::Arc::new(include_bytes!("assets/Inter-SemiBoldItalic.ttf").to_vec()),
+    ]
+}
+
 impl Primer {
     /// Creates an instance of the GitHub Primer theme.
     pub fn new(display: &mut dyn GraphicsDisplay) -> Result<Self, error::ThemeError> {
-        let typeface = {
-            let fonts = &[
-                std::sync::Arc::new(include_bytes!("assets/Inter-Regular.ttf").to_vec()),
-                std::sync::Arc::new(include_bytes!("assets/Inter-Italic.ttf").to_vec()),
-                std::sync::Arc::new(include_bytes!("assets/Inter-SemiBold.ttf").to_vec()),
-                std::sync::Arc::new(include_bytes!("assets/Inter-SemiBoldItalic.ttf").to_vec()),
-            ];
-
-            let fonts: Vec<(ResourceReference, FontInfo)> = fonts
-                .iter()
-                .map(|font| -> Result<(ResourceReference, FontInfo), error::ThemeError> {
-                    let font_info = FontInfo::from_data(font.clone(), 0)?;
-                    let font_resource = display.new_resource(ResourceDescriptor::Font(
-                        ResourceData::Data(SharedData::RefCount(font.clone())),
-                    ))?;
+        let fonts: Vec<(ResourceReference, FontInfo)> = font_data()
+            .iter()
+            .map(|font| -> Result<(ResourceReference, FontInfo), error::ThemeError> {
+                let font_info = FontInfo::from_data(font.clone(), 0)?;
+                let font_resource = display.new_resource(ResourceDescriptor::Font(
+                    ResourceData::Data(SharedData::RefCount(font.clone())),
+                ))?;
 
-                    Ok((font_resource, font_info))
-                })
-                .collect::<Result<Vec<_>, _>>()?;
+                Ok((font_resource, font_info))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-            draw::Typeface {
-                regular: fonts[0].clone(),
-                italic: fonts[1].clone(),
-                bold: fonts[2].clone(),
-                bold_italic: fonts[3].clone(),
-            }
-        };
+        Ok(Primer::with_typeface(draw::Typeface {
+            regular: fonts[0].clone(),
+            italic: fonts[1].clone(),
+            bold: fonts[2].clone(),
+            bold_italic: fonts[3].clone(),
+        }))
+    }
 
-        Ok(Primer {
+    /// Creates an instance of the theme with its fonts already loaded into the display.
+    fn with_typeface(typeface: draw::Typeface) -> Self {
+        Primer {
             data: draw::ThemeData {
                 scheme: draw::ColorScheme {
                     background: base::color_from_urgba(255, 255, 255, 1.0),
@@ -67,11 +231,19 @@ impl Primer {
                     primary: base::color_from_urgba(46, 186, 78, 1.0),
                     control_outset: base::color_from_urgba(244, 247, 249, 1.0),
                     control_inset: base::color_from_urgba(255, 255, 255, 1.0),
+                    text_selection: base::color_from_urgba(200, 225, 255, 1.0),
+                    list_selection: base::color_from_urgba(3, 102, 214, 1.0),
+                    table_selection: base::color_from_urgba(3, 102, 214, 1.0),
+                    inactive_selection: base::color_from_urgba(225, 228, 232, 1.0),
                     over_error: base::color_from_urgba(255, 255, 255, 1.0),
                     over_focus: base::color_from_urgba(255, 255, 255, 1.0),
                     over_primary: base::color_from_urgba(255, 255, 255, 1.0),
                     over_control_outset: base::color_from_urgba(36, 41, 46, 1.0),
                     over_control_inset: base::color_from_urgba(36, 41, 46, 1.0),
+                    over_text_selection: base::color_from_urgba(36, 41, 46, 1.0),
+                    over_list_selection: base::color_from_urgba(255, 255, 255, 1.0),
+                    over_table_selection: base::color_from_urgba(255, 255, 255, 1.0),
+                    over_inactive_selection: base::color_from_urgba(36, 41, 46, 1.0),
                 },
                 typography: draw::Typography {
                     header: draw::TypefaceStyle {
@@ -96,14 +268,32 @@ impl Primer {
                     },
                 },
                 contrast: draw::ThemeContrast::Light,
+                icons: draw::IconMetrics { size: 16.0, spacing: 6.0 },
+                styles: draw::style::StyleSheet::new()
+                    .rule(
+                        draw::style::StyleRule::new("primary")
+                            .color("background", base::color_from_urgba(46, 186, 78, 1.0))
+                            .color("color", base::color_from_urgba(255, 255, 255, 1.0)),
+                    )
+                    .rule(
+                        draw::style::StyleRule::new("danger")
+                            .color("background", base::color_from_urgba(211, 50, 63, 1.0))
+                            .color("color", base::color_from_urgba(255, 255, 255, 1.0))
+                            .color("focus", base::color_from_urgba(211, 50, 63, 0.3)),
+                    )
+                    .rule(
+                        draw::style::StyleRule::new("compact")
+                            .number("horizontal_padding", 6.0)
+                            .number("vertical_padding", 2.0),
+                    ),
             },
-        })
+        }
     }
 }
 
 impl draw::Theme for Primer {
     fn button(&self) -> Box<dyn draw::Painter<state::ButtonState>> {
-        Box::new(ButtonPainter)
+        Box::new(ButtonPainter { styles: self.data.styles.clone() })
     }
 
     fn checkbox(&self) -> Box<dyn draw::Painter<state::CheckboxState>> {
@@ -118,20 +308,220 @@ impl draw::Theme for Primer {
         Box::new(ScrollBarPainter)
     }
 
+    fn dialog(&self) -> Box<dyn draw::Painter<state::DialogState>> {
+        Box::new(DialogPainter)
+    }
+
+    fn sketch_canvas(&self) -> Box<dyn draw::Painter<state::SketchCanvasState>> {
+        Box::new(SketchCanvasPainter)
+    }
+
+    fn annotation_canvas(&self) -> Box<dyn draw::Painter<state::AnnotationCanvasState>> {
+        Box::new(AnnotationCanvasPainter)
+    }
+
+    fn toast(&self) -> Box<dyn draw::Painter<state::ToastState>> {
+        Box::new(ToastPainter)
+    }
+
+    fn collapsible(&self) -> Box<dyn draw::Painter<state::CollapsibleState>> {
+        Box::new(CollapsiblePainter)
+    }
+
+    fn cropper(&self) -> Box<dyn draw::Painter<state::CropperState>> {
+        Box::new(CropperPainter)
+    }
+
+    fn split_pane(&self) -> Box<dyn draw::Painter<state::SplitPaneState>> {
+        Box::new(SplitPanePainter)
+    }
+
+    fn dock(&self) -> Box<dyn draw::Painter<state::DockState>> {
+        Box::new(DockPainter)
+    }
+
+    fn tile_view(&self) -> Box<dyn draw::Painter<state::TileViewState>> {
+        Box::new(TileViewPainter)
+    }
+
+    fn video_surface(&self) -> Box<dyn draw::Painter<state::VideoSurfaceState>> {
+        Box::new(VideoSurfacePainter)
+    }
+
+    fn surface(&self) -> Box<dyn draw::Painter<state::SurfaceState>> {
+        Box::new(SurfacePainter)
+    }
+
+    fn level_meter(&self) -> Box<dyn draw::Painter<state::LevelMeterState>> {
+        Box::new(LevelMeterPainter)
+    }
+
+    fn waveform(&self) -> Box<dyn draw::Painter<state::WaveformState>> {
+        Box::new(WaveformPainter)
+    }
+
+    fn date_picker(&self) -> Box<dyn draw::Painter<state::DatePickerState>> {
+        Box::new(DatePickerPainter)
+    }
+
+    fn file_browser(&self) -> Box<dyn draw::Painter<state::FileBrowserState>> {
+        Box::new(FileBrowserPainter)
+    }
+
+    fn image(&self) -> Box<dyn draw::Painter<state::ImageState>> {
+        Box::new(ImagePainter)
+    }
+
+    fn clipboard_history_picker(
+        &self,
+    ) -> Box<dyn draw::Painter<state::ClipboardHistoryPickerState>> {
+        Box::new(ClipboardHistoryPickerPainter)
+    }
+
+    fn radial_menu(&self) -> Box<dyn draw::Painter<state::RadialMenuState>> {
+        Box::new(RadialMenuPainter)
+    }
+
+    fn badge(&self) -> Box<dyn draw::Painter<state::BadgeState>> {
+        Box::new(BadgePainter)
+    }
+
+    fn chip_input(&self) -> Box<dyn draw::Painter<state::ChipInputState>> {
+        Box::new(ChipInputPainter)
+    }
+
+    fn knob(&self) -> Box<dyn draw::Painter<state::KnobState>> {
+        Box::new(KnobPainter)
+    }
+
+    fn gauge(&self) -> Box<dyn draw::Painter<state::GaugeState>> {
+        Box::new(GaugePainter)
+    }
+
+    fn chart(&self) -> Box<dyn draw::Painter<state::ChartState>> {
+        Box::new(ChartPainter)
+    }
+
+    fn sparkline(&self) -> Box<dyn draw::Painter<state::SparklineState>> {
+        Box::new(SparklinePainter)
+    }
+
+    fn pagination(&self) -> Box<dyn draw::Painter<state::PaginationState>> {
+        Box::new(PaginationPainter)
+    }
+
+    fn tree_table(&self) -> Box<dyn draw::Painter<state::TreeTableState>> {
+        Box::new(TreeTablePainter)
+    }
+
+    fn property_grid(&self) -> Box<dyn draw::Painter<state::PropertyGridState>> {
+        Box::new(PropertyGridPainter)
+    }
+
+    fn swatch_grid(&self) -> Box<dyn draw::Painter<state::SwatchGridState>> {
+        Box::new(SwatchGridPainter)
+    }
+
+    fn carousel(&self) -> Box<dyn draw::Painter<state::CarouselState>> {
+        Box::new(CarouselPainter)
+    }
+
+    fn fab(&self) -> Box<dyn draw::Painter<state::FabState>> {
+        Box::new(FabPainter)
+    }
+
+    fn masked_input(&self) -> Box<dyn draw::Painter<state::MaskedInputState>> {
+        Box::new(MaskedInputPainter)
+    }
+
+    fn auto_complete(&self) -> Box<dyn draw::Painter<state::AutoCompleteState>> {
+        Box::new(AutoCompletePainter)
+    }
+
+    fn title_bar(&self) -> Box<dyn draw::Painter<state::TitleBarState>> {
+        Box::new(TitleBarPainter)
+    }
+
+    fn minimap(&self) -> Box<dyn draw::Painter<state::MinimapState>> {
+        Box::new(MinimapPainter)
+    }
+
+    fn wizard(&self) -> Box<dyn draw::Painter<state::WizardState>> {
+        Box::new(WizardPainter)
+    }
+
+    fn card(&self) -> Box<dyn draw::Painter<state::CardState>> {
+        Box::new(CardPainter)
+    }
+
+    fn group_box(&self) -> Box<dyn draw::Painter<state::GroupBoxState>> {
+        Box::new(GroupBoxPainter)
+    }
+
+    fn avatar(&self) -> Box<dyn draw::Painter<state::AvatarState>> {
+        Box::new(AvatarPainter)
+    }
+
+    fn search_box(&self) -> Box<dyn draw::Painter<state::SearchBoxState>> {
+        Box::new(SearchBoxPainter)
+    }
+
+    fn password_box(&self) -> Box<dyn draw::Painter<state::PasswordBoxState>> {
+        Box::new(PasswordBoxPainter)
+    }
+
+    fn code_editor(&self) -> Box<dyn draw::Painter<state::CodeEditorState>> {
+        Box::new(CodeEditorPainter)
+    }
+
+    fn log_view(&self) -> Box<dyn draw::Painter<state::LogViewState>> {
+        Box::new(LogViewPainter)
+    }
+
+    #[cfg(feature = "extra-widgets")]
+    fn qr_code(&self) -> Box<dyn draw::Painter<state::QrCodeState>> {
+        Box::new(QrCodePainter)
+    }
+
     fn data(&self) -> &draw::ThemeData {
         &self.data
     }
+
+    fn name(&self) -> &str {
+        "Primer"
+    }
+
+    fn color_pairs(&self) -> Vec<draw::audit::ColorPair> {
+        let scheme = &self.data.scheme;
+        let mut pairs = draw::audit::scheme_pairs(scheme);
+        pairs.push(draw::audit::ColorPair::new(
+            "placeholder text",
+            draw::weaken(scheme.over_control_inset, 0.5, self.data.contrast),
+            scheme.control_inset,
+            draw::audit::ColorUsage::Text,
+        ));
+        pairs
+    }
 }
 
-struct ButtonPainter;
+struct ButtonPainter {
+    styles: draw::style::StyleSheet,
+}
 
 impl ButtonPainter {
-    fn make_text_item(
-        &self,
-        state: &state::ButtonState,
-        color: StyleColor,
-        centered: bool,
-    ) -> TextDisplayItem {
+    /// Applies the color overrides of the classes of the button.
+    fn styled(&self, mut state: state::ButtonState) -> state::ButtonState {
+        let (styles, classes) = (&self.styles, &state.data.classes);
+        let color = styles.color("button", classes, "color", state.data.color);
+        let background = styles.color("button", classes, "background", state.data.background);
+        let focus = styles.color("button", classes, "focus", state.data.focus);
+        state.data.color = color;
+        state.data.background = background;
+        state.data.focus = focus;
+        state
+    }
+
+    fn make_text_item(&self, state: &state::ButtonState, color: StyleColor) -> TextDisplayItem {
         let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
         let mut text_item = TextDisplayItem {
             text: state.data.text.clone(),
@@ -142,14 +532,18 @@ impl ButtonPainter {
             color,
         };
 
-        text_item.set_top_left(if centered {
-            display::center(text_item.bounds().unwrap().size, state.rect.cast_unit())
-        } else {
-            state.rect.origin.cast_unit()
-        });
+        text_item.set_top_left(state.rect.origin.cast_unit());
 
         text_item
     }
+
+    fn icon(&self, state: &state::ButtonState) -> Option<(f32, f32, draw::IconPlacement)> {
+        state
+            .data
+            .icon
+            .as_ref()
+            .map(|_| (state.data.icon_size, state.data.icon_spacing, state.data.icon_placement))
+    }
 }
 
 impl draw::Painter<state::ButtonState> for ButtonPainter {
@@ -158,10 +552,14 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
     }
 
     fn size_hint(&self, state: state::ButtonState) -> Size {
-        self.make_text_item(&state, Color::default().into(), false)
-            .bounds()
-            .unwrap()
-            .inflate(10.0, 5.0)
+        let text = self.make_text_item(&state, Color::default().into()).bounds().unwrap().size;
+        let classes = &state.data.classes;
+        let padding = (
+            self.styles.number("button", classes, "horizontal_padding", 10.0),
+            self.styles.number("button", classes, "vertical_padding", 5.0),
+        );
+        Rect::new(Point::zero(), icon_label_size(text, self.icon(&state)))
+            .inflate(padding.0, padding.1)
             .size
     }
 
@@ -175,6 +573,7 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
     }
 
     fn draw(&mut self, state: state::ButtonState) -> Vec<DisplayCommand> {
+        let state = self.styled(state);
         let (background, border, text, focus) = if state.data.disabled {
             (
                 draw::strengthen(state.data.background, 0.2, state.data.contrast).into(),
@@ -222,7 +621,18 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
             )
         };
 
-        let text_item = self.make_text_item(&state, text, true);
+        // The text and the icon are centered as a whole.
+        let mut text_item = self.make_text_item(&state, text);
+        let text_size = text_item.bounds().unwrap().size;
+        let icon = self.icon(&state);
+        let content = icon_label_size(text_size, icon);
+        let (text_origin, icon_rect) = icon_label_layout(
+            display::center(content, state.rect.cast_unit()),
+            content.height,
+            text_size,
+            icon,
+        );
+        text_item.set_top_left(text_origin);
 
         let mut builder = DisplayListBuilder::new();
 
@@ -250,7 +660,7 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
         builder.push_text(text_item, None);
 
         // Focus rect
-        if state.interaction.contains(state::InteractionState::FOCUSED)
+        if state.interaction.is_focus_visible()
             && !state.interaction.contains(state::InteractionState::PRESSED)
         {
             builder.push_round_rectangle(
@@ -280,19 +690,79 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
             );
         }
 
-        builder.build()
+        let mut commands = builder.build();
+
+        // Icon, faded into the background while disabled
+        if let (Some(icon), Some(rect)) = (&state.data.icon, icon_rect) {
+            let tint = if state.data.disabled {
+                let background = draw::strengthen(state.data.background, 0.2, state.data.contrast);
+                Some(draw::with_opacity(background, 0.6))
+            } else {
+                None
+            };
+            commands.extend(icon_commands(icon, rect, state.icon, tint));
+        }
+
+        commands
     }
 }
 
 struct CheckboxPainter;
 
+impl CheckboxPainter {
+    /// Side of the box.
+    const BOX: f32 = 20.0;
+
+    fn make_label_item(&self, state: &state::CheckboxState, color: StyleColor) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: state.data.label.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        }
+    }
+
+    fn icon(&self, state: &state::CheckboxState) -> Option<(f32, f32, draw::IconPlacement)> {
+        state
+            .data
+            .icon
+            .as_ref()
+            .map(|_| (state.data.icon_size, state.data.icon_spacing, state.data.icon_placement))
+    }
+
+    /// Returns the size of the label and the icon beside the box, or `None` if there is neither.
+    fn label_size(&self, state: &state::CheckboxState) -> Option<Size> {
+        let text = self
+            .make_label_item(state, Color::default().into())
+            .bounds()
+            .map(|bounds| bounds.size)
+            .unwrap_or_default();
+        let text = if text.width > 0.0 { text } else { Size::zero() };
+        let icon = self.icon(state);
+        if text.width > 0.0 || icon.is_some() {
+            Some(icon_label_size(text, icon))
+        } else {
+            None
+        }
+    }
+}
+
 impl draw::Painter<state::CheckboxState> for CheckboxPainter {
     fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CheckboxState>> {
         theme.checkbox()
     }
 
-    fn size_hint(&self, _state: state::CheckboxState) -> Size {
-        Size::new(20.0, 20.0)
+    fn size_hint(&self, state: state::CheckboxState) -> Size {
+        match self.label_size(&state) {
+            Some(label) => Size::new(
+                Self::BOX + state.data.icon_spacing + label.width,
+                label.height.max(Self::BOX),
+            ),
+            None => Size::new(Self::BOX, Self::BOX),
+        }
     }
 
     fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
@@ -300,12 +770,18 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
     }
 
     fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
-        RelativeRect::new(rect.origin, Size::new(20.0, 20.0).cast_unit())
+        // the checkbox passes the size of its content, so that the label can be pressed too
+        rect
     }
 
     fn draw(&mut self, mut state: state::CheckboxState) -> Vec<DisplayCommand> {
-        state.rect.size = Size::new(20.0, 20.0).cast_unit();
-        state.rect = base::sharp_align(state.rect.cast_unit()).cast_unit();
+        let content = self.size_hint(state.clone());
+        let origin = state.rect.origin.cast_unit();
+        state.rect = base::sharp_align(Rect::new(
+            Point::new(origin.x, origin.y + (content.height - Self::BOX) / 2.0),
+            Size::new(Self::BOX, Self::BOX),
+        ))
+        .cast_unit();
 
         let (background, foreground, border, focus) = if state.data.checked {
             (
@@ -372,7 +848,7 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
         );
 
         // Focus rect
-        if state.interaction.contains(state::InteractionState::FOCUSED)
+        if state.interaction.is_focus_visible()
             && !state.interaction.contains(state::InteractionState::PRESSED)
         {
             builder.push_round_rectangle(
@@ -387,7 +863,43 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
             );
         }
 
-        builder.build()
+        let mut commands = builder.build();
+
+        // Label and icon, after the box
+        if self.label_size(&state).is_some() {
+            let color = if state.data.disabled {
+                draw::weaken(state.data.foreground, 0.4, state.data.contrast)
+            } else {
+                state.data.foreground
+            };
+            let mut label_item = self.make_label_item(&state, color.into());
+            let text_size = label_item.bounds().map(|bounds| bounds.size).unwrap_or_default();
+            let text_size = if text_size.width > 0.0 { text_size } else { Size::zero() };
+            let (text_origin, icon_rect) = icon_label_layout(
+                Point::new(origin.x + Self::BOX + state.data.icon_spacing, origin.y),
+                content.height,
+                text_size,
+                self.icon(&state),
+            );
+
+            if text_size.width > 0.0 {
+                label_item.set_top_left(text_origin);
+                let mut builder = DisplayListBuilder::new();
+                builder.push_text(label_item, None);
+                commands.extend(builder.build());
+            }
+
+            if let (Some(icon), Some(rect)) = (&state.data.icon, icon_rect) {
+                let tint = if state.data.disabled {
+                    Some(draw::with_opacity(state.data.background, 0.6))
+                } else {
+                    None
+                };
+                commands.extend(icon_commands(icon, rect, state.icon, tint));
+            }
+        }
+
+        commands
     }
 }
 
@@ -448,7 +960,9 @@ impl draw::Painter<state::TextAreaState> for TextAreaPainter {
 
         let text_item = self.make_text_item(&state, text);
 
-        let cursor = if state.interaction.contains(state::InteractionState::FOCUSED) {
+        let cursor = if state.interaction.contains(state::InteractionState::FOCUSED)
+            && !state.interaction.contains(state::InteractionState::INACTIVE)
+        {
             let bounds = text_item.limited_bounds(state.data.cursor).unwrap();
             Some((bounds.origin + Size::new(bounds.size.width, 0.0), bounds.origin + bounds.size))
         } else {
@@ -549,3 +1063,5744 @@ impl draw::Painter<state::ScrollBarState> for ScrollBarPainter {
         builder.build()
     }
 }
+
+struct DialogPainter;
+
+impl draw::Painter<state::DialogState> for DialogPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::DialogState>> {
+        theme.dialog()
+    }
+
+    fn size_hint(&self, state: state::DialogState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::DialogState) -> Vec<DisplayCommand> {
+        if !state.data.open {
+            return Vec::new();
+        }
+
+        let panel = base::sharp_align(state.panel.cast_unit());
+        let border = draw::strengthen(state.data.background, 0.2, state.data.contrast);
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Scrim
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(state.data.scrim.into()),
+            None,
+        );
+
+        // Drop shadow
+        draw::push_drop_shadow(&mut builder, panel, [3.5; 4], 8.0, Color::new(0.0, 0.0, 0.0, 0.3));
+
+        // Panel
+        builder.push_round_rectangle(
+            panel,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            panel,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: border.into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        builder.build()
+    }
+}
+
+struct SketchCanvasPainter;
+
+impl draw::Painter<state::SketchCanvasState> for SketchCanvasPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SketchCanvasState>> {
+        theme.sketch_canvas()
+    }
+
+    fn size_hint(&self, state: state::SketchCanvasState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SketchCanvasState) -> Vec<DisplayCommand> {
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let origin = state.rect.origin.cast_unit().to_vector();
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        builder.push_round_rectangle_clip(rect, [3.5; 4]);
+
+        // Strokes, where each segment is as thick as the average pressure of its end points.
+        for stroke in &state.data.strokes {
+            for segment in stroke.points.windows(2) {
+                let pressure = (segment[0].pressure + segment[1].pressure) / 2.0;
+                builder.push_line(
+                    segment[0].position + origin,
+                    segment[1].position + origin,
+                    GraphicsDisplayStroke {
+                        thickness: (stroke.width * pressure).max(0.5),
+                        color: stroke.color.into(),
+                        ..Default::default()
+                    },
+                    None,
+                );
+            }
+        }
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::weaken(state.data.color, 0.4, state.data.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        builder.build()
+    }
+}
+
+struct AnnotationCanvasPainter;
+
+impl AnnotationCanvasPainter {
+    fn stroke(color: Color, thickness: f32) -> GraphicsDisplayStroke {
+        GraphicsDisplayStroke { thickness, color: color.into(), ..Default::default() }
+    }
+}
+
+impl draw::Painter<state::AnnotationCanvasState> for AnnotationCanvasPainter {
+    fn invoke(
+        &self,
+        theme: &dyn draw::Theme,
+    ) -> Box<dyn draw::Painter<state::AnnotationCanvasState>> {
+        theme.annotation_canvas()
+    }
+
+    fn size_hint(&self, state: state::AnnotationCanvasState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::AnnotationCanvasState) -> Vec<DisplayCommand> {
+        use crate::ui::Annotation;
+
+        let origin = state.rect.origin.cast_unit().to_vector();
+
+        let mut builder = DisplayListBuilder::new();
+
+        for annotation in &state.data.annotations {
+            match annotation {
+                Annotation::Freehand(stroke) => {
+                    for segment in stroke.points.windows(2) {
+                        let pressure = (segment[0].pressure + segment[1].pressure) / 2.0;
+                        builder.push_line(
+                            segment[0].position + origin,
+                            segment[1].position + origin,
+                            Self::stroke(stroke.color, (stroke.width * pressure).max(0.5)),
+                            None,
+                        );
+                    }
+                }
+                Annotation::Arrow { from, to, color, width } => {
+                    let (from, to) = (*from + origin, *to + origin);
+                    builder.push_line(from, to, Self::stroke(*color, *width), None);
+
+                    // Arrow head; two lines angled 30 degrees off the shaft.
+                    let shaft = from - to;
+                    if shaft.length() > 0.0 {
+                        let head = shaft.normalize() * (*width * 3.0 + 6.0);
+                        let (sin, cos) = std::f32::consts::FRAC_PI_6.sin_cos();
+                        for &sin in &[sin, -sin] {
+                            let wing = Vector::new(
+                                head.x * cos - head.y * sin,
+                                head.x * sin + head.y * cos,
+                            );
+                            builder.push_line(to, to + wing, Self::stroke(*color, *width), None);
+                        }
+                    }
+                }
+                Annotation::Rectangle { rect, color, width } => {
+                    builder.push_rectangle(
+                        rect.translate(origin),
+                        GraphicsDisplayPaint::Stroke(Self::stroke(*color, *width)),
+                        None,
+                    );
+                }
+                Annotation::Callout { anchor, position, text, color } => {
+                    let (anchor, position) = (*anchor + origin, *position + origin);
+
+                    let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+                    let mut text_item = TextDisplayItem {
+                        text: text.clone().into(),
+                        font: typeface.0,
+                        font_info: typeface.1,
+                        size: state.data.typeface.size,
+                        bottom_left: Default::default(),
+                        color: (*color).into(),
+                    };
+                    text_item.set_top_left(position);
+
+                    let text_box = text_item.bounds().unwrap().inflate(5.0, 3.0);
+
+                    // Leader line
+                    builder.push_line(anchor, position, Self::stroke(*color, 1.5), None);
+
+                    builder.push_round_rectangle(
+                        text_box,
+                        [3.5; 4],
+                        GraphicsDisplayPaint::Fill(state.data.callout_background.into()),
+                        None,
+                    );
+                    builder.push_round_rectangle(
+                        text_box,
+                        [3.5; 4],
+                        GraphicsDisplayPaint::Stroke(Self::stroke(*color, 1.5)),
+                        None,
+                    );
+                    builder.push_text(text_item, None);
+                }
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct ToastPainter;
+
+impl ToastPainter {
+    fn make_text_item(&self, state: &state::ToastState, color: StyleColor) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: state.data.text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        text_item.set_top_left(if state.rect.size.is_empty_or_negative() {
+            state.rect.origin.cast_unit()
+        } else {
+            display::center(text_item.bounds().unwrap().size, state.rect.cast_unit())
+        });
+
+        text_item
+    }
+}
+
+impl draw::Painter<state::ToastState> for ToastPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ToastState>> {
+        theme.toast()
+    }
+
+    fn size_hint(&self, state: state::ToastState) -> Size {
+        let size = self
+            .make_text_item(&state, Color::default().into())
+            .bounds()
+            .unwrap()
+            .inflate(15.0, 10.0)
+            .size;
+        Size::new(size.width.max(200.0), size.height)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ToastState) -> Vec<DisplayCommand> {
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let opacity = |color: Color| draw::with_opacity(color, color.alpha * state.opacity);
+
+        let text_item = self.make_text_item(&state, opacity(state.data.color).into());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Shadow
+        builder.push_round_rectangle(
+            rect.translate(Vector::new(0.0, 2.0)),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.2 * state.opacity).into()),
+            Some(Filter::Blur(5.0, 5.0)),
+        );
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(opacity(state.data.background).into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: opacity(draw::weaken(state.data.color, 0.4, state.data.contrast)).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        builder.push_text(text_item, None);
+
+        builder.build()
+    }
+}
+
+struct CollapsiblePainter;
+
+impl CollapsiblePainter {
+    fn make_text_item(
+        &self,
+        state: &state::CollapsibleState,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: state.data.title.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(
+            state.rect.origin.cast_unit()
+                + Size::new(10.0, ((state.rect.size.height - height) / 2.0).max(5.0)),
+        );
+
+        text_item
+    }
+}
+
+impl draw::Painter<state::CollapsibleState> for CollapsiblePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CollapsibleState>> {
+        theme.collapsible()
+    }
+
+    fn size_hint(&self, state: state::CollapsibleState) -> Size {
+        let size = self
+            .make_text_item(&state, Color::default().into())
+            .bounds()
+            .unwrap()
+            .inflate(10.0, 5.0)
+            .size;
+        // leave room for the chevron
+        Size::new(size.width + size.height, size.height)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CollapsibleState) -> Vec<DisplayCommand> {
+        let (background, color) = if state.data.disabled {
+            (
+                draw::strengthen(state.data.background, 0.2, state.data.contrast),
+                draw::weaken(state.data.color, 0.4, state.data.contrast),
+            )
+        } else if state.interaction.contains(state::InteractionState::PRESSED) {
+            (draw::strengthen(state.data.background, 0.2, state.data.contrast), state.data.color)
+        } else if state.interaction.contains(state::InteractionState::HOVERED) {
+            (draw::strengthen(state.data.background, 0.1, state.data.contrast), state.data.color)
+        } else {
+            (state.data.background, state.data.color)
+        };
+
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let text_item = self.make_text_item(&state, color.into());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(background.into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::weaken(state.data.color, 0.4, state.data.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        builder.push_text(text_item, None);
+
+        // Chevron; points right when collapsed and down when expanded
+        {
+            let center = rect.origin
+                + Size::new(rect.size.width - rect.size.height / 2.0, rect.size.height / 2.0);
+            builder.push_path(
+                chevron_icon(center, 3.5, state.data.expanded),
+                false,
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.5,
+                    color: color.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        // Focus rect
+        if state.interaction.is_focus_visible()
+            && !state.interaction.contains(state::InteractionState::PRESSED)
+        {
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: state.data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct CropperPainter;
+
+impl draw::Painter<state::CropperState> for CropperPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CropperState>> {
+        theme.cropper()
+    }
+
+    fn size_hint(&self, state: state::CropperState) -> Size {
+        if state.data.image_size.is_empty_or_negative() {
+            Size::new(200.0, 200.0)
+        } else {
+            state.data.image_size
+        }
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for handles
+        rect.inflate(4.0, 4.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CropperState) -> Vec<DisplayCommand> {
+        let image: Rect = state.image.cast_unit();
+        let crop: Rect = state.crop.cast_unit();
+        let foreground = if state.data.disabled {
+            draw::weaken(state.data.foreground, 0.4, state.data.contrast)
+        } else {
+            state.data.foreground
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        // Image
+        if let Some(resource) = state.data.image {
+            builder.push_image(None, image, resource, None);
+        }
+
+        // Scrim outside the crop rectangle
+        for rect in &[
+            Rect::new(image.origin, Size::new(image.size.width, crop.min_y() - image.min_y())),
+            Rect::new(
+                Point::new(image.min_x(), crop.max_y()),
+                Size::new(image.size.width, image.max_y() - crop.max_y()),
+            ),
+            Rect::new(
+                Point::new(image.min_x(), crop.min_y()),
+                Size::new(crop.min_x() - image.min_x(), crop.size.height),
+            ),
+            Rect::new(
+                Point::new(crop.max_x(), crop.min_y()),
+                Size::new(image.max_x() - crop.max_x(), crop.size.height),
+            ),
+        ] {
+            if !rect.is_empty_or_negative() {
+                builder.push_rectangle(
+                    *rect,
+                    GraphicsDisplayPaint::Fill(state.data.scrim.into()),
+                    None,
+                );
+            }
+        }
+
+        let line = |thickness: f32| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness,
+                color: foreground.into(),
+                ..Default::default()
+            })
+        };
+
+        // Rule-of-thirds guides
+        if state.data.guides {
+            let guides = draw::with_opacity(foreground, foreground.alpha * 0.5);
+            for i in 1..3 {
+                let x = crop.min_x() + crop.size.width * i as f32 / 3.0;
+                let y = crop.min_y() + crop.size.height * i as f32 / 3.0;
+                for (from, to) in &[
+                    (Point::new(x, crop.min_y()), Point::new(x, crop.max_y())),
+                    (Point::new(crop.min_x(), y), Point::new(crop.max_x(), y)),
+                ] {
+                    builder.push_line(
+                        *from,
+                        *to,
+                        GraphicsDisplayStroke {
+                            thickness: 1.0 / 3.0,
+                            color: guides.into(),
+                            ..Default::default()
+                        },
+                        None,
+                    );
+                }
+            }
+        }
+
+        // Border
+        builder.push_rectangle(base::sharp_align(crop), line(1.0), None);
+
+        // Handles
+        for corner in &[
+            crop.origin,
+            Point::new(crop.max_x(), crop.min_y()),
+            Point::new(crop.min_x(), crop.max_y()),
+            Point::new(crop.max_x(), crop.max_y()),
+        ] {
+            builder.push_rectangle(
+                Rect::new(*corner - Vector::new(3.0, 3.0), Size::new(6.0, 6.0)),
+                GraphicsDisplayPaint::Fill(foreground.into()),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct SplitPanePainter;
+
+impl draw::Painter<state::SplitPaneState> for SplitPanePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SplitPaneState>> {
+        theme.split_pane()
+    }
+
+    fn size_hint(&self, _state: state::SplitPaneState) -> Size {
+        Size::new(300.0, 200.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SplitPaneState) -> Vec<DisplayCommand> {
+        let divider: Rect = state.divider.cast_unit();
+        let color = if state.data.disabled {
+            state.data.color
+        } else if state.interaction.contains(state::InteractionState::PRESSED) {
+            draw::strengthen(state.data.color, 0.2, state.data.contrast)
+        } else if state.interaction.contains(state::InteractionState::HOVERED) {
+            draw::strengthen(state.data.color, 0.1, state.data.contrast)
+        } else {
+            state.data.color
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(divider, GraphicsDisplayPaint::Fill(color.into()), None);
+
+        // Grip in the middle of the divider
+        if !state.data.disabled {
+            use crate::ui::Orientation;
+
+            let grip = draw::strengthen(state.data.color, 0.4, state.data.contrast);
+            let center = divider.center();
+            let dot = (divider.size.width.min(divider.size.height) / 2.0).max(1.5);
+            for i in -1..=1 {
+                let offset = i as f32 * dot * 2.0;
+                let dot_center = match state.data.orientation {
+                    Orientation::Horizontal => center + Vector::new(0.0, offset),
+                    Orientation::Vertical => center + Vector::new(offset, 0.0),
+                };
+                builder.push_rectangle(
+                    Rect::new(dot_center - Vector::new(dot, dot) / 2.0, Size::new(dot, dot)),
+                    GraphicsDisplayPaint::Fill(grip.into()),
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct DockPainter;
+
+impl DockPainter {
+    fn make_text_item(
+        &self,
+        state: &state::DockState,
+        text: &display::DisplayText,
+        rect: AbsoluteRect,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        let bounds = text_item.bounds().unwrap();
+        text_item.set_top_left(
+            rect.origin.cast_unit()
+                + Size::new(
+                    (rect.size.width - bounds.size.width) / 2.0,
+                    (rect.size.height - bounds.size.height) / 2.0,
+                ),
+        );
+
+        text_item
+    }
+
+    fn draw_tab(
+        &self,
+        builder: &mut DisplayListBuilder,
+        state: &state::DockState,
+        text: &display::DisplayText,
+        rect: AbsoluteRect,
+        active: bool,
+    ) {
+        let data = &state.data;
+        let color = if active && !data.disabled {
+            data.foreground
+        } else {
+            draw::weaken(data.foreground, 0.4, data.contrast)
+        };
+
+        if active {
+            builder.push_rectangle(
+                base::sharp_align(rect.cast_unit()),
+                GraphicsDisplayPaint::Fill(data.background.into()),
+                None,
+            );
+            builder.push_rectangle(
+                Rect::new(rect.origin.cast_unit(), Size::new(rect.size.width, 2.0)),
+                GraphicsDisplayPaint::Fill(data.highlight.into()),
+                None,
+            );
+        }
+
+        builder.push_text(self.make_text_item(state, text, rect, color.into()), None);
+    }
+}
+
+impl draw::Painter<state::DockState> for DockPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::DockState>> {
+        theme.dock()
+    }
+
+    fn size_hint(&self, _state: state::DockState) -> Size {
+        Size::new(400.0, 300.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::DockState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let mut builder = DisplayListBuilder::new();
+
+        for divider in &state.dividers {
+            builder.push_rectangle(
+                divider.cast_unit(),
+                GraphicsDisplayPaint::Fill(data.tab_background.into()),
+                None,
+            );
+        }
+
+        for group in &state.groups {
+            let rect = base::sharp_align(group.rect.cast_unit());
+
+            if group.floating {
+                // Shadow
+                builder.push_round_rectangle(
+                    rect.translate(Vector::new(0.0, 2.0)).inflate(1.0, 1.0),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.2).into()),
+                    None,
+                );
+            }
+
+            builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+            builder.push_rectangle(
+                base::sharp_align(group.tab_bar.cast_unit()),
+                GraphicsDisplayPaint::Fill(data.tab_background.into()),
+                None,
+            );
+
+            for (i, (title, tab)) in group.tabs.iter().enumerate() {
+                self.draw_tab(&mut builder, &state, title, *tab, i == group.active);
+            }
+
+            if group.floating {
+                builder.push_rectangle(
+                    rect,
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: draw::strengthen(data.tab_background, 0.2, data.contrast).into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+        }
+
+        if let Some(preview) = state.preview {
+            let preview = base::sharp_align(preview.cast_unit());
+            builder.push_rectangle(
+                preview,
+                GraphicsDisplayPaint::Fill(draw::with_opacity(data.highlight, 0.25).into()),
+                None,
+            );
+            builder.push_rectangle(
+                preview,
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 2.0,
+                    color: data.highlight.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        if let Some((ref title, rect)) = state.dragging {
+            self.draw_tab(&mut builder, &state, title, rect, true);
+        }
+
+        builder.build()
+    }
+}
+
+struct TileViewPainter;
+
+impl draw::Painter<state::TileViewState> for TileViewPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TileViewState>> {
+        theme.tile_view()
+    }
+
+    fn size_hint(&self, _state: state::TileViewState) -> Size {
+        Size::new(400.0, 300.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TileViewState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        for tile in &state.tiles {
+            builder.push_image(tile.source, tile.rect.cast_unit(), tile.resource, None);
+        }
+
+        let radius = data.marker_radius;
+        for (marker, position) in data.markers.iter().zip(&state.markers) {
+            let position: Point = position.cast_unit();
+            let rect =
+                Rect::new(position - Vector::new(radius, radius), Size::new(radius, radius) * 2.0);
+
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [radius + 1.5; 4],
+                GraphicsDisplayPaint::Fill(Color::new(1.0, 1.0, 1.0, 1.0).into()),
+                None,
+            );
+            builder.push_round_rectangle(
+                rect,
+                [radius; 4],
+                GraphicsDisplayPaint::Fill(marker.color.into()),
+                None,
+            );
+
+            if let Some(ref label) = marker.label {
+                let typeface = data.typeface.typeface.pick(data.typeface.style);
+                let mut text_item = TextDisplayItem {
+                    text: label.clone(),
+                    font: typeface.0,
+                    font_info: typeface.1,
+                    size: data.typeface.size,
+                    bottom_left: Default::default(),
+                    color: data.foreground.into(),
+                };
+                let height = text_item.bounds().unwrap().size.height;
+                text_item.set_top_left(position + Size::new(radius + 4.0, -height / 2.0));
+                builder.push_text(text_item, None);
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct VideoSurfacePainter;
+
+impl draw::Painter<state::VideoSurfaceState> for VideoSurfacePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::VideoSurfaceState>> {
+        theme.video_surface()
+    }
+
+    fn size_hint(&self, _state: state::VideoSurfaceState) -> Size {
+        Size::new(320.0, 180.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::VideoSurfaceState) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        if let Some(frame) = state.frame {
+            builder.push_image(frame.source, frame.rect.cast_unit(), frame.resource, None);
+        }
+
+        builder.build()
+    }
+}
+
+struct SurfacePainter;
+
+impl draw::Painter<state::SurfaceState> for SurfacePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SurfaceState>> {
+        theme.surface()
+    }
+
+    fn size_hint(&self, _state: state::SurfaceState) -> Size {
+        Size::new(320.0, 180.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SurfaceState) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        if let Some(frame) = state.frame {
+            builder.push_image(frame.source, frame.rect.cast_unit(), frame.resource, None);
+        }
+
+        builder.build()
+    }
+}
+
+/// Length of the clip indicator at the end of each level meter bar.
+const LEVEL_METER_INDICATOR: f32 = 4.0;
+
+struct LevelMeterPainter;
+
+impl draw::Painter<state::LevelMeterState> for LevelMeterPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::LevelMeterState>> {
+        theme.level_meter()
+    }
+
+    fn size_hint(&self, state: state::LevelMeterState) -> Size {
+        use crate::ui::Orientation;
+
+        let channels = state.levels.len() as f32;
+        let breadth = (channels * 6.0 + (channels - 1.0).max(0.0) * state.data.spacing).max(6.0);
+        match state.data.orientation {
+            Orientation::Horizontal => Size::new(150.0, breadth),
+            Orientation::Vertical => Size::new(breadth, 150.0),
+        }
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::LevelMeterState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let vertical = data.orientation == crate::ui::Orientation::Vertical;
+        let mut builder = DisplayListBuilder::new();
+
+        let channels = state.levels.len().max(1) as f32;
+        let (length, breadth) = if vertical {
+            (rect.size.height, rect.size.width)
+        } else {
+            (rect.size.width, rect.size.height)
+        };
+        let bar_breadth = ((breadth - data.spacing * (channels - 1.0)) / channels).max(0.0);
+        let meter_length = (length - LEVEL_METER_INDICATOR - data.spacing).max(0.0);
+
+        let range = (data.max_db - data.min_db).max(std::f32::EPSILON);
+        let fraction = |db: f32| ((db - data.min_db) / range).max(0.0).min(1.0);
+        let zones = [
+            (data.min_db, data.warning_db, data.color),
+            (data.warning_db, data.clip_db, data.warning_color),
+            (data.clip_db, data.max_db, data.clip_color),
+        ];
+        let zone_color = |db: f32| {
+            zones.iter().rev().find(|(low, _, _)| db >= *low).map_or(data.color, |zone| zone.2)
+        };
+
+        for (i, level) in state.levels.iter().enumerate() {
+            let across = i as f32 * (bar_breadth + data.spacing);
+            // Portion of the bar between two fractions of the meter; vertical meters fill upwards.
+            let span = |from: f32, to: f32| {
+                let (from, to) = (from * meter_length, to * meter_length);
+                if vertical {
+                    Rect::new(
+                        Point::new(rect.min_x() + across, rect.max_y() - to),
+                        Size::new(bar_breadth, to - from),
+                    )
+                } else {
+                    Rect::new(
+                        Point::new(rect.min_x() + from, rect.min_y() + across),
+                        Size::new(to - from, bar_breadth),
+                    )
+                }
+            };
+
+            builder.push_rectangle(
+                span(0.0, 1.0),
+                GraphicsDisplayPaint::Fill(data.background.into()),
+                None,
+            );
+
+            // The peak is drawn faintly behind the RMS.
+            for &(low, high, color) in &zones {
+                let (low, high) = (fraction(low), fraction(high));
+                let peak = fraction(level.peak).min(high);
+                if peak > low {
+                    builder.push_rectangle(
+                        span(low, peak),
+                        GraphicsDisplayPaint::Fill(draw::with_opacity(color, 0.4).into()),
+                        None,
+                    );
+                }
+                let rms = fraction(level.rms).min(high);
+                if rms > low {
+                    builder.push_rectangle(
+                        span(low, rms),
+                        GraphicsDisplayPaint::Fill(color.into()),
+                        None,
+                    );
+                }
+            }
+
+            if level.held_peak > data.min_db && meter_length > 0.0 {
+                let held = fraction(level.held_peak);
+                builder.push_rectangle(
+                    span((held - 2.0 / meter_length).max(0.0), held),
+                    GraphicsDisplayPaint::Fill(zone_color(level.held_peak).into()),
+                    None,
+                );
+            }
+
+            let indicator = if vertical {
+                Rect::new(
+                    Point::new(rect.min_x() + across, rect.min_y()),
+                    Size::new(bar_breadth, LEVEL_METER_INDICATOR),
+                )
+            } else {
+                Rect::new(
+                    Point::new(rect.max_x() - LEVEL_METER_INDICATOR, rect.min_y() + across),
+                    Size::new(LEVEL_METER_INDICATOR, bar_breadth),
+                )
+            };
+            let indicator_color = if level.clipped {
+                data.clip_color
+            } else {
+                draw::strengthen(data.background, 0.1, data.contrast)
+            };
+            builder.push_rectangle(
+                indicator,
+                GraphicsDisplayPaint::Fill(indicator_color.into()),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct WaveformPainter;
+
+impl draw::Painter<state::WaveformState> for WaveformPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::WaveformState>> {
+        theme.waveform()
+    }
+
+    fn size_hint(&self, _state: state::WaveformState) -> Size {
+        Size::new(400.0, 100.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::WaveformState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        if let Some(selection) = state.selection {
+            builder.push_rectangle(
+                selection.cast_unit(),
+                GraphicsDisplayPaint::Fill(draw::with_opacity(data.highlight, 0.3).into()),
+                None,
+            );
+        }
+
+        let center = rect.center().y;
+        builder.push_rectangle(
+            base::sharp_align(Rect::new(
+                Point::new(rect.min_x(), center),
+                Size::new(rect.size.width, 1.0),
+            )),
+            GraphicsDisplayPaint::Fill(draw::weaken(data.color, 0.6, data.contrast).into()),
+            None,
+        );
+
+        let amplitude = rect.size.height / 2.0;
+        let color =
+            if data.disabled { draw::weaken(data.color, 0.4, data.contrast) } else { data.color };
+        let mut previous: Option<(f32, f32)> = None;
+        for (i, &(min, max)) in state.columns.iter().enumerate() {
+            // Extend towards the previous column so that sparse samples are drawn as a connected line.
+            let (min, max) = match previous {
+                Some((prev_min, prev_max)) => (min.min(prev_max), max.max(prev_min)),
+                None => (min, max),
+            };
+            previous = Some((min, max));
+
+            let top = center - max.max(-1.0).min(1.0) * amplitude;
+            let bottom = center - min.max(-1.0).min(1.0) * amplitude;
+            builder.push_rectangle(
+                Rect::new(
+                    Point::new(rect.min_x() + i as f32, top),
+                    Size::new(1.0, (bottom - top).max(1.0)),
+                ),
+                GraphicsDisplayPaint::Fill(color.into()),
+                None,
+            );
+        }
+
+        if let Some(selection) = state.selection {
+            let selection: Rect = selection.cast_unit();
+            for x in &[selection.min_x(), selection.max_x()] {
+                builder.push_rectangle(
+                    base::sharp_align(Rect::new(
+                        Point::new(*x, rect.min_y()),
+                        Size::new(1.0, rect.size.height),
+                    )),
+                    GraphicsDisplayPaint::Fill(data.highlight.into()),
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+/// Labels of the date picker navigation buttons.
+const DATE_PICKER_NAVIGATION: [&str; 4] = ["\u{ab}", "\u{2039}", "\u{203a}", "\u{bb}"];
+
+struct DatePickerPainter;
+
+impl DatePickerPainter {
+    fn make_text_item(
+        &self,
+        state: &state::DatePickerState,
+        text: String,
+        rect: AbsoluteRect,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        text_item.set_top_left(display::center(text_item.bounds().unwrap().size, rect.cast_unit()));
+
+        text_item
+    }
+}
+
+impl draw::Painter<state::DatePickerState> for DatePickerPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::DatePickerState>> {
+        theme.date_picker()
+    }
+
+    fn size_hint(&self, _state: state::DatePickerState) -> Size {
+        Size::new(7.0 * 32.0, 8.0 * 28.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::DatePickerState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            base::sharp_align(state.rect.cast_unit()),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.4, data.contrast)
+        } else {
+            data.foreground
+        };
+
+        let title = data.locale.format_month_year(data.year, data.month);
+        builder.push_text(self.make_text_item(&state, title, state.title, foreground.into()), None);
+
+        for (i, (rect, enabled)) in
+            state.navigation.iter().zip(state.navigation_enabled.iter()).enumerate()
+        {
+            let color = if *enabled && !data.disabled {
+                foreground
+            } else {
+                draw::weaken(foreground, 0.6, data.contrast)
+            };
+            builder.push_text(
+                self.make_text_item(
+                    &state,
+                    // the arrows point the other way when the buttons are mirrored
+                    DATE_PICKER_NAVIGATION[if data.locale.right_to_left { 3 - i } else { i }]
+                        .to_string(),
+                    *rect,
+                    color.into(),
+                ),
+                None,
+            );
+        }
+
+        let weekday_color = draw::weaken(foreground, 0.4, data.contrast);
+        for (weekday, rect) in &state.weekdays {
+            builder.push_text(
+                self.make_text_item(
+                    &state,
+                    data.locale.weekday_name(*weekday).to_string(),
+                    *rect,
+                    weekday_color.into(),
+                ),
+                None,
+            );
+        }
+
+        for day in &state.days {
+            let rect: Rect = day.rect.cast_unit();
+            let size = (rect.size.width.min(rect.size.height) - 4.0).max(0.0);
+            let marker =
+                Rect::new(display::center(Size::new(size, size), rect), Size::new(size, size));
+            let selected = data.selected == Some(day.date);
+
+            if selected {
+                let highlight = if data.disabled {
+                    draw::weaken(data.highlight, 0.4, data.contrast)
+                } else {
+                    data.highlight
+                };
+                builder.push_round_rectangle(
+                    marker,
+                    [size / 2.0; 4],
+                    GraphicsDisplayPaint::Fill(highlight.into()),
+                    None,
+                );
+            } else if state.hovered == Some(day.date) && day.enabled && !data.disabled {
+                builder.push_round_rectangle(
+                    marker,
+                    [size / 2.0; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(data.background, 0.1, data.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            if data.today == Some(day.date) && !selected {
+                builder.push_round_rectangle(
+                    marker,
+                    [size / 2.0; 4],
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: data.highlight.into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+
+            let color = if selected {
+                data.over_highlight
+            } else if !day.enabled {
+                draw::weaken(foreground, 0.7, data.contrast)
+            } else if !day.in_month {
+                draw::weaken(foreground, 0.5, data.contrast)
+            } else {
+                foreground
+            };
+            builder.push_text(
+                self.make_text_item(
+                    &state,
+                    data.locale.localize_digits(&day.date.day.to_string()),
+                    day.rect,
+                    color.into(),
+                ),
+                None,
+            );
+        }
+
+        if state.interaction.is_focus_visible() {
+            builder.push_round_rectangle(
+                base::sharp_align(state.rect.cast_unit()).inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct FileBrowserPainter;
+
+impl FileBrowserPainter {
+    fn make_text_item(
+        &self,
+        state: &state::FileBrowserState,
+        text: &display::DisplayText,
+        rect: AbsoluteRect,
+        indent: f32,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        let bounds = text_item.bounds().unwrap();
+        text_item.set_top_left(
+            rect.origin.cast_unit()
+                + Size::new(indent, (rect.size.height - bounds.size.height) / 2.0),
+        );
+
+        text_item
+    }
+}
+
+impl draw::Painter<state::FileBrowserState> for FileBrowserPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::FileBrowserState>> {
+        theme.file_browser()
+    }
+
+    fn size_hint(&self, _state: state::FileBrowserState) -> Size {
+        Size::new(560.0, 340.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::FileBrowserState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.4, data.contrast)
+        } else {
+            data.foreground
+        };
+        let faint = draw::weaken(foreground, 0.5, data.contrast);
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_round_rectangle(
+            base::sharp_align(state.rect.cast_unit()),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+        builder.push_rectangle(
+            state.tree_rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(
+                draw::strengthen(data.background, 0.05, data.contrast).into(),
+            ),
+            None,
+        );
+        builder.push_rectangle(
+            base::sharp_align(Rect::new(
+                Point::new(state.tree_rect.max_x(), state.tree_rect.min_y()),
+                Size::new(1.0, state.tree_rect.size.height),
+            )),
+            GraphicsDisplayPaint::Fill(draw::weaken(foreground, 0.8, data.contrast).into()),
+            None,
+        );
+        let mut commands = builder.build();
+
+        // Directory tree
+        let mut builder = DisplayListBuilder::new();
+        for row in &state.tree {
+            if row.current {
+                builder.push_rectangle(
+                    row.rect.cast_unit(),
+                    GraphicsDisplayPaint::Fill(draw::with_opacity(data.highlight, 0.25).into()),
+                    None,
+                );
+            }
+
+            let indent = row.depth as f32 * data.indent;
+            if let Some(expanded) = row.expanded {
+                let center = row.rect.origin.cast_unit()
+                    + Size::new(indent + data.indent / 2.0, row.rect.size.height / 2.0);
+                builder.push_path(
+                    chevron_icon(center, 3.0, expanded),
+                    false,
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.5,
+                        color: faint.into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+
+            builder.push_text(
+                self.make_text_item(
+                    &state,
+                    &row.name,
+                    row.rect,
+                    indent + data.indent + 4.0,
+                    foreground.into(),
+                ),
+                None,
+            );
+        }
+        commands.extend(clipped(state.tree_rect.cast_unit(), builder.build()));
+
+        // File list
+        // selections are drawn with the inactive colors while the window is inactive
+        let (highlight, over_highlight) =
+            if state.interaction.contains(state::InteractionState::INACTIVE) {
+                (data.inactive_highlight, data.over_inactive_highlight)
+            } else {
+                (data.highlight, data.over_highlight)
+            };
+        let mut builder = DisplayListBuilder::new();
+        for entry in &state.entries {
+            let rect: Rect = entry.rect.cast_unit();
+            let color = if entry.selected {
+                builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(highlight.into()), None);
+                over_highlight
+            } else {
+                if entry.hovered && !data.disabled {
+                    builder.push_rectangle(
+                        rect,
+                        GraphicsDisplayPaint::Fill(
+                            draw::strengthen(data.background, 0.08, data.contrast).into(),
+                        ),
+                        None,
+                    );
+                }
+                foreground
+            };
+
+            // Icon; a tab-less folder or a page
+            let icon_center = rect.origin + Size::new(12.0, rect.size.height / 2.0);
+            if entry.is_dir {
+                builder.push_round_rectangle(
+                    Rect::new(icon_center - Size::new(6.0, 4.5), Size::new(12.0, 9.0)),
+                    [1.5; 4],
+                    GraphicsDisplayPaint::Fill(draw::with_opacity(color, 0.6).into()),
+                    None,
+                );
+            } else {
+                builder.push_round_rectangle(
+                    base::sharp_align(Rect::new(
+                        icon_center - Size::new(4.5, 6.0),
+                        Size::new(9.0, 12.0),
+                    )),
+                    [1.5; 4],
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: draw::with_opacity(color, 0.6).into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+
+            let name = self.make_text_item(&state, &entry.name, entry.rect, 24.0, color.into());
+            let matched_color = if entry.selected { color } else { data.highlight };
+            for text_item in highlight_text_item(
+                name,
+                &entry.matched,
+                data.typeface.typeface.pick(draw::TextStyle::Bold),
+                matched_color.into(),
+            ) {
+                builder.push_text(text_item, None);
+            }
+
+            if let Some(size) = entry.size {
+                let size: display::DisplayText = crate::ui::format_size(size).into();
+                let mut text_item = self.make_text_item(
+                    &state,
+                    &size,
+                    entry.rect,
+                    0.0,
+                    if entry.selected { color } else { faint }.into(),
+                );
+                let width = text_item.bounds().unwrap().size.width;
+                text_item.set_top_left(
+                    text_item.bounds().unwrap().origin
+                        + Size::new(rect.size.width - width - 8.0, 0.0),
+                );
+                builder.push_text(text_item, None);
+            }
+        }
+
+        if let Some(ref message) = state.message {
+            let mut text_item =
+                self.make_text_item(&state, message, state.list_rect, 0.0, faint.into());
+            let size = text_item.bounds().unwrap().size;
+            text_item.set_top_left(display::center(size, state.list_rect.cast_unit()));
+            builder.push_text(text_item, None);
+        }
+        commands.extend(clipped(state.list_rect.cast_unit(), builder.build()));
+
+        if state.interaction.is_focus_visible() {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                base::sharp_align(state.rect.cast_unit()).inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+            commands.extend(builder.build());
+        }
+
+        commands
+    }
+}
+
+struct ImagePainter;
+
+impl draw::Painter<state::ImageState> for ImagePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ImageState>> {
+        theme.image()
+    }
+
+    fn size_hint(&self, state: state::ImageState) -> Size {
+        state
+            .data
+            .shown_image()
+            .and_then(|image| image.size())
+            .unwrap_or_else(|| Size::new(64.0, 64.0))
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ImageState) -> Vec<DisplayCommand> {
+        let rect: Rect = state.rect.cast_unit();
+        let radii = [state.data.corner_radius; 4];
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_round_rectangle(
+            rect,
+            radii,
+            GraphicsDisplayPaint::Fill(
+                if state.loading { state.data.placeholder } else { state.data.background }.into(),
+            ),
+            None,
+        );
+        if let Some(resource) = state.resource {
+            for destination in &state.destinations {
+                builder.push_image(state.source, destination.cast_unit(), resource, None);
+            }
+        }
+
+        let mut commands = vec![
+            DisplayCommand::Save,
+            if state.data.corner_radius > 0.0 {
+                DisplayCommand::Clip(DisplayClip::RoundRectangle { rect, radii })
+            } else {
+                DisplayCommand::Clip(DisplayClip::Rectangle { rect, antialias: false })
+            },
+        ];
+        commands.extend(builder.build());
+        commands.push(DisplayCommand::Restore);
+        commands
+    }
+}
+
+struct RadialMenuPainter;
+
+/// Sector of a ring around `center`, between the angles `start` and `end` (in radians, clockwise from the positive
+/// x-axis).
+fn ring_sector_path(center: Point, inner: f32, outer: f32, start: f32, end: f32) -> VectorPath {
+    let mut builder = VectorPathBuilder::new();
+
+    let segments = ((end - start).abs() / std::f32::consts::PI * 24.0).ceil().max(1.0) as usize;
+    let point =
+        |radius: f32, angle: f32| center + Size::new(angle.cos() * radius, angle.sin() * radius);
+    builder.move_to(point(outer, start));
+    for i in 1..=segments {
+        builder.line_to(point(outer, start + (end - start) * i as f32 / segments as f32));
+    }
+    for i in (0..=segments).rev() {
+        builder.line_to(point(inner, start + (end - start) * i as f32 / segments as f32));
+    }
+
+    builder.build()
+}
+
+impl draw::Painter<state::RadialMenuState> for RadialMenuPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::RadialMenuState>> {
+        theme.radial_menu()
+    }
+
+    fn size_hint(&self, state: state::RadialMenuState) -> Size {
+        Size::new(state.data.radius * 2.0, state.data.radius * 2.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(20.0, 20.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::RadialMenuState) -> Vec<DisplayCommand> {
+        if !state.data.open {
+            return Vec::new();
+        }
+
+        let data = &state.data;
+        let center: Point = state.center.cast_unit();
+        let (inner, outer) = (data.inner_radius, data.radius);
+        let border = draw::strengthen(data.background, 0.2, data.contrast);
+        let typeface = data.typeface.typeface.pick(data.typeface.style);
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Drop shadow
+        builder.push_round_rectangle(
+            Rect::new(center - Size::new(outer, outer - 3.0), Size::new(outer * 2.0, outer * 2.0)),
+            [outer; 4],
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.25).into()),
+            Some(Filter::Blur(8.0, 8.0)),
+        );
+
+        // Ring; a single item fills the whole ring
+        for item in &state.items {
+            let path = || ring_sector_path(center, inner, outer, item.start_angle, item.end_angle);
+            let (fill, foreground) = if item.selected && item.enabled {
+                (data.highlight, data.over_highlight)
+            } else if item.selected {
+                (draw::strengthen(data.background, 0.08, data.contrast), data.foreground)
+            } else {
+                (data.background, data.foreground)
+            };
+            builder.push_path(path(), true, GraphicsDisplayPaint::Fill(fill.into()), None);
+            builder.push_path(
+                path(),
+                true,
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: border.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+
+            // Label, centered between the inner and outer edges
+            let foreground = if item.enabled {
+                foreground
+            } else {
+                draw::weaken(foreground, 0.5, data.contrast)
+            };
+            let mut text_item = TextDisplayItem {
+                text: item.label.clone(),
+                font: typeface.0,
+                font_info: typeface.1.clone(),
+                size: data.typeface.size,
+                bottom_left: Default::default(),
+                color: foreground.into(),
+            };
+            let angle = (item.start_angle + item.end_angle) / 2.0;
+            let distance = (inner + outer) / 2.0;
+            let anchor = center + Size::new(angle.cos() * distance, angle.sin() * distance);
+            let size = text_item.bounds().unwrap().size;
+            text_item.set_top_left(anchor - size / 2.0);
+            builder.push_text(text_item, None);
+        }
+
+        builder.build()
+    }
+}
+
+struct ClipboardHistoryPickerPainter;
+
+impl ClipboardHistoryPickerPainter {
+    fn make_text_item(
+        &self,
+        state: &state::ClipboardHistoryPickerState,
+        text: &display::DisplayText,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::ClipboardHistoryPickerState> for ClipboardHistoryPickerPainter {
+    fn invoke(
+        &self,
+        theme: &dyn draw::Theme,
+    ) -> Box<dyn draw::Painter<state::ClipboardHistoryPickerState>> {
+        theme.clipboard_history_picker()
+    }
+
+    fn size_hint(&self, state: state::ClipboardHistoryPickerState) -> Size {
+        Size::new(300.0, state.data.max_rows as f32 * state.data.row_height + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(20.0, 20.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ClipboardHistoryPickerState) -> Vec<DisplayCommand> {
+        if !state.data.open {
+            return Vec::new();
+        }
+
+        let data = &state.data;
+        let panel = base::sharp_align(state.panel.cast_unit());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Drop shadow
+        builder.push_round_rectangle(
+            panel.translate(Vector::new(0.0, 3.0)),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.25).into()),
+            Some(Filter::Blur(8.0, 8.0)),
+        );
+
+        builder.push_round_rectangle(
+            panel,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+        builder.push_round_rectangle(
+            panel,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::strengthen(data.background, 0.2, data.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        for row in &state.rows {
+            let rect: Rect = row.rect.cast_unit();
+            let color = if row.selected {
+                builder.push_round_rectangle(
+                    rect,
+                    [2.5; 4],
+                    GraphicsDisplayPaint::Fill(data.highlight.into()),
+                    None,
+                );
+                data.over_highlight
+            } else {
+                if row.hovered {
+                    builder.push_round_rectangle(
+                        rect,
+                        [2.5; 4],
+                        GraphicsDisplayPaint::Fill(
+                            draw::strengthen(data.background, 0.08, data.contrast).into(),
+                        ),
+                        None,
+                    );
+                }
+                data.foreground
+            };
+
+            let mut text_item = self.make_text_item(&state, &row.text, color);
+            let height = text_item.bounds().unwrap().size.height;
+            text_item.set_top_left(rect.origin + Size::new(8.0, (rect.size.height - height) / 2.0));
+            builder.push_text(text_item, None);
+        }
+
+        if state.rows.is_empty() {
+            let mut text_item = self.make_text_item(
+                &state,
+                &"Clipboard history is empty".to_string().into(),
+                draw::weaken(data.foreground, 0.5, data.contrast),
+            );
+            text_item.set_top_left(display::center(text_item.bounds().unwrap().size, panel));
+            builder.push_text(text_item, None);
+        }
+
+        builder.build()
+    }
+}
+
+struct BadgePainter;
+
+impl BadgePainter {
+    fn make_text_item(&self, state: &state::BadgeState, text: String) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: state.data.foreground.into(),
+        };
+        text_item.set_top_left(display::center(
+            text_item.bounds().unwrap().size,
+            state.rect.cast_unit(),
+        ));
+        text_item
+    }
+}
+
+impl draw::Painter<state::BadgeState> for BadgePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::BadgeState>> {
+        theme.badge()
+    }
+
+    fn size_hint(&self, state: state::BadgeState) -> Size {
+        match state.data.label() {
+            Some(label) => {
+                let size = self.make_text_item(&state, label).bounds().unwrap().size;
+                let height = size.height + 2.0;
+                // pill-shaped, but never narrower than a circle
+                Size::new((size.width + 8.0).max(height), height)
+            }
+            None => Size::new(8.0, 8.0),
+        }
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for border
+        rect.inflate(1.5, 1.5)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::BadgeState) -> Vec<DisplayCommand> {
+        let rect: Rect = state.rect.cast_unit();
+        let radius = rect.size.height / 2.0;
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect.inflate(1.5, 1.5),
+            [radius + 1.5; 4],
+            GraphicsDisplayPaint::Fill(state.data.border.into()),
+            None,
+        );
+        builder.push_round_rectangle(
+            rect,
+            [radius; 4],
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        if let Some(label) = state.data.label() {
+            builder.push_text(self.make_text_item(&state, label), None);
+        }
+
+        builder.build()
+    }
+}
+
+struct ChipInputPainter;
+
+impl ChipInputPainter {
+    fn make_text_item(
+        &self,
+        state: &state::ChipInputState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::ChipInputState> for ChipInputPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ChipInputState>> {
+        theme.chip_input()
+    }
+
+    fn size_hint(&self, state: state::ChipInputState) -> Size {
+        // a single line; the widget grows to fit wrapped chips
+        Size::new(200.0, (state.data.typeface.size * 1.6).round() + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ChipInputState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let inactive = state.interaction.contains(state::InteractionState::INACTIVE);
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.5, data.contrast)
+        } else {
+            data.foreground
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        let mut commands = builder.build();
+        let mut builder = DisplayListBuilder::new();
+
+        for chip in &state.chips {
+            let chip_rect: Rect = chip.rect.cast_unit();
+            let remove: Rect = chip.remove.cast_unit();
+            builder.push_round_rectangle(
+                chip_rect,
+                [chip_rect.size.height / 2.0; 4],
+                GraphicsDisplayPaint::Fill(data.chip_background.into()),
+                None,
+            );
+
+            if chip.remove_hovered {
+                builder.push_round_rectangle(
+                    remove.inflate(-3.0, -3.0),
+                    [remove.size.height / 2.0 - 3.0; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(data.chip_background, 0.15, data.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            let mut text_item = self.make_text_item(&state, &chip.text, foreground);
+            let height = text_item.bounds().unwrap().size.height;
+            text_item.set_top_left(
+                chip_rect.origin + Size::new(8.0, (chip_rect.size.height - height) / 2.0),
+            );
+            builder.push_text(text_item, None);
+
+            let mut cross =
+                self.make_text_item(&state, "\u{d7}", draw::weaken(foreground, 0.3, data.contrast));
+            cross.set_top_left(display::center(cross.bounds().unwrap().size, remove));
+            builder.push_text(cross, None);
+        }
+
+        let field: Rect = state.field.cast_unit();
+        let (text, color) = if data.text.is_empty() && data.chips.is_empty() {
+            (&data.placeholder, draw::weaken(foreground, 0.5, data.contrast))
+        } else {
+            (&data.text, foreground)
+        };
+
+        let mut text_item = self.make_text_item(&state, text, color);
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(field.origin + Size::new(2.0, (field.size.height - height) / 2.0));
+
+        if focused && !inactive && !data.disabled {
+            let x = if data.text.is_empty() {
+                field.min_x() + 2.0
+            } else {
+                text_item.bounds().unwrap().max_x() + 1.0
+            };
+            builder.push_line(
+                Point::new(x, field.min_y() + 3.0),
+                Point::new(x, field.max_y() - 3.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: foreground.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+
+        commands.extend(clipped(rect, builder.build()));
+
+        if focused {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+            commands.extend(builder.build());
+        }
+
+        commands
+    }
+}
+
+struct KnobPainter;
+
+impl KnobPainter {
+    /// Space above the knob for the value tooltip.
+    const TOOLTIP_SPACE: f32 = 32.0;
+    /// Space on either side of the knob which the value tooltip may overhang.
+    const TOOLTIP_OVERHANG: f32 = 24.0;
+}
+
+impl draw::Painter<state::KnobState> for KnobPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::KnobState>> {
+        theme.knob()
+    }
+
+    fn size_hint(&self, _state: state::KnobState) -> Size {
+        Size::new(48.0, 48.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for value tooltip
+        let rect: Rect = rect.cast_unit();
+        Rect::new(
+            Point::new(rect.min_x() - Self::TOOLTIP_OVERHANG, rect.min_y() - Self::TOOLTIP_SPACE),
+            rect.size + Size::new(Self::TOOLTIP_OVERHANG * 2.0, Self::TOOLTIP_SPACE),
+        )
+        .cast_unit()
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::KnobState) -> Vec<DisplayCommand> {
+        use crate::ui::{KNOB_START_ANGLE, KNOB_SWEEP};
+
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let center = rect.center();
+        let radius = rect.size.width.min(rect.size.height) / 2.0;
+        let weakened = |color: Color| {
+            if data.disabled {
+                draw::weaken(color, 0.5, data.contrast)
+            } else {
+                color
+            }
+        };
+        let stroke = |thickness: f32, color: Color| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness,
+                color: color.into(),
+                ..Default::default()
+            })
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Track and filled portion
+        let end = KNOB_START_ANGLE + KNOB_SWEEP;
+        builder.push_path(
+            arc_path(center, radius - 2.0, KNOB_START_ANGLE, end),
+            false,
+            stroke(3.0, data.track),
+            None,
+        );
+        if data.fraction() > 0.0 {
+            builder.push_path(
+                arc_path(center, radius - 2.0, KNOB_START_ANGLE, data.angle()),
+                false,
+                stroke(3.0, weakened(data.fill)),
+                None,
+            );
+        }
+
+        // Tick marks
+        if data.ticks > 1 {
+            for i in 0..data.ticks {
+                let angle = KNOB_START_ANGLE + KNOB_SWEEP * i as f32 / (data.ticks - 1) as f32;
+                let direction = Size::new(angle.cos(), angle.sin());
+                builder.push_line(
+                    center + direction * radius * 0.72,
+                    center + direction * radius * 0.8,
+                    stroke(1.0, draw::weaken(data.foreground, 0.4, data.contrast)),
+                    None,
+                );
+            }
+        }
+
+        // Body and indicator
+        let body = radius * 0.62;
+        let background = if state.interaction.contains(state::InteractionState::PRESSED) {
+            draw::strengthen(data.background, 0.1, data.contrast)
+        } else if state.interaction.contains(state::InteractionState::HOVERED) {
+            draw::strengthen(data.background, 0.05, data.contrast)
+        } else {
+            data.background
+        };
+        builder.push_round_rectangle(
+            Rect::new(center - Size::new(body, body), Size::new(body, body) * 2.0),
+            [body; 4],
+            GraphicsDisplayPaint::Fill(weakened(background).into()),
+            None,
+        );
+        let direction = Size::new(data.angle().cos(), data.angle().sin());
+        builder.push_line(
+            center + direction * body * 0.35,
+            center + direction * body * 0.85,
+            stroke(2.0, weakened(data.foreground)),
+            None,
+        );
+
+        // Value tooltip
+        if data.show_value && state.interaction.contains(state::InteractionState::PRESSED) {
+            let typeface = data.typeface.typeface.pick(data.typeface.style);
+            let mut text_item = TextDisplayItem {
+                text: data.label().into(),
+                font: typeface.0,
+                font_info: typeface.1,
+                size: data.typeface.size,
+                bottom_left: Default::default(),
+                color: data.background.into(),
+            };
+            let text_size = text_item.bounds().unwrap().size;
+            let size = Size::new(
+                (text_size.width + 12.0).min(rect.size.width + Self::TOOLTIP_OVERHANG * 2.0),
+                (text_size.height + 6.0).min(Self::TOOLTIP_SPACE - 4.0),
+            );
+            let tooltip = Rect::new(
+                Point::new(center.x - size.width / 2.0, rect.min_y() - size.height - 4.0),
+                size,
+            );
+            builder.push_round_rectangle(
+                tooltip,
+                [3.0; 4],
+                GraphicsDisplayPaint::Fill(data.foreground.into()),
+                None,
+            );
+            text_item.set_top_left(display::center(text_size, tooltip));
+            builder.push_text(text_item, None);
+        }
+
+        builder.build()
+    }
+}
+
+struct GaugePainter;
+
+impl GaugePainter {
+    fn draw_radial(&self, state: &state::GaugeState, builder: &mut DisplayListBuilder) {
+        use crate::ui::{GAUGE_START_ANGLE, GAUGE_SWEEP};
+
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let center = rect.center();
+        let radius = rect.size.width.min(rect.size.height) / 2.0 - 6.0;
+        let stroke = |thickness: f32, color: Color| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness,
+                color: color.into(),
+                ..Default::default()
+            })
+        };
+
+        // Track and zones
+        builder.push_path(
+            arc_path(center, radius, GAUGE_START_ANGLE, GAUGE_START_ANGLE + GAUGE_SWEEP),
+            false,
+            stroke(10.0, data.background),
+            None,
+        );
+        for zone in &data.zones {
+            let (start, end) = (data.angle(zone.start), data.angle(zone.end));
+            if end > start {
+                builder.push_path(
+                    arc_path(center, radius, start, end),
+                    false,
+                    stroke(10.0, zone.color),
+                    None,
+                );
+            }
+        }
+
+        // Needle and hub
+        let angle = data.angle(state.value);
+        builder.push_line(
+            center,
+            center + Size::new(angle.cos(), angle.sin()) * (radius - 8.0),
+            stroke(2.5, data.needle),
+            None,
+        );
+        builder.push_round_rectangle(
+            Rect::new(center - Size::new(5.0, 5.0), Size::new(10.0, 10.0)),
+            [5.0; 4],
+            GraphicsDisplayPaint::Fill(data.needle.into()),
+            None,
+        );
+
+        if data.show_value {
+            let typeface = data.typeface.typeface.pick(data.typeface.style);
+            let mut text_item = TextDisplayItem {
+                text: data.label(state.value).into(),
+                font: typeface.0,
+                font_info: typeface.1,
+                size: data.typeface.size,
+                bottom_left: Default::default(),
+                color: data.foreground.into(),
+            };
+            let size = text_item.bounds().unwrap().size;
+            text_item
+                .set_top_left(Point::new(center.x - size.width / 2.0, center.y + radius / 2.0));
+            builder.push_text(text_item, None);
+        }
+    }
+
+    fn draw_linear(
+        &self,
+        state: &state::GaugeState,
+        orientation: crate::ui::Orientation,
+        builder: &mut DisplayListBuilder,
+    ) {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let vertical = orientation == crate::ui::Orientation::Vertical;
+        // the bar, inset to leave room for the marker
+        let bar = if vertical { rect.inflate(-6.0, 0.0) } else { rect.inflate(0.0, -6.0) };
+        // section of the bar between two fractions of the range
+        let section = |from: f32, to: f32| {
+            if vertical {
+                Rect::new(
+                    Point::new(bar.min_x(), bar.max_y() - bar.size.height * to),
+                    Size::new(bar.size.width, bar.size.height * (to - from)),
+                )
+            } else {
+                Rect::new(
+                    Point::new(bar.min_x() + bar.size.width * from, bar.min_y()),
+                    Size::new(bar.size.width * (to - from), bar.size.height),
+                )
+            }
+        };
+
+        builder.push_round_rectangle(
+            bar,
+            [2.0; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+        for zone in &data.zones {
+            let (start, end) = (data.fraction(zone.start), data.fraction(zone.end));
+            if end > start {
+                builder.push_rectangle(
+                    section(start, end),
+                    GraphicsDisplayPaint::Fill(zone.color.into()),
+                    None,
+                );
+            }
+        }
+
+        // Marker across the bar
+        let fraction = data.fraction(state.value);
+        let marker = if vertical {
+            let y = bar.max_y() - bar.size.height * fraction;
+            Rect::new(Point::new(rect.min_x(), y - 1.5), Size::new(rect.size.width, 3.0))
+        } else {
+            let x = bar.min_x() + bar.size.width * fraction;
+            Rect::new(Point::new(x - 1.5, rect.min_y()), Size::new(3.0, rect.size.height))
+        };
+        builder.push_round_rectangle(
+            marker,
+            [1.5; 4],
+            GraphicsDisplayPaint::Fill(data.needle.into()),
+            None,
+        );
+    }
+}
+
+impl draw::Painter<state::GaugeState> for GaugePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::GaugeState>> {
+        theme.gauge()
+    }
+
+    fn size_hint(&self, state: state::GaugeState) -> Size {
+        match state.data.style {
+            crate::ui::GaugeStyle::Radial => Size::new(120.0, 120.0),
+            crate::ui::GaugeStyle::Linear(crate::ui::Orientation::Horizontal) => {
+                Size::new(200.0, 24.0)
+            }
+            crate::ui::GaugeStyle::Linear(crate::ui::Orientation::Vertical) => {
+                Size::new(24.0, 200.0)
+            }
+        }
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::GaugeState) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+
+        match state.data.style {
+            crate::ui::GaugeStyle::Radial => self.draw_radial(&state, &mut builder),
+            crate::ui::GaugeStyle::Linear(orientation) => {
+                self.draw_linear(&state, orientation, &mut builder)
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct ChartPainter;
+
+impl ChartPainter {
+    fn make_text_item(
+        &self,
+        state: &state::ChartState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::ChartState> for ChartPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ChartState>> {
+        theme.chart()
+    }
+
+    fn size_hint(&self, _state: state::ChartState) -> Size {
+        Size::new(320.0, 200.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ChartState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let axes = &state.axes;
+        let rect: Rect = state.rect.cast_unit();
+        let plot: Rect = axes.plot.cast_unit();
+        let muted = draw::weaken(data.foreground, 0.4, data.contrast);
+        let stroke = |thickness: f32, color: Color| GraphicsDisplayStroke {
+            thickness,
+            color: color.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        // Gridlines and tick labels
+        for (value, label) in &axes.y_ticks {
+            let y = axes.y_position(*value).round() + 0.5;
+            if data.grid {
+                builder.push_line(
+                    Point::new(plot.min_x(), y),
+                    Point::new(plot.max_x(), y),
+                    stroke(1.0, data.grid_color),
+                    None,
+                );
+            }
+            let mut text_item = self.make_text_item(&state, label, muted);
+            let size = text_item.bounds().unwrap().size;
+            text_item
+                .set_top_left(Point::new(plot.min_x() - size.width - 6.0, y - size.height / 2.0));
+            builder.push_text(text_item, None);
+        }
+        for (value, label) in &axes.x_ticks {
+            let x = axes.x_position(*value).round() + 0.5;
+            if data.grid {
+                builder.push_line(
+                    Point::new(x, plot.min_y()),
+                    Point::new(x, plot.max_y()),
+                    stroke(1.0, data.grid_color),
+                    None,
+                );
+            }
+            let mut text_item = self.make_text_item(&state, label, muted);
+            let size = text_item.bounds().unwrap().size;
+            text_item.set_top_left(Point::new(x - size.width / 2.0, plot.max_y() + 6.0));
+            builder.push_text(text_item, None);
+        }
+
+        // Axes
+        builder.push_line(
+            Point::new(plot.min_x().round() + 0.5, plot.min_y()),
+            Point::new(plot.min_x().round() + 0.5, plot.max_y()),
+            stroke(1.0, muted),
+            None,
+        );
+        builder.push_line(
+            Point::new(plot.min_x(), plot.max_y().round() + 0.5),
+            Point::new(plot.max_x(), plot.max_y().round() + 0.5),
+            stroke(1.0, muted),
+            None,
+        );
+
+        let mut commands = builder.build();
+
+        // Series
+        let mut builder = DisplayListBuilder::new();
+        let bar_count =
+            state.series.iter().filter(|series| series.kind == crate::ui::SeriesKind::Bar).count();
+        let mut bar_slot = 0;
+        for (i, series) in state.series.iter().enumerate() {
+            let color = data.series_color(i, series);
+            match series.kind {
+                crate::ui::SeriesKind::Bar => {
+                    for (j, &point) in series.points.iter().enumerate() {
+                        let color = if state.hovered == Some((i, j)) {
+                            draw::strengthen(color, 0.15, data.contrast)
+                        } else {
+                            color
+                        };
+                        builder.push_rectangle(
+                            axes.bar(point, bar_slot, bar_count).cast_unit(),
+                            GraphicsDisplayPaint::Fill(color.into()),
+                            None,
+                        );
+                    }
+                    bar_slot += 1;
+                }
+                crate::ui::SeriesKind::Line => {
+                    if series.points.len() > 1 {
+                        let mut path = VectorPathBuilder::new();
+                        for (j, &(x, y)) in series.points.iter().enumerate() {
+                            let point: Point = axes.point(x, y).cast_unit();
+                            if j == 0 {
+                                path.move_to(point);
+                            } else {
+                                path.line_to(point);
+                            }
+                        }
+                        builder.push_path(
+                            path.build(),
+                            false,
+                            GraphicsDisplayPaint::Stroke(stroke(2.0, color)),
+                            None,
+                        );
+                    }
+                    for (j, &(x, y)) in series.points.iter().enumerate() {
+                        // a lone point is drawn as a dot, as is the hovered point
+                        if series.points.len() == 1 || state.hovered == Some((i, j)) {
+                            let point: Point = axes.point(x, y).cast_unit();
+                            builder.push_round_rectangle(
+                                Rect::new(point - Size::new(3.5, 3.5), Size::new(7.0, 7.0)),
+                                [3.5; 4],
+                                GraphicsDisplayPaint::Fill(color.into()),
+                                None,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        commands.extend(clipped(plot.inflate(4.0, 4.0), builder.build()));
+
+        // Tooltip
+        let hovered = state.hovered.and_then(|(i, j)| {
+            let series = state.series.get(i)?;
+            Some((series, *series.points.get(j)?))
+        });
+        if let Some((series, (x, y))) = hovered {
+            let text = format!(
+                "{}: {}, {}",
+                series.name,
+                data.format_value(x, data.precision),
+                data.format_value(y, data.precision)
+            );
+            let mut text_item = self.make_text_item(&state, &text, data.background);
+            let size = text_item.bounds().unwrap().size + Size::new(12.0, 6.0);
+            let anchor: Point = axes.point(x, y).cast_unit();
+            // above the point, kept within the chart
+            let origin = Point::new(
+                (anchor.x - size.width / 2.0).max(rect.min_x()).min(rect.max_x() - size.width),
+                (anchor.y - size.height - 8.0).max(rect.min_y()),
+            );
+            let tooltip = Rect::new(origin, size);
+
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                tooltip,
+                [3.0; 4],
+                GraphicsDisplayPaint::Fill(data.foreground.into()),
+                None,
+            );
+            text_item.set_top_left(display::center(text_item.bounds().unwrap().size, tooltip));
+            builder.push_text(text_item, None);
+            commands.extend(builder.build());
+        }
+
+        commands
+    }
+}
+
+struct SparklinePainter;
+
+impl draw::Painter<state::SparklineState> for SparklinePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SparklineState>> {
+        theme.sparkline()
+    }
+
+    fn size_hint(&self, _state: state::SparklineState) -> Size {
+        Size::new(80.0, 20.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SparklineState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let (min, max) = state.range;
+        let span = if (max - min).abs() > std::f32::EPSILON { max - min } else { 1.0 };
+        // a flat series sits in the middle rather than along an edge
+        let offset = if (max - min).abs() > std::f32::EPSILON { 0.0 } else { 0.5 };
+        // slots are laid out for a full sparkline, so that existing values don't stretch as values are pushed
+        let slot = rect.size.width / data.capacity.max(1) as f32;
+        let first = data.capacity.saturating_sub(state.values.len());
+        let y = |value: f32| {
+            let fraction = ((value - min) / span + offset).max(0.0).min(1.0);
+            rect.max_y() - fraction * rect.size.height
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        match data.style {
+            crate::ui::SparklineStyle::Bar => {
+                let base = y(0.0_f32.max(min).min(max));
+                for (i, &value) in state.values.iter().enumerate().filter(|(_, v)| v.is_finite()) {
+                    let x = rect.min_x() + (first + i) as f32 * slot;
+                    let top = y(value);
+                    builder.push_rectangle(
+                        Rect::new(
+                            Point::new(x + slot * 0.1, top.min(base)),
+                            Size::new(slot * 0.8, (top - base).abs().max(1.0)),
+                        ),
+                        GraphicsDisplayPaint::Fill(data.color.into()),
+                        None,
+                    );
+                }
+            }
+            crate::ui::SparklineStyle::Line => {
+                let points: Vec<Point> = state
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, value)| value.is_finite())
+                    .map(|(i, &value)| {
+                        Point::new(rect.min_x() + ((first + i) as f32 + 0.5) * slot, y(value))
+                    })
+                    .collect();
+
+                if points.len() > 1 {
+                    if let Some(fill) = data.fill {
+                        let mut path = VectorPathBuilder::new();
+                        path.move_to(Point::new(points[0].x, rect.max_y()));
+                        for &point in &points {
+                            path.line_to(point);
+                        }
+                        path.line_to(Point::new(points[points.len() - 1].x, rect.max_y()));
+                        builder.push_path(
+                            path.build(),
+                            true,
+                            GraphicsDisplayPaint::Fill(fill.into()),
+                            None,
+                        );
+                    }
+
+                    let mut path = VectorPathBuilder::new();
+                    path.move_to(points[0]);
+                    for &point in &points[1..] {
+                        path.line_to(point);
+                    }
+                    builder.push_path(
+                        path.build(),
+                        false,
+                        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                            thickness: 1.5,
+                            color: data.color.into(),
+                            ..Default::default()
+                        }),
+                        None,
+                    );
+                }
+
+                if let Some(&last) = points.last() {
+                    if data.mark_last || points.len() == 1 {
+                        builder.push_round_rectangle(
+                            Rect::new(last - Size::new(2.0, 2.0), Size::new(4.0, 4.0)),
+                            [2.0; 4],
+                            GraphicsDisplayPaint::Fill(data.color.into()),
+                            None,
+                        );
+                    }
+                }
+            }
+        }
+
+        clipped(rect.inflate(2.0, 2.0), builder.build())
+    }
+}
+
+struct TreeTablePainter;
+
+impl TreeTablePainter {
+    /// Returns the text item of a cell, aligned within it and vertically centered.
+    fn make_cell_item(
+        &self,
+        state: &state::TreeTableState,
+        text: &str,
+        color: Color,
+        cell: Rect,
+        align: crate::ui::TextAlign,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        };
+        let size = text_item.bounds().unwrap().size;
+        let x = match align {
+            crate::ui::TextAlign::Left => cell.min_x() + 6.0,
+            crate::ui::TextAlign::Middle => cell.center().x - size.width / 2.0,
+            crate::ui::TextAlign::Right => cell.max_x() - 6.0 - size.width,
+        };
+        text_item
+            .set_top_left(Point::new(x, cell.min_y() + (cell.size.height - size.height) / 2.0));
+        text_item
+    }
+
+    /// Draws the content of a cell, as formatted by the renderer of its column.
+    fn draw_cell(
+        &self,
+        state: &state::TreeTableState,
+        content: &state::TableCellState,
+        foreground: Color,
+        accent: Color,
+        cell: Rect,
+        align: crate::ui::TextAlign,
+    ) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let mut builder = DisplayListBuilder::new();
+        match content {
+            state::TableCellState::Text(text) => {
+                builder.push_text(self.make_cell_item(state, text, foreground, cell, align), None);
+            }
+            state::TableCellState::Progress(fraction, label) => {
+                // the bar takes whatever width the label leaves
+                let label = self.make_cell_item(
+                    state,
+                    label,
+                    foreground,
+                    cell,
+                    crate::ui::TextAlign::Right,
+                );
+                let label_width = label.bounds().unwrap().size.width;
+                let height = 6.0;
+                let track = Rect::new(
+                    Point::new(cell.min_x() + 6.0, cell.center().y - height / 2.0),
+                    Size::new((cell.size.width - label_width - 18.0).max(0.0), height),
+                );
+                builder.push_round_rectangle(
+                    track,
+                    [height / 2.0; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::weaken(foreground, 0.85, data.contrast).into(),
+                    ),
+                    None,
+                );
+                if *fraction > 0.0 {
+                    builder.push_round_rectangle(
+                        Rect::new(track.origin, Size::new(track.size.width * fraction, height)),
+                        [height / 2.0; 4],
+                        GraphicsDisplayPaint::Fill(accent.into()),
+                        None,
+                    );
+                }
+                builder.push_text(label, None);
+            }
+            state::TableCellState::IconText(icon, text) => {
+                let slot = data.row_height;
+                let icon_rect =
+                    Rect::new(cell.origin + Size::new(3.0, 0.0), Size::new(slot, cell.size.height));
+                let text_rect = Rect::new(
+                    cell.origin + Size::new(slot, 0.0),
+                    Size::new((cell.size.width - slot).max(0.0), cell.size.height),
+                );
+                builder.push_text(
+                    self.make_cell_item(
+                        state,
+                        icon,
+                        foreground,
+                        icon_rect,
+                        crate::ui::TextAlign::Middle,
+                    ),
+                    None,
+                );
+                builder.push_text(
+                    self.make_cell_item(
+                        state,
+                        text,
+                        foreground,
+                        text_rect,
+                        crate::ui::TextAlign::Left,
+                    ),
+                    None,
+                );
+            }
+            state::TableCellState::Check(checked) => {
+                let size = (data.row_height - 10.0).max(0.0);
+                let x = match align {
+                    crate::ui::TextAlign::Left => cell.min_x() + 6.0,
+                    crate::ui::TextAlign::Middle => cell.center().x - size / 2.0,
+                    crate::ui::TextAlign::Right => cell.max_x() - 6.0 - size,
+                };
+                let check = base::sharp_align(Rect::new(
+                    Point::new(x, cell.min_y() + (cell.size.height - size) / 2.0),
+                    Size::new(size, size),
+                ));
+                builder.push_round_rectangle(
+                    check,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: draw::weaken(foreground, 0.4, data.contrast).into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+                if *checked {
+                    builder.push_path(
+                        check_mark_icon(check.inflate(-3.0, -3.0)),
+                        false,
+                        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                            thickness: 2.0,
+                            color: foreground.into(),
+                            ..Default::default()
+                        }),
+                        None,
+                    );
+                }
+            }
+        }
+        builder.build()
+    }
+}
+
+impl draw::Painter<state::TreeTableState> for TreeTablePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TreeTableState>> {
+        theme.tree_table()
+    }
+
+    fn size_hint(&self, _state: state::TreeTableState) -> Size {
+        Size::new(480.0, 320.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TreeTableState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let body: Rect = state.body.cast_unit();
+        let scroll_columns: Rect = state.scroll_columns.cast_unit();
+        let scroll_body: Rect = state.scroll_body.cast_unit();
+        let faint = draw::weaken(data.foreground, 0.5, data.contrast);
+        let stroke = |thickness: f32, color: Color| GraphicsDisplayStroke {
+            thickness,
+            color: color.into(),
+            ..Default::default()
+        };
+        // columns which aren't frozen are hidden beneath the frozen columns
+        let visible = |column: usize, cell: Rect| {
+            if column < data.frozen_columns {
+                cell
+            } else {
+                cell.intersection(&scroll_columns).unwrap_or(Rect::new(cell.origin, Size::zero()))
+            }
+        };
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+        builder.push_rectangle(
+            Rect::new(rect.origin, Size::new(rect.size.width, body.min_y() - rect.min_y())),
+            GraphicsDisplayPaint::Fill(data.header_background.into()),
+            None,
+        );
+        let mut commands = builder.build();
+
+        // Header
+        for (i, (column, column_rect)) in data.columns.iter().zip(&state.columns).enumerate() {
+            let column_rect: Rect = column_rect.cast_unit();
+            let header = Rect::new(
+                column_rect.origin,
+                Size::new(column_rect.size.width, data.row_height.min(body.min_y() - rect.min_y())),
+            );
+            let mut builder = DisplayListBuilder::new();
+            builder.push_text(
+                self.make_cell_item(&state, &column.title, faint, header, column.align),
+                None,
+            );
+            commands.extend(clipped(visible(i, header), builder.build()));
+        }
+
+        // Filter row
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        for (i, (column, filter)) in data.columns.iter().zip(&state.filters).enumerate() {
+            let cell: Rect = filter.rect.cast_unit();
+            let field = cell.inflate(-2.0, -2.0);
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                field,
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(data.background.into()),
+                None,
+            );
+            builder.push_round_rectangle(
+                field,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(if filter.focused {
+                    stroke(1.0, data.focus)
+                } else {
+                    stroke(1.0, data.border)
+                }),
+                None,
+            );
+
+            let text_item = self.make_cell_item(
+                &state,
+                &filter.text,
+                data.foreground,
+                cell,
+                if filter.focused { crate::ui::TextAlign::Left } else { column.align },
+            );
+            let x = if filter.text.is_empty() {
+                cell.min_x() + 6.0
+            } else {
+                text_item.bounds().unwrap().max_x() + 1.0
+            };
+            builder.push_text(text_item, None);
+
+            if filter.focused && focused {
+                builder.push_line(
+                    Point::new(x, field.min_y() + 3.0),
+                    Point::new(x, field.max_y() - 3.0),
+                    stroke(1.0, data.foreground),
+                    None,
+                );
+            }
+            commands.extend(clipped(visible(i, cell), builder.build()));
+        }
+
+        // Rows
+        // selections are drawn with the inactive colors while the window is inactive
+        let (highlight, over_highlight) =
+            if state.interaction.contains(state::InteractionState::INACTIVE) {
+                (data.inactive_highlight, data.over_inactive_highlight)
+            } else {
+                (data.highlight, data.over_highlight)
+            };
+        let (mut frozen, mut rows) = (Vec::new(), Vec::new());
+        for row in &state.rows {
+            let row_rect: Rect = row.rect.cast_unit();
+            let mut builder = DisplayListBuilder::new();
+            let foreground = if row.selected { over_highlight } else { data.foreground };
+
+            if row.selected {
+                builder.push_rectangle(
+                    row_rect,
+                    GraphicsDisplayPaint::Fill(highlight.into()),
+                    None,
+                );
+            } else if row.hovered {
+                builder.push_rectangle(
+                    row_rect,
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(data.background, 0.05, data.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            let mut cells = Vec::new();
+            for (i, (column, column_rect)) in data.columns.iter().zip(&state.columns).enumerate() {
+                // cells spanning several columns cover the cells after them
+                let span = row.spans.get(i).copied().unwrap_or(1);
+                if span == 0 {
+                    continue;
+                }
+                let last = state.columns.get(i + span - 1).unwrap_or(column_rect);
+                let mut cell = Rect::new(
+                    Point::new(column_rect.min_x(), row_rect.min_y()),
+                    Size::new(last.max_x() - column_rect.min_x(), row_rect.size.height),
+                );
+
+                // the first column holds the tree
+                if i == 0 {
+                    let indent = row.depth as f32 * data.indent;
+                    if let Some(expanded) = row.expanded {
+                        let center = cell.origin
+                            + Size::new(indent + data.indent / 2.0, cell.size.height / 2.0);
+                        builder.push_path(
+                            chevron_icon(center, 3.0, expanded),
+                            false,
+                            GraphicsDisplayPaint::Stroke(stroke(
+                                1.5,
+                                if row.selected { foreground } else { faint },
+                            )),
+                            None,
+                        );
+                    }
+                    let inset = indent + data.indent;
+                    cell = Rect::new(
+                        cell.origin + Size::new(inset - 6.0, 0.0),
+                        Size::new((cell.size.width - inset + 6.0).max(0.0), cell.size.height),
+                    );
+                }
+
+                if let Some(content) = row.cells.get(i) {
+                    // progress is filled with the text color in the selected row, against the highlight
+                    let accent = if row.selected { foreground } else { data.highlight };
+                    let commands =
+                        self.draw_cell(&state, content, foreground, accent, cell, column.align);
+                    cells.extend(clipped(visible(i, cell), commands));
+                }
+            }
+
+            let target = if row.frozen { &mut frozen } else { &mut rows };
+            target.extend(builder.build());
+            target.extend(cells);
+        }
+        commands.extend(clipped(scroll_body, rows));
+        commands.extend(clipped(body, frozen));
+
+        // Column dividers and focus
+        let mut builder = DisplayListBuilder::new();
+        for (i, column_rect) in state.columns.iter().enumerate().skip(1) {
+            if i >= data.frozen_columns && column_rect.min_x() < scroll_columns.min_x() {
+                continue;
+            }
+            let x = column_rect.min_x().round() + 0.5;
+
+            // dividers aren't drawn through cells spanning several columns
+            let mut covered: Vec<(f32, f32)> = state
+                .rows
+                .iter()
+                .filter(|row| {
+                    row.spans.iter().take(i).enumerate().any(|(j, &span)| span > 0 && j + span > i)
+                })
+                .map(|row| {
+                    let clip: Rect = if row.frozen { body } else { scroll_body };
+                    (row.rect.min_y().max(clip.min_y()), row.rect.max_y().min(clip.max_y()))
+                })
+                .collect();
+            covered.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            let mut y = rect.min_y();
+            for (start, end) in covered.into_iter().chain(Some((rect.max_y(), rect.max_y()))) {
+                if start > y {
+                    builder.push_line(
+                        Point::new(x, y),
+                        Point::new(x, start),
+                        stroke(1.0, data.border),
+                        None,
+                    );
+                }
+                y = y.max(end);
+            }
+        }
+        if data.frozen_columns > 0 && data.frozen_columns < data.columns.len() {
+            let x = scroll_columns.min_x().round() + 0.5;
+            builder.push_line(
+                Point::new(x, rect.min_y()),
+                Point::new(x, rect.max_y()),
+                stroke(1.0, data.border),
+                None,
+            );
+        }
+        let y = body.min_y().round() + 0.5;
+        builder.push_line(
+            Point::new(rect.min_x(), y),
+            Point::new(rect.max_x(), y),
+            stroke(1.0, data.border),
+            None,
+        );
+        if state.rows.iter().any(|row| row.frozen) {
+            let y = scroll_body.min_y().round() + 0.5;
+            builder.push_line(
+                Point::new(rect.min_x(), y),
+                Point::new(rect.max_x(), y),
+                stroke(1.0, data.border),
+                None,
+            );
+        }
+        builder.push_rectangle(
+            base::sharp_align(rect),
+            GraphicsDisplayPaint::Stroke(stroke(
+                1.0,
+                if state.interaction.contains(state::InteractionState::FOCUSED) {
+                    data.highlight
+                } else {
+                    data.border
+                },
+            )),
+            None,
+        );
+        commands.extend(builder.build());
+
+        commands
+    }
+}
+
+struct PropertyGridPainter;
+
+impl PropertyGridPainter {
+    fn make_text_item(
+        &self,
+        state: &state::PropertyGridState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+
+    /// Returns the text item placed at the left of a cell, vertically centered.
+    fn cell_text_item(
+        &self,
+        state: &state::PropertyGridState,
+        text: &str,
+        color: Color,
+        cell: Rect,
+    ) -> TextDisplayItem {
+        let mut text_item = self.make_text_item(state, text, color);
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(cell.origin + Size::new(6.0, (cell.size.height - height) / 2.0));
+        text_item
+    }
+}
+
+impl draw::Painter<state::PropertyGridState> for PropertyGridPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::PropertyGridState>> {
+        theme.property_grid()
+    }
+
+    fn size_hint(&self, state: state::PropertyGridState) -> Size {
+        // the widget fits its height to the rows
+        Size::new(280.0, state.data.properties.len() as f32 * state.data.row_height)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::PropertyGridState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let stroke = |thickness: f32, color: Color| GraphicsDisplayStroke {
+            thickness,
+            color: color.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        let mut commands = builder.build();
+
+        for (property, row) in data.properties.iter().zip(&state.rows) {
+            let name: Rect = row.name.cast_unit();
+            let value: Rect = row.value.cast_unit();
+            let editable = !data.disabled && !property.read_only;
+            let foreground = if data.disabled {
+                draw::weaken(data.foreground, 0.5, data.contrast)
+            } else {
+                data.foreground
+            };
+            let value_foreground = if editable {
+                foreground
+            } else {
+                draw::weaken(data.foreground, 0.5, data.contrast)
+            };
+
+            let mut builder = DisplayListBuilder::new();
+            builder.push_text(
+                self.cell_text_item(
+                    &state,
+                    &property.name,
+                    draw::weaken(foreground, 0.2, data.contrast),
+                    name,
+                ),
+                None,
+            );
+            commands.extend(clipped(name, builder.build()));
+
+            let mut builder = DisplayListBuilder::new();
+
+            if editable && row.hovered == Some(crate::ui::PropertyPart::Value) {
+                builder.push_rectangle(
+                    value,
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(data.background, 0.05, data.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            if let Some(text) = &row.editing {
+                let field = value.inflate(-2.0, -2.0);
+                builder.push_round_rectangle(
+                    field,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Stroke(stroke(1.0, data.focus)),
+                    None,
+                );
+
+                let text_item = self.cell_text_item(&state, text, foreground, value);
+                let x = if text.is_empty() {
+                    value.min_x() + 6.0
+                } else {
+                    text_item.bounds().unwrap().max_x() + 1.0
+                };
+                builder.push_text(text_item, None);
+
+                if focused {
+                    builder.push_line(
+                        Point::new(x, field.min_y() + 3.0),
+                        Point::new(x, field.max_y() - 3.0),
+                        stroke(1.0, foreground),
+                        None,
+                    );
+                }
+            } else {
+                match &property.value {
+                    crate::ui::PropertyValue::Bool(checked) => {
+                        let size = (data.row_height - 10.0).max(0.0);
+                        let check = base::sharp_align(Rect::new(
+                            value.origin + Size::new(6.0, (value.size.height - size) / 2.0),
+                            Size::new(size, size),
+                        ));
+                        builder.push_round_rectangle(
+                            check,
+                            [3.5; 4],
+                            GraphicsDisplayPaint::Stroke(stroke(
+                                1.0,
+                                draw::weaken(value_foreground, 0.4, data.contrast),
+                            )),
+                            None,
+                        );
+                        if *checked {
+                            builder.push_path(
+                                check_mark_icon(check.inflate(-3.0, -3.0)),
+                                false,
+                                GraphicsDisplayPaint::Stroke(stroke(2.0, value_foreground)),
+                                None,
+                            );
+                        }
+                    }
+                    crate::ui::PropertyValue::Number(number) => {
+                        builder.push_text(
+                            self.cell_text_item(
+                                &state,
+                                &number.to_string(),
+                                value_foreground,
+                                value,
+                            ),
+                            None,
+                        );
+                    }
+                    crate::ui::PropertyValue::Text(text) => {
+                        builder.push_text(
+                            self.cell_text_item(&state, text, value_foreground, value),
+                            None,
+                        );
+                    }
+                    crate::ui::PropertyValue::Color(color) => {
+                        let size = (data.row_height - 10.0).max(0.0);
+                        let swatch = base::sharp_align(Rect::new(
+                            value.origin + Size::new(6.0, (value.size.height - size) / 2.0),
+                            Size::new(size * 2.0, size),
+                        ));
+                        builder.push_round_rectangle(
+                            swatch,
+                            [3.0; 4],
+                            GraphicsDisplayPaint::Fill((*color).into()),
+                            None,
+                        );
+                        builder.push_round_rectangle(
+                            swatch,
+                            [3.0; 4],
+                            GraphicsDisplayPaint::Stroke(stroke(1.0, data.border)),
+                            None,
+                        );
+
+                        let channel = |c: f32| (c.max(0.0).min(1.0) * 255.0).round() as u8;
+                        let hex = format!(
+                            "#{:02X}{:02X}{:02X}",
+                            channel(color.red),
+                            channel(color.green),
+                            channel(color.blue)
+                        );
+                        let mut text_item = self.make_text_item(&state, &hex, value_foreground);
+                        let height = text_item.bounds().unwrap().size.height;
+                        text_item.set_top_left(Point::new(
+                            swatch.max_x() + 6.0,
+                            value.min_y() + (value.size.height - height) / 2.0,
+                        ));
+                        builder.push_text(text_item, None);
+                    }
+                }
+            }
+
+            if let Some((decrement, increment)) = row.stepper.filter(|_| editable) {
+                for (button, label, part) in &[
+                    (decrement, "\u{2212}", crate::ui::PropertyPart::Decrement),
+                    (increment, "+", crate::ui::PropertyPart::Increment),
+                ] {
+                    let button: Rect = button.cast_unit();
+                    if row.hovered == Some(*part) {
+                        builder.push_round_rectangle(
+                            button.inflate(-3.0, -3.0),
+                            [3.5; 4],
+                            GraphicsDisplayPaint::Fill(
+                                draw::strengthen(data.background, 0.15, data.contrast).into(),
+                            ),
+                            None,
+                        );
+                    }
+                    let mut text_item = self.make_text_item(&state, label, foreground);
+                    text_item
+                        .set_top_left(display::center(text_item.bounds().unwrap().size, button));
+                    builder.push_text(text_item, None);
+                }
+            }
+
+            commands.extend(clipped(value, builder.build()));
+        }
+
+        // Grid lines
+        let mut builder = DisplayListBuilder::new();
+        let divider = state.rows.first().map(|row| row.value.min_x()).unwrap_or(rect.min_x());
+        for row in state.rows.iter().skip(1) {
+            let y = row.name.min_y().round() + 0.5;
+            builder.push_line(
+                Point::new(rect.min_x(), y),
+                Point::new(rect.max_x(), y),
+                stroke(1.0, data.border),
+                None,
+            );
+        }
+        if !state.rows.is_empty() {
+            let x = divider.round() + 0.5;
+            builder.push_line(
+                Point::new(x, rect.min_y()),
+                Point::new(x, rect.max_y()),
+                stroke(1.0, data.border),
+                None,
+            );
+        }
+        builder.push_rectangle(
+            base::sharp_align(rect),
+            GraphicsDisplayPaint::Stroke(stroke(
+                1.0,
+                if focused { data.focus } else { data.border },
+            )),
+            None,
+        );
+        commands.extend(builder.build());
+
+        commands
+    }
+}
+
+struct SwatchGridPainter;
+
+impl draw::Painter<state::SwatchGridState> for SwatchGridPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SwatchGridState>> {
+        theme.swatch_grid()
+    }
+
+    fn size_hint(&self, state: state::SwatchGridState) -> Size {
+        state.data.grid_size()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for the selection outline, which surrounds the swatch
+        rect.inflate(3.0, 3.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SwatchGridState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let stroke = |thickness: f32, color: Color| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness,
+                color: color.into(),
+                ..Default::default()
+            })
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        for swatch in &state.swatches {
+            let rect = base::sharp_align(swatch.rect.cast_unit());
+            let color = if data.disabled {
+                draw::weaken(swatch.color, 0.5, data.contrast)
+            } else {
+                swatch.color
+            };
+
+            builder.push_round_rectangle(
+                rect,
+                [data.corner_radius; 4],
+                GraphicsDisplayPaint::Fill(color.into()),
+                None,
+            );
+
+            let border = if swatch.hovered {
+                draw::strengthen(data.border, 0.3, data.contrast)
+            } else {
+                data.border
+            };
+            builder.push_round_rectangle(rect, [data.corner_radius; 4], stroke(1.0, border), None);
+
+            // Selection outline, separated from the swatch by a gap
+            if swatch.selected {
+                builder.push_round_rectangle(
+                    rect.inflate(2.5, 2.5),
+                    [data.corner_radius + 2.5; 4],
+                    stroke(1.5, data.selection),
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct CarouselPainter;
+
+impl draw::Painter<state::CarouselState> for CarouselPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CarouselState>> {
+        theme.carousel()
+    }
+
+    fn size_hint(&self, _state: state::CarouselState) -> Size {
+        Size::new(320.0, 200.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CarouselState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let disabled_foreground = draw::weaken(data.foreground, 0.5, data.contrast);
+
+        let mut builder = DisplayListBuilder::new();
+
+        let arrows = [
+            (state.previous, crate::ui::CarouselPart::Previous, data.wrap || data.page > 0),
+            (state.next, crate::ui::CarouselPart::Next, data.wrap || data.page + 1 < state.pages),
+        ];
+        for &(rect, part, enabled) in &arrows {
+            let rect: Rect = match rect {
+                Some(rect) => rect.cast_unit(),
+                None => continue,
+            };
+            let enabled = enabled && !data.disabled;
+            if enabled && state.hovered == Some(part) {
+                builder.push_round_rectangle(
+                    rect,
+                    [rect.size.width / 2.0; 4],
+                    GraphicsDisplayPaint::Fill(data.hover.into()),
+                    None,
+                );
+            }
+
+            // chevron pointing away from the current page
+            let center = rect.center();
+            let half = (rect.size.height / 5.0).round();
+            let direction = if part == crate::ui::CarouselPart::Next { 1.0 } else { -1.0 };
+            let mut path = VectorPathBuilder::new();
+            path.move_to(center + Size::new(-half / 2.0 * direction, -half));
+            path.line_to(center + Size::new(half / 2.0 * direction, 0.0));
+            path.line_to(center + Size::new(-half / 2.0 * direction, half));
+            builder.push_path(
+                path.build(),
+                false,
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 2.0,
+                    color: if enabled { data.foreground } else { disabled_foreground }.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        for (page, dot) in state.dots.iter().enumerate() {
+            let rect: Rect = dot.cast_unit();
+            // the dot of the current page fades in as the page slides into view
+            let current = (1.0 - (page as f32 - state.offset).abs()).max(0.0);
+            let color = draw::mix(data.dot, data.foreground, current);
+            if state.hovered == Some(crate::ui::CarouselPart::Dot(page)) && !data.disabled {
+                builder.push_round_rectangle(
+                    rect.inflate(3.0, 3.0),
+                    [rect.size.width / 2.0 + 3.0; 4],
+                    GraphicsDisplayPaint::Fill(data.hover.into()),
+                    None,
+                );
+            }
+            builder.push_round_rectangle(
+                rect,
+                [rect.size.width / 2.0; 4],
+                GraphicsDisplayPaint::Fill(
+                    if data.disabled { draw::weaken(color, 0.5, data.contrast) } else { color }
+                        .into(),
+                ),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct FabPainter;
+
+impl FabPainter {
+    /// Returns the fill of a button, accounting for interaction.
+    fn button_fill(
+        &self,
+        state: &state::FabState,
+        part: crate::ui::FabPart,
+        color: Color,
+    ) -> Color {
+        let data = &state.data;
+        if data.disabled {
+            draw::weaken(color, 0.3, data.contrast)
+        } else if state.pressed == Some(part) {
+            draw::strengthen(color, 0.15, data.contrast)
+        } else if state.hovered == Some(part) {
+            draw::strengthen(color, 0.07, data.contrast)
+        } else {
+            color
+        }
+    }
+
+    fn push_text(
+        &self,
+        builder: &mut DisplayListBuilder,
+        state: &state::FabState,
+        text: &str,
+        color: Color,
+        rect: Rect,
+    ) {
+        if text.is_empty() {
+            return;
+        }
+
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        };
+        if let Some(bounds) = text_item.bounds() {
+            text_item.set_top_left(display::center(bounds.size, rect));
+        }
+        builder.push_text(text_item, None);
+    }
+}
+
+impl draw::Painter<state::FabState> for FabPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::FabState>> {
+        theme.fab()
+    }
+
+    fn size_hint(&self, state: state::FabState) -> Size {
+        Size::new(state.data.diameter, state.data.diameter)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::FabState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let action_foreground = if data.disabled {
+            draw::weaken(data.action_foreground, 0.5, data.contrast)
+        } else {
+            data.action_foreground
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Actions, which are raised less than the button
+        for (i, action) in state.actions.iter().enumerate() {
+            let part = crate::ui::FabPart::Action(i);
+            let fill = self.button_fill(&state, part, data.action_background);
+            let text = data.actions.get(i);
+
+            let label: Rect = action.label.cast_unit();
+            if !label.is_empty() {
+                draw::push_drop_shadow(
+                    &mut builder,
+                    label,
+                    [3.5; 4],
+                    data.elevation / 2.0,
+                    data.shadow,
+                );
+                builder.push_round_rectangle(
+                    label,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(fill.into()),
+                    None,
+                );
+                self.push_text(
+                    &mut builder,
+                    &state,
+                    text.map_or("", |text| &text.label),
+                    action_foreground,
+                    label,
+                );
+            }
+
+            let button: Rect = action.button.cast_unit();
+            let radii = [button.size.width / 2.0; 4];
+            draw::push_drop_shadow(&mut builder, button, radii, data.elevation / 2.0, data.shadow);
+            builder.push_round_rectangle(
+                button,
+                radii,
+                GraphicsDisplayPaint::Fill(fill.into()),
+                None,
+            );
+            self.push_text(
+                &mut builder,
+                &state,
+                text.map_or("", |text| &text.icon),
+                action_foreground,
+                button,
+            );
+        }
+
+        // Button
+        let button: Rect = state.button.cast_unit();
+        let radii = [button.size.width / 2.0; 4];
+        let elevation = if state.pressed == Some(crate::ui::FabPart::Button) {
+            data.elevation * 1.5
+        } else {
+            data.elevation
+        };
+        draw::push_drop_shadow(&mut builder, button, radii, elevation, data.shadow);
+        builder.push_round_rectangle(
+            button,
+            radii,
+            GraphicsDisplayPaint::Fill(
+                self.button_fill(&state, crate::ui::FabPart::Button, data.background).into(),
+            ),
+            None,
+        );
+
+        // Plus icon, turned into a cross while the actions are shown
+        let center = button.center();
+        let half = (button.size.width * 0.18).round();
+        let angle = if data.expanded && !data.actions.is_empty() {
+            std::f32::consts::FRAC_PI_4
+        } else {
+            0.0
+        };
+        let (sin, cos) = angle.sin_cos();
+        let stroke = GraphicsDisplayStroke {
+            thickness: 2.0,
+            color: if data.disabled {
+                draw::weaken(data.foreground, 0.5, data.contrast)
+            } else {
+                data.foreground
+            }
+            .into(),
+            ..Default::default()
+        };
+        for &(x, y) in &[(cos, sin), (-sin, cos)] {
+            builder.push_line(
+                center + Size::new(-x * half, -y * half),
+                center + Size::new(x * half, y * half),
+                stroke.clone(),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct TitleBarPainter;
+
+impl draw::Painter<state::TitleBarState> for TitleBarPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TitleBarState>> {
+        theme.title_bar()
+    }
+
+    fn size_hint(&self, state: state::TitleBarState) -> Size {
+        Size::new(0.0, (state.data.typeface.size * 1.6).round() + 10.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TitleBarState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        // the title and icons fade while the window is inactive, as native title bars do
+        let foreground =
+            if data.disabled || state.interaction.contains(state::InteractionState::INACTIVE) {
+                draw::weaken(data.foreground, 0.5, data.contrast)
+            } else {
+                data.foreground
+            };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        // Title, clipped before the buttons
+        let buttons_width = state.buttons.len() as f32 * data.button_width;
+        let title_rect = Rect::new(
+            rect.origin + Size::new(12.0, 0.0),
+            Size::new((rect.size.width - buttons_width - 24.0).max(0.0), rect.size.height),
+        );
+        let typeface = data.typeface.typeface.pick(data.typeface.style);
+        let mut title = TextDisplayItem {
+            text: data.title.clone().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: data.typeface.size,
+            bottom_left: Default::default(),
+            color: foreground.into(),
+        };
+        if let Some(bounds) = title.bounds() {
+            title.set_top_left(
+                title_rect.origin
+                    + Size::new(0.0, (title_rect.size.height - bounds.size.height) / 2.0),
+            );
+        }
+        let mut title_builder = DisplayListBuilder::new();
+        title_builder.push_text(title, None);
+
+        let mut commands = builder.build();
+        commands.extend(clipped(title_rect, title_builder.build()));
+
+        let mut builder = DisplayListBuilder::new();
+
+        for button in &state.buttons {
+            let rect: Rect = button.rect.cast_unit();
+            let close = button.button == crate::ui::WindowButton::Close;
+            let active = (button.hovered || button.pressed) && !data.disabled;
+
+            let icon_color = if close && active {
+                let fill = if button.pressed {
+                    draw::strengthen(data.close_hover, 0.15, data.contrast)
+                } else {
+                    data.close_hover
+                };
+                builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(fill.into()), None);
+                data.over_close_hover
+            } else {
+                if active {
+                    let amount = if button.pressed { 0.15 } else { 0.08 };
+                    builder.push_rectangle(
+                        rect,
+                        GraphicsDisplayPaint::Fill(
+                            draw::strengthen(data.background, amount, data.contrast).into(),
+                        ),
+                        None,
+                    );
+                }
+                foreground
+            };
+
+            let stroke = GraphicsDisplayStroke {
+                thickness: 1.0,
+                color: icon_color.into(),
+                ..Default::default()
+            };
+            let center = base::sharp_align(Rect::new(rect.center(), Size::new(0.0, 0.0))).origin;
+            let half = 5.0;
+            match button.button {
+                crate::ui::WindowButton::Minimize => {
+                    builder.push_line(
+                        center + Size::new(-half, 0.0),
+                        center + Size::new(half, 0.0),
+                        stroke,
+                        None,
+                    );
+                }
+                crate::ui::WindowButton::Maximize if data.maximized => {
+                    // Restore icon; a window in front of another
+                    let inset = 2.0;
+                    builder.push_rectangle(
+                        Rect::new(
+                            center + Size::new(-half, -half + inset),
+                            Size::new(half * 2.0 - inset, half * 2.0 - inset),
+                        ),
+                        GraphicsDisplayPaint::Stroke(stroke.clone()),
+                        None,
+                    );
+                    builder.push_line(
+                        center + Size::new(-half + inset, -half),
+                        center + Size::new(half, -half),
+                        stroke.clone(),
+                        None,
+                    );
+                    builder.push_line(
+                        center + Size::new(half, -half),
+                        center + Size::new(half, half - inset),
+                        stroke,
+                        None,
+                    );
+                }
+                crate::ui::WindowButton::Maximize => {
+                    builder.push_rectangle(
+                        Rect::new(
+                            center + Size::new(-half, -half),
+                            Size::new(half * 2.0, half * 2.0),
+                        ),
+                        GraphicsDisplayPaint::Stroke(stroke),
+                        None,
+                    );
+                }
+                crate::ui::WindowButton::Close => {
+                    for &(x, y) in &[(half, half), (half, -half)] {
+                        builder.push_line(
+                            center + Size::new(-x, -y),
+                            center + Size::new(x, y),
+                            stroke.clone(),
+                            None,
+                        );
+                    }
+                }
+            }
+        }
+
+        commands.extend(builder.build());
+        commands
+    }
+}
+
+struct MinimapPainter;
+
+impl draw::Painter<state::MinimapState> for MinimapPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::MinimapState>> {
+        theme.minimap()
+    }
+
+    fn size_hint(&self, _state: state::MinimapState) -> Size {
+        Size::new(120.0, 200.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::MinimapState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let content: Rect = state.content.cast_unit();
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        let mut commands = builder.build();
+        let scale = if data.content_size.width > 0.0 {
+            content.size.width / data.content_size.width
+        } else {
+            1.0
+        };
+        commands.extend(clipped(content, data.content.replay_scaled(content.origin, scale)));
+
+        // Viewport, emphasized while hovered or dragged
+        let viewport = base::sharp_align(state.viewport.cast_unit());
+        let active = state
+            .interaction
+            .intersects(state::InteractionState::HOVERED | state::InteractionState::PRESSED);
+        let fill = if data.disabled {
+            draw::weaken(data.viewport_fill, 0.5, data.contrast)
+        } else if active {
+            draw::strengthen(data.viewport_fill, 0.1, data.contrast)
+        } else {
+            data.viewport_fill
+        };
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(viewport, GraphicsDisplayPaint::Fill(fill.into()), None);
+        builder.push_rectangle(
+            viewport,
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: if active { 2.0 } else { 1.0 },
+                color: data.viewport_border.into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        commands.extend(clipped(rect, builder.build()));
+        commands
+    }
+}
+
+struct PaginationPainter;
+
+impl PaginationPainter {
+    fn make_text_item(
+        &self,
+        state: &state::PaginationState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::PaginationState> for PaginationPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::PaginationState>> {
+        theme.pagination()
+    }
+
+    fn size_hint(&self, state: state::PaginationState) -> Size {
+        // the widget fits its width to the items
+        Size::new(0.0, (state.data.typeface.size * 2.0).round())
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::PaginationState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let disabled_foreground = draw::weaken(data.foreground, 0.5, data.contrast);
+
+        let mut builder = DisplayListBuilder::new();
+
+        for (i, &(item, rect)) in state.items.iter().enumerate() {
+            let rect: Rect = rect.cast_unit();
+            let current = item == crate::ui::PaginationItem::Page(data.page);
+            let foreground = if current {
+                data.over_selected
+            } else if data.is_enabled(item) || item == crate::ui::PaginationItem::Ellipsis {
+                data.foreground
+            } else {
+                disabled_foreground
+            };
+
+            let fill = if current {
+                Some(data.selected)
+            } else if state.hovered == Some(i) {
+                Some(data.hover)
+            } else {
+                None
+            };
+            if let Some(fill) = fill {
+                builder.push_round_rectangle(
+                    rect,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(fill.into()),
+                    None,
+                );
+            }
+
+            match item {
+                crate::ui::PaginationItem::Previous | crate::ui::PaginationItem::Next => {
+                    let center = rect.center();
+                    let half = (rect.size.height / 6.0).round();
+                    // chevron pointing away from the pages
+                    let direction =
+                        if item == crate::ui::PaginationItem::Next { 1.0 } else { -1.0 };
+                    let mut path = VectorPathBuilder::new();
+                    path.move_to(center + Size::new(-half / 2.0 * direction, -half));
+                    path.line_to(center + Size::new(half / 2.0 * direction, 0.0));
+                    path.line_to(center + Size::new(-half / 2.0 * direction, half));
+                    builder.push_path(
+                        path.build(),
+                        false,
+                        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                            thickness: 1.5,
+                            color: foreground.into(),
+                            ..Default::default()
+                        }),
+                        None,
+                    );
+                }
+                _ => {
+                    let mut text_item = self.make_text_item(&state, &data.label(item), foreground);
+                    text_item.set_top_left(display::center(text_item.bounds().unwrap().size, rect));
+                    builder.push_text(text_item, None);
+                }
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct WizardPainter;
+
+impl WizardPainter {
+    fn make_text_item(
+        &self,
+        state: &state::WizardState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::WizardState> for WizardPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::WizardState>> {
+        theme.wizard()
+    }
+
+    fn size_hint(&self, _state: state::WizardState) -> Size {
+        Size::new(480.0, 360.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::WizardState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let muted = draw::weaken(data.foreground, 0.5, data.contrast);
+        let line_color = draw::strengthen(data.background, 0.2, data.contrast);
+        let stroke = |thickness: f32, color: Color| GraphicsDisplayStroke {
+            thickness,
+            color: color.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        // Step indicator
+        let indicator: Rect = state.indicator.cast_unit();
+        let count = data.steps.len().max(1);
+        let slot = indicator.size.width / count as f32;
+        let diameter = ((indicator.size.height - 8.0) / 2.0).round();
+        let center = |i: usize| {
+            Point::new(
+                indicator.min_x() + (i as f32 + 0.5) * slot,
+                indicator.min_y() + diameter / 2.0,
+            )
+        };
+
+        // connectors between consecutive steps, colored up to the current step
+        for i in 1..data.steps.len() {
+            let (from, to) = (center(i - 1), center(i));
+            builder.push_line(
+                Point::new(from.x + diameter / 2.0 + 4.0, from.y),
+                Point::new(to.x - diameter / 2.0 - 4.0, to.y),
+                stroke(2.0, if i <= data.step { data.accent } else { line_color }),
+                None,
+            );
+        }
+
+        for (i, title) in data.steps.iter().enumerate() {
+            let center = center(i);
+            let circle = Rect::new(
+                center - Size::new(diameter / 2.0, diameter / 2.0),
+                Size::new(diameter, diameter),
+            );
+
+            if i <= data.step {
+                builder.push_round_rectangle(
+                    circle,
+                    [diameter / 2.0; 4],
+                    GraphicsDisplayPaint::Fill(data.accent.into()),
+                    None,
+                );
+            } else {
+                builder.push_round_rectangle(
+                    circle.inflate(-1.0, -1.0),
+                    [diameter / 2.0 - 1.0; 4],
+                    GraphicsDisplayPaint::Stroke(stroke(2.0, line_color)),
+                    None,
+                );
+            }
+
+            if i < data.step {
+                // completed
+                builder.push_path(
+                    check_mark_icon(circle.inflate(-diameter * 0.3, -diameter * 0.3)),
+                    false,
+                    GraphicsDisplayPaint::Stroke(stroke(2.0, data.over_accent)),
+                    None,
+                );
+            } else {
+                let color = if i == data.step { data.over_accent } else { muted };
+                let mut number = self.make_text_item(&state, &(i + 1).to_string(), color);
+                number.set_top_left(display::center(number.bounds().unwrap().size, circle));
+                builder.push_text(number, None);
+            }
+
+            let mut title_item = self.make_text_item(
+                &state,
+                title,
+                if i <= data.step { data.foreground } else { muted },
+            );
+            let size = title_item.bounds().unwrap().size;
+            title_item.set_top_left(Point::new(center.x - size.width / 2.0, circle.max_y() + 8.0));
+            builder.push_text(title_item, None);
+        }
+
+        // Buttons
+        for &(button, rect) in &state.buttons {
+            let rect: Rect = rect.cast_unit();
+            let enabled = data.is_enabled(button);
+            let hovered = enabled && state.hovered == Some(button);
+            let primary = button == crate::ui::WizardButton::Next
+                || button == crate::ui::WizardButton::Finish;
+
+            let (fill, foreground) = if primary {
+                (data.accent, data.over_accent)
+            } else {
+                (data.background, data.foreground)
+            };
+            let fill = if hovered { draw::strengthen(fill, 0.1, data.contrast) } else { fill };
+            let foreground =
+                if enabled { foreground } else { draw::weaken(foreground, 0.5, data.contrast) };
+
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(fill.into()),
+                None,
+            );
+            if !primary {
+                builder.push_round_rectangle(
+                    base::sharp_align(rect),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Stroke(stroke(1.0, line_color)),
+                    None,
+                );
+            }
+
+            let mut label = self.make_text_item(&state, data.label(button), foreground);
+            label.set_top_left(display::center(label.bounds().unwrap().size, rect));
+            builder.push_text(label, None);
+        }
+
+        builder.build()
+    }
+}
+
+struct CardPainter;
+
+impl draw::Painter<state::CardState> for CardPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CardState>> {
+        theme.card()
+    }
+
+    fn size_hint(&self, state: state::CardState) -> Size {
+        Size::new(state.data.padding * 2.0, state.data.padding * 2.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CardState) -> Vec<DisplayCommand> {
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let radii = [state.data.corner_radius; 4];
+        let stroke = GraphicsDisplayStroke {
+            thickness: 1.0,
+            color: state.data.border.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Drop shadow
+        draw::push_drop_shadow(&mut builder, rect, radii, state.data.elevation, state.data.shadow);
+
+        // Surface
+        builder.push_round_rectangle(
+            rect,
+            radii,
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        // Separators
+        if let Some(header) = state.header {
+            let y = base::sharp_align(header.cast_unit()).max_y();
+            builder.push_line(
+                Point::new(rect.min_x(), y),
+                Point::new(rect.max_x(), y),
+                stroke.clone(),
+                None,
+            );
+        }
+        if let Some(footer) = state.footer {
+            let y = base::sharp_align(footer.cast_unit()).min_y();
+            builder.push_line(
+                Point::new(rect.min_x(), y),
+                Point::new(rect.max_x(), y),
+                stroke.clone(),
+                None,
+            );
+        }
+
+        // Border
+        builder.push_round_rectangle(rect, radii, GraphicsDisplayPaint::Stroke(stroke), None);
+
+        builder.build()
+    }
+}
+
+struct GroupBoxPainter;
+
+/// Horizontal distance from the left edge of the frame to the gap around the title.
+const GROUP_BOX_TITLE_INSET: f32 = 8.0;
+/// Horizontal space between the title and the border on either side.
+const GROUP_BOX_TITLE_GAP: f32 = 4.0;
+
+impl GroupBoxPainter {
+    fn make_title_item(&self, state: &state::GroupBoxState) -> Option<TextDisplayItem> {
+        if state.data.title.is_empty() {
+            return None;
+        }
+
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        Some(TextDisplayItem {
+            text: state.data.title.clone().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: state.data.foreground.into(),
+        })
+    }
+}
+
+impl draw::Painter<state::GroupBoxState> for GroupBoxPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::GroupBoxState>> {
+        theme.group_box()
+    }
+
+    fn size_hint(&self, state: state::GroupBoxState) -> Size {
+        let padding = state.data.padding;
+        let title = self
+            .make_title_item(&state)
+            .map(|item| {
+                let width = item.bounds().unwrap().size.width;
+                Size::new(
+                    width + (GROUP_BOX_TITLE_INSET + GROUP_BOX_TITLE_GAP) * 2.0,
+                    state.data.typeface.size.ceil(),
+                )
+            })
+            .unwrap_or_default();
+        Size::new(title.width.max(padding * 2.0), title.height + padding * 2.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::GroupBoxState) -> Vec<DisplayCommand> {
+        let frame = base::sharp_align(state.frame.cast_unit());
+        let radii = [state.data.corner_radius; 4];
+        let border = || {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                frame,
+                radii,
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.data.border.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+            builder.build()
+        };
+
+        let mut title = match self.make_title_item(&state) {
+            Some(title) => title,
+            None => return border(),
+        };
+
+        let size = title.bounds().unwrap().size;
+        let title_rect = Rect::new(
+            Point::new(
+                frame.min_x() + GROUP_BOX_TITLE_INSET + GROUP_BOX_TITLE_GAP,
+                frame.min_y() - size.height / 2.0,
+            ),
+            size,
+        );
+        title.set_top_left(title_rect.origin);
+
+        // The border is stroked around the title (left, right and below it), so that the title interrupts the top line
+        let outer = frame.inflate(1.0, 1.0);
+        let gap = title_rect.inflate(GROUP_BOX_TITLE_GAP, 0.0);
+        let regions = [
+            Rect::new(outer.origin, Size::new(gap.min_x() - outer.min_x(), outer.size.height)),
+            Rect::new(
+                Point::new(gap.max_x(), outer.min_y()),
+                Size::new(outer.max_x() - gap.max_x(), outer.size.height),
+            ),
+            Rect::new(
+                Point::new(gap.min_x(), gap.max_y()),
+                Size::new(gap.size.width, (outer.max_y() - gap.max_y()).max(0.0)),
+            ),
+        ];
+
+        let mut commands = Vec::new();
+        for region in &regions {
+            commands.extend(clipped(*region, border()));
+        }
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_text(title, None);
+        commands.extend(builder.build());
+        commands
+    }
+}
+
+struct AvatarPainter;
+
+impl draw::Painter<state::AvatarState> for AvatarPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::AvatarState>> {
+        theme.avatar()
+    }
+
+    fn size_hint(&self, state: state::AvatarState) -> Size {
+        let diameter = state.data.size.diameter();
+        Size::new(diameter, diameter)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for status dot, which overhangs the circle
+        let overhang = rect.size.width.min(rect.size.height) * 0.1;
+        rect.inflate(overhang, overhang)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::AvatarState) -> Vec<DisplayCommand> {
+        let rect: Rect = state.rect.cast_unit();
+        let diameter = rect.size.width.min(rect.size.height);
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            rect,
+            GraphicsDisplayPaint::Fill(
+                if state.loading { state.data.placeholder } else { state.data.background }.into(),
+            ),
+            None,
+        );
+        let initials = state.data.initials();
+        match state.resource {
+            Some(resource) => builder.push_image(state.source, rect, resource, None),
+            // skeleton, without initials, until the picture resolves
+            None if state.loading => {}
+            None if !initials.is_empty() => {
+                let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+                let mut text_item = TextDisplayItem {
+                    text: initials.into(),
+                    font: typeface.0,
+                    font_info: typeface.1,
+                    // initials grow with the avatar
+                    size: diameter * 0.4,
+                    bottom_left: Default::default(),
+                    color: state.data.foreground.into(),
+                };
+                text_item.set_top_left(display::center(text_item.bounds().unwrap().size, rect));
+                builder.push_text(text_item, None);
+            }
+            None => {}
+        }
+
+        let mut commands = draw::clip_circle(rect, builder.build());
+
+        if let Some(status) = state.data.status {
+            // dot at the bottom-right, on the edge of the circle
+            let dot = diameter * 0.3;
+            let center = rect.center()
+                + Vector::new(diameter, diameter) * (std::f32::consts::FRAC_1_SQRT_2 / 2.0);
+            let dot = Rect::new(center - Vector::new(dot, dot) / 2.0, Size::new(dot, dot));
+            let ring = (diameter * 0.05).max(1.5);
+
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                dot.inflate(ring, ring),
+                [dot.size.width / 2.0 + ring; 4],
+                GraphicsDisplayPaint::Fill(state.data.border.into()),
+                None,
+            );
+            builder.push_round_rectangle(
+                dot,
+                [dot.size.width / 2.0; 4],
+                GraphicsDisplayPaint::Fill(status.into()),
+                None,
+            );
+            commands.extend(builder.build());
+        }
+
+        commands
+    }
+}
+
+struct SearchBoxPainter;
+
+impl SearchBoxPainter {
+    fn make_text_item(
+        &self,
+        state: &state::SearchBoxState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::SearchBoxState> for SearchBoxPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SearchBoxState>> {
+        theme.search_box()
+    }
+
+    fn size_hint(&self, state: state::SearchBoxState) -> Size {
+        Size::new(200.0, (state.data.typeface.size * 1.6).round() + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow of the popup
+        rect.inflate(draw::shadow_extent(6.0), draw::shadow_extent(6.0))
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SearchBoxState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let inactive = state.interaction.contains(state::InteractionState::INACTIVE);
+        // the selected suggestion is drawn with the inactive colors while the window is inactive
+        let (highlight, over_highlight) = if inactive {
+            (data.inactive_highlight, data.over_inactive_highlight)
+        } else {
+            (data.highlight, data.over_highlight)
+        };
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.5, data.contrast)
+        } else {
+            data.foreground
+        };
+        let icon_color = draw::weaken(foreground, 0.3, data.contrast);
+        let stroke = |color: Color| GraphicsDisplayStroke {
+            thickness: 1.5,
+            color: color.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        // Magnifying glass
+        let icon = rect.size.height;
+        let lens = Point::new(rect.min_x() + icon / 2.0 - 1.0, rect.center().y - 1.0);
+        let radius = (icon / 6.0).max(4.0);
+        builder.push_round_rectangle(
+            Rect::new(lens - Vector::new(radius, radius), Size::new(radius * 2.0, radius * 2.0)),
+            [radius; 4],
+            GraphicsDisplayPaint::Stroke(stroke(icon_color)),
+            None,
+        );
+        builder.push_line(
+            lens + Vector::new(radius, radius) * std::f32::consts::FRAC_1_SQRT_2,
+            lens + Vector::new(radius, radius) * 1.6,
+            stroke(icon_color),
+            None,
+        );
+
+        let mut commands = builder.build();
+        let mut builder = DisplayListBuilder::new();
+
+        let field = Rect::new(
+            Point::new(rect.min_x() + icon, rect.min_y()),
+            Size::new(
+                (rect.size.width - icon - state.clear.map_or(0.0, |clear| clear.size.width))
+                    .max(0.0),
+                rect.size.height,
+            ),
+        );
+        let (text, color) = if data.text.is_empty() {
+            (&data.placeholder, draw::weaken(foreground, 0.5, data.contrast))
+        } else {
+            (&data.text, foreground)
+        };
+
+        let mut text_item = self.make_text_item(&state, text, color);
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(field.origin + Size::new(0.0, (field.size.height - height) / 2.0));
+
+        if focused && !inactive && !data.disabled {
+            let x = if data.text.is_empty() {
+                field.min_x()
+            } else {
+                text_item.bounds().unwrap().max_x() + 1.0
+            };
+            builder.push_line(
+                Point::new(x, field.min_y() + 5.0),
+                Point::new(x, field.max_y() - 5.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: foreground.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+        commands.extend(clipped(field, builder.build()));
+
+        let mut builder = DisplayListBuilder::new();
+
+        if let Some(clear) = state.clear {
+            let clear: Rect = clear.cast_unit();
+            if state.clear_hovered {
+                builder.push_round_rectangle(
+                    clear.inflate(-4.0, -4.0),
+                    [clear.size.height / 2.0 - 4.0; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(data.background, 0.15, data.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            let mut cross = self.make_text_item(&state, "\u{d7}", icon_color);
+            cross.set_top_left(display::center(cross.bounds().unwrap().size, clear));
+            builder.push_text(cross, None);
+        }
+
+        if focused {
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        if let Some(popup) = state.popup {
+            let popup = base::sharp_align(popup.cast_unit());
+
+            draw::push_drop_shadow(
+                &mut builder,
+                popup,
+                [3.5; 4],
+                6.0,
+                Color::new(0.0, 0.0, 0.0, 0.25),
+            );
+            builder.push_round_rectangle(
+                popup,
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(data.popup_background.into()),
+                None,
+            );
+            builder.push_round_rectangle(
+                popup,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0 / 3.0,
+                    color: draw::strengthen(data.popup_background, 0.2, data.contrast).into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+
+            for suggestion in &state.suggestions {
+                let rect: Rect = suggestion.rect.cast_unit();
+                let color = if suggestion.selected {
+                    builder.push_round_rectangle(
+                        rect,
+                        [2.5; 4],
+                        GraphicsDisplayPaint::Fill(highlight.into()),
+                        None,
+                    );
+                    over_highlight
+                } else {
+                    if suggestion.hovered {
+                        builder.push_round_rectangle(
+                            rect,
+                            [2.5; 4],
+                            GraphicsDisplayPaint::Fill(
+                                draw::strengthen(data.popup_background, 0.08, data.contrast).into(),
+                            ),
+                            None,
+                        );
+                    }
+                    data.foreground
+                };
+
+                let mut text_item = self.make_text_item(&state, &suggestion.text, color);
+                let height = text_item.bounds().unwrap().size.height;
+                text_item
+                    .set_top_left(rect.origin + Size::new(8.0, (rect.size.height - height) / 2.0));
+                builder.push_text(text_item, None);
+            }
+        }
+
+        commands.extend(builder.build());
+        commands
+    }
+}
+
+struct PasswordBoxPainter;
+
+impl PasswordBoxPainter {
+    fn make_text_item(
+        &self,
+        state: &state::PasswordBoxState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::PasswordBoxState> for PasswordBoxPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::PasswordBoxState>> {
+        theme.password_box()
+    }
+
+    fn size_hint(&self, state: state::PasswordBoxState) -> Size {
+        Size::new(200.0, (state.data.typeface.size * 1.6).round() + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::PasswordBoxState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let reveal: Rect = state.reveal.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let inactive = state.interaction.contains(state::InteractionState::INACTIVE);
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.5, data.contrast)
+        } else {
+            data.foreground
+        };
+        let icon_color = if state.reveal_hovered && !data.disabled {
+            foreground
+        } else {
+            draw::weaken(foreground, 0.3, data.contrast)
+        };
+        let stroke = |color: Color| GraphicsDisplayStroke {
+            thickness: 1.5,
+            color: color.into(),
+            ..Default::default()
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        let mut commands = builder.build();
+        let mut builder = DisplayListBuilder::new();
+
+        let field = Rect::new(
+            Point::new(rect.min_x() + 6.0, rect.min_y()),
+            Size::new((reveal.min_x() - rect.min_x() - 6.0).max(0.0), rect.size.height),
+        );
+        let (text, color) = if data.text.is_empty() {
+            (&data.placeholder, draw::weaken(foreground, 0.5, data.contrast))
+        } else {
+            (&state.display_text, foreground)
+        };
+
+        let mut text_item = self.make_text_item(&state, text, color);
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(field.origin + Size::new(0.0, (field.size.height - height) / 2.0));
+
+        if focused && !inactive && !data.disabled {
+            let x = if data.text.is_empty() {
+                field.min_x()
+            } else {
+                text_item.limited_bounds(state.cursor).unwrap().max_x() + 1.0
+            };
+            builder.push_line(
+                Point::new(x, field.min_y() + 5.0),
+                Point::new(x, field.max_y() - 5.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: foreground.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+        commands.extend(clipped(field, builder.build()));
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Reveal toggle; an eye, crossed out while the password is revealed
+        let center = reveal.center();
+        let half = (reveal.size.height / 4.0).max(5.0);
+        builder.push_path(
+            eye_icon(center, half),
+            true,
+            GraphicsDisplayPaint::Stroke(stroke(icon_color)),
+            None,
+        );
+        builder.push_round_rectangle(
+            Rect::new(center - Vector::new(2.0, 2.0), Size::new(4.0, 4.0)),
+            [2.0; 4],
+            GraphicsDisplayPaint::Fill(icon_color.into()),
+            None,
+        );
+        if data.revealed {
+            builder.push_line(
+                center + Vector::new(-half, half) * 0.8,
+                center + Vector::new(half, -half) * 0.8,
+                stroke(icon_color),
+                None,
+            );
+        }
+
+        if let Some(strength) = state.strength {
+            if !data.text.is_empty() {
+                let meter = Rect::new(
+                    Point::new(rect.min_x() + 1.0, rect.max_y() - 3.0),
+                    Size::new((rect.size.width - 2.0) * strength, 2.0),
+                );
+                builder.push_round_rectangle(
+                    meter,
+                    [1.0; 4],
+                    GraphicsDisplayPaint::Fill(draw::mix(data.weak, data.strong, strength).into()),
+                    None,
+                );
+            }
+        }
+
+        if focused {
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        commands.extend(builder.build());
+        commands
+    }
+}
+
+struct MaskedInputPainter;
+
+impl MaskedInputPainter {
+    fn make_text_item(
+        &self,
+        state: &state::MaskedInputState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::MaskedInputState> for MaskedInputPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::MaskedInputState>> {
+        theme.masked_input()
+    }
+
+    fn size_hint(&self, state: state::MaskedInputState) -> Size {
+        Size::new(200.0, (state.data.typeface.size * 1.6).round() + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::MaskedInputState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let inactive = state.interaction.contains(state::InteractionState::INACTIVE);
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.5, data.contrast)
+        } else {
+            data.foreground
+        };
+        let faint = draw::weaken(foreground, 0.5, data.contrast);
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        let mut commands = builder.build();
+        let mut builder = DisplayListBuilder::new();
+
+        let field = Rect::new(
+            Point::new(rect.min_x() + 6.0, rect.min_y()),
+            Size::new((rect.size.width - 12.0).max(0.0), rect.size.height),
+        );
+
+        if data.text.is_empty() && !focused {
+            let mut text_item = self.make_text_item(&state, &data.placeholder, faint);
+            let height = text_item.bounds().unwrap().size.height;
+            text_item
+                .set_top_left(field.origin + Size::new(0.0, (field.size.height - height) / 2.0));
+            builder.push_text(text_item, None);
+        } else {
+            // the filled start of the text, followed by the placeholders (and literals) of the unfilled slots
+            let mut text_item = self.make_text_item(&state, &state.display_text, foreground);
+            let height = text_item.bounds().unwrap().size.height;
+            let origin = field.origin + Size::new(0.0, (field.size.height - height) / 2.0);
+            text_item.set_top_left(origin);
+
+            let x_at = |index: usize| {
+                if index == 0 {
+                    field.min_x()
+                } else {
+                    text_item.limited_bounds(index).unwrap().max_x()
+                }
+            };
+
+            if focused && !inactive && !data.disabled {
+                let x = x_at(state.cursor) + 1.0;
+                builder.push_line(
+                    Point::new(x, field.min_y() + 5.0),
+                    Point::new(x, field.max_y() - 5.0),
+                    GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: foreground.into(),
+                        ..Default::default()
+                    },
+                    None,
+                );
+            }
+
+            let mut filled =
+                self.make_text_item(&state, &state.display_text[..state.filled], foreground);
+            filled.set_top_left(origin);
+            let mut unfilled =
+                self.make_text_item(&state, &state.display_text[state.filled..], faint);
+            unfilled.set_top_left(Point::new(x_at(state.filled), origin.y));
+            builder.push_text(filled, None);
+            builder.push_text(unfilled, None);
+        }
+
+        commands.extend(clipped(field, builder.build()));
+
+        // Outline; incomplete values are marked once the field loses focus
+        let outline = if focused {
+            Some(data.focus)
+        } else if !state.complete && !data.text.is_empty() {
+            Some(data.error)
+        } else {
+            None
+        };
+        if let Some(color) = outline {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: color.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+            commands.extend(builder.build());
+        }
+
+        commands
+    }
+}
+
+struct AutoCompletePainter;
+
+impl AutoCompletePainter {
+    fn make_text_item(
+        &self,
+        state: &state::AutoCompleteState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::AutoCompleteState> for AutoCompletePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::AutoCompleteState>> {
+        theme.auto_complete()
+    }
+
+    fn size_hint(&self, state: state::AutoCompleteState) -> Size {
+        Size::new(200.0, (state.data.typeface.size * 1.6).round() + 8.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow of the popup
+        rect.inflate(draw::shadow_extent(6.0), draw::shadow_extent(6.0))
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::AutoCompleteState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let focused = state.interaction.contains(state::InteractionState::FOCUSED);
+        let inactive = state.interaction.contains(state::InteractionState::INACTIVE);
+        // the selected completion is drawn with the inactive colors while the window is inactive
+        let (highlight, over_highlight) = if inactive {
+            (data.inactive_highlight, data.over_inactive_highlight)
+        } else {
+            (data.highlight, data.over_highlight)
+        };
+        let foreground = if data.disabled {
+            draw::weaken(data.foreground, 0.5, data.contrast)
+        } else {
+            data.foreground
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(data.background.into()),
+            None,
+        );
+
+        let mut commands = builder.build();
+        let mut builder = DisplayListBuilder::new();
+
+        let field = rect.inflate(-8.0, 0.0);
+        let (text, color) = if data.text.is_empty() {
+            (&data.placeholder, draw::weaken(foreground, 0.5, data.contrast))
+        } else {
+            (&data.text, foreground)
+        };
+
+        let mut text_item = self.make_text_item(&state, text, color);
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(field.origin + Size::new(0.0, (field.size.height - height) / 2.0));
+        let end = if data.text.is_empty() {
+            field.min_x()
+        } else {
+            text_item.bounds().unwrap().max_x() + 1.0
+        };
+
+        // the rest of the inline completion follows the caret, as if it were selected text
+        let inline = state.inline.as_ref().filter(|_| !inactive && !data.disabled);
+        if let Some(inline) = inline {
+            let mut inline_item = self.make_text_item(&state, inline, data.over_selection);
+            let inline_bounds = inline_item.bounds().unwrap();
+            inline_item.set_top_left(Point::new(
+                end,
+                field.min_y() + (field.size.height - inline_bounds.size.height) / 2.0,
+            ));
+            builder.push_rectangle(
+                Rect::new(
+                    Point::new(end, field.min_y() + 4.0),
+                    Size::new(inline_bounds.size.width, (field.size.height - 8.0).max(0.0)),
+                ),
+                GraphicsDisplayPaint::Fill(data.selection.into()),
+                None,
+            );
+            builder.push_text(inline_item, None);
+        }
+
+        if focused && !inactive && !data.disabled {
+            builder.push_line(
+                Point::new(end, field.min_y() + 5.0),
+                Point::new(end, field.max_y() - 5.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: foreground.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+        commands.extend(clipped(field, builder.build()));
+
+        let mut builder = DisplayListBuilder::new();
+
+        if focused {
+            builder.push_round_rectangle(
+                rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        if let Some(popup) = state.popup {
+            let popup = base::sharp_align(popup.cast_unit());
+
+            draw::push_drop_shadow(
+                &mut builder,
+                popup,
+                [3.5; 4],
+                6.0,
+                Color::new(0.0, 0.0, 0.0, 0.25),
+            );
+            builder.push_round_rectangle(
+                popup,
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(data.popup_background.into()),
+                None,
+            );
+            builder.push_round_rectangle(
+                popup,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0 / 3.0,
+                    color: draw::strengthen(data.popup_background, 0.2, data.contrast).into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+
+            for completion in &state.completions {
+                let rect: Rect = completion.rect.cast_unit();
+                let color = if completion.selected {
+                    builder.push_round_rectangle(
+                        rect,
+                        [2.5; 4],
+                        GraphicsDisplayPaint::Fill(highlight.into()),
+                        None,
+                    );
+                    over_highlight
+                } else {
+                    if completion.hovered {
+                        builder.push_round_rectangle(
+                            rect,
+                            [2.5; 4],
+                            GraphicsDisplayPaint::Fill(
+                                draw::strengthen(data.popup_background, 0.08, data.contrast).into(),
+                            ),
+                            None,
+                        );
+                    }
+                    data.foreground
+                };
+
+                let mut text_item = self.make_text_item(&state, &completion.text, color);
+                let height = text_item.bounds().unwrap().size.height;
+                text_item
+                    .set_top_left(rect.origin + Size::new(8.0, (rect.size.height - height) / 2.0));
+                let matched_color = if completion.selected { color } else { highlight };
+                for text_item in highlight_text_item(
+                    text_item,
+                    &completion.ranges,
+                    data.typeface.typeface.pick(draw::TextStyle::Bold),
+                    matched_color.into(),
+                ) {
+                    builder.push_text(text_item, None);
+                }
+            }
+        }
+
+        commands.extend(builder.build());
+        commands
+    }
+}
+
+struct CodeEditorPainter;
+
+impl CodeEditorPainter {
+    fn make_text_item(
+        &self,
+        state: &state::CodeEditorState,
+        text: &str,
+        color: Color,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+}
+
+impl draw::Painter<state::CodeEditorState> for CodeEditorPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CodeEditorState>> {
+        theme.code_editor()
+    }
+
+    fn size_hint(&self, _state: state::CodeEditorState) -> Size {
+        Size::new(400.0, 300.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CodeEditorState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+        let gutter: Rect = state.gutter.cast_unit();
+        let viewport: Rect = state.viewport.cast_unit();
+        let dim = |color: Color| {
+            if data.disabled {
+                draw::weaken(color, 0.5, data.contrast)
+            } else {
+                color
+            }
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+
+        let mut commands = builder.build();
+
+        // Code
+        let mut builder = DisplayListBuilder::new();
+        for line in &state.lines {
+            let line_rect: Rect = line.rect.cast_unit();
+            if line.current && !data.disabled {
+                builder.push_rectangle(
+                    Rect::new(
+                        Point::new(viewport.min_x(), line_rect.min_y()),
+                        Size::new(viewport.size.width, line_rect.size.height),
+                    ),
+                    GraphicsDisplayPaint::Fill(data.current_line.into()),
+                    None,
+                );
+            }
+
+            for token in &line.tokens {
+                let text = &line.text[token.range.clone()];
+                if text.trim().is_empty() {
+                    continue;
+                }
+
+                let column = line.text[..token.range.start].chars().count();
+                let mut text_item =
+                    self.make_text_item(&state, text, dim(data.syntax.color(token.kind)));
+                let height = text_item.bounds().unwrap().size.height;
+                text_item.set_top_left(Point::new(
+                    line_rect.min_x() + column as f32 * state.advance,
+                    line_rect.min_y() + (line_rect.size.height - height) / 2.0,
+                ));
+                builder.push_text(text_item, None);
+            }
+        }
+        for cursor in &state.cursors {
+            builder.push_rectangle(
+                cursor.cast_unit(),
+                GraphicsDisplayPaint::Fill(data.cursor_color.into()),
+                None,
+            );
+        }
+        commands.extend(clipped(viewport, builder.build()));
+
+        // Gutter
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            gutter,
+            GraphicsDisplayPaint::Fill(data.gutter_background.into()),
+            None,
+        );
+        for line in &state.lines {
+            let line_rect: Rect = line.rect.cast_unit();
+            let color = if line.current { data.syntax.plain } else { data.gutter_foreground };
+            let mut number = self.make_text_item(&state, &line.number.to_string(), dim(color));
+            let size = number.bounds().unwrap().size;
+            // right-aligned
+            number.set_top_left(Point::new(
+                gutter.max_x() - 8.0 - size.width,
+                line_rect.min_y() + (line_rect.size.height - size.height) / 2.0,
+            ));
+            builder.push_text(number, None);
+        }
+        commands.extend(clipped(gutter, builder.build()));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            rect,
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0,
+                color: if state.interaction.contains(state::InteractionState::FOCUSED) {
+                    data.focus
+                } else {
+                    draw::strengthen(data.background, 0.2, data.contrast)
+                }
+                .into(),
+                ..Default::default()
+            }),
+            None,
+        );
+        commands.extend(builder.build());
+
+        commands
+    }
+}
+
+struct LogViewPainter;
+
+impl draw::Painter<state::LogViewState> for LogViewPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::LogViewState>> {
+        theme.log_view()
+    }
+
+    fn size_hint(&self, _state: state::LogViewState) -> Size {
+        Size::new(400.0, 300.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::LogViewState) -> Vec<DisplayCommand> {
+        let data = &state.data;
+        let rect: Rect = state.rect.cast_unit();
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(data.background.into()), None);
+        let mut commands = builder.build();
+
+        let mut builder = DisplayListBuilder::new();
+        for line in &state.lines {
+            let line_rect: Rect = line.rect.cast_unit();
+            for span in &line.spans {
+                let x = line_rect.min_x() + span.column as f32 * state.advance;
+                if x > rect.max_x() {
+                    break;
+                }
+
+                if let Some(background) = span.background {
+                    builder.push_rectangle(
+                        Rect::new(
+                            Point::new(x, line_rect.min_y()),
+                            Size::new(
+                                span.text.chars().count() as f32 * state.advance,
+                                line_rect.size.height,
+                            ),
+                        ),
+                        GraphicsDisplayPaint::Fill(background.into()),
+                        None,
+                    );
+                }
+
+                if span.text.trim().is_empty() {
+                    continue;
+                }
+
+                let typeface = data.typeface.typeface.pick(span.style);
+                let mut text_item = TextDisplayItem {
+                    text: span.text.clone().into(),
+                    font: typeface.0,
+                    font_info: typeface.1,
+                    size: data.typeface.size,
+                    bottom_left: Default::default(),
+                    color: span.foreground.into(),
+                };
+                let height = text_item.bounds().unwrap().size.height;
+                text_item.set_top_left(Point::new(
+                    x,
+                    line_rect.min_y() + (line_rect.size.height - height) / 2.0,
+                ));
+                builder.push_text(text_item, None);
+            }
+        }
+
+        if let Some(thumb) = state.thumb {
+            builder.push_round_rectangle(
+                thumb.cast_unit(),
+                [2.0; 4],
+                GraphicsDisplayPaint::Fill(
+                    draw::with_opacity(draw::strengthen(data.background, 0.4, data.contrast), 0.6)
+                        .into(),
+                ),
+                None,
+            );
+        }
+        commands.extend(clipped(rect, builder.build()));
+
+        commands
+    }
+}
+
+#[cfg(feature = "extra-widgets")]
+struct QrCodePainter;
+
+#[cfg(feature = "extra-widgets")]
+impl draw::Painter<state::QrCodeState> for QrCodePainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::QrCodeState>> {
+        theme.qr_code()
+    }
+
+    fn size_hint(&self, state: state::QrCodeState) -> Size {
+        let quiet = 2 * state.data.quiet_zone as usize;
+        if state.modules.rows == 1 {
+            Size::new(((state.modules.columns + quiet) * 2) as f32, 60.0)
+        } else {
+            let side = ((state.modules.columns + quiet) * 4) as f32;
+            Size::new(side, side)
+        }
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::QrCodeState) -> Vec<DisplayCommand> {
+        let rect: Rect = state.rect.cast_unit();
+        let modules = &state.modules;
+        let quiet = state.data.quiet_zone as f32;
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle(
+            rect,
+            GraphicsDisplayPaint::Fill(state.data.background.into()),
+            None,
+        );
+
+        if modules.columns == 0 {
+            return builder.build();
+        }
+
+        // 1D barcodes stretch vertically, whereas 2D codes keep square modules.
+        let module_width = rect.size.width / (modules.columns as f32 + 2.0 * quiet);
+        let (module_size, origin) = if modules.rows == 1 {
+            let height = (rect.size.height - 2.0 * quiet * module_width).max(module_width);
+            (
+                Size::new(module_width, height),
+                Point::new(
+                    rect.min_x() + quiet * module_width,
+                    rect.min_y() + (rect.size.height - height) / 2.0,
+                ),
+            )
+        } else {
+            let module = rect.size.width.min(rect.size.height)
+                / (modules.columns.max(modules.rows) as f32 + 2.0 * quiet);
+            let code = Size::new(modules.columns as f32, modules.rows as f32) * module;
+            (
+                Size::new(module, module),
+                Point::new(
+                    rect.min_x() + (rect.size.width - code.width) / 2.0,
+                    rect.min_y() + (rect.size.height - code.height) / 2.0,
+                ),
+            )
+        };
+
+        // Merge horizontal runs of dark modules to avoid seams between adjacent rectangles.
+        for row in 0..modules.rows {
+            let mut column = 0;
+            while column < modules.columns {
+                if !modules.is_dark(column, row) {
+                    column += 1;
+                    continue;
+                }
+
+                let start = column;
+                while column < modules.columns && modules.is_dark(column, row) {
+                    column += 1;
+                }
+
+                builder.push_rectangle(
+                    Rect::new(
+                        origin
+                            + Vector::new(
+                                start as f32 * module_size.width,
+                                row as f32 * module_size.height,
+                            ),
+                        Size::new((column - start) as f32 * module_size.width, module_size.height),
+                    ),
+                    GraphicsDisplayPaint::Fill(state.data.foreground.into()),
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    //! Theme and auxiliaries for testing widgets and painters without a window.
+
+    use {super::*, reclutch::event::RcEventQueue};
+
+    pub(crate) struct UAux {
+        window_queue: RcEventQueue<base::WindowEvent>,
+        g_aux: GAux,
+    }
+
+    pub(crate) struct GAux;
+
+    impl base::UpdateAuxiliary for UAux {
+        fn window_queue(&self) -> &RcEventQueue<base::WindowEvent> {
+            &self.window_queue
+        }
+
+        fn window_queue_mut(&mut self) -> &mut RcEventQueue<base::WindowEvent> {
+            &mut self.window_queue
+        }
+
+        fn graphical(&self) -> &dyn base::GraphicalAuxiliary {
+            &self.g_aux
+        }
+
+        fn graphical_mut(&mut self) -> &mut dyn base::GraphicalAuxiliary {
+            &mut self.g_aux
+        }
+    }
+
+    impl base::GraphicalAuxiliary for GAux {
+        fn scaling(&self) -> f32 {
+            1.0
+        }
+    }
+
+    pub(crate) fn u_aux() -> UAux {
+        UAux { window_queue: RcEventQueue::new(), g_aux: GAux }
+    }
+
+    /// The theme, with fonts which haven't been loaded into a display (painters only need their metrics).
+    pub(crate) fn primer() -> Primer {
+        let fonts = font_data();
+        let font = |i: usize| {
+            (ResourceReference::Font(i as u64), FontInfo::from_data(fonts[i].clone(), 0).unwrap())
+        };
+        Primer::with_typeface(draw::Typeface {
+            regular: font(0),
+            italic: font(1),
+            bold: font(2),
+            bold_italic: font(3),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            testing::{primer, GAux, UAux},
+            *,
+        },
+        crate::{
+            draw::{
+                harness::{self, StateMatrix},
+                Theme as _,
+            },
+            ui::{self, WidgetConstructor},
+        },
+    };
+
+    fn rect() -> AbsoluteRect {
+        AbsoluteRect::new(AbsolutePoint::new(10.0, 10.0), Size::new(160.0, 40.0).cast_unit())
+    }
+
+    #[test]
+    fn button_painter() {
+        let theme = primer();
+        let mut data = <ui::Button as WidgetConstructor<UAux, GAux>>::from_theme(&theme);
+        data.text = "Button".to_string().into();
+        let base = state::ButtonState {
+            rect: rect(),
+            data,
+            icon: None,
+            interaction: state::InteractionState::empty(),
+        };
+
+        let matrix = StateMatrix::new("button", base)
+            .vary(&[
+                ("default", &|_: &mut state::ButtonState| {}),
+                ("danger", &|state: &mut state::ButtonState| {
+                    state.data.classes = vec!["danger".to_string()]
+                }),
+            ])
+            .vary_interaction(|state| &mut state.interaction, |state| &mut state.data.disabled);
+        harness::check_painter(
+            &mut *theme.button(),
+            &matrix,
+            |state| state.rect,
+            Default::default(),
+        )
+        .assert_ok();
+    }
+
+    #[test]
+    fn checkbox_painter() {
+        let theme = primer();
+        let mut data = <ui::Checkbox as WidgetConstructor<UAux, GAux>>::from_theme(&theme);
+        data.label = "Checkbox".to_string().into();
+        let base = state::CheckboxState {
+            rect: rect(),
+            data,
+            icon: None,
+            interaction: state::InteractionState::empty(),
+        };
+
+        let matrix = StateMatrix::new("checkbox", base)
+            .vary(&[
+                ("unchecked", &|state: &mut state::CheckboxState| state.data.checked = false),
+                ("checked", &|state: &mut state::CheckboxState| state.data.checked = true),
+            ])
+            .vary_interaction(|state| &mut state.interaction, |state| &mut state.data.disabled);
+        harness::check_painter(
+            &mut *theme.checkbox(),
+            &matrix,
+            |state| state.rect,
+            Default::default(),
+        )
+        .assert_ok();
+    }
+
+    #[test]
+    fn text_area_painter() {
+        let theme = primer();
+        let mut data = <ui::TextArea as WidgetConstructor<UAux, GAux>>::from_theme(&theme);
+        data.placeholder = "Placeholder".to_string();
+        let base = state::TextAreaState {
+            rect: rect(),
+            data,
+            interaction: state::InteractionState::empty(),
+        };
+
+        let matrix = StateMatrix::new("text area", base)
+            .vary(&[
+                ("placeholder", &|_: &mut state::TextAreaState| {}),
+                ("text", &|state: &mut state::TextAreaState| {
+                    state.data.text = "Text".to_string();
+                    state.data.cursor = state.data.text.len();
+                }),
+            ])
+            .vary_interaction(|state| &mut state.interaction, |state| &mut state.data.disabled);
+        harness::check_painter(
+            &mut *theme.text_area(),
+            &matrix,
+            |state| state.rect,
+            Default::default(),
+        )
+        .assert_ok();
+    }
+}
@@ -0,0 +1,472 @@
+//! Layout which pins children to the edges of the container or of each other.
+
+use {
+    crate::{base, draw, geom::*, ui},
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::collections::HashMap,
+};
+
+/// What an anchor is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnchorTarget {
+    /// The anchor layout itself, inset by its `padding`.
+    Parent,
+    /// Another child of the same anchor layout, by its layout ID (see `LayableWidget::layout_id`).
+    /// Anchors to children which have been removed are ignored.
+    Sibling(u64),
+}
+
+/// Line of a target along one axis; the left, center or right for horizontal anchors, and the top, center or bottom
+/// for vertical anchors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnchorEdge {
+    Start,
+    Center,
+    End,
+}
+
+/// Line which an edge (or the center) of a child is attached to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    pub target: AnchorTarget,
+    pub edge: AnchorEdge,
+    /// Distance from the line of the target, towards the right or the bottom (e.g. negative to keep a child's right
+    /// edge away from the right of the container).
+    pub offset: f32,
+}
+
+impl Anchor {
+    /// Creates an anchor to a line of the anchor layout.
+    pub fn parent(edge: AnchorEdge, offset: f32) -> Self {
+        Anchor { target: AnchorTarget::Parent, edge, offset }
+    }
+
+    /// Creates an anchor to a line of another child, by its layout ID.
+    pub fn sibling(id: u64, edge: AnchorEdge, offset: f32) -> Self {
+        Anchor { target: AnchorTarget::Sibling(id), edge, offset }
+    }
+
+    fn resolve(&self, (start, end): (f32, f32)) -> f32 {
+        let line = match self.edge {
+            AnchorEdge::Start => start,
+            AnchorEdge::Center => (start + end) / 2.0,
+            AnchorEdge::End => end,
+        };
+        line + self.offset
+    }
+}
+
+/// Anchors of a child of an `AnchorLayout`, for each axis.
+///
+/// Along each axis, a child anchored at both edges is stretched between them, and a child anchored at one edge or
+/// at its center keeps its size. The center anchor is ignored if either edge is anchored. A child without anchors
+/// along an axis keeps its position along it.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct AnchorItem {
+    pub left: Option<Anchor>,
+    pub right: Option<Anchor>,
+    pub top: Option<Anchor>,
+    pub bottom: Option<Anchor>,
+    pub horizontal_center: Option<Anchor>,
+    pub vertical_center: Option<Anchor>,
+}
+
+impl AnchorItem {
+    /// Pins the left edge `offset` right of the left of the container.
+    pub fn left(self, offset: f32) -> AnchorItem {
+        AnchorItem { left: Some(Anchor::parent(AnchorEdge::Start, offset)), ..self }
+    }
+
+    /// Pins the right edge `offset` left of the right of the container.
+    pub fn right(self, offset: f32) -> AnchorItem {
+        AnchorItem { right: Some(Anchor::parent(AnchorEdge::End, -offset)), ..self }
+    }
+
+    /// Pins the top edge `offset` below the top of the container.
+    pub fn top(self, offset: f32) -> AnchorItem {
+        AnchorItem { top: Some(Anchor::parent(AnchorEdge::Start, offset)), ..self }
+    }
+
+    /// Pins the bottom edge `offset` above the bottom of the container.
+    pub fn bottom(self, offset: f32) -> AnchorItem {
+        AnchorItem { bottom: Some(Anchor::parent(AnchorEdge::End, -offset)), ..self }
+    }
+
+    /// Centers the child horizontally within the container.
+    pub fn center_horizontally(self) -> AnchorItem {
+        AnchorItem { horizontal_center: Some(Anchor::parent(AnchorEdge::Center, 0.0)), ..self }
+    }
+
+    /// Centers the child vertically within the container.
+    pub fn center_vertically(self) -> AnchorItem {
+        AnchorItem { vertical_center: Some(Anchor::parent(AnchorEdge::Center, 0.0)), ..self }
+    }
+
+    /// Centers the child within the container.
+    pub fn center(self) -> AnchorItem {
+        self.center_horizontally().center_vertically()
+    }
+
+    /// Sets the `left` anchor.
+    pub fn left_to(self, anchor: Anchor) -> AnchorItem {
+        AnchorItem { left: Some(anchor), ..self }
+    }
+
+    /// Sets the `right` anchor.
+    pub fn right_to(self, anchor: Anchor) -> AnchorItem {
+        AnchorItem { right: Some(anchor), ..self }
+    }
+
+    /// Sets the `top` anchor.
+    pub fn top_to(self, anchor: Anchor) -> AnchorItem {
+        AnchorItem { top: Some(anchor), ..self }
+    }
+
+    /// Sets the `bottom` anchor.
+    pub fn bottom_to(self, anchor: Anchor) -> AnchorItem {
+        AnchorItem { bottom: Some(anchor), ..self }
+    }
+
+    /// Returns the siblings which the anchors are relative to.
+    fn siblings(&self) -> Vec<u64> {
+        let anchors = [
+            self.left,
+            self.right,
+            self.top,
+            self.bottom,
+            self.horizontal_center,
+            self.vertical_center,
+        ];
+        let mut siblings = Vec::new();
+        for anchor in anchors.iter().flatten() {
+            if let AnchorTarget::Sibling(id) = anchor.target {
+                siblings.push(id);
+            }
+        }
+        siblings
+    }
+}
+
+/// Places a child along one axis, from its current extent and its anchors along the axis.
+/// Anchors which can't be resolved are ignored.
+fn place(
+    (origin, size): (f32, f32),
+    start: Option<f32>,
+    end: Option<f32>,
+    center: Option<f32>,
+) -> (f32, f32) {
+    match (start, end, center) {
+        (Some(start), Some(end), _) => (start, (end - start).max(0.0)),
+        (Some(start), None, _) => (start, size),
+        (None, Some(end), _) => (end - size, size),
+        (None, None, Some(center)) => (center - size / 2.0, size),
+        (None, None, None) => (origin, size),
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: AnchorItem,
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Layout which places each child by anchoring its edges (or its center) to the edges of the container or of
+/// other children, e.g. for dialogs with buttons pinned to a corner which stay there as the dialog is resized.
+///
+/// Children are re-placed whenever the layout or any child changes size. Anchors to other children are resolved
+/// after those children are placed; anchors forming a cycle are resolved against the rectangles the children had
+/// before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnchorLayout {
+    /// Inset of the edges of the container which children are anchored to.
+    pub padding: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for AnchorLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AnchorLayoutWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for AnchorLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        AnchorLayout { padding: 0.0 }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, _u_aux: &mut U) -> AnchorLayoutWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            AnchorLayoutWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                }
+            }
+        };
+
+        AnchorLayoutWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for AnchorLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct AnchorLayoutWidget {
+        widget::MAX,
+
+        <AnchorLayout> State,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> AnchorLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the anchors of a child which has been pushed, by its layout ID.
+    pub fn set_anchors(&mut self, id: u64, anchors: AnchorItem) {
+        if let Some(child) = self.rects.get_mut(&id) {
+            child.data = anchors;
+            self.dirty = true;
+        }
+    }
+
+    /// Returns the anchors of a child which has been pushed, by its layout ID.
+    pub fn anchors(&self, id: u64) -> Option<AnchorItem> {
+        self.rects.get(&id).map(|child| child.data)
+    }
+
+    /// Places a child from its anchors, with `placed` holding the rectangles of the children placed so far.
+    fn place_child(
+        &self,
+        child: &ChildData,
+        container: AbsoluteRect,
+        placed: &HashMap<u64, AbsoluteRect>,
+    ) -> AbsoluteRect {
+        let target = |anchor: &Anchor| match anchor.target {
+            AnchorTarget::Parent => Some(container),
+            AnchorTarget::Sibling(id) if id == child.id => None,
+            AnchorTarget::Sibling(id) => {
+                placed.get(&id).copied().or_else(|| self.rects.get(&id).map(|sibling| sibling.rect))
+            }
+        };
+        let horizontal = |anchor: &Option<Anchor>| {
+            let anchor = anchor.as_ref()?;
+            target(anchor).map(|rect| anchor.resolve((rect.min_x(), rect.max_x())))
+        };
+        let vertical = |anchor: &Option<Anchor>| {
+            let anchor = anchor.as_ref()?;
+            target(anchor).map(|rect| anchor.resolve((rect.min_y(), rect.max_y())))
+        };
+
+        let item = &child.data;
+        let rect = child.rect;
+        let (x, width) = place(
+            (rect.origin.x, rect.size.width),
+            horizontal(&item.left),
+            horizontal(&item.right),
+            horizontal(&item.horizontal_center),
+        );
+        let (y, height) = place(
+            (rect.origin.y, rect.size.height),
+            vertical(&item.top),
+            vertical(&item.bottom),
+            vertical(&item.vertical_center),
+        );
+
+        AbsoluteRect::new(AbsolutePoint::new(x, y), Size::new(width, height).cast_unit())
+    }
+
+    fn update_layout(&mut self) {
+        let padding = self.data.padding;
+        let container = self.abs_rect().inflate(-padding, -padding);
+
+        // children are placed once the siblings they're anchored to have been placed
+        let mut placed: HashMap<u64, AbsoluteRect> = HashMap::new();
+        while placed.len() < self.rects.len() {
+            let mut progress = false;
+            for (&id, child) in &self.rects {
+                let ready = child.data.siblings().into_iter().all(|sibling| {
+                    sibling == id
+                        || placed.contains_key(&sibling)
+                        || !self.rects.contains_key(&sibling)
+                });
+                if !placed.contains_key(&id) && ready {
+                    let rect = self.place_child(child, container, &placed);
+                    placed.insert(id, rect);
+                    progress = true;
+                }
+            }
+
+            if !progress {
+                // the rest are anchored in a cycle
+                for (&id, child) in &self.rects {
+                    if !placed.contains_key(&id) {
+                        let rect = self.place_child(child, container, &placed);
+                        placed.insert(id, rect);
+                    }
+                }
+            }
+        }
+
+        for (id, child) in &mut self.rects {
+            if let Some(&rect) = placed.get(id) {
+                if rect != child.rect {
+                    child.rect = rect;
+                    child.evq.emit_owned(rect);
+                }
+            }
+        }
+    }
+}
+
+impl<U, G> base::Layout for AnchorLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = AnchorItem;
+
+    fn push(&mut self, data: Option<AnchorItem>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data: data.unwrap_or_default(),
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.shift_remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for AnchorLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.shift_remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.dirty = false;
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! Markup canvas for annotating other widgets.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        error::AnnotationError,
+        geom::*,
+        ui::{self, SketchPoint, SketchStroke},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Point, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by an annotation canvas.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum AnnotationEvent {
+    /// Emitted when an annotation is added, with the index of the annotation.
+    #[event_key(add)]
+    Add(usize),
+    /// Emitted when the last annotation is undone.
+    #[event_key(undo)]
+    Undo,
+    /// Emitted when an undone annotation is restored.
+    #[event_key(redo)]
+    Redo,
+}
+
+/// The kind of annotation created by user input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnotationTool {
+    /// Input is ignored, leaving the canvas as a passive overlay.
+    None,
+    Freehand,
+    Arrow,
+    Rectangle,
+    /// A text box with a leader line pointing to where the drag started.
+    Callout,
+}
+
+/// A single annotation. All the positions are relative to the top-left of the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Annotation {
+    Freehand(SketchStroke),
+    Arrow { from: Point, to: Point, color: Color, width: f32 },
+    Rectangle { rect: Rect, color: Color, width: f32 },
+    Callout { anchor: Point, position: Point, text: String, color: Color },
+}
+
+impl Annotation {
+    fn drag_to(&mut self, point: Point, pressure: f32) {
+        match self {
+            Annotation::Freehand(stroke) => {
+                stroke.points.push(SketchPoint { position: point, pressure })
+            }
+            Annotation::Arrow { to, .. } => *to = point,
+            Annotation::Rectangle { rect, .. } => rect.size = (point - rect.origin).to_size(),
+            Annotation::Callout { position, .. } => *position = point,
+        }
+    }
+
+    fn normalized(self) -> Self {
+        match self {
+            Annotation::Rectangle { rect, color, width } => Annotation::Rectangle {
+                rect: Rect::from_points(&[rect.origin, rect.origin + rect.size]),
+                color,
+                width,
+            },
+            other => other,
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(c) => out.push(c),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Serializes a list of annotations into a line-based text format, which can be read back with `deserialize_annotations`.
+pub fn serialize_annotations(annotations: &[Annotation]) -> String {
+    let color = |c: &Color| format!("{} {} {} {}", c.red, c.green, c.blue, c.alpha);
+
+    annotations
+        .iter()
+        .map(|annotation| match annotation {
+            Annotation::Freehand(stroke) => {
+                let points: Vec<String> = stroke
+                    .points
+                    .iter()
+                    .map(|p| format!("{},{},{}", p.position.x, p.position.y, p.pressure))
+                    .collect();
+                format!("freehand {} {} {}", color(&stroke.color), stroke.width, points.join(" "))
+            }
+            Annotation::Arrow { from, to, color: c, width } => {
+                format!("arrow {} {} {} {} {} {}", color(c), width, from.x, from.y, to.x, to.y)
+            }
+            Annotation::Rectangle { rect, color: c, width } => format!(
+                "rectangle {} {} {} {} {} {}",
+                color(c),
+                width,
+                rect.origin.x,
+                rect.origin.y,
+                rect.size.width,
+                rect.size.height
+            ),
+            Annotation::Callout { anchor, position, text, color: c } => format!(
+                "callout {} {} {} {} {} {}",
+                color(c),
+                anchor.x,
+                anchor.y,
+                position.x,
+                position.y,
+                escape(text)
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses annotations previously serialized with `serialize_annotations`.
+pub fn deserialize_annotations(text: &str) -> Result<Vec<Annotation>, AnnotationError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line_number = i + 1;
+            let malformed = || AnnotationError::Malformed(line_number);
+
+            let mut parts = line.splitn(2, ' ');
+            let kind = parts.next().unwrap_or_default();
+            let rest = parts.next().unwrap_or_default();
+
+            // Callout text may contain spaces, so only split off the numeric fields.
+            let field_count = match kind {
+                "freehand" => 5,
+                "arrow" => 9,
+                "rectangle" => 9,
+                "callout" => 8,
+                _ => return Err(AnnotationError::UnknownKind(kind.to_string(), line_number)),
+            };
+            let mut fields = rest.splitn(field_count + 1, ' ');
+            let mut numbers = Vec::with_capacity(field_count);
+            for _ in 0..field_count {
+                numbers
+                    .push(fields.next().and_then(|x| x.parse::<f32>().ok()).ok_or_else(malformed)?);
+            }
+            let remainder = fields.next().unwrap_or_default();
+
+            let color = Color::new(numbers[0], numbers[1], numbers[2], numbers[3]);
+
+            Ok(match kind {
+                "freehand" => Annotation::Freehand(SketchStroke {
+                    points: remainder
+                        .split_whitespace()
+                        .map(|point| {
+                            let coords: Vec<f32> =
+                                point.split(',').filter_map(|x| x.parse().ok()).collect();
+                            if coords.len() == 3 {
+                                Ok(SketchPoint {
+                                    position: Point::new(coords[0], coords[1]),
+                                    pressure: coords[2],
+                                })
+                            } else {
+                                Err(malformed())
+                            }
+                        })
+                        .collect::<Result<_, _>>()?,
+                    color,
+                    width: numbers[4],
+                }),
+                "arrow" => Annotation::Arrow {
+                    from: Point::new(numbers[5], numbers[6]),
+                    to: Point::new(numbers[7], numbers[8]),
+                    color,
+                    width: numbers[4],
+                },
+                "rectangle" => Annotation::Rectangle {
+                    rect: Rect::new(
+                        Point::new(numbers[5], numbers[6]),
+                        Size::new(numbers[7], numbers[8]),
+                    ),
+                    color,
+                    width: numbers[4],
+                },
+                _ => Annotation::Callout {
+                    anchor: Point::new(numbers[4], numbers[5]),
+                    position: Point::new(numbers[6], numbers[7]),
+                    text: unescape(remainder),
+                    color,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Generates an unbound terminal which creates annotations from mouse and pen input.
+pub fn annotation_canvas_handler<U, G>(
+) -> vg::UnboundQueueHandler<AnnotationCanvasWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        AnnotationCanvasWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                obj.accepts_input() && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.begin_annotation(*pos, 1.0);
+            }
+        }
+
+        mouse_move => {
+            if let Some((pos, _)) = event.with(|_| obj.dragging) {
+                obj.drag_annotation(*pos, 1.0);
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.dragging && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.end_annotation();
+            }
+        }
+
+        pen_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, pen, _)) =
+                event.with(|(pos, _, _)| obj.accepts_input() && bounds.contains(*pos))
+            {
+                obj.begin_annotation(*pos, pen.pressure);
+            }
+        }
+
+        pen_move => {
+            if let Some((pos, pen, _)) = event.with(|_| obj.dragging) {
+                obj.drag_annotation(*pos, pen.pressure);
+            }
+        }
+
+        pen_release => {
+            if event.with(|_| obj.dragging).is_some() {
+                obj.end_annotation();
+            }
+        }
+    }
+}
+
+/// Transparent canvas which records markup (freehand strokes, arrows, rectangles and callouts).
+///
+/// Intended to be placed over another widget (e.g. a screenshot) with the same rectangle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationCanvas {
+    pub annotations: Vec<Annotation>,
+    pub tool: AnnotationTool,
+    pub color: Color,
+    pub stroke_width: f32,
+    /// Text of new callouts.
+    pub callout_text: String,
+    pub typeface: draw::TypefaceStyle,
+    /// Background color of callout text boxes.
+    pub callout_background: Color,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for AnnotationCanvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AnnotationCanvasWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for AnnotationCanvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        AnnotationCanvas {
+            annotations: Vec::new(),
+            tool: AnnotationTool::Freehand,
+            color: data.scheme.error,
+            stroke_width: 3.0,
+            callout_text: String::new(),
+            typeface: data.typography.body.clone(),
+            callout_background: data.scheme.background,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> AnnotationCanvasWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            AnnotationCanvasWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph =
+            graph.add("annotate", annotation_canvas_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.annotation_canvas();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::AnnotationCanvasState {
+                    rect: AbsoluteRect::new(
+                        Default::default(),
+                        Size::new(200.0, 200.0).cast_unit(),
+                    ),
+                    data: data.clone(),
+                })
+                .cast_unit(),
+        );
+
+        AnnotationCanvasWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            redo: Vec::new(),
+            dragging: false,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::AnnotationCanvasState> for AnnotationCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::AnnotationCanvasState {
+        state::AnnotationCanvasState { rect: self.abs_rect(), data: self.data.clone() }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct AnnotationCanvasWidget {
+        widget::MAX,
+
+        <AnnotationEvent> EventQueue,
+        <AnnotationCanvas> State,
+        <state::AnnotationCanvasState> Painter,
+
+        {
+            redo: Vec<Annotation>,
+            dragging: bool,
+        },
+    }
+}
+
+impl<U, G> AnnotationCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Removes the most recent annotation, so that it can be restored with `redo`.
+    pub fn undo(&mut self) {
+        if let Some(annotation) = self.data.annotations.pop() {
+            self.redo.push(annotation);
+            self.event_queue.emit_owned(AnnotationEvent::Undo);
+        }
+    }
+
+    /// Restores the most recently undone annotation.
+    pub fn redo(&mut self) {
+        if let Some(annotation) = self.redo.pop() {
+            self.data.annotations.push(annotation);
+            self.event_queue.emit_owned(AnnotationEvent::Redo);
+        }
+    }
+
+    /// Returns `true` if there is an annotation which can be undone.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.data.annotations.is_empty()
+    }
+
+    /// Returns `true` if there is an undone annotation which can be restored.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns the annotations in the format of `serialize_annotations`.
+    pub fn serialize(&self) -> String {
+        serialize_annotations(&self.data.annotations)
+    }
+
+    /// Replaces all the annotations with those parsed from `text` (see `deserialize_annotations`).
+    /// The undo history is cleared.
+    pub fn deserialize(&mut self, text: &str) -> Result<(), AnnotationError> {
+        self.data.annotations = deserialize_annotations(text)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Returns display commands which render the annotations with the top-left of the canvas at the origin.
+    ///
+    /// These can be pushed into any `GraphicsDisplay`, such as an offscreen one, to export the markup.
+    pub fn export(&mut self) -> Vec<DisplayCommand> {
+        let mut state = self.derive_state();
+        state.rect.origin = Default::default();
+        self.painter.draw(state)
+    }
+
+    fn accepts_input(&self) -> bool {
+        !self.data.disabled && self.data.tool != AnnotationTool::None
+    }
+
+    fn local_point(&self, pos: AbsolutePoint) -> Point {
+        (pos - self.abs_rect().origin).to_point().cast_unit()
+    }
+
+    fn begin_annotation(&mut self, pos: AbsolutePoint, pressure: f32) {
+        let point = self.local_point(pos);
+        let color = self.data.color;
+        let width = self.data.stroke_width;
+
+        let annotation = match self.data.tool {
+            AnnotationTool::None => return,
+            AnnotationTool::Freehand => Annotation::Freehand(SketchStroke {
+                points: vec![SketchPoint { position: point, pressure }],
+                color,
+                width,
+            }),
+            AnnotationTool::Arrow => Annotation::Arrow { from: point, to: point, color, width },
+            AnnotationTool::Rectangle => {
+                Annotation::Rectangle { rect: Rect::new(point, Size::zero()), color, width }
+            }
+            AnnotationTool::Callout => Annotation::Callout {
+                anchor: point,
+                position: point,
+                text: self.data.callout_text.clone(),
+                color,
+            },
+        };
+
+        self.dragging = true;
+        self.data.annotations.push(annotation);
+    }
+
+    fn drag_annotation(&mut self, pos: AbsolutePoint, pressure: f32) {
+        let point = self.local_point(pos);
+        if let Some(annotation) = self.data.annotations.last_mut() {
+            annotation.drag_to(point, pressure);
+        }
+    }
+
+    fn end_annotation(&mut self) {
+        self.dragging = false;
+        if let Some(annotation) = self.data.annotations.pop() {
+            self.data.annotations.push(annotation.normalized());
+            self.redo.clear();
+            self.event_queue.emit_owned(AnnotationEvent::Add(self.data.annotations.len() - 1));
+        }
+    }
+}
+
+impl<U, G> Widget for AnnotationCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,673 @@
+//! Text field which completes the typed text from a list of completions.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        fuzzy::{self, FuzzyMatch},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventListener,
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Space between the edges of the popup and its rows.
+const POPUP_PADDING: f32 = 4.0;
+/// Space between the field and the popup.
+const POPUP_OFFSET: f32 = 2.0;
+
+/// Events emitted by an autocomplete field.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum AutoCompleteEvent {
+    /// Emitted when a completion has been accepted, with the completed text.
+    #[event_key(complete)]
+    Complete(String),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Decides which completions match the typed text of an autocomplete field, and how well.
+pub trait CompletionMatcher {
+    /// Matches the typed text against a completion, returning `None` if it doesn't match.
+    ///
+    /// Completions are listed from the highest to the lowest score, and the ranges of the match are highlighted.
+    fn matches(&self, text: &str, completion: &str) -> Option<FuzzyMatch>;
+}
+
+impl<F: Fn(&str, &str) -> Option<FuzzyMatch>> CompletionMatcher for F {
+    #[inline]
+    fn matches(&self, text: &str, completion: &str) -> Option<FuzzyMatch> {
+        (self)(text, completion)
+    }
+}
+
+/// Matches completions fuzzily (see [`fuzzy_match`](../fuzzy/fn.fuzzy_match.html)); the default matcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FuzzyMatcher;
+
+impl CompletionMatcher for FuzzyMatcher {
+    #[inline]
+    fn matches(&self, text: &str, completion: &str) -> Option<FuzzyMatch> {
+        fuzzy::fuzzy_match(text, completion)
+    }
+}
+
+/// Matches completions which start with the typed text, ignoring case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrefixMatcher;
+
+impl CompletionMatcher for PrefixMatcher {
+    fn matches(&self, text: &str, completion: &str) -> Option<FuzzyMatch> {
+        prefix_len(text, completion).map(|len| FuzzyMatch { score: 0, ranges: vec![0..len] })
+    }
+}
+
+/// Returns the byte length of the start of `completion` which `text` is a prefix of, ignoring case.
+fn prefix_len(text: &str, completion: &str) -> Option<usize> {
+    let mut completion_chars = completion.char_indices();
+    for c in text.chars() {
+        match completion_chars.next() {
+            Some((_, d)) if c.to_lowercase().eq(d.to_lowercase()) => {}
+            _ => return None,
+        }
+    }
+    Some(completion_chars.next().map_or(completion.len(), |(i, _)| i))
+}
+
+/// Generates an unbound terminal which handles typing, navigating and accepting completions.
+pub fn auto_complete_handler<U, G>(
+) -> vg::UnboundQueueHandler<AutoCompleteWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        AutoCompleteWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && (bounds.contains(*pos) || obj.completion_at(*pos).is_some())
+            }) {
+                if let Some(index) = obj.completion_at(*pos) {
+                    obj.choose(index);
+                } else {
+                    obj.focus();
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.completion_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            // keys which aren't handled (e.g. tab without an inline completion) are left for other widgets
+            if let Some((key, _)) = event.with(|(key, _)| {
+                !obj.data.disabled
+                    && obj.interaction.contains(state::InteractionState::FOCUSED)
+                    && obj.handles_key(*key)
+            }) {
+                let len = obj.matches.len();
+                match key {
+                    base::KeyInput::Back => {
+                        // the first backspace only rejects the inline completion
+                        if obj.inline.is_some() {
+                            obj.suppress_inline = true;
+                            obj.refresh();
+                        } else {
+                            obj.data.text.pop();
+                        }
+                    }
+                    base::KeyInput::Up => {
+                        let selected = obj.selected.map_or(len - 1, |i| (i + len - 1) % len);
+                        obj.select(Some(selected));
+                    }
+                    base::KeyInput::Down => {
+                        let selected = obj.selected.map_or(0, |i| (i + 1) % len);
+                        obj.select(Some(selected));
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                        match obj.selected.filter(|_| obj.open) {
+                            Some(selected) => obj.choose(selected),
+                            None => obj.accept_inline(),
+                        }
+                    }
+                    base::KeyInput::Tab | base::KeyInput::Right | base::KeyInput::End => {
+                        obj.accept_inline();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        escape => {
+            // the popup is closed first, then the inline completion is rejected
+            if let Some(stage) = event.with(|stage| {
+                obj.interaction.contains(state::InteractionState::FOCUSED)
+                    && match stage {
+                        base::EscapeStage::Popup => obj.open,
+                        base::EscapeStage::Cancel => obj.inline.is_some(),
+                        _ => false,
+                    }
+            }) {
+                if *stage == base::EscapeStage::Popup {
+                    obj.close();
+                } else {
+                    obj.suppress_inline = true;
+                    obj.refresh();
+                }
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if !c.is_control() && obj.data.input_hints.accepts(c) {
+                    obj.suppress_inline = false;
+                    obj.data.text.push(c);
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.close();
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(AutoCompleteEvent::Blur);
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Single line text field which completes the typed text from a list of completions.
+///
+/// While typing, the completions matching the text (as decided by the [`CompletionMatcher`](trait.CompletionMatcher.html)
+/// given to `AutoCompleteWidget::set_matcher`, fuzzily by default) are listed in a popup below the field, and the best
+/// completion which starts with the text is shown inline after the cursor.
+/// The inline completion is accepted with tab, right, end or enter, and rejected with backspace or escape; completions
+/// in the popup are selected with the arrow keys and accepted with enter, or clicked.
+///
+/// Unlike a [`SearchBox`](struct.SearchBox.html), which looks queries up and submits them, the completions are known
+/// upfront and accepting one replaces the text, emitting `AutoCompleteEvent::Complete`.
+/// The popup is closed as described by the `dismiss_policy`, or with escape. It overhangs the bottom of the field;
+/// for it to appear above other widgets, the field should be drawn after them, and updated before them so that presses
+/// on the popup don't reach them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoComplete {
+    /// The typed text.
+    pub text: String,
+    /// Text shown while the text is empty.
+    pub placeholder: String,
+    /// Texts which the typed text can be completed to.
+    pub completions: Vec<String>,
+    /// Maximum number of completions listed.
+    pub max_completions: usize,
+    /// Hints on the text for the input method, which also restrict the characters which can be typed.
+    pub input_hints: base::InputHints,
+    pub row_height: f32,
+    /// Interactions which close the popup of completions.
+    pub dismiss_policy: base::DismissPolicy,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub focus: Color,
+    /// Color behind the inline completion.
+    pub selection: Color,
+    /// Color of the inline completion.
+    pub over_selection: Color,
+    /// Background of the popup of completions.
+    pub popup_background: Color,
+    /// Color of the selected completion, and of the matched characters of the others.
+    pub highlight: Color,
+    /// Color of the text of the selected completion.
+    pub over_highlight: Color,
+    /// Color of the selected completion while the window is inactive.
+    pub inactive_highlight: Color,
+    /// Color of the text of the selected completion while the window is inactive.
+    pub over_inactive_highlight: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for AutoComplete
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AutoCompleteWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for AutoComplete
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        AutoComplete {
+            text: String::new(),
+            placeholder: String::new(),
+            completions: Vec::new(),
+            max_completions: 8,
+            input_hints: base::InputHints::TEXT.without_autocorrect(),
+            row_height: 24.0,
+            dismiss_policy: Default::default(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            focus: data.scheme.focus,
+            selection: data.scheme.text_selection,
+            over_selection: data.scheme.over_text_selection,
+            popup_background: data.scheme.background,
+            highlight: data.scheme.list_selection,
+            over_highlight: data.scheme.over_list_selection,
+            inactive_highlight: data.scheme.inactive_selection,
+            over_inactive_highlight: data.scheme.over_inactive_selection,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> AutoCompleteWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            AutoCompleteWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.refresh();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("auto_complete", auto_complete_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.auto_complete();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::AutoCompleteState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    inline: None,
+                    popup: None,
+                    completions: Vec::new(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let typed = data.text.clone();
+        let dismiss_listener = u_aux.window_queue().listen();
+        AutoCompleteWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            matcher: Box::new(FuzzyMatcher),
+            typed,
+            matches: Vec::new(),
+            inline: None,
+            suppress_inline: false,
+            open: false,
+            selected: None,
+            hovered: None,
+            dismiss_listener,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::AutoCompleteState> for AutoCompleteWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::AutoCompleteState {
+        let focused = self.interaction.contains(state::InteractionState::FOCUSED);
+        let inline = self.inline.filter(|_| focused).and_then(|index| {
+            let completion = &self.data.completions[index];
+            prefix_len(&self.data.text, completion).map(|len| completion[len..].to_string())
+        });
+
+        state::AutoCompleteState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            inline,
+            popup: self.popup_rect(),
+            completions: if self.open {
+                self.matches
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (index, m))| state::CompletionState {
+                        text: self.data.completions[*index].clone(),
+                        ranges: m.ranges.clone(),
+                        rect: self.completion_rect(i),
+                        selected: self.selected == Some(i),
+                        hovered: self.hovered == Some(i),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct AutoCompleteWidget {
+        widget::MAX,
+
+        <AutoCompleteEvent> EventQueue,
+        <AutoComplete> State,
+        <state::AutoCompleteState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            matcher: Box<dyn CompletionMatcher>,
+            /// Text as of the last refresh; compared against the text to detect typing.
+            typed: String,
+            /// Index of each matching completion with its match, from best to worst.
+            matches: Vec<(usize, FuzzyMatch)>,
+            /// Index of the completion shown inline.
+            inline: Option<usize>,
+            /// Whether the inline completion has been rejected, until the next typed character.
+            suppress_inline: bool,
+            open: bool,
+            selected: Option<usize>,
+            hovered: Option<usize>,
+            dismiss_listener: RcEventListener<base::WindowEvent>,
+        },
+    }
+}
+
+impl<U, G> AutoCompleteWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes how completions are matched against the typed text.
+    pub fn set_matcher(&mut self, matcher: impl CompletionMatcher + 'static) {
+        self.matcher = Box::new(matcher);
+        self.refresh();
+    }
+
+    /// Returns the completions matching the typed text, from best to worst.
+    pub fn matches(&self) -> Vec<&str> {
+        self.matches.iter().map(|(index, _)| self.data.completions[*index].as_str()).collect()
+    }
+
+    /// Returns the completion shown inline, if any.
+    pub fn inline_completion(&self) -> Option<&str> {
+        self.inline.map(|index| self.data.completions[index].as_str())
+    }
+
+    /// Returns `true` if the popup of completions is open.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Replaces the text with a listed completion, closing the popup and emitting `AutoCompleteEvent::Complete`.
+    pub fn choose(&mut self, index: usize) {
+        if let Some(&(completion, _)) = self.matches.get(index) {
+            self.complete(completion);
+        }
+    }
+
+    /// Replaces the text with the inline completion, if any, emitting `AutoCompleteEvent::Complete`.
+    pub fn accept_inline(&mut self) {
+        if let Some(completion) = self.inline {
+            self.complete(completion);
+        }
+    }
+
+    /// Closes the popup of completions.
+    pub fn close(&mut self) {
+        if self.open {
+            self.open = false;
+            self.selected = None;
+            self.hovered = None;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns the rectangle of the popup of completions, if open.
+    pub fn popup_rect(&self) -> Option<AbsoluteRect> {
+        if !self.open {
+            return None;
+        }
+
+        let rect = self.abs_rect();
+        Some(AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x(), rect.max_y() + POPUP_OFFSET),
+            Size::new(
+                rect.size.width,
+                self.matches.len() as f32 * self.data.row_height + POPUP_PADDING * 2.0,
+            )
+            .cast_unit(),
+        ))
+    }
+
+    /// Returns the index of the listed completion at a given point, if the popup is open.
+    pub fn completion_at(&self, point: AbsolutePoint) -> Option<usize> {
+        if !self.open {
+            return None;
+        }
+        (0..self.matches.len()).find(|&i| self.completion_rect(i).contains(point))
+    }
+
+    fn completion_rect(&self, index: usize) -> AbsoluteRect {
+        let popup = self.popup_rect().unwrap_or_default();
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                popup.min_x() + POPUP_PADDING,
+                popup.min_y() + POPUP_PADDING + index as f32 * self.data.row_height,
+            ),
+            Size::new(popup.size.width - POPUP_PADDING * 2.0, self.data.row_height).cast_unit(),
+        )
+    }
+
+    fn complete(&mut self, completion: usize) {
+        let text = self.data.completions[completion].clone();
+        self.close();
+        // accepting a completion isn't typing, so it doesn't reopen the popup
+        self.typed = text.clone();
+        self.suppress_inline = false;
+        self.data.text = text.clone();
+        self.event_queue.emit_owned(AutoCompleteEvent::Complete(text));
+    }
+
+    fn handles_key(&self, key: base::KeyInput) -> bool {
+        match key {
+            base::KeyInput::Back => true,
+            base::KeyInput::Up | base::KeyInput::Down => self.open && !self.matches.is_empty(),
+            base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                self.inline.is_some() || (self.open && self.selected.is_some())
+            }
+            base::KeyInput::Tab | base::KeyInput::Right | base::KeyInput::End => {
+                self.inline.is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+        self.command_group.repaint();
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(AutoCompleteEvent::Focus);
+        }
+    }
+
+    /// Matches the completions against the text, opening the popup if the text has been typed.
+    fn refresh(&mut self) {
+        let typed = self.data.text != self.typed;
+        if typed {
+            self.typed = self.data.text.clone();
+            self.selected = None;
+            self.hovered = None;
+        }
+
+        let text = &self.data.text;
+        self.matches = if text.is_empty() {
+            Vec::new()
+        } else {
+            let matcher = &self.matcher;
+            let mut matches: Vec<_> = self
+                .data
+                .completions
+                .iter()
+                .enumerate()
+                .filter_map(|(i, completion)| matcher.matches(text, completion).map(|m| (i, m)))
+                .collect();
+            // sort_by is stable, preserving the order of otherwise equal completions
+            let completions = &self.data.completions;
+            matches.sort_by(|a, b| {
+                b.1.score.cmp(&a.1.score).then(completions[a.0].len().cmp(&completions[b.0].len()))
+            });
+            matches.truncate(self.data.max_completions);
+            matches
+        };
+
+        // the best completion which the text is a prefix of, and which adds to it
+        self.inline = if self.suppress_inline {
+            None
+        } else {
+            let completions = &self.data.completions;
+            self.matches.iter().map(|&(index, _)| index).find(|&index| {
+                prefix_len(text, &completions[index])
+                    .map_or(false, |len| len < completions[index].len())
+            })
+        };
+
+        if self.selected.map_or(false, |selected| selected >= self.matches.len()) {
+            self.selected = None;
+        }
+
+        let focused = self.interaction.contains(state::InteractionState::FOCUSED);
+        let open = !self.matches.is_empty() && focused && (typed || self.open);
+        if open && !self.open {
+            // Forget the events which preceded opening, so that they don't immediately dismiss the popup.
+            self.dismiss_listener.peek();
+        }
+        self.open = open;
+        if !open {
+            self.selected = None;
+            self.hovered = None;
+        }
+        self.command_group.repaint();
+    }
+}
+
+impl<U, G> Widget for AutoCompleteWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        // the popup is as wide as the field, directly below it
+        let mut rect = self.rect;
+        if let Some(popup) = self.popup_rect() {
+            rect.size.height += POPUP_OFFSET + popup.size.height;
+        }
+        self.painter.paint_hint(rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: self.data.input_hints,
+                area: self.abs_rect(),
+            });
+        }
+
+        let events = self.dismiss_listener.peek();
+        if let Some(popup) = self.popup_rect() {
+            // presses on the field keep the popup open, as well as presses on the popup itself
+            let region = popup.union(&self.abs_rect());
+            if events.iter().any(|event| self.data.dismiss_policy.dismisses(event, region)) {
+                self.close();
+            }
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Circular picture representing a user.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Size of an [`Avatar`](struct.Avatar.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AvatarSize {
+    /// 24 pixels across.
+    Small,
+    /// 32 pixels across.
+    Medium,
+    /// 48 pixels across.
+    Large,
+    /// 64 pixels across.
+    ExtraLarge,
+    /// Custom diameter.
+    Custom(f32),
+}
+
+impl AvatarSize {
+    /// Returns the diameter of the avatar.
+    pub fn diameter(self) -> f32 {
+        match self {
+            AvatarSize::Small => 24.0,
+            AvatarSize::Medium => 32.0,
+            AvatarSize::Large => 48.0,
+            AvatarSize::ExtraLarge => 64.0,
+            AvatarSize::Custom(diameter) => diameter.max(0.0),
+        }
+    }
+}
+
+/// Picture of a user cropped to a circle, falling back to their initials when there's no picture (or it fails to
+/// load), with an optional status dot (e.g. online or away) at the bottom-right.
+///
+/// The picture can be loaded asynchronously through an [`ImageLoader`](../draw/loader/struct.ImageLoader.html), by
+/// setting `handle`; the circle is filled with `placeholder` until the picture resolves.
+///
+/// The avatar is always square, sized by `size`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Avatar {
+    pub image: Option<draw::ImageResource>,
+    /// Picture from an image loader, shown instead of `image`.
+    pub handle: Option<draw::loader::ImageHandle>,
+    /// Name of the user, from which the initials are taken.
+    pub name: String,
+    pub size: AvatarSize,
+    /// Color of the status dot, if shown.
+    pub status: Option<Color>,
+    pub typeface: draw::TypefaceStyle,
+    /// Color of the initials.
+    pub foreground: Color,
+    /// Color behind the initials.
+    pub background: Color,
+    /// Color of the ring around the status dot, separating it from the picture; typically the color behind the avatar.
+    pub border: Color,
+    /// Color of the skeleton shown while the picture is loading.
+    pub placeholder: Color,
+}
+
+impl Avatar {
+    /// Returns the initials shown without a picture; the first letters of the first and last words of the name.
+    pub fn initials(&self) -> String {
+        let mut words = self.name.split_whitespace().filter_map(|word| word.chars().next());
+        let first = words.next();
+        let last = words.last();
+        first.into_iter().chain(last).flat_map(char::to_uppercase).collect()
+    }
+
+    /// Returns the picture which is shown; the picture of `handle` if there is one, otherwise `image`.
+    pub fn shown_image(&self) -> Option<draw::ImageResource> {
+        match self.handle {
+            Some(ref handle) => handle.image(),
+            None => self.image.clone(),
+        }
+    }
+
+    /// Returns `true` if the picture of `handle` is still loading.
+    pub fn is_loading(&self) -> bool {
+        self.handle.as_ref().map_or(false, |handle| handle.is_loading())
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Avatar
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AvatarWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Avatar
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Avatar {
+            image: None,
+            handle: None,
+            name: String::new(),
+            size: AvatarSize::Medium,
+            status: None,
+            typeface: draw::TypefaceStyle {
+                style: draw::TextStyle::Bold,
+                ..data.typography.button.clone()
+            },
+            foreground: data.scheme.over_primary,
+            background: data.scheme.primary,
+            border: data.scheme.background,
+            placeholder: draw::strengthen(data.scheme.background, 0.08, data.contrast),
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> AvatarWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            AvatarWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    let diameter = obj.data.size.diameter();
+                    obj.set_size(Size::new(diameter, diameter));
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let painter = theme.avatar();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::AvatarState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    resource: None,
+                    source: None,
+                    loading: false,
+                })
+                .cast_unit(),
+        );
+
+        AvatarWidgetBuilder { rect, graph: graph.into(), data, painter, loading: false }.build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::AvatarState> for AvatarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::AvatarState {
+        let image = self.data.shown_image();
+        let image = image.as_ref();
+        state::AvatarState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            resource: image.and_then(|image| image.resource()),
+            // pictures which aren't square are cropped to their center
+            source: image.and_then(|image| image.size()).map(|size| ui::fit_crop(size, Some(1.0))),
+            loading: self.data.is_loading(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct AvatarWidget {
+        widget::MAX,
+
+        <Avatar> State,
+        <state::AvatarState> Painter,
+
+        {
+            /// Whether the picture of the handle was loading as of the last update.
+            loading: bool,
+        },
+    }
+}
+
+impl<U, G> Widget for AvatarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        let loading = self.data.is_loading();
+        if loading != self.loading {
+            self.loading = loading;
+            self.command_group.repaint();
+        }
+        // the picture is loaded on another thread, which doesn't wake the app once it's done
+        if loading {
+            aux.request_animation_frame();
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        // The picture is decoded lazily, once the display is available.
+        if let Some(ref image) = self.data.shown_image() {
+            if image.resource().is_none() && !image.is_failed() {
+                image.load(display);
+                self.command_group.repaint();
+            }
+        }
+        if let Some(ref handle) = self.data.handle {
+            handle.unload_evicted(display);
+        }
+
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
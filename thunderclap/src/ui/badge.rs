@@ -0,0 +1,242 @@
+//! Small count or dot overlaid on the corner of another widget.
+
+use {
+    crate::{
+        base::{self, Rectangular, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Point, Rect, Vector},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Corner of the host widget a [`Badge`](struct.Badge.html) is anchored to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BadgeCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Count (e.g. of unread notifications) or dot shown over the corner of a host widget.
+///
+/// The badge isn't a child of its host; it should be a sibling declared after the host (so that it's drawn above it),
+/// and positioned with `BadgeWidget::anchor_to` using the bounds of the host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    /// Count shown in the badge, or `None` to show a dot.
+    pub count: Option<u32>,
+    /// Largest count shown as-is; larger counts are shown as `"{max}+"`.
+    pub max: u32,
+    /// Whether the badge is shown when the count is zero.
+    pub show_zero: bool,
+    pub corner: BadgeCorner,
+    /// Offset of the center of the badge from the corner, toward the inside of the host.
+    pub inset: Vector,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the ring around the badge, separating it from the host; typically the color behind the host.
+    pub border: Color,
+}
+
+impl Badge {
+    /// Returns the text shown in the badge, or `None` if it's a dot.
+    pub fn label(&self) -> Option<String> {
+        let count = self.count?;
+        Some(if count > self.max { format!("{}+", self.max) } else { count.to_string() })
+    }
+
+    /// Returns `true` if the badge is shown; it's hidden when the count is zero unless `show_zero` is set.
+    #[inline]
+    pub fn is_shown(&self) -> bool {
+        self.show_zero || self.count != Some(0)
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Badge
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = BadgeWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Badge
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Badge {
+            count: None,
+            max: 99,
+            show_zero: false,
+            corner: BadgeCorner::TopRight,
+            inset: Vector::new(0.0, 0.0),
+            typeface: draw::TypefaceStyle {
+                size: data.typography.button.size * 0.75,
+                style: draw::TextStyle::Bold,
+                ..data.typography.button.clone()
+            },
+            foreground: data.scheme.over_error,
+            background: data.scheme.error,
+            border: data.scheme.background,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> BadgeWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            BadgeWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.reposition();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let painter = theme.badge();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::BadgeState { rect: Default::default(), data: data.clone() })
+                .cast_unit(),
+        );
+
+        BadgeWidgetBuilder { rect, graph: graph.into(), data, painter, host: None }.build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::BadgeState> for BadgeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::BadgeState {
+        state::BadgeState { rect: self.abs_rect(), data: self.data.clone() }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct BadgeWidget {
+        widget::MAX,
+
+        <Badge> State,
+        <state::BadgeState> Painter,
+
+        {
+            /// Bounds of the host widget, as last given to `anchor_to`.
+            host: Option<Rect>,
+        },
+    }
+}
+
+impl<U, G> BadgeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Anchors the badge to the corner of a host widget, given the bounds of the host (i.e. `host.bounds()`).
+    ///
+    /// The host must share the same parent as the badge. This should be called whenever the host moves or is resized;
+    /// calling it with unchanged bounds does nothing.
+    pub fn anchor_to(&mut self, host: Rect) {
+        if self.host != Some(host) {
+            self.host = Some(host);
+            self.reposition();
+        }
+    }
+
+    /// Returns the bounds of the host, as last given to `anchor_to`.
+    #[inline]
+    pub fn host(&self) -> Option<Rect> {
+        self.host
+    }
+
+    /// Resizes the badge to fit its content, centered on the anchored corner of the host.
+    fn reposition(&mut self) {
+        let size = self.painter.size_hint(self.derive_state());
+        let host = match self.host {
+            Some(host) => host,
+            None => {
+                self.set_size(size);
+                return;
+            }
+        };
+
+        let (left, top) = match self.data.corner {
+            BadgeCorner::TopLeft => (true, true),
+            BadgeCorner::TopRight => (false, true),
+            BadgeCorner::BottomLeft => (true, false),
+            BadgeCorner::BottomRight => (false, false),
+        };
+        let corner = Point::new(
+            if left { host.min_x() + self.data.inset.x } else { host.max_x() - self.data.inset.x },
+            if top { host.min_y() + self.data.inset.y } else { host.max_y() - self.data.inset.y },
+        );
+
+        self.set_rect(RelativeRect::new(
+            (corner - size.to_vector() / 2.0).cast_unit(),
+            size.cast_unit(),
+        ));
+    }
+}
+
+impl<U, G> Widget for BadgeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let shown = self.data.is_shown();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || if shown { painter.draw(state) } else { Vec::new() },
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
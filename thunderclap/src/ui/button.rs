@@ -67,19 +67,38 @@ where
             ui::InteractionEvent::EndHover(pos) => ButtonEvent::EndHover(pos),
             ui::InteractionEvent::Focus => ButtonEvent::Focus,
             ui::InteractionEvent::Blur => ButtonEvent::Blur,
+            ui::InteractionEvent::FocusVisible
+            | ui::InteractionEvent::Deactivate
+            | ui::InteractionEvent::Activate => return,
         });
     }
+
+    #[inline]
+    fn focus_entry(&self) -> Option<&base::FocusEntry> {
+        Some(&self.focus_entry)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Button {
     pub text: DisplayText,
     pub typeface: draw::TypefaceStyle,
+    /// Icon shown alongside the text, if any; the text may be empty for an icon-only button.
+    pub icon: Option<draw::Icon>,
+    pub icon_placement: draw::IconPlacement,
+    /// Width and height of the icon.
+    pub icon_size: f32,
+    /// Space between the icon and the text.
+    pub icon_spacing: f32,
     pub color: Color,
     pub background: Color,
     pub focus: Color,
     pub contrast: draw::ThemeContrast,
     pub disabled: bool,
+    /// Position in the tab order, ahead of widgets without one (see `base::FocusChain`).
+    pub tab_index: Option<u32>,
+    /// Style classes (e.g. `"danger"`), whose theme rules override the colors and padding.
+    pub classes: Vec<String>,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for Button
@@ -100,11 +119,17 @@ where
         Button {
             text: "".to_string().into(),
             typeface: data.typography.button.clone(),
+            icon: None,
+            icon_placement: draw::IconPlacement::Before,
+            icon_size: data.icons.size,
+            icon_spacing: data.icons.spacing,
             color: data.scheme.over_control_outset,
             background: data.scheme.control_outset,
             focus: data.scheme.focus,
             contrast: data.contrast,
             disabled: false,
+            tab_index: None,
+            classes: Vec::new(),
         }
     }
 
@@ -138,6 +163,7 @@ where
                 .size_hint(state::ButtonState {
                     rect: Default::default(),
                     data: data.clone(),
+                    icon: None,
                     interaction: state::InteractionState::empty(),
                 })
                 .cast_unit(),
@@ -151,6 +177,7 @@ where
             painter,
 
             interaction: state::InteractionState::empty(),
+            focus_entry: u_aux.focus_chain().entry(),
         }
         .build()
     }
@@ -165,6 +192,7 @@ where
         state::ButtonState {
             rect: self.abs_rect(),
             data: self.data.clone(),
+            icon: None,
             interaction: self.interaction,
         }
     }
@@ -186,6 +214,7 @@ crate::widget! {
 
         {
             interaction: state::InteractionState,
+            focus_entry: base::FocusEntry,
         },
     }
 }
@@ -205,6 +234,11 @@ where
     }
 
     fn update(&mut self, aux: &mut U) {
+        self.focus_entry.set_tab_index(self.data.tab_index);
+        self.focus_entry.set_group(self.layout.focus_group());
+        self.focus_entry
+            .set_focusable(!self.data.disabled && base::HasVisibility::visibility(self).renders());
+
         let mut graph = self.graph.take().unwrap();
         graph.update_all(self, aux);
         self.graph = Some(graph);
@@ -216,7 +250,9 @@ where
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
-        let button_state = self.derive_state();
+        let mut button_state = self.derive_state();
+        // raster icons are uploaded lazily, once the display is available
+        button_state.icon = self.data.icon.as_ref().and_then(|icon| icon.load(display));
         let painter = &mut self.painter;
         self.command_group.push_with(
             display,
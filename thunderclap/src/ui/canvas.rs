@@ -0,0 +1,278 @@
+//! Widget drawn by a user-supplied closure.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            DisplayClip, DisplayCommand, DisplayListBuilder, GraphicsDisplay, Point, Rect, Vector,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Pointer events forwarded by a canvas, while `Canvas::pointer_events` is set.
+///
+/// Positions are relative to the top-left of the canvas.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum CanvasEvent {
+    /// Emitted when a mouse button is pressed over the canvas.
+    #[event_key(press)]
+    Press(Point, base::MouseButton, base::KeyModifiers),
+    /// Emitted when a mouse button pressed over the canvas is released (which may be outside the canvas).
+    #[event_key(release)]
+    Release(Point, base::MouseButton, base::KeyModifiers),
+    /// Emitted when the mouse is moved over the canvas, or anywhere while a button pressed over the canvas is held.
+    #[event_key(mouse_move)]
+    Move(Point, base::KeyModifiers),
+    /// Emitted when the mouse wheel is scrolled over the canvas, with the scrolled distance.
+    #[event_key(scroll)]
+    Scroll(Point, Vector, base::KeyModifiers),
+}
+
+/// Draws the contents of a canvas.
+pub trait CanvasDraw {
+    /// Draws into `builder`, given the absolute rectangle of the canvas.
+    fn draw(&mut self, rect: AbsoluteRect, builder: &mut DisplayListBuilder);
+}
+
+impl<F: FnMut(AbsoluteRect, &mut DisplayListBuilder)> CanvasDraw for F {
+    #[inline]
+    fn draw(&mut self, rect: AbsoluteRect, builder: &mut DisplayListBuilder) {
+        (self)(rect, builder)
+    }
+}
+
+/// Generates an unbound terminal which forwards pointer events to the event queue of a canvas.
+pub fn canvas_handler<U, G>() -> vg::UnboundQueueHandler<CanvasWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        CanvasWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, button, modifiers)) =
+                event.with(|(pos, _, _)| obj.data.pointer_events && bounds.contains(*pos))
+            {
+                obj.pressed += 1;
+                let pos = obj.local_point(*pos);
+                obj.event_queue.emit_owned(CanvasEvent::Press(pos, *button, *modifiers));
+            }
+        }
+
+        mouse_release => {
+            if let Some((pos, button, modifiers)) = event.with(|_| obj.pressed > 0) {
+                obj.pressed -= 1;
+                let pos = obj.local_point(*pos);
+                obj.event_queue.emit_owned(CanvasEvent::Release(pos, *button, *modifiers));
+            }
+        }
+
+        mouse_move => {
+            // not consumed, so that widgets underneath still see the mouse leave
+            let (pos, modifiers) = *event.get();
+            if obj.data.pointer_events && (obj.pressed > 0 || obj.abs_rect().contains(pos)) {
+                let pos = obj.local_point(pos);
+                obj.event_queue.emit_owned(CanvasEvent::Move(pos, modifiers));
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, delta, modifiers)) =
+                event.with(|(pos, _, _)| obj.data.pointer_events && bounds.contains(*pos))
+            {
+                let pos = obj.local_point(*pos);
+                obj.event_queue.emit_owned(CanvasEvent::Scroll(pos, *delta, *modifiers));
+            }
+        }
+    }
+}
+
+/// Widget whose contents are drawn by a closure (or any [`CanvasDraw`](trait.CanvasDraw.html)); an escape hatch for
+/// bespoke visuals which don't warrant a widget and painter of their own.
+///
+/// The closure given to `CanvasWidget::set_draw` is given the rectangle of the canvas and a display list builder,
+/// and is called whenever the canvas is repainted; when it's moved or resized, when its data changes, or when
+/// `Repaintable::repaint` is called. Otherwise, what was drawn is kept in the command group of the canvas.
+///
+/// Pointer input over the canvas is forwarded as `CanvasEvent`s if `pointer_events` is set, and passes through to
+/// widgets underneath otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    /// Whether drawing is clipped to the rectangle of the canvas.
+    pub clip: bool,
+    /// Whether pointer input over the canvas is consumed and forwarded as `CanvasEvent`s.
+    pub pointer_events: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Canvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CanvasWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Canvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Canvas { clip: true, pointer_events: false }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, u_aux: &mut U) -> CanvasWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CanvasWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if !obj.data.pointer_events {
+                        obj.pressed = 0;
+                    }
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("canvas", canvas_handler::<U, G>().bind(u_aux.window_queue()));
+
+        CanvasWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            drawer: None,
+            pressed: 0,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for CanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CanvasWidget {
+        widget::MAX,
+
+        <CanvasEvent> EventQueue,
+        <Canvas> State,
+
+        {
+            drawer: Option<Box<dyn CanvasDraw>>,
+            /// Number of mouse buttons pressed over the canvas and yet to be released.
+            pressed: usize,
+        },
+    }
+}
+
+impl<U, G> CanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes how the canvas is drawn, repainting it.
+    pub fn set_draw(&mut self, drawer: impl CanvasDraw + 'static) {
+        self.drawer = Some(Box::new(drawer));
+        self.command_group.repaint();
+    }
+
+    /// Removes the drawing closure, leaving the canvas empty.
+    pub fn clear_draw(&mut self) {
+        self.drawer = None;
+        self.command_group.repaint();
+    }
+
+    fn local_point(&self, pos: AbsolutePoint) -> Point {
+        (pos - self.abs_rect().origin).to_point().cast_unit()
+    }
+}
+
+impl<U, G> Widget for CanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let rect = self.abs_rect();
+        let clip = self.data.clip;
+        let drawer = &mut self.drawer;
+        self.command_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                if let Some(drawer) = drawer {
+                    drawer.draw(rect, &mut builder);
+                }
+                let commands = builder.build();
+
+                if clip {
+                    let mut clipped = Vec::with_capacity(commands.len() + 3);
+                    clipped.push(DisplayCommand::Save);
+                    clipped.push(DisplayCommand::Clip(DisplayClip::Rectangle {
+                        rect: rect.cast_unit(),
+                        antialias: true,
+                    }));
+                    clipped.extend(commands);
+                    clipped.push(DisplayCommand::Restore);
+                    clipped
+                } else {
+                    commands
+                }
+            },
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! Raised container with optional header and footer.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Section of a card which a child is placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardSlot {
+    Header,
+    Body,
+    Footer,
+}
+
+impl Default for CardSlot {
+    fn default() -> Self {
+        CardSlot::Body
+    }
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    slot: CardSlot,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Surface raised above the background by a drop shadow, grouping related content.
+///
+/// Content is added by pushing widgets into the card as a layout, along with the slot they belong in (the body by
+/// default). The header, body and footer are stacked vertically and padded, each preserving the arrangement of its
+/// children relative to each other, and the card resizes itself to fit them so that parent layouts reflow.
+/// The header and footer are only shown if they have children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    /// Space between the edges of each section and its content.
+    pub padding: f32,
+    /// Height above the background, which determines the size of the shadow; zero for no shadow.
+    pub elevation: f32,
+    pub corner_radius: f32,
+    pub background: Color,
+    /// Color of the border and of the lines separating the header and footer from the body.
+    pub border: Color,
+    pub shadow: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Card
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CardWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Card
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Card {
+            padding: 12.0,
+            elevation: 2.0,
+            corner_radius: 3.5,
+            background: data.scheme.background,
+            border: draw::strengthen(data.scheme.background, 0.2, data.contrast),
+            shadow: Color::new(0.0, 0.0, 0.0, 0.3),
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> CardWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            CardWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let painter = theme.card();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::CardState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    header: None,
+                    footer: None,
+                })
+                .cast_unit(),
+        );
+
+        CardWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            header: None,
+            footer: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::CardState> for CardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::CardState {
+        state::CardState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            header: self.header,
+            footer: self.footer,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CardWidget {
+        widget::MAX,
+
+        <Card> State,
+        <state::CardState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            /// Rectangle of the header, if shown.
+            header: Option<AbsoluteRect>,
+            /// Rectangle of the footer, if shown.
+            footer: Option<AbsoluteRect>,
+        },
+    }
+}
+
+impl<U, G> CardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the rectangle of the header, in absolute coordinates, if shown.
+    #[inline]
+    pub fn header_rect(&self) -> Option<AbsoluteRect> {
+        self.header
+    }
+
+    /// Returns the rectangle of the footer, in absolute coordinates, if shown.
+    #[inline]
+    pub fn footer_rect(&self) -> Option<AbsoluteRect> {
+        self.footer
+    }
+
+    /// Returns the union of the children in a slot, if it has any.
+    fn slot_content(&self, slot: CardSlot) -> Option<AbsoluteRect> {
+        self.rects.values().filter(|data| data.slot == slot).fold(None, |content, data| {
+            Some(content.map_or(data.rect, |content: AbsoluteRect| content.union(&data.rect)))
+        })
+    }
+
+    /// Stacks the sections vertically, moving the children of each section into place, and fits the card to them.
+    fn update_layout(&mut self) {
+        let padding = self.data.padding;
+        let origin = self.abs_rect().origin;
+
+        let header = self.slot_content(CardSlot::Header);
+        let body = self.slot_content(CardSlot::Body);
+        let footer = self.slot_content(CardSlot::Footer);
+
+        let width = [header, body, footer]
+            .iter()
+            .flatten()
+            .fold(0.0f32, |width, content| width.max(content.size.width))
+            + padding * 2.0;
+
+        let mut y = origin.y;
+        let mut sections = Vec::with_capacity(3);
+        for &(slot, content) in
+            &[(CardSlot::Header, header), (CardSlot::Body, body), (CardSlot::Footer, footer)]
+        {
+            // the body is always shown, even when empty
+            let content = match content {
+                Some(content) => content,
+                None if slot == CardSlot::Body => {
+                    AbsoluteRect::new(AbsolutePoint::new(origin.x, y), Default::default())
+                }
+                None => continue,
+            };
+
+            let offset = AbsolutePoint::new(origin.x + padding, y + padding) - content.origin;
+            if offset != Default::default() {
+                for (_, data) in &mut self.rects {
+                    if data.slot == slot {
+                        data.rect = data.rect.translate(offset);
+                        data.evq.emit_owned(data.rect);
+                    }
+                }
+            }
+
+            let height = content.size.height + padding * 2.0;
+            sections.push((
+                slot,
+                AbsoluteRect::new(
+                    AbsolutePoint::new(origin.x, y),
+                    Size::new(width, height).cast_unit(),
+                ),
+            ));
+            y += height;
+        }
+
+        let section = |slot| sections.iter().find(|(s, _)| *s == slot).map(|(_, rect)| *rect);
+        self.header = section(CardSlot::Header);
+        self.footer = section(CardSlot::Footer);
+
+        let size = Size::new(width, y - origin.y);
+        if size != self.size() {
+            // Notifies the parent layout through `on_transform`.
+            self.set_size(size);
+        }
+    }
+}
+
+impl<U, G> base::Layout for CardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = CardSlot;
+
+    fn push(&mut self, data: Option<CardSlot>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                slot: data.unwrap_or_default(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for CardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        // the extent of the shadow depends on the elevation, which the painter can't see
+        let extent = draw::shadow_extent(self.data.elevation);
+        self.painter.paint_hint(self.rect).inflate(extent, extent).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
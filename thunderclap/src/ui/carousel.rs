@@ -0,0 +1,671 @@
+//! Horizontally paged content, with swiping, arrows and a page indicator.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Width of the gutters on either side of the pages which hold the arrows.
+const ARROW_GUTTER: f32 = 32.0;
+/// Height of the strip below the pages which holds the page indicator.
+const INDICATOR_HEIGHT: f32 = 20.0;
+/// Diameter of a page indicator dot, and the space between dots.
+const DOT_SIZE: f32 = 8.0;
+/// Distance the cursor has to move after being pressed for it to start swiping.
+const SWIPE_THRESHOLD: f32 = 4.0;
+/// Fraction of a page which a swipe has to cover to change pages.
+const SWIPE_FRACTION: f32 = 0.2;
+
+/// Events emitted by a carousel.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum CarouselEvent {
+    /// Emitted when the current page is changed by the user or by auto-advancing, with the new page.
+    #[event_key(page_changed)]
+    PageChanged(usize),
+}
+
+/// Interactive part of a carousel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CarouselPart {
+    Previous,
+    Next,
+    /// Dot of the page indicator for a page.
+    Dot(usize),
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    clip: base::LayoutClip,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+    page: usize,
+}
+
+struct Swipe {
+    origin: AbsolutePoint,
+    /// Page position when the swipe started.
+    offset: f32,
+    moved: bool,
+}
+
+/// Generates an unbound terminal which handles swiping, and pressing and hovering the arrows and dots.
+pub fn carousel_handler<U, G>(
+) -> vg::UnboundQueueHandler<CarouselWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        CarouselWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && obj.part_at(*pos).is_some()
+            }) {
+                match obj.part_at(*pos) {
+                    Some(CarouselPart::Previous) => obj.previous(),
+                    Some(CarouselPart::Next) => obj.next(),
+                    Some(CarouselPart::Dot(page)) => obj.go_to(page),
+                    None => {}
+                }
+            } else {
+                // the press is left to the pages, which only stop receiving events once it becomes a swipe
+                let (pos, button, _) = *event.get();
+                if !obj.data.disabled
+                    && button == base::MouseButton::Left
+                    && obj.content.contains(pos)
+                {
+                    obj.swipe = Some(Swipe { origin: pos, offset: obj.shown, moved: false });
+                }
+            }
+        }
+
+        mouse_release => {
+            if let Some(swipe) = obj.swipe.take() {
+                if swipe.moved {
+                    obj.end_swipe(swipe.offset);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            if let Some(ref mut swipe) = obj.swipe {
+                let distance = swipe.origin.x - pos.x;
+                swipe.moved |= distance.abs() > SWIPE_THRESHOLD;
+                if swipe.moved {
+                    let width = obj.content.size.width.max(1.0);
+                    let last = obj.page_count().saturating_sub(1) as f32;
+                    obj.shown = (swipe.offset + distance / width).max(0.0).min(last);
+                    obj.animation = (obj.shown, Instant::now());
+                    obj.dirty = true;
+                }
+            }
+
+            let hovered = obj.part_at(pos);
+            obj.hovering = obj.abs_rect().contains(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Pages shown one at a time, sliding horizontally between them.
+///
+/// Pages are added by pushing widgets into the carousel as a layout, along with the (zero-based) page they belong to;
+/// a page can have several children, which are placed in the content area preserving their arrangement relative to
+/// each other. Children are clipped to the content area while they slide in and out of view.
+///
+/// The user changes pages by swiping (dragging the pages), by the arrows on either side, or by the dots of the page
+/// indicator below. The carousel can also advance by itself every `auto_advance`, pausing while the cursor is over it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Carousel {
+    /// Current page, from zero.
+    pub page: usize,
+    /// Whether going forwards from the last page wraps around to the first, and vice versa.
+    pub wrap: bool,
+    /// Interval at which the carousel advances to the next page by itself (wrapping around after the last),
+    /// or `None` to only change pages by the user.
+    pub auto_advance: Option<Duration>,
+    /// Duration of the transition between pages.
+    pub transition: Duration,
+    /// Whether the previous and next arrows are shown.
+    pub arrows: bool,
+    /// Whether the page indicator is shown.
+    pub indicator: bool,
+    /// Color of the arrows and of the dot of the current page.
+    pub foreground: Color,
+    /// Color of the dots of the other pages.
+    pub dot: Color,
+    /// Color behind hovered arrows and dots.
+    pub hover: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Carousel
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CarouselWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Carousel
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Carousel {
+            page: 0,
+            wrap: false,
+            auto_advance: None,
+            transition: Duration::from_millis(300),
+            arrows: true,
+            indicator: true,
+            foreground: data.scheme.over_background,
+            dot: draw::strengthen(data.scheme.background, 0.25, data.contrast),
+            hover: draw::strengthen(data.scheme.background, 0.08, data.contrast),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> CarouselWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CarouselWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.retarget();
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("carousel", carousel_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.carousel();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::CarouselState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    previous: None,
+                    next: None,
+                    dots: Vec::new(),
+                    pages: 0,
+                    offset: 0.0,
+                    hovered: None,
+                })
+                .cast_unit(),
+        );
+
+        let page = data.page as f32;
+        CarouselWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            content: Default::default(),
+            target: page,
+            animation: (page, Instant::now()),
+            shown: page,
+            advanced: Instant::now(),
+            swipe: None,
+            hovered: None,
+            hovering: false,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::CarouselState> for CarouselWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::CarouselState {
+        let (previous, next) = if self.data.arrows {
+            (Some(self.arrow_rect(false)), Some(self.arrow_rect(true)))
+        } else {
+            (None, None)
+        };
+        let dots = if self.data.indicator {
+            (0..self.page_count()).map(|page| self.dot_rect(page)).collect()
+        } else {
+            Vec::new()
+        };
+
+        state::CarouselState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            previous,
+            next,
+            dots,
+            pages: self.page_count(),
+            offset: self.shown,
+            hovered: self.hovered,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CarouselWidget {
+        widget::MAX,
+
+        <CarouselEvent> EventQueue,
+        <Carousel> State,
+        <state::CarouselState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            /// Rectangle in which the current page is placed.
+            content: AbsoluteRect,
+            /// Page position the pages are sliding toward.
+            target: f32,
+            /// Page position the pages started sliding from, and when.
+            animation: (f32, Instant),
+            /// Page position currently shown; fractional while sliding between pages.
+            shown: f32,
+            /// When the page was last changed, from which the next auto-advance is timed.
+            advanced: Instant,
+            swipe: Option<Swipe>,
+            hovered: Option<CarouselPart>,
+            /// Whether the cursor is over the carousel, which pauses auto-advancing.
+            hovering: bool,
+        },
+    }
+}
+
+impl<U, G> CarouselWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the number of pages; one more than the highest page of any child.
+    pub fn page_count(&self) -> usize {
+        self.rects.values().map(|data| data.page).max().map_or(0, |max| max + 1)
+    }
+
+    /// Goes to a page, sliding to it and emitting `CarouselEvent::PageChanged`.
+    pub fn go_to(&mut self, page: usize) {
+        let page = page.min(self.page_count().saturating_sub(1));
+        self.advanced = Instant::now();
+        if page != self.data.page {
+            self.data.page = page;
+            self.event_queue.emit_owned(CarouselEvent::PageChanged(page));
+        }
+    }
+
+    /// Goes to the previous page, wrapping around to the last if `wrap` is enabled.
+    pub fn previous(&mut self) {
+        if self.data.page > 0 {
+            let page = self.data.page - 1;
+            self.go_to(page);
+        } else if self.data.wrap {
+            let page = self.page_count().saturating_sub(1);
+            self.go_to(page);
+        }
+    }
+
+    /// Goes to the next page, wrapping around to the first if `wrap` is enabled.
+    pub fn next(&mut self) {
+        if self.data.page + 1 < self.page_count() {
+            let page = self.data.page + 1;
+            self.go_to(page);
+        } else if self.data.wrap {
+            self.go_to(0);
+        }
+    }
+
+    /// Returns the rectangle in which the current page is placed, in absolute coordinates.
+    #[inline]
+    pub fn content_rect(&self) -> AbsoluteRect {
+        self.content
+    }
+
+    /// Returns the page position currently shown, which is fractional while sliding between pages.
+    #[inline]
+    pub fn shown_offset(&self) -> f32 {
+        self.shown
+    }
+
+    /// Returns the part of the carousel containing a given point, if any.
+    pub fn part_at(&self, point: AbsolutePoint) -> Option<CarouselPart> {
+        if self.data.arrows {
+            if self.arrow_rect(false).contains(point) {
+                return Some(CarouselPart::Previous);
+            } else if self.arrow_rect(true).contains(point) {
+                return Some(CarouselPart::Next);
+            }
+        }
+        if self.data.indicator {
+            // the dots are small, so the whole height of the indicator is accepted
+            return (0..self.page_count())
+                .find(|&page| {
+                    self.dot_rect(page)
+                        .inflate(DOT_SIZE / 2.0, (INDICATOR_HEIGHT - DOT_SIZE) / 2.0)
+                        .contains(point)
+                })
+                .map(CarouselPart::Dot);
+        }
+        None
+    }
+
+    /// Returns the rectangle of the previous (or next) arrow, centered in its gutter.
+    fn arrow_rect(&self, next: bool) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        let size = ARROW_GUTTER - 4.0;
+        let x = if next { bounds.max_x() - ARROW_GUTTER + 2.0 } else { bounds.min_x() + 2.0 };
+        AbsoluteRect::new(
+            AbsolutePoint::new(x, self.content.center().y - size / 2.0),
+            Size::new(size, size).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the dot of a page, with the dots centered below the content.
+    fn dot_rect(&self, page: usize) -> AbsoluteRect {
+        let count = self.page_count() as f32;
+        let width = count * DOT_SIZE * 2.0 - DOT_SIZE;
+        let left = self.content.center().x - width / 2.0;
+        let top = self.content.max_y() + (INDICATOR_HEIGHT - DOT_SIZE) / 2.0;
+        AbsoluteRect::new(
+            AbsolutePoint::new(left + page as f32 * DOT_SIZE * 2.0, top),
+            Size::new(DOT_SIZE, DOT_SIZE).cast_unit(),
+        )
+    }
+
+    /// Settles a swipe which started at page position `offset` on the nearest page, or on the next page in the
+    /// direction of the swipe if it covered enough of a page.
+    fn end_swipe(&mut self, offset: f32) {
+        let start = offset.round();
+        let page = if self.shown - start > SWIPE_FRACTION {
+            start + 1.0
+        } else if start - self.shown > SWIPE_FRACTION {
+            start - 1.0
+        } else {
+            start
+        };
+        self.go_to(page.max(0.0) as usize);
+        // the pages slide onward from where they were released, even if the page didn't change
+        self.retarget();
+    }
+
+    /// Starts sliding the pages toward the current page.
+    fn retarget(&mut self) {
+        let target = self.data.page.min(self.page_count().saturating_sub(1)) as f32;
+        if (target - self.target).abs() > std::f32::EPSILON
+            || (target - self.shown).abs() > std::f32::EPSILON
+        {
+            self.target = target;
+            self.animation = (self.shown, Instant::now());
+            self.command_group.repaint();
+        }
+    }
+
+    /// Advances the pages toward the current page.
+    fn animate(&mut self) {
+        if self.swipe.as_ref().map_or(false, |swipe| swipe.moved)
+            || (self.shown - self.target).abs() <= std::f32::EPSILON
+        {
+            return;
+        }
+
+        let (from, start) = self.animation;
+        let duration = self.data.transition.as_secs_f32().max(std::f32::EPSILON);
+        let progress = ((Instant::now() - start).as_secs_f32() / duration).min(1.0);
+        // Ease out (cubic)
+        let eased = 1.0 - (1.0 - progress).powi(3);
+
+        self.shown = if progress < 1.0 { from + (self.target - from) * eased } else { self.target };
+        self.dirty = true;
+    }
+
+    /// Advances to the next page once `auto_advance` has elapsed since the page last changed.
+    fn auto_advance(&mut self) {
+        let interval = match self.data.auto_advance {
+            Some(interval) if !self.data.disabled => interval,
+            _ => return,
+        };
+        if self.hovering || self.swipe.is_some() {
+            self.advanced = Instant::now();
+            return;
+        }
+
+        if self.advanced.elapsed() >= interval && self.page_count() > 1 {
+            let page = (self.data.page + 1) % self.page_count();
+            self.go_to(page);
+        }
+    }
+
+    /// Places the pages side by side, offset by the page position shown, and clips them to the content area.
+    fn update_layout(&mut self) {
+        let bounds = self.abs_rect();
+        let gutter = if self.data.arrows { ARROW_GUTTER } else { 0.0 };
+        let indicator = if self.data.indicator { INDICATOR_HEIGHT } else { 0.0 };
+        self.content = AbsoluteRect::new(
+            AbsolutePoint::new(bounds.min_x() + gutter, bounds.min_y()),
+            Size::new(
+                (bounds.size.width - gutter * 2.0).max(0.0),
+                (bounds.size.height - indicator).max(0.0),
+            )
+            .cast_unit(),
+        );
+
+        let width = self.content.size.width;
+        let settled = (self.shown - self.target).abs() <= std::f32::EPSILON
+            && !self.swipe.as_ref().map_or(false, |swipe| swipe.moved);
+        for page in 0..self.page_count() {
+            let mut origin: Option<AbsolutePoint> = None;
+            for data in self.rects.values().filter(|data| data.page == page) {
+                origin = Some(origin.map_or(data.rect.origin, |origin| {
+                    AbsolutePoint::new(
+                        origin.x.min(data.rect.origin.x),
+                        origin.y.min(data.rect.origin.y),
+                    )
+                }));
+            }
+            let origin = match origin {
+                Some(origin) => origin,
+                None => continue,
+            };
+
+            let position = AbsolutePoint::new(
+                self.content.origin.x + (page as f32 - self.shown) * width,
+                self.content.origin.y,
+            );
+            let offset = position - origin;
+            // only the current page is interactive, and only once it has settled
+            let visibility = if (page as f32 - self.shown).abs() >= 1.0 {
+                base::Visibility::None
+            } else if settled && page == self.data.page {
+                base::Visibility::Normal
+            } else {
+                base::Visibility::Static
+            };
+
+            for data in self.rects.values_mut().filter(|data| data.page == page) {
+                if offset != Default::default() {
+                    data.rect = data.rect.translate(offset);
+                    data.evq.emit_owned(data.rect);
+                }
+                data.visibility.set(visibility);
+                data.clip.set(Some(self.content));
+            }
+        }
+    }
+}
+
+impl<U, G> base::Layout for CarouselWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Page which the child belongs to; `None` for a new page after the last.
+    type PushData = usize;
+
+    fn push(&mut self, data: Option<usize>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let page = data.unwrap_or_else(|| self.page_count());
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+        let clip = base::LayoutClip::default();
+        // hidden until it's placed
+        visibility.set(base::Visibility::None);
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: clip.clone(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                clip,
+                rect,
+                original_rect: rect,
+                id,
+                page,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for CarouselWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        self.auto_advance();
+        self.animate();
+        if (self.shown - self.target).abs() > std::f32::EPSILON {
+            aux.request_animation_frame();
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,607 @@
+//! Line and bar charts with automatically scaled axes.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Space between the tick labels and the plot area.
+const LABEL_SPACING: f32 = 6.0;
+/// Portion of the space between adjacent points taken by bars.
+const BAR_FILL: f32 = 0.8;
+
+/// Events emitted by a chart.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartEvent {
+    /// Emitted when the cursor moves onto a point, with the index of the series and the index of the point.
+    #[event_key(hover)]
+    Hover(usize, usize),
+    /// Emitted when the cursor moves off of the hovered point.
+    #[event_key(unhover)]
+    Unhover,
+}
+
+/// How a series is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeriesKind {
+    Line,
+    /// Bars from zero to each point; bar series are grouped side by side.
+    Bar,
+}
+
+/// Named series of points shown in a chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartSeries {
+    pub name: String,
+    pub kind: SeriesKind,
+    /// Color of the series, or `None` to use the chart's palette.
+    pub color: Option<Color>,
+    /// Points as `(x, y)`, in increasing order of `x`.
+    pub points: Vec<(f32, f32)>,
+}
+
+/// Source of the series shown in a chart.
+///
+/// The chart reads the series when the provider is set (see `ChartWidget::set_provider`), and again whenever `changed`
+/// returns `true`, which is checked on every update.
+pub trait ChartProvider {
+    /// Returns the series to show.
+    fn series(&self) -> Vec<ChartSeries>;
+    /// Returns `true` if the series have changed since the last invocation.
+    fn changed(&mut self) -> bool {
+        false
+    }
+}
+
+impl ChartProvider for Vec<ChartSeries> {
+    fn series(&self) -> Vec<ChartSeries> {
+        self.clone()
+    }
+}
+
+/// Computed axes of a chart, mapping values to the plot area.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartAxes {
+    /// Area within which the series are plotted.
+    pub plot: AbsoluteRect,
+    /// Range of the horizontal axis, from left to right.
+    pub x: (f32, f32),
+    /// Range of the vertical axis, from bottom to top.
+    pub y: (f32, f32),
+    /// Values at which the horizontal axis is labelled, with their labels.
+    pub x_ticks: Vec<(f32, String)>,
+    /// Values at which the vertical axis is labelled, with their labels.
+    pub y_ticks: Vec<(f32, String)>,
+    /// Width of each bar, in pixels.
+    pub bar_width: f32,
+}
+
+impl ChartAxes {
+    /// Returns the horizontal position of a value along the horizontal axis.
+    pub fn x_position(&self, x: f32) -> f32 {
+        let range = (self.x.1 - self.x.0).max(std::f32::EPSILON);
+        self.plot.min_x() + (x - self.x.0) / range * self.plot.size.width
+    }
+
+    /// Returns the vertical position of a value along the vertical axis.
+    pub fn y_position(&self, y: f32) -> f32 {
+        let range = (self.y.1 - self.y.0).max(std::f32::EPSILON);
+        self.plot.max_y() - (y - self.y.0) / range * self.plot.size.height
+    }
+
+    /// Returns the position of a point within the plot area.
+    #[inline]
+    pub fn point(&self, x: f32, y: f32) -> AbsolutePoint {
+        AbsolutePoint::new(self.x_position(x), self.y_position(y))
+    }
+
+    /// Returns the bar of the point of a series, given the index of the series among the bar series and the number
+    /// of bar series.
+    pub fn bar(&self, (x, y): (f32, f32), slot: usize, slots: usize) -> AbsoluteRect {
+        let group = self.bar_width * slots as f32;
+        let left = self.x_position(x) - group / 2.0 + self.bar_width * slot as f32;
+        let (zero, top) = (self.y_position(0.0f32.max(self.y.0).min(self.y.1)), self.y_position(y));
+        AbsoluteRect::new(
+            AbsolutePoint::new(left, zero.min(top)),
+            Size::new(self.bar_width, (zero - top).abs()).cast_unit(),
+        )
+    }
+}
+
+/// Returns ticks at round values (multiples of 1, 2 or 5 times a power of ten) spanning `min` to `max`, with about
+/// `count` ticks. The first and last ticks enclose the range.
+pub fn nice_ticks(min: f32, max: f32, count: usize) -> Vec<f32> {
+    let (min, max) = if (max - min).abs() > std::f32::EPSILON {
+        (min.min(max), min.max(max))
+    } else {
+        // a flat range is widened so that it's still visible
+        (min - 1.0, max + 1.0)
+    };
+
+    let rough = (max - min) / (count.max(2) - 1) as f32;
+    let magnitude = 10.0f32.powf(rough.log10().floor());
+    let step = match rough / magnitude {
+        r if r <= 1.0 => 1.0,
+        r if r <= 2.0 => 2.0,
+        r if r <= 5.0 => 5.0,
+        _ => 10.0,
+    } * magnitude;
+
+    let first = (min / step).floor() as i64;
+    let last = (max / step).ceil() as i64;
+    (first..=last).map(|i| i as f32 * step).collect()
+}
+
+/// Generates an unbound terminal which tracks the hovered point.
+pub fn chart_handler<U, G>() -> vg::UnboundQueueHandler<ChartWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ChartWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.point_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.event_queue.emit_owned(match hovered {
+                    Some((series, point)) => ChartEvent::Hover(series, point),
+                    None => ChartEvent::Unhover,
+                });
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Chart plotting line and bar series on automatically scaled axes, with gridlines and a tooltip showing the
+/// hovered point.
+///
+/// Series are read from a `ChartProvider` (see `ChartWidget::set_provider`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    /// Range of the horizontal axis, or `None` to fit the series.
+    pub x_range: Option<(f32, f32)>,
+    /// Range of the vertical axis, or `None` to fit the series.
+    pub y_range: Option<(f32, f32)>,
+    /// Approximate number of ticks along each axis.
+    pub ticks: usize,
+    /// Whether gridlines are drawn at the ticks.
+    pub grid: bool,
+    /// Distance within which the cursor hovers the nearest point of a line series.
+    pub hover_radius: f32,
+    /// Number of decimal places of the values shown in the tooltip.
+    pub precision: usize,
+    pub locale: i18n::Locale,
+    /// Colors of series which don't specify their own, in turn.
+    pub palette: Vec<Color>,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub grid_color: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl Chart {
+    /// Returns the color of a series.
+    pub fn series_color(&self, index: usize, series: &ChartSeries) -> Color {
+        series.color.unwrap_or_else(|| {
+            if self.palette.is_empty() {
+                self.foreground
+            } else {
+                self.palette[index % self.palette.len()]
+            }
+        })
+    }
+
+    /// Formats a value, as shown on the axes and in the tooltip.
+    pub fn format_value(&self, value: f32, precision: usize) -> String {
+        self.locale.format_decimal(value as f64, precision)
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Chart
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ChartWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Chart
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Chart {
+            x_range: None,
+            y_range: None,
+            ticks: 6,
+            grid: true,
+            hover_radius: 12.0,
+            precision: 2,
+            locale: i18n::locale(),
+            palette: vec![
+                data.scheme.primary,
+                Color::new(0.96, 0.52, 0.14, 1.0),
+                Color::new(0.16, 0.65, 0.27, 1.0),
+                Color::new(0.44, 0.26, 0.76, 1.0),
+                data.scheme.error,
+            ],
+            typeface: draw::TypefaceStyle {
+                size: data.typography.body.size * 0.85,
+                ..data.typography.body.clone()
+            },
+            foreground: data.scheme.over_background,
+            background: data.scheme.background,
+            grid_color: draw::strengthen(data.scheme.background, 0.08, data.contrast),
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> ChartWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ChartWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("chart", chart_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.chart();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::ChartState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    series: Vec::new(),
+                    axes: Default::default(),
+                    hovered: None,
+                })
+                .cast_unit(),
+        );
+
+        ChartWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            provider: None,
+            series: Vec::new(),
+            axes: Default::default(),
+            hovered: None,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::ChartState> for ChartWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::ChartState {
+        state::ChartState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            series: self.series.clone(),
+            axes: self.axes.clone(),
+            hovered: self.hovered,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ChartWidget {
+        widget::MAX,
+
+        <ChartEvent> EventQueue,
+        <Chart> State,
+        <state::ChartState> Painter,
+
+        {
+            provider: Option<Box<dyn ChartProvider>>,
+            series: Vec<ChartSeries>,
+            axes: ChartAxes,
+            /// Index of the series and of the point under the cursor.
+            hovered: Option<(usize, usize)>,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> ChartWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the source of the series, and reads the series from it.
+    pub fn set_provider(&mut self, provider: impl ChartProvider + 'static) {
+        self.series = provider.series();
+        self.provider = Some(Box::new(provider));
+        self.hovered = None;
+        self.dirty = true;
+    }
+
+    /// Returns the series currently shown.
+    #[inline]
+    pub fn series(&self) -> &[ChartSeries] {
+        &self.series
+    }
+
+    /// Returns the computed axes.
+    #[inline]
+    pub fn axes(&self) -> &ChartAxes {
+        &self.axes
+    }
+
+    /// Returns the index of the series and of the point at a position, if any.
+    ///
+    /// Bars are hit anywhere within them, whereas the nearest point of a line within `hover_radius` is hit.
+    pub fn point_at(&self, position: AbsolutePoint) -> Option<(usize, usize)> {
+        if !self
+            .axes
+            .plot
+            .inflate(self.data.hover_radius, self.data.hover_radius)
+            .contains(position)
+        {
+            return None;
+        }
+
+        let bar_series = self.bar_series();
+        let mut nearest: Option<((usize, usize), f32)> = None;
+        for (i, series) in self.series.iter().enumerate() {
+            for (j, &point) in series.points.iter().enumerate() {
+                let distance = match series.kind {
+                    SeriesKind::Bar => {
+                        let slot = bar_series.iter().position(|&k| k == i).unwrap_or(0);
+                        if self.axes.bar(point, slot, bar_series.len()).contains(position) {
+                            0.0
+                        } else {
+                            continue;
+                        }
+                    }
+                    SeriesKind::Line => {
+                        let distance = (self.axes.point(point.0, point.1) - position).length();
+                        if distance > self.data.hover_radius {
+                            continue;
+                        }
+                        distance
+                    }
+                };
+                if nearest.map_or(true, |(_, nearest)| distance < nearest) {
+                    nearest = Some(((i, j), distance));
+                }
+            }
+        }
+
+        nearest.map(|(hit, _)| hit)
+    }
+
+    /// Returns the indices of the bar series.
+    fn bar_series(&self) -> Vec<usize> {
+        self.series
+            .iter()
+            .enumerate()
+            .filter(|(_, series)| series.kind == SeriesKind::Bar)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+        }
+        .bounds()
+        .map(|bounds| bounds.size.width)
+        .unwrap_or(0.0)
+    }
+
+    /// Scales the axes to fit the series (unless the ranges are fixed), and lays out the plot area around the tick
+    /// labels.
+    fn update_axes(&mut self) {
+        let points = self.series.iter().flat_map(|series| series.points.iter());
+        let (mut x_min, mut x_max, mut y_min, mut y_max) = points.fold(
+            (
+                std::f32::INFINITY,
+                std::f32::NEG_INFINITY,
+                std::f32::INFINITY,
+                std::f32::NEG_INFINITY,
+            ),
+            |(x_min, x_max, y_min, y_max), &(x, y)| {
+                (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
+            },
+        );
+        if !x_min.is_finite() || !y_min.is_finite() {
+            x_min = 0.0;
+            x_max = 1.0;
+            y_min = 0.0;
+            y_max = 1.0;
+        }
+
+        // Bars need space on either side of the outermost points, and rise from zero.
+        let bar_series = self.bar_series();
+        let mut slot = 1.0f32;
+        if !bar_series.is_empty() {
+            let mut xs: Vec<f32> = bar_series
+                .iter()
+                .flat_map(|&i| self.series[i].points.iter().map(|point| point.0))
+                .collect();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            slot = xs
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .filter(|gap| *gap > std::f32::EPSILON)
+                .fold(std::f32::INFINITY, f32::min);
+            if !slot.is_finite() {
+                slot = 1.0;
+            }
+            x_min -= slot / 2.0;
+            x_max += slot / 2.0;
+            y_min = y_min.min(0.0);
+            y_max = y_max.max(0.0);
+        }
+
+        let ticks = self.data.ticks;
+        let (x, x_ticks) = match self.data.x_range {
+            Some(range) => (range, nice_ticks(range.0, range.1, ticks)),
+            None => {
+                let ticks = nice_ticks(x_min, x_max, ticks);
+                // bars keep their tight range, so that the outermost bars aren't pushed inwards
+                let range = if bar_series.is_empty() {
+                    (ticks[0], ticks[ticks.len() - 1])
+                } else {
+                    (x_min, x_max)
+                };
+                (range, ticks)
+            }
+        };
+        let (y, y_ticks) = match self.data.y_range {
+            Some(range) => (range, nice_ticks(range.0, range.1, ticks)),
+            None => {
+                let ticks = nice_ticks(y_min, y_max, ticks);
+                ((ticks[0], ticks[ticks.len() - 1]), ticks)
+            }
+        };
+
+        let in_range = |value: &f32, (min, max): (f32, f32)| {
+            *value >= min.min(max) - std::f32::EPSILON && *value <= min.max(max) + std::f32::EPSILON
+        };
+        let label = |ticks: &[f32], value: f32| {
+            // enough decimal places to tell adjacent ticks apart
+            let step = if ticks.len() > 1 { (ticks[1] - ticks[0]).abs() } else { 1.0 };
+            let precision = (-step.log10().floor()).max(0.0) as usize;
+            self.data.format_value(value, precision)
+        };
+        let x_ticks: Vec<_> = x_ticks
+            .iter()
+            .filter(|value| in_range(value, x))
+            .map(|&value| (value, label(&x_ticks, value)))
+            .collect();
+        let y_ticks: Vec<_> = y_ticks
+            .iter()
+            .filter(|value| in_range(value, y))
+            .map(|&value| (value, label(&y_ticks, value)))
+            .collect();
+
+        let bounds = self.abs_rect();
+        let label_width =
+            y_ticks.iter().map(|(_, label)| self.text_width(label)).fold(0.0, f32::max);
+        let label_height = self.data.typeface.size * 1.2;
+        let plot = AbsoluteRect::new(
+            AbsolutePoint::new(
+                bounds.min_x() + label_width + LABEL_SPACING,
+                bounds.min_y() + label_height / 2.0,
+            ),
+            Size::new(
+                (bounds.size.width - label_width - LABEL_SPACING - self.text_width("0") / 2.0)
+                    .max(0.0),
+                (bounds.size.height - label_height * 1.5 - LABEL_SPACING).max(0.0),
+            )
+            .cast_unit(),
+        );
+
+        let x_span = (x.1 - x.0).abs().max(std::f32::EPSILON);
+        let bar_width = if bar_series.is_empty() {
+            0.0
+        } else {
+            slot / x_span * plot.size.width * BAR_FILL / bar_series.len() as f32
+        };
+
+        self.axes = ChartAxes { plot, x, y, x_ticks, y_ticks, bar_width };
+    }
+}
+
+impl<U, G> Widget for ChartWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        if let Some(ref mut provider) = self.provider {
+            if provider.changed() {
+                self.series = provider.series();
+                self.hovered = None;
+                self.dirty = true;
+            }
+        }
+
+        if self.dirty {
+            self.dirty = false;
+            self.update_axes();
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use {
     crate::{
         base::{self, Repaintable},
-        draw::{self, state},
+        draw::{self, state, HasTheme},
         geom::*,
         ui,
     },
     reclutch::{
-        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        display::{Color, DisplayCommand, DisplayText, GraphicsDisplay, Rect},
         prelude::*,
         verbgraph as vg,
     },
@@ -51,9 +51,10 @@ where
         &mut self.interaction
     }
 
-    #[inline]
     fn mouse_bounds(&self) -> RelativeRect {
-        self.painter.mouse_hint(self.rect)
+        // the box and its label, rather than the whole rectangle given by the layout
+        let content = self.painter.size_hint(self.derive_state()).cast_unit();
+        self.painter.mouse_hint(RelativeRect::new(self.rect.origin, content))
     }
 
     #[inline(always)]
@@ -88,18 +89,39 @@ where
             ui::InteractionEvent::Blur => {
                 self.event_queue.emit_owned(CheckboxEvent::Blur);
             }
+            ui::InteractionEvent::FocusVisible
+            | ui::InteractionEvent::Deactivate
+            | ui::InteractionEvent::Activate => {}
         };
     }
+
+    #[inline]
+    fn focus_entry(&self) -> Option<&base::FocusEntry> {
+        Some(&self.focus_entry)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Checkbox {
+    /// Text shown after the box, if not empty. Pressing the label toggles the checkbox too.
+    pub label: DisplayText,
+    pub typeface: draw::TypefaceStyle,
+    /// Icon shown alongside the label, if any.
+    pub icon: Option<draw::Icon>,
+    pub icon_placement: draw::IconPlacement,
+    /// Width and height of the icon.
+    pub icon_size: f32,
+    /// Space between the box, the icon and the label.
+    pub icon_spacing: f32,
+    /// Color of the check mark and the label.
     pub foreground: Color,
     pub background: Color,
     pub focus: Color,
     pub contrast: draw::ThemeContrast,
     pub checked: bool,
     pub disabled: bool,
+    /// Position in the tab order, ahead of widgets without one (see `base::FocusChain`).
+    pub tab_index: Option<u32>,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for Checkbox
@@ -118,12 +140,19 @@ where
     fn from_theme(theme: &dyn draw::Theme) -> Self {
         let data = theme.data();
         Checkbox {
+            label: "".to_string().into(),
+            typeface: data.typography.body.clone(),
+            icon: None,
+            icon_placement: draw::IconPlacement::Before,
+            icon_size: data.icons.size,
+            icon_spacing: data.icons.spacing,
             foreground: data.scheme.over_control_inset,
             background: data.scheme.control_inset,
             focus: data.scheme.focus,
             contrast: data.contrast,
             checked: false,
             disabled: false,
+            tab_index: None,
         }
     }
 
@@ -133,7 +162,12 @@ where
         let mut graph = vg::verbgraph! {
             CheckboxWidget<U, G> as obj,
             U as _aux,
-            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.resize_from_theme();
+                    obj.command_group.repaint();
+                }
+            }
         };
 
         graph = graph.add(
@@ -147,7 +181,8 @@ where
             painter
                 .size_hint(state::CheckboxState {
                     rect: Default::default(),
-                    data: *data,
+                    data: data.clone(),
+                    icon: None,
                     interaction: state::InteractionState::empty(),
                 })
                 .cast_unit(),
@@ -162,6 +197,7 @@ where
             painter,
 
             interaction: state::InteractionState::empty(),
+            focus_entry: u_aux.focus_chain().entry(),
         }
         .build()
     }
@@ -175,7 +211,8 @@ where
     fn derive_state(&self) -> state::CheckboxState {
         state::CheckboxState {
             rect: self.abs_rect(),
-            data: *self.data,
+            data: self.data.clone(),
+            icon: None,
             interaction: self.interaction,
         }
     }
@@ -197,6 +234,7 @@ crate::widget! {
 
         {
             interaction: state::InteractionState,
+            focus_entry: base::FocusEntry,
         },
     }
 }
@@ -215,6 +253,11 @@ where
     }
 
     fn update(&mut self, aux: &mut U) {
+        self.focus_entry.set_tab_index(self.data.tab_index);
+        self.focus_entry.set_group(self.layout.focus_group());
+        self.focus_entry
+            .set_focusable(!self.data.disabled && base::HasVisibility::visibility(self).renders());
+
         let was_focused = self.interaction.contains(state::InteractionState::FOCUSED);
 
         let mut graph = self.graph.take().unwrap();
@@ -237,7 +280,9 @@ where
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
-        let state = self.derive_state();
+        let mut state = self.derive_state();
+        // raster icons are uploaded lazily, once the display is available
+        state.icon = self.data.icon.as_ref().and_then(|icon| icon.load(display));
         let painter = &mut self.painter;
         self.command_group.push_with(
             display,
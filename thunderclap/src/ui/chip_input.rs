@@ -0,0 +1,488 @@
+//! Text input which turns committed entries into removable chips.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Space between the edges of the input and its content.
+const PADDING: f32 = 4.0;
+/// Space between chips, horizontally and vertically.
+const SPACING: f32 = 4.0;
+/// Horizontal space between the edges of a chip and its text.
+const CHIP_PADDING: f32 = 8.0;
+/// Minimum width of the text field following the chips.
+const MIN_FIELD_WIDTH: f32 = 60.0;
+
+/// Events emitted by a chip input.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum ChipEvent {
+    /// Emitted when a chip has been added, with the text of the chip.
+    #[event_key(added)]
+    Added(String),
+    /// Emitted when a chip has been removed, with its former index and its text.
+    #[event_key(removed)]
+    Removed(usize, String),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Generates an unbound terminal which handles typing, committing and removing chips.
+pub fn chip_input_handler<U, G>(
+) -> vg::UnboundQueueHandler<ChipInputWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ChipInputWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.focus();
+                if let Some(index) = obj.remove_button_at(*pos) {
+                    obj.remove(index);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.remove_button_at(pos);
+            if hovered != obj.hovered_remove {
+                obj.hovered_remove = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                match key {
+                    base::KeyInput::Back => {
+                        if obj.data.text.is_empty() {
+                            if !obj.data.chips.is_empty() {
+                                obj.remove(obj.data.chips.len() - 1);
+                            }
+                        } else {
+                            obj.data.text.pop();
+                        }
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => obj.commit(),
+                    _ => {}
+                }
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if obj.data.separators.contains(&c) {
+                    obj.commit();
+                } else if !c.is_control() && obj.data.input_hints.accepts(c) {
+                    obj.data.text.push(c);
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(ChipEvent::Blur);
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Text input in which committed text becomes a chip, removable with its close button or by pressing backspace
+/// in the empty text field. Suitable for lists of tags or email recipients.
+///
+/// Text is committed by pressing enter or typing one of the `separators`. Chips wrap onto new lines,
+/// and the height of the input follows its content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipInput {
+    pub chips: Vec<String>,
+    /// Text typed but not yet committed.
+    pub text: String,
+    /// Text shown when there are no chips and no text.
+    pub placeholder: String,
+    /// Characters which commit the typed text, rather than being typed.
+    pub separators: Vec<char>,
+    /// Whether text equal to an existing chip can be committed.
+    pub allow_duplicates: bool,
+    /// Hints on the typed text for the input method (e.g. `InputHints::EMAIL` for recipients), which also restrict
+    /// the characters which can be typed.
+    pub input_hints: base::InputHints,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub chip_background: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ChipInput
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ChipInputWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for ChipInput
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        ChipInput {
+            chips: Vec::new(),
+            text: String::new(),
+            placeholder: String::new(),
+            separators: vec![','],
+            allow_duplicates: false,
+            input_hints: Default::default(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            chip_background: draw::strengthen(data.scheme.control_inset, 0.1, data.contrast),
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> ChipInputWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ChipInputWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("chip_input", chip_input_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.chip_input();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::ChipInputState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    chips: Vec::new(),
+                    field: Default::default(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let mut chip_input = ChipInputWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            chip_rects: Vec::new(),
+            field: Default::default(),
+            hovered_remove: None,
+            previous_rect: Default::default(),
+            dirty: true,
+        }
+        .build();
+
+        chip_input.update_layout();
+        chip_input.previous_rect = chip_input.rect;
+
+        chip_input
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::ChipInputState> for ChipInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::ChipInputState {
+        state::ChipInputState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            chips: self
+                .data
+                .chips
+                .iter()
+                .zip(&self.chip_rects)
+                .enumerate()
+                .map(|(i, (text, (rect, remove)))| state::ChipState {
+                    text: text.clone(),
+                    rect: *rect,
+                    remove: *remove,
+                    remove_hovered: self.hovered_remove == Some(i),
+                })
+                .collect(),
+            field: self.field,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        if (self.previous_rect.size.width - self.rect.size.width).abs() > std::f32::EPSILON
+            || self.previous_rect.origin != self.rect.origin
+        {
+            self.dirty = true;
+        }
+
+        self.previous_rect = self.rect;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ChipInputWidget {
+        widget::MAX,
+
+        <ChipEvent> EventQueue,
+        <ChipInput> State,
+        <state::ChipInputState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            /// Rectangle of each chip and of its remove button.
+            chip_rects: Vec<(AbsoluteRect, AbsoluteRect)>,
+            /// Rectangle of the text field following the chips.
+            field: AbsoluteRect,
+            hovered_remove: Option<usize>,
+            previous_rect: RelativeRect,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> ChipInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Adds a chip, emitting `ChipEvent::Added`.
+    ///
+    /// Surrounding whitespace is trimmed, and the chip isn't added if it's empty, or if it's a duplicate and
+    /// duplicates aren't allowed. Returns whether the chip was added.
+    pub fn add(&mut self, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty()
+            || (!self.data.allow_duplicates && self.data.chips.iter().any(|chip| chip == text))
+        {
+            return false;
+        }
+
+        self.data.chips.push(text.to_string());
+        self.event_queue.emit_owned(ChipEvent::Added(text.to_string()));
+        true
+    }
+
+    /// Removes a chip, emitting `ChipEvent::Removed`.
+    pub fn remove(&mut self, index: usize) -> Option<String> {
+        if index >= self.data.chips.len() {
+            return None;
+        }
+
+        let chip = self.data.chips.remove(index);
+        self.hovered_remove = None;
+        self.event_queue.emit_owned(ChipEvent::Removed(index, chip.clone()));
+        Some(chip)
+    }
+
+    /// Commits the typed text as a chip. The text is kept if it can't be added (e.g. if it's a duplicate).
+    pub fn commit(&mut self) {
+        let text = self.data.text.clone();
+        if self.add(&text) || text.trim().is_empty() {
+            self.data.text.clear();
+        }
+    }
+
+    /// Returns the index of the chip whose remove button contains a given point, if any.
+    pub fn remove_button_at(&self, point: AbsolutePoint) -> Option<usize> {
+        if self.data.disabled {
+            return None;
+        }
+        self.chip_rects.iter().position(|(_, remove)| remove.contains(point))
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(ChipEvent::Focus);
+        }
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        if text.is_empty() {
+            return 0.0;
+        }
+
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+        }
+        .bounds()
+        .map(|bounds| bounds.size.width)
+        .unwrap_or(0.0)
+    }
+
+    /// Flows the chips and the text field from left to right, wrapping onto new lines, and fits the height of
+    /// the input to them.
+    fn update_layout(&mut self) {
+        let bounds = self.abs_rect();
+        let height = (self.data.typeface.size * 1.6).round();
+        let (left, right) =
+            (bounds.min_x() + PADDING, (bounds.max_x() - PADDING).max(bounds.min_x() + PADDING));
+        let mut position = AbsolutePoint::new(left, bounds.min_y() + PADDING);
+
+        let mut place = |width: f32| {
+            let width = width.min(right - left);
+            if position.x > left && position.x + width > right {
+                position = AbsolutePoint::new(left, position.y + height + SPACING);
+            }
+            let rect = AbsoluteRect::new(position, Size::new(width, height).cast_unit());
+            position.x += width + SPACING;
+            rect
+        };
+
+        let mut chip_rects = Vec::with_capacity(self.data.chips.len());
+        for chip in &self.data.chips {
+            let rect = place(self.text_width(chip) + CHIP_PADDING * 2.0 + height);
+            let remove = AbsoluteRect::new(
+                AbsolutePoint::new(rect.max_x() - height, rect.min_y()),
+                Size::new(height, height).cast_unit(),
+            );
+            chip_rects.push((rect, remove));
+        }
+
+        let shown_text = if self.data.text.is_empty() && self.data.chips.is_empty() {
+            &self.data.placeholder
+        } else {
+            &self.data.text
+        };
+        let field = place(MIN_FIELD_WIDTH.max(self.text_width(shown_text) + SPACING));
+        // The text field takes the rest of its line.
+        let field = AbsoluteRect::new(
+            field.origin,
+            Size::new((right - field.min_x()).max(field.size.width), height).cast_unit(),
+        );
+
+        self.chip_rects = chip_rects;
+        self.field = field;
+
+        let content_height = field.max_y() + PADDING - bounds.min_y();
+        if (content_height - bounds.size.height).abs() > std::f32::EPSILON {
+            self.set_ctxt_rect(AbsoluteRect::new(
+                bounds.origin,
+                Size::new(bounds.size.width, content_height).cast_unit(),
+            ));
+        }
+    }
+}
+
+impl<U, G> Widget for ChipInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: self.data.input_hints,
+                area: self.abs_rect(),
+            });
+        }
+
+        if self.dirty {
+            self.dirty = false;
+            self.update_layout();
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
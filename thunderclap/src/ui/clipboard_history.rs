@@ -0,0 +1,519 @@
+//! History of recently copied texts, and a popup to pick from it.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventListener,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{cell::RefCell, collections::VecDeque, rc::Rc},
+};
+
+/// Maximum number of characters of an entry shown in the picker.
+const PREVIEW_LENGTH: usize = 60;
+
+/// Events emitted by a clipboard history picker.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum ClipboardHistoryEvent {
+    /// Emitted when an entry has been picked, with the text of the entry.
+    ///
+    /// The text is typically pasted into the focused text widget (see `LogicalTextArea::insert_str`).
+    #[event_key(pick)]
+    Pick(String),
+    /// Emitted when the picker is closed without picking an entry.
+    #[event_key(dismiss)]
+    Dismiss,
+}
+
+#[derive(Debug)]
+struct ClipboardHistoryInner {
+    entries: VecDeque<String>,
+    capacity: usize,
+    max_entry_len: usize,
+    enabled: bool,
+}
+
+/// Shared record of recently copied texts, most recent first.
+///
+/// Thunderclap doesn't access the system clipboard, so texts are recorded by the application whenever it copies (or cuts) text.
+/// Cloning the handle yields another handle to the same history, so it can be shared with a `ClipboardHistoryPicker`.
+#[derive(Debug, Clone)]
+pub struct ClipboardHistory(Rc<RefCell<ClipboardHistoryInner>>);
+
+impl ClipboardHistory {
+    /// Creates an empty history which keeps at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        ClipboardHistory(Rc::new(RefCell::new(ClipboardHistoryInner {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            max_entry_len: 64 * 1024,
+            enabled: true,
+        })))
+    }
+
+    /// Records a copied text as the most recent entry.
+    ///
+    /// Empty texts and texts longer than the maximum entry length (in bytes) are ignored, as is everything while recording is disabled.
+    /// Recording a text which is already in the history moves it to the front.
+    pub fn record(&self, text: impl Into<String>) {
+        let text = text.into();
+        let mut inner = self.0.borrow_mut();
+        if !inner.enabled || text.is_empty() || text.len() > inner.max_entry_len {
+            return;
+        }
+
+        inner.entries.retain(|entry| *entry != text);
+        inner.entries.push_front(text);
+        let capacity = inner.capacity;
+        inner.entries.truncate(capacity);
+    }
+
+    /// Returns the recorded texts, most recent first.
+    pub fn entries(&self) -> Vec<String> {
+        self.0.borrow().entries.iter().cloned().collect()
+    }
+
+    /// Returns the number of recorded texts.
+    pub fn len(&self) -> usize {
+        self.0.borrow().entries.len()
+    }
+
+    /// Returns `true` if no texts are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().entries.is_empty()
+    }
+
+    /// Removes a single entry.
+    pub fn remove(&self, index: usize) {
+        self.0.borrow_mut().entries.remove(index);
+    }
+
+    /// Removes all the entries.
+    pub fn clear(&self) {
+        self.0.borrow_mut().entries.clear();
+    }
+
+    /// Changes the maximum number of entries, discarding the oldest entries beyond it.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.0.borrow_mut();
+        inner.capacity = capacity;
+        inner.entries.truncate(capacity);
+    }
+
+    /// Changes the maximum length of recorded texts, in bytes. Existing entries are kept.
+    pub fn set_max_entry_len(&self, max_entry_len: usize) {
+        self.0.borrow_mut().max_entry_len = max_entry_len;
+    }
+
+    /// Enables or disables recording (e.g. as a privacy opt-out). Disabling also clears the history.
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut inner = self.0.borrow_mut();
+        inner.enabled = enabled;
+        if !enabled {
+            inner.entries.clear();
+        }
+    }
+
+    /// Returns `true` if recording is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.0.borrow().enabled
+    }
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        ClipboardHistory::new(20)
+    }
+}
+
+impl PartialEq for ClipboardHistory {
+    fn eq(&self, other: &ClipboardHistory) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Returns the first line of a text, shortened to `PREVIEW_LENGTH` characters.
+fn preview(text: &str) -> String {
+    let line = text.trim_start().lines().next().unwrap_or("");
+    let mut preview: String = line.chars().take(PREVIEW_LENGTH).collect();
+    if preview.len() < line.len() || line.len() < text.trim().len() {
+        preview.push_str("...");
+    }
+    preview.replace('\t', "    ")
+}
+
+/// Generates an unbound terminal which handles picking entries with the mouse and keyboard.
+pub fn clipboard_history_handler<U, G>(
+) -> vg::UnboundQueueHandler<ClipboardHistoryPickerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ClipboardHistoryPickerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            // Presses outside the picker are left to the dismiss policy.
+            if let Some((pos, _, _)) =
+                event.with(|(pos, _, _)| obj.data.open && obj.row_at(*pos).is_some())
+            {
+                if let Some(row) = obj.row_at(*pos) {
+                    obj.pick(row);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = if obj.data.open { obj.row_at(pos) } else { None };
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| obj.data.open) {
+                let len = obj.entries.len();
+                match key {
+                    base::KeyInput::Up if len > 0 => obj.select((obj.selected + len - 1) % len),
+                    base::KeyInput::Down if len > 0 => obj.select((obj.selected + 1) % len),
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter if len > 0 => {
+                        let selected = obj.selected;
+                        obj.pick(selected);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        text_input => {
+            // Keep typed characters from reaching the focused text widget while open.
+            event.with(|_| obj.data.open);
+        }
+    }
+}
+
+/// Popup listing the entries of a `ClipboardHistory`, from which one can be picked to be pasted again.
+///
+/// The picker doesn't take focus, so that the focused text widget can receive the picked text.
+/// It's closed as described by its `dismiss_policy`. While open, it blocks mouse input to the rest of the window,
+/// unless the policy keeps it open on outside presses (e.g. when pinned).
+/// While open, it handles the arrow, enter and escape keys, and consumes text input; for this to take precedence over text widgets,
+/// the picker should be updated before them. For the popup to appear above other widgets, it should be drawn after them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardHistoryPicker {
+    pub history: ClipboardHistory,
+    pub open: bool,
+    /// Interactions which close the picker without picking an entry.
+    pub dismiss_policy: base::DismissPolicy,
+    /// Maximum number of entries shown at once.
+    pub max_rows: usize,
+    pub row_height: f32,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the selected entry.
+    pub highlight: Color,
+    /// Color of the text of the selected entry.
+    pub over_highlight: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ClipboardHistoryPicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ClipboardHistoryPickerWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for ClipboardHistoryPicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        ClipboardHistoryPicker {
+            history: Default::default(),
+            open: false,
+            dismiss_policy: Default::default(),
+            max_rows: 8,
+            row_height: 24.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_background,
+            background: data.scheme.background,
+            highlight: data.scheme.list_selection,
+            over_highlight: data.scheme.over_list_selection,
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> ClipboardHistoryPickerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ClipboardHistoryPickerWidget<U, G> as obj,
+            U as aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.sync_open(aux);
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "clipboard_history",
+            clipboard_history_handler::<U, G>().bind(u_aux.window_queue()),
+        );
+
+        let painter = theme.clipboard_history_picker();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::ClipboardHistoryPickerState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    panel: Default::default(),
+                    rows: Vec::new(),
+                })
+                .cast_unit(),
+        );
+
+        let dismiss_listener = u_aux.window_queue().listen();
+        ClipboardHistoryPickerWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            entries: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            hovered: None,
+            grab: None,
+            dismiss_listener,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::ClipboardHistoryPickerState>
+    for ClipboardHistoryPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::ClipboardHistoryPickerState {
+        let panel = self.panel_rect();
+        let rows = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.data.max_rows)
+            .map(|(i, entry)| state::ClipboardRowState {
+                text: preview(entry).into(),
+                selected: i == self.selected,
+                hovered: self.hovered == Some(i),
+                rect: self.row_rect(i),
+            })
+            .collect();
+
+        state::ClipboardHistoryPickerState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            panel,
+            rows,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        if let Some(ref grab) = self.grab {
+            grab.set_region(self.panel_rect());
+        }
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ClipboardHistoryPickerWidget {
+        widget::MAX,
+
+        <ClipboardHistoryEvent> EventQueue,
+        <ClipboardHistoryPicker> State,
+        <state::ClipboardHistoryPickerState> Painter,
+
+        {
+            /// Entries of the history as of opening.
+            entries: Vec<String>,
+            selected: usize,
+            /// Index of the first visible entry.
+            scroll: usize,
+            hovered: Option<usize>,
+            grab: Option<base::InputGrab>,
+            dismiss_listener: RcEventListener<base::WindowEvent>,
+        },
+    }
+}
+
+impl<U, G> ClipboardHistoryPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Opens the picker, listing the current entries of the history with the most recent selected.
+    pub fn open(&mut self) {
+        self.data.open = true;
+    }
+
+    /// Closes the picker without picking an entry, and emits `ClipboardHistoryEvent::Dismiss`.
+    pub fn dismiss(&mut self) {
+        if self.data.open {
+            self.data.open = false;
+            self.event_queue.emit_owned(ClipboardHistoryEvent::Dismiss);
+        }
+    }
+
+    /// Closes the picker and emits `ClipboardHistoryEvent::Pick` with the entry at `index`, making it the most recent entry.
+    pub fn pick(&mut self, index: usize) {
+        if let Some(text) = self.entries.get(index).cloned() {
+            self.data.history.record(text.clone());
+            self.data.open = false;
+            self.event_queue.emit_owned(ClipboardHistoryEvent::Pick(text));
+        }
+    }
+
+    /// Returns the rectangle of the visible part of the popup, which only spans the listed entries.
+    pub fn panel_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let rows = self.entries.len().min(self.data.max_rows).max(1);
+        AbsoluteRect::new(
+            rect.origin,
+            Size::new(
+                rect.size.width,
+                (rows as f32 * self.data.row_height + 8.0).min(rect.size.height),
+            )
+            .cast_unit(),
+        )
+    }
+
+    fn row_rect(&self, index: usize) -> AbsoluteRect {
+        let panel = self.panel_rect();
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                panel.origin.x + 4.0,
+                panel.origin.y + 4.0 + (index as f32 - self.scroll as f32) * self.data.row_height,
+            ),
+            Size::new(panel.size.width - 8.0, self.data.row_height).cast_unit(),
+        )
+    }
+
+    fn row_at(&self, point: AbsolutePoint) -> Option<usize> {
+        (self.scroll..self.entries.len())
+            .take(self.data.max_rows)
+            .find(|&i| self.row_rect(i).contains(point))
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        if index < self.scroll {
+            self.scroll = index;
+        } else if index >= self.scroll + self.data.max_rows {
+            self.scroll = index + 1 - self.data.max_rows;
+        }
+        self.command_group.repaint();
+    }
+
+    fn sync_open(&mut self, aux: &mut U) {
+        if let Some(ref grab) = self.grab {
+            grab.set_input_transparent(
+                !self.data.dismiss_policy.contains(base::DismissPolicy::OUTSIDE_PRESS),
+            );
+        }
+
+        if self.data.open == self.grab.is_some() {
+            return;
+        }
+
+        if self.data.open {
+            self.entries = self.data.history.entries();
+            self.selected = 0;
+            self.scroll = 0;
+            self.hovered = None;
+            let grab = aux.input_grabs().grab(self.panel_rect());
+            grab.set_input_transparent(
+                !self.data.dismiss_policy.contains(base::DismissPolicy::OUTSIDE_PRESS),
+            );
+            self.grab = Some(grab);
+            // Forget the events which preceded opening (e.g. the press which opened the picker).
+            self.dismiss_listener.peek();
+        } else {
+            self.entries.clear();
+            self.grab = None;
+        }
+    }
+}
+
+impl<U, G> Widget for ClipboardHistoryPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        let events = self.dismiss_listener.peek();
+        if self.data.open {
+            let (policy, panel) = (self.data.dismiss_policy, self.panel_rect());
+            if events.iter().any(|event| policy.dismisses(event, panel)) {
+                self.dismiss();
+            }
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,871 @@
+//! Multi-line text editor for source code.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem, Vector},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::ops::Range,
+};
+
+/// Horizontal space between the edges of the gutter and the line numbers.
+const GUTTER_PADDING: f32 = 8.0;
+/// Horizontal space between the gutter and the start of each line.
+const TEXT_PADDING: f32 = 4.0;
+
+/// Events emitted by a code editor.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum CodeEditorEvent {
+    /// Emitted when the text has been modified by the user, with the new text.
+    #[event_key(user_modify)]
+    UserModify(String),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Kind of a token, which determines its color (see `SyntaxColors`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    /// Name of a type.
+    Type,
+    String,
+    Number,
+    Comment,
+    Punctuation,
+}
+
+/// Span of a line of source code, highlighted as a single kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// Byte range of the token within the line.
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// Splits lines of source code into tokens for syntax highlighting.
+///
+/// Tokens may be returned in any order; spans of the line not covered by a token are shown as plain text, and
+/// tokens which overlap a previous token are ignored.
+/// Lines are tokenized independently, hence constructs spanning several lines (e.g. block comments) aren't supported.
+pub trait Tokenizer {
+    /// Returns the tokens of a line.
+    fn tokenize(&self, line: &str) -> Vec<Token>;
+}
+
+impl<F: Fn(&str) -> Vec<Token>> Tokenizer for F {
+    #[inline]
+    fn tokenize(&self, line: &str) -> Vec<Token> {
+        (self)(line)
+    }
+}
+
+/// Colors of each kind of token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntaxColors {
+    pub plain: Color,
+    pub keyword: Color,
+    pub type_name: Color,
+    pub string: Color,
+    pub number: Color,
+    pub comment: Color,
+    pub punctuation: Color,
+}
+
+impl SyntaxColors {
+    /// Returns the color of a kind of token.
+    pub fn color(&self, kind: TokenKind) -> Color {
+        match kind {
+            TokenKind::Plain => self.plain,
+            TokenKind::Keyword => self.keyword,
+            TokenKind::Type => self.type_name,
+            TokenKind::String => self.string,
+            TokenKind::Number => self.number,
+            TokenKind::Comment => self.comment,
+            TokenKind::Punctuation => self.punctuation,
+        }
+    }
+}
+
+/// Position of a cursor within a code editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct CodePosition {
+    /// Index of the line, from zero.
+    pub line: usize,
+    /// Index of the character within the line.
+    pub column: usize,
+}
+
+impl CodePosition {
+    #[inline]
+    pub fn new(line: usize, column: usize) -> Self {
+        CodePosition { line, column }
+    }
+}
+
+/// Returns the number of characters in a line.
+fn line_len(line: &str) -> usize {
+    line.chars().count()
+}
+
+/// Converts a position into a character offset within the text, clamping it to the text.
+fn offset_of(text: &str, position: CodePosition) -> usize {
+    let mut offset = 0;
+    for (i, line) in text.split('\n').enumerate() {
+        let len = line_len(line);
+        if i == position.line {
+            return offset + position.column.min(len);
+        }
+        offset += len + 1;
+    }
+    // past the last line
+    offset.saturating_sub(1)
+}
+
+/// Converts a character offset within the text into a position.
+fn position_of(text: &str, offset: usize) -> CodePosition {
+    let mut position = CodePosition::default();
+    for c in text.chars().take(offset) {
+        if c == '\n' {
+            position.line += 1;
+            position.column = 0;
+        } else {
+            position.column += 1;
+        }
+    }
+    position
+}
+
+/// Returns the character offset of the start of the line containing `offset`.
+fn line_start(chars: &[char], offset: usize) -> usize {
+    chars[..offset].iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1)
+}
+
+/// Measures the width of each character of a monospaced typeface.
+pub(crate) fn measure_advance(typeface: &draw::TypefaceStyle) -> f32 {
+    const SAMPLE: &str = "0000000000";
+    let font = typeface.typeface.pick(typeface.style);
+    TextDisplayItem {
+        text: SAMPLE.to_string().into(),
+        font: font.0,
+        font_info: font.1,
+        size: typeface.size,
+        bottom_left: Default::default(),
+        color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+    }
+    .bounds()
+    .map(|bounds| bounds.size.width / SAMPLE.len() as f32)
+    .unwrap_or(typeface.size / 2.0)
+}
+
+/// Generates an unbound terminal which handles editing, moving the cursors and scrolling a code editor.
+pub fn code_editor_handler<U, G>(
+) -> vg::UnboundQueueHandler<CodeEditorWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        CodeEditorWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, modifiers)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                let position = obj.position_at(*pos);
+                if modifiers.alt {
+                    // alt-clicking adds a cursor, or removes an existing one
+                    if let Some(i) = obj.cursors.iter().position(|&c| c == position) {
+                        if obj.cursors.len() > 1 {
+                            obj.cursors.remove(i);
+                        }
+                    } else {
+                        obj.cursors.push(position);
+                        obj.cursors.sort();
+                    }
+                } else {
+                    obj.cursors = vec![position];
+                }
+                obj.focus();
+                obj.command_group.repaint();
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, modifiers)) =
+                event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos))
+            {
+                // shift scrolls vertical wheels horizontally
+                let delta = if modifiers.shift && delta.x == 0.0 {
+                    Vector::new(delta.y, 0.0)
+                } else {
+                    *delta
+                };
+                let scroll = obj.scroll - delta;
+                obj.scroll_to(scroll);
+            }
+        }
+
+        key_press => {
+            if let Some((key, modifiers)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                let multi = modifiers.ctrl && modifiers.alt;
+                match key {
+                    base::KeyInput::Back => obj.backspace(),
+                    base::KeyInput::Delete => obj.delete(),
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => obj.new_line(),
+                    base::KeyInput::Tab => obj.indent(),
+                    base::KeyInput::Up if multi => obj.add_cursor(-1),
+                    base::KeyInput::Down if multi => obj.add_cursor(1),
+                    base::KeyInput::Left => obj.move_cursors(|lines, c| {
+                        if c.column > 0 {
+                            CodePosition::new(c.line, c.column - 1)
+                        } else if c.line > 0 {
+                            CodePosition::new(c.line - 1, line_len(lines[c.line - 1]))
+                        } else {
+                            c
+                        }
+                    }),
+                    base::KeyInput::Right => obj.move_cursors(|lines, c| {
+                        if c.column < line_len(lines[c.line]) {
+                            CodePosition::new(c.line, c.column + 1)
+                        } else if c.line + 1 < lines.len() {
+                            CodePosition::new(c.line + 1, 0)
+                        } else {
+                            c
+                        }
+                    }),
+                    base::KeyInput::Up => obj.move_cursors(|lines, c| {
+                        if c.line > 0 {
+                            CodePosition::new(c.line - 1, c.column.min(line_len(lines[c.line - 1])))
+                        } else {
+                            CodePosition::new(0, 0)
+                        }
+                    }),
+                    base::KeyInput::Down => obj.move_cursors(|lines, c| {
+                        if c.line + 1 < lines.len() {
+                            CodePosition::new(c.line + 1, c.column.min(line_len(lines[c.line + 1])))
+                        } else {
+                            CodePosition::new(c.line, line_len(lines[c.line]))
+                        }
+                    }),
+                    base::KeyInput::Home => obj.move_cursors(|_, c| CodePosition::new(c.line, 0)),
+                    base::KeyInput::End => obj.move_cursors(|lines, c| {
+                        CodePosition::new(c.line, line_len(lines[c.line]))
+                    }),
+                    _ => {}
+                }
+            }
+        }
+
+        escape => {
+            if event
+                .with(|stage| {
+                    *stage == base::EscapeStage::Selection
+                        && obj.cursors.len() > 1
+                        && obj.interaction.contains(state::InteractionState::FOCUSED)
+                })
+                .is_some()
+            {
+                obj.cursors.truncate(1);
+                obj.command_group.repaint();
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if !c.is_control() {
+                    let mut buf = [0; 4];
+                    obj.insert_str(c.encode_utf8(&mut buf));
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(CodeEditorEvent::Blur);
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Multi-line text editor for source code, with line numbers in a gutter on the left.
+///
+/// Lines are syntax highlighted by the [`Tokenizer`](trait.Tokenizer.html) given to
+/// `CodeEditorWidget::set_tokenizer`, or shown as plain text without one.
+/// The typeface should be monospaced; every character is assumed to be as wide as a `0`.
+///
+/// Editing supports several cursors at once; alt-clicking adds (or removes) a cursor, Ctrl+Alt+Up/Down adds a
+/// cursor on the line above/below, and escape leaves only the first cursor. Selections aren't supported.
+/// The view is scrolled with the mouse wheel (horizontally with shift held), and follows the first cursor while
+/// typing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeEditor {
+    pub text: String,
+    pub typeface: draw::TypefaceStyle,
+    pub line_height: f32,
+    /// Number of columns between tab stops; pressing tab inserts spaces up to the next tab stop.
+    pub tab_width: usize,
+    pub syntax: SyntaxColors,
+    pub background: Color,
+    pub gutter_background: Color,
+    /// Color of the line numbers.
+    pub gutter_foreground: Color,
+    /// Color behind the lines containing a cursor.
+    pub current_line: Color,
+    pub cursor_color: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for CodeEditor
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CodeEditorWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for CodeEditor
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        let typeface = draw::TypefaceStyle { size: 14.0, ..data.typography.body.clone() };
+        CodeEditor {
+            text: String::new(),
+            line_height: (typeface.size * 1.5).round(),
+            typeface,
+            tab_width: 4,
+            syntax: SyntaxColors {
+                plain: data.scheme.over_control_inset,
+                keyword: base::color_from_urgba(215, 58, 73, 1.0),
+                type_name: base::color_from_urgba(111, 66, 193, 1.0),
+                string: base::color_from_urgba(3, 47, 98, 1.0),
+                number: base::color_from_urgba(0, 92, 197, 1.0),
+                comment: base::color_from_urgba(106, 115, 125, 1.0),
+                punctuation: data.scheme.over_control_inset,
+            },
+            background: data.scheme.control_inset,
+            gutter_background: draw::strengthen(data.scheme.control_inset, 0.03, data.contrast),
+            gutter_foreground: draw::weaken(data.scheme.over_control_inset, 0.5, data.contrast),
+            current_line: draw::strengthen(data.scheme.control_inset, 0.05, data.contrast),
+            cursor_color: data.scheme.over_control_inset,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> CodeEditorWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CodeEditorWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.advance = measure_advance(&obj.data.typeface);
+                    // the text may have been replaced
+                    let text = &obj.data.text;
+                    for cursor in &mut obj.cursors {
+                        *cursor = position_of(text, offset_of(text, *cursor));
+                    }
+                    obj.cursors.dedup();
+                    let scroll = obj.scroll;
+                    obj.scroll_to(scroll);
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("code_editor", code_editor_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.code_editor();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::CodeEditorState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    gutter: Default::default(),
+                    viewport: Default::default(),
+                    lines: Vec::new(),
+                    cursors: Vec::new(),
+                    advance: 0.0,
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let mut editor = CodeEditorWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            tokenizer: None,
+            cursors: vec![Default::default()],
+            scroll: Vector::zero(),
+            advance: 0.0,
+        }
+        .build();
+
+        editor.advance = measure_advance(&editor.data.typeface);
+        editor
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::CodeEditorState> for CodeEditorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::CodeEditorState {
+        let viewport = self.viewport_rect();
+        let line_height = self.data.line_height;
+
+        // only the lines in view are tokenized
+        let first = (self.scroll.y / line_height).floor().max(0.0) as usize;
+        let count = (viewport.size.height / line_height).ceil() as usize + 1;
+        let lines = self
+            .data
+            .text
+            .split('\n')
+            .enumerate()
+            .skip(first)
+            .take(count)
+            .map(|(i, text)| state::CodeLineState {
+                number: i + 1,
+                text: text.to_string(),
+                tokens: self.highlight(text),
+                rect: self.line_rect(i),
+                current: self.cursors.iter().any(|cursor| cursor.line == i),
+            })
+            .collect();
+
+        // cursors are hidden while the window is inactive
+        let visible = self.interaction.contains(state::InteractionState::FOCUSED)
+            && !self.interaction.contains(state::InteractionState::INACTIVE);
+        let cursors = if visible {
+            self.cursors
+                .iter()
+                .map(|&cursor| {
+                    let line = self.line_rect(cursor.line);
+                    AbsoluteRect::new(
+                        AbsolutePoint::new(
+                            line.min_x() + cursor.column as f32 * self.advance,
+                            line.min_y(),
+                        ),
+                        Size::new(1.0, line_height).cast_unit(),
+                    )
+                })
+                .filter(|cursor| viewport.intersects(cursor))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        state::CodeEditorState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            gutter: self.gutter_rect(),
+            viewport,
+            lines,
+            cursors,
+            advance: self.advance,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CodeEditorWidget {
+        widget::MAX,
+
+        <CodeEditorEvent> EventQueue,
+        <CodeEditor> State,
+        <state::CodeEditorState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            tokenizer: Option<Box<dyn Tokenizer>>,
+            /// Cursors in document order; never empty.
+            cursors: Vec<CodePosition>,
+            scroll: Vector,
+            /// Width of each character.
+            advance: f32,
+        },
+    }
+}
+
+impl<U, G> CodeEditorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes how lines are split into tokens for syntax highlighting.
+    pub fn set_tokenizer(&mut self, tokenizer: impl Tokenizer + 'static) {
+        self.tokenizer = Some(Box::new(tokenizer));
+        self.command_group.repaint();
+    }
+
+    /// Removes the tokenizer, showing the code as plain text.
+    pub fn clear_tokenizer(&mut self) {
+        self.tokenizer = None;
+        self.command_group.repaint();
+    }
+
+    /// Returns the cursors, in document order.
+    #[inline]
+    pub fn cursors(&self) -> &[CodePosition] {
+        &self.cursors
+    }
+
+    /// Replaces the cursors, clamping them to the text. If `cursors` is empty, a cursor is placed at the start.
+    pub fn set_cursors(&mut self, cursors: Vec<CodePosition>) {
+        let text = &self.data.text;
+        self.cursors = cursors.into_iter().map(|c| position_of(text, offset_of(text, c))).collect();
+        self.cursors.sort();
+        self.cursors.dedup();
+        if self.cursors.is_empty() {
+            self.cursors.push(Default::default());
+        }
+        self.command_group.repaint();
+    }
+
+    /// Returns how far the view is scrolled, horizontally and vertically.
+    #[inline]
+    pub fn scroll(&self) -> Vector {
+        self.scroll
+    }
+
+    /// Scrolls the view, clamping it to the extent of the text.
+    pub fn scroll_to(&mut self, scroll: Vector) {
+        let max = self.max_scroll();
+        let scroll = Vector::new(scroll.x.max(0.0).min(max.x), scroll.y.max(0.0).min(max.y));
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns the rectangle of the gutter showing the line numbers.
+    pub fn gutter_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let digits = self.data.text.split('\n').count().to_string().len();
+        AbsoluteRect::new(
+            rect.origin,
+            Size::new(digits as f32 * self.advance + GUTTER_PADDING * 2.0, rect.size.height)
+                .cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle in which the code is shown, to the right of the gutter.
+    pub fn viewport_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let gutter = self.gutter_rect().size.width.min(rect.size.width);
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x() + gutter, rect.min_y()),
+            Size::new(rect.size.width - gutter, rect.size.height).cast_unit(),
+        )
+    }
+
+    /// Returns the position of the character boundary closest to a point.
+    pub fn position_at(&self, point: AbsolutePoint) -> CodePosition {
+        let viewport = self.viewport_rect();
+        let lines: Vec<_> = self.data.text.split('\n').collect();
+        let line = ((point.y - viewport.min_y() + self.scroll.y) / self.data.line_height)
+            .floor()
+            .max(0.0) as usize;
+        let line = line.min(lines.len() - 1);
+        let column = ((point.x - viewport.min_x() - TEXT_PADDING + self.scroll.x)
+            / self.advance.max(1.0))
+        .round()
+        .max(0.0) as usize;
+        CodePosition::new(line, column.min(line_len(lines[line])))
+    }
+
+    /// Returns the rectangle of a line, starting at its first character and spanning the viewport.
+    fn line_rect(&self, line: usize) -> AbsoluteRect {
+        let viewport = self.viewport_rect();
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                viewport.min_x() + TEXT_PADDING - self.scroll.x,
+                viewport.min_y() + line as f32 * self.data.line_height - self.scroll.y,
+            ),
+            Size::new(viewport.size.width + self.scroll.x, self.data.line_height).cast_unit(),
+        )
+    }
+
+    fn max_scroll(&self) -> Vector {
+        let viewport = self.viewport_rect();
+        let (count, longest) = self
+            .data
+            .text
+            .split('\n')
+            .fold((0, 0), |(count, longest), line| (count + 1, longest.max(line_len(line))));
+        Vector::new(
+            (longest as f32 * self.advance + TEXT_PADDING * 2.0 - viewport.size.width).max(0.0),
+            (count as f32 * self.data.line_height - viewport.size.height).max(0.0),
+        )
+    }
+
+    /// Scrolls the first cursor into view.
+    fn scroll_to_cursor(&mut self) {
+        let viewport = self.viewport_rect();
+        let cursor = self.cursors[0];
+        let x = TEXT_PADDING + cursor.column as f32 * self.advance;
+        let y = cursor.line as f32 * self.data.line_height;
+
+        let mut scroll = self.scroll;
+        scroll.x = scroll.x.min(x - TEXT_PADDING).max(x + TEXT_PADDING - viewport.size.width);
+        scroll.y = scroll.y.min(y).max(y + self.data.line_height - viewport.size.height);
+        self.scroll_to(scroll);
+    }
+
+    /// Tokenizes a line, covering the whole line with non-overlapping tokens in order.
+    fn highlight(&self, line: &str) -> Vec<Token> {
+        let mut tokens = self.tokenizer.as_ref().map(|t| t.tokenize(line)).unwrap_or_default();
+        tokens.sort_by_key(|token| token.range.start);
+
+        let mut highlighted = Vec::with_capacity(tokens.len() * 2 + 1);
+        let mut end = 0;
+        for token in tokens {
+            let range = token.range.start..token.range.end.min(line.len());
+            if range.start < end
+                || range.start >= range.end
+                || !line.is_char_boundary(range.start)
+                || !line.is_char_boundary(range.end)
+            {
+                continue;
+            }
+            if range.start > end {
+                highlighted.push(Token { range: end..range.start, kind: TokenKind::Plain });
+            }
+            end = range.end;
+            highlighted.push(Token { range, kind: token.kind });
+        }
+        if end < line.len() {
+            highlighted.push(Token { range: end..line.len(), kind: TokenKind::Plain });
+        }
+        highlighted
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(CodeEditorEvent::Focus);
+        }
+    }
+
+    /// Moves every cursor, given the lines of the text.
+    fn move_cursors(&mut self, f: impl Fn(&[&str], CodePosition) -> CodePosition) {
+        {
+            let lines: Vec<_> = self.data.text.split('\n').collect();
+            for cursor in &mut self.cursors {
+                *cursor = f(&lines, *cursor);
+            }
+        }
+        self.cursors.sort();
+        self.cursors.dedup();
+        self.scroll_to_cursor();
+        self.command_group.repaint();
+    }
+
+    /// Adds a cursor on the line above (`-1`) or below (`1`) the first or last cursor respectively.
+    fn add_cursor(&mut self, direction: isize) {
+        let lines: Vec<_> = self.data.text.split('\n').map(line_len).collect();
+        let from =
+            if direction < 0 { self.cursors[0] } else { self.cursors[self.cursors.len() - 1] };
+        let line = from.line as isize + direction;
+        if line >= 0 && (line as usize) < lines.len() {
+            let line = line as usize;
+            self.cursors.push(CodePosition::new(line, from.column.min(lines[line])));
+            self.cursors.sort();
+            self.cursors.dedup();
+            self.command_group.repaint();
+        }
+    }
+
+    /// Applies an edit at every cursor.
+    ///
+    /// `edit` is given the characters of the text and the offset of a cursor, and returns the new offset of the
+    /// cursor and the change in length. Cursors are edited from last to first, so that edits don't move the cursors
+    /// which are yet to be edited.
+    fn edit(&mut self, mut edit: impl FnMut(&mut Vec<char>, usize) -> (usize, isize)) {
+        let mut chars: Vec<char> = self.data.text.chars().collect();
+        let text = &self.data.text;
+        let offsets: Vec<_> = self.cursors.iter().map(|&cursor| offset_of(text, cursor)).collect();
+
+        let mut edited: Vec<usize> = Vec::with_capacity(offsets.len());
+        for &offset in offsets.iter().rev() {
+            let (cursor, delta) = edit(&mut chars, offset);
+            for later in &mut edited {
+                *later = (*later as isize + delta) as usize;
+            }
+            edited.push(cursor);
+        }
+
+        let text: String = chars.into_iter().collect();
+        self.cursors = edited.into_iter().rev().map(|offset| position_of(&text, offset)).collect();
+        self.cursors.dedup();
+
+        if text != self.data.text {
+            self.data.text = text.clone();
+            self.event_queue.emit_owned(CodeEditorEvent::UserModify(text));
+        }
+
+        self.scroll_to_cursor();
+        self.command_group.repaint();
+    }
+
+    /// Inserts text at every cursor.
+    pub fn insert_str(&mut self, text: &str) {
+        let inserted: Vec<char> = text.chars().collect();
+        self.edit(|chars, offset| {
+            chars.splice(offset..offset, inserted.iter().cloned());
+            (offset + inserted.len(), inserted.len() as isize)
+        });
+    }
+
+    /// Removes the character before every cursor.
+    fn backspace(&mut self) {
+        self.edit(|chars, offset| {
+            if offset > 0 {
+                chars.remove(offset - 1);
+                (offset - 1, -1)
+            } else {
+                (offset, 0)
+            }
+        });
+    }
+
+    /// Removes the character after every cursor.
+    fn delete(&mut self) {
+        self.edit(|chars, offset| {
+            if offset < chars.len() {
+                chars.remove(offset);
+                (offset, -1)
+            } else {
+                (offset, 0)
+            }
+        });
+    }
+
+    /// Breaks the line at every cursor, keeping the indentation of the line.
+    fn new_line(&mut self) {
+        self.edit(|chars, offset| {
+            let start = line_start(chars, offset);
+            let indent = chars[start..offset].iter().take_while(|c| **c == ' ' || **c == '\t');
+            let inserted: Vec<char> = std::iter::once('\n').chain(indent.cloned()).collect();
+            chars.splice(offset..offset, inserted.iter().cloned());
+            (offset + inserted.len(), inserted.len() as isize)
+        });
+    }
+
+    /// Inserts spaces at every cursor, up to the next tab stop.
+    fn indent(&mut self) {
+        let tab_width = self.data.tab_width.max(1);
+        self.edit(|chars, offset| {
+            let column = offset - line_start(chars, offset);
+            let spaces = tab_width - column % tab_width;
+            chars.splice(offset..offset, std::iter::repeat(' ').take(spaces));
+            (offset + spaces, spaces as isize)
+        });
+    }
+}
+
+impl<U, G> Widget for CodeEditorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        // code shouldn't be autocorrected
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: base::InputHints::TEXT.without_autocorrect(),
+                area: self.abs_rect(),
+            });
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
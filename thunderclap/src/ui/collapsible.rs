@@ -0,0 +1,505 @@
+//! Collapsible panel and accordion grouping.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, DisplayText, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{cell::Cell, rc::Rc},
+};
+
+/// Events emitted by a collapsible panel.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum CollapsibleEvent {
+    /// Emitted when the panel is expanded.
+    #[event_key(expand)]
+    Expand,
+    /// Emitted when the panel is collapsed.
+    #[event_key(collapse)]
+    Collapse,
+    /// Emitted when the mouse enters the header boundaries.
+    #[event_key(begin_hover)]
+    BeginHover(AbsolutePoint),
+    /// Emitted when the mouse leaves the header boundaries.
+    #[event_key(end_hover)]
+    EndHover(AbsolutePoint),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Groups collapsible panels such that at most one of them is expanded at a time.
+///
+/// Clones refer to the same group; give every panel of the accordion a clone.
+#[derive(Debug, Clone, Default)]
+pub struct AccordionGroup {
+    expanded: Rc<Cell<Option<u64>>>,
+    next_id: Rc<Cell<u64>>,
+}
+
+impl AccordionGroup {
+    /// Creates a new, empty accordion group.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Collapses whichever panel of the group is expanded.
+    pub fn collapse_all(&self) {
+        self.expanded.set(None);
+    }
+
+    fn register(&self) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+impl PartialEq for AccordionGroup {
+    fn eq(&self, other: &AccordionGroup) -> bool {
+        Rc::ptr_eq(&self.expanded, &other.expanded)
+    }
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+impl<U, G> ui::InteractiveWidget for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(RelativeRect::new(self.rect.origin, self.header.cast_unit()))
+    }
+
+    #[inline(always)]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        self.repaint();
+        match event {
+            ui::InteractionEvent::Released(_) => self.toggle(),
+            ui::InteractionEvent::BeginHover(pos) => {
+                self.event_queue.emit_owned(CollapsibleEvent::BeginHover(pos));
+            }
+            ui::InteractionEvent::EndHover(pos) => {
+                self.event_queue.emit_owned(CollapsibleEvent::EndHover(pos));
+            }
+            ui::InteractionEvent::Focus => {
+                self.event_queue.emit_owned(CollapsibleEvent::Focus);
+            }
+            ui::InteractionEvent::Blur => {
+                self.event_queue.emit_owned(CollapsibleEvent::Blur);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Panel with a clickable header which shows or hides its children.
+///
+/// Content is added by pushing widgets into the collapsible as a layout; the children are placed below the header,
+/// preserving their arrangement relative to each other.
+/// The panel resizes itself to fit the header (and the children, if expanded), so that parent layouts reflow.
+///
+/// Panels sharing an `AccordionGroup` behave as an accordion; expanding one collapses the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collapsible {
+    pub title: DisplayText,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub background: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub expanded: bool,
+    pub group: Option<AccordionGroup>,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Collapsible
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CollapsibleWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Collapsible
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Collapsible {
+            title: "".to_string().into(),
+            typeface: data.typography.button.clone(),
+            color: data.scheme.over_control_outset,
+            background: data.scheme.control_outset,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            expanded: false,
+            group: None,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> CollapsibleWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CollapsibleWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.sync_expanded();
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<CollapsibleWidget<U, G>, U>()
+                .bind(u_aux.window_queue()),
+        );
+
+        let painter = theme.collapsible();
+        let header = painter.size_hint(state::CollapsibleState {
+            rect: Default::default(),
+            data: data.clone(),
+            interaction: state::InteractionState::empty(),
+        });
+
+        let mut widget = CollapsibleWidgetBuilder {
+            rect: RelativeRect::new(Default::default(), header.cast_unit()),
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            header,
+            expanded: false,
+            member: None,
+        }
+        .build();
+
+        widget.sync_expanded();
+        widget
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::CollapsibleState> for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::CollapsibleState {
+        state::CollapsibleState {
+            rect: self.abs_convert_rect(RelativeRect::new(
+                self.rect.origin,
+                Size::new(self.rect.size.width, self.header.height).cast_unit(),
+            )),
+            data: self.data.clone(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CollapsibleWidget {
+        widget::MAX,
+
+        <CollapsibleEvent> EventQueue,
+        <Collapsible> State,
+        <state::CollapsibleState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            header: Size,
+            expanded: bool,
+            member: Option<(AccordionGroup, u64)>,
+        },
+    }
+}
+
+impl<U, G> CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Expands the panel, collapsing the rest of the accordion group (if any).
+    pub fn expand(&mut self) {
+        if !self.data.expanded {
+            self.data.expanded = true;
+        }
+    }
+
+    /// Collapses the panel.
+    pub fn collapse(&mut self) {
+        if self.data.expanded {
+            self.data.expanded = false;
+        }
+    }
+
+    /// Expands the panel if it is collapsed, and vice versa.
+    pub fn toggle(&mut self) {
+        let expanded = self.data.expanded;
+        self.data.expanded = !expanded;
+    }
+
+    /// Returns the rectangle of the header, in absolute coordinates.
+    pub fn header_rect(&self) -> AbsoluteRect {
+        self.derive_state().rect
+    }
+
+    /// Applies changes of `expanded` and `group` made to the data.
+    fn sync_expanded(&mut self) {
+        if self.member.as_ref().map(|(group, _)| group) != self.data.group.as_ref() {
+            self.member = self.data.group.clone().map(|group| {
+                let id = group.register();
+                (group, id)
+            });
+            // Joining a group counts as newly expanding if expanded.
+            self.expanded = false;
+        }
+
+        if self.data.expanded == self.expanded {
+            return;
+        }
+        self.expanded = self.data.expanded;
+
+        if let Some((ref group, id)) = self.member {
+            if self.expanded {
+                group.expanded.set(Some(id));
+            } else if group.expanded.get() == Some(id) {
+                group.expanded.set(None);
+            }
+        }
+
+        let visibility =
+            if self.expanded { base::Visibility::Normal } else { base::Visibility::None };
+        for (_, data) in &self.rects {
+            data.visibility.set(visibility);
+        }
+
+        self.dirty = true;
+        self.event_queue.emit_owned(if self.expanded {
+            CollapsibleEvent::Expand
+        } else {
+            CollapsibleEvent::Collapse
+        });
+    }
+
+    fn child_visibility(&self) -> base::Visibility {
+        if self.expanded {
+            base::Visibility::Normal
+        } else {
+            base::Visibility::None
+        }
+    }
+}
+
+impl<U, G> base::Layout for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ();
+
+    fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+        visibility.set(self.child_visibility());
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        // Another panel of the accordion has been expanded.
+        let superseded = match self.member {
+            Some((ref group, id)) => self.expanded && group.expanded.get() != Some(id),
+            None => false,
+        };
+        if superseded {
+            self.data.expanded = false;
+            self.sync_expanded();
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.header = self.painter.size_hint(self.derive_state());
+
+            let mut content: Option<AbsoluteRect> = None;
+            for (_, data) in &self.rects {
+                content = Some(content.map_or(data.rect, |content| content.union(&data.rect)));
+            }
+            let content = content.unwrap_or_default();
+
+            // Place the children as a whole directly below the header.
+            let abs_rect = self.abs_rect();
+            let offset =
+                AbsolutePoint::new(abs_rect.origin.x, abs_rect.origin.y + self.header.height)
+                    - content.origin;
+            if offset != Default::default() {
+                for (_, data) in &mut self.rects {
+                    data.rect = data.rect.translate(offset);
+                    data.evq.emit_owned(data.rect);
+                }
+            }
+
+            let mut size = Size::new(
+                self.rect.size.width.max(self.header.width).max(content.size.width),
+                self.header.height,
+            );
+            if self.expanded {
+                size.height += content.size.height;
+            }
+
+            if size != self.size() {
+                // Notifies the parent layout through `on_transform`.
+                self.set_size(size);
+            }
+
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
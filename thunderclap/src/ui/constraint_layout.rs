@@ -0,0 +1,450 @@
+//! Layout which places children by solving linear constraints between their edges.
+
+use {
+    crate::{base, draw, error, geom::*, ui},
+    cassowary::{strength, Constraint, Expression, Solver, Variable, WeightedRelation::*},
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::collections::HashSet,
+};
+
+/// Strength which the rectangle of the container is held at; it only yields to required constraints.
+const CONTAINER: f64 = strength::REQUIRED - 1.0;
+
+/// Solver variables of a rectangle (of a child of a `ConstraintLayout`, or of its container), in absolute
+/// coordinates, for building constraints with the operators of `cassowary`:
+///
+/// ```ignore
+/// use thunderclap::cassowary::{strength::*, WeightedRelation::*};
+///
+/// let container = layout.container();
+/// let label = ConstraintRect::new();
+/// let field = ConstraintRect::new();
+/// layout.push(Some(label), &mut label_widget);
+/// layout.push(Some(field), &mut field_widget);
+/// layout.add_constraints(vec![
+///     label.right() + 8.0 | EQ(REQUIRED) | field.left,
+///     field.right() | EQ(STRONG) | container.right(),
+/// ])?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstraintRect {
+    pub left: Variable,
+    pub top: Variable,
+    pub width: Variable,
+    pub height: Variable,
+}
+
+impl Default for ConstraintRect {
+    fn default() -> Self {
+        ConstraintRect::new()
+    }
+}
+
+impl ConstraintRect {
+    /// Creates a rectangle of new variables.
+    pub fn new() -> Self {
+        ConstraintRect {
+            left: Variable::new(),
+            top: Variable::new(),
+            width: Variable::new(),
+            height: Variable::new(),
+        }
+    }
+
+    /// Returns the expression of the right edge.
+    pub fn right(&self) -> Expression {
+        self.left + self.width
+    }
+
+    /// Returns the expression of the bottom edge.
+    pub fn bottom(&self) -> Expression {
+        self.top + self.height
+    }
+
+    /// Returns the expression of the horizontal center.
+    pub fn center_x(&self) -> Expression {
+        self.left + self.width / 2.0
+    }
+
+    /// Returns the expression of the vertical center.
+    pub fn center_y(&self) -> Expression {
+        self.top + self.height / 2.0
+    }
+
+    fn variables(&self) -> [Variable; 4] {
+        [self.left, self.top, self.width, self.height]
+    }
+}
+
+fn add_error(err: cassowary::AddConstraintError) -> error::ConstraintError {
+    match err {
+        cassowary::AddConstraintError::DuplicateConstraint => error::ConstraintError::Duplicate,
+        cassowary::AddConstraintError::UnsatisfiableConstraint => {
+            error::ConstraintError::Unsatisfiable
+        }
+        cassowary::AddConstraintError::InternalSolverError(err) => {
+            error::ConstraintError::Internal(err)
+        }
+    }
+}
+
+struct ChildData {
+    vars: ConstraintRect,
+    /// Keeps the size of the child non-negative.
+    bounds: [Constraint; 2],
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Layout which places children by solving linear equalities and inequalities (each with a strength) between the
+/// edges and sizes of the children and the container, e.g. to align labels across separate group boxes.
+///
+/// Each child is pushed with the `ConstraintRect` its constraints refer to; the container's own rectangle is
+/// `ConstraintLayoutWidget::container`. Children weakly keep their position, and keep their size at a medium
+/// strength, so that they stay as they are unless constrained otherwise.
+///
+/// The solver is incremental; changes to the container, to the sizes of children or to the constraints only
+/// re-place the children whose variables changed. Constraints referring to a removed child should be removed by the
+/// app.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstraintLayout {
+    /// Inset of the container rectangle from the edges of the layout.
+    pub padding: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ConstraintLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ConstraintLayoutWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for ConstraintLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        ConstraintLayout { padding: 0.0 }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, _u_aux: &mut U) -> ConstraintLayoutWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            ConstraintLayoutWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                }
+            }
+        };
+
+        let container = ConstraintRect::new();
+        let mut solver = Solver::new();
+        for &var in &container.variables() {
+            // fresh variables are always accepted
+            solver.add_edit_variable(var, CONTAINER).unwrap();
+        }
+
+        ConstraintLayoutWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            solver,
+            container,
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for ConstraintLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ConstraintLayoutWidget {
+        widget::MAX,
+
+        <ConstraintLayout> State,
+
+        {
+            solver: Solver,
+            container: ConstraintRect,
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            /// Whether the container rectangle has to be suggested to the solver again.
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> ConstraintLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the variables of the container rectangle, i.e. the layout inset by its `padding`.
+    #[inline]
+    pub fn container(&self) -> ConstraintRect {
+        self.container
+    }
+
+    /// Returns the variables of a child which has been pushed, by its layout ID.
+    pub fn variables(&self, id: u64) -> Option<ConstraintRect> {
+        self.rects.get(&id).map(|child| child.vars)
+    }
+
+    /// Adds a constraint, re-placing the affected children.
+    pub fn add_constraint(&mut self, constraint: Constraint) -> Result<(), error::ConstraintError> {
+        self.solver.add_constraint(constraint).map_err(add_error)?;
+        self.apply_changes();
+        Ok(())
+    }
+
+    /// Adds several constraints, re-placing the affected children once they're all added.
+    /// Stops at the first constraint which can't be added, keeping those added before it.
+    pub fn add_constraints(
+        &mut self,
+        constraints: impl IntoIterator<Item = Constraint>,
+    ) -> Result<(), error::ConstraintError> {
+        let result = constraints
+            .into_iter()
+            .try_for_each(|constraint| self.solver.add_constraint(constraint).map_err(add_error));
+        self.apply_changes();
+        result
+    }
+
+    /// Removes a constraint which has been added, re-placing the affected children.
+    pub fn remove_constraint(
+        &mut self,
+        constraint: &Constraint,
+    ) -> Result<(), error::ConstraintError> {
+        self.solver.remove_constraint(constraint).map_err(|err| match err {
+            cassowary::RemoveConstraintError::UnknownConstraint => error::ConstraintError::Unknown,
+            cassowary::RemoveConstraintError::InternalSolverError(err) => {
+                error::ConstraintError::Internal(err)
+            }
+        })?;
+        self.apply_changes();
+        Ok(())
+    }
+
+    /// Returns `true` if a constraint has been added.
+    #[inline]
+    pub fn has_constraint(&self, constraint: &Constraint) -> bool {
+        self.solver.has_constraint(constraint)
+    }
+
+    fn suggest(&mut self, var: Variable, value: f32) {
+        // fails only for the variables of a removed child, if they're shared with another child
+        let _ = self.solver.suggest_value(var, value as f64);
+    }
+
+    /// Moves the children whose variables have changed since the last time.
+    fn apply_changes(&mut self) {
+        let changed: HashSet<Variable> =
+            self.solver.fetch_changes().iter().map(|&(var, _)| var).collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        let solver = &self.solver;
+        for (_, child) in &mut self.rects {
+            if !child.vars.variables().iter().any(|var| changed.contains(var)) {
+                continue;
+            }
+
+            let value = |var: Variable| solver.get_value(var) as f32;
+            let rect = AbsoluteRect::new(
+                AbsolutePoint::new(value(child.vars.left), value(child.vars.top)),
+                Size::new(value(child.vars.width), value(child.vars.height)).cast_unit(),
+            );
+            if rect != child.rect {
+                child.rect = rect;
+                child.evq.emit_owned(rect);
+            }
+        }
+    }
+
+    /// Removes the edit variables and size bounds of a removed child from the solver.
+    fn forget(&mut self, child: &ChildData) {
+        for var in &child.vars.variables() {
+            let _ = self.solver.remove_edit_variable(*var);
+        }
+        for constraint in &child.bounds {
+            let _ = self.solver.remove_constraint(constraint);
+        }
+        self.apply_changes();
+    }
+
+    fn update_layout(&mut self) {
+        let padding = self.data.padding;
+        let container = self.abs_rect().inflate(-padding, -padding);
+        let vars = self.container;
+        self.suggest(vars.left, container.min_x());
+        self.suggest(vars.top, container.min_y());
+        self.suggest(vars.width, container.size.width.max(0.0));
+        self.suggest(vars.height, container.size.height.max(0.0));
+        self.apply_changes();
+    }
+}
+
+impl<U, G> base::Layout for ConstraintLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ConstraintRect;
+
+    fn push(&mut self, data: Option<ConstraintRect>, child: &mut impl base::LayableWidget) {
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+        let vars = data.unwrap_or_default();
+        let bounds =
+            [vars.width | GE(strength::REQUIRED) | 0.0, vars.height | GE(strength::REQUIRED) | 0.0];
+        for constraint in &bounds {
+            // may already have been added if the variables are shared with another child
+            let _ = self.solver.add_constraint(constraint.clone());
+        }
+        for (&var, &strength) in vars.variables().iter().zip(&[
+            strength::WEAK,
+            strength::WEAK,
+            strength::MEDIUM,
+            strength::MEDIUM,
+        ]) {
+            let _ = self.solver.add_edit_variable(var, strength);
+        }
+
+        self.rects.insert(
+            id,
+            ChildData {
+                vars,
+                bounds,
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+
+        self.suggest(vars.left, rect.min_x());
+        self.suggest(vars.top, rect.min_y());
+        self.suggest(vars.width, rect.size.width);
+        self.suggest(vars.height, rect.size.height);
+        self.apply_changes();
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.shift_remove(&id)) {
+            self.forget(&data);
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for ConstraintLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        let mut removals = Vec::new();
+        let mut suggestions = Vec::new();
+        for (_, data) in &mut self.rects {
+            if !data.drop_listener.peek().is_empty() {
+                removals.push(data.id);
+                continue;
+            }
+
+            if let Some(new_ev) = data.evq.retrieve_newest() {
+                if new_ev != data.rect {
+                    // the child moved or resized itself
+                    data.rect = new_ev;
+                    suggestions.push((data.vars, new_ev));
+                }
+            }
+        }
+        for removal in removals {
+            if let Some(data) = self.rects.shift_remove(&removal) {
+                self.forget(&data);
+            }
+        }
+        for (vars, rect) in suggestions {
+            self.suggest(vars.left, rect.min_x());
+            self.suggest(vars.top, rect.min_y());
+            self.suggest(vars.width, rect.size.width);
+            self.suggest(vars.height, rect.size.height);
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.dirty = false;
+        } else {
+            self.apply_changes();
+        }
+    }
+}
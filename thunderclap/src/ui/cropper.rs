@@ -0,0 +1,505 @@
+//! Image cropping widget.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, GraphicsDisplay, Point, Rect, ResourceReference, Size, Vector,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a cropper.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum CropperEvent {
+    /// Emitted while the crop rectangle is being dragged, with the crop in image pixels.
+    #[event_key(change)]
+    Change(Rect),
+    /// Emitted when the user finishes dragging the crop rectangle, with the crop rounded to whole image pixels.
+    #[event_key(commit)]
+    Commit(Rect),
+}
+
+/// Part of the crop rectangle which is being dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CropHandle {
+    /// The entire rectangle is moved.
+    Move,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl CropHandle {
+    fn moves_left(self) -> bool {
+        match self {
+            CropHandle::Left | CropHandle::TopLeft | CropHandle::BottomLeft => true,
+            _ => false,
+        }
+    }
+
+    fn moves_right(self) -> bool {
+        match self {
+            CropHandle::Right | CropHandle::TopRight | CropHandle::BottomRight => true,
+            _ => false,
+        }
+    }
+
+    fn moves_top(self) -> bool {
+        match self {
+            CropHandle::Top | CropHandle::TopLeft | CropHandle::TopRight => true,
+            _ => false,
+        }
+    }
+
+    fn moves_bottom(self) -> bool {
+        match self {
+            CropHandle::Bottom | CropHandle::BottomLeft | CropHandle::BottomRight => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the largest crop rectangle with a given aspect ratio (width / height) centered within an image.
+pub fn fit_crop(image_size: Size, aspect_ratio: Option<f32>) -> Rect {
+    let size = match aspect_ratio {
+        Some(ratio) if ratio > 0.0 && image_size.height > 0.0 => {
+            if image_size.width / image_size.height > ratio {
+                Size::new(image_size.height * ratio, image_size.height)
+            } else {
+                Size::new(image_size.width, image_size.width / ratio)
+            }
+        }
+        _ => image_size,
+    };
+    Rect::new(
+        Point::new((image_size.width - size.width) / 2.0, (image_size.height - size.height) / 2.0),
+        size,
+    )
+}
+
+/// Computes the result of dragging a `handle` of the crop rectangle `start` by `delta` (all in image pixels).
+fn drag_crop(
+    start: Rect,
+    handle: CropHandle,
+    delta: Vector,
+    image_size: Size,
+    min_size: f32,
+    aspect_ratio: Option<f32>,
+) -> Rect {
+    let (width, height) = (image_size.width, image_size.height);
+    let min_size = min_size.min(width).min(height);
+
+    if handle == CropHandle::Move {
+        let x = (start.origin.x + delta.x).max(0.0).min(width - start.size.width);
+        let y = (start.origin.y + delta.y).max(0.0).min(height - start.size.height);
+        return Rect::new(Point::new(x, y), start.size);
+    }
+
+    let (mut l, mut t, mut r, mut b) = (start.min_x(), start.min_y(), start.max_x(), start.max_y());
+    if handle.moves_left() {
+        l = (l + delta.x).max(0.0).min(r - min_size);
+    }
+    if handle.moves_right() {
+        r = (r + delta.x).min(width).max(l + min_size);
+    }
+    if handle.moves_top() {
+        t = (t + delta.y).max(0.0).min(b - min_size);
+    }
+    if handle.moves_bottom() {
+        b = (b + delta.y).min(height).max(t + min_size);
+    }
+
+    if let Some(ratio) = aspect_ratio.filter(|ratio| *ratio > 0.0) {
+        let horizontal = handle.moves_left() || handle.moves_right();
+        let vertical = handle.moves_top() || handle.moves_bottom();
+
+        if horizontal && vertical {
+            // Corner; shrink whichever dimension is too large, keeping the opposite corner fixed.
+            let (mut w, mut h) = (r - l, b - t);
+            if w / h > ratio {
+                w = h * ratio;
+            } else {
+                h = w / ratio;
+            }
+            if handle.moves_left() {
+                l = r - w;
+            } else {
+                r = l + w;
+            }
+            if handle.moves_top() {
+                t = b - h;
+            } else {
+                b = t + h;
+            }
+        } else if horizontal {
+            // Vertical edge; grow the height about the center, limited by the image height.
+            let mut w = r - l;
+            let h = (w / ratio).min(height);
+            w = h * ratio;
+            if handle.moves_left() {
+                l = r - w;
+            } else {
+                r = l + w;
+            }
+            let center = start.min_y() + start.size.height / 2.0;
+            t = (center - h / 2.0).max(0.0).min(height - h);
+            b = t + h;
+        } else {
+            // Horizontal edge; grow the width about the center, limited by the image width.
+            let mut h = b - t;
+            let w = (h * ratio).min(width);
+            h = w / ratio;
+            if handle.moves_top() {
+                t = b - h;
+            } else {
+                b = t + h;
+            }
+            let center = start.min_x() + start.size.width / 2.0;
+            l = (center - w / 2.0).max(0.0).min(width - w);
+            r = l + w;
+        }
+    }
+
+    Rect::new(Point::new(l, t), Size::new(r - l, b - t))
+}
+
+/// Generates an unbound terminal which handles dragging of the crop rectangle.
+pub fn cropper_handler<U, G>() -> vg::UnboundQueueHandler<CropperWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        CropperWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let handle = if obj.data.disabled { None } else { obj.handle_at(event.get().0) };
+            if let (Some(handle), Some((pos, _, _))) = (
+                handle,
+                event.with(|(_, button, _)| {
+                    handle.is_some() && *button == base::MouseButton::Left
+                }),
+            ) {
+                obj.drag = Some((handle, obj.image_point(*pos), obj.data.crop));
+                obj.interaction.insert(state::InteractionState::PRESSED);
+                obj.repaint();
+            }
+        }
+
+        mouse_move => {
+            if let Some((pos, _)) = event.with(|_| obj.drag.is_some()) {
+                let pos = obj.image_point(*pos);
+                if let Some((handle, start, crop)) = obj.drag {
+                    obj.data.crop = drag_crop(
+                        crop,
+                        handle,
+                        pos - start,
+                        obj.data.image_size,
+                        obj.data.min_size,
+                        obj.data.aspect_ratio,
+                    );
+                    let crop = obj.data.crop;
+                    obj.event_queue.emit_owned(CropperEvent::Change(crop));
+                }
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.drag.is_some() && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.drag = None;
+                obj.interaction.remove(state::InteractionState::PRESSED);
+                obj.repaint();
+                let crop = obj.pixel_crop();
+                obj.event_queue.emit_owned(CropperEvent::Commit(crop));
+            }
+        }
+    }
+}
+
+/// Displays an image with a draggable, resizable crop rectangle; commonly used for avatar uploads.
+///
+/// The crop rectangle is expressed in image pixels. The image is scaled to fit within the widget,
+/// preserving its aspect ratio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cropper {
+    /// Image resource to display, if loaded.
+    pub image: Option<ResourceReference>,
+    /// Size of the image in pixels.
+    pub image_size: Size,
+    /// Crop rectangle in image pixels.
+    pub crop: Rect,
+    /// If set, the crop rectangle is locked to this aspect ratio (width / height).
+    pub aspect_ratio: Option<f32>,
+    /// Minimum width and height of the crop rectangle in image pixels.
+    pub min_size: f32,
+    /// Whether rule-of-thirds guides are drawn within the crop rectangle.
+    pub guides: bool,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color covering the parts of the image outside the crop rectangle.
+    pub scrim: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Cropper
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CropperWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Cropper
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Cropper {
+            image: None,
+            image_size: Size::zero(),
+            crop: Rect::zero(),
+            aspect_ratio: None,
+            min_size: 16.0,
+            guides: true,
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            scrim: Color::new(0.0, 0.0, 0.0, 0.5),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(mut self, theme: &dyn draw::Theme, u_aux: &mut U) -> CropperWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        if self.crop.size.is_empty_or_negative() {
+            self.crop = fit_crop(self.image_size, self.aspect_ratio);
+        }
+
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CropperWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("crop", cropper_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.cropper();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::CropperState {
+                    rect: Default::default(),
+                    image: Default::default(),
+                    crop: Default::default(),
+                    data: data.clone(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        CropperWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            drag: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::CropperState> for CropperWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::CropperState {
+        state::CropperState {
+            rect: self.abs_rect(),
+            image: self.image_rect(),
+            crop: self.screen_crop(),
+            data: self.data.clone(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct CropperWidget {
+        widget::MAX,
+
+        <CropperEvent> EventQueue,
+        <Cropper> State,
+        <state::CropperState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            drag: Option<(CropHandle, Point, Rect)>,
+        },
+    }
+}
+
+impl<U, G> CropperWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Resets the crop rectangle to the largest rectangle (respecting the aspect ratio) centered within the image.
+    pub fn reset(&mut self) {
+        self.data.crop = fit_crop(self.data.image_size, self.data.aspect_ratio);
+    }
+
+    /// Returns the crop rectangle rounded to whole image pixels.
+    pub fn pixel_crop(&self) -> Rect {
+        self.data.crop.round()
+    }
+
+    /// Returns the rectangle the image is displayed in, in absolute coordinates.
+    pub fn image_rect(&self) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        let image_size = self.data.image_size;
+        if image_size.is_empty_or_negative() {
+            return bounds;
+        }
+
+        let scale =
+            (bounds.size.width / image_size.width).min(bounds.size.height / image_size.height);
+        let size = image_size * scale;
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                bounds.origin.x + (bounds.size.width - size.width) / 2.0,
+                bounds.origin.y + (bounds.size.height - size.height) / 2.0,
+            ),
+            size.cast_unit(),
+        )
+    }
+
+    /// Returns the crop rectangle in absolute coordinates.
+    pub fn screen_crop(&self) -> AbsoluteRect {
+        let image = self.image_rect();
+        let scale = self.scale();
+        let crop = self.data.crop;
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                image.origin.x + crop.origin.x * scale,
+                image.origin.y + crop.origin.y * scale,
+            ),
+            (crop.size * scale).cast_unit(),
+        )
+    }
+
+    /// Returns the part of the crop rectangle at a given point, if any.
+    pub fn handle_at(&self, point: AbsolutePoint) -> Option<CropHandle> {
+        const GRAB: f32 = 6.0;
+
+        let crop = self.screen_crop();
+        if !crop.inflate(GRAB, GRAB).contains(point) {
+            return None;
+        }
+
+        let near_left = (point.x - crop.min_x()).abs() <= GRAB;
+        let near_right = (point.x - crop.max_x()).abs() <= GRAB;
+        let near_top = (point.y - crop.min_y()).abs() <= GRAB;
+        let near_bottom = (point.y - crop.max_y()).abs() <= GRAB;
+
+        Some(match (near_left, near_right, near_top, near_bottom) {
+            (true, _, true, _) => CropHandle::TopLeft,
+            (_, true, true, _) => CropHandle::TopRight,
+            (true, _, _, true) => CropHandle::BottomLeft,
+            (_, true, _, true) => CropHandle::BottomRight,
+            (true, _, _, _) => CropHandle::Left,
+            (_, true, _, _) => CropHandle::Right,
+            (_, _, true, _) => CropHandle::Top,
+            (_, _, _, true) => CropHandle::Bottom,
+            _ => CropHandle::Move,
+        })
+    }
+
+    /// Ratio of displayed size to image pixels.
+    fn scale(&self) -> f32 {
+        if self.data.image_size.width > 0.0 {
+            self.image_rect().size.width / self.data.image_size.width
+        } else {
+            1.0
+        }
+    }
+
+    /// Converts an absolute point into image pixels.
+    fn image_point(&self, point: AbsolutePoint) -> Point {
+        let image = self.image_rect();
+        let scale = self.scale();
+        Point::new((point.x - image.origin.x) / scale, (point.y - image.origin.y) / scale)
+    }
+}
+
+impl<U, G> Widget for CropperWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,584 @@
+//! Calendar month grid for picking a date.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::fmt,
+};
+
+/// English names of the months, starting from January.
+pub const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Abbreviated English names of the weekdays, starting from Monday.
+pub const WEEKDAY_NAMES: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+/// A date in the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    /// Month of the year, from `1` to `12`.
+    pub month: u32,
+    /// Day of the month, from `1`.
+    pub day: u32,
+}
+
+impl Date {
+    /// Creates a date, returning `None` if the month or day is out of range.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if (1..=12).contains(&month) && day >= 1 && day <= Date::days_in_month(year, month) {
+            Some(Date { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the current date in UTC.
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Date::from_days((seconds / 86400) as i64)
+    }
+
+    #[inline]
+    pub fn is_leap_year(year: i32) -> bool {
+        (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+    }
+
+    pub fn days_in_month(year: i32, month: u32) -> u32 {
+        match month {
+            2 if Date::is_leap_year(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn to_days(self) -> i64 {
+        let (month, day) = (self.month as i64, self.day as i64);
+        let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+        let era = (if year >= 0 { year } else { year - 399 }) / 400;
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the date a given number of days after 1970-01-01.
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = (if days >= 0 { days } else { days - 146_096 }) / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Date { year: year as i32, month: month as u32, day: day as u32 }
+    }
+
+    /// Returns the day of the week, in days since Monday (i.e. Monday is `0` and Sunday is `6`).
+    #[inline]
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.to_days() + 3).rem_euclid(7) as u32
+    }
+
+    #[inline]
+    pub fn add_days(self, days: i64) -> Self {
+        Date::from_days(self.to_days() + days)
+    }
+
+    /// Offsets the month, clamping the day to the length of the resulting month.
+    pub fn add_months(self, months: i32) -> Self {
+        let index = self.year as i64 * 12 + self.month as i64 - 1 + months as i64;
+        let (year, month) = (index.div_euclid(12) as i32, index.rem_euclid(12) as u32 + 1);
+        Date { year, month, day: self.day.min(Date::days_in_month(year, month)) }
+    }
+
+    /// Returns the first day of the month.
+    #[inline]
+    pub fn first_of_month(self) -> Self {
+        Date { day: 1, ..self }
+    }
+}
+
+impl fmt::Display for Date {
+    /// Formats the date as ISO 8601 (`YYYY-MM-DD`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Events emitted by a date picker.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateEvent {
+    /// Emitted when a date has been selected by the user.
+    #[event_key(selected)]
+    Selected(Date),
+    /// Emitted when the shown month has changed, with the year and month.
+    #[event_key(navigate)]
+    Navigate(i32, u32),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Navigation buttons of a date picker, in the order they appear.
+const NAVIGATION_MONTHS: [i32; 4] = [-12, -1, 1, 12];
+
+/// Generates an unbound terminal which handles date selection and navigation.
+pub fn date_picker_handler<U, G>(
+) -> vg::UnboundQueueHandler<DatePickerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        DatePickerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.focus();
+
+                let navigation = obj.navigation_rects();
+                if let Some(i) = navigation.iter().position(|rect| rect.contains(*pos)) {
+                    obj.navigate(NAVIGATION_MONTHS[i]);
+                } else if let Some(date) = obj.day_at(*pos) {
+                    if obj.is_enabled(date) {
+                        obj.select(date);
+                    }
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.day_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, modifiers)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if !obj.interaction.contains(state::InteractionState::FOCUS_VISIBLE) {
+                    obj.interaction.insert(state::InteractionState::FOCUS_VISIBLE);
+                    obj.command_group.repaint();
+                }
+
+                let forward = if obj.data.locale.right_to_left { -1 } else { 1 };
+                let step = match key {
+                    base::KeyInput::Left => Some(-forward),
+                    base::KeyInput::Right => Some(forward),
+                    base::KeyInput::Up => Some(-7),
+                    base::KeyInput::Down => Some(7),
+                    _ => None,
+                };
+                let months = match key {
+                    base::KeyInput::PageUp => Some(if modifiers.shift { -12 } else { -1 }),
+                    base::KeyInput::PageDown => Some(if modifiers.shift { 12 } else { 1 }),
+                    _ => None,
+                };
+
+                let origin = obj.data.selected.unwrap_or_else(|| obj.shown_month());
+                let date = match (step, months) {
+                    (Some(step), _) => Some(origin.add_days(step)),
+                    (_, Some(months)) => Some(origin.add_months(months)),
+                    _ => None,
+                };
+                if let Some(date) = date {
+                    obj.select(obj.clamp(date));
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(DateEvent::Blur);
+            }
+        }
+    }
+}
+
+/// Month grid from which a date can be picked, with buttons to navigate by month and by year.
+///
+/// When focused, the arrow keys move the selection by day and by week, and page up/down by month
+/// (or by year, while shift is held).
+///
+/// Names and numbers are formatted according to `locale`, and the grid is mirrored for right-to-left locales.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatePicker {
+    pub selected: Option<Date>,
+    /// Year of the shown month.
+    pub year: i32,
+    /// Shown month, from `1` to `12`.
+    pub month: u32,
+    /// Earliest date which can be selected.
+    pub min: Option<Date>,
+    /// Latest date which can be selected.
+    pub max: Option<Date>,
+    /// Date marked as the current date, if any.
+    pub today: Option<Date>,
+    /// Day which weeks start on, in days since Monday.
+    pub first_weekday: u32,
+    pub locale: i18n::Locale,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the selected date.
+    pub highlight: Color,
+    /// Color of the text of the selected date.
+    pub over_highlight: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for DatePicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = DatePickerWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for DatePicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        let today = Date::today();
+        let locale = i18n::locale();
+        DatePicker {
+            selected: None,
+            year: today.year,
+            month: today.month,
+            min: None,
+            max: None,
+            today: Some(today),
+            first_weekday: locale.first_weekday,
+            locale,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            highlight: data.scheme.primary,
+            over_highlight: data.scheme.over_primary,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> DatePickerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            DatePickerWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("date_picker", date_picker_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.date_picker();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::DatePickerState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    title: Default::default(),
+                    navigation: Default::default(),
+                    navigation_enabled: [true; 4],
+                    weekdays: Vec::new(),
+                    days: Vec::new(),
+                    hovered: None,
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        DatePickerWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            hovered: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::DatePickerState> for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::DatePickerState {
+        let shown = self.shown_month();
+        let first_shown = self.first_shown();
+        let days = (0..42)
+            .map(|i| {
+                let date = first_shown.add_days(i);
+                state::DayCellState {
+                    date,
+                    rect: self.cell_rect(self.column(i as usize % 7), i as usize / 7 + 2),
+                    in_month: date.year == shown.year && date.month == shown.month,
+                    enabled: self.is_enabled(date),
+                }
+            })
+            .collect();
+
+        let mut navigation_enabled = [true; 4];
+        for (enabled, months) in navigation_enabled.iter_mut().zip(NAVIGATION_MONTHS.iter()) {
+            *enabled = self.can_navigate(*months);
+        }
+
+        let title = self.cell_rect(2, 0);
+        state::DatePickerState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            title: AbsoluteRect::new(
+                title.origin,
+                Size::new(title.size.width * 3.0, title.size.height).cast_unit(),
+            ),
+            navigation: self.navigation_rects(),
+            navigation_enabled,
+            weekdays: (0..7)
+                .map(|i| {
+                    ((self.data.first_weekday + i as u32) % 7, self.cell_rect(self.column(i), 1))
+                })
+                .collect(),
+            days,
+            hovered: self.hovered,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct DatePickerWidget {
+        widget::MAX,
+
+        <DateEvent> EventQueue,
+        <DatePicker> State,
+        <state::DatePickerState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            hovered: Option<Date>,
+        },
+    }
+}
+
+impl<U, G> DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Selects a date (regardless of `min` and `max`) and shows its month, emitting `DateEvent::Selected`.
+    pub fn select(&mut self, date: Date) {
+        self.data.selected = Some(date);
+        self.show(date);
+        self.event_queue.emit_owned(DateEvent::Selected(date));
+    }
+
+    /// Shows the month of a given date.
+    pub fn show(&mut self, date: Date) {
+        if (date.year, date.month) != (self.data.year, self.data.month) {
+            self.data.year = date.year;
+            self.data.month = date.month;
+            self.event_queue.emit_owned(DateEvent::Navigate(date.year, date.month));
+        }
+    }
+
+    /// Offsets the shown month, unless the resulting month lies entirely outside of `min` and `max`.
+    pub fn navigate(&mut self, months: i32) {
+        if self.can_navigate(months) {
+            self.show(self.shown_month().add_months(months));
+        }
+    }
+
+    /// Returns whether a date lies within `min` and `max`.
+    pub fn is_enabled(&self, date: Date) -> bool {
+        self.data.min.map_or(true, |min| date >= min)
+            && self.data.max.map_or(true, |max| date <= max)
+    }
+
+    /// Returns the date whose cell contains a given point, if any.
+    pub fn day_at(&self, point: AbsolutePoint) -> Option<Date> {
+        let bounds = self.abs_rect();
+        if !bounds.contains(point) {
+            return None;
+        }
+
+        let cell = self.cell_size();
+        let column = ((point.x - bounds.min_x()) / cell.width) as i64;
+        let row = ((point.y - bounds.min_y()) / cell.height) as i64 - 2;
+        if row < 0 || column > 6 {
+            return None;
+        }
+        let column = self.column(column as usize) as i64;
+        Some(self.first_shown().add_days(row * 7 + column))
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(DateEvent::Focus);
+        }
+    }
+
+    fn clamp(&self, date: Date) -> Date {
+        let date = self.data.min.map_or(date, |min| date.max(min));
+        self.data.max.map_or(date, |max| date.min(max))
+    }
+
+    fn can_navigate(&self, months: i32) -> bool {
+        let target = self.shown_month().add_months(months);
+        let last = target.add_months(1).add_days(-1);
+        self.data.min.map_or(true, |min| last >= min)
+            && self.data.max.map_or(true, |max| target <= max)
+    }
+
+    /// First day of the shown month.
+    fn shown_month(&self) -> Date {
+        Date::new(self.data.year, self.data.month.max(1).min(12), 1).unwrap()
+    }
+
+    /// Date in the top-left cell of the grid.
+    fn first_shown(&self) -> Date {
+        let first = self.shown_month();
+        let offset = (first.weekday() + 7 - self.data.first_weekday % 7) % 7;
+        first.add_days(-(offset as i64))
+    }
+
+    /// Maps a column in reading order to its position from the left, mirroring it for right-to-left locales.
+    fn column(&self, column: usize) -> usize {
+        if self.data.locale.right_to_left {
+            6 - column
+        } else {
+            column
+        }
+    }
+
+    /// The grid consists of 8 rows; the title, the weekdays and 6 weeks.
+    fn cell_size(&self) -> Size {
+        let bounds = self.abs_rect();
+        Size::new(bounds.size.width / 7.0, bounds.size.height / 8.0)
+    }
+
+    fn cell_rect(&self, column: usize, row: usize) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        let cell = self.cell_size();
+        AbsoluteRect::new(
+            bounds.origin
+                + Size::new(column as f32 * cell.width, row as f32 * cell.height).cast_unit(),
+            cell.cast_unit(),
+        )
+    }
+
+    /// Navigation buttons in the title row; previous year, previous month, next month and next year.
+    fn navigation_rects(&self) -> [AbsoluteRect; 4] {
+        [
+            self.cell_rect(self.column(0), 0),
+            self.cell_rect(self.column(1), 0),
+            self.cell_rect(self.column(5), 0),
+            self.cell_rect(self.column(6), 0),
+        ]
+    }
+}
+
+impl<U, G> Widget for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
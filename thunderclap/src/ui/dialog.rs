@@ -0,0 +1,396 @@
+//! Modal dialog widget.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{self, Color, DisplayCommand, GraphicsDisplay, Rect},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a dialog.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum DialogEvent {
+    /// Emitted when the dialog is closed by accepting it.
+    #[event_key(accepted)]
+    Accepted,
+    /// Emitted when the dialog is closed by rejecting it (including dismissal).
+    #[event_key(rejected)]
+    Rejected,
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Generates an unbound terminal which closes the dialog when dismissed by the user.
+/// A dialog is dismissed by pressing the escape key or pressing outside the content panel.
+pub fn dialog_handler<U, G>() -> vg::UnboundQueueHandler<DialogWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        DialogWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            // Presses outside the panel have already been consumed by the input grab, unless it is transparent.
+            let (pos, _, _) = event.get();
+            if obj.data.open
+                && obj.data.dismissable
+                && !obj.data.input_transparent
+                && !obj.panel_rect().contains(*pos)
+            {
+                obj.reject();
+            }
+        }
+
+        escape => {
+            if event
+                .with(|stage| {
+                    obj.data.open && obj.data.dismissable && *stage == base::EscapeStage::Dialog
+                })
+                .is_some()
+            {
+                obj.reject();
+            }
+        }
+    }
+}
+
+/// Modal overlay which blocks input to the rest of the window and centers its children in a content panel.
+///
+/// Content is added by pushing widgets into the dialog as a layout.
+/// For the overlay to appear above other widgets, the dialog should be the last widget in the tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dialog {
+    pub open: bool,
+    pub scrim: Color,
+    pub background: Color,
+    pub padding: f32,
+    pub dismissable: bool,
+    /// Whether input outside the content panel passes through the scrim to the widgets beneath it.
+    pub input_transparent: bool,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Dialog
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = DialogWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Dialog
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Dialog {
+            open: false,
+            scrim: Color::new(0.0, 0.0, 0.0, 0.4),
+            background: data.scheme.background,
+            padding: 15.0,
+            dismissable: true,
+            input_transparent: false,
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> DialogWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            DialogWidget<U, G> as obj,
+            U as aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.sync_open(aux);
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("dismiss", dialog_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.dialog();
+
+        DialogWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+            painter,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            content: Default::default(),
+            grab: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::DialogState> for DialogWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::DialogState {
+        state::DialogState { rect: self.abs_rect(), panel: self.panel_rect(), data: *self.data }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct DialogWidget {
+        widget::MAX,
+
+        <DialogEvent> EventQueue,
+        <Dialog> State,
+        <state::DialogState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            content: AbsoluteRect,
+            grab: Option<base::InputGrab>,
+        },
+    }
+}
+
+impl<U, G> DialogWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Opens the dialog.
+    pub fn open(&mut self) {
+        self.data.open = true;
+    }
+
+    /// Closes the dialog and emits `DialogEvent::Accepted`.
+    pub fn accept(&mut self) {
+        if self.data.open {
+            self.data.open = false;
+            self.event_queue.emit_owned(DialogEvent::Accepted);
+        }
+    }
+
+    /// Closes the dialog and emits `DialogEvent::Rejected`.
+    pub fn reject(&mut self) {
+        if self.data.open {
+            self.data.open = false;
+            self.event_queue.emit_owned(DialogEvent::Rejected);
+        }
+    }
+
+    /// Returns the rectangle of the content panel, in absolute coordinates.
+    pub fn panel_rect(&self) -> AbsoluteRect {
+        self.content.inflate(self.data.padding, self.data.padding)
+    }
+
+    fn sync_open(&mut self, aux: &mut U) {
+        if let Some(ref grab) = self.grab {
+            grab.set_input_transparent(self.data.input_transparent);
+        }
+
+        if self.data.open == self.grab.is_some() {
+            return;
+        }
+
+        let visibility = if self.data.open {
+            let grab = aux.input_grabs().grab(self.panel_rect());
+            grab.set_input_transparent(self.data.input_transparent);
+            self.grab = Some(grab);
+            // Blur anything focused beneath the dialog so that it stops receiving key input.
+            aux.window_queue_mut().emit_owned(base::WindowEvent::ClearFocus);
+            aux.record_metric(base::Metric::DialogOpened);
+            base::Visibility::Normal
+        } else {
+            self.grab = None;
+            aux.record_metric(base::Metric::DialogClosed);
+            base::Visibility::None
+        };
+
+        for (_, data) in &self.rects {
+            data.visibility.set(visibility);
+        }
+    }
+
+    fn child_visibility(&self) -> base::Visibility {
+        if self.data.open {
+            base::Visibility::Normal
+        } else {
+            base::Visibility::None
+        }
+    }
+}
+
+impl<U, G> base::Layout for DialogWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ();
+
+    fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+        visibility.set(self.child_visibility());
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for DialogWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            let mut content: Option<AbsoluteRect> = None;
+            for (_, data) in &self.rects {
+                content = Some(content.map_or(data.rect, |content| content.union(&data.rect)));
+            }
+            let content = content.unwrap_or_default();
+
+            // Center the children as a whole, preserving their arrangement relative to each other.
+            let offset = (display::center(content.size.cast_unit(), self.abs_rect().cast_unit())
+                - content.origin.cast_unit())
+            .cast_unit();
+            if offset != Default::default() {
+                for (_, data) in &mut self.rects {
+                    data.rect = data.rect.translate(offset);
+                    data.evq.emit_owned(data.rect);
+                }
+            }
+
+            self.content = content.translate(offset);
+            if let Some(ref grab) = self.grab {
+                grab.set_region(self.panel_rect());
+            }
+
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
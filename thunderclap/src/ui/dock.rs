@@ -0,0 +1,1310 @@
+//! Docking panel system.
+
+use {
+    super::split_pane::split_rect,
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        error::DockError,
+        geom::*,
+        history,
+        ui::{self, Orientation, SplitSide},
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{
+            self, Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Point, Rect,
+            Size, TextDisplayItem,
+        },
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Distance the pointer has to move before a pressed tab starts being dragged.
+const DRAG_THRESHOLD: f32 = 5.0;
+/// Width of the band along the edges of the dock which docks panels to the root.
+const ROOT_EDGE: f32 = 24.0;
+/// Fraction of a node taken by a panel docked to one of its edges.
+const EDGE_SPLIT: f32 = 0.3;
+
+/// Events emitted by a dock.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum DockEvent {
+    /// Emitted when a tab is activated by the user, with the panel key.
+    #[event_key(activate)]
+    Activate(String),
+    /// Emitted when a panel has been dragged somewhere else by the user (docked, tabbed or floated), with the panel key.
+    #[event_key(relocate)]
+    Relocate(String),
+    /// Emitted when the user has finished dragging a divider.
+    #[event_key(resize)]
+    Resize,
+}
+
+/// Edge or center of a region which a panel can be docked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DockZone {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Adds the panel as a tab.
+    Center,
+}
+
+/// Node in the tree of docked panels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockNode {
+    /// Panels sharing an area as tabs, of which only the active panel is shown.
+    Tabs { panels: Vec<String>, active: usize },
+    /// Two nodes separated by a draggable divider.
+    Split {
+        orientation: Orientation,
+        /// Position of the divider as a fraction of the available length.
+        split: f32,
+        first: Box<DockNode>,
+        second: Box<DockNode>,
+    },
+}
+
+impl DockNode {
+    fn tab(key: String) -> Self {
+        DockNode::Tabs { panels: vec![key], active: 0 }
+    }
+
+    /// Returns `true` if a panel is docked within this node.
+    pub fn contains(&self, key: &str) -> bool {
+        match self {
+            DockNode::Tabs { panels, .. } => panels.iter().any(|panel| panel == key),
+            DockNode::Split { first, second, .. } => first.contains(key) || second.contains(key),
+        }
+    }
+
+    fn for_each_tabs(&mut self, f: &mut impl FnMut(&mut Vec<String>, &mut usize)) {
+        match self {
+            DockNode::Tabs { panels, active } => f(panels, active),
+            DockNode::Split { first, second, .. } => {
+                first.for_each_tabs(f);
+                second.for_each_tabs(f);
+            }
+        }
+    }
+
+    fn node_mut(&mut self, path: &[SplitSide]) -> Option<&mut DockNode> {
+        match path.split_first() {
+            None => Some(self),
+            Some((side, rest)) => match self {
+                DockNode::Split { first, .. } if *side == SplitSide::First => first.node_mut(rest),
+                DockNode::Split { second, .. } => second.node_mut(rest),
+                DockNode::Tabs { .. } => None,
+            },
+        }
+    }
+
+    fn first_tabs_mut(&mut self) -> &mut DockNode {
+        match self {
+            DockNode::Split { first, .. } => first.first_tabs_mut(),
+            node => node,
+        }
+    }
+
+    /// Adds a panel as a tab (if this is a `Tabs` node) and activates it.
+    fn push_tab(&mut self, key: String) {
+        if let DockNode::Tabs { panels, active } = self.first_tabs_mut() {
+            panels.push(key);
+            *active = panels.len() - 1;
+        }
+    }
+
+    /// Replaces this node with a split between itself and `node`, placing `node` at `zone`.
+    fn split_with(&mut self, node: DockNode, zone: DockZone) {
+        let old = std::mem::replace(self, DockNode::Tabs { panels: Vec::new(), active: 0 });
+        let orientation = match zone {
+            DockZone::Left | DockZone::Right => Orientation::Horizontal,
+            _ => Orientation::Vertical,
+        };
+        *self = match zone {
+            DockZone::Left | DockZone::Top => DockNode::Split {
+                orientation,
+                split: EDGE_SPLIT,
+                first: Box::new(node),
+                second: Box::new(old),
+            },
+            _ => DockNode::Split {
+                orientation,
+                split: 1.0 - EDGE_SPLIT,
+                first: Box::new(old),
+                second: Box::new(node),
+            },
+        };
+    }
+
+    /// Removes empty tabs, collapsing splits left with a single side.
+    fn pruned(self) -> Option<DockNode> {
+        match self {
+            DockNode::Tabs { ref panels, .. } if panels.is_empty() => None,
+            DockNode::Split { orientation, split, first, second } => {
+                match (first.pruned(), second.pruned()) {
+                    (Some(first), Some(second)) => Some(DockNode::Split {
+                        orientation,
+                        split,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                    }),
+                    (Some(node), None) | (None, Some(node)) => Some(node),
+                    (None, None) => None,
+                }
+            }
+            node => Some(node),
+        }
+    }
+}
+
+/// Group of tabbed panels floating above the docked panels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatingGroup {
+    /// Rectangle of the group (including the tab bar), relative to the dock.
+    pub rect: Rect,
+    pub panels: Vec<String>,
+    pub active: usize,
+}
+
+/// Arrangement of panels within a dock, identifying panels by their key.
+///
+/// Arrangements can be saved with `serialize` and restored with `deserialize` and `DockWidget::restore`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DockArrangement {
+    /// Tree of docked panels, or `None` if no panels are docked.
+    pub root: Option<DockNode>,
+    pub floating: Vec<FloatingGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DockTarget {
+    /// Edge or center of the entire dock.
+    Root(DockZone),
+    /// Edge or center of the node at a path.
+    Node(Vec<SplitSide>, DockZone),
+    /// Tab of the floating group at an index.
+    Floating(usize),
+    /// New floating group with a rectangle relative to the dock.
+    Float(Rect),
+}
+
+impl DockArrangement {
+    /// Returns `true` if a panel is within the layout, whether docked or floating.
+    pub fn contains(&self, key: &str) -> bool {
+        self.root.as_ref().map(|root| root.contains(key)).unwrap_or(false)
+            || self.floating.iter().any(|group| group.panels.iter().any(|panel| panel == key))
+    }
+
+    /// Makes a panel the active tab of its group.
+    /// Returns `false` if the panel isn't in the layout or was already active.
+    pub fn activate(&mut self, key: &str) -> bool {
+        let mut changed = false;
+        self.for_each_tabs(&mut |panels, active| {
+            if let Some(index) = panels.iter().position(|panel| panel == key) {
+                changed |= *active != index;
+                *active = index;
+            }
+        });
+        changed
+    }
+
+    /// Removes a panel from the layout.
+    /// Returns `false` if the panel wasn't in the layout.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let removed = self.take(key);
+        self.prune();
+        removed
+    }
+
+    /// Removes all the panels for which `f` returns `false`.
+    pub fn retain(&mut self, mut f: impl FnMut(&str) -> bool) {
+        self.for_each_tabs(&mut |panels, active| {
+            let active_key = panels.get(*active).cloned();
+            panels.retain(|panel| f(panel));
+            *active = active_key
+                .and_then(|key| panels.iter().position(|panel| *panel == key))
+                .unwrap_or(0);
+        });
+        self.prune();
+    }
+
+    /// Serializes the layout into a line-based text format, which can be read back with `deserialize`.
+    ///
+    /// Panel keys are separated by spaces, hence they mustn't contain whitespace.
+    pub fn serialize(&self) -> String {
+        fn write_node(node: &DockNode, lines: &mut Vec<String>) {
+            match node {
+                DockNode::Tabs { panels, active } => {
+                    lines.push(format!("tabs {} {}", active, panels.join(" ")))
+                }
+                DockNode::Split { orientation, split, first, second } => {
+                    let orientation = match orientation {
+                        Orientation::Horizontal => "horizontal",
+                        Orientation::Vertical => "vertical",
+                    };
+                    lines.push(format!("split {} {}", orientation, split));
+                    write_node(first, lines);
+                    write_node(second, lines);
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        if let Some(ref root) = self.root {
+            write_node(root, &mut lines);
+        }
+        for group in &self.floating {
+            lines.push(format!(
+                "floating {} {} {} {} {} {}",
+                group.rect.origin.x,
+                group.rect.origin.y,
+                group.rect.size.width,
+                group.rect.size.height,
+                group.active,
+                group.panels.join(" ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Parses a layout previously serialized with `serialize`.
+    pub fn deserialize(text: &str) -> Result<Self, DockError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty())
+            .peekable();
+
+        let mut layout = DockArrangement::default();
+        while let Some(&(line_number, line)) = lines.peek() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("floating") {
+                lines.next();
+                let malformed = || DockError::Malformed(line_number);
+                let mut numbers = [0.0; 4];
+                for number in &mut numbers {
+                    *number = fields.next().and_then(|x| x.parse().ok()).ok_or_else(malformed)?;
+                }
+                let active = fields.next().and_then(|x| x.parse().ok()).ok_or_else(malformed)?;
+                layout.floating.push(FloatingGroup {
+                    rect: Rect::new(
+                        Point::new(numbers[0], numbers[1]),
+                        Size::new(numbers[2], numbers[3]),
+                    ),
+                    panels: fields.map(String::from).collect(),
+                    active,
+                });
+            } else if layout.root.is_none() {
+                layout.root = Some(read_node(&mut lines)?);
+            } else {
+                return Err(DockError::Malformed(line_number));
+            }
+        }
+
+        layout.prune();
+        Ok(layout)
+    }
+
+    fn for_each_tabs(&mut self, f: &mut impl FnMut(&mut Vec<String>, &mut usize)) {
+        if let Some(ref mut root) = self.root {
+            root.for_each_tabs(f);
+        }
+        for group in &mut self.floating {
+            f(&mut group.panels, &mut group.active);
+        }
+    }
+
+    /// Removes a panel without collapsing the node it leaves behind, so that paths to other nodes remain valid.
+    fn take(&mut self, key: &str) -> bool {
+        let mut removed = false;
+        self.for_each_tabs(&mut |panels, active| {
+            if let Some(index) = panels.iter().position(|panel| panel == key) {
+                panels.remove(index);
+                if index < *active || *active >= panels.len() {
+                    *active = active.saturating_sub(1);
+                }
+                removed = true;
+            }
+        });
+        removed
+    }
+
+    fn insert(&mut self, key: String, target: DockTarget) {
+        match target {
+            DockTarget::Root(zone) => match self.root {
+                None => self.root = Some(DockNode::tab(key)),
+                Some(ref mut root) if zone == DockZone::Center => root.push_tab(key),
+                Some(ref mut root) => root.split_with(DockNode::tab(key), zone),
+            },
+            DockTarget::Node(path, zone) => {
+                let node = self.root.as_mut().and_then(|root| root.node_mut(&path));
+                match node {
+                    Some(node) if zone == DockZone::Center => node.push_tab(key),
+                    Some(node) => node.split_with(DockNode::tab(key), zone),
+                    None => self.insert(key, DockTarget::Root(zone)),
+                }
+            }
+            DockTarget::Floating(index) => match self.floating.get_mut(index) {
+                Some(group) => {
+                    group.panels.push(key);
+                    group.active = group.panels.len() - 1;
+                }
+                None => self.insert(key, DockTarget::Root(DockZone::Center)),
+            },
+            DockTarget::Float(rect) => {
+                self.floating.push(FloatingGroup { rect, panels: vec![key], active: 0 })
+            }
+        }
+    }
+
+    fn prune(&mut self) {
+        self.root = self.root.take().and_then(DockNode::pruned);
+        self.floating.retain(|group| !group.panels.is_empty());
+        self.for_each_tabs(&mut |panels, active| {
+            *active = (*active).min(panels.len().saturating_sub(1));
+        });
+    }
+}
+
+fn read_node<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Result<DockNode, DockError> {
+    let (line_number, line) = lines.next().ok_or(DockError::UnexpectedEnd)?;
+    let malformed = || DockError::Malformed(line_number);
+
+    let mut fields = line.split_whitespace();
+    match fields.next().unwrap_or_default() {
+        "tabs" => {
+            let active = fields.next().and_then(|x| x.parse().ok()).ok_or_else(malformed)?;
+            Ok(DockNode::Tabs { panels: fields.map(String::from).collect(), active })
+        }
+        "split" => {
+            let orientation = match fields.next() {
+                Some("horizontal") => Orientation::Horizontal,
+                Some("vertical") => Orientation::Vertical,
+                _ => return Err(malformed()),
+            };
+            let split = fields.next().and_then(|x| x.parse().ok()).ok_or_else(malformed)?;
+            let first = read_node(lines)?;
+            let second = read_node(lines)?;
+            Ok(DockNode::Split {
+                orientation,
+                split,
+                first: Box::new(first),
+                second: Box::new(second),
+            })
+        }
+        kind => Err(DockError::UnknownKind(kind.to_string(), line_number)),
+    }
+}
+
+/// Layout data of a panel pushed into a dock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockPanel {
+    /// Unique key identifying the panel in `DockArrangement`s; mustn't contain whitespace.
+    pub key: String,
+    /// Text shown in the tab of the panel.
+    pub title: DisplayText,
+    /// Where the panel is initially docked, relative to the entire dock.
+    pub zone: DockZone,
+}
+
+impl DockPanel {
+    /// Creates a panel which is initially added as a tab.
+    pub fn new(key: impl Into<String>, title: impl Into<DisplayText>) -> Self {
+        DockPanel { key: key.into(), title: title.into(), zone: DockZone::Center }
+    }
+
+    /// Changes where the panel is initially docked.
+    pub fn zone(self, zone: DockZone) -> Self {
+        DockPanel { zone, ..self }
+    }
+}
+
+struct ChildData {
+    panel: DockPanel,
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum GroupId {
+    Docked(Vec<SplitSide>),
+    Floating(usize),
+}
+
+struct TabGeometry {
+    key: String,
+    title: DisplayText,
+    rect: AbsoluteRect,
+}
+
+struct GroupGeometry {
+    id: GroupId,
+    rect: AbsoluteRect,
+    tab_bar: AbsoluteRect,
+    content: AbsoluteRect,
+    tabs: Vec<TabGeometry>,
+    active: usize,
+}
+
+struct DividerGeometry {
+    path: Vec<SplitSide>,
+    orientation: Orientation,
+    /// Rectangle of the divider.
+    rect: AbsoluteRect,
+    /// Rectangle of the split node.
+    node: AbsoluteRect,
+}
+
+enum DockDrag {
+    Tab {
+        key: String,
+        title: DisplayText,
+        origin: AbsolutePoint,
+        position: AbsolutePoint,
+        /// Target and preview rectangle, once the pointer has moved past `DRAG_THRESHOLD`.
+        target: Option<(DockTarget, AbsoluteRect)>,
+    },
+    Divider(Vec<SplitSide>),
+    Floating {
+        index: usize,
+        offset: Size,
+    },
+}
+
+/// Generates an unbound terminal which handles tab activation and dragging of tabs, dividers and floating groups.
+pub fn dock_handler<U, G>() -> vg::UnboundQueueHandler<DockWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        DockWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let grab = obj.grab_at(event.get().0);
+            if event
+                .with(|(_, button, _)| {
+                    !obj.data.disabled && *button == base::MouseButton::Left && grab.is_some()
+                })
+                .is_some()
+            {
+                if let Some(DockDrag::Tab { ref key, .. }) = grab {
+                    if obj.arrangement.activate(key) {
+                        obj.event_queue.emit_owned(DockEvent::Activate(key.clone()));
+                        obj.dirty = true;
+                    }
+                }
+                obj.drag = grab;
+            }
+        }
+
+        mouse_move => {
+            let (pos, modifiers) = *event.get();
+            obj.cursor = pos;
+            if obj.drag.is_some() {
+                obj.drag_to(pos, modifiers.shift);
+                event.consume();
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.drag.is_some() && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.end_drag();
+            }
+        }
+    }
+}
+
+/// Layout of panels which can be tabbed together, docked to the edges of other panels, and dragged out to float.
+///
+/// Each child is pushed as a `DockPanel`, identified by a unique key. Dragging a tab shows where it will be dropped;
+/// dropping it onto the edge of a panel group (or of the entire dock) docks it there, dropping it onto the center or tab bar
+/// of a group adds it as a tab, and dropping it outside the dock (or while holding shift) makes it float.
+/// Floating groups can be moved by dragging their tab bar.
+///
+/// The arrangement of panels can be saved and restored through `DockArrangement`.
+///
+/// Floating groups are drawn in tree order like any other widget, so they should contain widgets declared after the docked widgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dock {
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    /// Color of panel areas and active tabs.
+    pub background: Color,
+    /// Color of tab bars and dividers.
+    pub tab_background: Color,
+    /// Color of the active tab indicator and drop preview.
+    pub highlight: Color,
+    /// Thickness of the dividers between docked panels.
+    pub divider_width: f32,
+    /// Minimum length of a docked panel group.
+    pub min_size: f32,
+    /// Size of newly floated groups.
+    pub float_size: Size,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Dock
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = DockWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Dock
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Dock {
+            typeface: data.typography.button.clone(),
+            foreground: data.scheme.over_control_outset,
+            background: data.scheme.background,
+            tab_background: data.scheme.control_outset,
+            highlight: data.scheme.focus,
+            divider_width: 4.0,
+            min_size: 60.0,
+            float_size: Size::new(300.0, 200.0),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> DockWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            DockWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("dock", dock_handler::<U, G>().bind(u_aux.window_queue()));
+
+        DockWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+            painter: theme.dock(),
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            arrangement: Default::default(),
+            groups: Vec::new(),
+            dividers: Vec::new(),
+            drag: None,
+            cursor: Default::default(),
+            overlay: CommandGroup::new(),
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::DockState> for DockWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::DockState {
+        state::DockState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            groups: self
+                .groups
+                .iter()
+                .map(|group| state::DockGroupState {
+                    rect: group.rect,
+                    tab_bar: group.tab_bar,
+                    tabs: group.tabs.iter().map(|tab| (tab.title.clone(), tab.rect)).collect(),
+                    active: group.active,
+                    floating: matches!(group.id, GroupId::Floating(_)),
+                })
+                .collect(),
+            dividers: self.dividers.iter().map(|divider| divider.rect).collect(),
+            preview: None,
+            dragging: None,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct DockWidget {
+        widget::MAX,
+
+        <DockEvent> EventQueue,
+        <Dock> State,
+        <state::DockState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            arrangement: DockArrangement,
+            groups: Vec<GroupGeometry>,
+            dividers: Vec<DividerGeometry>,
+            drag: Option<DockDrag>,
+            cursor: AbsolutePoint,
+            overlay: CommandGroup,
+        },
+    }
+}
+
+impl<U, G> DockWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the current arrangement of panels.
+    #[inline]
+    pub fn arrangement(&self) -> &DockArrangement {
+        &self.arrangement
+    }
+
+    /// Replaces the arrangement of panels.
+    ///
+    /// Panels in `layout` which haven't been pushed are discarded, and pushed panels missing from `layout`
+    /// are docked as they were initially.
+    pub fn restore(&mut self, mut layout: DockArrangement) {
+        let rects = &self.rects;
+        layout.retain(|key| rects.values().any(|child| child.panel.key == key));
+        for child in self.rects.values() {
+            if !layout.contains(&child.panel.key) {
+                layout.insert(child.panel.key.clone(), DockTarget::Root(child.panel.zone));
+            }
+        }
+        self.arrangement = layout;
+        self.dirty = true;
+    }
+
+    /// Returns the arrangement of panels in the format of `DockArrangement::serialize`.
+    #[inline]
+    pub fn serialize(&self) -> String {
+        self.arrangement.serialize()
+    }
+
+    /// Restores an arrangement of panels parsed with `DockArrangement::deserialize` (see `restore`).
+    pub fn deserialize(&mut self, text: &str) -> Result<(), DockError> {
+        self.restore(DockArrangement::deserialize(text)?);
+        Ok(())
+    }
+
+    /// Makes a panel the active tab of its group.
+    pub fn activate(&mut self, key: &str) {
+        if self.arrangement.activate(key) {
+            self.dirty = true;
+        }
+    }
+
+    fn title(&self, key: &str) -> DisplayText {
+        self.rects
+            .values()
+            .find(|child| child.panel.key == key)
+            .map(|child| child.panel.title.clone())
+            .unwrap_or_else(|| key.to_string().into())
+    }
+
+    fn tab_size(&self, title: &DisplayText) -> Size {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        let text = TextDisplayItem {
+            text: title.clone(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::default().into(),
+        };
+        text.bounds().unwrap().inflate(10.0, 6.0).size
+    }
+
+    /// Recomputes the rectangles of groups and dividers, then resizes and shows/hides the children accordingly.
+    fn relayout(&mut self) {
+        let bounds = self.abs_rect();
+        let tab_height = self.tab_size(&"Ag".to_string().into()).height;
+
+        let mut groups = Vec::new();
+        let mut dividers = Vec::new();
+        if let Some(ref root) = self.arrangement.root {
+            self.layout_node(root, bounds, Vec::new(), tab_height, &mut groups, &mut dividers);
+        }
+        for (i, group) in self.arrangement.floating.iter().enumerate() {
+            let rect = AbsoluteRect::new(
+                bounds.origin + group.rect.origin.to_vector().cast_unit(),
+                group.rect.size.cast_unit(),
+            );
+            groups.push(self.group_geometry(
+                GroupId::Floating(i),
+                rect,
+                &group.panels,
+                group.active,
+                tab_height,
+            ));
+        }
+
+        for child in self.rects.values_mut() {
+            let placement = groups.iter().find_map(|group| {
+                group
+                    .tabs
+                    .iter()
+                    .position(|tab| tab.key == child.panel.key)
+                    .map(|index| (index == group.active, group.content))
+            });
+
+            match placement {
+                Some((true, rect)) => {
+                    child.visibility.set(base::Visibility::Normal);
+                    if rect != child.rect {
+                        child.rect = rect;
+                        child.evq.emit_owned(rect);
+                    }
+                }
+                _ => child.visibility.set(base::Visibility::None),
+            }
+        }
+
+        self.groups = groups;
+        self.dividers = dividers;
+    }
+
+    fn layout_node(
+        &self,
+        node: &DockNode,
+        rect: AbsoluteRect,
+        path: Vec<SplitSide>,
+        tab_height: f32,
+        groups: &mut Vec<GroupGeometry>,
+        dividers: &mut Vec<DividerGeometry>,
+    ) {
+        match node {
+            DockNode::Tabs { panels, active } => groups.push(self.group_geometry(
+                GroupId::Docked(path),
+                rect,
+                panels,
+                *active,
+                tab_height,
+            )),
+            DockNode::Split { orientation, split, first, second } => {
+                let length = match orientation {
+                    Orientation::Horizontal => rect.size.width,
+                    Orientation::Vertical => rect.size.height,
+                };
+                let available = (length - self.data.divider_width).max(0.0);
+                let position = (split * available)
+                    .min(available - self.data.min_size)
+                    .max(self.data.min_size)
+                    .min(available)
+                    .max(0.0);
+                let (first_rect, divider, second_rect) =
+                    split_rect(rect, *orientation, position, self.data.divider_width);
+
+                dividers.push(DividerGeometry {
+                    path: path.clone(),
+                    orientation: *orientation,
+                    rect: divider,
+                    node: rect,
+                });
+
+                let mut first_path = path.clone();
+                first_path.push(SplitSide::First);
+                let mut second_path = path;
+                second_path.push(SplitSide::Second);
+                self.layout_node(first, first_rect, first_path, tab_height, groups, dividers);
+                self.layout_node(second, second_rect, second_path, tab_height, groups, dividers);
+            }
+        }
+    }
+
+    fn group_geometry(
+        &self,
+        id: GroupId,
+        rect: AbsoluteRect,
+        panels: &[String],
+        active: usize,
+        tab_height: f32,
+    ) -> GroupGeometry {
+        let tab_height = tab_height.min(rect.size.height);
+        let mut x = rect.origin.x;
+        let tabs = panels
+            .iter()
+            .map(|key| {
+                let title = self.title(key);
+                let width = self.tab_size(&title).width;
+                let tab = AbsoluteRect::new(
+                    AbsolutePoint::new(x, rect.origin.y),
+                    Size::new(width, tab_height).cast_unit(),
+                );
+                x += width;
+                TabGeometry { key: key.clone(), title, rect: tab }
+            })
+            .collect();
+
+        GroupGeometry {
+            id,
+            rect,
+            tab_bar: AbsoluteRect::new(
+                rect.origin,
+                Size::new(rect.size.width, tab_height).cast_unit(),
+            ),
+            content: AbsoluteRect::new(
+                AbsolutePoint::new(rect.origin.x, rect.origin.y + tab_height),
+                Size::new(rect.size.width, rect.size.height - tab_height).cast_unit(),
+            ),
+            tabs,
+            active,
+        }
+    }
+
+    /// Returns what would be dragged if the pointer were pressed at `point`.
+    fn grab_at(&self, point: AbsolutePoint) -> Option<DockDrag> {
+        // Floating groups are last, and are above the docked groups.
+        for group in self.groups.iter().rev() {
+            if let Some(tab) = group.tabs.iter().find(|tab| tab.rect.contains(point)) {
+                return Some(DockDrag::Tab {
+                    key: tab.key.clone(),
+                    title: tab.title.clone(),
+                    origin: point,
+                    position: point,
+                    target: None,
+                });
+            }
+
+            if let GroupId::Floating(index) = group.id {
+                if group.tab_bar.contains(point) {
+                    let offset = point - group.rect.origin;
+                    return Some(DockDrag::Floating {
+                        index,
+                        offset: Size::new(offset.x, offset.y),
+                    });
+                }
+            }
+
+            if group.rect.contains(point) {
+                return None;
+            }
+        }
+
+        self.dividers
+            .iter()
+            .find(|divider| {
+                let leeway = (6.0 - self.data.divider_width).max(0.0) / 2.0;
+                match divider.orientation {
+                    Orientation::Horizontal => divider.rect.inflate(leeway, 0.0),
+                    Orientation::Vertical => divider.rect.inflate(0.0, leeway),
+                }
+                .contains(point)
+            })
+            .map(|divider| DockDrag::Divider(divider.path.clone()))
+    }
+
+    /// Returns where a tab dropped at `point` would go, and the preview rectangle thereof.
+    fn drop_target(&self, point: AbsolutePoint, float: bool) -> (DockTarget, AbsoluteRect) {
+        let bounds = self.abs_rect();
+
+        if float || !bounds.contains(point) {
+            let size = self.data.float_size;
+            let origin = point - Size::new(size.width / 2.0, 10.0).to_vector().cast_unit();
+            return (
+                DockTarget::Float(Rect::new((origin - bounds.origin).to_point().cast_unit(), size)),
+                AbsoluteRect::new(origin, size.cast_unit()),
+            );
+        }
+
+        if self.arrangement.root.is_some() {
+            if let Some(zone) = edge_zone(bounds, point) {
+                return (DockTarget::Root(zone), zone_rect(bounds, zone));
+            }
+        }
+
+        match self.groups.iter().rev().find(|group| group.rect.contains(point)) {
+            Some(GroupGeometry { id: GroupId::Floating(index), rect, .. }) => {
+                (DockTarget::Floating(*index), *rect)
+            }
+            Some(group) => {
+                let zone = if group.tab_bar.contains(point) {
+                    DockZone::Center
+                } else {
+                    group_zone(group.content, point)
+                };
+                let path = match group.id {
+                    GroupId::Docked(ref path) => path.clone(),
+                    GroupId::Floating(_) => Vec::new(),
+                };
+                (DockTarget::Node(path, zone), zone_rect(group.rect, zone))
+            }
+            None => (DockTarget::Root(DockZone::Center), bounds),
+        }
+    }
+
+    fn drag_to(&mut self, point: AbsolutePoint, float: bool) {
+        let bounds = self.abs_rect();
+        match self.drag.take() {
+            Some(DockDrag::Tab { key, title, origin, target, .. }) => {
+                let target = if target.is_some() || (point - origin).length() > DRAG_THRESHOLD {
+                    Some(self.drop_target(point, float))
+                } else {
+                    None
+                };
+                self.drag = Some(DockDrag::Tab { key, title, origin, position: point, target });
+                self.overlay.repaint();
+            }
+            Some(DockDrag::Divider(path)) => {
+                if let Some(divider) = self.dividers.iter().find(|divider| divider.path == path) {
+                    let (start, length, position) = match divider.orientation {
+                        Orientation::Horizontal => {
+                            (divider.node.origin.x, divider.node.size.width, point.x)
+                        }
+                        Orientation::Vertical => {
+                            (divider.node.origin.y, divider.node.size.height, point.y)
+                        }
+                    };
+                    let available = length - self.data.divider_width;
+                    if available > 0.0 {
+                        let new_split = ((position - start - self.data.divider_width / 2.0)
+                            / available)
+                            .max(0.0)
+                            .min(1.0);
+                        if let Some(DockNode::Split { split, .. }) =
+                            self.arrangement.root.as_mut().and_then(|root| root.node_mut(&path))
+                        {
+                            *split = new_split;
+                            self.dirty = true;
+                        }
+                    }
+                }
+                self.drag = Some(DockDrag::Divider(path));
+            }
+            Some(DockDrag::Floating { index, offset }) => {
+                if let Some(group) = self.arrangement.floating.get_mut(index) {
+                    group.rect.origin = Point::new(
+                        point.x - offset.width - bounds.origin.x,
+                        point.y - offset.height - bounds.origin.y,
+                    );
+                    self.dirty = true;
+                }
+                self.drag = Some(DockDrag::Floating { index, offset });
+            }
+            None => {}
+        }
+    }
+
+    fn end_drag(&mut self) {
+        match self.drag.take() {
+            Some(DockDrag::Tab { key, target: Some((target, _)), .. }) => {
+                // Only collapse the node the panel was taken from after inserting it, since the target path refers to the current tree.
+                self.arrangement.take(&key);
+                self.arrangement.insert(key.clone(), target);
+                self.arrangement.prune();
+                self.dirty = true;
+                self.event_queue.emit_owned(DockEvent::Relocate(key));
+            }
+            Some(DockDrag::Divider(_)) => self.event_queue.emit_owned(DockEvent::Resize),
+            _ => {}
+        }
+        self.overlay.repaint();
+    }
+
+    fn overlay_state(&self) -> state::DockState {
+        let (preview, dragging) = match self.drag {
+            Some(DockDrag::Tab { ref title, position, target: Some((_, preview)), .. }) => {
+                let size = self.tab_size(title);
+                (
+                    Some(preview),
+                    Some((
+                        title.clone(),
+                        AbsoluteRect::new(
+                            position - size.to_vector().cast_unit() / 2.0,
+                            size.cast_unit(),
+                        ),
+                    )),
+                )
+            }
+            _ => (None, None),
+        };
+
+        state::DockState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            groups: Vec::new(),
+            dividers: Vec::new(),
+            preview,
+            dragging,
+        }
+    }
+}
+
+/// Returns the edge of `rect` which `point` is within `ROOT_EDGE` of, if any.
+fn edge_zone(rect: AbsoluteRect, point: AbsolutePoint) -> Option<DockZone> {
+    if point.x - rect.min_x() < ROOT_EDGE {
+        Some(DockZone::Left)
+    } else if rect.max_x() - point.x < ROOT_EDGE {
+        Some(DockZone::Right)
+    } else if point.y - rect.min_y() < ROOT_EDGE {
+        Some(DockZone::Top)
+    } else if rect.max_y() - point.y < ROOT_EDGE {
+        Some(DockZone::Bottom)
+    } else {
+        None
+    }
+}
+
+/// Returns the zone of `rect` which `point` is in; the middle half of the rectangle is the center
+/// and the rest is divided between the nearest edges.
+fn group_zone(rect: AbsoluteRect, point: AbsolutePoint) -> DockZone {
+    let u = (point.x - rect.min_x()) / rect.size.width.max(1.0);
+    let v = (point.y - rect.min_y()) / rect.size.height.max(1.0);
+    if u > 0.25 && u < 0.75 && v > 0.25 && v < 0.75 {
+        return DockZone::Center;
+    }
+
+    let edges = [
+        (u, DockZone::Left),
+        (1.0 - u, DockZone::Right),
+        (v, DockZone::Top),
+        (1.0 - v, DockZone::Bottom),
+    ];
+    edges
+        .iter()
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|edge| edge.1)
+        .unwrap_or(DockZone::Center)
+}
+
+/// Returns the area of `rect` which a panel docked to `zone` would occupy.
+fn zone_rect(rect: AbsoluteRect, zone: DockZone) -> AbsoluteRect {
+    let width = rect.size.width * EDGE_SPLIT;
+    let height = rect.size.height * EDGE_SPLIT;
+    match zone {
+        DockZone::Left => {
+            AbsoluteRect::new(rect.origin, Size::new(width, rect.size.height).cast_unit())
+        }
+        DockZone::Right => AbsoluteRect::new(
+            AbsolutePoint::new(rect.max_x() - width, rect.min_y()),
+            Size::new(width, rect.size.height).cast_unit(),
+        ),
+        DockZone::Top => {
+            AbsoluteRect::new(rect.origin, Size::new(rect.size.width, height).cast_unit())
+        }
+        DockZone::Bottom => AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x(), rect.max_y() - height),
+            Size::new(rect.size.width, height).cast_unit(),
+        ),
+        DockZone::Center => rect,
+    }
+}
+
+impl<U, G> base::Layout for DockWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// The key, title and initial placement of the panel.
+    /// If unspecified, the panel is keyed by its layout ID and added as a tab.
+    type PushData = DockPanel;
+
+    fn push(&mut self, data: Option<DockPanel>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let panel = data.unwrap_or_else(|| DockPanel::new(format!("panel{}", id), String::new()));
+        if !self.arrangement.contains(&panel.key) {
+            self.arrangement.insert(panel.key.clone(), DockTarget::Root(panel.zone));
+        }
+
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                panel,
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            self.arrangement.remove(&data.panel.key);
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+/// Snapshots the arrangement of panels (see `restore`).
+impl<U, G> history::Snapshot for DockWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Snapshot = DockArrangement;
+
+    #[inline]
+    fn snapshot(&self) -> DockArrangement {
+        self.arrangement.clone()
+    }
+
+    #[inline]
+    fn restore_snapshot(&mut self, snapshot: DockArrangement) {
+        self.restore(snapshot);
+    }
+}
+
+impl<U, G> Widget for DockWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        if !self.data.disabled {
+            let divider = match self.drag {
+                Some(DockDrag::Tab { target: Some(_), .. }) => {
+                    aux.set_cursor_icon(base::CursorIcon::Grabbing);
+                    None
+                }
+                Some(DockDrag::Divider(ref path)) => {
+                    self.dividers.iter().find(|divider| divider.path == *path)
+                }
+                Some(_) => None,
+                None => match self.grab_at(self.cursor) {
+                    Some(DockDrag::Divider(ref path)) => {
+                        self.dividers.iter().find(|divider| divider.path == *path)
+                    }
+                    _ => None,
+                },
+            };
+            if let Some(divider) = divider {
+                aux.set_cursor_icon(match divider.orientation {
+                    Orientation::Horizontal => base::CursorIcon::ResizeHorizontal,
+                    Orientation::Vertical => base::CursorIcon::ResizeVertical,
+                });
+            }
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                if let Some(data) = self.rects.remove(&removal) {
+                    self.arrangement.remove(&data.panel.key);
+                }
+            }
+        }
+
+        if self.dirty {
+            self.relayout();
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let overlay = self.overlay_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+        // The drop preview has to be above the panels themselves.
+        self.overlay.push_with(
+            display,
+            || painter.draw(overlay),
+            display::ZOrder(std::i32::MAX - 1),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Layout which docks children to its edges, around a child filling the rest.
+
+use {
+    crate::{base, draw, geom::*, ui},
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Where a child of a `DockLayout` is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DockSide {
+    /// Along the top of the remaining space, keeping its height.
+    Top,
+    /// Along the bottom of the remaining space, keeping its height.
+    Bottom,
+    /// Along the left of the remaining space, keeping its width.
+    Left,
+    /// Along the right of the remaining space, keeping its width.
+    Right,
+    /// Over the space remaining once every other child has been docked.
+    Fill,
+}
+
+impl Default for DockSide {
+    #[inline]
+    fn default() -> Self {
+        DockSide::Fill
+    }
+}
+
+/// Splits the space remaining in a dock layout between a child docked to `side` and the rest.
+/// Returns the rectangle of the child, and the space which remains after it.
+fn dock(
+    remaining: AbsoluteRect,
+    side: DockSide,
+    size: Size,
+    spacing: f32,
+) -> (AbsoluteRect, AbsoluteRect) {
+    let (x, y) = (remaining.origin.x, remaining.origin.y);
+    let (width, height) = (remaining.size.width, remaining.size.height);
+    let rect = |x: f32, y: f32, width: f32, height: f32| {
+        AbsoluteRect::new(
+            AbsolutePoint::new(x, y),
+            Size::new(width.max(0.0), height.max(0.0)).cast_unit(),
+        )
+    };
+
+    match side {
+        DockSide::Top => {
+            let taken = size.height.min(height);
+            let advance = (taken + spacing).min(height);
+            (rect(x, y, width, taken), rect(x, y + advance, width, height - advance))
+        }
+        DockSide::Bottom => {
+            let taken = size.height.min(height);
+            let advance = (taken + spacing).min(height);
+            (rect(x, y + height - taken, width, taken), rect(x, y, width, height - advance))
+        }
+        DockSide::Left => {
+            let taken = size.width.min(width);
+            let advance = (taken + spacing).min(width);
+            (rect(x, y, taken, height), rect(x + advance, y, width - advance, height))
+        }
+        DockSide::Right => {
+            let taken = size.width.min(width);
+            let advance = (taken + spacing).min(width);
+            (rect(x + width - taken, y, taken, height), rect(x, y, width - advance, height))
+        }
+        DockSide::Fill => (remaining, remaining),
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: DockSide,
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Layout which docks children to its top, bottom, left and right, with a child filling the space in between;
+/// the classic arrangement of an app window, with a menu bar at the top, a status bar at the bottom, a sidebar to
+/// the left and the content in the middle.
+///
+/// Docked children are placed in the order they were pushed, each taking a strip of the space left by those before
+/// it, so that a top child pushed before a left child spans the whole width, and one pushed after it doesn't.
+/// Children pushed with `DockSide::Fill` (the default) fill whatever remains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockLayout {
+    /// Inset of the children from the edges of the layout.
+    pub padding: f32,
+    /// Space between each docked child and the rest.
+    pub spacing: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for DockLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = DockLayoutWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for DockLayout
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        DockLayout { padding: 0.0, spacing: 0.0 }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, _u_aux: &mut U) -> DockLayoutWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            DockLayoutWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                }
+            }
+        };
+
+        DockLayoutWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for DockLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct DockLayoutWidget {
+        widget::MAX,
+
+        <DockLayout> State,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> DockLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the side a child which has been pushed is docked to, by its layout ID.
+    pub fn set_side(&mut self, id: u64, side: DockSide) {
+        if let Some(child) = self.rects.get_mut(&id) {
+            child.data = side;
+            self.dirty = true;
+        }
+    }
+
+    /// Returns the side a child which has been pushed is docked to, by its layout ID.
+    pub fn side(&self, id: u64) -> Option<DockSide> {
+        self.rects.get(&id).map(|child| child.data)
+    }
+
+    fn update_layout(&mut self) {
+        let padding = self.data.padding;
+        let spacing = self.data.spacing;
+        let mut remaining = self.abs_rect().inflate(-padding, -padding);
+
+        for (_, child) in &mut self.rects {
+            if child.data != DockSide::Fill {
+                let (rect, rest) =
+                    dock(remaining, child.data, child.rect.size.cast_unit(), spacing);
+                remaining = rest;
+                if rect != child.rect {
+                    child.rect = rect;
+                    child.evq.emit_owned(rect);
+                }
+            }
+        }
+
+        for (_, child) in &mut self.rects {
+            if child.data == DockSide::Fill && remaining != child.rect {
+                child.rect = remaining;
+                child.evq.emit_owned(remaining);
+            }
+        }
+    }
+}
+
+impl<U, G> base::Layout for DockLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = DockSide;
+
+    fn push(&mut self, data: Option<DockSide>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data: data.unwrap_or_default(),
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.shift_remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for DockLayoutWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.shift_remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.dirty = false;
+        }
+    }
+}
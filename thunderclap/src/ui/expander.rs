@@ -0,0 +1,384 @@
+//! Container which animates its content open and closed.
+
+use {
+    crate::{
+        base::{self, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Events emitted by an expander.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpanderEvent {
+    /// Emitted when the expander starts opening.
+    #[event_key(expand)]
+    Expand,
+    /// Emitted when the expander starts closing.
+    #[event_key(collapse)]
+    Collapse,
+    /// Emitted once the content has finished opening or closing.
+    #[event_key(settle)]
+    Settle,
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    clip: base::LayoutClip,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Container whose content slides open and closed, its height interpolating over `duration` rather than snapping.
+///
+/// Content is added by pushing widgets into the expander as a layout, preserving their arrangement relative to each
+/// other. The expander resizes itself every frame while animating, so that parent layouts (e.g. a `VStack`) reflow
+/// smoothly; the content is clipped to the expander meanwhile, and only becomes interactive once fully open.
+///
+/// The expander has no header of its own; it's toggled by the app (e.g. when a button is pressed).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Expander {
+    pub expanded: bool,
+    /// How long the content takes to open or close.
+    pub duration: Duration,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Expander
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ExpanderWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Expander
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Expander { expanded: false, duration: Duration::from_millis(200) }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, _u_aux: &mut U) -> ExpanderWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+        // starts settled, without animating the initial state
+        let openness = if self.expanded { 1.0 } else { 0.0 };
+
+        let graph = vg::verbgraph! {
+            ExpanderWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.sync_expanded();
+                }
+            }
+        };
+
+        ExpanderWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            expanded: self.expanded,
+            openness,
+            animation: (openness, Instant::now()),
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for ExpanderWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ExpanderWidget {
+        widget::MAX,
+
+        <ExpanderEvent> EventQueue,
+        <Expander> State,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            expanded: bool,
+            /// How far the content is open, before easing; 0 when closed and 1 when open.
+            openness: f32,
+            /// Openness when the current animation started, and when it started.
+            animation: (f32, Instant),
+        },
+    }
+}
+
+impl<U, G> ExpanderWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Starts opening the content.
+    pub fn expand(&mut self) {
+        if !self.data.expanded {
+            self.data.expanded = true;
+        }
+    }
+
+    /// Starts closing the content.
+    pub fn collapse(&mut self) {
+        if self.data.expanded {
+            self.data.expanded = false;
+        }
+    }
+
+    /// Opens the content if it's closed or closing, and vice versa.
+    pub fn toggle(&mut self) {
+        let expanded = self.data.expanded;
+        self.data.expanded = !expanded;
+    }
+
+    /// Returns the fraction of the height of the content which is shown, eased, from 0 (closed) to 1 (open).
+    pub fn shown_fraction(&self) -> f32 {
+        // Ease in-out (cubic)
+        let t = self.openness;
+        if t < 0.5 {
+            4.0 * t * t * t
+        } else {
+            1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+        }
+    }
+
+    /// Returns `true` if the content is opening or closing.
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.openness != self.target()
+    }
+
+    fn target(&self) -> f32 {
+        if self.expanded {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Applies changes of `expanded` made to the data, animating from the current openness.
+    fn sync_expanded(&mut self) {
+        if self.data.expanded == self.expanded {
+            return;
+        }
+        self.expanded = self.data.expanded;
+        self.animation = (self.openness, Instant::now());
+        self.dirty = true;
+        self.event_queue.emit_owned(if self.expanded {
+            ExpanderEvent::Expand
+        } else {
+            ExpanderEvent::Collapse
+        });
+    }
+
+    /// Advances the openness toward `expanded`, emitting `Settle` once it's reached.
+    fn animate(&mut self) {
+        if !self.is_animating() {
+            return;
+        }
+
+        let target = self.target();
+        let (from, start) = self.animation;
+        // a reversal midway takes as long as the distance left to cover
+        let duration = self.data.duration.as_secs_f32() * (target - from).abs();
+        let progress = if duration > 0.0 {
+            ((Instant::now() - start).as_secs_f32() / duration).min(1.0)
+        } else {
+            1.0
+        };
+
+        self.openness = from + (target - from) * progress;
+        self.dirty = true;
+        if progress >= 1.0 {
+            self.openness = target;
+            self.event_queue.emit_owned(ExpanderEvent::Settle);
+        }
+    }
+
+    /// Places the content at the top of the expander, and resizes the expander to show the open fraction of it.
+    fn update_layout(&mut self) {
+        let mut content: Option<AbsoluteRect> = None;
+        for (_, data) in &self.rects {
+            content = Some(content.map_or(data.rect, |content| content.union(&data.rect)));
+        }
+        let content = content.unwrap_or_default();
+
+        let abs_rect = self.abs_rect();
+        let offset = abs_rect.origin - content.origin;
+        let size = Size::new(
+            self.rect.size.width.max(content.size.width),
+            (content.size.height * self.shown_fraction()).round(),
+        );
+
+        // the content only receives input once fully open, and isn't drawn once fully closed
+        let (visibility, clip) = match self.openness {
+            openness if openness >= 1.0 => (base::Visibility::Normal, None),
+            openness if openness <= 0.0 => (base::Visibility::None, None),
+            _ => (
+                base::Visibility::Static,
+                Some(AbsoluteRect::new(abs_rect.origin, size.cast_unit())),
+            ),
+        };
+
+        for (_, data) in &mut self.rects {
+            if offset != Default::default() {
+                data.rect = data.rect.translate(offset);
+                data.evq.emit_owned(data.rect);
+            }
+            data.visibility.set(visibility);
+            data.clip.set(clip);
+        }
+
+        if size != self.size() {
+            // Notifies the parent layout through `on_transform`.
+            self.set_size(size);
+        }
+    }
+}
+
+impl<U, G> base::Layout for ExpanderWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ();
+
+    fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+        let clip = base::LayoutClip::default();
+        // hidden until it's placed
+        visibility.set(base::Visibility::None);
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: clip.clone(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                clip,
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for ExpanderWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        self.animate();
+        if self.is_animating() {
+            aux.request_animation_frame();
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.dirty = false;
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Floating action button, anchored to a corner of its container.
+
+use {
+    crate::{
+        base::{self, Rectangular, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui::{self, Corner},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Point, Rect, Size, TextDisplayItem},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Space between the button and the first action, and between actions.
+const ACTION_SPACING: f32 = 12.0;
+/// Space between an action and its label.
+const LABEL_GAP: f32 = 8.0;
+/// Horizontal space between the edges of a label and its text.
+const LABEL_PADDING: f32 = 8.0;
+
+/// Events emitted by a floating action button.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum FabEvent {
+    /// Emitted when a button without actions is pressed.
+    #[event_key(press)]
+    Press,
+    /// Emitted when the speed-dial menu of actions is expanded.
+    #[event_key(expand)]
+    Expand,
+    /// Emitted when the speed-dial menu of actions is collapsed.
+    #[event_key(collapse)]
+    Collapse,
+    /// Emitted when an action is picked, with the index of the action.
+    #[event_key(action)]
+    Action(usize),
+}
+
+/// Interactive part of a floating action button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FabPart {
+    Button,
+    /// An action of the speed-dial menu (or its label).
+    Action(usize),
+}
+
+/// Action of the speed-dial menu of a floating action button.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FabAction {
+    /// Short text (e.g. a single symbol) shown in the button of the action.
+    pub icon: String,
+    /// Text shown next to the button of the action.
+    pub label: String,
+}
+
+impl FabAction {
+    pub fn new(icon: impl Into<String>, label: impl Into<String>) -> Self {
+        FabAction { icon: icon.into(), label: label.into() }
+    }
+}
+
+/// Generates an unbound terminal which handles pressing and hovering the button and its actions.
+pub fn fab_handler<U, G>() -> vg::UnboundQueueHandler<FabWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        FabWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && obj.part_at(*pos).is_some()
+            }) {
+                obj.pressed = obj.part_at(*pos);
+                obj.command_group.repaint();
+            } else if obj.data.expanded {
+                // pressing anywhere else dismisses the actions, without consuming the press
+                obj.collapse();
+            }
+        }
+
+        mouse_release => {
+            if let Some((pos, _, _)) = event.with(|(_, button, _)| {
+                *button == base::MouseButton::Left && obj.pressed.is_some()
+            }) {
+                if let Some(part) = obj.pressed.take() {
+                    if obj.part_at(*pos) == Some(part) {
+                        obj.activate(part);
+                    }
+                }
+                obj.command_group.repaint();
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = if obj.data.disabled { None } else { obj.part_at(pos) };
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Circular button raised above the content, anchored to a corner of its container, which optionally expands into a
+/// speed-dial menu of actions.
+///
+/// The button isn't a child of its container; it should be a sibling declared after the container (so that it's drawn
+/// above it), and positioned with `FabWidget::anchor_to` using the bounds of the container. Since it's anchored to the
+/// bounds of the container rather than its content, it stays put while the content scrolls.
+///
+/// Without actions, pressing the button emits `FabEvent::Press`. With actions, pressing the button toggles the menu,
+/// which stacks the actions (with their labels) away from the corner; pressing anywhere else collapses it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fab {
+    /// Corner of the container which the button is anchored to.
+    pub corner: Corner,
+    /// Space between the button and the edges of the container at the corner.
+    pub margin: f32,
+    pub diameter: f32,
+    /// Diameter of the buttons of the actions.
+    pub action_diameter: f32,
+    pub actions: Vec<FabAction>,
+    /// Whether the speed-dial menu of actions is shown.
+    pub expanded: bool,
+    /// Height above the content, which determines the size of the shadows.
+    pub elevation: f32,
+    pub typeface: draw::TypefaceStyle,
+    /// Color of the icon of the button.
+    pub foreground: Color,
+    /// Color of the button.
+    pub background: Color,
+    /// Color of the icons and labels of the actions.
+    pub action_foreground: Color,
+    /// Color of the buttons and labels of the actions.
+    pub action_background: Color,
+    pub shadow: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Fab
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = FabWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Fab
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Fab {
+            corner: Corner::BottomRight,
+            margin: 16.0,
+            diameter: 56.0,
+            action_diameter: 40.0,
+            actions: Vec::new(),
+            expanded: false,
+            elevation: 6.0,
+            typeface: data.typography.button.clone(),
+            foreground: data.scheme.over_primary,
+            background: data.scheme.primary,
+            action_foreground: data.scheme.over_control_outset,
+            action_background: data.scheme.control_outset,
+            shadow: Color::new(0.0, 0.0, 0.0, 0.3),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> FabWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            FabWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.reposition();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("fab", fab_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.fab();
+        let rect = RelativeRect::new(
+            Default::default(),
+            Size::new(data.diameter, data.diameter).cast_unit(),
+        );
+
+        FabWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            container: None,
+            hovered: None,
+            pressed: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::FabState> for FabWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::FabState {
+        let actions = if self.data.expanded {
+            (0..self.data.actions.len())
+                .map(|i| state::FabActionState {
+                    button: self.action_rect(i),
+                    label: self.label_rect(i),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        state::FabState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            button: self.button_rect(),
+            actions,
+            hovered: self.hovered,
+            pressed: self.pressed,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct FabWidget {
+        widget::MAX,
+
+        <FabEvent> EventQueue,
+        <Fab> State,
+        <state::FabState> Painter,
+
+        {
+            /// Bounds of the container, as last given to `anchor_to`.
+            container: Option<Rect>,
+            hovered: Option<FabPart>,
+            pressed: Option<FabPart>,
+        },
+    }
+}
+
+impl<U, G> FabWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Anchors the button to the corner of the container with the given bounds.
+    ///
+    /// The container must share the same parent as the button. This should be called whenever the container moves or
+    /// is resized; calling it with unchanged bounds does nothing.
+    pub fn anchor_to(&mut self, container: Rect) {
+        if self.container != Some(container) {
+            self.container = Some(container);
+            self.reposition();
+        }
+    }
+
+    /// Returns the bounds of the container, as last given to `anchor_to`.
+    #[inline]
+    pub fn container(&self) -> Option<Rect> {
+        self.container
+    }
+
+    /// Shows the speed-dial menu of actions, emitting `FabEvent::Expand`.
+    pub fn expand(&mut self) {
+        if !self.data.expanded && !self.data.actions.is_empty() {
+            self.data.expanded = true;
+            self.event_queue.emit_owned(FabEvent::Expand);
+        }
+    }
+
+    /// Hides the speed-dial menu of actions, emitting `FabEvent::Collapse`.
+    pub fn collapse(&mut self) {
+        if self.data.expanded {
+            self.data.expanded = false;
+            self.event_queue.emit_owned(FabEvent::Collapse);
+        }
+    }
+
+    /// Presses a part of the button, as if clicked.
+    pub fn activate(&mut self, part: FabPart) {
+        match part {
+            FabPart::Button if self.data.actions.is_empty() => {
+                self.event_queue.emit_owned(FabEvent::Press)
+            }
+            FabPart::Button if self.data.expanded => self.collapse(),
+            FabPart::Button => self.expand(),
+            FabPart::Action(index) => {
+                if index < self.data.actions.len() {
+                    self.event_queue.emit_owned(FabEvent::Action(index));
+                    self.collapse();
+                }
+            }
+        }
+    }
+
+    /// Returns the part of the button containing a given point, if any.
+    pub fn part_at(&self, point: AbsolutePoint) -> Option<FabPart> {
+        let within_circle =
+            |rect: AbsoluteRect| (point - rect.center()).length() <= rect.size.width / 2.0;
+
+        if within_circle(self.button_rect()) {
+            return Some(FabPart::Button);
+        }
+        if self.data.expanded {
+            return (0..self.data.actions.len())
+                .find(|&i| within_circle(self.action_rect(i)) || self.label_rect(i).contains(point))
+                .map(FabPart::Action);
+        }
+        None
+    }
+
+    fn is_left(&self) -> bool {
+        self.data.corner.is_left()
+    }
+
+    fn is_top(&self) -> bool {
+        self.data.corner.is_top()
+    }
+
+    /// Returns the rectangle of the button, at the anchored corner of the widget.
+    fn button_rect(&self) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        let diameter = self.data.diameter;
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                if self.is_left() { bounds.min_x() } else { bounds.max_x() - diameter },
+                if self.is_top() { bounds.min_y() } else { bounds.max_y() - diameter },
+            ),
+            Size::new(diameter, diameter).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the button of an action; the first action is nearest to the button.
+    fn action_rect(&self, index: usize) -> AbsoluteRect {
+        let button = self.button_rect();
+        let diameter = self.data.action_diameter;
+        let distance =
+            button.size.height / 2.0 + ACTION_SPACING + index as f32 * (diameter + ACTION_SPACING);
+        let center_y = if self.is_top() {
+            button.center().y + distance + diameter / 2.0
+        } else {
+            button.center().y - distance - diameter / 2.0
+        };
+        AbsoluteRect::new(
+            AbsolutePoint::new(button.center().x - diameter / 2.0, center_y - diameter / 2.0),
+            Size::new(diameter, diameter).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the label of an action, beside its button toward the inside of the container.
+    fn label_rect(&self, index: usize) -> AbsoluteRect {
+        let action = self.action_rect(index);
+        let width = self.data.actions.get(index).map_or(0.0, |action| self.label_width(action));
+        let height = (self.data.typeface.size * 1.8).round();
+        let left = if self.is_left() {
+            action.max_x() + LABEL_GAP
+        } else {
+            action.min_x() - LABEL_GAP - width
+        };
+        AbsoluteRect::new(
+            AbsolutePoint::new(left, action.center().y - height / 2.0),
+            Size::new(width, height).cast_unit(),
+        )
+    }
+
+    fn label_width(&self, action: &FabAction) -> f32 {
+        if action.label.is_empty() {
+            return 0.0;
+        }
+
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        let text = TextDisplayItem {
+            text: action.label.clone().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+        };
+        text.bounds().map(|bounds| bounds.size.width).unwrap_or(0.0) + LABEL_PADDING * 2.0
+    }
+
+    /// Resizes the widget to fit the button and any shown actions, at the anchored corner of the container.
+    fn reposition(&mut self) {
+        let diameter = self.data.diameter;
+        let mut size = Size::new(diameter, diameter);
+        if self.data.expanded && !self.data.actions.is_empty() {
+            let count = self.data.actions.len() as f32;
+            let labels =
+                self.data.actions.iter().map(|action| self.label_width(action)).fold(0.0, f32::max);
+            // the actions are centered on the button, with the labels extending toward the inside
+            let extent = self.data.action_diameter / 2.0 + LABEL_GAP + labels;
+            size.width = diameter / 2.0 + extent.max(diameter / 2.0);
+            size.height += count * (self.data.action_diameter + ACTION_SPACING);
+        }
+
+        let container = match self.container {
+            Some(container) => container,
+            None => {
+                self.set_size(size);
+                return;
+            }
+        };
+
+        let margin = self.data.margin;
+        let origin = Point::new(
+            if self.is_left() {
+                container.min_x() + margin
+            } else {
+                container.max_x() - margin - size.width
+            },
+            if self.is_top() {
+                container.min_y() + margin
+            } else {
+                container.max_y() - margin - size.height
+            },
+        );
+        self.set_rect(RelativeRect::new(origin.cast_unit(), size.cast_unit()));
+    }
+}
+
+impl<U, G> Widget for FabWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        // the extent of the shadow depends on the elevation, which the painter can't see
+        let extent = draw::shadow_extent(self.data.elevation);
+        self.painter.paint_hint(self.rect).inflate(extent, extent).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
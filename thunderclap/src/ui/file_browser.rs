@@ -0,0 +1,881 @@
+//! In-app file browser with a directory tree and file list.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        fuzzy,
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        collections::{BTreeSet, HashSet},
+        io,
+        ops::Range,
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    },
+};
+
+/// Maximum time between two clicks on the same entry for them to count as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Events emitted by a file browser.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum FileEvent {
+    /// Emitted when files have been chosen by the user, by double-clicking or pressing enter.
+    #[event_key(chosen)]
+    Chosen(Vec<PathBuf>),
+    /// Emitted when the listed directory has changed.
+    #[event_key(navigate)]
+    Navigate(PathBuf),
+    /// Emitted when the selected entries have changed.
+    #[event_key(selection_change)]
+    SelectionChange,
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// A file or directory listed by a file browser.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
+    /// Size of files, in bytes.
+    pub size: Option<u64>,
+    /// Byte ranges of `name` matched by the search of the file browser.
+    pub matched: Vec<Range<usize>>,
+}
+
+/// A directory shown in the tree of a file browser.
+#[derive(Debug, Clone)]
+struct TreeRow {
+    path: PathBuf,
+    name: String,
+    depth: usize,
+    expanded: bool,
+    /// Whether the directory is known to have no subdirectories.
+    leaf: bool,
+}
+
+/// Returns whether a file name matches a filter pattern, ignoring case.
+///
+/// `*` matches any sequence of characters and `?` matches any single character.
+pub fn matches_filter(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*`, and the name position it's currently matched up to.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Formats a file size in bytes for display (e.g. `1.5 MB`), with the numerals of the active locale.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let locale = i18n::locale();
+    if bytes < 1024 {
+        return format!("{} B", locale.format_integer(bytes as i64));
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", locale.format_decimal(size, 1), UNITS[unit])
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.to_string_lossy().into_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Lists the subdirectories of a directory, sorted by name.
+fn read_subdirectories(path: &Path, show_hidden: bool) -> Vec<PathBuf> {
+    let mut directories: Vec<_> = match std::fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map_or(false, |ty| ty.is_dir()))
+            .map(|entry| entry.path())
+            .filter(|path| show_hidden || !is_hidden(&file_name(path)))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    directories.sort_by_key(|path| file_name(path).to_lowercase());
+    directories
+}
+
+/// Generates an unbound terminal which handles navigation, selection and scrolling.
+pub fn file_browser_handler<U, G>(
+) -> vg::UnboundQueueHandler<FileBrowserWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        FileBrowserWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, modifiers)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.focus();
+
+                if obj.tree_rect().contains(*pos) {
+                    if let Some(row) = obj.tree_row_at(*pos) {
+                        let arrow = obj.tree_rect().min_x()
+                            + (obj.tree[row].depth + 1) as f32 * obj.data.indent;
+                        if pos.x < arrow {
+                            obj.toggle_expanded(row);
+                        } else {
+                            let path = obj.tree[row].path.clone();
+                            obj.navigate(path);
+                        }
+                    }
+                } else if let Some(index) = obj.entry_at(*pos) {
+                    let now = Instant::now();
+                    let double_click = match obj.last_click {
+                        Some((last, time)) => last == index && now - time <= DOUBLE_CLICK_TIME,
+                        None => false,
+                    };
+
+                    if double_click {
+                        obj.last_click = None;
+                        obj.select_only(index);
+                        obj.activate();
+                    } else {
+                        obj.last_click = Some((index, now));
+                        obj.click_entry(index, modifiers.ctrl || modifiers.logo, modifiers.shift);
+                    }
+                } else if !obj.selected.is_empty() {
+                    obj.selected.clear();
+                    obj.anchor = None;
+                    obj.command_group.repaint();
+                    obj.event_queue.emit_owned(FileEvent::SelectionChange);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.entry_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, delta, _)) =
+                event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos))
+            {
+                if obj.tree_rect().contains(*pos) {
+                    obj.tree_scroll = obj.clamp_scroll(obj.tree_scroll - delta.y, obj.tree.len());
+                } else {
+                    obj.list_scroll =
+                        obj.clamp_scroll(obj.list_scroll - delta.y, obj.entries.len());
+                }
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, modifiers)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if !obj.interaction.contains(state::InteractionState::FOCUS_VISIBLE) {
+                    obj.interaction.insert(state::InteractionState::FOCUS_VISIBLE);
+                    obj.command_group.repaint();
+                }
+
+                match key {
+                    base::KeyInput::Up | base::KeyInput::Down if !obj.entries.is_empty() => {
+                        let last = obj.entries.len() - 1;
+                        let index = match (obj.anchor, key) {
+                            (Some(anchor), base::KeyInput::Up) => anchor.saturating_sub(1),
+                            (Some(anchor), _) => (anchor + 1).min(last),
+                            (None, base::KeyInput::Up) => last,
+                            (None, _) => 0,
+                        };
+                        if modifiers.shift && obj.data.multi_select {
+                            obj.click_entry(index, false, true);
+                        } else {
+                            obj.select_only(index);
+                        }
+                        obj.scroll_to_entry(index);
+                    }
+                    base::KeyInput::A if modifiers.ctrl || modifiers.logo => {
+                        if obj.data.multi_select {
+                            obj.selected = (0..obj.entries.len()).collect();
+                            obj.command_group.repaint();
+                            obj.event_queue.emit_owned(FileEvent::SelectionChange);
+                        }
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => obj.activate(),
+                    base::KeyInput::Back => obj.navigate_up(),
+                    _ => {}
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(FileEvent::Blur);
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// In-app (rather than native) file browser, showing a directory tree beside a list of the files in the current directory.
+///
+/// Double-clicking a directory (or pressing enter) opens it, and double-clicking a file (or pressing enter) chooses the selected files.
+/// Backspace goes up to the parent directory. With `multi_select`, entries can be added to the selection with ctrl/cmd and shift.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileBrowser {
+    /// Directory whose contents are listed.
+    pub directory: PathBuf,
+    /// Directory at the root of the tree.
+    pub root: PathBuf,
+    /// Patterns which file names must match one of to be listed (e.g. `*.png`); all files are listed if empty.
+    /// Directories are always listed.
+    pub filters: Vec<String>,
+    /// Whether files and directories whose names start with `.` are listed.
+    pub show_hidden: bool,
+    /// Text which names are fuzzy-matched against; only matching entries are listed, with the matched characters highlighted.
+    /// All entries are listed if empty.
+    pub search: String,
+    /// Whether multiple files can be selected and chosen at once.
+    pub multi_select: bool,
+    /// Width of the directory tree.
+    pub tree_width: f32,
+    /// Horizontal indentation of each level of the directory tree.
+    pub indent: f32,
+    pub row_height: f32,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of selected entries.
+    pub highlight: Color,
+    /// Color of the text of selected entries.
+    pub over_highlight: Color,
+    /// Color of selected entries while the window is inactive.
+    pub inactive_highlight: Color,
+    /// Color of the text of selected entries while the window is inactive.
+    pub over_inactive_highlight: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for FileBrowser
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = FileBrowserWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for FileBrowser
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        let directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let root =
+            directory.ancestors().last().map_or_else(|| directory.clone(), Path::to_path_buf);
+        FileBrowser {
+            directory,
+            root,
+            filters: Vec::new(),
+            show_hidden: false,
+            search: String::new(),
+            multi_select: true,
+            tree_width: 180.0,
+            indent: 12.0,
+            row_height: 22.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            highlight: data.scheme.list_selection,
+            over_highlight: data.scheme.over_list_selection,
+            inactive_highlight: data.scheme.inactive_selection,
+            over_inactive_highlight: data.scheme.over_inactive_selection,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> FileBrowserWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            FileBrowserWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.refresh_if_changed();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("file_browser", file_browser_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.file_browser();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::FileBrowserState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    tree_rect: Default::default(),
+                    list_rect: Default::default(),
+                    tree: Vec::new(),
+                    entries: Vec::new(),
+                    message: None,
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let mut widget = FileBrowserWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            entries: Vec::new(),
+            tree: Vec::new(),
+            expanded: HashSet::new(),
+            listed: None,
+            selected: BTreeSet::new(),
+            anchor: None,
+            hovered: None,
+            last_click: None,
+            tree_scroll: 0.0,
+            list_scroll: 0.0,
+            error: None,
+            interaction: state::InteractionState::empty(),
+        }
+        .build();
+
+        widget.refresh();
+        widget
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::FileBrowserState> for FileBrowserWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::FileBrowserState {
+        let tree_rect = self.tree_rect();
+        let list_rect = self.list_rect();
+
+        let tree = self
+            .visible_rows(tree_rect, self.tree_scroll, self.tree.len())
+            .map(|(i, rect)| {
+                let row = &self.tree[i];
+                state::FileTreeRowState {
+                    name: row.name.clone().into(),
+                    depth: row.depth,
+                    expanded: if row.leaf && row.expanded { None } else { Some(row.expanded) },
+                    current: row.path == self.data.directory,
+                    rect,
+                }
+            })
+            .collect();
+
+        let entries = self
+            .visible_rows(list_rect, self.list_scroll, self.entries.len())
+            .map(|(i, rect)| {
+                let entry = &self.entries[i];
+                state::FileRowState {
+                    name: entry.name.clone().into(),
+                    is_dir: entry.is_dir,
+                    size: entry.size,
+                    matched: entry.matched.clone(),
+                    selected: self.selected.contains(&i),
+                    hovered: self.hovered == Some(i),
+                    rect,
+                }
+            })
+            .collect();
+
+        let message = match self.error {
+            Some(ref error) => Some(error.to_string().into()),
+            None if self.entries.is_empty() => Some("This folder is empty.".to_string().into()),
+            None => None,
+        };
+
+        state::FileBrowserState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            tree_rect,
+            list_rect,
+            tree,
+            entries,
+            message,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct FileBrowserWidget {
+        widget::MAX,
+
+        <FileEvent> EventQueue,
+        <FileBrowser> State,
+        <state::FileBrowserState> Painter,
+
+        {
+            entries: Vec<FileEntry>,
+            tree: Vec<TreeRow>,
+            expanded: HashSet<PathBuf>,
+            /// Directory, filters, whether hidden files are shown and search, as of the last refresh.
+            listed: Option<(PathBuf, Vec<String>, bool, String)>,
+            /// Indices of the selected entries.
+            selected: BTreeSet<usize>,
+            /// Entry which range selections extend from.
+            anchor: Option<usize>,
+            hovered: Option<usize>,
+            last_click: Option<(usize, Instant)>,
+            tree_scroll: f32,
+            list_scroll: f32,
+            error: Option<io::Error>,
+            interaction: state::InteractionState,
+        },
+    }
+}
+
+impl<U, G> FileBrowserWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the entries of the current directory which pass the filters, directories first.
+    #[inline]
+    pub fn entries(&self) -> &[FileEntry] {
+        &self.entries
+    }
+
+    /// Returns the paths of the selected entries.
+    pub fn selection(&self) -> Vec<PathBuf> {
+        self.selected.iter().map(|&i| self.entries[i].path.clone()).collect()
+    }
+
+    /// Returns the error which occurred while listing the current directory, if any.
+    #[inline]
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Lists a different directory.
+    pub fn navigate(&mut self, directory: impl Into<PathBuf>) {
+        self.data.directory = directory.into();
+        self.refresh_if_changed();
+    }
+
+    /// Lists the parent of the current directory, if any.
+    pub fn navigate_up(&mut self) {
+        if let Some(parent) = self.data.directory.parent().map(Path::to_path_buf) {
+            self.navigate(parent);
+        }
+    }
+
+    /// Re-reads the current directory and the directory tree from the file system.
+    pub fn refresh(&mut self) {
+        let directory = self.data.directory.clone();
+        let first = self.listed.is_none();
+        let navigated =
+            self.listed.as_ref().map_or(false, |(listed, _, _, _)| *listed != directory);
+
+        match self.read_entries(&directory) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(error) => {
+                self.entries.clear();
+                self.error = Some(error);
+            }
+        }
+
+        if first || navigated {
+            self.selected.clear();
+            self.anchor = None;
+            self.list_scroll = 0.0;
+            // Reveal the directory in the tree.
+            for ancestor in directory.ancestors().skip(1) {
+                self.expanded.insert(ancestor.to_path_buf());
+            }
+        } else {
+            let len = self.entries.len();
+            self.selected.retain(|&i| i < len);
+            self.anchor = self.anchor.filter(|&i| i < len);
+        }
+        self.hovered = None;
+        self.rebuild_tree();
+
+        self.listed = Some((
+            directory.clone(),
+            self.data.filters.clone(),
+            self.data.show_hidden,
+            self.data.search.clone(),
+        ));
+        self.command_group.repaint();
+
+        if navigated {
+            self.event_queue.emit_owned(FileEvent::Navigate(directory));
+        }
+    }
+
+    /// Chooses the selected files, or opens the selected directory if it's the only selected entry.
+    pub fn activate(&mut self) {
+        if self.selected.len() == 1 {
+            let entry = &self.entries[*self.selected.iter().next().unwrap()];
+            if entry.is_dir {
+                let path = entry.path.clone();
+                self.navigate(path);
+                return;
+            }
+        }
+
+        let chosen: Vec<_> = self
+            .selected
+            .iter()
+            .map(|&i| &self.entries[i])
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path.clone())
+            .collect();
+        if !chosen.is_empty() {
+            self.event_queue.emit_owned(FileEvent::Chosen(chosen));
+        }
+    }
+
+    fn refresh_if_changed(&mut self) {
+        let changed = match self.listed {
+            Some((ref directory, ref filters, show_hidden, ref search)) => {
+                *directory != self.data.directory
+                    || *filters != self.data.filters
+                    || show_hidden != self.data.show_hidden
+                    || *search != self.data.search
+            }
+            None => true,
+        };
+        if changed {
+            self.refresh();
+        }
+    }
+
+    fn read_entries(&self, directory: &Path) -> io::Result<Vec<FileEntry>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !self.data.show_hidden && is_hidden(&name) {
+                continue;
+            }
+
+            // Follow symbolic links to determine whether they're directories.
+            let metadata = std::fs::metadata(entry.path()).or_else(|_| entry.metadata())?;
+            let is_dir = metadata.is_dir();
+            if !is_dir
+                && !self.data.filters.is_empty()
+                && !self.data.filters.iter().any(|filter| matches_filter(filter, &name))
+            {
+                continue;
+            }
+
+            let matched = if self.data.search.is_empty() {
+                Vec::new()
+            } else {
+                match fuzzy::fuzzy_match(&self.data.search, &name) {
+                    Some(found) => found.ranges,
+                    None => continue,
+                }
+            };
+
+            entries.push(FileEntry {
+                path: entry.path(),
+                name,
+                is_dir,
+                size: if is_dir { None } else { Some(metadata.len()) },
+                matched,
+            });
+        }
+
+        entries.sort_by(|a, b| {
+            b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        Ok(entries)
+    }
+
+    fn rebuild_tree(&mut self) {
+        fn push_rows(
+            rows: &mut Vec<TreeRow>,
+            expanded: &HashSet<PathBuf>,
+            path: PathBuf,
+            depth: usize,
+            show_hidden: bool,
+        ) {
+            let is_expanded = expanded.contains(&path);
+            let children =
+                if is_expanded { read_subdirectories(&path, show_hidden) } else { Vec::new() };
+            rows.push(TreeRow {
+                name: file_name(&path),
+                path,
+                depth,
+                expanded: is_expanded,
+                leaf: is_expanded && children.is_empty(),
+            });
+            for child in children {
+                push_rows(rows, expanded, child, depth + 1, show_hidden);
+            }
+        }
+
+        let mut rows = Vec::new();
+        push_rows(&mut rows, &self.expanded, self.data.root.clone(), 0, self.data.show_hidden);
+        self.tree = rows;
+        self.tree_scroll = self.clamp_scroll(self.tree_scroll, self.tree.len());
+    }
+
+    fn toggle_expanded(&mut self, row: usize) {
+        let path = self.tree[row].path.clone();
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+        self.rebuild_tree();
+        self.command_group.repaint();
+    }
+
+    fn click_entry(&mut self, index: usize, toggle: bool, range: bool) {
+        match self.anchor {
+            Some(anchor) if range && self.data.multi_select => {
+                self.selected = (anchor.min(index)..=anchor.max(index)).collect();
+            }
+            _ if toggle && self.data.multi_select => {
+                if !self.selected.remove(&index) {
+                    self.selected.insert(index);
+                }
+                self.anchor = Some(index);
+            }
+            _ => {
+                self.select_only(index);
+                return;
+            }
+        }
+        self.command_group.repaint();
+        self.event_queue.emit_owned(FileEvent::SelectionChange);
+    }
+
+    fn select_only(&mut self, index: usize) {
+        self.anchor = Some(index);
+        if self.selected.len() != 1 || !self.selected.contains(&index) {
+            self.selected.clear();
+            self.selected.insert(index);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(FileEvent::SelectionChange);
+        }
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(FileEvent::Focus);
+        }
+    }
+
+    fn tree_rect(&self) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        AbsoluteRect::new(
+            bounds.origin,
+            Size::new(self.data.tree_width.min(bounds.size.width), bounds.size.height).cast_unit(),
+        )
+    }
+
+    fn list_rect(&self) -> AbsoluteRect {
+        let bounds = self.abs_rect();
+        let tree_width = self.tree_rect().size.width;
+        AbsoluteRect::new(
+            bounds.origin + Size::new(tree_width, 0.0).cast_unit(),
+            Size::new(bounds.size.width - tree_width, bounds.size.height).cast_unit(),
+        )
+    }
+
+    /// Returns the index and rectangle of each row which is at least partially visible in a scrolled pane.
+    fn visible_rows(
+        &self,
+        pane: AbsoluteRect,
+        scroll: f32,
+        count: usize,
+    ) -> impl Iterator<Item = (usize, AbsoluteRect)> {
+        let row_height = self.data.row_height.max(1.0);
+        let first = (scroll / row_height).floor().max(0.0) as usize;
+        let last = (((scroll + pane.size.height) / row_height).ceil().max(0.0) as usize).min(count);
+        (first..last).map(move |i| {
+            (
+                i,
+                AbsoluteRect::new(
+                    AbsolutePoint::new(pane.min_x(), pane.min_y() + i as f32 * row_height - scroll),
+                    Size::new(pane.size.width, row_height).cast_unit(),
+                ),
+            )
+        })
+    }
+
+    fn row_at(
+        &self,
+        pane: AbsoluteRect,
+        scroll: f32,
+        count: usize,
+        point: AbsolutePoint,
+    ) -> Option<usize> {
+        if !pane.contains(point) {
+            return None;
+        }
+        let index = ((point.y - pane.min_y() + scroll) / self.data.row_height.max(1.0)) as usize;
+        if index < count {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn tree_row_at(&self, point: AbsolutePoint) -> Option<usize> {
+        self.row_at(self.tree_rect(), self.tree_scroll, self.tree.len(), point)
+    }
+
+    fn entry_at(&self, point: AbsolutePoint) -> Option<usize> {
+        self.row_at(self.list_rect(), self.list_scroll, self.entries.len(), point)
+    }
+
+    fn clamp_scroll(&self, scroll: f32, count: usize) -> f32 {
+        let content = count as f32 * self.data.row_height;
+        scroll.min(content - self.abs_rect().size.height).max(0.0)
+    }
+
+    fn scroll_to_entry(&mut self, index: usize) {
+        let row_height = self.data.row_height;
+        let height = self.list_rect().size.height;
+        let top = index as f32 * row_height;
+        if top < self.list_scroll {
+            self.list_scroll = top;
+        } else if top + row_height > self.list_scroll + height {
+            self.list_scroll = top + row_height - height;
+        }
+        self.command_group.repaint();
+    }
+}
+
+impl<U, G> Widget for FileBrowserWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Radial or linear gauge showing a value within colored zones.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Angle of the minimum value of a radial gauge, in radians clockwise from the positive x-axis.
+pub const GAUGE_START_ANGLE: f32 = std::f32::consts::PI * 0.75;
+/// Angle swept from the minimum value to the maximum value of a radial gauge, in radians.
+pub const GAUGE_SWEEP: f32 = std::f32::consts::PI * 1.5;
+
+/// Events emitted by a gauge.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum GaugeEvent {
+    /// Emitted when the value rises to or above a threshold, with the threshold.
+    #[event_key(rise)]
+    Rise(f32),
+    /// Emitted when the value falls below a threshold, with the threshold.
+    #[event_key(fall)]
+    Fall(f32),
+}
+
+/// Shape of a gauge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GaugeStyle {
+    /// Needle sweeping an arc.
+    Radial,
+    /// Marker moving along a bar; vertical bars increase upwards.
+    Linear(ui::Orientation),
+}
+
+/// Range of values of a gauge drawn in a color (e.g. a red zone above a safe limit).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaugeZone {
+    pub start: f32,
+    pub end: f32,
+    pub color: Color,
+}
+
+/// Gauge showing a value within colored zones, such as a speedometer or a temperature readout.
+///
+/// The value isn't part of this data; it's the `value` field of the widget, which can be driven by an external data
+/// source. The needle moves smoothly toward new values, and `GaugeEvent`s are emitted as soon as the value crosses
+/// one of the `thresholds`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub min: f32,
+    pub max: f32,
+    pub zones: Vec<GaugeZone>,
+    /// Values which emit an event when crossed.
+    pub thresholds: Vec<f32>,
+    pub style: GaugeStyle,
+    /// Time taken by the needle to reach a new value.
+    pub animation: Duration,
+    /// Whether the value is shown below the needle (radial gauges only).
+    pub show_value: bool,
+    /// Number of decimal places of the value shown.
+    pub precision: usize,
+    /// Unit appended to the value shown (e.g. `" km/h"`).
+    pub unit: String,
+    pub locale: i18n::Locale,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    /// Color of the track outside of any zone.
+    pub background: Color,
+    pub needle: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl Gauge {
+    /// Returns a value as a fraction of the range, from `0.0` to `1.0`.
+    pub fn fraction(&self, value: f32) -> f32 {
+        let range = self.max - self.min;
+        if range.abs() > std::f32::EPSILON {
+            ((value - self.min) / range).max(0.0).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the angle of a value on a radial gauge, in radians clockwise from the positive x-axis.
+    #[inline]
+    pub fn angle(&self, value: f32) -> f32 {
+        GAUGE_START_ANGLE + self.fraction(value) * GAUGE_SWEEP
+    }
+
+    /// Returns the last zone containing a value, if any.
+    pub fn zone(&self, value: f32) -> Option<&GaugeZone> {
+        self.zones.iter().rev().find(|zone| value >= zone.start && value <= zone.end)
+    }
+
+    /// Returns a value as shown on the gauge.
+    pub fn label(&self, value: f32) -> String {
+        format!("{}{}", self.locale.format_decimal(value as f64, self.precision), self.unit)
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Gauge
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = GaugeWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Gauge
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Gauge {
+            min: 0.0,
+            max: 1.0,
+            zones: Vec::new(),
+            thresholds: Vec::new(),
+            style: GaugeStyle::Radial,
+            animation: Duration::from_millis(300),
+            show_value: true,
+            precision: 0,
+            unit: String::new(),
+            locale: i18n::locale(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_background,
+            background: data.scheme.control_inset,
+            needle: data.scheme.primary,
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> GaugeWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+        let value = base::Observed::new(data.min);
+
+        let graph = vg::verbgraph! {
+            GaugeWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+            "value" => _ev in &value.on_change => { change => { obj.retarget(); } }
+        };
+
+        let painter = theme.gauge();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::GaugeState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    value: data.min,
+                })
+                .cast_unit(),
+        );
+
+        let initial = *value.get();
+        GaugeWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            value,
+            target: initial,
+            animation: (initial, Instant::now()),
+            shown: initial,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::GaugeState> for GaugeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::GaugeState {
+        state::GaugeState { rect: self.abs_rect(), data: self.data.clone(), value: self.shown }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct GaugeWidget {
+        widget::MAX,
+
+        <GaugeEvent> EventQueue,
+        <Gauge> State,
+        <state::GaugeState> Painter,
+
+        {
+            /// Value indicated by the gauge; changing it moves the needle.
+            pub value: base::Observed<f32>,
+            /// Value last seen in `value`, used to detect threshold crossings.
+            target: f32,
+            /// Value the needle started moving from, and when.
+            animation: (f32, Instant),
+            /// Value currently indicated by the needle.
+            shown: f32,
+        },
+    }
+}
+
+impl<U, G> GaugeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the value currently indicated by the needle, which lags behind `value` while it moves.
+    #[inline]
+    pub fn shown_value(&self) -> f32 {
+        self.shown
+    }
+
+    /// Moves the needle toward a new value, emitting events for the thresholds crossed.
+    fn retarget(&mut self) {
+        let (previous, target) = (self.target, *self.value.get());
+        if (target - previous).abs() <= std::f32::EPSILON {
+            return;
+        }
+        self.target = target;
+
+        for &threshold in &self.data.thresholds {
+            if previous < threshold && target >= threshold {
+                self.event_queue.emit_owned(GaugeEvent::Rise(threshold));
+            } else if previous >= threshold && target < threshold {
+                self.event_queue.emit_owned(GaugeEvent::Fall(threshold));
+            }
+        }
+
+        self.animation = (self.shown, Instant::now());
+        self.command_group.repaint();
+    }
+
+    /// Advances the needle toward the value.
+    fn animate(&mut self) {
+        if (self.shown - self.target).abs() <= std::f32::EPSILON {
+            return;
+        }
+
+        let (from, start) = self.animation;
+        let duration = self.data.animation.as_secs_f32().max(std::f32::EPSILON);
+        let progress = ((Instant::now() - start).as_secs_f32() / duration).min(1.0);
+        // Ease out (cubic)
+        let eased = 1.0 - (1.0 - progress).powi(3);
+
+        self.shown = if progress < 1.0 { from + (self.target - from) * eased } else { self.target };
+        self.command_group.repaint();
+    }
+}
+
+impl<U, G> Widget for GaugeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.animate();
+        if (self.shown - self.target).abs() > std::f32::EPSILON {
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Titled frame grouping related content.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Frame with a title interrupting its top border, visually grouping related content (e.g. a set of options).
+///
+/// Content is added by pushing widgets into the group box as a layout. The children are moved inside the frame,
+/// preserving their arrangement relative to each other, and the group box resizes itself to fit them (and at least
+/// its title) so that parent layouts reflow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupBox {
+    pub title: String,
+    pub typeface: draw::TypefaceStyle,
+    /// Space between the frame and the content.
+    pub padding: f32,
+    pub corner_radius: f32,
+    /// Color of the title.
+    pub foreground: Color,
+    pub border: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for GroupBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = GroupBoxWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for GroupBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        GroupBox {
+            title: String::new(),
+            typeface: data.typography.body.clone(),
+            padding: 12.0,
+            corner_radius: 3.5,
+            foreground: data.scheme.over_control_outset,
+            border: draw::strengthen(data.scheme.background, 0.2, data.contrast),
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> GroupBoxWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            GroupBoxWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let painter = theme.group_box();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::GroupBoxState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    frame: Default::default(),
+                })
+                .cast_unit(),
+        );
+
+        GroupBoxWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::GroupBoxState> for GroupBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::GroupBoxState {
+        state::GroupBoxState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            frame: self.frame_rect(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct GroupBoxWidget {
+        widget::MAX,
+
+        <GroupBox> State,
+        <state::GroupBoxState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> GroupBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the rectangle of the frame, whose top border runs through the middle of the title.
+    pub fn frame_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let inset = self.title_height() / 2.0;
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x(), rect.min_y() + inset),
+            Size::new(rect.size.width, (rect.size.height - inset).max(0.0)).cast_unit(),
+        )
+    }
+
+    /// Returns the height of the band occupied by the title above the content; zero without a title.
+    fn title_height(&self) -> f32 {
+        if self.data.title.is_empty() {
+            0.0
+        } else {
+            self.data.typeface.size.ceil()
+        }
+    }
+
+    /// Moves the children inside the frame, below the title, and fits the group box to them.
+    fn update_layout(&mut self) {
+        let padding = self.data.padding;
+        let origin = self.abs_rect().origin;
+        let top = self.title_height();
+
+        let content = self.rects.values().fold(None, |content, data| {
+            Some(content.map_or(data.rect, |content: AbsoluteRect| content.union(&data.rect)))
+        });
+
+        if let Some(content) = content {
+            let offset =
+                AbsolutePoint::new(origin.x + padding, origin.y + top + padding) - content.origin;
+            if offset != Default::default() {
+                for (_, data) in &mut self.rects {
+                    data.rect = data.rect.translate(offset);
+                    data.evq.emit_owned(data.rect);
+                }
+            }
+        }
+
+        // the group box is at least large enough for its title
+        let min = self.painter.size_hint(self.derive_state());
+        let content = content.map(|content| content.size).unwrap_or_default();
+        let size = Size::new(
+            (content.width + padding * 2.0).max(min.width),
+            (top + content.height + padding * 2.0).max(min.height),
+        );
+        if size != self.size() {
+            // Notifies the parent layout through `on_transform`.
+            self.set_size(size);
+        }
+    }
+}
+
+impl<U, G> base::Layout for GroupBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ();
+
+    fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for GroupBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
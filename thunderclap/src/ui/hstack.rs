@@ -11,6 +11,7 @@ use {
         display::{self, DisplayCommand, Rect, Size},
         event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
         prelude::*,
+        verbgraph as vg,
     },
 };
 
@@ -54,9 +55,16 @@ struct ChildData {
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct HStack {
+    /// Default `left_margin` of children pushed without a `HStackItem`.
     pub left_margin: f32,
+    /// Default `right_margin` of children pushed without a `HStackItem`.
     pub right_margin: f32,
+    /// Default `alignment` of children pushed without a `HStackItem`.
     pub alignment: Align,
+    /// Space between each pair of adjacent children, in addition to their margins.
+    pub spacing: f32,
+    /// Whether the children form a focus group, which the arrow keys cycle through (see `base::FocusChain`).
+    pub focus_group: bool,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for HStack
@@ -73,28 +81,47 @@ where
     G: base::GraphicalAuxiliary,
 {
     fn from_theme(_theme: &dyn draw::Theme) -> Self {
-        HStack { left_margin: 0.0, right_margin: 0.0, alignment: Align::Begin }
+        HStack {
+            left_margin: 0.0,
+            right_margin: 0.0,
+            alignment: Align::Begin,
+            spacing: 0.0,
+            focus_group: false,
+        }
     }
 
-    fn construct(
-        self,
-        _theme: &dyn draw::Theme,
-        _u_aux: &mut U,
-    ) -> HStackWidget<U, G>
+    fn construct(self, _theme: &dyn draw::Theme, u_aux: &mut U) -> HStackWidget<U, G>
     where
         U: base::UpdateAuxiliary,
         G: base::GraphicalAuxiliary,
     {
         let data = base::Observed::new(self);
 
+        let focus_group_id = u_aux.focus_chain().group();
+        let focus_group = base::LayoutFocusGroup::default();
+        focus_group.set(if data.focus_group { Some(focus_group_id) } else { None });
+
+        let graph = vg::verbgraph! {
+            HStackWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.focus_group.set(if obj.data.focus_group { Some(obj.focus_group_id) } else { None });
+                }
+            }
+        };
+
         HStackWidgetBuilder {
             rect: Default::default(),
-            graph: None,
+            graph: graph.into(),
             data,
 
             rects: IndexMap::new(),
             next_rect_id: 0,
             dirty: true,
+            focus_group,
+            focus_group_id,
         }
         .build()
     }
@@ -125,6 +152,8 @@ crate::widget! {
             rects: IndexMap<u64, ChildData>,
             next_rect_id: u64,
             dirty: bool,
+            focus_group: base::LayoutFocusGroup,
+            focus_group_id: u64,
         },
     }
 }
@@ -134,8 +163,26 @@ where
     U: base::UpdateAuxiliary,
     G: base::GraphicalAuxiliary,
 {
+    /// Lays out `child` like `push`, but inserted at `index` among the children rather than after all of them.
+    /// An `index` past the last child is the same as `push`.
+    pub fn push_at(
+        &mut self,
+        index: usize,
+        data: Option<HStackItem>,
+        child: &mut impl base::LayableWidget,
+    ) {
+        base::Layout::push(self, data, child);
+
+        let mut rects: Vec<_> = std::mem::take(&mut self.rects).into_iter().collect();
+        if let Some(last) = rects.pop() {
+            rects.insert(index.min(rects.len()), last);
+        }
+        self.rects = rects.into_iter().collect();
+    }
+
     fn resize_to_fit(&mut self) {
         let mut max_size = Size::zero();
+        max_size.width = self.data.spacing * self.rects.len().saturating_sub(1) as f32;
         for (_, child) in &self.rects {
             let size: Size = child.rect.size.cast_unit();
             max_size.width += size.width + child.data.left_margin + child.data.right_margin;
@@ -163,7 +210,13 @@ where
 
         let evq = BidirSingleEventQueue::new();
 
-        child.listen_to_layout(base::WidgetLayoutEventsInner { id, evq: evq.secondary() });
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: self.focus_group.clone(),
+        });
 
         let rect = child.abs_rect();
 
@@ -187,7 +240,8 @@ where
     }
 
     fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
-        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.shift_remove(&id)) {
+            self.dirty = true;
             child.listen_to_layout(None);
             if restore_original {
                 child.set_ctxt_rect(data.original_rect);
@@ -209,7 +263,11 @@ where
         self.rect.cast_unit()
     }
 
-    fn update(&mut self, _aux: &mut U) {
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
         if let Some(rect) = self.layout.receive() {
             self.set_ctxt_rect(rect);
             self.dirty = true;
@@ -231,16 +289,20 @@ where
                 }
             }
             for removal in removals {
-                self.rects.remove(&removal);
+                self.rects.shift_remove(&removal);
             }
         }
 
         if self.dirty {
             self.resize_to_fit();
             let abs_rect = self.abs_rect();
+            let spacing = self.data.spacing;
             let mut advance = abs_rect.origin.x;
-            let mut max_height = 0.0;
-            for (_, data) in &mut self.rects {
+            for (i, (_, data)) in self.rects.iter_mut().enumerate() {
+                if i > 0 {
+                    advance += spacing;
+                }
+
                 advance += data.data.left_margin;
 
                 let mut rect = data.rect;
@@ -261,10 +323,6 @@ where
                 data.rect = rect;
 
                 advance += rect.size.width + data.data.right_margin;
-
-                if data.rect.size.height > max_height {
-                    max_height = data.rect.size.height;
-                }
             }
 
             self.dirty = false;
@@ -0,0 +1,414 @@
+//! Display of raster images.
+
+use {
+    crate::{
+        base::{self, HasVisibility, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Events emitted by an image.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageEvent {
+    /// Emitted when the image has been loaded for display.
+    #[event_key(load)]
+    Load,
+    /// Emitted when the image fails to load (e.g. if the data can't be decoded).
+    #[event_key(fail)]
+    Fail,
+}
+
+/// How an image is fitted within the bounds of an [`Image`](struct.Image.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// Stretches the image to fill the bounds, ignoring the aspect ratio.
+    Fill,
+    /// Scales the image to fit entirely within the bounds, centered.
+    Contain,
+    /// Scales the image to fill the bounds, cropping the excess equally on either side.
+    Cover,
+    /// Repeats the image at its natural size, starting from the top-left corner.
+    Tile,
+}
+
+/// Displays an image resource.
+///
+/// The image is only decoded once it's first drawn. Images whose size is unknown (see `ImageResource::size`) are always
+/// stretched to fill the bounds.
+///
+/// Images can also be loaded asynchronously through an [`ImageLoader`](../draw/loader/struct.ImageLoader.html), by
+/// setting `handle`; a skeleton filled with `placeholder` is shown until the image resolves.
+///
+/// Animated images (see `draw::animated::AnimatedImage`) are played by setting `animation`. Playback is controlled through
+/// `ImageWidget::play` and `ImageWidget::pause`, and is also paused while the widget isn't rendered (e.g. while it's
+/// hidden by its layout).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub image: Option<draw::ImageResource>,
+    /// Image from an image loader, shown instead of `image`.
+    pub handle: Option<draw::loader::ImageHandle>,
+    /// Animated image, played instead of `image` and `handle`.
+    pub animation: Option<draw::animated::AnimatedImage>,
+    pub fit: ImageFit,
+    /// Radius of the corners the image is clipped to; `0.0` for square corners.
+    pub corner_radius: f32,
+    /// Color behind the image, visible around contained images and through transparent pixels.
+    pub background: Color,
+    /// Color of the skeleton shown while the image is loading.
+    pub placeholder: Color,
+}
+
+impl Image {
+    /// Returns the image which is shown; the first frame of `animation` if there is one, otherwise the image of
+    /// `handle` if there is one, otherwise `image`.
+    pub fn shown_image(&self) -> Option<draw::ImageResource> {
+        if let Some(ref animation) = self.animation {
+            return animation.frames().first().map(|frame| frame.image.clone());
+        }
+        match self.handle {
+            Some(ref handle) => handle.image(),
+            None => self.image.clone(),
+        }
+    }
+
+    /// Returns `true` if the image of `handle` is still loading.
+    pub fn is_loading(&self) -> bool {
+        self.handle.as_ref().map_or(false, |handle| handle.is_loading())
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Image
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ImageWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Image
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Image {
+            image: None,
+            handle: None,
+            animation: None,
+            fit: ImageFit::Contain,
+            corner_radius: 0.0,
+            background: Color::new(0.0, 0.0, 0.0, 0.0),
+            placeholder: draw::strengthen(data.scheme.background, 0.08, data.contrast),
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> ImageWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            ImageWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        let painter = theme.image();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::ImageState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    resource: None,
+                    source: None,
+                    destinations: Vec::new(),
+                    loading: false,
+                })
+                .cast_unit(),
+        );
+
+        ImageWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            loaded: None,
+            loading: false,
+            animated: None,
+            frame: 0,
+            playing: true,
+            position: Duration::default(),
+            resumed: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::ImageState> for ImageWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::ImageState {
+        let resource = self.current_image().and_then(|image| image.resource());
+        let (source, destinations) =
+            if resource.is_some() { self.image_rects() } else { (None, Vec::new()) };
+
+        state::ImageState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            resource,
+            source,
+            destinations,
+            loading: self.data.is_loading(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ImageWidget {
+        widget::MAX,
+
+        <ImageEvent> EventQueue,
+        <Image> State,
+        <state::ImageState> Painter,
+
+        {
+            /// Image for which the load (or fail) event was last emitted.
+            loaded: Option<draw::ImageResource>,
+            /// Whether the image of the handle was loading as of the last update.
+            loading: bool,
+            /// Animation being played, as of the last update.
+            animated: Option<draw::animated::AnimatedImage>,
+            /// Index of the frame of the animation shown.
+            frame: usize,
+            playing: bool,
+            /// Playback time of the animation up to when it was last paused.
+            position: Duration,
+            /// When playback was last resumed, or `None` while paused (or not rendered).
+            resumed: Option<Instant>,
+        },
+    }
+}
+
+impl<U, G> ImageWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Resumes playing the animation.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Pauses the animation on the current frame.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Returns `true` if the animation is playing (even while the widget isn't rendered).
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Restarts the animation from the first frame.
+    pub fn restart(&mut self) {
+        self.position = Duration::default();
+        self.resumed = self.resumed.map(|_| Instant::now());
+        self.frame = 0;
+        self.command_group.repaint();
+    }
+
+    /// Returns the index of the frame of the animation shown.
+    #[inline]
+    pub fn current_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Returns the image currently shown; the current frame of the animation, if any.
+    fn current_image(&self) -> Option<draw::ImageResource> {
+        match self.data.animation {
+            Some(ref animation) => {
+                animation.frames().get(self.frame).map(|frame| frame.image.clone())
+            }
+            None => self.data.shown_image(),
+        }
+    }
+
+    /// Advances the animation to the frame for the playback time.
+    fn animate(&mut self) {
+        if self.animated != self.data.animation {
+            self.animated = self.data.animation.clone();
+            self.restart();
+        }
+        let animation = match self.animated {
+            Some(ref animation) => animation.clone(),
+            None => return,
+        };
+
+        let running = self.playing && self.visibility().renders();
+        match (running, self.resumed) {
+            (true, None) => self.resumed = Some(Instant::now()),
+            (false, Some(resumed)) => {
+                self.position += resumed.elapsed();
+                self.resumed = None;
+            }
+            _ => {}
+        }
+
+        let elapsed =
+            self.position + self.resumed.map_or(Duration::default(), |resumed| resumed.elapsed());
+        if let Some(frame) = animation.frame_at(elapsed) {
+            if frame != self.frame {
+                self.frame = frame;
+                self.command_group.repaint();
+            }
+        }
+    }
+
+    /// Returns the portion of the image which is shown (in image pixels, or `None` for the entire image),
+    /// and the rectangles it's shown in.
+    fn image_rects(&self) -> (Option<Rect>, Vec<AbsoluteRect>) {
+        let bounds = self.abs_rect();
+        let image = match self.data.shown_image().and_then(|image| image.size()) {
+            Some(size) if size.width > 0.0 && size.height > 0.0 => size,
+            _ => return (None, vec![bounds]),
+        };
+        if bounds.size.width <= 0.0 || bounds.size.height <= 0.0 {
+            return (None, Vec::new());
+        }
+
+        match self.data.fit {
+            ImageFit::Fill => (None, vec![bounds]),
+            ImageFit::Contain => {
+                let scale =
+                    (bounds.size.width / image.width).min(bounds.size.height / image.height);
+                let size = image * scale;
+                (
+                    None,
+                    vec![AbsoluteRect::new(
+                        AbsolutePoint::new(
+                            bounds.origin.x + (bounds.size.width - size.width) / 2.0,
+                            bounds.origin.y + (bounds.size.height - size.height) / 2.0,
+                        ),
+                        size.cast_unit(),
+                    )],
+                )
+            }
+            ImageFit::Cover => (
+                Some(ui::fit_crop(image, Some(bounds.size.width / bounds.size.height))),
+                vec![bounds],
+            ),
+            ImageFit::Tile => {
+                let columns = (bounds.size.width / image.width).ceil() as usize;
+                let rows = (bounds.size.height / image.height).ceil() as usize;
+                let mut destinations = Vec::with_capacity(columns * rows);
+                for row in 0..rows {
+                    for column in 0..columns {
+                        destinations.push(AbsoluteRect::new(
+                            bounds.origin
+                                + Size::new(column as f32 * image.width, row as f32 * image.height)
+                                    .cast_unit(),
+                            image.cast_unit(),
+                        ));
+                    }
+                }
+                (None, destinations)
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for ImageWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        let loading = self.data.is_loading();
+        if loading != self.loading {
+            self.loading = loading;
+            // images which fail to decode are reported once they're drawn
+            if !loading && self.data.handle.is_some() && self.data.shown_image().is_none() {
+                self.event_queue.emit_owned(ImageEvent::Fail);
+            }
+            self.command_group.repaint();
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.animate();
+        // the image is loaded on another thread, which doesn't wake the app once it's done
+        if self.resumed.is_some() || loading {
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        // The image is decoded lazily, once the display is available.
+        // It may also have been unloaded through another clone, in which case it's loaded again.
+        if let Some(image) = self.current_image() {
+            let loaded = image.load(display);
+            // the events of animations are emitted once, for the first frame
+            let shown = self.data.shown_image();
+            if self.loaded != shown {
+                self.event_queue.emit_owned(match loaded {
+                    Some(_) => ImageEvent::Load,
+                    None => ImageEvent::Fail,
+                });
+                self.loaded = shown;
+                self.command_group.repaint();
+            }
+        }
+
+        if let Some(ref handle) = self.data.handle {
+            handle.unload_evicted(display);
+        }
+
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! Rotary knob controlling a continuous value.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Angle of the minimum value, in radians clockwise from the positive x-axis.
+pub const KNOB_START_ANGLE: f32 = std::f32::consts::PI * 0.75;
+/// Angle swept from the minimum value to the maximum value, in radians.
+pub const KNOB_SWEEP: f32 = std::f32::consts::PI * 1.5;
+
+/// Events emitted by a knob.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum KnobEvent {
+    /// Emitted when the user starts dragging the knob.
+    #[event_key(begin_drag)]
+    BeginDrag,
+    /// Emitted when the value is changed by the user, with the new value.
+    #[event_key(change)]
+    Change(f32),
+    /// Emitted when the user stops dragging the knob, with the final value.
+    #[event_key(end_drag)]
+    EndDrag(f32),
+}
+
+/// How dragging the cursor turns a knob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnobDragMode {
+    /// The knob follows the angle of the cursor around its center.
+    Circular,
+    /// Dragging up increases the value and dragging down decreases it.
+    Vertical,
+}
+
+/// Generates an unbound terminal which handles dragging and scrolling of the knob.
+pub fn knob_handler<U, G>() -> vg::UnboundQueueHandler<KnobWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        KnobWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.drag = Some((*pos, obj.data.value));
+                obj.interaction.insert(state::InteractionState::PRESSED);
+                obj.event_queue.emit_owned(KnobEvent::BeginDrag);
+                obj.repaint();
+            }
+        }
+
+        mouse_move => {
+            let (pos, modifiers) = *event.get();
+            if let Some((last, value)) = obj.drag {
+                let factor = if modifiers.shift { obj.data.fine_factor } else { 1.0 };
+                let value = obj.data.clamp(value + obj.drag_delta(last, pos) * factor);
+                obj.drag = Some((pos, value));
+                obj.set_value(obj.data.snap(value));
+                event.consume();
+            }
+
+            let hovered = obj.abs_rect().contains(pos);
+            if hovered != obj.interaction.contains(state::InteractionState::HOVERED) {
+                obj.interaction.set(state::InteractionState::HOVERED, hovered);
+                obj.repaint();
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.drag.is_some() && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.drag = None;
+                obj.interaction.remove(state::InteractionState::PRESSED);
+                obj.event_queue.emit_owned(KnobEvent::EndDrag(obj.data.value));
+                obj.repaint();
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, modifiers)) = event.with(|(pos, _, _)| {
+                !obj.data.disabled && obj.drag.is_none() && bounds.contains(*pos)
+            }) {
+                let factor = if modifiers.shift { obj.data.fine_factor } else { 1.0 };
+                let range = obj.data.max - obj.data.min;
+                let value = obj.data.value + delta.y / obj.data.sensitivity * range * factor;
+                obj.set_value(obj.data.snap(obj.data.clamp(value)));
+            }
+        }
+    }
+}
+
+/// Rotary knob controlling a value within a range, turned by dragging the cursor, or by scrolling over it.
+///
+/// Holding shift while dragging or scrolling adjusts the value more finely. The value is shown in a tooltip while
+/// the knob is dragged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Knob {
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Interval which the value is snapped to, relative to `min`; `0.0` to not snap.
+    pub step: f32,
+    /// Number of tick marks drawn around the knob, spread evenly from `min` to `max`.
+    pub ticks: u32,
+    pub drag_mode: KnobDragMode,
+    /// Distance in pixels to drag vertically (or to scroll) to sweep the whole range.
+    pub sensitivity: f32,
+    /// Factor applied to adjustments while shift is held.
+    pub fine_factor: f32,
+    /// Whether the value is shown in a tooltip while dragging.
+    pub show_value: bool,
+    /// Number of decimal places of the value shown in the tooltip.
+    pub precision: usize,
+    /// Unit appended to the value shown in the tooltip (e.g. `" dB"`).
+    pub unit: String,
+    pub locale: i18n::Locale,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the unfilled portion of the arc around the knob.
+    pub track: Color,
+    /// Color of the portion of the arc from `min` to the value.
+    pub fill: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl Knob {
+    /// Clamps a value to the range of the knob.
+    #[inline]
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.max(self.min).min(self.max)
+    }
+
+    /// Snaps a value to the `step` of the knob.
+    pub fn snap(&self, value: f32) -> f32 {
+        if self.step > 0.0 {
+            self.clamp(self.min + ((value - self.min) / self.step).round() * self.step)
+        } else {
+            value
+        }
+    }
+
+    /// Returns the value as a fraction of the range, from `0.0` to `1.0`.
+    pub fn fraction(&self) -> f32 {
+        let range = self.max - self.min;
+        if range.abs() > std::f32::EPSILON {
+            ((self.value - self.min) / range).max(0.0).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the angle of the value, in radians clockwise from the positive x-axis.
+    #[inline]
+    pub fn angle(&self) -> f32 {
+        KNOB_START_ANGLE + self.fraction() * KNOB_SWEEP
+    }
+
+    /// Returns the value as shown in the tooltip.
+    pub fn label(&self) -> String {
+        format!("{}{}", self.locale.format_decimal(self.value as f64, self.precision), self.unit)
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Knob
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = KnobWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Knob
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Knob {
+            value: 0.0,
+            min: 0.0,
+            max: 1.0,
+            step: 0.0,
+            ticks: 11,
+            drag_mode: KnobDragMode::Vertical,
+            sensitivity: 200.0,
+            fine_factor: 0.1,
+            show_value: true,
+            precision: 2,
+            unit: String::new(),
+            locale: i18n::locale(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_outset,
+            background: data.scheme.control_outset,
+            track: data.scheme.control_inset,
+            fill: data.scheme.primary,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> KnobWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            KnobWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("knob", knob_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.knob();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::KnobState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        KnobWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            drag: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::KnobState> for KnobWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::KnobState {
+        state::KnobState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct KnobWidget {
+        widget::MAX,
+
+        <KnobEvent> EventQueue,
+        <Knob> State,
+        <state::KnobState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            /// Last cursor position and unsnapped value of the current drag.
+            drag: Option<(AbsolutePoint, f32)>,
+        },
+    }
+}
+
+impl<U, G> KnobWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns `true` if the knob is being dragged.
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Returns the change in value from dragging the cursor from `from` to `to`, before the fine-adjust factor.
+    fn drag_delta(&self, from: AbsolutePoint, to: AbsolutePoint) -> f32 {
+        let range = self.data.max - self.data.min;
+        match self.data.drag_mode {
+            KnobDragMode::Vertical => (from.y - to.y) / self.data.sensitivity * range,
+            KnobDragMode::Circular => {
+                let center = self.abs_rect().center();
+                let angle = |point: AbsolutePoint| (point.y - center.y).atan2(point.x - center.x);
+                let mut delta = angle(to) - angle(from);
+                // the shortest way around, so that crossing the gap below the knob doesn't jump
+                if delta > std::f32::consts::PI {
+                    delta -= std::f32::consts::PI * 2.0;
+                } else if delta < -std::f32::consts::PI {
+                    delta += std::f32::consts::PI * 2.0;
+                }
+                delta / KNOB_SWEEP * range
+            }
+        }
+    }
+
+    fn set_value(&mut self, value: f32) {
+        if (value - self.data.value).abs() > std::f32::EPSILON {
+            self.data.value = value;
+            self.event_queue.emit_owned(KnobEvent::Change(value));
+        }
+    }
+}
+
+impl<U, G> Widget for KnobWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
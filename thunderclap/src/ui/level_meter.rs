@@ -0,0 +1,365 @@
+//! Audio level meter with peak and RMS bars.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Events emitted by a level meter.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelMeterEvent {
+    /// Emitted when the peak level of a channel reaches `clip_db`, with the index of the channel.
+    #[event_key(clip)]
+    Clip(usize),
+}
+
+/// Displayed levels of a single channel, in dBFS.
+///
+/// Silence is represented as negative infinity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelLevel {
+    pub peak: f32,
+    pub rms: f32,
+    /// Highest recent peak, shown as a line above the bars.
+    pub held_peak: f32,
+    /// Whether the channel has clipped since the last call to `LevelMeterWidget::reset_clip`.
+    pub clipped: bool,
+}
+
+impl Default for ChannelLevel {
+    fn default() -> Self {
+        ChannelLevel {
+            peak: std::f32::NEG_INFINITY,
+            rms: std::f32::NEG_INFINITY,
+            held_peak: std::f32::NEG_INFINITY,
+            clipped: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChannelMeter {
+    level: ChannelLevel,
+    /// Levels measured since the last update, as linear amplitudes.
+    input_peak: f32,
+    input_rms: f32,
+    held_at: Instant,
+}
+
+impl ChannelMeter {
+    fn new(now: Instant) -> Self {
+        ChannelMeter { level: Default::default(), input_peak: 0.0, input_rms: 0.0, held_at: now }
+    }
+}
+
+/// Converts a linear amplitude to dBFS.
+#[inline]
+pub fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.abs().log10()
+}
+
+/// Multi-channel meter showing the peak and RMS levels of an audio signal.
+///
+/// Levels are supplied as blocks of samples (see `LevelMeterWidget::feed`) or as pre-measured amplitudes
+/// (see `LevelMeterWidget::set_levels`). Displayed levels rise immediately and fall at `decay` dB per second,
+/// measured from the time elapsed between updates, so the meter keeps falling after the input stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelMeter {
+    pub channels: usize,
+    /// Direction in which the levels increase; vertical meters increase upwards.
+    pub orientation: ui::Orientation,
+    /// Level at the empty end of the meter, in dBFS.
+    pub min_db: f32,
+    /// Level at the full end of the meter, in dBFS.
+    pub max_db: f32,
+    /// Level from which `warning_color` is used, in dBFS.
+    pub warning_db: f32,
+    /// Level from which `clip_color` is used and the channel is considered clipping, in dBFS.
+    pub clip_db: f32,
+    /// Rate at which displayed levels fall, in dB per second.
+    pub decay: f32,
+    /// How long the held peak is shown before it starts falling.
+    pub peak_hold: Duration,
+    /// Distance between the bars of adjacent channels.
+    pub spacing: f32,
+    pub color: Color,
+    pub warning_color: Color,
+    pub clip_color: Color,
+    pub background: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for LevelMeter
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = LevelMeterWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for LevelMeter
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        LevelMeter {
+            channels: 2,
+            orientation: ui::Orientation::Vertical,
+            min_db: -60.0,
+            max_db: 0.0,
+            warning_db: -12.0,
+            clip_db: -0.1,
+            decay: 24.0,
+            peak_hold: Duration::from_millis(1500),
+            spacing: 2.0,
+            color: data.scheme.primary,
+            warning_color: Color::new(0.96, 0.72, 0.14, 1.0),
+            clip_color: data.scheme.error,
+            background: data.scheme.control_inset,
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> LevelMeterWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            LevelMeterWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        let now = Instant::now();
+        let meters = vec![ChannelMeter::new(now); data.channels];
+
+        let painter = theme.level_meter();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::LevelMeterState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    levels: vec![Default::default(); data.channels],
+                })
+                .cast_unit(),
+        );
+
+        LevelMeterWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            meters,
+            last_update: now,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::LevelMeterState> for LevelMeterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::LevelMeterState {
+        state::LevelMeterState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            levels: self.levels(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct LevelMeterWidget {
+        widget::MAX,
+
+        <LevelMeterEvent> EventQueue,
+        <LevelMeter> State,
+        <state::LevelMeterState> Painter,
+
+        {
+            meters: Vec<ChannelMeter>,
+            last_update: Instant,
+        },
+    }
+}
+
+impl<U, G> LevelMeterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Measures a block of samples of a single channel.
+    ///
+    /// Blocks fed between two updates are combined; the loudest peak and RMS are displayed.
+    pub fn feed(&mut self, channel: usize, samples: &[f32]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>()
+            / samples.len() as f32)
+            .sqrt();
+        self.set_levels(channel, peak, rms);
+    }
+
+    /// Measures a block of interleaved samples of all the channels.
+    pub fn feed_interleaved(&mut self, samples: &[f32]) {
+        let channels = self.data.channels;
+        if channels == 0 {
+            return;
+        }
+
+        let mut channel_samples = Vec::with_capacity(samples.len() / channels + 1);
+        for channel in 0..channels {
+            channel_samples.clear();
+            channel_samples.extend(samples.iter().skip(channel).step_by(channels));
+            self.feed(channel, &channel_samples);
+        }
+    }
+
+    /// Supplies pre-measured peak and RMS levels of a single channel, as linear amplitudes (where `1.0` is full scale).
+    pub fn set_levels(&mut self, channel: usize, peak: f32, rms: f32) {
+        self.sync_channels();
+        if let Some(meter) = self.meters.get_mut(channel) {
+            meter.input_peak = meter.input_peak.max(peak.abs());
+            meter.input_rms = meter.input_rms.max(rms.abs());
+        }
+    }
+
+    /// Returns the displayed levels of each channel.
+    pub fn levels(&self) -> Vec<ChannelLevel> {
+        self.meters.iter().map(|meter| meter.level).collect()
+    }
+
+    /// Clears the clip indicators of all the channels.
+    pub fn reset_clip(&mut self) {
+        for meter in &mut self.meters {
+            meter.level.clipped = false;
+        }
+        self.command_group.repaint();
+    }
+
+    fn sync_channels(&mut self) {
+        let now = self.last_update;
+        self.meters.resize_with(self.data.channels, || ChannelMeter::new(now));
+    }
+
+    /// Applies the levels measured since the last update, and lets the displayed levels decay.
+    fn update_levels(&mut self) {
+        self.sync_channels();
+
+        let now = Instant::now();
+        let fall = (now - self.last_update).as_secs_f32() * self.data.decay;
+        self.last_update = now;
+
+        // Levels below the meter look the same, so they don't need a repaint.
+        let min_db = self.data.min_db;
+        let visible = |db: f32| db.max(min_db);
+
+        let mut changed = false;
+        for (i, meter) in self.meters.iter_mut().enumerate() {
+            let old = meter.level;
+            let level = &mut meter.level;
+
+            level.peak = amplitude_to_db(meter.input_peak).max(level.peak - fall);
+            level.rms = amplitude_to_db(meter.input_rms).max(level.rms - fall);
+            meter.input_peak = 0.0;
+            meter.input_rms = 0.0;
+
+            if level.peak >= level.held_peak {
+                level.held_peak = level.peak;
+                meter.held_at = now;
+            } else if now - meter.held_at >= self.data.peak_hold {
+                level.held_peak = (level.held_peak - fall).max(level.peak);
+            }
+
+            if level.peak >= self.data.clip_db && !level.clipped {
+                level.clipped = true;
+                self.event_queue.emit_owned(LevelMeterEvent::Clip(i));
+            }
+
+            changed |= visible(old.peak) != visible(level.peak)
+                || visible(old.rms) != visible(level.rms)
+                || visible(old.held_peak) != visible(level.held_peak)
+                || old.clipped != level.clipped;
+        }
+
+        if changed {
+            self.command_group.repaint();
+        }
+    }
+}
+
+impl<U, G> Widget for LevelMeterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.update_levels();
+
+        // levels fall over time, so keep updating until every channel has fallen below the meter.
+        let min_db = self.data.min_db;
+        if self.meters.iter().any(|meter| {
+            meter.level.peak > min_db || meter.level.rms > min_db || meter.level.held_peak > min_db
+        }) {
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,586 @@
+//! Append-only view of log output, with ANSI colors.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui::{self, code_editor::measure_advance},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::collections::VecDeque,
+};
+
+/// Horizontal space between the edges of the view and the text.
+const TEXT_PADDING: f32 = 4.0;
+/// Number of columns between tab stops.
+const TAB_WIDTH: usize = 8;
+
+/// Color of a span of log output, as set by ANSI escape codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogColor {
+    /// Default foreground/background of the log view.
+    Default,
+    /// One of the 16 colors of the palette (see `LogView::palette`).
+    Palette(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Default for LogColor {
+    #[inline]
+    fn default() -> Self {
+        LogColor::Default
+    }
+}
+
+/// Style of a span of log output, as set by ANSI escape codes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LogStyle {
+    pub foreground: LogColor,
+    pub background: LogColor,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// Span of a log line with a single style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogSpan {
+    /// Text of the span, without escape codes and with tabs expanded.
+    pub text: String,
+    pub style: LogStyle,
+}
+
+/// Line of log output, split into styled spans.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LogLine {
+    pub spans: Vec<LogSpan>,
+}
+
+impl LogLine {
+    /// Parses a line of output, applying (and updating) a style carried over from previous lines.
+    pub fn parse(text: &str, style: &mut LogStyle) -> Self {
+        let mut line = LogLine::default();
+        line.append(text, style);
+        line
+    }
+
+    /// Returns the text of the line, without escape codes.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Parses more output onto the end of the line.
+    fn append(&mut self, text: &str, style: &mut LogStyle) {
+        let mut column: usize = self.spans.iter().map(|span| span.text.chars().count()).sum();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    if chars.peek() != Some(&'[') {
+                        // not a control sequence; the escaped character is dropped
+                        chars.next();
+                        continue;
+                    }
+                    chars.next();
+
+                    let mut params = String::new();
+                    while let Some(c) = chars.next() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            // only SGR (i.e. styling) sequences are interpreted
+                            if c == 'm' {
+                                apply_sgr(&params, style);
+                            }
+                            break;
+                        }
+                        params.push(c);
+                    }
+                }
+                '\t' => {
+                    let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                    for _ in 0..spaces {
+                        self.push_char(' ', *style);
+                    }
+                    column += spaces;
+                }
+                c if c.is_control() => {}
+                c => {
+                    self.push_char(c, *style);
+                    column += 1;
+                }
+            }
+        }
+    }
+
+    fn push_char(&mut self, c: char, style: LogStyle) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.text.push(c),
+            _ => self.spans.push(LogSpan { text: c.to_string(), style }),
+        }
+    }
+}
+
+/// Applies the parameters of an SGR ("Select Graphic Rendition") sequence to a style.
+fn apply_sgr(params: &str, style: &mut LogStyle) {
+    // an empty parameter is the same as 0 (reset)
+    let mut codes = params.split(';').map(|code| code.parse::<u16>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        match code {
+            0 => *style = LogStyle::default(),
+            1 => style.bold = true,
+            3 => style.italic = true,
+            22 => style.bold = false,
+            23 => style.italic = false,
+            30..=37 => style.foreground = LogColor::Palette((code - 30) as u8),
+            38 => style.foreground = extended_color(&mut codes),
+            39 => style.foreground = LogColor::Default,
+            40..=47 => style.background = LogColor::Palette((code - 40) as u8),
+            48 => style.background = extended_color(&mut codes),
+            49 => style.background = LogColor::Default,
+            90..=97 => style.foreground = LogColor::Palette((code - 90 + 8) as u8),
+            100..=107 => style.background = LogColor::Palette((code - 100 + 8) as u8),
+            _ => {}
+        }
+    }
+}
+
+/// Parses the color of an extended (`38`/`48`) SGR code; either 256-color (`5;n`) or true color (`2;r;g;b`).
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> LogColor {
+    let mut next = || codes.next().unwrap_or(0).min(255) as u8;
+    match next() {
+        5 => match next() {
+            n @ 0..=15 => LogColor::Palette(n),
+            n @ 16..=231 => {
+                // 6x6x6 color cube
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                let n = n - 16;
+                LogColor::Rgb(level(n / 36), level(n / 6 % 6), level(n % 6))
+            }
+            n => {
+                // grayscale ramp
+                let v = 8 + (n - 232) * 10;
+                LogColor::Rgb(v, v, v)
+            }
+        },
+        2 => LogColor::Rgb(next(), next(), next()),
+        _ => LogColor::Default,
+    }
+}
+
+/// Generates an unbound terminal which handles scrolling a log view.
+pub fn log_view_handler<U, G>() -> vg::UnboundQueueHandler<LogViewWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        LogViewWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, _)) = event.with(|(pos, _, _)| bounds.contains(*pos)) {
+                let scroll = obj.scroll - delta.y;
+                obj.scroll_to(scroll);
+            }
+        }
+    }
+}
+
+/// Append-only view of log output (e.g. from a build tool or a debugger), which understands ANSI color codes.
+///
+/// Output is appended with `LogViewWidget::push_str`/`push_line`, and the oldest lines are dropped beyond
+/// `capacity`.
+/// The view stays pinned to the bottom as output is appended, unless scrolled up with the mouse wheel; scrolling
+/// back to the bottom pins it again.
+/// Only the lines in view are laid out and drawn, so large logs (hundreds of thousands of lines) stay cheap.
+///
+/// SGR escape codes (colors, bold and italic) are interpreted, and other escape codes are removed.
+/// The typeface should be monospaced, and lines aren't wrapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogView {
+    /// Number of lines kept.
+    pub capacity: usize,
+    pub typeface: draw::TypefaceStyle,
+    pub line_height: f32,
+    pub foreground: Color,
+    pub background: Color,
+    /// Colors of the 16 ANSI colors; the 8 normal colors followed by the 8 bright colors.
+    pub palette: [Color; 16],
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for LogView
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = LogViewWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for LogView
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        let typeface = draw::TypefaceStyle { size: 13.0, ..data.typography.body.clone() };
+        LogView {
+            capacity: 500_000,
+            line_height: (typeface.size * 1.4).round(),
+            typeface,
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            palette: [
+                base::color_from_urgba(36, 41, 46, 1.0),
+                base::color_from_urgba(215, 58, 73, 1.0),
+                base::color_from_urgba(40, 167, 69, 1.0),
+                base::color_from_urgba(219, 171, 9, 1.0),
+                base::color_from_urgba(3, 102, 214, 1.0),
+                base::color_from_urgba(111, 66, 193, 1.0),
+                base::color_from_urgba(27, 124, 131, 1.0),
+                base::color_from_urgba(106, 115, 125, 1.0),
+                base::color_from_urgba(149, 157, 165, 1.0),
+                base::color_from_urgba(203, 36, 49, 1.0),
+                base::color_from_urgba(34, 134, 58, 1.0),
+                base::color_from_urgba(176, 136, 0, 1.0),
+                base::color_from_urgba(0, 92, 197, 1.0),
+                base::color_from_urgba(90, 50, 163, 1.0),
+                base::color_from_urgba(50, 146, 188, 1.0),
+                base::color_from_urgba(209, 213, 218, 1.0),
+            ],
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> LogViewWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            LogViewWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.advance = measure_advance(&obj.data.typeface);
+                    obj.truncate();
+                    obj.follow();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("log_view", log_view_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.log_view();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::LogViewState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    lines: Vec::new(),
+                    thumb: None,
+                    advance: 0.0,
+                })
+                .cast_unit(),
+        );
+
+        let advance = measure_advance(&data.typeface);
+        LogViewWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            lines: VecDeque::new(),
+            open: false,
+            style: Default::default(),
+            scroll: 0.0,
+            pinned: true,
+            advance,
+            feed: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::LogViewState> for LogViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::LogViewState {
+        let rect = self.abs_rect();
+        let line_height = self.data.line_height;
+
+        // only the lines in view are laid out
+        let first = (self.scroll / line_height).floor().max(0.0) as usize;
+        let count = (rect.size.height / line_height).ceil() as usize + 1;
+        let lines = (first..(first + count).min(self.lines.len()))
+            .map(|i| state::LogLineState {
+                spans: self.lines[i]
+                    .spans
+                    .iter()
+                    .scan(0, |column, span| {
+                        let start = *column;
+                        *column += span.text.chars().count();
+                        Some(state::LogSpanState {
+                            text: span.text.clone(),
+                            column: start,
+                            foreground: self
+                                .resolve(span.style.foreground)
+                                .unwrap_or(self.data.foreground),
+                            background: self.resolve(span.style.background),
+                            style: match (span.style.bold, span.style.italic) {
+                                (false, false) => draw::TextStyle::Regular,
+                                (false, true) => draw::TextStyle::RegularItalic,
+                                (true, false) => draw::TextStyle::Bold,
+                                (true, true) => draw::TextStyle::BoldItalic,
+                            },
+                        })
+                    })
+                    .collect(),
+                rect: AbsoluteRect::new(
+                    AbsolutePoint::new(
+                        rect.min_x() + TEXT_PADDING,
+                        rect.min_y() + i as f32 * line_height - self.scroll,
+                    ),
+                    Size::new(rect.size.width - TEXT_PADDING * 2.0, line_height).cast_unit(),
+                ),
+            })
+            .collect();
+
+        let content = self.lines.len() as f32 * line_height;
+        let thumb = if content > rect.size.height {
+            let height = (rect.size.height * rect.size.height / content).max(16.0);
+            let y = self.scroll / self.max_scroll() * (rect.size.height - height);
+            Some(AbsoluteRect::new(
+                AbsolutePoint::new(rect.max_x() - 6.0, rect.min_y() + y),
+                Size::new(4.0, height).cast_unit(),
+            ))
+        } else {
+            None
+        };
+
+        state::LogViewState { rect, data: self.data.clone(), lines, thumb, advance: self.advance }
+    }
+
+    fn on_transform(&mut self) {
+        self.follow();
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct LogViewWidget {
+        widget::MAX,
+
+        <LogView> State,
+        <state::LogViewState> Painter,
+
+        {
+            lines: VecDeque<LogLine>,
+            /// Whether the last line hasn't been terminated, so that more output is appended to it.
+            open: bool,
+            /// Style carried over to the next output.
+            style: LogStyle,
+            scroll: f32,
+            pinned: bool,
+            /// Width of each character.
+            advance: f32,
+            feed: Option<base::UpdateFeed<String>>,
+        },
+    }
+}
+
+impl<U, G> LogViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Appends output, which may contain several lines and escape codes.
+    ///
+    /// Output following the last newline is appended to by the next call, hence output can be pushed as it's read
+    /// (as long as escape codes aren't split).
+    pub fn push_str(&mut self, text: &str) {
+        let mut rest = text;
+        loop {
+            let (line, terminated) = match rest.find('\n') {
+                Some(end) => (&rest[..end], true),
+                None => (rest, false),
+            };
+
+            if terminated || !line.is_empty() {
+                if !self.open {
+                    self.lines.push_back(LogLine::default());
+                }
+                let style = &mut self.style;
+                if let Some(last) = self.lines.back_mut() {
+                    last.append(line, style);
+                }
+                self.open = !terminated;
+            }
+
+            if !terminated {
+                break;
+            }
+            rest = &rest[line.len() + 1..];
+        }
+
+        self.truncate();
+        self.follow();
+        self.command_group.repaint();
+    }
+
+    /// Appends a complete line.
+    pub fn push_line(&mut self, line: &str) {
+        self.push_str(line);
+        self.push_str("\n");
+    }
+
+    /// Appends the output pushed into `feed` (as with `push_str`), as a batch once per update. Replaces the previous
+    /// feed, if any.
+    pub fn set_feed(&mut self, feed: base::UpdateFeed<String>) {
+        self.feed = Some(feed);
+    }
+
+    /// Stops appending output from the feed, dropping any output pending.
+    pub fn clear_feed(&mut self) {
+        self.feed = None;
+    }
+
+    /// Removes all the lines, and resets the style.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.open = false;
+        self.style = Default::default();
+        self.scroll = 0.0;
+        self.pinned = true;
+        self.command_group.repaint();
+    }
+
+    /// Returns the lines kept, from oldest to most recent.
+    #[inline]
+    pub fn lines(&self) -> &VecDeque<LogLine> {
+        &self.lines
+    }
+
+    /// Returns how far the view is scrolled down.
+    #[inline]
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Returns whether the view is pinned to the bottom, following new output.
+    #[inline]
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /// Scrolls the view, clamping it to the extent of the lines.
+    /// Scrolling to the bottom pins the view to new output, and scrolling anywhere else unpins it.
+    pub fn scroll_to(&mut self, scroll: f32) {
+        let max = self.max_scroll();
+        let scroll = scroll.max(0.0).min(max);
+        self.pinned = scroll >= max;
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Scrolls to the bottom, pinning the view to new output.
+    pub fn scroll_to_bottom(&mut self) {
+        let max = self.max_scroll();
+        self.scroll_to(max);
+    }
+
+    fn max_scroll(&self) -> f32 {
+        (self.lines.len() as f32 * self.data.line_height - self.abs_rect().size.height).max(0.0)
+    }
+
+    /// Resolves a color of a span, or `None` for the default color.
+    fn resolve(&self, color: LogColor) -> Option<Color> {
+        match color {
+            LogColor::Default => None,
+            LogColor::Palette(i) => Some(self.data.palette[i as usize % 16]),
+            LogColor::Rgb(r, g, b) => Some(base::color_from_urgba(r, g, b, 1.0)),
+        }
+    }
+
+    /// Drops the oldest lines beyond the capacity, keeping the same lines in view if unpinned.
+    fn truncate(&mut self) {
+        let capacity = self.data.capacity.max(1);
+        if self.lines.len() > capacity {
+            let dropped = self.lines.len() - capacity;
+            self.lines.drain(..dropped);
+            self.scroll = (self.scroll - dropped as f32 * self.data.line_height).max(0.0);
+            self.command_group.repaint();
+        }
+    }
+
+    /// Keeps the view at the bottom if pinned, otherwise keeps the scroll within the lines.
+    fn follow(&mut self) {
+        let max = self.max_scroll();
+        let scroll = if self.pinned { max } else { self.scroll.min(max) };
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            self.command_group.repaint();
+        }
+    }
+}
+
+impl<U, G> Widget for LogViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(output) = self.feed.as_ref().map(|feed| feed.drain().concat()) {
+            if !output.is_empty() {
+                self.push_str(&output);
+            }
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
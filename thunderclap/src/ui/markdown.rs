@@ -0,0 +1,629 @@
+//! Rendering of a subset of Markdown.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw,
+        geom::*,
+        ui::{self, RichTextLayout, SpanStyle, TextSpan},
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, DisplayListBuilder, GraphicsDisplay, GraphicsDisplayPaint, Rect,
+            Size,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Text size of each heading level, relative to the size of body text.
+const HEADING_SCALE: [f32; 6] = [2.0, 1.5, 1.25, 1.1, 1.0, 0.9];
+
+/// Events emitted by a Markdown view.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownEvent {
+    /// Emitted when a link is clicked, with the destination of the link.
+    #[event_key(link_activated)]
+    LinkActivated(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListMarker {
+    Bullet,
+    Number(u64),
+}
+
+/// A block of Markdown, with unparsed inline text.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    ListItem { depth: usize, marker: ListMarker, text: String },
+    Code(String),
+    Rule,
+}
+
+/// Parses a list item marker (`-`, `*`, `+`, `1.` or `1)`) followed by a space, returning the marker and the remaining text.
+fn parse_list_marker(line: &str) -> Option<(ListMarker, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if line.starts_with(bullet) {
+            return Some((ListMarker::Bullet, &line[2..]));
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && digits <= 9 {
+        let rest = &line[digits..];
+        if rest.starts_with(". ") || rest.starts_with(") ") {
+            return Some((ListMarker::Number(line[..digits].parse().ok()?), &rest[2..]));
+        }
+    }
+
+    None
+}
+
+/// Whether a line is a horizontal rule; three or more of either `-`, `*` or `_`, optionally separated by spaces.
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && "-*_".contains(chars[0]) && chars.iter().all(|&c| c == chars[0])
+}
+
+/// Splits Markdown into blocks.
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Option<String> = None;
+    let mut code: Option<String> = None;
+    // Whether the last block is a list item which following lines continue.
+    let mut in_list_item = false;
+
+    for line in text.lines() {
+        if let Some(ref mut code_text) = code {
+            if line.trim_start().starts_with("```") {
+                blocks.push(Block::Code(code.take().unwrap()));
+            } else {
+                if !code_text.is_empty() {
+                    code_text.push('\n');
+                }
+                code_text.push_str(line);
+            }
+            continue;
+        }
+
+        let indent: usize = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        let trimmed = line.trim();
+
+        let block = if trimmed.is_empty() {
+            in_list_item = false;
+            None
+        } else if trimmed.starts_with("```") {
+            code = Some(String::new());
+            None
+        } else if trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let rest = &trimmed[level..];
+            if level <= 6 && (rest.is_empty() || rest.starts_with(' ')) {
+                Some(Block::Heading(level, rest.trim_end_matches('#').trim().to_string()))
+            } else {
+                Some(Block::Paragraph(trimmed.to_string()))
+            }
+        } else if is_rule(trimmed) {
+            Some(Block::Rule)
+        } else if let Some((marker, rest)) = parse_list_marker(trimmed) {
+            Some(Block::ListItem { depth: indent / 2, marker, text: rest.trim().to_string() })
+        } else {
+            // Continuation of a paragraph or list item.
+            let continued = if in_list_item {
+                match blocks.last_mut() {
+                    Some(Block::ListItem { ref mut text, .. }) => Some(text),
+                    _ => None,
+                }
+            } else {
+                paragraph.as_mut()
+            };
+            match continued {
+                Some(text) => {
+                    text.push(' ');
+                    text.push_str(trimmed);
+                }
+                None => paragraph = Some(trimmed.to_string()),
+            }
+            continue;
+        };
+
+        if let Some(text) = paragraph.take() {
+            blocks.push(Block::Paragraph(text));
+        }
+        if let Some(block) = block {
+            in_list_item = match block {
+                Block::ListItem { .. } => true,
+                _ => false,
+            };
+            blocks.push(block);
+        }
+    }
+
+    if let Some(text) = paragraph {
+        blocks.push(Block::Paragraph(text));
+    }
+    if let Some(text) = code {
+        blocks.push(Block::Code(text));
+    }
+
+    blocks
+}
+
+/// State shared while parsing the inline text of every block.
+struct InlineContext<'a> {
+    code: &'a SpanStyle,
+    links: &'a mut Vec<String>,
+}
+
+/// Parses inline emphasis (`*`, `_`, `**`, `__`), code (`` ` ``) and links (`[text](destination)`) into spans.
+fn parse_inline(text: &str, base: &SpanStyle, context: &mut InlineContext) -> Vec<TextSpan> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut style = base.clone();
+    let mut buffer = String::new();
+
+    fn flush(spans: &mut Vec<TextSpan>, buffer: &mut String, style: &SpanStyle) {
+        if !buffer.is_empty() {
+            spans.push(TextSpan { text: std::mem::take(buffer), style: style.clone() });
+        }
+    }
+
+    let find = |from: usize, target: &[char]| {
+        (from..chars.len()).find(|&i| chars[i..].starts_with(target))
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.map_or(false, |next| next.is_ascii_punctuation()) => {
+                buffer.push(next.unwrap());
+                i += 2;
+            }
+            '`' => match find(i + 1, &['`']) {
+                Some(end) => {
+                    flush(&mut spans, &mut buffer, &style);
+                    let mut code = context.code.clone();
+                    code.link = style.link;
+                    spans.push(TextSpan { text: chars[i + 1..end].iter().collect(), style: code });
+                    i = end + 1;
+                }
+                None => {
+                    buffer.push(c);
+                    i += 1;
+                }
+            },
+            '*' | '_' if next == Some(c) => {
+                flush(&mut spans, &mut buffer, &style);
+                style.bold = !style.bold;
+                i += 2;
+            }
+            '*' | '_' => {
+                // Underscores within words (e.g. snake_case) aren't emphasis.
+                let within_word = c == '_'
+                    && i > 0
+                    && chars[i - 1].is_alphanumeric()
+                    && next.map_or(false, char::is_alphanumeric);
+                if within_word {
+                    buffer.push(c);
+                } else {
+                    flush(&mut spans, &mut buffer, &style);
+                    style.italic = !style.italic;
+                }
+                i += 1;
+            }
+            '[' => {
+                let link = find(i + 1, &[']', '('])
+                    .and_then(|middle| find(middle + 2, &[')']).map(|end| (middle, end)));
+                match link {
+                    Some((middle, end)) if style.link.is_none() => {
+                        flush(&mut spans, &mut buffer, &style);
+                        let label: String = chars[i + 1..middle].iter().collect();
+                        let destination: String = chars[middle + 2..end].iter().collect();
+
+                        let mut link_style = style.clone();
+                        link_style.link = Some(context.links.len());
+                        context.links.push(destination.trim().to_string());
+                        spans.extend(parse_inline(&label, &link_style, context));
+                        i = end + 1;
+                    }
+                    _ => {
+                        buffer.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                buffer.push(c);
+                i += 1;
+            }
+        }
+    }
+    flush(&mut spans, &mut buffer, &style);
+
+    spans
+}
+
+/// Renders Markdown text; headings, paragraphs, (nested) lists, fenced code blocks, horizontal rules, emphasis, inline code and links
+/// are supported.
+///
+/// The height of the view follows its content, and text is wrapped to its width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Markdown {
+    pub text: String,
+    /// Typeface of body text; headings are scaled from it.
+    pub typeface: draw::TypefaceStyle,
+    /// Typeface of inline code and code blocks (typically monospace).
+    pub code_typeface: draw::Typeface,
+    pub color: Color,
+    pub link_color: Color,
+    pub code_color: Color,
+    /// Color behind code blocks, also used for horizontal rules.
+    pub code_background: Color,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Markdown
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = MarkdownWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Markdown
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Markdown {
+            text: String::new(),
+            typeface: data.typography.body.clone(),
+            code_typeface: data.typography.body.typeface.clone(),
+            color: data.scheme.over_background,
+            link_color: data.scheme.primary,
+            code_color: data.scheme.over_control_inset,
+            code_background: data.scheme.control_inset,
+        }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, u_aux: &mut U) -> MarkdownWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            MarkdownWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.blocks = parse_blocks(&obj.data.text);
+                    obj.update_layout();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("markdown", markdown_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let blocks = parse_blocks(&data.text);
+
+        let mut markdown = MarkdownWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            blocks,
+            texts: Vec::new(),
+            fills: Vec::new(),
+            links: Vec::new(),
+            hovered_link: None,
+            pressed_link: None,
+            previous_rect: Default::default(),
+            dirty: true,
+        }
+        .build();
+
+        markdown.update_layout();
+        markdown.previous_rect = markdown.rect;
+
+        markdown
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for MarkdownWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        if (self.previous_rect.size.width - self.rect.size.width).abs() > std::f32::EPSILON {
+            self.dirty = true;
+        } else if self.previous_rect.origin != self.rect.origin {
+            let diff = (self.rect.origin - self.previous_rect.origin).cast_unit();
+            for text in &mut self.texts {
+                text.translate(diff);
+            }
+            for fill in &mut self.fills {
+                *fill = fill.translate(diff.cast_unit());
+            }
+        }
+
+        self.previous_rect = self.rect;
+        self.layout.notify(self.abs_rect());
+        self.repaint();
+    }
+}
+
+fn link_at(texts: &[RichTextLayout], point: AbsolutePoint) -> Option<usize> {
+    texts.iter().find_map(|text| text.link_at(point))
+}
+
+/// Generates an unbound terminal which handles link hovering and clicking.
+pub fn markdown_handler<U, G>(
+) -> vg::UnboundQueueHandler<MarkdownWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        MarkdownWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let texts = &obj.texts;
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && link_at(texts, *pos).is_some()
+            }) {
+                obj.pressed_link = obj.link_at(*pos);
+            }
+        }
+
+        mouse_release => {
+            if let Some(pressed) = obj.pressed_link.take() {
+                let texts = &obj.texts;
+                if event
+                    .with(|(pos, button, _)| {
+                        *button == base::MouseButton::Left && link_at(texts, *pos) == Some(pressed)
+                    })
+                    .is_some()
+                {
+                    let destination = obj.links[pressed].clone();
+                    obj.event_queue.emit_owned(MarkdownEvent::LinkActivated(destination));
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            obj.hovered_link = obj.link_at(pos);
+        }
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct MarkdownWidget {
+        widget::MAX,
+
+        <MarkdownEvent> EventQueue,
+        <Markdown> State,
+
+        {
+            blocks: Vec<Block>,
+            texts: Vec<RichTextLayout>,
+            /// Code block backgrounds and horizontal rules.
+            fills: Vec<AbsoluteRect>,
+            /// Destinations of the links, indexed by link identifier.
+            links: Vec<String>,
+            hovered_link: Option<usize>,
+            pressed_link: Option<usize>,
+            previous_rect: RelativeRect,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> MarkdownWidget<U, G> {
+    fn link_at(&self, point: AbsolutePoint) -> Option<usize> {
+        link_at(&self.texts, point)
+    }
+
+    fn update_layout(&mut self) {
+        let rect = self.abs_rect();
+        let data = &self.data;
+        let size = data.typeface.size;
+        let spacing = size * 0.75;
+        let indent = size * 1.5;
+
+        let base = SpanStyle::default();
+        let code = SpanStyle {
+            color: Some(data.code_color),
+            typeface: Some(data.code_typeface.clone()),
+            ..Default::default()
+        };
+        let mut links = Vec::new();
+        let mut context = InlineContext { code: &code, links: &mut links };
+
+        let layout_text = |spans: &[TextSpan],
+                           typeface: &draw::TypefaceStyle,
+                           x: f32,
+                           y: f32,
+                           width: Option<f32>| {
+            ui::layout_rich_text(
+                spans,
+                typeface,
+                data.color,
+                data.link_color,
+                AbsolutePoint::new(x, y),
+                width.map(|width| width.max(0.0)),
+                ui::TextAlign::Left,
+            )
+        };
+
+        let mut texts = Vec::new();
+        let mut fills = Vec::new();
+        let mut y = rect.origin.y;
+        for (i, block) in self.blocks.iter().enumerate() {
+            if i > 0 {
+                y += spacing;
+            }
+
+            match block {
+                Block::Heading(level, text) => {
+                    if i > 0 {
+                        y += spacing / 2.0;
+                    }
+                    let typeface = draw::TypefaceStyle {
+                        size: size * HEADING_SCALE[*level - 1],
+                        ..data.typeface.clone()
+                    };
+                    let heading = SpanStyle { bold: true, ..Default::default() };
+                    let spans = parse_inline(text, &heading, &mut context);
+                    let text =
+                        layout_text(&spans, &typeface, rect.origin.x, y, Some(rect.size.width));
+                    y += text.bounds.size.height;
+                    texts.push(text);
+                }
+                Block::Paragraph(text) => {
+                    let spans = parse_inline(text, &base, &mut context);
+                    let text = layout_text(
+                        &spans,
+                        &data.typeface,
+                        rect.origin.x,
+                        y,
+                        Some(rect.size.width),
+                    );
+                    y += text.bounds.size.height;
+                    texts.push(text);
+                }
+                Block::ListItem { depth, marker, text } => {
+                    // Consecutive items are closer together than paragraphs.
+                    if let Some(Block::ListItem { .. }) = i.checked_sub(1).map(|i| &self.blocks[i])
+                    {
+                        y -= spacing * 0.6;
+                    }
+
+                    let x = rect.origin.x + *depth as f32 * indent;
+                    let marker = match marker {
+                        ListMarker::Bullet => "\u{2022}".to_string(),
+                        ListMarker::Number(number) => format!("{}.", number),
+                    };
+                    texts.push(layout_text(&[TextSpan::new(marker)], &data.typeface, x, y, None));
+
+                    let spans = parse_inline(text, &base, &mut context);
+                    let text = layout_text(
+                        &spans,
+                        &data.typeface,
+                        x + indent,
+                        y,
+                        Some(rect.max_x() - x - indent),
+                    );
+                    y += text.bounds.size.height;
+                    texts.push(text);
+                }
+                Block::Code(text) => {
+                    let padding = size * 0.5;
+                    let spans = [TextSpan { text: text.clone(), style: code.clone() }];
+                    let text = layout_text(
+                        &spans,
+                        &data.typeface,
+                        rect.origin.x + padding,
+                        y + padding,
+                        None,
+                    );
+                    let height = text.bounds.size.height + padding * 2.0;
+                    fills.push(AbsoluteRect::new(
+                        AbsolutePoint::new(rect.origin.x, y),
+                        Size::new(rect.size.width, height).cast_unit(),
+                    ));
+                    y += height;
+                    texts.push(text);
+                }
+                Block::Rule => {
+                    fills.push(AbsoluteRect::new(
+                        AbsolutePoint::new(rect.origin.x, y + spacing / 2.0),
+                        Size::new(rect.size.width, 1.0).cast_unit(),
+                    ));
+                    y += spacing + 1.0;
+                }
+            }
+        }
+
+        self.texts = texts;
+        self.fills = fills;
+        self.links = links;
+
+        let height = y - rect.origin.y;
+        self.set_ctxt_rect(AbsoluteRect::new(
+            rect.origin,
+            Size::new(rect.size.width, height).cast_unit(),
+        ));
+    }
+}
+
+impl<U, G> Widget for MarkdownWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        if self.dirty {
+            self.dirty = false;
+            self.update_layout();
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.hovered_link.is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle_clip(self.abs_rect().cast_unit(), true);
+        for fill in &self.fills {
+            builder.push_rectangle(
+                fill.cast_unit(),
+                GraphicsDisplayPaint::Fill(self.data.code_background.into()),
+                None,
+            );
+        }
+        for text in &self.texts {
+            text.push_to(&mut builder);
+        }
+        self.command_group.push(display, &builder.build(), Default::default(), None, None);
+    }
+}
@@ -0,0 +1,545 @@
+//! Single line text field which enforces an input mask.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui::{self, LogicalTextArea, TextAreaEvent},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a masked input when its completeness changes.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum MaskedInputEvent {
+    /// Emitted when every slot of the mask has been filled, with the formatted text.
+    #[event_key(complete)]
+    Complete(String),
+    /// Emitted when a complete value stops being complete (e.g. a character is removed).
+    #[event_key(incomplete)]
+    Incomplete,
+}
+
+/// A single character position of an [`InputMask`](struct.InputMask.html).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaskSlot {
+    /// An ASCII digit.
+    Digit,
+    /// An alphabetic character.
+    Letter,
+    /// An alphanumeric character.
+    Alphanumeric,
+    /// Any character.
+    Any,
+    /// Any of the given characters.
+    OneOf(String),
+    /// A character inserted automatically, which isn't part of the value.
+    Literal(char),
+}
+
+impl MaskSlot {
+    /// Returns `true` if the slot is filled by the user, rather than being a literal.
+    #[inline]
+    pub fn is_input(&self) -> bool {
+        match self {
+            MaskSlot::Literal(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if `c` can fill the slot.
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            MaskSlot::Digit => c.is_ascii_digit(),
+            MaskSlot::Letter => c.is_alphabetic(),
+            MaskSlot::Alphanumeric => c.is_alphanumeric(),
+            MaskSlot::Any => !c.is_control(),
+            MaskSlot::OneOf(chars) => chars.contains(c),
+            MaskSlot::Literal(_) => false,
+        }
+    }
+}
+
+/// Pattern which the text of a [`MaskedInput`](struct.MaskedInput.html) must follow, made of slots filled by the
+/// user and literal characters inserted automatically.
+///
+/// The value of a masked input only holds the characters filling the slots; the literals are added when formatting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMask {
+    slots: Vec<MaskSlot>,
+}
+
+impl InputMask {
+    /// Parses a mask pattern, where:
+    /// - `9` is a digit.
+    /// - `a` is a letter.
+    /// - `*` is a letter or digit.
+    /// - `?` is any character.
+    /// - `[...]` is any of the characters within the brackets.
+    /// - `\` makes the next character a literal.
+    ///
+    /// Every other character is a literal. For example, `(999) 999-9999` is a phone number,
+    /// `[0123]9/[01]9/9999` is a date and `9999 9999 9999 9999` is a credit card number.
+    pub fn new(pattern: &str) -> Self {
+        let mut slots = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            slots.push(match c {
+                '9' => MaskSlot::Digit,
+                'a' => MaskSlot::Letter,
+                '*' => MaskSlot::Alphanumeric,
+                '?' => MaskSlot::Any,
+                '[' => MaskSlot::OneOf(chars.by_ref().take_while(|&c| c != ']').collect()),
+                '\\' => match chars.next() {
+                    Some(c) => MaskSlot::Literal(c),
+                    None => break,
+                },
+                c => MaskSlot::Literal(c),
+            });
+        }
+        InputMask { slots }
+    }
+
+    /// Creates a mask from its slots.
+    #[inline]
+    pub fn from_slots(slots: Vec<MaskSlot>) -> Self {
+        InputMask { slots }
+    }
+
+    /// Returns the slots of the mask.
+    #[inline]
+    pub fn slots(&self) -> &[MaskSlot] {
+        &self.slots
+    }
+
+    /// Returns the number of characters in a complete value (i.e. the number of slots which aren't literals).
+    pub fn capacity(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_input()).count()
+    }
+
+    /// Returns the index of the slot filled by the character at `index` of a value, or the number of slots if the
+    /// value would be full.
+    pub fn slot_of(&self, index: usize) -> usize {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_input())
+            .nth(index)
+            .map_or(self.slots.len(), |(i, _)| i)
+    }
+
+    /// Returns `true` if every character of `value` can fill its slot, and there's a slot for every character.
+    pub fn accepts(&self, value: &str) -> bool {
+        let mut slots = self.slots.iter().filter(|slot| slot.is_input());
+        value.chars().all(|c| slots.next().map_or(false, |slot| slot.accepts(c)))
+    }
+
+    /// Returns `true` if `value` fills every slot of the mask.
+    pub fn is_complete(&self, value: &str) -> bool {
+        self.accepts(value) && value.chars().count() == self.capacity()
+    }
+
+    /// Extracts the value from text which may be formatted (e.g. pasted), skipping the literals of the mask and
+    /// dropping characters which can't fill their slot.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut value = String::new();
+        let mut slot = 0;
+        for c in text.chars() {
+            // literals are skipped over, consuming the character if it's the literal itself
+            let mut consumed = false;
+            while let Some(MaskSlot::Literal(literal)) = self.slots.get(slot) {
+                slot += 1;
+                if *literal == c {
+                    consumed = true;
+                    break;
+                }
+            }
+            if consumed {
+                continue;
+            }
+
+            if let Some(input) = self.slots.get(slot) {
+                if input.accepts(c) {
+                    value.push(c);
+                    slot += 1;
+                }
+            }
+        }
+        value
+    }
+
+    /// Formats a value with the literals of the mask.
+    ///
+    /// Unfilled slots are shown as `placeholder` if given; otherwise, the text ends at the first unfilled slot,
+    /// including the literals before it (so that they appear as soon as the previous slot is filled).
+    pub fn format(&self, value: &str, placeholder: Option<char>) -> String {
+        let mut text = String::new();
+        let mut chars = value.chars();
+        for slot in &self.slots {
+            match slot {
+                MaskSlot::Literal(literal) => text.push(*literal),
+                _ => match (chars.next(), placeholder) {
+                    (Some(c), _) | (None, Some(c)) => text.push(c),
+                    (None, None) => break,
+                },
+            }
+        }
+        text
+    }
+}
+
+/// Returns the byte index of the character at `index` of `text`, or the length of `text`.
+fn byte_index(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map_or(text.len(), |(i, _)| i)
+}
+
+/// Single line text field whose text follows an [`InputMask`](struct.InputMask.html), such as a phone number, date
+/// or credit card number.
+///
+/// Editing is handled the same as a [`TextArea`](struct.TextArea.html) (see `LogicalTextArea`), except that typed
+/// characters are only accepted if they can fill their slot of the mask, and the literals of the mask are inserted
+/// automatically. `text` only holds the characters filling the slots (e.g. `5551234567` for `(555) 123-4567`), and
+/// `cursor` is a character index into it; text assigned to it is normalized to fit the mask.
+///
+/// Besides the events of a text area, `mask_queue` emits `MaskedInputEvent::Complete` once every slot has been filled,
+/// and `MaskedInputEvent::Incomplete` if the value stops being complete. An incomplete value is outlined with the
+/// error color once the field loses focus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskedInput {
+    pub mask: InputMask,
+    /// Characters filling the slots of the mask, without the literals.
+    pub text: String,
+    /// Text shown while the value is empty and the field isn't focused.
+    pub placeholder: String,
+    /// Character shown in place of unfilled slots.
+    pub placeholder_char: char,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub focus: Color,
+    /// Color of the outline of incomplete values.
+    pub error: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+    pub cursor: usize,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for MaskedInput
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = MaskedInputWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for MaskedInput
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        MaskedInput {
+            mask: InputMask::new(""),
+            text: String::new(),
+            placeholder: String::new(),
+            placeholder_char: '_',
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            focus: data.scheme.focus,
+            error: data.scheme.error,
+            contrast: data.contrast,
+            disabled: false,
+            cursor: 0,
+        }
+    }
+
+    fn construct(mut self, theme: &dyn draw::Theme, u_aux: &mut U) -> MaskedInputWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        self.text = self.mask.normalize(&self.text);
+        self.cursor = self.cursor.min(self.text.chars().count());
+        let complete = self.mask.is_complete(&self.text);
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            MaskedInputWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.update_value();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<MaskedInputWidget<U, G>, U>()
+                .bind(u_aux.window_queue()),
+        );
+        graph = graph.add(
+            "text_area",
+            ui::text_area_handler::<MaskedInputWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+
+        let painter = theme.masked_input();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::MaskedInputState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    display_text: String::new(),
+                    filled: 0,
+                    cursor: 0,
+                    complete,
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        MaskedInputWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            mask_queue: RcEventQueue::new(),
+            interaction: state::InteractionState::empty(),
+            complete,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::MaskedInputState> for MaskedInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::MaskedInputState {
+        let mask = &self.data.mask;
+        let display_text = mask.format(&self.data.text, Some(self.data.placeholder_char));
+        let filled = match self.data.text.chars().count() {
+            0 => 0,
+            count => byte_index(&display_text, mask.slot_of(count - 1) + 1),
+        };
+        let cursor = byte_index(&display_text, mask.slot_of(self.data.cursor));
+
+        state::MaskedInputState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            display_text,
+            filled,
+            cursor,
+            complete: self.complete,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct MaskedInputWidget {
+        widget::MAX,
+
+        <TextAreaEvent> EventQueue,
+        <MaskedInput> State,
+        <state::MaskedInputState> Painter,
+
+        {
+            /// Emits when the value becomes complete or incomplete.
+            pub mask_queue: RcEventQueue<MaskedInputEvent>,
+            interaction: state::InteractionState,
+            complete: bool,
+        },
+    }
+}
+
+impl<U, G> MaskedInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns `true` if every slot of the mask is filled.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Returns the value formatted with the literals of the mask, up to the first unfilled slot.
+    pub fn formatted_text(&self) -> String {
+        self.data.mask.format(&self.data.text, None)
+    }
+
+    /// Fits the value to the mask after a change, emitting an event if its completeness changed.
+    fn update_value(&mut self) {
+        let value = self.data.mask.normalize(&self.data.text);
+        if value != self.data.text {
+            self.data.text = value;
+        }
+        let len = self.data.text.chars().count();
+        if self.data.cursor > len {
+            self.data.cursor = len;
+        }
+
+        let complete = self.data.mask.is_complete(&self.data.text);
+        if complete != self.complete {
+            self.complete = complete;
+            let event = if complete {
+                MaskedInputEvent::Complete(self.formatted_text())
+            } else {
+                MaskedInputEvent::Incomplete
+            };
+            self.mask_queue.emit_owned(event);
+        }
+    }
+}
+
+impl<U, G> ui::InteractiveWidget for MaskedInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        match event {
+            ui::InteractionEvent::Focus => {
+                self.repaint();
+                self.event_queue.emit_owned(TextAreaEvent::Focus);
+            }
+            ui::InteractionEvent::Blur => {
+                self.repaint();
+                self.event_queue.emit_owned(TextAreaEvent::Blur);
+            }
+            ui::InteractionEvent::Deactivate | ui::InteractionEvent::Activate => self.repaint(),
+            _ => {}
+        }
+    }
+}
+
+impl<U, G> LogicalTextArea for MaskedInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn event_queue(&mut self) -> &mut RcEventQueue<TextAreaEvent> {
+        &mut self.event_queue
+    }
+
+    fn push_char(&mut self, c: char) {
+        let cursor = self.data.cursor;
+        let mut value = self.data.text.clone();
+        value.insert(byte_index(&value, cursor), c);
+        // rejected if the slot doesn't accept the character, or if it would push the following characters out of
+        // their slots; this also skips typed (or pasted) literals, since they're inserted automatically
+        if self.data.mask.accepts(&value) {
+            self.data.text = value;
+            self.data.cursor += 1;
+        }
+    }
+
+    fn remove_char(&mut self) {
+        let cursor = self.data.cursor;
+        if cursor > 0 && cursor <= self.data.text.chars().count() {
+            let index = byte_index(&self.data.text, cursor - 1);
+            self.data.text.remove(index);
+            self.data.cursor -= 1;
+        }
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        let cursor = self.data.cursor as isize + offset;
+        if cursor >= 0 && cursor <= self.data.text.chars().count() as isize {
+            self.data.cursor = cursor as _;
+        }
+    }
+
+    fn input_hints(&self) -> base::InputHints {
+        let mut slots = self.data.mask.slots().iter().filter(|slot| slot.is_input()).peekable();
+        if slots.peek().is_some() && slots.all(|slot| *slot == MaskSlot::Digit) {
+            base::InputHints::NUMERIC
+        } else {
+            base::InputHints::TEXT.without_autocorrect()
+        }
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        Some(self.formatted_text())
+    }
+}
+
+impl<U, G> Widget for MaskedInputWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: self.input_hints(),
+                area: self.abs_rect(),
+            });
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Scaled-down overview of scrollable content, for navigating it.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Point, Rect, Size, Vector},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a minimap.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum MinimapEvent {
+    /// Emitted when the user starts dragging the viewport.
+    #[event_key(begin_drag)]
+    BeginDrag,
+    /// Emitted when the viewport has been moved by the user, with its new origin in content coordinates.
+    /// The content should be scrolled to it.
+    #[event_key(scroll)]
+    Scroll(Point),
+    /// Emitted when the user stops dragging the viewport.
+    #[event_key(end_drag)]
+    EndDrag,
+}
+
+/// Generates an unbound terminal which handles dragging the viewport of a minimap.
+pub fn minimap_handler<U, G>() -> vg::UnboundQueueHandler<MinimapWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        MinimapWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let content = obj.content_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && content.contains(*pos)
+            }) {
+                let point = obj.to_content(*pos);
+                let viewport = obj.data.viewport;
+                // pressing outside the viewport centers it on the cursor before dragging it
+                let grab = if viewport.contains(point) {
+                    point - viewport.origin
+                } else {
+                    viewport.size.to_vector() / 2.0
+                };
+                obj.drag = Some(grab);
+                obj.interaction.insert(state::InteractionState::PRESSED);
+                obj.event_queue.emit_owned(MinimapEvent::BeginDrag);
+                obj.scroll_to(point - grab);
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            if let Some(grab) = obj.drag {
+                let point = obj.to_content(pos);
+                obj.scroll_to(point - grab);
+            }
+
+            let hovered = obj.viewport_rect().contains(pos);
+            if hovered != obj.interaction.contains(state::InteractionState::HOVERED) {
+                obj.interaction.toggle(state::InteractionState::HOVERED);
+                obj.command_group.repaint();
+            }
+        }
+
+        mouse_release => {
+            if obj.drag.is_some() {
+                if event.with(|(_, button, _)| *button == base::MouseButton::Left).is_some() {
+                    obj.drag = None;
+                    obj.interaction.remove(state::InteractionState::PRESSED);
+                    obj.command_group.repaint();
+                    obj.event_queue.emit_owned(MinimapEvent::EndDrag);
+                }
+            }
+        }
+    }
+}
+
+/// Scaled-down overview of content which is larger than its view, with a rectangle showing the viewport; dragging
+/// the rectangle (or pressing elsewhere) scrolls the content.
+///
+/// The content is a snapshot of display commands in content coordinates (e.g. those of
+/// `AnnotationCanvasWidget::export`), which is replayed scaled down to fit the minimap. The snapshot is taken by the
+/// app, and should be replaced when the content changes.
+///
+/// The minimap doesn't own the scrolled content; the app pairs them, scrolling the content on `MinimapEvent::Scroll`
+/// (e.g. with `CodeEditorWidget::scroll_to`) and updating `viewport` whenever the content is scrolled otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Minimap {
+    /// Snapshot of the content.
+    pub content: draw::CommandSnapshot,
+    /// Size of the whole content.
+    pub content_size: Size,
+    /// Part of the content in view, in content coordinates.
+    pub viewport: Rect,
+    pub background: Color,
+    /// Color filling the viewport rectangle.
+    pub viewport_fill: Color,
+    /// Color of the outline of the viewport rectangle.
+    pub viewport_border: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Minimap
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = MinimapWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Minimap
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Minimap {
+            content: Default::default(),
+            content_size: Size::new(1.0, 1.0),
+            viewport: Rect::new(Point::zero(), Size::new(1.0, 1.0)),
+            background: data.scheme.control_inset,
+            viewport_fill: data.scheme.focus,
+            viewport_border: data.scheme.list_selection,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> MinimapWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            MinimapWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("minimap", minimap_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.minimap();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::MinimapState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    content: Default::default(),
+                    viewport: Default::default(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        MinimapWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            drag: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::MinimapState> for MinimapWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::MinimapState {
+        state::MinimapState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            content: self.content_rect(),
+            viewport: self.viewport_rect(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct MinimapWidget {
+        widget::MAX,
+
+        <MinimapEvent> EventQueue,
+        <Minimap> State,
+        <state::MinimapState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            /// Offset of the cursor from the origin of the viewport while dragging, in content coordinates.
+            drag: Option<Vector>,
+        },
+    }
+}
+
+impl<U, G> MinimapWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the ratio of the size of the minimap to the size of the content, fitting the whole content.
+    pub fn scale(&self) -> f32 {
+        let size = self.data.content_size;
+        if size.width <= 0.0 || size.height <= 0.0 {
+            return 1.0;
+        }
+        (self.rect.size.width / size.width).min(self.rect.size.height / size.height)
+    }
+
+    /// Returns the rectangle which the content is drawn in; centered horizontally, at the top of the minimap.
+    pub fn content_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let size = self.data.content_size * self.scale();
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x() + (rect.size.width - size.width) / 2.0, rect.min_y()),
+            size.cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the viewport within the minimap.
+    pub fn viewport_rect(&self) -> AbsoluteRect {
+        let scale = self.scale();
+        let viewport = self.data.viewport;
+        AbsoluteRect::new(
+            self.content_rect().origin + viewport.origin.to_vector().cast_unit() * scale,
+            (viewport.size * scale).cast_unit(),
+        )
+    }
+
+    /// Converts a point within the minimap into content coordinates.
+    pub fn to_content(&self, point: AbsolutePoint) -> Point {
+        ((point - self.content_rect().origin) / self.scale()).to_point().cast_unit()
+    }
+
+    /// Moves the viewport, keeping it within the content, and emits `MinimapEvent::Scroll` if it moved.
+    pub fn scroll_to(&mut self, origin: Point) {
+        let max = (self.data.content_size - self.data.viewport.size).to_vector();
+        let origin = Point::new(origin.x.min(max.x).max(0.0), origin.y.min(max.y).max(0.0));
+        if origin != self.data.viewport.origin {
+            self.data.viewport.origin = origin;
+            self.event_queue.emit_owned(MinimapEvent::Scroll(origin));
+        }
+    }
+}
+
+impl<U, G> Widget for MinimapWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.drag.is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Grabbing);
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
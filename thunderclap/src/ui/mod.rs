@@ -1,24 +1,88 @@
 //! The main part of Thunderclap; a widget toolkit built atop Reclutch.
 
+pub mod anchor_layout;
+pub mod annotation_canvas;
+pub mod auto_complete;
+pub mod avatar;
+pub mod badge;
 pub mod button;
+pub mod canvas;
+pub mod card;
+pub mod carousel;
+pub mod chart;
 pub mod checkbox;
+pub mod chip_input;
+pub mod clipboard_history;
+pub mod code_editor;
+pub mod collapsible;
+pub mod constraint_layout;
 pub mod container;
+pub mod cropper;
+pub mod date_picker;
+pub mod dialog;
+pub mod dock;
+pub mod dock_layout;
+pub mod expander;
+pub mod fab;
+pub mod file_browser;
+pub mod gauge;
+pub mod group_box;
 pub mod hstack;
+pub mod image;
+pub mod knob;
 pub mod label;
+pub mod level_meter;
+pub mod log_view;
 pub mod margins;
+pub mod markdown;
+pub mod masked_input;
 pub mod max_fill;
+pub mod minimap;
+pub mod overlay;
+pub mod pagination;
+pub mod property_grid;
+pub mod password_box;
+#[cfg(feature = "extra-widgets")]
+pub mod qr_code;
+pub mod radial_menu;
+pub mod rich_label;
 pub mod scroll_bar;
+pub mod search_box;
+pub mod sketch_canvas;
+pub mod sparkline;
+pub mod split_pane;
+pub mod surface;
+pub mod swatch_grid;
 pub mod text_area;
+pub mod tile_view;
+pub mod title_bar;
+pub mod tree_table;
+pub mod toast;
+pub mod video_surface;
 pub mod vstack;
+pub mod waveform;
+pub mod wizard;
+pub mod zstack;
 
 #[macro_use]
 pub mod core;
 
 pub use {
-    button::*, checkbox::*, container::*, hstack::*, label::*, margins::*, max_fill::*,
-    scroll_bar::*, text_area::*, vstack::*,
+    anchor_layout::*, annotation_canvas::*, auto_complete::*, avatar::*, badge::*, button::*,
+    canvas::*, card::*, carousel::*, chart::*, checkbox::*, chip_input::*, clipboard_history::*,
+    code_editor::*, collapsible::*, constraint_layout::*, container::*, cropper::*, date_picker::*,
+    dialog::*, dock::*, dock_layout::*, expander::*, fab::*, file_browser::*, gauge::*,
+    group_box::*, hstack::*, image::*, knob::*, label::*, level_meter::*, log_view::*, margins::*,
+    markdown::*, masked_input::*, max_fill::*, minimap::*, overlay::*, pagination::*,
+    password_box::*, property_grid::*, radial_menu::*, rich_label::*, scroll_bar::*, search_box::*,
+    sketch_canvas::*, sparkline::*, split_pane::*, surface::*, swatch_grid::*, text_area::*,
+    tile_view::*, title_bar::*, toast::*, tree_table::*, video_surface::*, vstack::*, waveform::*,
+    wizard::*, zstack::*,
 };
 
+#[cfg(feature = "extra-widgets")]
+pub use qr_code::*;
+
 use {
     crate::{
         base,
@@ -94,6 +158,38 @@ impl Default for Align {
     }
 }
 
+/// Direction along which a widget is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Left to right.
+    Horizontal,
+    /// Top to bottom.
+    Vertical,
+}
+
+/// A corner of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// Returns `true` if the corner is on the left side.
+    #[inline]
+    pub fn is_left(self) -> bool {
+        self == Corner::TopLeft || self == Corner::BottomLeft
+    }
+
+    /// Returns `true` if the corner is on the top side.
+    #[inline]
+    pub fn is_top(self) -> bool {
+        self == Corner::TopLeft || self == Corner::TopRight
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InteractionEvent {
     Pressed(AbsolutePoint),
@@ -102,6 +198,12 @@ pub enum InteractionEvent {
     EndHover(AbsolutePoint),
     Focus,
     Blur,
+    /// Focus has become visible, because the keyboard was used while focused.
+    FocusVisible,
+    /// The window has been deactivated.
+    Deactivate,
+    /// The window has been activated again.
+    Activate,
 }
 
 pub trait InteractiveWidget: ContextuallyRectangular {
@@ -109,6 +211,11 @@ pub trait InteractiveWidget: ContextuallyRectangular {
     fn mouse_bounds(&self) -> RelativeRect;
     fn disabled(&self) -> bool;
     fn on_interaction_event(&mut self, event: InteractionEvent);
+
+    /// Returns the entry through which the widget can be focused from the keyboard, if any (see `base::FocusChain`).
+    fn focus_entry(&self) -> Option<&base::FocusEntry> {
+        None
+    }
 }
 
 pub trait WidgetDataTarget<U, G>
@@ -164,8 +271,13 @@ pub fn basic_interaction_handler<W: InteractiveWidget, U: base::UpdateAuxiliary>
                     && *button == base::MouseButton::Left
                     && obj.interaction().contains(state::InteractionState::PRESSED)
             }) {
-                obj.interaction().remove(state::InteractionState::PRESSED);
+                obj.interaction()
+                    .remove(state::InteractionState::PRESSED | state::InteractionState::FOCUS_VISIBLE);
                 obj.interaction().insert(state::InteractionState::FOCUSED);
+                if let Some(entry) = obj.focus_entry() {
+                    entry.set_focused(true);
+                }
+                aux.record_metric(base::Metric::WidgetActivated(base::widget_kind::<W>()));
                 obj.on_interaction_event(InteractionEvent::Released(*pos));
                 obj.on_interaction_event(InteractionEvent::Focus);
             }
@@ -184,12 +296,53 @@ pub fn basic_interaction_handler<W: InteractiveWidget, U: base::UpdateAuxiliary>
             }
         }
 
+        key_press => {
+            let interaction = *obj.interaction();
+            if interaction.contains(state::InteractionState::FOCUSED)
+                && !interaction.contains(state::InteractionState::FOCUS_VISIBLE)
+            {
+                obj.interaction().insert(state::InteractionState::FOCUS_VISIBLE);
+                obj.on_interaction_event(InteractionEvent::FocusVisible);
+            }
+        }
+
+        focus => {
+            if event
+                .with(|id| {
+                    !obj.disabled() && obj.focus_entry().map_or(false, |entry| entry.id() == *id)
+                })
+                .is_some()
+            {
+                if let Some(entry) = obj.focus_entry() {
+                    entry.set_focused(true);
+                }
+                obj.interaction()
+                    .insert(state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE);
+                obj.on_interaction_event(InteractionEvent::Focus);
+                obj.on_interaction_event(InteractionEvent::FocusVisible);
+            }
+        }
+
         clear_focus => {
             let was_focused = obj.interaction().contains(state::InteractionState::FOCUSED);
-            obj.interaction().remove(state::InteractionState::FOCUSED);
+            obj.interaction()
+                .remove(state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE);
             if was_focused {
+                if let Some(entry) = obj.focus_entry() {
+                    entry.set_focused(false);
+                }
                 obj.on_interaction_event(InteractionEvent::Blur);
             }
         }
+
+        deactivate => {
+            obj.interaction().insert(state::InteractionState::INACTIVE);
+            obj.on_interaction_event(InteractionEvent::Deactivate);
+        }
+
+        activate => {
+            obj.interaction().remove(state::InteractionState::INACTIVE);
+            obj.on_interaction_event(InteractionEvent::Activate);
+        }
     }
 }
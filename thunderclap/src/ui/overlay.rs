@@ -0,0 +1,353 @@
+//! Window-wide stack of floating widgets, such as menus, tooltips, dialogs and dropdowns.
+
+use {
+    crate::{
+        base::{self, HasVisibility, WidgetChildren},
+        geom::*,
+    },
+    reclutch::{
+        display::{DisplayCommand, GraphicsDisplay},
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+    },
+    std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    },
+};
+
+/// Widget shown in an overlay manager.
+pub type OverlayWidget<U, G> =
+    Box<dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>;
+
+/// Events emitted by an overlay manager.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayEvent {
+    /// Emitted when an overlay has been shown, with the ID returned by `OverlayHandle::show`.
+    #[event_key(show)]
+    Show(u64),
+    /// Emitted when an overlay has been closed by an interaction of its dismiss policy.
+    /// The widget has been dropped by then.
+    #[event_key(dismiss)]
+    Dismiss(u64),
+}
+
+/// Tier of an overlay, which decides where it's stacked.
+///
+/// Overlays of a higher tier are always above those of a lower tier; within a tier, the most recently shown overlay
+/// is on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OverlayTier {
+    /// Dialogs and other panels which the rest of the window waits on.
+    Dialog,
+    /// Menus, dropdowns, pickers and other transient popups; these may be shown over dialogs.
+    Popup,
+    /// Tooltips, which are shown over everything else.
+    Tooltip,
+}
+
+/// How an overlay is stacked and closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayOptions {
+    pub tier: OverlayTier,
+    /// Interactions which close the overlay; presses and scrolls only do so outside of its bounds.
+    pub dismiss_policy: base::DismissPolicy,
+    /// Whether the overlay blocks mouse input to everything beneath it, in any tier.
+    pub modal: bool,
+}
+
+impl OverlayOptions {
+    /// Options for a modal dialog, which is only closed explicitly.
+    pub fn dialog() -> Self {
+        OverlayOptions {
+            tier: OverlayTier::Dialog,
+            dismiss_policy: base::DismissPolicy::empty(),
+            modal: true,
+        }
+    }
+
+    /// Options for a popup (e.g. a menu or a dropdown), closed by any interaction outside of it.
+    pub fn popup() -> Self {
+        OverlayOptions {
+            tier: OverlayTier::Popup,
+            dismiss_policy: base::DismissPolicy::all(),
+            modal: false,
+        }
+    }
+
+    /// Options for a tooltip, closed by any interaction outside of it.
+    pub fn tooltip() -> Self {
+        OverlayOptions {
+            tier: OverlayTier::Tooltip,
+            dismiss_policy: base::DismissPolicy::all(),
+            modal: false,
+        }
+    }
+}
+
+enum OverlayRequest<U, G> {
+    Show(u64, OverlayWidget<U, G>, OverlayOptions),
+    Close(u64),
+}
+
+/// Shared queue of overlays to be shown or closed by an overlay manager.
+///
+/// Cloning the handle yields another handle to the same overlay manager, so it can be passed to wherever popups may
+/// originate from. Requests are applied at the start of the next update of the overlay manager.
+pub struct OverlayHandle<U, G> {
+    queue: Rc<RefCell<Vec<OverlayRequest<U, G>>>>,
+    next_id: Rc<Cell<u64>>,
+}
+
+impl<U, G> Clone for OverlayHandle<U, G> {
+    fn clone(&self) -> Self {
+        OverlayHandle { queue: self.queue.clone(), next_id: self.next_id.clone() }
+    }
+}
+
+impl<U, G> Default for OverlayHandle<U, G> {
+    fn default() -> Self {
+        OverlayHandle { queue: Default::default(), next_id: Default::default() }
+    }
+}
+
+impl<U, G> OverlayHandle<U, G> {
+    /// Queues a widget to be shown as an overlay, positioned wherever it already is.
+    /// Returns an ID identifying the overlay in `OverlayEvent`s.
+    pub fn show(&self, widget: OverlayWidget<U, G>, options: OverlayOptions) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.queue.borrow_mut().push(OverlayRequest::Show(id, widget, options));
+        id
+    }
+
+    /// Queues closing the overlay with a given ID, dropping its widget. No event is emitted.
+    pub fn close(&self, id: u64) {
+        self.queue.borrow_mut().push(OverlayRequest::Close(id));
+    }
+
+    fn take(&self) -> Vec<OverlayRequest<U, G>> {
+        std::mem::take(&mut *self.queue.borrow_mut())
+    }
+}
+
+struct Overlay<U, G> {
+    id: u64,
+    widget: OverlayWidget<U, G>,
+    options: OverlayOptions,
+    /// Input grab of a modal overlay.
+    grab: Option<base::InputGrab>,
+}
+
+/// Z-ordered stack of floating widgets (menus, tooltips, dialogs, dropdowns), drawn above the rest of the window
+/// and receiving input before it.
+///
+/// The overlay manager owns the widgets shown in it. Each overlay is closed by the interactions of its
+/// `DismissPolicy` outside of its bounds, except for the escape key, which only closes the topmost overlay which
+/// accepts it (and is consumed). A modal overlay grabs mouse input, blocking every overlay and widget beneath it.
+///
+/// The overlay manager isn't a widget itself; it should be updated before, and drawn after, everything it's above,
+/// hence `app::App` owns one. Overlays are shown through an `OverlayHandle` (see `OverlayManager::handle`).
+pub struct OverlayManager<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<OverlayEvent>,
+
+    handle: OverlayHandle<U, G>,
+    /// Overlays from bottom to top.
+    overlays: Vec<Overlay<U, G>>,
+    listener: RcEventListener<base::WindowEvent>,
+}
+
+impl<U, G> OverlayManager<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Creates an empty overlay manager, listening to the window events of `u_aux` for dismissals.
+    pub fn new(u_aux: &U) -> Self {
+        OverlayManager {
+            event_queue: RcEventQueue::new(),
+            handle: Default::default(),
+            overlays: Vec::new(),
+            listener: u_aux.window_queue().listen(),
+        }
+    }
+
+    /// Returns a handle through which overlays can be shown and closed.
+    #[inline]
+    pub fn handle(&self) -> OverlayHandle<U, G> {
+        self.handle.clone()
+    }
+
+    /// Shows a widget as an overlay immediately, rather than on the next update.
+    /// Returns an ID identifying the overlay in `OverlayEvent`s.
+    pub fn show(&mut self, widget: OverlayWidget<U, G>, options: OverlayOptions, aux: &U) -> u64 {
+        let id = self.handle.next_id.get();
+        self.handle.next_id.set(id + 1);
+        self.insert(id, widget, options, aux);
+        id
+    }
+
+    /// Closes the overlay with a given ID immediately, returning its widget. No event is emitted.
+    pub fn close(&mut self, id: u64) -> Option<OverlayWidget<U, G>> {
+        let index = self.overlays.iter().position(|overlay| overlay.id == id)?;
+        Some(self.overlays.remove(index).widget)
+    }
+
+    /// Closes every overlay, dropping their widgets. No events are emitted.
+    pub fn close_all(&mut self) {
+        self.overlays.clear();
+    }
+
+    /// Returns the widget of the overlay with a given ID, if it's shown.
+    pub fn get(
+        &self,
+        id: u64,
+    ) -> Option<&dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>
+    {
+        self.overlays.iter().find(|overlay| overlay.id == id).map(|overlay| &*overlay.widget)
+    }
+
+    /// Returns the widget of the overlay with a given ID mutably, if it's shown.
+    pub fn get_mut(
+        &mut self,
+        id: u64,
+    ) -> Option<
+        &mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        match self.overlays.iter_mut().find(|overlay| overlay.id == id) {
+            Some(overlay) => Some(&mut *overlay.widget),
+            None => None,
+        }
+    }
+
+    /// Returns `true` if the overlay with a given ID is shown.
+    #[inline]
+    pub fn contains(&self, id: u64) -> bool {
+        self.overlays.iter().any(|overlay| overlay.id == id)
+    }
+
+    /// Returns the IDs of the shown overlays, from bottom to top.
+    pub fn ids(&self) -> Vec<u64> {
+        self.overlays.iter().map(|overlay| overlay.id).collect()
+    }
+
+    /// Returns `true` if no overlay is shown.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    /// Returns the ID of the topmost overlay at a given point, if any.
+    pub fn overlay_at(&self, point: AbsolutePoint) -> Option<u64> {
+        self.overlays
+            .iter()
+            .rev()
+            .find(|overlay| overlay.widget.abs_bounds().contains(point))
+            .map(|overlay| overlay.id)
+    }
+
+    /// Returns the widgets of the shown overlays, from bottom to top (e.g. for diagnostics).
+    pub fn widgets(
+        &self,
+    ) -> Vec<&dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>
+    {
+        self.overlays.iter().map(|overlay| &*overlay.widget).collect()
+    }
+
+    /// Returns the widgets of the shown overlays mutably, from bottom to top.
+    pub fn widgets_mut(
+        &mut self,
+    ) -> Vec<&mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>
+    {
+        let mut widgets: Vec<
+            &mut dyn WidgetChildren<
+                UpdateAux = U,
+                GraphicalAux = G,
+                DisplayObject = DisplayCommand,
+            >,
+        > = Vec::with_capacity(self.overlays.len());
+        for overlay in &mut self.overlays {
+            widgets.push(&mut *overlay.widget);
+        }
+        widgets
+    }
+
+    fn insert(&mut self, id: u64, widget: OverlayWidget<U, G>, options: OverlayOptions, aux: &U) {
+        let grab =
+            if options.modal { Some(aux.input_grabs().grab(widget.abs_bounds())) } else { None };
+        // above every overlay of the same or a lower tier
+        let index = self
+            .overlays
+            .iter()
+            .position(|overlay| overlay.options.tier > options.tier)
+            .unwrap_or_else(|| self.overlays.len());
+        self.overlays.insert(index, Overlay { id, widget, options, grab });
+        self.event_queue.emit_owned(OverlayEvent::Show(id));
+    }
+
+    /// Closes the overlays which `event` dismisses.
+    fn dismiss(&mut self, event: &base::WindowEvent) {
+        let mut dismissed = Vec::new();
+        if let base::WindowEvent::Escape(escape) = event {
+            // escape only closes one overlay at a time, starting from the top
+            let top = self.overlays.iter().rev().find(|overlay| {
+                overlay.options.dismiss_policy.contains(base::DismissPolicy::ESCAPE)
+            });
+            if let Some(overlay) = top {
+                if escape.with(|stage| *stage == base::EscapeStage::Popup).is_some() {
+                    dismissed.push(overlay.id);
+                }
+            }
+        } else {
+            for overlay in &self.overlays {
+                if overlay.options.dismiss_policy.dismisses(event, overlay.widget.abs_bounds()) {
+                    dismissed.push(overlay.id);
+                }
+            }
+        }
+
+        for id in dismissed {
+            if self.close(id).is_some() {
+                self.event_queue.emit_owned(OverlayEvent::Dismiss(id));
+            }
+        }
+    }
+
+    /// Applies dismissals and queued requests, then updates the overlays from top to bottom.
+    pub fn update(&mut self, aux: &mut U) {
+        // dismissals only apply to the overlays which were shown when the event was emitted
+        for event in self.listener.peek() {
+            self.dismiss(&event);
+        }
+
+        for request in self.handle.take() {
+            match request {
+                OverlayRequest::Show(id, widget, options) => self.insert(id, widget, options, aux),
+                OverlayRequest::Close(id) => {
+                    self.close(id);
+                }
+            }
+        }
+
+        for overlay in self.overlays.iter_mut().rev() {
+            if let Some(grab) = &overlay.grab {
+                grab.set_region(overlay.widget.abs_bounds());
+            }
+            match overlay.widget.visibility() {
+                base::Visibility::Static | base::Visibility::None => {}
+                _ => overlay.widget.update(aux),
+            }
+        }
+    }
+
+    /// Draws the overlays from bottom to top.
+    pub fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut G) {
+        for overlay in &mut self.overlays {
+            base::invoke_draw(&mut *overlay.widget, display, aux);
+        }
+    }
+}
@@ -0,0 +1,420 @@
+//! Control for navigating between pages of data.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Space between items.
+const SPACING: f32 = 2.0;
+/// Horizontal space between the edges of an item and its label.
+const ITEM_PADDING: f32 = 6.0;
+
+/// Events emitted by a pagination control.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum PaginationEvent {
+    /// Emitted when the page is changed by the user, with the new (zero-based) page.
+    #[event_key(page_changed)]
+    PageChanged(usize),
+}
+
+/// Item shown in a pagination control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaginationItem {
+    /// Button to go to the previous page.
+    Previous,
+    /// Button to go to a (zero-based) page.
+    Page(usize),
+    /// Placeholder for collapsed pages.
+    Ellipsis,
+    /// Button to go to the next page.
+    Next,
+}
+
+/// Generates an unbound terminal which handles pressing and hovering the items of the pagination control.
+pub fn pagination_handler<U, G>(
+) -> vg::UnboundQueueHandler<PaginationWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        PaginationWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && obj.item_at(*pos).is_some()
+            }) {
+                match obj.item_at(*pos) {
+                    Some(PaginationItem::Previous) => obj.previous(),
+                    Some(PaginationItem::Next) => obj.next(),
+                    Some(PaginationItem::Page(page)) => obj.go_to(page),
+                    _ => {}
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj
+                .items
+                .iter()
+                .position(|(item, rect)| rect.contains(pos) && obj.data.is_enabled(*item));
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Row of previous/next buttons and numbered page buttons, for navigating paged data such as a table or list.
+///
+/// When there are too many pages to show, the pages away from the current page and from either end are
+/// collapsed into ellipses, so that the number of items stays the same as the page changes. The width of the
+/// control follows its items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    /// Current page, from zero.
+    pub page: usize,
+    pub page_count: usize,
+    /// Number of pages shown on each side of the current page.
+    pub siblings: usize,
+    /// Number of pages always shown at the start and at the end.
+    pub boundaries: usize,
+    pub locale: i18n::Locale,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    /// Color behind hovered items.
+    pub hover: Color,
+    /// Color of the current page.
+    pub selected: Color,
+    /// Color of the label of the current page.
+    pub over_selected: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl Pagination {
+    /// Returns the items shown, from left to right.
+    pub fn items(&self) -> Vec<PaginationItem> {
+        let (count, boundaries, siblings) = (self.page_count, self.boundaries, self.siblings);
+        let page = self.page.min(count.saturating_sub(1));
+        let mut items = vec![PaginationItem::Previous];
+
+        // every page fits when collapsing wouldn't save anything
+        if count <= boundaries * 2 + siblings * 2 + 3 {
+            items.extend((0..count).map(PaginationItem::Page));
+        } else {
+            // window of pages around the current page, kept clear of the boundary pages and their ellipses
+            let start = page
+                .saturating_sub(siblings)
+                .min(count - boundaries - siblings * 2 - 2)
+                .max(boundaries + 1);
+            let end =
+                (page + siblings).max(boundaries + siblings * 2 + 1).min(count - boundaries - 2);
+
+            items.extend((0..boundaries).map(PaginationItem::Page));
+            // an ellipsis only replaces two or more pages; a single page is shown instead
+            if start > boundaries + 1 {
+                items.push(PaginationItem::Ellipsis);
+            } else {
+                items.push(PaginationItem::Page(boundaries));
+            }
+            items.extend((start..=end).map(PaginationItem::Page));
+            if end < count - boundaries - 2 {
+                items.push(PaginationItem::Ellipsis);
+            } else {
+                items.push(PaginationItem::Page(count - boundaries - 1));
+            }
+            items.extend((count - boundaries..count).map(PaginationItem::Page));
+        }
+
+        items.push(PaginationItem::Next);
+        items
+    }
+
+    /// Returns the label of an item; empty for the previous and next buttons, which are drawn as icons.
+    pub fn label(&self, item: PaginationItem) -> String {
+        match item {
+            PaginationItem::Page(page) => self.locale.format_integer(page as i64 + 1),
+            PaginationItem::Ellipsis => "\u{2026}".to_string(),
+            PaginationItem::Previous | PaginationItem::Next => String::new(),
+        }
+    }
+
+    /// Returns `true` if an item can be pressed.
+    pub fn is_enabled(&self, item: PaginationItem) -> bool {
+        !self.disabled
+            && match item {
+                PaginationItem::Previous => self.page > 0,
+                PaginationItem::Next => self.page + 1 < self.page_count,
+                PaginationItem::Page(_) => true,
+                PaginationItem::Ellipsis => false,
+            }
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Pagination
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = PaginationWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Pagination
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Pagination {
+            page: 0,
+            page_count: 1,
+            siblings: 1,
+            boundaries: 1,
+            locale: i18n::locale(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_background,
+            hover: draw::strengthen(data.scheme.background, 0.1, data.contrast),
+            selected: data.scheme.primary,
+            over_selected: data.scheme.over_primary,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> PaginationWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            PaginationWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("pagination", pagination_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.pagination();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::PaginationState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    items: Vec::new(),
+                    hovered: None,
+                })
+                .cast_unit(),
+        );
+
+        let mut pagination = PaginationWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            items: Vec::new(),
+            hovered: None,
+            dirty: true,
+        }
+        .build();
+
+        pagination.update_layout();
+
+        pagination
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::PaginationState> for PaginationWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::PaginationState {
+        state::PaginationState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            items: self.items.clone(),
+            hovered: self.hovered,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct PaginationWidget {
+        widget::MAX,
+
+        <PaginationEvent> EventQueue,
+        <Pagination> State,
+        <state::PaginationState> Painter,
+
+        {
+            /// Items shown and their rectangles.
+            items: Vec<(PaginationItem, AbsoluteRect)>,
+            /// Index of the hovered item.
+            hovered: Option<usize>,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> PaginationWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the page, emitting `PaginationEvent::PageChanged` if it changed.
+    pub fn go_to(&mut self, page: usize) {
+        let page = page.min(self.data.page_count.saturating_sub(1));
+        if page != self.data.page {
+            self.data.page = page;
+            self.event_queue.emit_owned(PaginationEvent::PageChanged(page));
+        }
+    }
+
+    /// Goes to the previous page, if any.
+    pub fn previous(&mut self) {
+        if self.data.page > 0 {
+            self.go_to(self.data.page - 1);
+        }
+    }
+
+    /// Goes to the next page, if any.
+    pub fn next(&mut self) {
+        self.go_to(self.data.page + 1);
+    }
+
+    /// Returns the enabled item containing a given point, if any.
+    pub fn item_at(&self, point: AbsolutePoint) -> Option<PaginationItem> {
+        self.items
+            .iter()
+            .find(|(item, rect)| rect.contains(point) && self.data.is_enabled(*item))
+            .map(|(item, _)| *item)
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        if text.is_empty() {
+            return 0.0;
+        }
+
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+        }
+        .bounds()
+        .map(|bounds| bounds.size.width)
+        .unwrap_or(0.0)
+    }
+
+    /// Lays the items out from left to right, as squares at least, and fits the width of the control to them.
+    fn update_layout(&mut self) {
+        let bounds = self.abs_rect();
+        let height = bounds.size.height;
+        let mut x = bounds.min_x();
+
+        let mut items = Vec::new();
+        for item in self.data.items() {
+            let width = height.max(self.text_width(&self.data.label(item)) + ITEM_PADDING * 2.0);
+            items.push((
+                item,
+                AbsoluteRect::new(
+                    AbsolutePoint::new(x, bounds.min_y()),
+                    Size::new(width, height).cast_unit(),
+                ),
+            ));
+            x += width + SPACING;
+        }
+
+        if items.len() != self.items.len() {
+            self.hovered = None;
+        }
+        self.items = items;
+
+        let content_width = (x - SPACING - bounds.min_x()).max(0.0);
+        if (content_width - bounds.size.width).abs() > std::f32::EPSILON {
+            self.set_ctxt_rect(AbsoluteRect::new(
+                bounds.origin,
+                Size::new(content_width, height).cast_unit(),
+            ));
+        }
+    }
+}
+
+impl<U, G> Widget for PaginationWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.dirty {
+            self.dirty = false;
+            self.update_layout();
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,478 @@
+//! Single line text field for passwords.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n,
+        ui::{self, text_area, LogicalTextArea, TextAreaEvent},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Character each character of a masked password is shown as.
+pub const MASK_CHAR: char = '\u{2022}';
+
+/// Estimates the strength of a password, shown as a meter along the bottom of a password box.
+pub trait StrengthMeter {
+    /// Returns the strength of a password, from 0 (weakest) to 1 (strongest).
+    fn strength(&self, password: &str) -> f32;
+}
+
+impl<F: Fn(&str) -> f32> StrengthMeter for F {
+    #[inline]
+    fn strength(&self, password: &str) -> f32 {
+        (self)(password)
+    }
+}
+
+/// Generates an unbound terminal which handles the reveal toggle of a password box.
+pub fn password_box_handler<U, G>(
+) -> vg::UnboundQueueHandler<PasswordBoxWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        PasswordBoxWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            // not consumed, so that pressing the toggle also focuses the field
+            let (pos, button, _) = *event.get();
+            if !obj.data.disabled
+                && button == base::MouseButton::Left
+                && obj.reveal_rect().contains(pos)
+            {
+                let revealed = !obj.data.revealed;
+                obj.data.revealed = revealed;
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let reveal_hovered = obj.reveal_rect().contains(pos);
+            if reveal_hovered != obj.reveal_hovered {
+                obj.reveal_hovered = reveal_hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Single line text field which masks the password with bullets, with a toggle to reveal it.
+///
+/// Editing is handled the same as a [`TextArea`](struct.TextArea.html) (see `LogicalTextArea`).
+/// Unlike a text area, the password can't be copied (i.e. `TextAreaEvent::Copy` isn't emitted) unless `allow_copy`
+/// is set, so that it doesn't leak through the clipboard.
+///
+/// A [`StrengthMeter`](trait.StrengthMeter.html) can be given to `PasswordBoxWidget::set_strength_meter`, in which
+/// case the strength of the password is shown as a bar along the bottom of the field.
+///
+/// The password is redacted from the `Debug` output, since it may end up in logs or diagnostics reports.
+#[derive(Clone, PartialEq)]
+pub struct PasswordBox {
+    /// The password.
+    pub text: String,
+    /// Text shown while the password is empty.
+    pub placeholder: String,
+    /// Whether the password is shown as plain text rather than masked.
+    pub revealed: bool,
+    /// Whether the password can be copied, even while masked.
+    pub allow_copy: bool,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub focus: Color,
+    /// Color of the strength meter for the weakest passwords.
+    pub weak: Color,
+    /// Color of the strength meter for the strongest passwords.
+    pub strong: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+    /// Byte index of the cursor in `text`, on a character boundary.
+    pub cursor: usize,
+}
+
+impl std::fmt::Debug for PasswordBox {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PasswordBox")
+            .field("text", &format_args!("<redacted>"))
+            .field("placeholder", &self.placeholder)
+            .field("revealed", &self.revealed)
+            .field("allow_copy", &self.allow_copy)
+            .field("typeface", &self.typeface)
+            .field("foreground", &self.foreground)
+            .field("background", &self.background)
+            .field("focus", &self.focus)
+            .field("weak", &self.weak)
+            .field("strong", &self.strong)
+            .field("contrast", &self.contrast)
+            .field("disabled", &self.disabled)
+            .field("cursor", &self.cursor)
+            .finish()
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for PasswordBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = PasswordBoxWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for PasswordBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        PasswordBox {
+            text: String::new(),
+            placeholder: i18n::locale().localize_text("Password"),
+            revealed: false,
+            allow_copy: false,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            focus: data.scheme.focus,
+            weak: data.scheme.error,
+            strong: data.scheme.primary,
+            contrast: data.contrast,
+            disabled: false,
+            cursor: 0,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> PasswordBoxWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            PasswordBoxWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.update_strength();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("password_box", password_box_handler::<U, G>().bind(u_aux.window_queue()));
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<PasswordBoxWidget<U, G>, U>()
+                .bind(u_aux.window_queue()),
+        );
+        graph = graph.add(
+            "text_area",
+            ui::text_area_handler::<PasswordBoxWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+
+        let painter = theme.password_box();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::PasswordBoxState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    display_text: String::new(),
+                    cursor: 0,
+                    reveal: Default::default(),
+                    reveal_hovered: false,
+                    strength: None,
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        PasswordBoxWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            meter: None,
+            strength: None,
+            reveal_hovered: false,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::PasswordBoxState> for PasswordBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::PasswordBoxState {
+        state::PasswordBoxState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            display_text: self.display_text(),
+            cursor: self.display_cursor(),
+            reveal: self.reveal_rect(),
+            reveal_hovered: self.reveal_hovered,
+            strength: self.strength,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct PasswordBoxWidget {
+        widget::MAX,
+
+        <TextAreaEvent> EventQueue,
+        <PasswordBox> State,
+        <state::PasswordBoxState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            meter: Option<Box<dyn StrengthMeter>>,
+            /// Strength of the password as of the last change, if measured.
+            strength: Option<f32>,
+            reveal_hovered: bool,
+        },
+    }
+}
+
+impl<U, G> PasswordBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes how the strength of the password is measured, showing the strength meter.
+    pub fn set_strength_meter(&mut self, meter: impl StrengthMeter + 'static) {
+        self.meter = Some(Box::new(meter));
+        self.update_strength();
+        self.command_group.repaint();
+    }
+
+    /// Removes the strength meter.
+    pub fn clear_strength_meter(&mut self) {
+        self.meter = None;
+        self.strength = None;
+        self.command_group.repaint();
+    }
+
+    /// Returns the strength of the password, from 0 to 1, if a strength meter has been given.
+    #[inline]
+    pub fn strength(&self) -> Option<f32> {
+        self.strength
+    }
+
+    /// Returns the text shown in the field; the password masked with bullets, unless revealed.
+    pub fn display_text(&self) -> String {
+        if self.data.revealed {
+            self.data.text.clone()
+        } else {
+            std::iter::repeat(MASK_CHAR).take(self.data.text.chars().count()).collect()
+        }
+    }
+
+    /// Returns the byte index of the cursor in the text shown in the field (see `display_text`).
+    fn display_cursor(&self) -> usize {
+        if self.data.revealed {
+            self.data.cursor
+        } else {
+            let before = self.data.text.get(..self.data.cursor).unwrap_or(&self.data.text);
+            before.chars().count() * MASK_CHAR.len_utf8()
+        }
+    }
+
+    /// Returns the rectangle of the reveal toggle, at the right end of the field.
+    pub fn reveal_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let size = rect.size.height;
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.max_x() - size, rect.min_y()),
+            Size::new(size, size).cast_unit(),
+        )
+    }
+
+    fn update_strength(&mut self) {
+        let password = &self.data.text;
+        self.strength = self.meter.as_ref().map(|meter| meter.strength(password).max(0.0).min(1.0));
+    }
+}
+
+impl<U, G> ui::InteractiveWidget for PasswordBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        match event {
+            ui::InteractionEvent::Focus => {
+                self.repaint();
+                self.event_queue.emit_owned(TextAreaEvent::Focus);
+            }
+            ui::InteractionEvent::Blur => {
+                self.repaint();
+                self.event_queue.emit_owned(TextAreaEvent::Blur);
+            }
+            ui::InteractionEvent::Deactivate | ui::InteractionEvent::Activate => self.repaint(),
+            _ => {}
+        }
+    }
+}
+
+impl<U, G> LogicalTextArea for PasswordBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn event_queue(&mut self) -> &mut RcEventQueue<TextAreaEvent> {
+        &mut self.event_queue
+    }
+
+    #[inline]
+    fn push_char(&mut self, c: char) {
+        let data = &mut *self.data;
+        text_area::insert_at_cursor(&mut data.text, &mut data.cursor, c.encode_utf8(&mut [0; 4]));
+        self.repaint();
+    }
+
+    #[inline]
+    fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|&c| self.accepts(c)).collect();
+        let data = &mut *self.data;
+        text_area::insert_at_cursor(&mut data.text, &mut data.cursor, &text);
+        self.repaint();
+    }
+
+    #[inline]
+    fn remove_char(&mut self) {
+        self.repaint();
+        let data = &mut *self.data;
+        text_area::remove_before_cursor(&mut data.text, &mut data.cursor);
+    }
+
+    #[inline]
+    fn move_cursor(&mut self, offset: isize) {
+        self.repaint();
+        let data = &mut *self.data;
+        text_area::move_cursor_by(&data.text, &mut data.cursor, offset);
+    }
+
+    #[inline]
+    fn input_hints(&self) -> base::InputHints {
+        base::InputHints::PASSWORD
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        if self.data.allow_copy {
+            Some(self.data.text.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl<U, G> Widget for PasswordBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: base::InputHints::PASSWORD,
+                area: self.abs_rect(),
+            });
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+#[cfg(all(test, feature = "default-themes"))]
+mod tests {
+    use {
+        super::*,
+        crate::themes::testing::{self, GAux, UAux},
+        draw::HasTheme,
+    };
+
+    #[test]
+    fn password_is_redacted_from_painted_state() {
+        let theme = testing::primer();
+        let mut u_aux = testing::u_aux();
+        let mut data = <PasswordBox as ui::WidgetConstructor<UAux, GAux>>::from_theme(&theme);
+        data.text = "hunter2".to_string();
+
+        for &revealed in &[false, true] {
+            data.revealed = revealed;
+            let mut widget: PasswordBoxWidget<UAux, GAux> =
+                ui::WidgetConstructor::construct(data.clone(), &theme, &mut u_aux);
+            let (state, _) = widget.painted_state().unwrap();
+            assert!(!state.contains("hunter2"), "password in painted state: {}", state);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! Inspector listing the properties of an object, each with an editor chosen by its type.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Value of a property; its type decides which editor is shown (see `PropertyEditor`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Color(Color),
+}
+
+impl PropertyValue {
+    /// Returns the editor shown for the value.
+    pub fn editor(&self) -> PropertyEditor {
+        match self {
+            PropertyValue::Bool(_) => PropertyEditor::Checkbox,
+            PropertyValue::Number(_) => PropertyEditor::Spinbox,
+            PropertyValue::Text(_) => PropertyEditor::TextBox,
+            PropertyValue::Color(_) => PropertyEditor::Swatch,
+        }
+    }
+
+    /// Returns the value as edited in a text field, for numbers and text.
+    pub fn edit_text(&self) -> Option<String> {
+        match self {
+            PropertyValue::Number(number) => Some(number.to_string()),
+            PropertyValue::Text(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Editor of a property value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyEditor {
+    /// Toggled by clicking; for `PropertyValue::Bool`.
+    Checkbox,
+    /// Text field with decrement and increment buttons; for `PropertyValue::Number`.
+    Spinbox,
+    /// Text field; for `PropertyValue::Text`.
+    TextBox,
+    /// Color swatch, which requests a color to be picked when clicked; for `PropertyValue::Color`.
+    Swatch,
+}
+
+/// Part of a property row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyPart {
+    Name,
+    Value,
+    /// Decrement button of a spinbox.
+    Decrement,
+    /// Increment button of a spinbox.
+    Increment,
+}
+
+/// Named value listed in a property grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: PropertyValue,
+    /// Whether the value is shown without being editable.
+    pub read_only: bool,
+}
+
+impl Property {
+    /// Creates an editable property.
+    pub fn new(name: impl Into<String>, value: PropertyValue) -> Self {
+        Property { name: name.into(), value, read_only: false }
+    }
+}
+
+/// Object whose properties are listed, and edited, by a property grid.
+pub trait PropertySource {
+    /// Returns the properties, in the order they're listed.
+    fn properties(&self) -> Vec<Property>;
+    /// Changes the value of the property at `index` (as listed by `properties`).
+    ///
+    /// The value is always of the same type as the current value of the property.
+    fn set_property(&mut self, index: usize, value: PropertyValue);
+}
+
+/// Events emitted by a property grid.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum PropertyGridEvent {
+    /// Emitted when the value of a property has been changed through its editor, with the index of the property.
+    #[event_key(changed)]
+    Changed(usize),
+    /// Emitted when the swatch of a color property is clicked, with the index of the property.
+    #[event_key(pick_color)]
+    PickColor(usize),
+}
+
+/// Generates an unbound terminal which handles clicking the editors of a property grid, and typing into them.
+pub fn property_grid_handler<U, G>(
+) -> vg::UnboundQueueHandler<PropertyGridWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        PropertyGridWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                if !obj.interaction.contains(state::InteractionState::FOCUSED) {
+                    obj.interaction.insert(state::InteractionState::FOCUSED);
+                    obj.command_group.repaint();
+                }
+                if let Some((index, part)) = obj.part_at(*pos) {
+                    obj.activate(index, part);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = if obj.data.disabled { None } else { obj.part_at(pos) };
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| obj.editing.is_some()) {
+                match key {
+                    base::KeyInput::Back => {
+                        if let Some((_, text)) = &mut obj.editing {
+                            text.pop();
+                        }
+                        obj.command_group.repaint();
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => obj.commit_edit(),
+                    _ => {}
+                }
+            }
+        }
+
+        escape => {
+            if event
+                .with(|stage| *stage == base::EscapeStage::Cancel && obj.editing.is_some())
+                .is_some()
+            {
+                obj.cancel_edit();
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|c| {
+                !c.is_control() && obj.edit_hints().map_or(false, |hints| hints.accepts(*c))
+            }) {
+                if let Some((_, text)) = &mut obj.editing {
+                    text.push(c);
+                }
+                obj.command_group.repaint();
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.commit_edit();
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Inspector listing named properties in rows, with an editor for each value chosen by its type: a checkbox for
+/// booleans, a spinbox for numbers, a text field for text and a swatch for colors. Intended for editor tooling
+/// (e.g. inspecting the selected object of a scene).
+///
+/// Properties are read from, and written back to, a [`PropertySource`](trait.PropertySource.html) given to
+/// `PropertyGridWidget::set_source`; `PropertyGridWidget::refresh` re-reads them when the object changes elsewhere.
+/// Without a source, `properties` can be set directly, and edits only change `properties`.
+///
+/// Text and numbers are edited by clicking the value and typing; the edit is applied by pressing enter or clicking
+/// elsewhere, and discarded by pressing escape (or, for numbers, if it isn't a number). There's no color editor
+/// built in; clicking a swatch emits `PropertyGridEvent::PickColor`, and the color picked by the app (e.g. from a
+/// [`SwatchGrid`](struct.SwatchGrid.html)) is applied with `PropertyGridWidget::set_value`.
+///
+/// The height of the grid follows the number of properties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyGrid {
+    /// The properties, as of the last refresh from the source.
+    pub properties: Vec<Property>,
+    /// Fraction of the width taken by the names.
+    pub name_width: f32,
+    pub row_height: f32,
+    /// Amount a spinbox button adds or subtracts.
+    pub step: f64,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the lines between rows and columns.
+    pub border: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for PropertyGrid
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = PropertyGridWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for PropertyGrid
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        PropertyGrid {
+            properties: Vec::new(),
+            name_width: 0.4,
+            row_height: 24.0,
+            step: 1.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            border: draw::strengthen(data.scheme.control_inset, 0.15, data.contrast),
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> PropertyGridWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            PropertyGridWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if obj.editing.as_ref().map_or(false, |(index, _)| *index >= obj.data.properties.len()) {
+                        obj.editing = None;
+                    }
+                    obj.fit_height();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("property_grid", property_grid_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.property_grid();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::PropertyGridState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    rows: Vec::new(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let mut property_grid = PropertyGridWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            source: None,
+            hovered: None,
+            editing: None,
+        }
+        .build();
+
+        property_grid.fit_height();
+
+        property_grid
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::PropertyGridState> for PropertyGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::PropertyGridState {
+        let rows = (0..self.data.properties.len())
+            .map(|i| state::PropertyRowState {
+                name: self.part_rect(i, PropertyPart::Name),
+                value: self.part_rect(i, PropertyPart::Value),
+                stepper: if self.data.properties[i].value.editor() == PropertyEditor::Spinbox {
+                    Some((
+                        self.part_rect(i, PropertyPart::Decrement),
+                        self.part_rect(i, PropertyPart::Increment),
+                    ))
+                } else {
+                    None
+                },
+                hovered: self.hovered.filter(|(index, _)| *index == i).map(|(_, part)| part),
+                editing: self
+                    .editing
+                    .as_ref()
+                    .filter(|(index, _)| *index == i)
+                    .map(|(_, text)| text.clone()),
+            })
+            .collect();
+
+        state::PropertyGridState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            rows,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct PropertyGridWidget {
+        widget::MAX,
+
+        <PropertyGridEvent> EventQueue,
+        <PropertyGrid> State,
+        <state::PropertyGridState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            source: Option<Box<dyn PropertySource>>,
+            hovered: Option<(usize, PropertyPart)>,
+            /// Property being edited and the text typed so far.
+            editing: Option<(usize, String)>,
+        },
+    }
+}
+
+impl<U, G> PropertyGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the object whose properties are listed, and reads its properties.
+    pub fn set_source(&mut self, source: impl PropertySource + 'static) {
+        self.editing = None;
+        self.source = Some(Box::new(source));
+        self.refresh();
+    }
+
+    /// Removes the source, keeping the properties last read from it.
+    pub fn clear_source(&mut self) -> Option<Box<dyn PropertySource>> {
+        self.source.take()
+    }
+
+    /// Re-reads the properties from the source, e.g. after the object has been changed elsewhere.
+    pub fn refresh(&mut self) {
+        if let Some(source) = &self.source {
+            let properties = source.properties();
+            self.data.properties = properties;
+        }
+    }
+
+    /// Changes the value of the property at `index`, writing it to the source and emitting
+    /// `PropertyGridEvent::Changed`.
+    ///
+    /// Nothing is changed if the value is of a different type than the current value.
+    pub fn set_value(&mut self, index: usize, value: PropertyValue) {
+        match self.data.properties.get(index) {
+            Some(property) if property.value.editor() == value.editor() => {
+                if property.value == value {
+                    return;
+                }
+            }
+            _ => return,
+        }
+
+        if let Some(source) = &mut self.source {
+            source.set_property(index, value.clone());
+        }
+        self.data.properties[index].value = value;
+        self.event_queue.emit_owned(PropertyGridEvent::Changed(index));
+    }
+
+    /// Starts editing the property at `index` as text, if it's a number or text and isn't read-only.
+    pub fn begin_edit(&mut self, index: usize) {
+        self.commit_edit();
+        if let Some(property) = self.data.properties.get(index).filter(|p| !p.read_only) {
+            if let Some(text) = property.value.edit_text() {
+                self.editing = Some((index, text));
+                self.command_group.repaint();
+            }
+        }
+    }
+
+    /// Applies the text being edited, if any, to its property.
+    ///
+    /// Text which isn't a number is discarded when editing a number.
+    pub fn commit_edit(&mut self) {
+        if let Some((index, text)) = self.editing.take() {
+            self.command_group.repaint();
+            let value = match self.data.properties.get(index).map(|p| &p.value) {
+                Some(PropertyValue::Number(_)) => match text.trim().parse::<f64>() {
+                    Ok(number) if number.is_finite() => PropertyValue::Number(number),
+                    _ => return,
+                },
+                Some(PropertyValue::Text(_)) => PropertyValue::Text(text),
+                _ => return,
+            };
+            self.set_value(index, value);
+        }
+    }
+
+    /// Discards the text being edited, if any.
+    pub fn cancel_edit(&mut self) {
+        if self.editing.take().is_some() {
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns the index of the property being edited as text, if any.
+    #[inline]
+    pub fn editing(&self) -> Option<usize> {
+        self.editing.as_ref().map(|(index, _)| *index)
+    }
+
+    /// Returns the input hints for the property being edited as text, if any.
+    fn edit_hints(&self) -> Option<base::InputHints> {
+        let (index, _) = self.editing.as_ref()?;
+        match self.data.properties.get(*index)?.value {
+            PropertyValue::Number(_) => Some(base::InputHints::NUMERIC),
+            _ => Some(base::InputHints::TEXT),
+        }
+    }
+
+    /// Returns the rectangle of a part of the row of the property at `index`.
+    pub fn part_rect(&self, index: usize, part: PropertyPart) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let height = self.data.row_height;
+        let top = rect.min_y() + index as f32 * height;
+        let divider = rect.min_x() + (rect.size.width * self.data.name_width).round();
+        let (left, right) = match part {
+            PropertyPart::Name => (rect.min_x(), divider),
+            PropertyPart::Value => (divider, rect.max_x()),
+            PropertyPart::Decrement => (rect.max_x() - height * 1.5, rect.max_x() - height * 0.75),
+            PropertyPart::Increment => (rect.max_x() - height * 0.75, rect.max_x()),
+        };
+        AbsoluteRect::new(
+            AbsolutePoint::new(left, top),
+            Size::new((right - left).max(0.0), height).cast_unit(),
+        )
+    }
+
+    /// Returns the property and the part of its row containing a given point, if any.
+    pub fn part_at(&self, point: AbsolutePoint) -> Option<(usize, PropertyPart)> {
+        let rect = self.abs_rect();
+        if !rect.contains(point) {
+            return None;
+        }
+
+        let index = ((point.y - rect.min_y()) / self.data.row_height) as usize;
+        let property = self.data.properties.get(index)?;
+        let parts: &[PropertyPart] = if property.value.editor() == PropertyEditor::Spinbox {
+            // the buttons lie within the value, so they're tested first
+            &[
+                PropertyPart::Decrement,
+                PropertyPart::Increment,
+                PropertyPart::Value,
+                PropertyPart::Name,
+            ]
+        } else {
+            &[PropertyPart::Value, PropertyPart::Name]
+        };
+        parts
+            .iter()
+            .find(|&&part| self.part_rect(index, part).contains(point))
+            .map(|&part| (index, part))
+    }
+
+    /// Acts on a click on a part of the row of the property at `index`.
+    fn activate(&mut self, index: usize, part: PropertyPart) {
+        if self.editing() != Some(index) || part != PropertyPart::Value {
+            self.commit_edit();
+        }
+
+        let property = &self.data.properties[index];
+        if property.read_only {
+            return;
+        }
+
+        match (&property.value, part) {
+            (PropertyValue::Bool(checked), PropertyPart::Value) => {
+                let checked = *checked;
+                self.set_value(index, PropertyValue::Bool(!checked));
+            }
+            (PropertyValue::Number(number), PropertyPart::Decrement) => {
+                let number = *number - self.data.step;
+                self.set_value(index, PropertyValue::Number(number));
+            }
+            (PropertyValue::Number(number), PropertyPart::Increment) => {
+                let number = *number + self.data.step;
+                self.set_value(index, PropertyValue::Number(number));
+            }
+            (PropertyValue::Number(_), PropertyPart::Value)
+            | (PropertyValue::Text(_), PropertyPart::Value) => {
+                if self.editing() != Some(index) {
+                    self.begin_edit(index);
+                }
+            }
+            (PropertyValue::Color(_), PropertyPart::Value) => {
+                self.event_queue.emit_owned(PropertyGridEvent::PickColor(index));
+            }
+            _ => {}
+        }
+    }
+
+    /// Fits the height of the grid to its rows, keeping its width.
+    fn fit_height(&mut self) {
+        let height = self.data.properties.len() as f32 * self.data.row_height;
+        let size = self.size();
+        if (size.height - height).abs() > std::f32::EPSILON {
+            self.set_size(Size::new(size.width, height));
+        }
+    }
+}
+
+impl<U, G> Widget for PropertyGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        if let (Some(index), Some(hints)) = (self.editing(), self.edit_hints()) {
+            aux.request_text_input(base::TextInputRequest {
+                hints,
+                area: self.part_rect(index, PropertyPart::Value),
+            });
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
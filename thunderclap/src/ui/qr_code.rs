@@ -0,0 +1,320 @@
+//! QR code and barcode rendering widget.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        error,
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::rc::Rc,
+};
+
+/// Bar/space widths of the Code 128 symbols, indexed by symbol value. The last entry is the stop pattern.
+const CODE_128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+/// Symbol value of the Code 128 code set B start symbol.
+const CODE_128_START_B: usize = 104;
+
+/// Amount of redundancy in a QR code, allowing it to be read even if partially obscured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCorrection {
+    /// Recovers from ~7% damage.
+    Low,
+    /// Recovers from ~15% damage.
+    Medium,
+    /// Recovers from ~25% damage.
+    Quartile,
+    /// Recovers from ~30% damage.
+    High,
+}
+
+/// Symbology used to encode data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeFormat {
+    /// 2D QR code.
+    Qr(ErrorCorrection),
+    /// 1D Code 128 barcode (code set B); supports printable ASCII.
+    Code128,
+}
+
+/// Grid of modules (the "pixels" of a code) produced by encoding data.
+///
+/// 1D barcodes consist of a single row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeModules {
+    pub columns: usize,
+    pub rows: usize,
+    /// Whether each module is dark, in row-major order.
+    pub dark: Vec<bool>,
+}
+
+impl CodeModules {
+    /// Returns whether the module at a given column and row is dark.
+    #[inline]
+    pub fn is_dark(&self, column: usize, row: usize) -> bool {
+        self.dark[row * self.columns + column]
+    }
+}
+
+/// Encodes text into modules with the given symbology.
+pub fn encode(text: &str, format: CodeFormat) -> Result<CodeModules, error::EncodeError> {
+    match format {
+        CodeFormat::Qr(ec) => {
+            let ec = match ec {
+                ErrorCorrection::Low => qrcode::EcLevel::L,
+                ErrorCorrection::Medium => qrcode::EcLevel::M,
+                ErrorCorrection::Quartile => qrcode::EcLevel::Q,
+                ErrorCorrection::High => qrcode::EcLevel::H,
+            };
+            let code = qrcode::QrCode::with_error_correction_level(text.as_bytes(), ec)?;
+            Ok(CodeModules {
+                columns: code.width(),
+                rows: code.width(),
+                dark: code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect(),
+            })
+        }
+        CodeFormat::Code128 => {
+            let mut symbols = vec![CODE_128_START_B];
+            for c in text.chars() {
+                match c {
+                    ' '..='~' => symbols.push(c as usize - ' ' as usize),
+                    _ => return Err(error::EncodeError::InvalidCharacter(c)),
+                }
+            }
+
+            let checksum =
+                symbols.iter().enumerate().map(|(i, symbol)| i.max(1) * symbol).sum::<usize>()
+                    % 103;
+            symbols.push(checksum);
+            symbols.push(CODE_128_PATTERNS.len() - 1);
+
+            // Each digit of a pattern is the width of a bar or space, alternating and starting with a bar.
+            let mut dark = Vec::new();
+            for symbol in symbols {
+                for (i, width) in CODE_128_PATTERNS[symbol].bytes().enumerate() {
+                    for _ in 0..(width - b'0') {
+                        dark.push(i % 2 == 0);
+                    }
+                }
+            }
+
+            Ok(CodeModules { columns: dark.len(), rows: 1, dark })
+        }
+    }
+}
+
+/// Renders text as a QR code or barcode, scaled to the size of the widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrCode {
+    pub text: String,
+    pub format: CodeFormat,
+    pub foreground: Color,
+    pub background: Color,
+    /// Width of the blank margin around the code, in modules.
+    pub quiet_zone: u32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for QrCode
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = QrCodeWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for QrCode
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        // Codes are read most reliably as dark on light, regardless of theme.
+        QrCode {
+            text: String::new(),
+            format: CodeFormat::Qr(ErrorCorrection::Medium),
+            foreground: Color::new(0.0, 0.0, 0.0, 1.0),
+            background: Color::new(1.0, 1.0, 1.0, 1.0),
+            quiet_zone: 4,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> QrCodeWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            QrCodeWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.update_modules();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let (modules, error) = encode_modules(&data);
+
+        let painter = theme.qr_code();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::QrCodeState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    modules: modules.clone(),
+                })
+                .cast_unit(),
+        );
+
+        QrCodeWidgetBuilder { rect, graph: graph.into(), data, painter, modules, error }.build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::QrCodeState> for QrCodeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::QrCodeState {
+        state::QrCodeState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            modules: self.modules.clone(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct QrCodeWidget {
+        widget::MAX,
+
+        <QrCode> State,
+        <state::QrCodeState> Painter,
+
+        {
+            modules: Rc<CodeModules>,
+            error: Option<error::EncodeError>,
+        },
+    }
+}
+
+impl<U, G> QrCodeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the error which occurred while encoding the text, if any.
+    /// Nothing but the background is drawn if the text could not be encoded.
+    #[inline]
+    pub fn error(&self) -> Option<&error::EncodeError> {
+        self.error.as_ref()
+    }
+
+    /// Returns the encoded modules.
+    #[inline]
+    pub fn modules(&self) -> &CodeModules {
+        &self.modules
+    }
+
+    fn update_modules(&mut self) {
+        let (modules, error) = encode_modules(&self.data);
+        self.modules = modules;
+        self.error = error;
+    }
+}
+
+fn encode_modules(data: &QrCode) -> (Rc<CodeModules>, Option<error::EncodeError>) {
+    match encode(&data.text, data.format) {
+        Ok(modules) => (Rc::new(modules), None),
+        Err(error) => (Default::default(), Some(error)),
+    }
+}
+
+impl<U, G> Widget for QrCodeWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code128_encodes_printable_ascii_only() {
+        // start, '~' and checksum symbols of 11 modules each, then the 13-module stop pattern
+        let modules = encode("~", CodeFormat::Code128).unwrap();
+        assert_eq!(modules.columns, 11 * 3 + 13);
+
+        assert!(matches!(
+            encode("\u{7f}", CodeFormat::Code128),
+            Err(error::EncodeError::InvalidCharacter('\u{7f}'))
+        ));
+        assert!(matches!(
+            encode("\u{1f}", CodeFormat::Code128),
+            Err(error::EncodeError::InvalidCharacter('\u{1f}'))
+        ));
+    }
+}
@@ -0,0 +1,443 @@
+//! Pie menu with items arranged in a ring around the cursor.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, Vector},
+        event::RcEventListener,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::f32::consts::PI,
+};
+
+/// Events emitted by a radial menu.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadialMenuEvent {
+    /// Emitted when an item has been chosen, with the index of the item.
+    #[event_key(choose)]
+    Choose(usize),
+    /// Emitted when the menu is closed without choosing an item.
+    #[event_key(dismiss)]
+    Dismiss,
+}
+
+/// Item of a radial menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialMenuItem {
+    pub label: String,
+    /// Whether the item can be chosen; disabled items are still shown, so that the layout of the ring stays the same.
+    pub enabled: bool,
+}
+
+impl RadialMenuItem {
+    /// Creates an enabled item.
+    pub fn new(label: impl Into<String>) -> Self {
+        RadialMenuItem { label: label.into(), enabled: true }
+    }
+}
+
+/// Generates an unbound terminal which handles choosing items with the mouse and keyboard.
+pub fn radial_menu_handler<U, G>(
+) -> vg::UnboundQueueHandler<RadialMenuWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        RadialMenuWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            // Presses outside the menu are left to the dismiss policy.
+            let bounds = obj.abs_rect();
+            if event.with(|(pos, _, _)| obj.data.open && bounds.contains(*pos)).is_some() {
+                obj.tracking = true;
+            }
+        }
+
+        mouse_release => {
+            // Read rather than taken, as a flick may be released outside of the input grab.
+            let (pos, _, _) = *event.get();
+            if obj.data.open && obj.tracking {
+                obj.tracking = false;
+                // Released near the center (e.g. after a click which opened the menu), the menu stays open.
+                if let Some(item) = obj.item_towards(pos) {
+                    event.consume();
+                    obj.choose(item);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            if obj.data.open {
+                let selected = obj.item_towards(pos);
+                if selected != obj.selected {
+                    obj.selected = selected;
+                    obj.command_group.repaint();
+                }
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| obj.data.open) {
+                match key {
+                    base::KeyInput::Left | base::KeyInput::Up => obj.select_next(false),
+                    base::KeyInput::Right | base::KeyInput::Down => obj.select_next(true),
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                        if let Some(selected) = obj.selected {
+                            obj.choose(selected);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        text_input => {
+            // Keep typed characters from reaching the focused text widget while open.
+            event.with(|_| obj.data.open);
+        }
+    }
+}
+
+/// Menu with its items arranged in a ring around the point it's opened at, popular in creative tools; also known as a
+/// pie menu.
+///
+/// Items are laid out clockwise, starting from the top, each occupying an equal sector of the ring. An item is chosen
+/// by clicking it, or by flicking; if the menu is opened while a mouse button is held (e.g. on press), moving the
+/// pointer towards an item and releasing the button chooses it, even beyond the ring. Releasing near the center keeps
+/// the menu open. The arrow keys move the selection around the ring and enter chooses the selected item.
+///
+/// The menu is closed as described by its `dismiss_policy`, and blocks mouse input to the rest of the window while
+/// open. As with other popups, it should be updated before and drawn after the widgets it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialMenu {
+    pub items: Vec<RadialMenuItem>,
+    pub open: bool,
+    /// Interactions which close the menu without choosing an item.
+    pub dismiss_policy: base::DismissPolicy,
+    /// Outer radius of the ring.
+    pub radius: f32,
+    /// Radius of the empty center of the ring, within which the pointer doesn't select any item.
+    pub inner_radius: f32,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the selected item.
+    pub highlight: Color,
+    /// Color of the label of the selected item.
+    pub over_highlight: Color,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for RadialMenu
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = RadialMenuWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for RadialMenu
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        RadialMenu {
+            items: Vec::new(),
+            open: false,
+            dismiss_policy: Default::default(),
+            radius: 96.0,
+            inner_radius: 24.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_background,
+            background: data.scheme.background,
+            highlight: data.scheme.primary,
+            over_highlight: data.scheme.over_primary,
+            contrast: data.contrast,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> RadialMenuWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            RadialMenuWidget<U, G> as obj,
+            U as aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.resize_to_radius();
+                    obj.sync_open(aux);
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("radial_menu", radial_menu_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let diameter = data.radius * 2.0;
+        let dismiss_listener = u_aux.window_queue().listen();
+        RadialMenuWidgetBuilder {
+            rect: RelativeRect::new(Default::default(), Size::new(diameter, diameter).cast_unit()),
+            graph: graph.into(),
+            data,
+            painter: theme.radial_menu(),
+
+            selected: None,
+            tracking: false,
+            grab: None,
+            dismiss_listener,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::RadialMenuState> for RadialMenuWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::RadialMenuState {
+        let sweep = self.sweep();
+        let items = self
+            .data
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let angle = Self::item_angle(i, sweep);
+                state::RadialMenuItemState {
+                    label: item.label.clone().into(),
+                    start_angle: angle - sweep / 2.0,
+                    end_angle: angle + sweep / 2.0,
+                    selected: self.selected == Some(i),
+                    enabled: item.enabled,
+                }
+            })
+            .collect();
+
+        state::RadialMenuState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            center: self.center(),
+            items,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        if let Some(ref grab) = self.grab {
+            grab.set_region(self.abs_rect());
+        }
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct RadialMenuWidget {
+        widget::MAX,
+
+        <RadialMenuEvent> EventQueue,
+        <RadialMenu> State,
+        <state::RadialMenuState> Painter,
+
+        {
+            selected: Option<usize>,
+            /// Whether a mouse button is held since the menu was opened or pressed, such that releasing it chooses the
+            /// item towards the pointer.
+            tracking: bool,
+            grab: Option<base::InputGrab>,
+            dismiss_listener: RcEventListener<base::WindowEvent>,
+        },
+    }
+}
+
+impl<U, G> RadialMenuWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Opens the menu centered at `center` (typically the cursor), with no item selected.
+    ///
+    /// If a mouse button is held when opening (e.g. when opened on press), releasing it away from the center chooses
+    /// the item towards the pointer.
+    pub fn open_at(&mut self, center: AbsolutePoint) {
+        let radius = self.data.radius;
+        self.set_ctxt_position((center - Vector::new(radius, radius).cast_unit()).into());
+        self.data.open = true;
+    }
+
+    /// Closes the menu without choosing an item, and emits `RadialMenuEvent::Dismiss`.
+    pub fn dismiss(&mut self) {
+        if self.data.open {
+            self.data.open = false;
+            self.event_queue.emit_owned(RadialMenuEvent::Dismiss);
+        }
+    }
+
+    /// Closes the menu and emits `RadialMenuEvent::Choose` with the item at `index`, if it's enabled.
+    pub fn choose(&mut self, index: usize) {
+        if self.data.items.get(index).map_or(false, |item| item.enabled) {
+            self.data.open = false;
+            self.event_queue.emit_owned(RadialMenuEvent::Choose(index));
+        }
+    }
+
+    /// Returns the selected item, which is highlighted.
+    #[inline]
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Returns the point the menu is centered at.
+    pub fn center(&self) -> AbsolutePoint {
+        self.abs_rect().center()
+    }
+
+    /// Returns the angle of the sector of each item, in radians.
+    fn sweep(&self) -> f32 {
+        2.0 * PI / self.data.items.len().max(1) as f32
+    }
+
+    /// Returns the angle at the middle of the sector of an item, in radians clockwise from the positive x-axis.
+    fn item_angle(index: usize, sweep: f32) -> f32 {
+        // the first item is at the top
+        -PI / 2.0 + index as f32 * sweep
+    }
+
+    /// Returns the item in the direction of `point` from the center, if `point` is beyond the inner radius.
+    fn item_towards(&self, point: AbsolutePoint) -> Option<usize> {
+        if self.data.items.is_empty() {
+            return None;
+        }
+
+        let offset = point - self.center();
+        if offset.length() < self.data.inner_radius {
+            return None;
+        }
+
+        // angle relative to the start of the sector of the first item, in [0, 2π)
+        let sweep = self.sweep();
+        let angle = (offset.y.atan2(offset.x) - Self::item_angle(0, sweep) + sweep / 2.0)
+            .rem_euclid(2.0 * PI);
+        Some((angle / sweep) as usize % self.data.items.len())
+    }
+
+    /// Moves the selection to the next (or previous) enabled item around the ring.
+    fn select_next(&mut self, forward: bool) {
+        let len = self.data.items.len();
+        let start = match (self.selected, forward) {
+            (Some(selected), _) => selected,
+            // nothing selected yet; start from the top
+            (None, true) => len.saturating_sub(1),
+            (None, false) => 0,
+        };
+        let next = (1..=len)
+            .map(|step| if forward { (start + step) % len } else { (start + len - step) % len })
+            .find(|&i| self.data.items[i].enabled);
+        if next.is_some() && next != self.selected {
+            self.selected = next;
+            self.command_group.repaint();
+        }
+    }
+
+    fn resize_to_radius(&mut self) {
+        let center = self.center();
+        let diameter = self.data.radius * 2.0;
+        if self.size() != Size::new(diameter, diameter) {
+            self.set_size(Size::new(diameter, diameter));
+            // keep the menu centered where it was opened
+            let radius = self.data.radius;
+            self.set_ctxt_position((center - Vector::new(radius, radius).cast_unit()).into());
+        }
+    }
+
+    fn sync_open(&mut self, aux: &mut U) {
+        if let Some(ref grab) = self.grab {
+            grab.set_input_transparent(
+                !self.data.dismiss_policy.contains(base::DismissPolicy::OUTSIDE_PRESS),
+            );
+        }
+
+        if self.data.open == self.grab.is_some() {
+            return;
+        }
+
+        if self.data.open {
+            self.selected = None;
+            self.tracking = true;
+            let grab = aux.input_grabs().grab(self.abs_rect());
+            grab.set_input_transparent(
+                !self.data.dismiss_policy.contains(base::DismissPolicy::OUTSIDE_PRESS),
+            );
+            self.grab = Some(grab);
+            // Forget the events which preceded opening (e.g. the press which opened the menu).
+            self.dismiss_listener.peek();
+        } else {
+            self.selected = None;
+            self.tracking = false;
+            self.grab = None;
+        }
+    }
+}
+
+impl<U, G> Widget for RadialMenuWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        let events = self.dismiss_listener.peek();
+        if self.data.open {
+            let (policy, bounds) = (self.data.dismiss_policy, self.abs_rect());
+            if events.iter().any(|event| policy.dismisses(event, bounds)) {
+                self.dismiss();
+            }
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
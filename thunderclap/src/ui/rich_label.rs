@@ -0,0 +1,619 @@
+//! Text made of differently styled spans, with clickable links.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, DisplayListBuilder, FontInfo, GraphicsDisplay,
+            GraphicsDisplayPaint, Point, Rect, ResourceReference, Size, TextDisplayItem, Vector,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::ops::Range,
+};
+
+/// Events emitted by a rich label.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RichLabelEvent {
+    /// Emitted when a link is clicked, with the identifier of the link (see `SpanStyle::link`).
+    #[event_key(link_activated)]
+    LinkActivated(usize),
+}
+
+/// Style of a span of rich text.
+///
+/// Unset properties are inherited from the text as a whole.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    /// Color of the text, overriding the color of the text as a whole (or the link color for links).
+    pub color: Option<Color>,
+    /// Text size in pixels, overriding the size of the typeface.
+    pub size: Option<f32>,
+    pub underline: bool,
+    /// Identifier of the link the span belongs to, if any. Links are underlined.
+    pub link: Option<usize>,
+    /// Typeface of the text (e.g. a monospace typeface for code), overriding the typeface of the text as a whole.
+    /// The size and style of the text as a whole still apply.
+    pub typeface: Option<draw::Typeface>,
+}
+
+/// A run of text with a single style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+impl TextSpan {
+    /// Creates an unstyled span.
+    pub fn new(text: impl Into<String>) -> Self {
+        TextSpan { text: text.into(), style: Default::default() }
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.style.italic = true;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.style.size = Some(size);
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.style.underline = true;
+        self
+    }
+
+    pub fn link(mut self, link: usize) -> Self {
+        self.style.link = Some(link);
+        self
+    }
+
+    pub fn typeface(mut self, typeface: draw::Typeface) -> Self {
+        self.style.typeface = Some(typeface);
+        self
+    }
+}
+
+impl From<&str> for TextSpan {
+    fn from(text: &str) -> Self {
+        TextSpan::new(text)
+    }
+}
+
+impl From<String> for TextSpan {
+    fn from(text: String) -> Self {
+        TextSpan::new(text)
+    }
+}
+
+/// Splits text into spans, such that the byte `ranges` of the text (e.g. `fuzzy::FuzzyMatch::ranges`) have the `highlight` style
+/// and the rest has `style`.
+pub fn highlight_spans(
+    text: &str,
+    ranges: &[Range<usize>],
+    style: &SpanStyle,
+    highlight: &SpanStyle,
+) -> Vec<TextSpan> {
+    let mut spans = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut push_span = |range: Range<usize>, style: &SpanStyle| {
+        if let Some(segment) = text.get(range) {
+            if !segment.is_empty() {
+                spans.push(TextSpan { text: segment.to_string(), style: style.clone() });
+            }
+        }
+    };
+
+    let mut end = 0;
+    for range in ranges {
+        push_span(end..range.start, style);
+        push_span(range.clone(), highlight);
+        end = range.end;
+    }
+    push_span(end..text.len(), style);
+
+    spans
+}
+
+/// Rich text which has been laid out into lines.
+#[derive(Debug, Clone, Default)]
+pub struct RichTextLayout {
+    pub items: Vec<TextDisplayItem>,
+    /// Underlines of underlined spans and links, with their color.
+    pub underlines: Vec<(AbsoluteRect, Color)>,
+    /// Areas covered by each link, with the identifier of the link; there's one area per line a link appears on.
+    pub links: Vec<(usize, AbsoluteRect)>,
+    /// Bounds of all the lines.
+    pub bounds: AbsoluteRect,
+}
+
+impl RichTextLayout {
+    /// Returns the identifier of the link at a point, if any.
+    pub fn link_at(&self, point: AbsolutePoint) -> Option<usize> {
+        self.links.iter().find(|(_, rect)| rect.contains(point)).map(|(link, _)| *link)
+    }
+
+    /// Moves all the laid out text by an offset.
+    pub fn translate(&mut self, offset: Vector) {
+        for item in &mut self.items {
+            item.bottom_left += offset;
+        }
+        for (rect, _) in &mut self.underlines {
+            *rect = rect.translate(offset.cast_unit());
+        }
+        for (_, rect) in &mut self.links {
+            *rect = rect.translate(offset.cast_unit());
+        }
+        self.bounds = self.bounds.translate(offset.cast_unit());
+    }
+
+    /// Appends display commands drawing the text and underlines to a display list.
+    pub fn push_to(&self, builder: &mut DisplayListBuilder) {
+        for item in &self.items {
+            builder.push_text(item.clone(), None);
+        }
+        for (rect, color) in &self.underlines {
+            builder.push_rectangle(
+                rect.cast_unit(),
+                GraphicsDisplayPaint::Fill((*color).into()),
+                None,
+            );
+        }
+    }
+}
+
+/// A piece of a line; consecutive words of the same span are merged into a single piece.
+struct LinePiece {
+    span: usize,
+    text: String,
+    font: (ResourceReference, FontInfo),
+    size: f32,
+    x: f32,
+    width: f32,
+    /// Width excluding trailing whitespace.
+    trimmed_width: f32,
+    ascent: f32,
+}
+
+#[derive(Default)]
+struct Line {
+    pieces: Vec<LinePiece>,
+    width: f32,
+    ascent: f32,
+    /// Descent and line gap.
+    below: f32,
+}
+
+fn text_width(text: &str, font: &(ResourceReference, FontInfo), size: f32) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+
+    TextDisplayItem {
+        text: text.to_string().into(),
+        font: font.0,
+        font_info: font.1.clone(),
+        size,
+        bottom_left: Default::default(),
+        color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+    }
+    .bounds()
+    .map(|bounds| bounds.size.width)
+    .unwrap_or(0.0)
+}
+
+/// Returns the ascent and the descent plus line gap of a font at a size, in pixels.
+fn vertical_metrics(font: &FontInfo, size: f32) -> (f32, f32) {
+    let metrics = font.font.metrics();
+    let scale = size / metrics.units_per_em as f32;
+    (metrics.ascent * scale, (metrics.line_gap - metrics.descent) * scale)
+}
+
+/// Splits text into words, each including its trailing whitespace.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let end = rest[word_end..]
+            .find(|c: char| !c.is_whitespace())
+            .map_or(rest.len(), |offset| word_end + offset);
+        let (word, remaining) = rest.split_at(end);
+        rest = remaining;
+        Some(word)
+    })
+}
+
+/// Lays out spans of rich text into lines, starting at `origin`.
+///
+/// Lines are broken at newlines and, if `width` is given, between words such that lines fit within `width`
+/// (words which are wider than `width` on their own are never broken).
+/// Links are drawn with `link_color` unless their span has a color.
+pub fn layout_rich_text(
+    spans: &[TextSpan],
+    typeface: &draw::TypefaceStyle,
+    color: Color,
+    link_color: Color,
+    origin: AbsolutePoint,
+    width: Option<f32>,
+    align: ui::TextAlign,
+) -> RichTextLayout {
+    let (base_bold, base_italic) = match typeface.style {
+        draw::TextStyle::Regular => (false, false),
+        draw::TextStyle::RegularItalic => (false, true),
+        draw::TextStyle::Bold => (true, false),
+        draw::TextStyle::BoldItalic => (true, true),
+    };
+
+    let mut lines = vec![Line::default()];
+    for (span_index, span) in spans.iter().enumerate() {
+        let style = match (span.style.bold || base_bold, span.style.italic || base_italic) {
+            (false, false) => draw::TextStyle::Regular,
+            (false, true) => draw::TextStyle::RegularItalic,
+            (true, false) => draw::TextStyle::Bold,
+            (true, true) => draw::TextStyle::BoldItalic,
+        };
+        let font = span.style.typeface.as_ref().unwrap_or(&typeface.typeface).pick(style);
+        let size = span.style.size.unwrap_or(typeface.size);
+        let (ascent, below) = vertical_metrics(&font.1, size);
+
+        for (i, paragraph) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+
+            // Empty lines still take the height of the text.
+            let line = lines.last_mut().unwrap();
+            line.ascent = line.ascent.max(ascent);
+            line.below = line.below.max(below);
+
+            for word in words(paragraph) {
+                let word_width = text_width(word, &font, size);
+                let trimmed_width = text_width(word.trim_end(), &font, size);
+
+                let line = lines.last_mut().unwrap();
+                let overflows = width.map_or(false, |width| line.width + trimmed_width > width);
+                if overflows && !line.pieces.is_empty() {
+                    lines.push(Line { ascent, below, ..Default::default() });
+                }
+
+                let line = lines.last_mut().unwrap();
+                line.ascent = line.ascent.max(ascent);
+                line.below = line.below.max(below);
+
+                match line.pieces.last_mut() {
+                    Some(piece) if piece.span == span_index => {
+                        piece.text.push_str(word);
+                        piece.trimmed_width = piece.width + trimmed_width;
+                        piece.width += word_width;
+                    }
+                    _ => line.pieces.push(LinePiece {
+                        span: span_index,
+                        text: word.to_string(),
+                        font: font.clone(),
+                        size,
+                        x: line.width,
+                        width: word_width,
+                        trimmed_width,
+                        ascent,
+                    }),
+                }
+                line.width += word_width;
+            }
+        }
+    }
+
+    let max_width =
+        width.unwrap_or_else(|| lines.iter().map(|line| line.width).fold(0.0, f32::max));
+
+    let mut layout = RichTextLayout {
+        bounds: AbsoluteRect::new(origin, Default::default()),
+        ..Default::default()
+    };
+    let mut y = origin.y;
+    for line in &lines {
+        let trimmed_width = line.pieces.last().map_or(0.0, |piece| piece.x + piece.trimmed_width);
+        let left = origin.x
+            + match align {
+                ui::TextAlign::Left => 0.0,
+                ui::TextAlign::Middle => (max_width - trimmed_width) / 2.0,
+                ui::TextAlign::Right => max_width - trimmed_width,
+            };
+        let baseline = y + line.ascent;
+
+        for piece in &line.pieces {
+            let style = &spans[piece.span].style;
+            let piece_color =
+                style.color.unwrap_or(if style.link.is_some() { link_color } else { color });
+
+            let mut item = TextDisplayItem {
+                text: piece.text.clone().into(),
+                font: piece.font.0,
+                font_info: piece.font.1.clone(),
+                size: piece.size,
+                bottom_left: Default::default(),
+                color: piece_color.into(),
+            };
+            item.set_top_left(Point::new(left + piece.x, baseline - piece.ascent));
+            layout.items.push(item);
+
+            if style.underline || style.link.is_some() {
+                let thickness = (piece.size / 14.0).max(1.0);
+                layout.underlines.push((
+                    AbsoluteRect::new(
+                        AbsolutePoint::new(left + piece.x, baseline + thickness),
+                        Size::new(piece.trimmed_width, thickness).cast_unit(),
+                    ),
+                    piece_color,
+                ));
+            }
+
+            if let Some(link) = style.link {
+                layout.links.push((
+                    link,
+                    AbsoluteRect::new(
+                        AbsolutePoint::new(left + piece.x, y),
+                        Size::new(piece.trimmed_width, line.ascent + line.below).cast_unit(),
+                    ),
+                ));
+            }
+        }
+
+        y += line.ascent + line.below;
+    }
+
+    layout.bounds.size = Size::new(max_width, y - origin.y).cast_unit();
+    layout
+}
+
+/// Label made of differently styled spans, laid out and wrapped as a single block of text.
+///
+/// Spans which are links can be clicked, emitting `RichLabelEvent::LinkActivated`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichLabel {
+    pub spans: Vec<TextSpan>,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub link_color: Color,
+    pub align: ui::TextAlign,
+    pub wrap: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for RichLabel
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = RichLabelWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for RichLabel
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        RichLabel {
+            spans: Vec::new(),
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_outset,
+            link_color: data.scheme.primary,
+            align: ui::TextAlign::Left,
+            wrap: true,
+        }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, u_aux: &mut U) -> RichLabelWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            RichLabelWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.update_layout();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("rich_label", rich_label_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let mut label = RichLabelWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            text_layout: Default::default(),
+            hovered_link: None,
+            pressed_link: None,
+            previous_rect: Default::default(),
+            dirty: true,
+        }
+        .build();
+
+        label.update_layout();
+        label.previous_rect = label.rect;
+
+        label
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        if (self.previous_rect.size.width - self.rect.size.width).abs() > std::f32::EPSILON {
+            self.dirty = true;
+        } else if self.previous_rect.origin != self.rect.origin {
+            let diff = self.rect.origin - self.previous_rect.origin;
+            self.text_layout.translate(diff.cast_unit());
+        }
+
+        self.previous_rect = self.rect;
+        self.layout.notify(self.abs_rect());
+        self.repaint();
+    }
+}
+
+/// Generates an unbound terminal which handles link hovering and clicking.
+pub fn rich_label_handler<U, G>(
+) -> vg::UnboundQueueHandler<RichLabelWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        RichLabelWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let text_layout = &obj.text_layout;
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && text_layout.link_at(*pos).is_some()
+            }) {
+                obj.pressed_link = obj.text_layout.link_at(*pos);
+            }
+        }
+
+        mouse_release => {
+            if let Some(pressed) = obj.pressed_link.take() {
+                let text_layout = &obj.text_layout;
+                if event
+                    .with(|(pos, button, _)| {
+                        *button == base::MouseButton::Left && text_layout.link_at(*pos) == Some(pressed)
+                    })
+                    .is_some()
+                {
+                    obj.event_queue.emit_owned(RichLabelEvent::LinkActivated(pressed));
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            obj.hovered_link = obj.text_layout.link_at(pos);
+        }
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct RichLabelWidget {
+        widget::MAX,
+
+        <RichLabelEvent> EventQueue,
+        <RichLabel> State,
+
+        {
+            text_layout: RichTextLayout,
+            hovered_link: Option<usize>,
+            pressed_link: Option<usize>,
+            previous_rect: RelativeRect,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> RichLabelWidget<U, G> {
+    /// Returns the laid out text.
+    #[inline]
+    pub fn text_layout(&self) -> &RichTextLayout {
+        &self.text_layout
+    }
+
+    fn update_layout(&mut self) {
+        let rect = self.abs_rect();
+        let width =
+            if self.data.wrap && rect.size.width > 0.0 { Some(rect.size.width) } else { None };
+
+        self.text_layout = layout_rich_text(
+            &self.data.spans,
+            &self.data.typeface,
+            self.data.color,
+            self.data.link_color,
+            rect.origin,
+            width,
+            self.data.align,
+        );
+
+        let size = Size::new(
+            width.unwrap_or(self.text_layout.bounds.size.width),
+            self.text_layout.bounds.size.height,
+        );
+        self.set_ctxt_rect(AbsoluteRect::new(rect.origin, size.cast_unit()));
+    }
+}
+
+impl<U, G> Widget for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        if self.dirty {
+            self.dirty = false;
+            self.update_layout();
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.hovered_link.is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle_clip(self.abs_rect().cast_unit(), true);
+        self.text_layout.push_to(&mut builder);
+        self.command_group.push(display, &builder.build(), Default::default(), None, None);
+    }
+}
@@ -0,0 +1,609 @@
+//! Search field with a dropdown of suggestions.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventListener,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Space between the edges of the popup and its rows.
+const POPUP_PADDING: f32 = 4.0;
+/// Space between the field and the popup.
+const POPUP_OFFSET: f32 = 2.0;
+
+/// Events emitted by a search box.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum SearchEvent {
+    /// Emitted once the user has stopped typing for the debounce duration, with the query.
+    #[event_key(query)]
+    Query(String),
+    /// Emitted when a suggestion has been chosen, with the text of the suggestion.
+    #[event_key(chosen)]
+    Chosen(String),
+    /// Emitted when enter is pressed without a suggestion selected, with the query.
+    #[event_key(submit)]
+    Submit(String),
+    /// Emitted when the query is cleared with the clear button.
+    #[event_key(clear)]
+    Clear,
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+/// Source of the suggestions shown below a search box.
+///
+/// Lookups may complete later (e.g. on another thread or over the network); `request` returns `None` in that case,
+/// and the results are then returned by `poll`, which is checked on every update (every frame, until it returns the
+/// results).
+/// Results of a lookup which has since been replaced by a newer request should be discarded by the provider.
+pub trait SuggestionProvider {
+    /// Starts looking up suggestions for a query, returning them immediately if they're available.
+    fn request(&mut self, query: &str) -> Option<Vec<String>>;
+    /// Returns the suggestions of the latest request, once they're available.
+    fn poll(&mut self) -> Option<Vec<String>> {
+        None
+    }
+}
+
+impl<F: FnMut(&str) -> Vec<String>> SuggestionProvider for F {
+    fn request(&mut self, query: &str) -> Option<Vec<String>> {
+        Some((self)(query))
+    }
+}
+
+/// Generates an unbound terminal which handles typing, choosing suggestions and clearing the search box.
+pub fn search_box_handler<U, G>(
+) -> vg::UnboundQueueHandler<SearchBoxWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SearchBoxWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && (bounds.contains(*pos) || obj.suggestion_at(*pos).is_some())
+            }) {
+                if let Some(index) = obj.suggestion_at(*pos) {
+                    obj.choose(index);
+                } else if obj.clear_rect().map_or(false, |clear| clear.contains(*pos)) {
+                    obj.clear();
+                    obj.focus();
+                } else {
+                    obj.focus();
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.suggestion_at(pos);
+            let clear_hovered = obj.clear_rect().map_or(false, |clear| clear.contains(pos));
+            if hovered != obj.hovered || clear_hovered != obj.clear_hovered {
+                obj.hovered = hovered;
+                obj.clear_hovered = clear_hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                let len = obj.suggestions.len();
+                match key {
+                    base::KeyInput::Back => {
+                        obj.data.text.pop();
+                    }
+                    base::KeyInput::Up if obj.open && len > 0 => {
+                        let selected = obj.selected.map_or(len - 1, |i| (i + len - 1) % len);
+                        obj.select(Some(selected));
+                    }
+                    base::KeyInput::Down if obj.open && len > 0 => {
+                        let selected = obj.selected.map_or(0, |i| (i + 1) % len);
+                        obj.select(Some(selected));
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                        match obj.selected.filter(|_| obj.open) {
+                            Some(selected) => obj.choose(selected),
+                            None => {
+                                let query = obj.data.text.clone();
+                                obj.close();
+                                obj.event_queue.emit_owned(SearchEvent::Submit(query));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                if !c.is_control() && obj.data.input_hints.accepts(c) {
+                    obj.data.text.push(c);
+                }
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.command_group.repaint();
+                obj.event_queue.emit_owned(SearchEvent::Blur);
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Single line text field for search queries, with a clear button and a popup list of suggestions below it.
+///
+/// Changes to the query are debounced; once the user stops typing for `debounce`, `SearchEvent::Query` is emitted
+/// and suggestions for the query are requested from the `SuggestionProvider` given to `SearchBoxWidget::set_provider`.
+/// The suggestions are shown once available, and can be chosen with the mouse, or with the arrow and enter keys.
+///
+/// The popup is closed as described by the `dismiss_policy`, or when the query is emptied. It overhangs the
+/// bottom of the search box; for it to appear above other widgets, the search box should be drawn after them,
+/// and updated before them so that presses on the popup don't reach them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchBox {
+    /// The query.
+    pub text: String,
+    /// Text shown while the query is empty.
+    pub placeholder: String,
+    /// Time to wait after the last change of the query before looking it up.
+    pub debounce: Duration,
+    /// Maximum number of suggestions shown.
+    pub max_suggestions: usize,
+    /// Hints on the query for the input method (e.g. `InputHints::URL` for an address bar), which also restrict the
+    /// characters which can be typed.
+    pub input_hints: base::InputHints,
+    pub row_height: f32,
+    /// Interactions which close the popup of suggestions.
+    pub dismiss_policy: base::DismissPolicy,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub focus: Color,
+    /// Background of the popup of suggestions.
+    pub popup_background: Color,
+    /// Color of the selected suggestion.
+    pub highlight: Color,
+    /// Color of the text of the selected suggestion.
+    pub over_highlight: Color,
+    /// Color of the selected suggestion while the window is inactive.
+    pub inactive_highlight: Color,
+    /// Color of the text of the selected suggestion while the window is inactive.
+    pub over_inactive_highlight: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SearchBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SearchBoxWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for SearchBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        SearchBox {
+            text: String::new(),
+            placeholder: i18n::locale().localize_text("Search"),
+            debounce: Duration::from_millis(250),
+            max_suggestions: 8,
+            input_hints: Default::default(),
+            row_height: 24.0,
+            dismiss_policy: Default::default(),
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            focus: data.scheme.focus,
+            popup_background: data.scheme.background,
+            highlight: data.scheme.list_selection,
+            over_highlight: data.scheme.over_list_selection,
+            inactive_highlight: data.scheme.inactive_selection,
+            over_inactive_highlight: data.scheme.over_inactive_selection,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> SearchBoxWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SearchBoxWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("search_box", search_box_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.search_box();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::SearchBoxState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    clear: None,
+                    clear_hovered: false,
+                    popup: None,
+                    suggestions: Vec::new(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let query = data.text.clone();
+        let dismiss_listener = u_aux.window_queue().listen();
+        SearchBoxWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            provider: None,
+            query,
+            edited: None,
+            looking_up: false,
+            suggestions: Vec::new(),
+            open: false,
+            selected: None,
+            hovered: None,
+            clear_hovered: false,
+            dismiss_listener,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SearchBoxState> for SearchBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SearchBoxState {
+        state::SearchBoxState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            clear: self.clear_rect(),
+            clear_hovered: self.clear_hovered,
+            popup: self.popup_rect(),
+            suggestions: self
+                .suggestions
+                .iter()
+                .enumerate()
+                .map(|(i, text)| state::SuggestionState {
+                    text: text.clone(),
+                    rect: self.suggestion_rect(i),
+                    selected: self.selected == Some(i),
+                    hovered: self.hovered == Some(i),
+                })
+                .collect(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SearchBoxWidget {
+        widget::MAX,
+
+        <SearchEvent> EventQueue,
+        <SearchBox> State,
+        <state::SearchBoxState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            provider: Option<Box<dyn SuggestionProvider>>,
+            /// Query as of the last change seen; compared against the text to detect changes.
+            query: String,
+            /// When the query was last changed, while waiting for the debounce.
+            edited: Option<Instant>,
+            /// Whether the suggestions of the latest request are still being looked up (see `SuggestionProvider::poll`).
+            looking_up: bool,
+            suggestions: Vec<String>,
+            open: bool,
+            selected: Option<usize>,
+            hovered: Option<usize>,
+            clear_hovered: bool,
+            dismiss_listener: RcEventListener<base::WindowEvent>,
+        },
+    }
+}
+
+impl<U, G> SearchBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the source of suggestions. Suggestions are requested from it on the next change of the query.
+    pub fn set_provider(&mut self, provider: impl SuggestionProvider + 'static) {
+        self.provider = Some(Box::new(provider));
+        self.looking_up = false;
+    }
+
+    /// Removes the source of suggestions, closing the popup.
+    pub fn clear_provider(&mut self) {
+        self.provider = None;
+        self.looking_up = false;
+        self.close();
+    }
+
+    /// Returns the suggestions for the current query.
+    #[inline]
+    pub fn suggestions(&self) -> &[String] {
+        &self.suggestions
+    }
+
+    /// Returns `true` if the popup of suggestions is open.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Replaces the query with a suggestion, closing the popup and emitting `SearchEvent::Chosen`.
+    pub fn choose(&mut self, index: usize) {
+        if let Some(text) = self.suggestions.get(index).cloned() {
+            // choosing isn't a change to look up
+            self.query = text.clone();
+            self.edited = None;
+            self.looking_up = false;
+            self.data.text = text.clone();
+            self.close();
+            self.event_queue.emit_owned(SearchEvent::Chosen(text));
+        }
+    }
+
+    /// Empties the query, closing the popup and emitting `SearchEvent::Clear`.
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.edited = None;
+        self.looking_up = false;
+        self.data.text.clear();
+        self.suggestions.clear();
+        self.close();
+        self.event_queue.emit_owned(SearchEvent::Clear);
+    }
+
+    /// Closes the popup of suggestions, keeping the suggestions.
+    pub fn close(&mut self) {
+        if self.open {
+            self.open = false;
+            self.selected = None;
+            self.hovered = None;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns the rectangle of the clear button, shown while the query isn't empty.
+    pub fn clear_rect(&self) -> Option<AbsoluteRect> {
+        if self.data.text.is_empty() || self.data.disabled {
+            return None;
+        }
+
+        let rect = self.abs_rect();
+        let size = rect.size.height;
+        Some(AbsoluteRect::new(
+            AbsolutePoint::new(rect.max_x() - size, rect.min_y()),
+            Size::new(size, size).cast_unit(),
+        ))
+    }
+
+    /// Returns the rectangle of the popup of suggestions, if open.
+    pub fn popup_rect(&self) -> Option<AbsoluteRect> {
+        if !self.open {
+            return None;
+        }
+
+        let rect = self.abs_rect();
+        Some(AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x(), rect.max_y() + POPUP_OFFSET),
+            Size::new(
+                rect.size.width,
+                self.suggestions.len() as f32 * self.data.row_height + POPUP_PADDING * 2.0,
+            )
+            .cast_unit(),
+        ))
+    }
+
+    /// Returns the index of the suggestion at a given point, if the popup is open.
+    pub fn suggestion_at(&self, point: AbsolutePoint) -> Option<usize> {
+        if !self.open {
+            return None;
+        }
+        (0..self.suggestions.len()).find(|&i| self.suggestion_rect(i).contains(point))
+    }
+
+    fn suggestion_rect(&self, index: usize) -> AbsoluteRect {
+        let popup = self.popup_rect().unwrap_or_default();
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                popup.min_x() + POPUP_PADDING,
+                popup.min_y() + POPUP_PADDING + index as f32 * self.data.row_height,
+            ),
+            Size::new(popup.size.width - POPUP_PADDING * 2.0, self.data.row_height).cast_unit(),
+        )
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+        self.command_group.repaint();
+    }
+
+    fn focus(&mut self) {
+        if !self.interaction.contains(state::InteractionState::FOCUSED) {
+            self.interaction.insert(state::InteractionState::FOCUSED);
+            self.command_group.repaint();
+            self.event_queue.emit_owned(SearchEvent::Focus);
+        }
+    }
+
+    /// Shows the suggestions for the latest query, opening the popup if there are any.
+    fn show_suggestions(&mut self, mut suggestions: Vec<String>) {
+        suggestions.truncate(self.data.max_suggestions);
+        self.suggestions = suggestions;
+        self.selected = None;
+        self.hovered = None;
+        self.open = !self.suggestions.is_empty() && !self.data.text.is_empty();
+        // Forget the events which preceded opening, so that they don't immediately dismiss the popup.
+        self.dismiss_listener.peek();
+        self.command_group.repaint();
+    }
+
+    /// Looks the query up once the user has stopped typing for the debounce duration.
+    fn update_query(&mut self) {
+        if self.data.text != self.query {
+            self.query = self.data.text.clone();
+            self.edited = Some(Instant::now());
+        }
+
+        match self.edited {
+            Some(edited) if edited.elapsed() >= self.data.debounce => {}
+            _ => return,
+        }
+        self.edited = None;
+
+        self.event_queue.emit_owned(SearchEvent::Query(self.query.clone()));
+
+        self.looking_up = false;
+        if self.query.is_empty() {
+            self.suggestions.clear();
+            self.close();
+        } else if let Some(ref mut provider) = self.provider {
+            match provider.request(&self.query) {
+                Some(suggestions) => self.show_suggestions(suggestions),
+                None => self.looking_up = true,
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for SearchBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        // the popup is as wide as the field, directly below it
+        let mut rect = self.rect;
+        if let Some(popup) = self.popup_rect() {
+            rect.size.height += POPUP_OFFSET + popup.size.height;
+        }
+        self.painter.paint_hint(rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        self.update_query();
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: self.data.input_hints,
+                area: self.abs_rect(),
+            });
+        }
+
+        let polled = self.provider.as_mut().and_then(|provider| provider.poll());
+        if let Some(suggestions) = polled {
+            self.looking_up = false;
+            self.show_suggestions(suggestions);
+        }
+
+        // the debounce and lookups complete without any input
+        if self.edited.is_some() || self.looking_up {
+            aux.request_animation_frame();
+        }
+
+        let events = self.dismiss_listener.peek();
+        if let Some(popup) = self.popup_rect() {
+            // presses on the field keep the popup open, as well as presses on the popup itself
+            let region = popup.union(&self.abs_rect());
+            if events.iter().any(|event| self.data.dismiss_policy.dismisses(event, region)) {
+                self.close();
+            }
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Freehand sketching widget driven by pen (or mouse) input.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Point, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a sketch canvas.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SketchCanvasEvent {
+    /// Emitted when a stroke is started.
+    #[event_key(begin_stroke)]
+    BeginStroke,
+    /// Emitted when a stroke is finished, with the index of the stroke.
+    #[event_key(end_stroke)]
+    EndStroke(usize),
+    /// Emitted when a stroke is erased, with the index the stroke had.
+    #[event_key(erase)]
+    Erase(usize),
+}
+
+/// A single point of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SketchPoint {
+    /// Position relative to the top-left of the canvas.
+    pub position: Point,
+    /// Pen pressure at this point, from `0.0` to `1.0`.
+    pub pressure: f32,
+}
+
+/// A freehand stroke recorded by a sketch canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SketchStroke {
+    pub points: Vec<SketchPoint>,
+    pub color: Color,
+    /// Stroke width at full pressure.
+    pub width: f32,
+}
+
+impl SketchStroke {
+    /// Returns `true` if any point of the stroke is within `radius` of `point`.
+    pub fn touches(&self, point: Point, radius: f32) -> bool {
+        self.points.iter().any(|p| (p.position - point).length() <= radius + self.width / 2.0)
+    }
+}
+
+/// Generates an unbound terminal which records strokes from pen and mouse input.
+pub fn sketch_canvas_handler<U, G>(
+) -> vg::UnboundQueueHandler<SketchCanvasWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SketchCanvasWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        pen_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, pen, _)) =
+                event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos))
+            {
+                obj.begin_stroke(*pos, *pen);
+            }
+        }
+
+        pen_move => {
+            if let Some((pos, pen, _)) = event.with(|_| obj.stroking) {
+                obj.continue_stroke(*pos, *pen);
+            }
+        }
+
+        pen_release => {
+            if event.with(|_| obj.stroking).is_some() {
+                obj.end_stroke();
+            }
+        }
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.begin_stroke(*pos, obj.data.mouse_pen);
+            }
+        }
+
+        mouse_move => {
+            if let Some((pos, _)) = event.with(|_| obj.stroking) {
+                obj.continue_stroke(*pos, obj.data.mouse_pen);
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.stroking && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.end_stroke();
+            }
+        }
+    }
+}
+
+/// Canvas which records pressure-modulated freehand strokes, suitable for annotation.
+///
+/// Pen input with the eraser end removes any strokes it touches.
+/// Mouse input is treated as a pen with the `mouse_pen` data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SketchCanvas {
+    pub strokes: Vec<SketchStroke>,
+    pub color: Color,
+    pub background: Color,
+    /// Width of new strokes at full pressure.
+    pub stroke_width: f32,
+    /// Radius of the eraser.
+    pub eraser_radius: f32,
+    /// Pen data used for mouse input.
+    pub mouse_pen: base::PenData,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SketchCanvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SketchCanvasWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for SketchCanvas
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        SketchCanvas {
+            strokes: Vec::new(),
+            color: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            stroke_width: 4.0,
+            eraser_radius: 8.0,
+            mouse_pen: base::PenData { pressure: 0.5, ..Default::default() },
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> SketchCanvasWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SketchCanvasWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("sketch", sketch_canvas_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.sketch_canvas();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::SketchCanvasState {
+                    rect: AbsoluteRect::new(
+                        Default::default(),
+                        Size::new(200.0, 200.0).cast_unit(),
+                    ),
+                    data: data.clone(),
+                })
+                .cast_unit(),
+        );
+
+        SketchCanvasWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            stroking: false,
+            erasing: false,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SketchCanvasState> for SketchCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SketchCanvasState {
+        state::SketchCanvasState { rect: self.abs_rect(), data: self.data.clone() }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SketchCanvasWidget {
+        widget::MAX,
+
+        <SketchCanvasEvent> EventQueue,
+        <SketchCanvas> State,
+        <state::SketchCanvasState> Painter,
+
+        {
+            stroking: bool,
+            erasing: bool,
+        },
+    }
+}
+
+impl<U, G> SketchCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Removes all the strokes.
+    pub fn clear(&mut self) {
+        self.data.strokes.clear();
+    }
+
+    fn local_point(&self, pos: AbsolutePoint) -> Point {
+        (pos - self.abs_rect().origin).to_point().cast_unit()
+    }
+
+    fn begin_stroke(&mut self, pos: AbsolutePoint, pen: base::PenData) {
+        self.stroking = true;
+        self.erasing = pen.eraser;
+
+        if self.erasing {
+            self.erase(pos);
+        } else {
+            let stroke = SketchStroke {
+                points: vec![SketchPoint {
+                    position: self.local_point(pos),
+                    pressure: pen.pressure,
+                }],
+                color: self.data.color,
+                width: self.data.stroke_width,
+            };
+            self.data.strokes.push(stroke);
+            self.event_queue.emit_owned(SketchCanvasEvent::BeginStroke);
+        }
+    }
+
+    fn continue_stroke(&mut self, pos: AbsolutePoint, pen: base::PenData) {
+        if self.erasing {
+            self.erase(pos);
+        } else {
+            let point = SketchPoint { position: self.local_point(pos), pressure: pen.pressure };
+            if let Some(stroke) = self.data.strokes.last_mut() {
+                stroke.points.push(point);
+            }
+        }
+    }
+
+    fn end_stroke(&mut self) {
+        self.stroking = false;
+        if !self.erasing && !self.data.strokes.is_empty() {
+            self.event_queue.emit_owned(SketchCanvasEvent::EndStroke(self.data.strokes.len() - 1));
+        }
+        self.erasing = false;
+    }
+
+    fn erase(&mut self, pos: AbsolutePoint) {
+        let point = self.local_point(pos);
+        let radius = self.data.eraser_radius;
+        while let Some(idx) = self.data.strokes.iter().position(|s| s.touches(point, radius)) {
+            self.data.strokes.remove(idx);
+            self.event_queue.emit_owned(SketchCanvasEvent::Erase(idx));
+        }
+    }
+}
+
+impl<U, G> Widget for SketchCanvasWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
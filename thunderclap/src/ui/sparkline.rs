@@ -0,0 +1,261 @@
+//! Compact chart of a rolling series of values.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::collections::VecDeque,
+};
+
+/// How the values of a sparkline are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SparklineStyle {
+    Line,
+    Bar,
+}
+
+/// Tiny chart of the most recent values of a series, without axes; suited to status bars and table cells.
+///
+/// Values are appended with `SparklineWidget::push_value`, and the oldest values are dropped beyond `capacity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sparkline {
+    /// Number of values kept and shown.
+    pub capacity: usize,
+    pub style: SparklineStyle,
+    /// Range of values spanning the height of the sparkline, or `None` to fit the values.
+    pub range: Option<(f32, f32)>,
+    /// Whether the most recent value is marked with a dot (line style only).
+    pub mark_last: bool,
+    pub color: Color,
+    /// Color of the area under the line (line style only), if filled.
+    pub fill: Option<Color>,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Sparkline
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SparklineWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Sparkline
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Sparkline {
+            capacity: 60,
+            style: SparklineStyle::Line,
+            range: None,
+            mark_last: true,
+            color: data.scheme.primary,
+            fill: None,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> SparklineWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            SparklineWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.truncate();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        let painter = theme.sparkline();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::SparklineState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    values: Vec::new(),
+                    range: (0.0, 1.0),
+                })
+                .cast_unit(),
+        );
+
+        let capacity = data.capacity;
+        SparklineWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            values: VecDeque::with_capacity(capacity),
+            feed: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SparklineState> for SparklineWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SparklineState {
+        state::SparklineState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            values: self.values.iter().copied().collect(),
+            range: self.range(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SparklineWidget {
+        widget::MAX,
+
+        <Sparkline> State,
+        <state::SparklineState> Painter,
+
+        {
+            values: VecDeque<f32>,
+            feed: Option<base::UpdateFeed<f32>>,
+        },
+    }
+}
+
+impl<U, G> SparklineWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Appends a value, dropping the oldest value if the sparkline is full.
+    ///
+    /// Only the sparkline is repainted, without invalidating its data (which would notify anything bound to it).
+    pub fn push_value(&mut self, value: f32) {
+        self.values.push_back(value);
+        self.truncate();
+        self.command_group.repaint();
+    }
+
+    /// Appends several values, in order.
+    pub fn extend_values(&mut self, values: impl IntoIterator<Item = f32>) {
+        self.values.extend(values);
+        self.truncate();
+        self.command_group.repaint();
+    }
+
+    /// Appends the values pushed into `feed`, as a batch once per update. Replaces the previous feed, if any.
+    pub fn set_feed(&mut self, feed: base::UpdateFeed<f32>) {
+        self.feed = Some(feed);
+    }
+
+    /// Stops appending values from the feed, dropping any values pending.
+    pub fn clear_feed(&mut self) {
+        self.feed = None;
+    }
+
+    /// Removes all the values.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.command_group.repaint();
+    }
+
+    /// Returns the values shown, from oldest to most recent.
+    #[inline]
+    pub fn values(&self) -> &VecDeque<f32> {
+        &self.values
+    }
+
+    /// Returns the range of values spanning the height of the sparkline.
+    pub fn range(&self) -> (f32, f32) {
+        if let Some(range) = self.data.range {
+            return range;
+        }
+
+        let (min, max) = self
+            .values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        if !min.is_finite() {
+            (0.0, 1.0)
+        } else if self.data.style == SparklineStyle::Bar {
+            // bars rise from zero
+            (min.min(0.0), max.max(0.0))
+        } else {
+            (min, max)
+        }
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.values.len().saturating_sub(self.data.capacity);
+        self.values.drain(..excess);
+    }
+}
+
+impl<U, G> Widget for SparklineWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(values) = self.feed.as_ref().map(|feed| feed.drain()) {
+            if !values.is_empty() {
+                self.extend_values(values);
+            }
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
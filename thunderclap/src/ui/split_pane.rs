@@ -0,0 +1,548 @@
+//! Split pane layout with a draggable divider.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        history,
+        ui::{self, Orientation},
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a split pane.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SplitPaneEvent {
+    /// Emitted when the user starts dragging the divider.
+    #[event_key(begin_drag)]
+    BeginDrag,
+    /// Emitted when the divider is dragged, with the new `split`.
+    #[event_key(drag)]
+    Drag(f32),
+    /// Emitted when the user stops dragging the divider, with the final `split`.
+    #[event_key(end_drag)]
+    EndDrag(f32),
+}
+
+/// Pane of a split pane which a child is placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SplitSide {
+    /// The left or top pane.
+    First,
+    /// The right or bottom pane.
+    Second,
+}
+
+struct ChildData {
+    side: SplitSide,
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Divides `rect` along `orientation` into a first pane of length `position`, a divider of length `divider`
+/// and a second pane filling the remainder.
+pub(crate) fn split_rect(
+    rect: AbsoluteRect,
+    orientation: Orientation,
+    position: f32,
+    divider: f32,
+) -> (AbsoluteRect, AbsoluteRect, AbsoluteRect) {
+    match orientation {
+        Orientation::Horizontal => {
+            let height = rect.size.height;
+            (
+                AbsoluteRect::new(rect.origin, Size::new(position, height).cast_unit()),
+                AbsoluteRect::new(
+                    AbsolutePoint::new(rect.origin.x + position, rect.origin.y),
+                    Size::new(divider, height).cast_unit(),
+                ),
+                AbsoluteRect::new(
+                    AbsolutePoint::new(rect.origin.x + position + divider, rect.origin.y),
+                    Size::new((rect.size.width - position - divider).max(0.0), height).cast_unit(),
+                ),
+            )
+        }
+        Orientation::Vertical => {
+            let width = rect.size.width;
+            (
+                AbsoluteRect::new(rect.origin, Size::new(width, position).cast_unit()),
+                AbsoluteRect::new(
+                    AbsolutePoint::new(rect.origin.x, rect.origin.y + position),
+                    Size::new(width, divider).cast_unit(),
+                ),
+                AbsoluteRect::new(
+                    AbsolutePoint::new(rect.origin.x, rect.origin.y + position + divider),
+                    Size::new(width, (rect.size.height - position - divider).max(0.0)).cast_unit(),
+                ),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DividerState {
+    Idle,
+    Dragging,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DividerEvent {
+    /// Left button pressed on the divider, with the offset of the cursor from the center of the divider.
+    Press(f32),
+    /// Left button released.
+    Release,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DividerDrag {
+    interaction: state::InteractionState,
+    /// Offset of the cursor from the center of the divider when the drag started.
+    offset: f32,
+}
+
+fn divider_machine() -> base::StateMachine<DividerState, DividerEvent, DividerDrag> {
+    base::StateMachine::new(DividerState::Idle)
+        .transition(
+            base::Transition::new(
+                DividerState::Idle,
+                DividerState::Dragging,
+                |ev: &DividerEvent| matches!(ev, DividerEvent::Press(_)),
+            )
+            .action(|drag: &mut DividerDrag, ev| {
+                if let DividerEvent::Press(offset) = ev {
+                    drag.offset = *offset;
+                }
+            }),
+        )
+        .on(DividerState::Dragging, DividerEvent::Release, DividerState::Idle)
+        .on_entry(DividerState::Dragging, |drag| {
+            drag.interaction.insert(state::InteractionState::PRESSED)
+        })
+        .on_exit(DividerState::Dragging, |drag| {
+            drag.interaction.remove(state::InteractionState::PRESSED)
+        })
+}
+
+/// Generates an unbound terminal which handles dragging of the divider.
+pub fn split_pane_handler<U, G>(
+) -> vg::UnboundQueueHandler<SplitPaneWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SplitPaneWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.divider_grab_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                let offset = obj.main_axis(*pos) - obj.main_axis(bounds.center());
+                if obj.divider.handle(&mut obj.drag, &DividerEvent::Press(offset)).is_some() {
+                    obj.event_queue.emit_owned(SplitPaneEvent::BeginDrag);
+                    obj.repaint();
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = event.get();
+            if obj.divider.is(&DividerState::Dragging) {
+                let split = obj.split_at(obj.main_axis(*pos) - obj.drag.offset);
+                obj.data.split = split;
+                obj.event_queue.emit_owned(SplitPaneEvent::Drag(split));
+                event.consume();
+            }
+
+            let hovered = obj.divider_grab_rect().contains(*pos);
+            if hovered != obj.drag.interaction.contains(state::InteractionState::HOVERED) {
+                obj.drag.interaction.set(state::InteractionState::HOVERED, hovered);
+                obj.repaint();
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| {
+                    obj.divider.is(&DividerState::Dragging) && *button == base::MouseButton::Left
+                })
+                .is_some()
+            {
+                obj.divider.handle(&mut obj.drag, &DividerEvent::Release);
+                obj.event_queue.emit_owned(SplitPaneEvent::EndDrag(obj.data.split));
+                obj.repaint();
+            }
+        }
+    }
+}
+
+/// Layout which divides its area into two panes separated by a draggable divider.
+///
+/// Children are pushed into either pane (see `SplitSide`), and are resized to fill their pane.
+/// The divider position is stored as a fraction of the available length, so the panes resize proportionally
+/// when the split pane itself is resized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitPane {
+    /// `Horizontal` places the panes side by side, `Vertical` places them on top of each other.
+    pub orientation: Orientation,
+    /// Position of the divider as a fraction of the available length, from `0.0` to `1.0`.
+    pub split: f32,
+    /// Minimum length of the first pane.
+    pub min_first: f32,
+    /// Minimum length of the second pane.
+    pub min_second: f32,
+    /// Thickness of the divider.
+    pub divider_width: f32,
+    pub color: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SplitPane
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SplitPaneWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for SplitPane
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        SplitPane {
+            orientation: Orientation::Horizontal,
+            split: 0.5,
+            min_first: 50.0,
+            min_second: 50.0,
+            divider_width: 5.0,
+            color: data.scheme.control_outset,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> SplitPaneWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SplitPaneWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("split", split_pane_handler::<U, G>().bind(u_aux.window_queue()));
+
+        SplitPaneWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+            painter: theme.split_pane(),
+
+            divider: divider_machine(),
+            drag: DividerDrag { interaction: state::InteractionState::empty(), offset: 0.0 },
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SplitPaneState> for SplitPaneWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SplitPaneState {
+        state::SplitPaneState {
+            rect: self.abs_rect(),
+            divider: self.panes().1,
+            data: *self.data,
+            interaction: self.drag.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SplitPaneWidget {
+        widget::MAX,
+
+        <SplitPaneEvent> EventQueue,
+        <SplitPane> State,
+        <state::SplitPaneState> Painter,
+
+        {
+            divider: base::StateMachine<DividerState, DividerEvent, DividerDrag>,
+            drag: DividerDrag,
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> SplitPaneWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the rectangles of the first pane, the divider and the second pane, in absolute coordinates.
+    #[inline]
+    pub fn panes(&self) -> (AbsoluteRect, AbsoluteRect, AbsoluteRect) {
+        split_rect(
+            self.abs_rect(),
+            self.data.orientation,
+            self.divider_position(),
+            self.data.divider_width,
+        )
+    }
+
+    /// Length along the main axis available to the panes (i.e. excluding the divider).
+    fn available(&self) -> f32 {
+        let length = match self.data.orientation {
+            Orientation::Horizontal => self.rect.size.width,
+            Orientation::Vertical => self.rect.size.height,
+        };
+        (length - self.data.divider_width).max(0.0)
+    }
+
+    /// Position of the divider relative to the start of the split pane, respecting the minimum pane lengths.
+    fn divider_position(&self) -> f32 {
+        let available = self.available();
+        (self.data.split * available)
+            .min(available - self.data.min_second)
+            .max(self.data.min_first)
+            .min(available)
+            .max(0.0)
+    }
+
+    /// Converts an absolute position along the main axis to a `split`, respecting the minimum pane lengths.
+    fn split_at(&self, position: f32) -> f32 {
+        let available = self.available();
+        if available <= 0.0 {
+            return self.data.split;
+        }
+        let start = self.main_axis(self.abs_rect().origin) + self.data.divider_width / 2.0;
+        let position = (position - start)
+            .min(available - self.data.min_second)
+            .max(self.data.min_first)
+            .min(available)
+            .max(0.0);
+        position / available
+    }
+
+    fn main_axis(&self, point: AbsolutePoint) -> f32 {
+        match self.data.orientation {
+            Orientation::Horizontal => point.x,
+            Orientation::Vertical => point.y,
+        }
+    }
+
+    /// Divider rectangle with some leeway, so that thin dividers are easier to grab.
+    fn divider_grab_rect(&self) -> AbsoluteRect {
+        let divider = self.panes().1;
+        let leeway = (6.0 - self.data.divider_width).max(0.0) / 2.0;
+        match self.data.orientation {
+            Orientation::Horizontal => divider.inflate(leeway, 0.0),
+            Orientation::Vertical => divider.inflate(0.0, leeway),
+        }
+    }
+}
+
+impl<U, G> base::Layout for SplitPaneWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// The pane to place the child in.
+    /// If unspecified, children go into the first pane until it is occupied.
+    type PushData = SplitSide;
+
+    fn push(&mut self, data: Option<SplitSide>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let side = data.unwrap_or_else(|| {
+            if self.rects.values().any(|data| data.side == SplitSide::First) {
+                SplitSide::Second
+            } else {
+                SplitSide::First
+            }
+        });
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                side,
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+/// Snapshots the position of the divider, as the `split` fraction.
+impl<U, G> history::Snapshot for SplitPaneWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Snapshot = f32;
+
+    #[inline]
+    fn snapshot(&self) -> f32 {
+        self.data.split
+    }
+
+    #[inline]
+    fn restore_snapshot(&mut self, snapshot: f32) {
+        self.data.split = snapshot;
+    }
+}
+
+impl<U, G> Widget for SplitPaneWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        if !self.data.disabled
+            && (self.divider.is(&DividerState::Dragging)
+                || self.drag.interaction.contains(state::InteractionState::HOVERED))
+        {
+            aux.set_cursor_icon(match self.data.orientation {
+                Orientation::Horizontal => base::CursorIcon::ResizeHorizontal,
+                Orientation::Vertical => base::CursorIcon::ResizeVertical,
+            });
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            let (first, _, second) = self.panes();
+            for (_, data) in &mut self.rects {
+                let rect = match data.side {
+                    SplitSide::First => first,
+                    SplitSide::Second => second,
+                };
+                if rect != data.rect {
+                    data.rect = rect;
+                    data.evq.emit_owned(rect);
+                }
+            }
+
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Display of frames produced externally, such as a camera feed.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui::{self, video_surface::fit_frame, VideoFit},
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, GraphicsDisplay, RasterImageFormat, RasterImageInfo, Rect,
+            ResourceData, SharedData, Size,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::sync::{Arc, Mutex},
+};
+
+/// Frame of pixels shown by a surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceFrame {
+    pub width: u32,
+    pub height: u32,
+    /// 8-bit RGBA pixels in row-major order, without row padding.
+    pub pixels: Arc<Vec<u8>>,
+}
+
+/// Thread-safe handle through which frames are submitted to a surface.
+///
+/// Cloning the handle yields another handle to the same surface, so it can be moved to a producer thread.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceHandle {
+    frame: Arc<Mutex<Option<SurfaceFrame>>>,
+}
+
+impl SurfaceHandle {
+    /// Submits a frame, superseding any frame which hasn't been shown yet.
+    ///
+    /// The surface keeps the app updating after each frame it receives, so frames submitted continuously are picked up
+    /// as they arrive; a frame submitted once the surface has gone idle is only picked up by the next update.
+    pub fn present(&self, frame: SurfaceFrame) {
+        *self.frame.lock().unwrap() = Some(frame);
+    }
+}
+
+/// Displays RGBA frames produced externally (e.g. by a camera or a renderer), fitted to the surface.
+///
+/// Frames are submitted through a `SurfaceHandle` (see `SurfaceWidget::handle`), possibly from another thread.
+/// Only the most recent frame is shown; frames submitted faster than the surface is drawn are skipped without being
+/// uploaded. For timed playback of decoded video, see [`VideoSurface`](struct.VideoSurface.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Surface {
+    pub fit: VideoFit,
+    /// Color of the bars around the frame, and of the surface when there is no frame.
+    pub background: Color,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Surface
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SurfaceWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Surface
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        // As with video, frames are shown on black regardless of theme.
+        Surface { fit: VideoFit::Contain, background: Color::new(0.0, 0.0, 0.0, 1.0) }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> SurfaceWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            SurfaceWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        let painter = theme.surface();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::SurfaceState {
+                    rect: Default::default(),
+                    data: *data,
+                    frame: None,
+                })
+                .cast_unit(),
+        );
+
+        SurfaceWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            handle: Default::default(),
+            image: Default::default(),
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SurfaceState> for SurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SurfaceState {
+        state::SurfaceState {
+            rect: self.abs_rect(),
+            data: *self.data,
+            frame: self.image.resource().map(|resource| {
+                let (source, rect) = fit_frame(self.abs_rect(), self.frame_size(), self.data.fit);
+                state::VideoFrameState { resource, source, rect }
+            }),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SurfaceWidget {
+        widget::MAX,
+
+        <Surface> State,
+        <state::SurfaceState> Painter,
+
+        {
+            handle: SurfaceHandle,
+            image: draw::UpdatableImage,
+        },
+    }
+}
+
+impl<U, G> SurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns a handle through which frames can be submitted.
+    #[inline]
+    pub fn handle(&self) -> SurfaceHandle {
+        self.handle.clone()
+    }
+
+    /// Returns the size of the frame shown in pixels, or zero if no frame has been shown.
+    pub fn frame_size(&self) -> Size {
+        self.image.size().unwrap_or_else(Size::zero)
+    }
+}
+
+impl<U, G> Widget for SurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        let frame = self.handle.frame.lock().unwrap().take();
+        if let Some(frame) = frame {
+            self.image.update(
+                ResourceData::Data(SharedData::RefCount(frame.pixels)),
+                RasterImageInfo {
+                    size: (frame.width, frame.height),
+                    format: RasterImageFormat::Rgba8,
+                },
+            );
+            self.command_group.repaint();
+            // the frame is shown in the following draw, and the next one is likely already on its way
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        self.image.upload(display);
+
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
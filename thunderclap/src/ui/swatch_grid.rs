@@ -0,0 +1,302 @@
+//! Palette of predefined colors to pick from.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a swatch grid.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SwatchEvent {
+    /// Emitted when a color is picked by the user.
+    #[event_key(picked)]
+    Picked(Color),
+}
+
+/// Generates an unbound terminal which handles picking and hovering swatches.
+pub fn swatch_grid_handler<U, G>(
+) -> vg::UnboundQueueHandler<SwatchGridWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SwatchGridWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && obj.swatch_at(*pos).is_some()
+            }) {
+                if let Some(index) = obj.swatch_at(*pos) {
+                    obj.pick(index);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = if obj.data.disabled { None } else { obj.swatch_at(pos) };
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Grid of uniformly sized swatches of predefined colors, from which one can be picked.
+///
+/// Swatches are laid out from left to right, wrapping after `columns` swatches; the grid is sized to fit them. The
+/// picked color is selected, and outlined with the `selection` color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwatchGrid {
+    pub colors: Vec<Color>,
+    /// Number of swatches in each row.
+    pub columns: usize,
+    /// Width and height of each swatch.
+    pub swatch_size: f32,
+    /// Space between swatches.
+    pub spacing: f32,
+    pub corner_radius: f32,
+    /// Index of the selected color.
+    pub selected: Option<usize>,
+    /// Color of the outline of the selected swatch.
+    pub selection: Color,
+    /// Color of the outline of every swatch, which keeps colors close to the background visible.
+    pub border: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl SwatchGrid {
+    /// Returns the number of rows needed for the colors.
+    pub fn rows(&self) -> usize {
+        let columns = self.columns.max(1);
+        (self.colors.len() + columns - 1) / columns
+    }
+
+    /// Returns the size of the grid, which fits all the swatches.
+    pub fn grid_size(&self) -> Size {
+        let extent = |count: usize| {
+            if count == 0 {
+                0.0
+            } else {
+                count as f32 * self.swatch_size + (count - 1) as f32 * self.spacing
+            }
+        };
+        Size::new(extent(self.columns.max(1).min(self.colors.len())), extent(self.rows()))
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SwatchGrid
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SwatchGridWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for SwatchGrid
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        SwatchGrid {
+            colors: Vec::new(),
+            columns: 8,
+            swatch_size: 20.0,
+            spacing: 6.0,
+            corner_radius: 3.0,
+            selected: None,
+            selection: data.scheme.focus,
+            border: draw::strengthen(data.scheme.background, 0.2, data.contrast),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> SwatchGridWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SwatchGridWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if obj.hovered.map_or(false, |hovered| hovered >= obj.data.colors.len()) {
+                        obj.hovered = None;
+                    }
+                    let size = obj.data.grid_size();
+                    obj.set_size(size);
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("swatch_grid", swatch_grid_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.swatch_grid();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::SwatchGridState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    swatches: Vec::new(),
+                })
+                .cast_unit(),
+        );
+
+        SwatchGridWidgetBuilder { rect, graph: graph.into(), data, painter, hovered: None }.build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::SwatchGridState> for SwatchGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::SwatchGridState {
+        let swatches = self
+            .data
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| state::SwatchState {
+                color,
+                rect: self.swatch_rect(i),
+                hovered: self.hovered == Some(i),
+                selected: self.data.selected == Some(i),
+            })
+            .collect();
+
+        state::SwatchGridState { rect: self.abs_rect(), data: self.data.clone(), swatches }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct SwatchGridWidget {
+        widget::MAX,
+
+        <SwatchEvent> EventQueue,
+        <SwatchGrid> State,
+        <state::SwatchGridState> Painter,
+
+        {
+            hovered: Option<usize>,
+        },
+    }
+}
+
+impl<U, G> SwatchGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Selects the color at `index` and emits `SwatchEvent::Picked` with it.
+    pub fn pick(&mut self, index: usize) {
+        if let Some(&color) = self.data.colors.get(index) {
+            self.data.selected = Some(index);
+            self.event_queue.emit_owned(SwatchEvent::Picked(color));
+        }
+    }
+
+    /// Returns the rectangle of the swatch at `index`.
+    pub fn swatch_rect(&self, index: usize) -> AbsoluteRect {
+        let columns = self.data.columns.max(1);
+        let step = self.data.swatch_size + self.data.spacing;
+        let origin = self.abs_rect().origin;
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                origin.x + (index % columns) as f32 * step,
+                origin.y + (index / columns) as f32 * step,
+            ),
+            Size::new(self.data.swatch_size, self.data.swatch_size).cast_unit(),
+        )
+    }
+
+    /// Returns the index of the swatch containing a given point, if any.
+    pub fn swatch_at(&self, point: AbsolutePoint) -> Option<usize> {
+        let columns = self.data.columns.max(1);
+        let step = self.data.swatch_size + self.data.spacing;
+        let offset = point - self.abs_rect().origin;
+        if offset.x < 0.0 || offset.y < 0.0 {
+            return None;
+        }
+
+        let (column, row) = ((offset.x / step) as usize, (offset.y / step) as usize);
+        let index = row * columns + column;
+        // points in the spacing between swatches don't belong to any
+        if column < columns
+            && index < self.data.colors.len()
+            && self.swatch_rect(index).contains(point)
+        {
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
+
+impl<U, G> Widget for SwatchGridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -3,7 +3,7 @@ use {
         base::{self, Repaintable},
         draw::{self, state},
         geom::*,
-        ui,
+        recovery, ui,
     },
     reclutch::{
         display::{Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect},
@@ -25,6 +25,9 @@ pub enum TextAreaEvent {
     /// The user modified text within the text area.
     #[event_key(user_modify)]
     UserModify(String),
+    /// The user copied text from the text area (e.g. with Ctrl+C), with the text to place on the clipboard.
+    #[event_key(copy)]
+    Copy(String),
 }
 
 pub fn text_area_handler<T, U>() -> vg::UnboundQueueHandler<T, U, base::WindowEvent>
@@ -39,15 +42,24 @@ where
 
         text_input => {
             if let Some(&c) = event.with(|_| obj.interaction().contains(state::InteractionState::FOCUSED)) {
-                if c.is_ascii_graphic() || c.is_ascii_whitespace() {
+                if obj.accepts(c) {
                     obj.push_char(c);
                 }
             }
         }
 
         key_press => {
-            if let Some((key, _)) = event.with(|_| obj.interaction().contains(state::InteractionState::FOCUSED)) {
+            // tab is left for moving the focus
+            if let Some((key, modifiers)) = event.with(|(key, _)| {
+                *key != base::KeyInput::Tab && obj.interaction().contains(state::InteractionState::FOCUSED)
+            }) {
                 match key {
+                    base::KeyInput::Copy => {
+                        obj.copy();
+                    }
+                    base::KeyInput::C if modifiers.ctrl || modifiers.logo => {
+                        obj.copy();
+                    }
                     base::KeyInput::Back => {
                         obj.remove_char();
                     }
@@ -73,6 +85,78 @@ pub trait LogicalTextArea {
     fn remove_char(&mut self);
     /// Move text cursor by an offset.
     fn move_cursor(&mut self, offset: isize);
+    /// Insert text at the cursor (e.g. when pasting), skipping the characters which couldn't be typed (see `accepts`).
+    fn insert_str(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.accepts(c) {
+                self.push_char(c);
+            }
+        }
+    }
+    /// Returns the hints on the expected text, which typed characters are checked against. Plain text by default.
+    fn input_hints(&self) -> base::InputHints {
+        base::InputHints::TEXT
+    }
+    /// Returns `true` if `c` can be typed (or pasted); control characters other than whitespace are rejected, as well
+    /// as characters which don't fit the `input_hints`.
+    fn accepts(&self, c: char) -> bool {
+        (!c.is_control() || c.is_ascii_whitespace()) && self.input_hints().accepts(c)
+    }
+    /// Returns the text which can be copied to the clipboard, if any. Nothing can be copied by default.
+    fn copy_text(&self) -> Option<String> {
+        None
+    }
+    /// Emits `TextAreaEvent::Copy` with the text returned by `copy_text`, if any.
+    fn copy(&mut self) {
+        if let Some(text) = self.copy_text() {
+            self.event_queue().emit_owned(TextAreaEvent::Copy(text));
+        }
+    }
+}
+
+/// Moves `cursor` (a byte index into `text`) back onto a character boundary, in case it was assigned one which isn't.
+fn clamp_cursor(text: &str, cursor: &mut usize) {
+    *cursor = (*cursor).min(text.len());
+    while !text.is_char_boundary(*cursor) {
+        *cursor -= 1;
+    }
+}
+
+/// Inserts `insert` into `text` at `cursor` (a byte index), then moves the cursor past it.
+///
+/// Shared by the text widgets implementing `LogicalTextArea` with a byte index cursor.
+pub(crate) fn insert_at_cursor(text: &mut String, cursor: &mut usize, insert: &str) {
+    clamp_cursor(text, cursor);
+    text.insert_str(*cursor, insert);
+    *cursor += insert.len();
+}
+
+/// Removes the character before `cursor` (a byte index) from `text`, moving the cursor back over it.
+pub(crate) fn remove_before_cursor(text: &mut String, cursor: &mut usize) {
+    clamp_cursor(text, cursor);
+    if let Some((index, _)) = text[..*cursor].char_indices().next_back() {
+        text.remove(index);
+        *cursor = index;
+    }
+}
+
+/// Moves `cursor` (a byte index into `text`) by `offset` characters, unless that would move it out of the text.
+pub(crate) fn move_cursor_by(text: &str, cursor: &mut usize, offset: isize) {
+    clamp_cursor(text, cursor);
+    let index = if offset < 0 {
+        text[..*cursor].char_indices().rev().nth((-offset) as usize - 1).map(|(i, _)| i)
+    } else if offset > 0 {
+        text[*cursor..]
+            .char_indices()
+            .map(|(i, _)| *cursor + i)
+            .chain(std::iter::once(text.len()))
+            .nth(offset as usize)
+    } else {
+        Some(*cursor)
+    };
+    if let Some(index) = index {
+        *cursor = index;
+    }
 }
 
 #[derive(
@@ -99,6 +183,7 @@ where
     graph: vg::OptionVerbGraph<Self, U>,
     painter: Box<dyn draw::Painter<state::TextAreaState>>,
     interaction: state::InteractionState,
+    focus_entry: base::FocusEntry,
     parent_position: AbsolutePoint,
 
     #[widget_rect]
@@ -145,9 +230,15 @@ where
                 self.repaint();
                 self.event_queue.emit_owned(TextAreaEvent::Blur);
             }
+            ui::InteractionEvent::Deactivate | ui::InteractionEvent::Activate => self.repaint(),
             _ => {}
         }
     }
+
+    #[inline]
+    fn focus_entry(&self) -> Option<&base::FocusEntry> {
+        Some(&self.focus_entry)
+    }
 }
 
 impl<U, G> LogicalTextArea for TextAreaWidget<U, G>
@@ -162,33 +253,41 @@ where
 
     #[inline]
     fn push_char(&mut self, c: char) {
-        {
-            let cursor = self.data.cursor;
-            self.data.text.insert(cursor, c);
-        }
+        let data = &mut *self.data;
+        insert_at_cursor(&mut data.text, &mut data.cursor, c.encode_utf8(&mut [0; 4]));
+        self.repaint();
+    }
+
+    #[inline]
+    fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|&c| self.accepts(c)).collect();
+        let data = &mut *self.data;
+        insert_at_cursor(&mut data.text, &mut data.cursor, &text);
         self.repaint();
-        self.data.cursor += 1;
     }
 
     #[inline]
     fn remove_char(&mut self) {
         self.repaint();
-        if !self.data.text.is_empty() && self.data.cursor > 0 {
-            {
-                let cursor = self.data.cursor;
-                self.data.text.remove(cursor - 1);
-            }
-            self.data.cursor -= 1;
-        }
+        let data = &mut *self.data;
+        remove_before_cursor(&mut data.text, &mut data.cursor);
     }
 
     #[inline]
     fn move_cursor(&mut self, offset: isize) {
         self.repaint();
-        let cursor = self.data.cursor as isize + offset;
-        if cursor >= 0 && cursor <= self.data.text.len() as isize {
-            self.data.cursor = cursor as _;
-        }
+        let data = &mut *self.data;
+        move_cursor_by(&data.text, &mut data.cursor, offset);
+    }
+
+    #[inline]
+    fn input_hints(&self) -> base::InputHints {
+        self.data.input_hints
+    }
+
+    #[inline]
+    fn copy_text(&self) -> Option<String> {
+        Some(self.data.text.clone())
     }
 }
 
@@ -200,8 +299,16 @@ pub struct TextArea {
     pub color: Color,
     pub placeholder_color: Color,
     pub cursor_color: Color,
+    /// Hints on the expected text for the input method, which also restrict the characters which can be typed.
+    pub input_hints: base::InputHints,
     pub disabled: bool,
+    /// Position in the tab order, ahead of widgets without one (see `base::FocusChain`).
+    pub tab_index: Option<u32>,
+    /// Byte index of the cursor in `text`, on a character boundary.
     pub cursor: usize,
+    /// Key under which the text is stored for crash recovery, if any.
+    /// The text is stored whenever it changes; restoring recovered text is left to the app.
+    pub recovery: Option<recovery::RecoveryKey>,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for TextArea
@@ -226,8 +333,11 @@ where
             color: data.scheme.over_control_inset,
             placeholder_color: draw::weaken(data.scheme.over_control_inset, 0.5, data.contrast),
             cursor_color: draw::weaken(data.scheme.over_control_inset, 0.1, data.contrast),
+            input_hints: Default::default(),
             disabled: false,
+            tab_index: None,
             cursor: 0,
+            recovery: None,
         }
     }
 
@@ -241,7 +351,12 @@ where
         let mut graph = vg::verbgraph! {
             TextAreaWidget<U, G> as obj,
             U as _aux,
-            "bind" => _ev in &data.on_change => { change => { obj.repaint(); } }
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.store_recovery();
+                    obj.repaint();
+                }
+            }
         };
 
         graph = graph.add(
@@ -265,13 +380,14 @@ where
                 .cast_unit(),
         );
 
-        TextAreaWidget {
+        let text_area = TextAreaWidget {
             event_queue: Default::default(),
             data,
 
             graph: graph.into(),
             painter: theme.text_area(),
             interaction: state::InteractionState::empty(),
+            focus_entry: u_aux.focus_chain().entry(),
             parent_position: Default::default(),
 
             rect,
@@ -281,7 +397,11 @@ where
             layout: Default::default(),
 
             phantom_g: Default::default(),
-        }
+        };
+
+        text_area.store_recovery();
+
+        text_area
     }
 }
 
@@ -295,6 +415,12 @@ where
         self.layout.notify(self.abs_rect());
     }
 
+    fn store_recovery(&self) {
+        if let Some(recovery) = &self.data.recovery {
+            recovery.store(&self.data.text);
+        }
+    }
+
     fn derive_state(&self) -> state::TextAreaState {
         state::TextAreaState {
             rect: self.abs_rect(),
@@ -328,6 +454,11 @@ where
     }
 
     fn update(&mut self, aux: &mut U) {
+        self.focus_entry.set_tab_index(self.data.tab_index);
+        self.focus_entry.set_group(self.layout.focus_group());
+        self.focus_entry
+            .set_focusable(!self.data.disabled && base::HasVisibility::visibility(self).renders());
+
         let mut graph = self.graph.take().unwrap();
         graph.update_all(self, aux);
         self.graph = Some(graph);
@@ -336,6 +467,13 @@ where
             self.set_ctxt_rect(rect);
             self.command_group.repaint();
         }
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::FOCUSED) {
+            aux.request_text_input(base::TextInputRequest {
+                hints: self.data.input_hints,
+                area: self.abs_rect(),
+            });
+        }
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
@@ -405,3 +543,83 @@ impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop for TextAreaWid
         self.drop_event.emit_owned(base::DropEvent);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Field {
+        event_queue: RcEventQueue<TextAreaEvent>,
+        text: String,
+        cursor: usize,
+    }
+
+    impl LogicalTextArea for Field {
+        fn event_queue(&mut self) -> &mut RcEventQueue<TextAreaEvent> {
+            &mut self.event_queue
+        }
+
+        fn push_char(&mut self, c: char) {
+            insert_at_cursor(&mut self.text, &mut self.cursor, c.encode_utf8(&mut [0; 4]));
+        }
+
+        fn remove_char(&mut self) {
+            remove_before_cursor(&mut self.text, &mut self.cursor);
+        }
+
+        fn move_cursor(&mut self, offset: isize) {
+            move_cursor_by(&self.text, &mut self.cursor, offset);
+        }
+    }
+
+    fn field() -> Field {
+        Field { event_queue: RcEventQueue::new(), text: String::new(), cursor: 0 }
+    }
+
+    #[test]
+    fn paste_non_ascii_then_type_and_backspace() {
+        let mut field = field();
+        field.insert_str("é日");
+        field.push_char('a');
+        assert_eq!(field.text, "é日a");
+        assert_eq!(field.cursor, field.text.len());
+
+        field.remove_char();
+        field.remove_char();
+        assert_eq!(field.text, "é");
+        field.push_char('b');
+        assert_eq!(field.text, "éb");
+    }
+
+    #[test]
+    fn cursor_moves_by_characters() {
+        let mut field = field();
+        field.insert_str("aé日");
+        field.move_cursor(-2);
+        assert_eq!(field.cursor, 1);
+        field.push_char('x');
+        assert_eq!(field.text, "axé日");
+        field.move_cursor(1);
+        field.remove_char();
+        assert_eq!(field.text, "ax日");
+        field.move_cursor(5);
+        assert_eq!(field.cursor, 2);
+        field.move_cursor(1);
+        assert_eq!(field.cursor, field.text.len());
+    }
+
+    #[test]
+    fn cursor_off_a_character_boundary_is_clamped() {
+        let (mut text, mut cursor) = (String::from("é"), 1);
+        insert_at_cursor(&mut text, &mut cursor, "a");
+        assert_eq!(text, "aé");
+        assert_eq!(cursor, 1);
+    }
+
+    #[test]
+    fn paste_is_filtered_like_typing() {
+        let mut field = field();
+        field.insert_str("a\u{7}b\u{1b}");
+        assert_eq!(field.text, "ab");
+    }
+}
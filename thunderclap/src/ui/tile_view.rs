@@ -0,0 +1,717 @@
+//! Pannable, zoomable viewer of tiled images, such as maps.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        error::TileError,
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, DisplayText, GraphicsDisplay, ImageData, Point, Rect,
+            ResourceData, ResourceDescriptor, ResourceReference, SharedData, Size,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        path::PathBuf,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+    },
+};
+
+/// Distance the cursor can move between press and release for it to still count as a click.
+const CLICK_THRESHOLD: f32 = 4.0;
+/// How many levels up to search for a loaded tile to show while a tile is loading.
+const FALLBACK_LEVELS: u32 = 3;
+
+/// Events emitted by a tile view.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum TileViewEvent {
+    /// Emitted when the user pans or zooms the view.
+    #[event_key(view_change)]
+    ViewChange,
+    /// Emitted when a marker is clicked, with the index of the marker.
+    #[event_key(marker_click)]
+    MarkerClick(usize),
+    /// Emitted when a tile fails to load.
+    #[event_key(tile_failed)]
+    TileFailed(TileId),
+}
+
+/// Identifies a tile within the tile pyramid.
+///
+/// Level `0` consists of a single tile, and each level has twice as many tiles along each axis as the level before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub level: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    #[inline]
+    pub fn new(level: u32, x: u32, y: u32) -> Self {
+        TileId { level, x, y }
+    }
+
+    /// Returns the tile one level up which contains this tile, or `None` for level `0`.
+    pub fn parent(self) -> Option<TileId> {
+        if self.level == 0 {
+            None
+        } else {
+            Some(TileId { level: self.level - 1, x: self.x / 2, y: self.y / 2 })
+        }
+    }
+
+    /// Replaces `{z}`, `{x}` and `{y}` in `template` with the level and coordinates of the tile,
+    /// e.g. to build the path or URL of a tile.
+    pub fn format(self, template: &str) -> String {
+        template
+            .replace("{z}", &self.level.to_string())
+            .replace("{x}", &self.x.to_string())
+            .replace("{y}", &self.y.to_string())
+    }
+}
+
+/// Source of tile images for a tile view.
+///
+/// Tiles are loaded asynchronously; `request` starts loading a tile and mustn't block,
+/// and the tile view repeatedly invokes `poll` to receive the tiles which have finished loading (every frame, for as
+/// long as any tile is loading).
+pub trait TileProvider {
+    /// Starts loading a tile.
+    fn request(&mut self, tile: TileId);
+    /// Notifies that a requested tile is no longer needed, because it has left the view.
+    /// Providers may ignore this and yield the tile regardless.
+    fn cancel(&mut self, _tile: TileId) {}
+    /// Returns the tiles which have finished loading (or failed to) since the last invocation.
+    fn poll(&mut self) -> Vec<(TileId, Result<ResourceData, TileError>)>;
+}
+
+/// Provides tiles from image files on the local filesystem.
+#[derive(Debug)]
+pub struct LocalTileProvider {
+    template: String,
+    loaded: Vec<(TileId, Result<ResourceData, TileError>)>,
+}
+
+impl LocalTileProvider {
+    /// Creates a provider loading tiles from the paths given by `template` (see `TileId::format`),
+    /// e.g. `"tiles/{z}/{x}/{y}.png"`.
+    pub fn new(template: impl Into<String>) -> Self {
+        LocalTileProvider { template: template.into(), loaded: Vec::new() }
+    }
+}
+
+impl TileProvider for LocalTileProvider {
+    fn request(&mut self, tile: TileId) {
+        let path = PathBuf::from(tile.format(&self.template));
+        let result = if path.is_file() {
+            Ok(ResourceData::File(path))
+        } else {
+            Err(TileError::NotFound(path.display().to_string()))
+        };
+        self.loaded.push((tile, result));
+    }
+
+    fn poll(&mut self) -> Vec<(TileId, Result<ResourceData, TileError>)> {
+        std::mem::take(&mut self.loaded)
+    }
+}
+
+/// Provides tiles by invoking a (blocking) fetch function on a pool of worker threads.
+///
+/// This is intended for remote tile servers; the fetch function can use any HTTP client to download
+/// the encoded image of a tile, for example:
+/// ```ignore
+/// ThreadedTileProvider::new(4, |tile| {
+///     let url = tile.format("https://tiles.example.com/{z}/{x}/{y}.png");
+///     download(&url).map_err(|err| TileError::Fetch(err.to_string()))
+/// })
+/// ```
+#[derive(Debug)]
+pub struct ThreadedTileProvider {
+    requests: mpsc::Sender<TileId>,
+    results: mpsc::Receiver<(TileId, Result<Vec<u8>, TileError>)>,
+    cancelled: Arc<Mutex<HashSet<TileId>>>,
+}
+
+impl ThreadedTileProvider {
+    /// Creates a provider with `threads` worker threads which invoke `fetch` to obtain the encoded image of each tile.
+    ///
+    /// The worker threads exit once the provider is dropped.
+    pub fn new<F>(threads: usize, fetch: F) -> Self
+    where
+        F: Fn(TileId) -> Result<Vec<u8>, TileError> + Send + Sync + 'static,
+    {
+        let (request_sender, request_receiver) = mpsc::channel::<TileId>();
+        let (result_sender, results) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let cancelled = Arc::new(Mutex::new(HashSet::new()));
+        let fetch = Arc::new(fetch);
+
+        for _ in 0..threads.max(1) {
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            let cancelled = cancelled.clone();
+            let fetch = fetch.clone();
+            thread::spawn(move || loop {
+                // The lock is only held while waiting for a request, so other workers can fetch meanwhile.
+                let tile = match request_receiver.lock().unwrap().recv() {
+                    Ok(tile) => tile,
+                    Err(_) => break,
+                };
+                if cancelled.lock().unwrap().remove(&tile) {
+                    continue;
+                }
+                if result_sender.send((tile, (*fetch)(tile))).is_err() {
+                    break;
+                }
+            });
+        }
+
+        ThreadedTileProvider { requests: request_sender, results, cancelled }
+    }
+}
+
+impl TileProvider for ThreadedTileProvider {
+    fn request(&mut self, tile: TileId) {
+        self.cancelled.lock().unwrap().remove(&tile);
+        // Sending only fails if all the workers have panicked.
+        let _ = self.requests.send(tile);
+    }
+
+    fn cancel(&mut self, tile: TileId) {
+        self.cancelled.lock().unwrap().insert(tile);
+    }
+
+    fn poll(&mut self) -> Vec<(TileId, Result<ResourceData, TileError>)> {
+        self.results
+            .try_iter()
+            .map(|(tile, result)| {
+                (tile, result.map(|data| ResourceData::Data(SharedData::RefCount(Arc::new(data)))))
+            })
+            .collect()
+    }
+}
+
+/// Point of interest shown above the tiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMarker {
+    /// Position in level `0` pixels (i.e. in the range of `0.0` to `tile_size`).
+    pub position: Point,
+    pub color: Color,
+    /// Text shown next to the marker.
+    pub label: Option<DisplayText>,
+}
+
+enum TileEntry {
+    Loading,
+    /// Loaded, but not yet uploaded to the display.
+    Loaded(ResourceData),
+    Ready(ResourceReference),
+    Failed,
+}
+
+struct CachedTile {
+    entry: TileEntry,
+    /// Update in which the tile was last visible.
+    last_used: u64,
+}
+
+struct TileDrag {
+    origin: AbsolutePoint,
+    center: Point,
+    moved: bool,
+}
+
+/// Generates an unbound terminal which handles panning, zooming and marker clicks.
+pub fn tile_view_handler<U, G>(
+) -> vg::UnboundQueueHandler<TileViewWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        TileViewWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                obj.drag = Some(TileDrag { origin: *pos, center: obj.data.center, moved: false });
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            obj.cursor = pos;
+            let scale = obj.scale();
+            if let Some(ref mut drag) = obj.drag {
+                drag.moved |= (pos - drag.origin).length() > CLICK_THRESHOLD;
+                if drag.moved {
+                    obj.data.center = drag.center - (pos - drag.origin).cast_unit() / scale;
+                    obj.event_queue.emit_owned(TileViewEvent::ViewChange);
+                }
+                event.consume();
+            }
+        }
+
+        mouse_release => {
+            if let Some((pos, _, _)) = event
+                .with(|(_, button, _)| obj.drag.is_some() && *button == base::MouseButton::Left)
+            {
+                if let Some(TileDrag { moved: false, .. }) = obj.drag.take() {
+                    if let Some(index) = obj.marker_at(*pos) {
+                        obj.event_queue.emit_owned(TileViewEvent::MarkerClick(index));
+                    }
+                }
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, delta, _)) =
+                event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos))
+            {
+                let zoom = obj.data.zoom + delta.y * obj.data.zoom_speed;
+                obj.zoom_at(*pos, zoom);
+                obj.event_queue.emit_owned(TileViewEvent::ViewChange);
+            }
+        }
+    }
+}
+
+/// Pannable, zoomable view of a pyramid of image tiles, with markers shown above.
+///
+/// Tiles are loaded through a `TileProvider` (see `TileViewWidget::set_provider`).
+/// While a tile is loading, a scaled portion of an already loaded tile from a lower level is shown in its place.
+/// Tile images are expected to be `tile_size` pixels square.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileView {
+    /// Size of a tile at its own level.
+    pub tile_size: f32,
+    pub min_level: u32,
+    pub max_level: u32,
+    /// Point at the center of the view, in level `0` pixels.
+    pub center: Point,
+    /// Current zoom, in levels; a zoom of `n` shows the tiles of level `n` at their own size.
+    pub zoom: f32,
+    /// Zoom change per logical pixel scrolled.
+    pub zoom_speed: f32,
+    /// Maximum number of tiles kept in memory.
+    pub cache_size: usize,
+    pub markers: Vec<TileMarker>,
+    pub marker_radius: f32,
+    pub typeface: draw::TypefaceStyle,
+    /// Color of marker labels.
+    pub foreground: Color,
+    /// Color behind the tiles.
+    pub background: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for TileView
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = TileViewWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for TileView
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        TileView {
+            tile_size: 256.0,
+            min_level: 0,
+            max_level: 19,
+            center: Point::new(128.0, 128.0),
+            zoom: 0.0,
+            zoom_speed: 0.0125,
+            cache_size: 256,
+            markers: Vec::new(),
+            marker_radius: 6.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> TileViewWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            TileViewWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("tile_view", tile_view_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.tile_view();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::TileViewState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    tiles: Vec::new(),
+                    markers: Vec::new(),
+                })
+                .cast_unit(),
+        );
+
+        TileViewWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            provider: None,
+            tiles: HashMap::new(),
+            evicted: Vec::new(),
+            frame: 0,
+            drag: None,
+            cursor: Default::default(),
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::TileViewState> for TileViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::TileViewState {
+        state::TileViewState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            tiles: self.tile_images(),
+            markers: self
+                .data
+                .markers
+                .iter()
+                .map(|marker| self.world_to_screen(marker.position))
+                .collect(),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct TileViewWidget {
+        widget::MAX,
+
+        <TileViewEvent> EventQueue,
+        <TileView> State,
+        <state::TileViewState> Painter,
+
+        {
+            provider: Option<Box<dyn TileProvider>>,
+            tiles: HashMap<TileId, CachedTile>,
+            evicted: Vec<ResourceReference>,
+            frame: u64,
+            drag: Option<TileDrag>,
+            cursor: AbsolutePoint,
+        },
+    }
+}
+
+impl<U, G> TileViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the source of tiles, discarding all the tiles loaded so far.
+    pub fn set_provider(&mut self, provider: impl TileProvider + 'static) {
+        self.provider = Some(Box::new(provider));
+        self.reload();
+    }
+
+    /// Discards all the loaded tiles, so that they are loaded again.
+    pub fn reload(&mut self) {
+        for (_, cached) in self.tiles.drain() {
+            if let TileEntry::Ready(resource) = cached.entry {
+                self.evicted.push(resource);
+            }
+        }
+        self.repaint();
+    }
+
+    /// Converts a point in level `0` pixels to absolute coordinates.
+    pub fn world_to_screen(&self, point: Point) -> AbsolutePoint {
+        self.abs_rect().center() + ((point - self.data.center) * self.scale()).cast_unit()
+    }
+
+    /// Converts a point in absolute coordinates to level `0` pixels.
+    pub fn screen_to_world(&self, point: AbsolutePoint) -> Point {
+        self.data.center + (point - self.abs_rect().center()).cast_unit() / self.scale()
+    }
+
+    /// Changes the zoom while keeping the world point under `point` (in absolute coordinates) in place.
+    pub fn zoom_at(&mut self, point: AbsolutePoint, zoom: f32) {
+        let anchor = self.screen_to_world(point);
+        self.data.zoom = zoom.max(self.data.min_level as f32).min(self.data.max_level as f32);
+        self.data.center = anchor - (point - self.abs_rect().center()).cast_unit() / self.scale();
+    }
+
+    /// Returns the index of the topmost marker at a given point, if any.
+    pub fn marker_at(&self, point: AbsolutePoint) -> Option<usize> {
+        let radius = self.data.marker_radius + 2.0;
+        self.data
+            .markers
+            .iter()
+            .rposition(|marker| (self.world_to_screen(marker.position) - point).length() <= radius)
+    }
+
+    /// Scale of the current zoom relative to level `0`.
+    fn scale(&self) -> f32 {
+        2f32.powf(self.data.zoom)
+    }
+
+    /// The level whose tiles are shown at the current zoom.
+    fn level(&self) -> u32 {
+        (self.data.zoom.round().max(0.0) as u32).max(self.data.min_level).min(self.data.max_level)
+    }
+
+    fn tile_rect(&self, tile: TileId) -> AbsoluteRect {
+        let size = self.data.tile_size / (1u64 << tile.level) as f32;
+        AbsoluteRect::new(
+            self.world_to_screen(Point::new(tile.x as f32 * size, tile.y as f32 * size)),
+            (Size::new(size, size) * self.scale()).cast_unit(),
+        )
+    }
+
+    fn visible_tiles(&self) -> Vec<TileId> {
+        let level = self.level();
+        let count = 1u64 << level;
+        let size = self.data.tile_size / count as f32;
+
+        let rect = self.abs_rect();
+        let min = self.screen_to_world(rect.origin);
+        let max = self.screen_to_world(AbsolutePoint::new(rect.max_x(), rect.max_y()));
+        let range = |min: f32, max: f32| {
+            let first = (min / size).floor().max(0.0) as u64;
+            let last = ((max / size).ceil().max(0.0) as u64).min(count);
+            first as u32..last as u32
+        };
+
+        let columns = range(min.x, max.x);
+        range(min.y, max.y)
+            .flat_map(|y| columns.clone().map(move |x| TileId { level, x, y }))
+            .collect()
+    }
+
+    /// Returns the image to draw for each visible tile, falling back to part of a loaded ancestor tile.
+    fn tile_images(&self) -> Vec<state::TileImage> {
+        self.visible_tiles()
+            .into_iter()
+            .filter_map(|tile| {
+                let mut ancestor = tile;
+                for depth in 0..=FALLBACK_LEVELS {
+                    if let Some(CachedTile { entry: TileEntry::Ready(resource), .. }) =
+                        self.tiles.get(&ancestor)
+                    {
+                        let size = self.data.tile_size / (1u32 << depth) as f32;
+                        let mask = (1u32 << depth) - 1;
+                        return Some(state::TileImage {
+                            resource: *resource,
+                            source: if depth == 0 {
+                                None
+                            } else {
+                                Some(Rect::new(
+                                    Point::new(
+                                        (tile.x & mask) as f32 * size,
+                                        (tile.y & mask) as f32 * size,
+                                    ),
+                                    Size::new(size, size),
+                                ))
+                            },
+                            rect: self.tile_rect(tile),
+                        });
+                    }
+                    ancestor = ancestor.parent()?;
+                }
+                None
+            })
+            .collect()
+    }
+
+    /// Receives loaded tiles, requests visible tiles and evicts tiles which haven't been visible for the longest.
+    fn update_tiles(&mut self) {
+        self.frame += 1;
+        let frame = self.frame;
+        let visible: HashSet<_> = self.visible_tiles().into_iter().collect();
+
+        let provider = match self.provider {
+            Some(ref mut provider) => provider,
+            None => return,
+        };
+
+        let mut changed = false;
+        for (tile, result) in provider.poll() {
+            let entry = match result {
+                Ok(data) => TileEntry::Loaded(data),
+                Err(_) => {
+                    self.event_queue.emit_owned(TileViewEvent::TileFailed(tile));
+                    TileEntry::Failed
+                }
+            };
+            self.tiles.insert(tile, CachedTile { entry, last_used: frame });
+            changed = true;
+        }
+
+        for tile in &visible {
+            self.tiles
+                .entry(*tile)
+                .or_insert_with(|| {
+                    provider.request(*tile);
+                    CachedTile { entry: TileEntry::Loading, last_used: frame }
+                })
+                .last_used = frame;
+
+            // Keep the fallback tiles alive too.
+            let mut ancestor = tile.parent();
+            for _ in 0..FALLBACK_LEVELS {
+                match ancestor {
+                    Some(parent) => {
+                        if let Some(cached) = self.tiles.get_mut(&parent) {
+                            cached.last_used = frame;
+                        }
+                        ancestor = parent.parent();
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        self.tiles.retain(|tile, cached| match cached.entry {
+            TileEntry::Loading if !visible.contains(tile) => {
+                provider.cancel(*tile);
+                false
+            }
+            _ => true,
+        });
+
+        if self.tiles.len() > self.data.cache_size {
+            let mut candidates: Vec<_> = self
+                .tiles
+                .iter()
+                .filter(|(_, cached)| cached.last_used != frame)
+                .map(|(tile, cached)| (cached.last_used, *tile))
+                .collect();
+            candidates.sort_by_key(|(last_used, _)| *last_used);
+            let excess = self.tiles.len() - self.data.cache_size;
+            for (_, tile) in candidates.into_iter().take(excess) {
+                if let Some(CachedTile { entry: TileEntry::Ready(resource), .. }) =
+                    self.tiles.remove(&tile)
+                {
+                    self.evicted.push(resource);
+                }
+            }
+        }
+
+        if changed {
+            self.repaint();
+        }
+    }
+}
+
+impl<U, G> Widget for TileViewWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.update_tiles();
+        // tiles are loaded by the provider, which doesn't wake the app once they're done
+        if self.tiles.values().any(|cached| matches!(cached.entry, TileEntry::Loading)) {
+            aux.request_animation_frame();
+        }
+
+        if self.drag.as_ref().map(|drag| drag.moved).unwrap_or(false) {
+            aux.set_cursor_icon(base::CursorIcon::Grabbing);
+        } else if self.marker_at(self.cursor).is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        for resource in self.evicted.drain(..) {
+            display.remove_resource(resource);
+        }
+
+        // Tile images can only be uploaded once the display is available.
+        for (tile, cached) in &mut self.tiles {
+            let uploaded = match cached.entry {
+                TileEntry::Loaded(ref data) => Some(
+                    display
+                        .new_resource(ResourceDescriptor::Image(ImageData::Encoded(data.clone()))),
+                ),
+                _ => None,
+            };
+            match uploaded {
+                Some(Ok(resource)) => cached.entry = TileEntry::Ready(resource),
+                Some(Err(_)) => {
+                    cached.entry = TileEntry::Failed;
+                    self.event_queue.emit_owned(TileViewEvent::TileFailed(*tile));
+                }
+                None => {}
+            }
+        }
+
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
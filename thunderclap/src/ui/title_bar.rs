@@ -0,0 +1,377 @@
+//! Custom title bar for frameless windows.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Maximum time between two presses on the caption for them to count as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Events emitted by a title bar.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleBarEvent {
+    /// Emitted when the window has been minimized by the user.
+    #[event_key(minimize)]
+    Minimize,
+    /// Emitted when the window has been maximized by the user.
+    #[event_key(maximize)]
+    Maximize,
+    /// Emitted when the window has been restored from being maximized by the user.
+    #[event_key(restore)]
+    Restore,
+    /// Emitted when the close button has been pressed.
+    #[event_key(close)]
+    Close,
+}
+
+/// Button shown at the end of a title bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowButton {
+    Minimize,
+    /// Maximizes the window, or restores it while maximized.
+    Maximize,
+    Close,
+}
+
+/// Generates an unbound terminal which handles pressing the buttons and double-clicking the caption of a title bar.
+pub fn title_bar_handler<U, G>(
+) -> vg::UnboundQueueHandler<TitleBarWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        TitleBarWidget<U, G> as obj,
+        U as aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                match obj.button_at(*pos) {
+                    Some(index) => {
+                        obj.pressed = Some(index);
+                        obj.last_press = None;
+                        obj.command_group.repaint();
+                    }
+                    None => {
+                        // pressing the caption moves the window (handled by the app), and double-clicking it
+                        // toggles between maximized and restored
+                        let now = Instant::now();
+                        let double_click =
+                            obj.last_press.map_or(false, |time| now - time <= DOUBLE_CLICK_TIME);
+                        if double_click && obj.data.buttons.contains(&WindowButton::Maximize) {
+                            obj.last_press = None;
+                            obj.trigger(WindowButton::Maximize, aux);
+                        } else {
+                            obj.last_press = Some(now);
+                        }
+                    }
+                }
+            }
+        }
+
+        mouse_release => {
+            if let Some(pressed) = obj.pressed {
+                if let Some((pos, _, _)) =
+                    event.with(|(_, button, _)| *button == base::MouseButton::Left)
+                {
+                    obj.pressed = None;
+                    obj.command_group.repaint();
+                    if obj.button_at(*pos) == Some(pressed) {
+                        let button = obj.data.buttons[pressed];
+                        obj.trigger(button, aux);
+                    }
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.button_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Title bar with minimize, maximize and close buttons, for windows drawing their own chrome.
+///
+/// Intended for frameless windows (see `app::AppOptions::frameless`); the title bar declares itself as the caption
+/// of the window, so dragging it moves the window, and double-clicking it maximizes or restores the window.
+/// The buttons request the corresponding window actions, and emit `TitleBarEvent`s.
+///
+/// Whether the window is maximized is tracked from the buttons; if the window is maximized or restored otherwise
+/// (e.g. by a keyboard shortcut of the window manager), `maximized` should be updated by the app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleBar {
+    pub title: String,
+    /// Buttons shown at the end of the title bar, from left to right.
+    pub buttons: Vec<WindowButton>,
+    /// Whether the window is maximized, in which case the maximize button restores it.
+    pub maximized: bool,
+    /// Whether the close button only emits `TitleBarEvent::Close`, leaving the app to close the window (e.g. after
+    /// asking to save changes).
+    pub intercept_close: bool,
+    pub button_width: f32,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color behind the hovered close button.
+    pub close_hover: Color,
+    /// Color of the icon of the hovered close button.
+    pub over_close_hover: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for TitleBar
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = TitleBarWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for TitleBar
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        TitleBar {
+            title: String::new(),
+            buttons: vec![WindowButton::Minimize, WindowButton::Maximize, WindowButton::Close],
+            maximized: false,
+            intercept_close: false,
+            button_width: 46.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_outset,
+            background: data.scheme.control_outset,
+            close_hover: data.scheme.error,
+            over_close_hover: data.scheme.over_error,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> TitleBarWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            TitleBarWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    let count = obj.data.buttons.len();
+                    if obj.hovered.map_or(false, |i| i >= count) {
+                        obj.hovered = None;
+                    }
+                    if obj.pressed.map_or(false, |i| i >= count) {
+                        obj.pressed = None;
+                    }
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("title_bar", title_bar_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.title_bar();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::TitleBarState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    buttons: Vec::new(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        TitleBarWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            hovered: None,
+            pressed: None,
+            last_press: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::TitleBarState> for TitleBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::TitleBarState {
+        state::TitleBarState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            buttons: self
+                .data
+                .buttons
+                .iter()
+                .enumerate()
+                .map(|(i, &button)| state::WindowButtonState {
+                    button,
+                    rect: self.button_rect(i),
+                    hovered: self.hovered == Some(i),
+                    pressed: self.pressed == Some(i),
+                })
+                .collect(),
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct TitleBarWidget {
+        widget::MAX,
+
+        <TitleBarEvent> EventQueue,
+        <TitleBar> State,
+        <state::TitleBarState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            hovered: Option<usize>,
+            pressed: Option<usize>,
+            /// When the caption was last pressed, for detecting double-clicks.
+            last_press: Option<Instant>,
+        },
+    }
+}
+
+impl<U, G> TitleBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Performs the action of a button (whether or not it's shown), as if it were pressed.
+    pub fn trigger(&mut self, button: WindowButton, aux: &mut U) {
+        let (action, event) = match button {
+            WindowButton::Minimize => (Some(base::WindowAction::Minimize), TitleBarEvent::Minimize),
+            WindowButton::Maximize if self.data.maximized => {
+                self.data.maximized = false;
+                (Some(base::WindowAction::Restore), TitleBarEvent::Restore)
+            }
+            WindowButton::Maximize => {
+                self.data.maximized = true;
+                (Some(base::WindowAction::Maximize), TitleBarEvent::Maximize)
+            }
+            WindowButton::Close if self.data.intercept_close => (None, TitleBarEvent::Close),
+            WindowButton::Close => (Some(base::WindowAction::Close), TitleBarEvent::Close),
+        };
+
+        if let Some(action) = action {
+            aux.request_window_action(action);
+        }
+        self.event_queue.emit_owned(event);
+    }
+
+    /// Returns the rectangle of the button at `index`; the buttons are placed at the end of the title bar.
+    pub fn button_rect(&self, index: usize) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let width = self.data.button_width;
+        let offset = (self.data.buttons.len() - index) as f32 * width;
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.max_x() - offset, rect.min_y()),
+            Size::new(width, rect.size.height).cast_unit(),
+        )
+    }
+
+    /// Returns the index of the button at a given point, if any.
+    pub fn button_at(&self, point: AbsolutePoint) -> Option<usize> {
+        if self.data.disabled {
+            return None;
+        }
+        (0..self.data.buttons.len()).find(|&i| self.button_rect(i).contains(point))
+    }
+}
+
+impl<U, G> Widget for TitleBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        // the buttons are excluded from the caption, so that pressing them doesn't move the window
+        aux.declare_window_region(base::WindowRegion::Caption, self.abs_rect());
+        for i in 0..self.data.buttons.len() {
+            aux.declare_window_region(base::WindowRegion::Client, self.button_rect(i));
+        }
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
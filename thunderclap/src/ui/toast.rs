@@ -0,0 +1,396 @@
+//! Non-blocking toast notifications.
+
+use {
+    crate::{
+        a11y,
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui::{self, Corner},
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, DisplayText, GraphicsDisplay, Rect},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Events emitted by a toast manager.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastEvent {
+    /// Emitted when a toast starts showing, with the ID returned by `ToastHandle::show`.
+    #[event_key(show)]
+    Show(u64),
+    /// Emitted when a toast has been clicked by the user.
+    #[event_key(click)]
+    Click(u64),
+    /// Emitted when a toast has fully disappeared.
+    #[event_key(dismiss)]
+    Dismiss(u64),
+}
+
+/// A single toast notification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub text: DisplayText,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub background: Color,
+    pub contrast: draw::ThemeContrast,
+    /// How long the toast is shown for before being dismissed automatically.
+    pub timeout: Duration,
+    /// How the text is announced to screen readers when the toast is shown, if at all.
+    /// Only plain (unshaped) text can be announced.
+    pub announce: Option<a11y::Politeness>,
+}
+
+/// Shared queue of toasts to be shown by a toast manager.
+///
+/// Cloning the handle yields another handle to the same toast manager, so it can be
+/// passed to wherever notifications may originate from.
+#[derive(Debug, Clone, Default)]
+pub struct ToastHandle {
+    queue: Rc<RefCell<Vec<(u64, ToastContent)>>>,
+    next_id: Rc<Cell<u64>>,
+}
+
+#[derive(Debug, Clone)]
+enum ToastContent {
+    Text(DisplayText),
+    Toast(Toast),
+}
+
+impl ToastHandle {
+    /// Queues a toast with the given text, using the toast manager's `template` for everything else.
+    /// Returns an ID identifying the toast in `ToastEvent`s.
+    pub fn show_text(&self, text: impl Into<DisplayText>) -> u64 {
+        self.push(ToastContent::Text(text.into()))
+    }
+
+    /// Queues a fully specified toast.
+    /// Returns an ID identifying the toast in `ToastEvent`s.
+    pub fn show(&self, toast: Toast) -> u64 {
+        self.push(ToastContent::Toast(toast))
+    }
+
+    fn push(&self, content: ToastContent) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.queue.borrow_mut().push((id, content));
+        id
+    }
+
+    fn take(&self) -> Vec<(u64, ToastContent)> {
+        std::mem::take(&mut *self.queue.borrow_mut())
+    }
+}
+
+struct ActiveToast {
+    id: u64,
+    toast: Toast,
+    shown: Instant,
+    dismissed: Option<Instant>,
+    rect: AbsoluteRect,
+}
+
+/// Generates an unbound terminal which dismisses toasts when clicked.
+pub fn toast_manager_handler<U, G>(
+) -> vg::UnboundQueueHandler<ToastManagerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ToastManagerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let hit = obj.toast_at(event.get().0);
+            if let (Some(id), Some(_)) = (hit, event.with(|_| hit.is_some())) {
+                obj.event_queue.emit_owned(ToastEvent::Click(id));
+                obj.dismiss(id);
+            }
+        }
+    }
+}
+
+/// Overlay which shows toasts stacked in a corner, sliding them in and dismissing them after their timeout.
+///
+/// The toast manager should cover the entire window and be drawn above everything else,
+/// hence `app::App` owns one. Toasts are shown through a `ToastHandle` (see `ToastManagerWidget::handle`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToastManager {
+    pub corner: Corner,
+    /// Distance between the toasts and the edges of the window.
+    pub margin: f32,
+    /// Distance between stacked toasts.
+    pub spacing: f32,
+    /// How long toasts take to slide in and out.
+    pub slide_duration: Duration,
+    /// Toast used for everything but the text when using `ToastHandle::show_text`.
+    pub template: Toast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ToastManager
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ToastManagerWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for ToastManager
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        ToastManager {
+            corner: Corner::BottomRight,
+            margin: 15.0,
+            spacing: 10.0,
+            slide_duration: Duration::from_millis(200),
+            template: Toast {
+                text: "".to_string().into(),
+                typeface: data.typography.button.clone(),
+                color: data.scheme.over_control_outset,
+                background: data.scheme.control_outset,
+                contrast: data.contrast,
+                timeout: Duration::from_secs(4),
+                announce: Some(a11y::Politeness::Polite),
+            },
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> ToastManagerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ToastManagerWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("toasts", toast_manager_handler::<U, G>().bind(u_aux.window_queue()));
+
+        ToastManagerWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            toast_painter: theme.toast(),
+            handle: Default::default(),
+            toasts: Vec::new(),
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for ToastManagerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ToastManagerWidget {
+        widget::MAX,
+
+        <ToastEvent> EventQueue,
+        <ToastManager> State,
+
+        {
+            toast_painter: Box<dyn draw::Painter<state::ToastState>>,
+            handle: ToastHandle,
+            toasts: Vec<ActiveToast>,
+        },
+    }
+}
+
+impl<U, G> ToastManagerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns a handle through which toasts can be shown.
+    #[inline]
+    pub fn handle(&self) -> ToastHandle {
+        self.handle.clone()
+    }
+
+    /// Starts dismissing the toast with a given ID early.
+    pub fn dismiss(&mut self, id: u64) {
+        if let Some(toast) = self.toasts.iter_mut().find(|toast| toast.id == id) {
+            toast.dismissed.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Starts dismissing all the toasts.
+    pub fn dismiss_all(&mut self) {
+        for toast in &mut self.toasts {
+            toast.dismissed.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Returns the ID of the toast at a given point, if any.
+    pub fn toast_at(&self, point: AbsolutePoint) -> Option<u64> {
+        self.toasts
+            .iter()
+            .find(|toast| toast.dismissed.is_none() && toast.rect.contains(point))
+            .map(|toast| toast.id)
+    }
+
+    /// Returns how far the toast has slid into view, from `0.0` to `1.0`.
+    fn slide_progress(&self, toast: &ActiveToast, now: Instant) -> f32 {
+        let slide = self.data.slide_duration.as_secs_f32().max(std::f32::EPSILON);
+        let progress = match toast.dismissed {
+            Some(dismissed) => 1.0 - (now - dismissed).as_secs_f32() / slide,
+            None => (now - toast.shown).as_secs_f32() / slide,
+        };
+        let progress = progress.max(0.0).min(1.0);
+        // Ease out (cubic)
+        1.0 - (1.0 - progress).powi(3)
+    }
+
+    fn toast_state(
+        &self,
+        toast: &ActiveToast,
+        rect: AbsoluteRect,
+        opacity: f32,
+    ) -> state::ToastState {
+        state::ToastState { rect, data: toast.toast.clone(), opacity }
+    }
+}
+
+impl<U, G> Widget for ToastManagerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        let now = Instant::now();
+
+        for (id, content) in self.handle.take() {
+            let toast = match content {
+                ToastContent::Text(text) => Toast { text, ..self.data.template.clone() },
+                ToastContent::Toast(toast) => toast,
+            };
+            if let (Some(politeness), DisplayText::Simple(text)) = (toast.announce, &toast.text) {
+                aux.announce(a11y::Announcement { message: text.clone(), politeness });
+            }
+            self.toasts.push(ActiveToast {
+                id,
+                toast,
+                shown: now,
+                dismissed: None,
+                rect: Default::default(),
+            });
+            self.event_queue.emit_owned(ToastEvent::Show(id));
+        }
+
+        for toast in &mut self.toasts {
+            if toast.dismissed.is_none() && now - toast.shown >= toast.toast.timeout {
+                toast.dismissed = Some(toast.shown + toast.toast.timeout);
+            }
+        }
+
+        let slide_duration = self.data.slide_duration;
+        let event_queue = &mut self.event_queue;
+        self.toasts.retain(|toast| match toast.dismissed {
+            Some(dismissed) if now - dismissed >= slide_duration => {
+                event_queue.emit_owned(ToastEvent::Dismiss(toast.id));
+                false
+            }
+            _ => true,
+        });
+
+        // Lay out the toasts; the newest toast is closest to the corner.
+        let bounds = self.abs_rect();
+        let corner = self.data.corner;
+        let mut advance = self.data.margin;
+        for i in (0..self.toasts.len()).rev() {
+            let size = self.toast_painter.size_hint(self.toast_state(
+                &self.toasts[i],
+                Default::default(),
+                1.0,
+            ));
+            let progress = self.slide_progress(&self.toasts[i], now);
+
+            // Slide in from beyond the horizontal window edge.
+            let hidden = (1.0 - progress) * (size.width + self.data.margin);
+            let x = if corner.is_left() {
+                bounds.min_x() + self.data.margin - hidden
+            } else {
+                bounds.max_x() - self.data.margin - size.width + hidden
+            };
+            let y = if corner.is_top() {
+                bounds.min_y() + advance
+            } else {
+                bounds.max_y() - advance - size.height
+            };
+
+            self.toasts[i].rect = AbsoluteRect::new(AbsolutePoint::new(x, y), size.cast_unit());
+            advance += size.height + self.data.spacing;
+        }
+
+        // toasts slide and time out on their own, without input
+        if !self.toasts.is_empty() {
+            self.command_group.repaint();
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let now = Instant::now();
+        let states: Vec<_> = self
+            .toasts
+            .iter()
+            .map(|toast| self.toast_state(toast, toast.rect, self.slide_progress(toast, now)))
+            .collect();
+        let painter = &mut self.toast_painter;
+        self.command_group.push_with(
+            display,
+            || states.into_iter().flat_map(|state| painter.draw(state)).collect(),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
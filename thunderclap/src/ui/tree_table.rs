@@ -0,0 +1,1498 @@
+//! Table whose rows form an expandable tree, with data columns alongside.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        history, i18n, ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, Vector},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{fs, io, path::Path},
+};
+
+/// Events emitted by a tree table. Nodes are identified by their path; the index of each ancestor among its
+/// siblings, from the roots down, followed by the index of the node.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum TreeTableEvent {
+    /// Emitted when a node has been expanded by the user.
+    #[event_key(expand)]
+    Expand(Vec<usize>),
+    /// Emitted when a node has been collapsed by the user.
+    #[event_key(collapse)]
+    Collapse(Vec<usize>),
+    /// Emitted when the selected node has been changed by the user.
+    #[event_key(select)]
+    Select(Vec<usize>),
+    /// Emitted when a cell of a `CellRenderer::Check` column has been toggled by the user, with the path of the node,
+    /// the index of the column and whether the cell is now checked.
+    #[event_key(check)]
+    Check(Vec<usize>, usize, bool),
+    /// Emitted when rows have been copied by the user (e.g. with Ctrl+C), with the TSV to place on the clipboard.
+    #[event_key(copy)]
+    Copy(String),
+    /// Emitted when the filter of a column has been changed by the user, with the index of the column and the filter.
+    #[event_key(filter)]
+    Filter(usize, String),
+}
+
+/// How the filter of a column (see `TreeTable::filters`) matches its cells. An empty filter matches every cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    /// Cells containing the filter, ignoring case, as they're shown.
+    Contains,
+    /// Cells equal to the filter, ignoring case, as they're shown.
+    Equals,
+    /// Numeric cells within a range; `a..b` (inclusive), `a..`, `..b`, `>a`, `>=a`, `<b`, `<=b`, or a single number.
+    /// Cells which aren't numbers never match, nor does a filter which isn't a range.
+    Range,
+}
+
+impl Default for FilterMode {
+    fn default() -> Self {
+        FilterMode::Contains
+    }
+}
+
+impl FilterMode {
+    /// Returns `true` if the cell matches `filter`, with `text` being the text of the cell and `shown` the text as
+    /// formatted by the renderer of its column.
+    pub fn matches(self, filter: &str, text: &str, shown: &str) -> bool {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            return true;
+        }
+
+        match self {
+            FilterMode::Contains => shown.to_lowercase().contains(&filter.to_lowercase()),
+            FilterMode::Equals => shown.trim().to_lowercase() == filter.to_lowercase(),
+            FilterMode::Range => {
+                let value = match text.trim().parse::<f64>() {
+                    Ok(value) => value,
+                    Err(_) => return false,
+                };
+                match parse_range(filter) {
+                    Some((min, max)) => {
+                        min.map_or(true, |min| min.contains(value, true))
+                            && max.map_or(true, |max| max.contains(value, false))
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+/// Bound of a numeric range filter, and whether the bound itself is within the range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FilterBound(f64, bool);
+
+impl FilterBound {
+    /// Returns `true` if `value` is on the inner side of the bound; above it if `lower`, below it otherwise.
+    fn contains(self, value: f64, lower: bool) -> bool {
+        let FilterBound(bound, inclusive) = self;
+        (inclusive && value == bound) || if lower { value > bound } else { value < bound }
+    }
+}
+
+/// Parses a numeric range filter (see `FilterMode::Range`) into its lower and upper bounds.
+fn parse_range(filter: &str) -> Option<(Option<FilterBound>, Option<FilterBound>)> {
+    let number = |text: &str| text.trim().parse::<f64>().ok();
+    let bound = |text: &str, inclusive| number(text).map(|value| FilterBound(value, inclusive));
+
+    if let Some(index) = filter.find("..") {
+        let (min, max) = (filter[..index].trim(), filter[index + 2..].trim());
+        let min = if min.is_empty() { None } else { Some(bound(min, true)?) };
+        let max = if max.is_empty() { None } else { Some(bound(max, true)?) };
+        Some((min, max))
+    } else if filter.starts_with(">=") {
+        Some((Some(bound(&filter[2..], true)?), None))
+    } else if filter.starts_with("<=") {
+        Some((None, Some(bound(&filter[2..], true)?)))
+    } else if filter.starts_with('>') {
+        Some((Some(bound(&filter[1..], false)?), None))
+    } else if filter.starts_with('<') {
+        Some((None, Some(bound(&filter[1..], false)?)))
+    } else {
+        let value = bound(filter, true)?;
+        Some((Some(value), Some(value)))
+    }
+}
+
+/// Plain text format which rows of a table are exported to, with a header line of the column titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableExportFormat {
+    /// Tab-separated values, as pasted into spreadsheets; tabs and line breaks within cells are replaced by spaces.
+    Tsv,
+    /// Comma-separated values, quoting cells as per RFC 4180.
+    Csv,
+}
+
+impl TableExportFormat {
+    fn escape(self, cell: &str) -> String {
+        match self {
+            TableExportFormat::Tsv => {
+                cell.replace(|c: char| c == '\t' || c == '\n' || c == '\r', " ")
+            }
+            TableExportFormat::Csv
+                if cell.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') =>
+            {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            }
+            TableExportFormat::Csv => cell.to_string(),
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            TableExportFormat::Tsv => "\t",
+            TableExportFormat::Csv => ",",
+        }
+    }
+
+    fn line_break(self) -> &'static str {
+        match self {
+            TableExportFormat::Tsv => "\n",
+            TableExportFormat::Csv => "\r\n",
+        }
+    }
+}
+
+/// Which rows of a table are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableExportScope {
+    /// The selected row, if any.
+    Selection,
+    /// The rows shown; the roots and the descendants of expanded nodes.
+    Shown,
+    /// Every node, including the descendants of collapsed nodes.
+    All,
+}
+
+/// Format of the cells of a `CellRenderer::DateTime` column, in the locale of the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateTimeFormat {
+    /// Numeric date (e.g. `01/31/2024`).
+    Date,
+    /// Date with the name of the month (e.g. `January 31, 2024`).
+    LongDate,
+    /// Time of day (e.g. `1:05 PM`).
+    Time,
+    /// Numeric date followed by the time of day.
+    DateTime,
+}
+
+/// How the cells of a column are shown.
+///
+/// Cells hold text, which renderers other than `Text` parse; cells which can't be parsed are shown as text, as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellRenderer {
+    Text,
+    /// Number with a fixed number of decimal places, with groups of thousands separated if `grouping`.
+    Number {
+        precision: usize,
+        grouping: bool,
+    },
+    /// Date and/or time of a Unix timestamp in seconds, in UTC.
+    DateTime(DateTimeFormat),
+    /// Progress bar filled by a fraction from `0` to `1`, labeled with the percentage.
+    Progress,
+    /// Icon followed by text; the first word of the cell is the icon (short text such as a symbol, as with
+    /// `FabAction::icon`), drawn in a slot of fixed width so that the text of the column lines up.
+    IconText,
+    /// Check box, checked by `true` or `1`. Clicking the cell toggles it (see `TreeTableEvent::Check`).
+    Check,
+}
+
+impl Default for CellRenderer {
+    fn default() -> Self {
+        CellRenderer::Text
+    }
+}
+
+impl CellRenderer {
+    /// Formats the text of a cell for display, in `locale`.
+    pub fn render(&self, text: &str, locale: &i18n::Locale) -> state::TableCellState {
+        let number = || text.trim().parse::<f64>().ok().filter(|value| value.is_finite());
+        let rendered = match *self {
+            CellRenderer::Text => None,
+            CellRenderer::Number { precision, grouping } => number().map(|value| {
+                state::TableCellState::Text(if grouping {
+                    locale.format_decimal(value, precision)
+                } else {
+                    let text = format!("{:.*}", precision, value)
+                        .replace('.', &locale.decimal_separator.to_string());
+                    locale.localize_digits(&text)
+                })
+            }),
+            CellRenderer::DateTime(format) => number().map(|timestamp| {
+                let seconds = timestamp.floor() as i64;
+                let date = ui::Date::from_days(seconds.div_euclid(86400));
+                let time = seconds.rem_euclid(86400) as u32;
+                let (hour, minute) = (time / 3600, time / 60 % 60);
+                state::TableCellState::Text(match format {
+                    DateTimeFormat::Date => locale.format_date(date),
+                    DateTimeFormat::LongDate => locale.format_long_date(date),
+                    DateTimeFormat::Time => locale.format_time(hour, minute, None),
+                    DateTimeFormat::DateTime => format!(
+                        "{} {}",
+                        locale.format_date(date),
+                        locale.format_time(hour, minute, None)
+                    ),
+                })
+            }),
+            CellRenderer::Progress => number().map(|fraction| {
+                let fraction = fraction.max(0.0).min(1.0);
+                state::TableCellState::Progress(
+                    fraction as f32,
+                    format!("{}%", locale.format_integer((fraction * 100.0).round() as i64)),
+                )
+            }),
+            CellRenderer::IconText => {
+                let mut parts = text.trim_start().splitn(2, char::is_whitespace);
+                let icon = parts.next().unwrap_or_default();
+                let rest = parts.next().unwrap_or_default().trim_start();
+                Some(state::TableCellState::IconText(icon.to_string(), rest.to_string()))
+            }
+            CellRenderer::Check => parse_check(text).map(state::TableCellState::Check),
+        };
+        rendered.unwrap_or_else(|| state::TableCellState::Text(text.to_string()))
+    }
+
+    /// Formats the text of a cell as plain text, in `locale`, as it's shown (e.g. for copying).
+    /// Icons are left out, and check boxes are written as `true` or `false`.
+    pub fn format(&self, text: &str, locale: &i18n::Locale) -> String {
+        match self.render(text, locale) {
+            state::TableCellState::Text(text)
+            | state::TableCellState::Progress(_, text)
+            | state::TableCellState::IconText(_, text) => text,
+            state::TableCellState::Check(checked) => checked.to_string(),
+        }
+    }
+}
+
+/// Parses the text of a `CellRenderer::Check` cell.
+fn parse_check(text: &str) -> Option<bool> {
+    match text.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Summary of the cells of a column across the descendants of a node, shown in the empty cells of nodes with
+/// children (e.g. the total of a group of rows).
+///
+/// Only the leaves among the descendants are summarized, so that nested groups aren't counted twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aggregate {
+    Sum,
+    Average,
+    Min,
+    Max,
+    /// Number of cells which aren't empty.
+    Count,
+}
+
+impl Aggregate {
+    /// Summarizes `cells`; cells which aren't numbers are ignored, other than when counting.
+    /// Returns `None` if there's nothing to summarize.
+    pub fn compute<'a>(self, cells: impl IntoIterator<Item = &'a str>) -> Option<f64> {
+        let mut count = 0;
+        let values: Vec<f64> = cells
+            .into_iter()
+            .map(str::trim)
+            .filter(|cell| !cell.is_empty())
+            .inspect(|_| count += 1)
+            .filter_map(|cell| cell.parse::<f64>().ok().filter(|value| value.is_finite()))
+            .collect();
+
+        match self {
+            Aggregate::Count => Some(count as f64),
+            _ if values.is_empty() => None,
+            Aggregate::Sum => Some(values.iter().sum()),
+            Aggregate::Average => Some(values.iter().sum::<f64>() / values.len() as f64),
+            Aggregate::Min => values.iter().copied().fold(None, |min: Option<f64>, value| {
+                Some(min.map_or(value, |min| min.min(value)))
+            }),
+            Aggregate::Max => values.iter().copied().fold(None, |max: Option<f64>, value| {
+                Some(max.map_or(value, |max| max.max(value)))
+            }),
+        }
+    }
+}
+
+/// Column of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    pub title: String,
+    /// Width of the column. The last column also takes any width left over.
+    pub width: f32,
+    /// Alignment of the cells (e.g. `TextAlign::Right` for figures).
+    pub align: ui::TextAlign,
+    /// How the cells are shown.
+    pub renderer: CellRenderer,
+    /// How the filter of the column matches the cells.
+    pub filter_mode: FilterMode,
+    /// Summary shown in the empty cells of nodes with children, if any.
+    pub aggregate: Option<Aggregate>,
+}
+
+impl TableColumn {
+    /// Creates a left-aligned column of text.
+    pub fn new(title: impl Into<String>, width: f32) -> Self {
+        TableColumn {
+            title: title.into(),
+            width,
+            align: ui::TextAlign::Left,
+            renderer: CellRenderer::Text,
+            filter_mode: FilterMode::Contains,
+            aggregate: None,
+        }
+    }
+
+    /// Returns the column with the empty cells of nodes with children showing `aggregate`.
+    pub fn with_aggregate(self, aggregate: Aggregate) -> Self {
+        TableColumn { aggregate: Some(aggregate), ..self }
+    }
+
+    /// Returns the column with its cells matched by `filter_mode`.
+    pub fn with_filter_mode(self, filter_mode: FilterMode) -> Self {
+        TableColumn { filter_mode, ..self }
+    }
+
+    /// Returns the column with its cells shown by `renderer`; numbers, dates and progress are aligned to the right.
+    pub fn with_renderer(self, renderer: CellRenderer) -> Self {
+        let align = match renderer {
+            CellRenderer::Number { .. } | CellRenderer::DateTime(_) | CellRenderer::Progress => {
+                ui::TextAlign::Right
+            }
+            _ => self.align,
+        };
+        TableColumn { renderer, align, ..self }
+    }
+}
+
+/// Node of a tree table, with a cell for each column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeTableNode {
+    /// Text of each cell, in column order; the first cell is shown in the tree.
+    pub cells: Vec<String>,
+    pub children: Vec<TreeTableNode>,
+    /// Whether the children are shown.
+    pub expanded: bool,
+    /// Number of columns spanned by each cell, in column order; cells without a span take one column. Cells covered
+    /// by a preceding cell aren't shown (e.g. a group heading spanning every column).
+    pub spans: Vec<usize>,
+}
+
+impl TreeTableNode {
+    /// Creates a collapsed node without children.
+    pub fn new(cells: Vec<String>) -> Self {
+        TreeTableNode { cells, children: Vec::new(), expanded: false, spans: Vec::new() }
+    }
+
+    /// Returns the node with the given children.
+    pub fn with_children(self, children: Vec<TreeTableNode>) -> Self {
+        TreeTableNode { children, ..self }
+    }
+
+    /// Returns the node with its cells spanning the given numbers of columns (see `spans`).
+    pub fn with_spans(self, spans: Vec<usize>) -> Self {
+        TreeTableNode { spans, ..self }
+    }
+
+    /// Returns the number of columns spanned by each of `columns` cells, clamped to the last column; cells covered
+    /// by a preceding cell span none.
+    pub fn column_spans(&self, columns: usize) -> Vec<usize> {
+        let mut spans = vec![0; columns];
+        let mut i = 0;
+        while i < columns {
+            let span = self.spans.get(i).copied().unwrap_or(1).max(1).min(columns - i);
+            spans[i] = span;
+            i += span;
+        }
+        spans
+    }
+
+    /// Appends the descendants of the node which have no children.
+    fn leaves<'a>(&'a self, leaves: &mut Vec<&'a TreeTableNode>) {
+        for child in &self.children {
+            if child.children.is_empty() {
+                leaves.push(child);
+            } else {
+                child.leaves(leaves);
+            }
+        }
+    }
+}
+
+/// Change to the nodes of a tree table, pushed through an `UpdateFeed` (see `TreeTableWidget::set_feed`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeTableUpdate {
+    /// Changes the text of the cell at a column of the node at a path.
+    SetCell(Vec<usize>, usize, String),
+    /// Inserts a node at a path, before the sibling which was there.
+    Insert(Vec<usize>, TreeTableNode),
+    /// Removes the node at a path, along with its descendants.
+    Remove(Vec<usize>),
+}
+
+/// Node of a tree table as listed in its rows (see `TreeTable::rows`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeTableRow {
+    pub path: Vec<usize>,
+    /// Number of ancestors of the node.
+    pub depth: usize,
+    /// Whether the node is expanded, or `None` if it has no children.
+    pub expanded: Option<bool>,
+}
+
+/// Generates an unbound terminal which handles expanding, selecting and scrolling in a tree table.
+pub fn tree_table_handler<U, G>(
+) -> vg::UnboundQueueHandler<TreeTableWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        TreeTableWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                if !obj.interaction.contains(state::InteractionState::FOCUSED) {
+                    obj.interaction.insert(state::InteractionState::FOCUSED);
+                    obj.command_group.repaint();
+                }
+
+                let filter = obj.filter_at(*pos);
+                if filter != obj.filter_focus {
+                    obj.filter_focus = filter;
+                    obj.command_group.repaint();
+                }
+
+                if let Some(row) = obj.row_at(*pos) {
+                    let path = obj.rows[row].path.clone();
+                    if obj.rows[row].expanded.is_some() && obj.toggle_rect(row).contains(*pos) {
+                        obj.toggle(&path);
+                    } else {
+                        obj.select(&path);
+                        if let Some(column) = obj.cell_at(&path, *pos) {
+                            if obj.data.columns[column].renderer == CellRenderer::Check {
+                                obj.toggle_check(&path, column);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.row_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, modifiers)) = event.with(|(pos, _, _)| bounds.contains(*pos)) {
+                // shift scrolls vertical wheels horizontally
+                let delta = if modifiers.shift && delta.x == 0.0 {
+                    Vector::new(delta.y, 0.0)
+                } else {
+                    *delta
+                };
+                let scroll = obj.scroll - delta.y;
+                obj.scroll_to(scroll);
+                let scroll_x = obj.scroll_x - delta.x;
+                obj.scroll_horizontally_to(scroll_x);
+            }
+        }
+
+        key_press => {
+            let filtering = obj.filter_focus.is_some();
+            if let Some((key, _)) = event.with(|_| {
+                filtering && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                let column = obj.filter_focus.unwrap_or_default();
+                let mut filter = obj.data.filters.get(column).cloned().unwrap_or_default();
+                match key {
+                    base::KeyInput::Back => {
+                        filter.pop();
+                        obj.set_filter(column, filter);
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                        obj.filter_focus = None;
+                    }
+                    base::KeyInput::Tab => {
+                        let next = (column + 1) % obj.data.columns.len().max(1);
+                        obj.filter_focus = Some(next);
+                        obj.scroll_column_into_view(next);
+                    }
+                    _ => {}
+                }
+                obj.command_group.repaint();
+            } else if let Some((key, modifiers)) =
+                event.with(|_| obj.interaction.contains(state::InteractionState::FOCUSED))
+            {
+                let selected = obj.selected_row();
+                let len = obj.rows.len();
+                match key {
+                    base::KeyInput::Up if len > 0 => {
+                        let row = selected.map_or(len - 1, |row| row.saturating_sub(1));
+                        let path = obj.rows[row].path.clone();
+                        obj.select(&path);
+                    }
+                    base::KeyInput::Down if len > 0 => {
+                        let row = selected.map_or(0, |row| (row + 1).min(len - 1));
+                        let path = obj.rows[row].path.clone();
+                        obj.select(&path);
+                    }
+                    base::KeyInput::Left => {
+                        if let Some(row) = selected {
+                            let path = obj.rows[row].path.clone();
+                            if obj.rows[row].expanded == Some(true) {
+                                obj.toggle(&path);
+                            } else if path.len() > 1 {
+                                // moves up to the parent
+                                obj.select(&path[..path.len() - 1]);
+                            }
+                        }
+                    }
+                    base::KeyInput::Right => {
+                        if let Some(row) = selected {
+                            if obj.rows[row].expanded == Some(false) {
+                                let path = obj.rows[row].path.clone();
+                                obj.toggle(&path);
+                            }
+                        }
+                    }
+                    base::KeyInput::Copy => obj.copy_selection(),
+                    base::KeyInput::C if modifiers.ctrl || modifiers.logo => obj.copy_selection(),
+                    _ => {}
+                }
+            }
+        }
+
+        text_input => {
+            if let Some(&c) = event.with(|c| !c.is_control() && obj.filter_focus.is_some()) {
+                if let Some(column) = obj.filter_focus {
+                    let mut filter = obj.data.filters.get(column).cloned().unwrap_or_default();
+                    filter.push(c);
+                    obj.set_filter(column, filter);
+                    obj.command_group.repaint();
+                }
+            }
+        }
+
+        escape => {
+            // escape clears the filter being typed into, then stops typing into it
+            if let Some(column) = obj.filter_focus.filter(|_| {
+                obj.interaction.contains(state::InteractionState::FOCUSED)
+                    && event.with(|stage| *stage == base::EscapeStage::Cancel).is_some()
+            }) {
+                if obj.data.filters.get(column).map_or(false, |filter| !filter.is_empty()) {
+                    obj.set_filter(column, "");
+                } else {
+                    obj.filter_focus = None;
+                }
+                obj.command_group.repaint();
+            }
+        }
+
+        clear_focus => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) {
+                obj.interaction.remove(
+                    state::InteractionState::FOCUSED | state::InteractionState::FOCUS_VISIBLE,
+                );
+                obj.filter_focus = None;
+                obj.command_group.repaint();
+            }
+        }
+
+        deactivate => {
+            obj.interaction.insert(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+
+        activate => {
+            obj.interaction.remove(state::InteractionState::INACTIVE);
+            obj.command_group.repaint();
+        }
+    }
+}
+
+/// Table whose first column is an expandable tree, with additional data columns alongside (e.g. a profiler's call
+/// tree with timings, or a scene hierarchy with per-node metrics).
+///
+/// Nodes are expanded and collapsed by clicking their chevron, or with the left and right arrow keys; a node is
+/// selected by clicking it, or with the up and down arrow keys. A header shows the title of each column.
+/// Only the rows in view are laid out and drawn, and the rows are scrolled with the mouse wheel.
+///
+/// Pressing Ctrl+C copies the selected row (or all the rows shown, if none is selected) as TSV, through
+/// `TreeTableEvent::Copy`. The rows can also be exported to CSV (see `TreeTable::export_csv`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeTable {
+    pub columns: Vec<TableColumn>,
+    pub roots: Vec<TreeTableNode>,
+    /// Path of the selected node.
+    pub selected: Option<Vec<usize>>,
+    /// Height of each row, and of the header.
+    pub row_height: f32,
+    /// Horizontal space added for each level of depth.
+    pub indent: f32,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    pub header_background: Color,
+    /// Color of the selected row.
+    pub highlight: Color,
+    /// Color of the text of the selected row.
+    pub over_highlight: Color,
+    /// Color of the selected row while the window is inactive.
+    pub inactive_highlight: Color,
+    /// Color of the text of the selected row while the window is inactive.
+    pub over_inactive_highlight: Color,
+    /// Color of the lines between columns.
+    pub border: Color,
+    /// Color of the outline of the filter being typed into.
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Locale which numbers and dates are formatted in (see `CellRenderer`).
+    pub locale: i18n::Locale,
+    /// Whether a row of filters is shown beneath the header, where typing into the cell of a column filters it.
+    pub filter_row: bool,
+    /// Filter of each column, in column order, matched by the `filter_mode` of the column. Columns without a filter
+    /// (or with an empty one) match every cell.
+    pub filters: Vec<String>,
+    /// Number of leading columns which stay in place while the rest of the columns are scrolled horizontally.
+    pub frozen_columns: usize,
+    /// Number of leading rows which stay in place beneath the header while the rest of the rows are scrolled.
+    pub frozen_rows: usize,
+}
+
+impl TreeTable {
+    /// Returns the node at `path`, if any.
+    pub fn node(&self, path: &[usize]) -> Option<&TreeTableNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter().try_fold(self.roots.get(*first)?, |node, &i| node.children.get(i))
+    }
+
+    /// Returns the node at `path` mutably, if any.
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut TreeTableNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter().try_fold(self.roots.get_mut(*first)?, |node, &i| node.children.get_mut(i))
+    }
+
+    /// Applies a change to the nodes. Changes to nodes which don't exist are ignored.
+    ///
+    /// Removing the selected node (or an ancestor of it) clears the selection; other selected paths are left as
+    /// they are.
+    pub fn apply(&mut self, update: TreeTableUpdate) {
+        match update {
+            TreeTableUpdate::SetCell(path, column, text) => {
+                if let Some(node) = self.node_mut(&path) {
+                    if node.cells.len() <= column {
+                        node.cells.resize(column + 1, String::new());
+                    }
+                    node.cells[column] = text;
+                }
+            }
+            TreeTableUpdate::Insert(path, node) => {
+                if let Some((&index, parent)) = path.split_last() {
+                    if let Some(siblings) = self.siblings_mut(parent) {
+                        let index = index.min(siblings.len());
+                        siblings.insert(index, node);
+                    }
+                }
+            }
+            TreeTableUpdate::Remove(path) => {
+                if let Some((&index, parent)) = path.split_last() {
+                    if let Some(siblings) = self.siblings_mut(parent) {
+                        if index < siblings.len() {
+                            siblings.remove(index);
+                        }
+                    }
+                }
+                if self.selected.as_ref().map_or(false, |selected| selected.starts_with(&path)) {
+                    self.selected = None;
+                }
+            }
+        }
+    }
+
+    /// Returns the children of the node at `parent`, or the roots if `parent` is empty.
+    fn siblings_mut(&mut self, parent: &[usize]) -> Option<&mut Vec<TreeTableNode>> {
+        if parent.is_empty() {
+            Some(&mut self.roots)
+        } else {
+            self.node_mut(parent).map(|node| &mut node.children)
+        }
+    }
+
+    /// Returns the nodes shown as rows, from top to bottom; the roots and the descendants of expanded nodes.
+    ///
+    /// While filtering, only the nodes which match the filters are shown, along with their ancestors; ancestors of
+    /// matching nodes are shown expanded.
+    pub fn rows(&self) -> Vec<TreeTableRow> {
+        fn walk(
+            table: &TreeTable,
+            nodes: &[TreeTableNode],
+            path: &mut Vec<usize>,
+            rows: &mut Vec<TreeTableRow>,
+        ) {
+            let filtering = table.is_filtering();
+            for (i, node) in nodes.iter().enumerate() {
+                if filtering && !table.shows(node) {
+                    continue;
+                }
+                let expanded = node.expanded
+                    || (filtering && node.children.iter().any(|child| table.shows(child)));
+                path.push(i);
+                rows.push(TreeTableRow {
+                    path: path.clone(),
+                    depth: path.len() - 1,
+                    expanded: if node.children.is_empty() { None } else { Some(expanded) },
+                });
+                if expanded {
+                    walk(table, &node.children, path, rows);
+                }
+                path.pop();
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(self, &self.roots, &mut Vec::new(), &mut rows);
+        rows
+    }
+
+    /// Returns `true` if any column has a non-empty filter.
+    pub fn is_filtering(&self) -> bool {
+        self.filters.iter().take(self.columns.len()).any(|filter| !filter.trim().is_empty())
+    }
+
+    /// Returns `true` if every cell of `node` matches the filter of its column.
+    pub fn matches(&self, node: &TreeTableNode) -> bool {
+        self.columns.iter().zip(&self.filters).enumerate().all(|(i, (column, filter))| {
+            let text = self.cell_text(node, i);
+            column.filter_mode.matches(filter, &text, &column.renderer.format(&text, &self.locale))
+        })
+    }
+
+    /// Returns the text of the cell of `node` at `column`, or the aggregate of the column (see `Aggregate`) if the
+    /// cell of a node with children is empty.
+    pub fn cell_text(&self, node: &TreeTableNode, column: usize) -> String {
+        let text = node.cells.get(column).map(String::as_str).unwrap_or_default();
+        match self.columns.get(column).and_then(|column| column.aggregate) {
+            Some(aggregate) if text.trim().is_empty() && !node.children.is_empty() => {
+                let mut leaves = Vec::new();
+                node.leaves(&mut leaves);
+                aggregate
+                    .compute(
+                        leaves.iter().map(|leaf| {
+                            leaf.cells.get(column).map(String::as_str).unwrap_or_default()
+                        }),
+                    )
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// Returns `true` if `node` or any of its descendants matches the filters.
+    fn shows(&self, node: &TreeTableNode) -> bool {
+        self.matches(node) || node.children.iter().any(|child| self.shows(child))
+    }
+
+    /// Returns the paths of the nodes in `scope`, from top to bottom.
+    fn paths(&self, scope: TableExportScope) -> Vec<Vec<usize>> {
+        fn walk(nodes: &[TreeTableNode], path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+            for (i, node) in nodes.iter().enumerate() {
+                path.push(i);
+                paths.push(path.clone());
+                walk(&node.children, path, paths);
+                path.pop();
+            }
+        }
+
+        match scope {
+            TableExportScope::Selection => self.selected.iter().cloned().collect(),
+            TableExportScope::Shown => self.rows().into_iter().map(|row| row.path).collect(),
+            TableExportScope::All => {
+                let mut paths = Vec::new();
+                walk(&self.roots, &mut Vec::new(), &mut paths);
+                paths
+            }
+        }
+    }
+
+    /// Exports the rows in `scope` as text, in the order of the columns; each cell is formatted by the renderer of its
+    /// column (see `CellRenderer::format`). The first line holds the titles of the columns.
+    pub fn export(&self, format: TableExportFormat, scope: TableExportScope) -> String {
+        let line = |cells: &mut dyn Iterator<Item = String>| {
+            cells.map(|cell| format.escape(&cell)).collect::<Vec<_>>().join(format.separator())
+        };
+
+        let mut lines = vec![line(&mut self.columns.iter().map(|column| column.title.clone()))];
+        for path in self.paths(scope) {
+            if let Some(node) = self.node(&path) {
+                lines.push(line(&mut self.columns.iter().enumerate().map(|(i, column)| {
+                    column.renderer.format(&self.cell_text(node, i), &self.locale)
+                })));
+            }
+        }
+
+        let mut text = lines.join(format.line_break());
+        text.push_str(format.line_break());
+        text
+    }
+
+    /// Exports every node to a CSV file at `path` (see `export`), e.g. as chosen by the user in a `FileBrowser`.
+    pub fn export_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.export(TableExportFormat::Csv, TableExportScope::All))
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for TreeTable
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = TreeTableWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for TreeTable
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        TreeTable {
+            columns: Vec::new(),
+            roots: Vec::new(),
+            selected: None,
+            row_height: 22.0,
+            indent: 16.0,
+            typeface: data.typography.body.clone(),
+            foreground: data.scheme.over_control_inset,
+            background: data.scheme.control_inset,
+            header_background: draw::strengthen(data.scheme.control_inset, 0.05, data.contrast),
+            highlight: data.scheme.table_selection,
+            over_highlight: data.scheme.over_table_selection,
+            inactive_highlight: data.scheme.inactive_selection,
+            over_inactive_highlight: data.scheme.over_inactive_selection,
+            border: draw::strengthen(data.scheme.control_inset, 0.15, data.contrast),
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            locale: i18n::locale(),
+            filter_row: false,
+            filters: Vec::new(),
+            frozen_columns: 0,
+            frozen_rows: 0,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> TreeTableWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            TreeTableWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.rows = obj.data.rows();
+                    obj.hovered = None;
+                    let scroll = obj.scroll;
+                    obj.scroll_to(scroll);
+                    let scroll_x = obj.scroll_x;
+                    obj.scroll_horizontally_to(scroll_x);
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("tree_table", tree_table_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.tree_table();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::TreeTableState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    columns: Vec::new(),
+                    filters: Vec::new(),
+                    body: Default::default(),
+                    scroll_columns: Default::default(),
+                    scroll_body: Default::default(),
+                    rows: Vec::new(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        let rows = data.rows();
+
+        TreeTableWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            interaction: state::InteractionState::empty(),
+            rows,
+            scroll: 0.0,
+            scroll_x: 0.0,
+            hovered: None,
+            filter_focus: None,
+            feed: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::TreeTableState> for TreeTableWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::TreeTableState {
+        let body = self.body_rect();
+        let scroll_body = self.scroll_body_rect();
+        let height = self.data.row_height;
+        let frozen = self.frozen_rows();
+        let first = frozen + (self.scroll / height).floor().max(0.0) as usize;
+        let count = (scroll_body.size.height / height).ceil() as usize + 1;
+
+        let rows = (0..frozen)
+            .chain(first..(first + count).min(self.rows.len()))
+            .map(|i| {
+                let row = &self.rows[i];
+                let node = self.data.node(&row.path);
+                state::TreeTableRowState {
+                    rect: self.row_rect(i),
+                    depth: row.depth,
+                    expanded: row.expanded,
+                    cells: node
+                        .map(|node| {
+                            self.data
+                                .columns
+                                .iter()
+                                .enumerate()
+                                .map(|(i, column)| {
+                                    column
+                                        .renderer
+                                        .render(&self.data.cell_text(node, i), &self.data.locale)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    spans: node
+                        .map(|node| node.column_spans(self.data.columns.len()))
+                        .unwrap_or_default(),
+                    selected: self.data.selected.as_ref() == Some(&row.path),
+                    hovered: self.hovered == Some(i),
+                    frozen: i < frozen,
+                }
+            })
+            .collect();
+
+        let filters = if self.data.filter_row {
+            (0..self.data.columns.len())
+                .map(|i| state::TableFilterState {
+                    rect: self.filter_rect(i),
+                    text: self.data.filters.get(i).cloned().unwrap_or_default(),
+                    focused: self.filter_focus == Some(i),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        state::TreeTableState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            columns: self.column_rects(),
+            filters,
+            body,
+            scroll_columns: self.scroll_columns_rect(),
+            scroll_body,
+            rows,
+            interaction: self.interaction,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        let scroll = self.scroll;
+        self.scroll_to(scroll);
+        let scroll_x = self.scroll_x;
+        self.scroll_horizontally_to(scroll_x);
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct TreeTableWidget {
+        widget::MAX,
+
+        <TreeTableEvent> EventQueue,
+        <TreeTable> State,
+        <state::TreeTableState> Painter,
+
+        {
+            interaction: state::InteractionState,
+            /// Rows as of the last change of the data.
+            rows: Vec<TreeTableRow>,
+            scroll: f32,
+            /// How far the columns which aren't frozen are scrolled to the left.
+            scroll_x: f32,
+            hovered: Option<usize>,
+            /// Column whose filter is being typed into.
+            filter_focus: Option<usize>,
+            feed: Option<base::UpdateFeed<TreeTableUpdate>>,
+        },
+    }
+}
+
+impl<U, G> TreeTableWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Expands the node at `path` if it's collapsed, or collapses it if it's expanded, emitting
+    /// `TreeTableEvent::Expand` or `TreeTableEvent::Collapse`. Nodes without children can't be expanded.
+    pub fn toggle(&mut self, path: &[usize]) {
+        let expanded = match self.data.node(path) {
+            Some(node) if !node.children.is_empty() => !node.expanded,
+            _ => return,
+        };
+
+        if let Some(node) = self.data.node_mut(path) {
+            node.expanded = expanded;
+        }
+        self.rows = self.data.rows();
+
+        // a selected descendant of a collapsed node would be hidden, so the node is selected instead
+        if !expanded
+            && self
+                .data
+                .selected
+                .as_ref()
+                .map_or(false, |selected| selected.len() > path.len() && selected.starts_with(path))
+        {
+            self.select(path);
+        }
+
+        self.event_queue.emit_owned(if expanded {
+            TreeTableEvent::Expand(path.to_vec())
+        } else {
+            TreeTableEvent::Collapse(path.to_vec())
+        });
+    }
+
+    /// Selects the node at `path`, emitting `TreeTableEvent::Select`, and scrolls it into view.
+    pub fn select(&mut self, path: &[usize]) {
+        if self.data.selected.as_ref().map(|selected| &selected[..]) == Some(path)
+            || self.data.node(path).is_none()
+        {
+            return;
+        }
+
+        self.data.selected = Some(path.to_vec());
+        if let Some(row) = self.rows.iter().position(|row| row.path == path) {
+            self.scroll_into_view(row);
+        }
+        self.event_queue.emit_owned(TreeTableEvent::Select(path.to_vec()));
+    }
+
+    /// Toggles the cell at `column` of the node at `path` between `true` and `false`, emitting
+    /// `TreeTableEvent::Check`. Cells which aren't a check box (see `CellRenderer::Check`) are left as they are.
+    pub fn toggle_check(&mut self, path: &[usize], column: usize) {
+        let checked = match self.data.node(path).and_then(|node| node.cells.get(column)) {
+            Some(cell) => match parse_check(cell) {
+                Some(checked) => !checked,
+                None => return,
+            },
+            None => return,
+        };
+
+        if let Some(cell) = self.data.node_mut(path).and_then(|node| node.cells.get_mut(column)) {
+            *cell = checked.to_string();
+        }
+        self.event_queue.emit_owned(TreeTableEvent::Check(path.to_vec(), column, checked));
+    }
+
+    /// Emits `TreeTableEvent::Copy` with the rows in `scope` as TSV, to be placed on the clipboard.
+    pub fn copy(&mut self, scope: TableExportScope) {
+        let text = self.data.export(TableExportFormat::Tsv, scope);
+        self.event_queue.emit_owned(TreeTableEvent::Copy(text));
+    }
+
+    /// Copies the selected row, or all the rows shown if none is selected (see `copy`).
+    pub fn copy_selection(&mut self) {
+        self.copy(if self.data.selected.is_some() {
+            TableExportScope::Selection
+        } else {
+            TableExportScope::Shown
+        });
+    }
+
+    /// Applies the changes pushed into `feed` to the nodes, as a batch once per update; the rows are only recomputed
+    /// once, however many changes were pushed since the last update. Replaces the previous feed, if any.
+    pub fn set_feed(&mut self, feed: base::UpdateFeed<TreeTableUpdate>) {
+        self.feed = Some(feed);
+    }
+
+    /// Stops applying changes from the feed, dropping any changes pending.
+    pub fn clear_feed(&mut self) {
+        self.feed = None;
+    }
+
+    /// Returns how far the rows are scrolled down.
+    #[inline]
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Scrolls the rows, clamping to their extent.
+    pub fn scroll_to(&mut self, scroll: f32) {
+        let max =
+            (self.rows.len() as f32 * self.data.row_height - self.body_rect().size.height).max(0.0);
+        let scroll = scroll.max(0.0).min(max);
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            self.hovered = None;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns how far the columns which aren't frozen are scrolled to the left.
+    #[inline]
+    pub fn horizontal_scroll(&self) -> f32 {
+        self.scroll_x
+    }
+
+    /// Scrolls the columns which aren't frozen horizontally, clamping to their extent.
+    pub fn scroll_horizontally_to(&mut self, scroll: f32) {
+        let width: f32 = self.data.columns.iter().map(|column| column.width).sum();
+        let max = (width - self.abs_rect().size.width).max(0.0);
+        let scroll = scroll.max(0.0).min(max);
+        if scroll != self.scroll_x {
+            self.scroll_x = scroll;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Scrolls horizontally so that the column at `index` is in view, unless it's frozen.
+    pub fn scroll_column_into_view(&mut self, index: usize) {
+        if index < self.data.frozen_columns {
+            return;
+        }
+        let column = match self.column_rects().get(index) {
+            Some(column) => *column,
+            None => return,
+        };
+        let view = self.scroll_columns_rect();
+        if column.min_x() < view.min_x() {
+            let scroll = self.scroll_x - (view.min_x() - column.min_x());
+            self.scroll_horizontally_to(scroll);
+        } else if column.max_x() > view.max_x() {
+            let scroll =
+                self.scroll_x + (column.max_x() - view.max_x()).min(column.min_x() - view.min_x());
+            self.scroll_horizontally_to(scroll);
+        }
+    }
+
+    /// Returns the rectangle of each column, including the header, as scrolled.
+    pub fn column_rects(&self) -> Vec<AbsoluteRect> {
+        let rect = self.abs_rect();
+        let mut x = rect.min_x();
+        let count = self.data.columns.len();
+        let frozen = self.data.frozen_columns;
+        self.data
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let width = if i + 1 == count {
+                    (rect.max_x() - x).max(column.width)
+                } else {
+                    column.width
+                };
+                let offset = if i < frozen { 0.0 } else { self.scroll_x };
+                let column_rect = AbsoluteRect::new(
+                    AbsolutePoint::new(x - offset, rect.min_y()),
+                    Size::new(width, rect.size.height).cast_unit(),
+                );
+                x += width;
+                column_rect
+            })
+            .collect()
+    }
+
+    /// Changes the filter of the column at `index`, emitting `TreeTableEvent::Filter`.
+    pub fn set_filter(&mut self, index: usize, filter: impl Into<String>) {
+        let filter = filter.into();
+        if index >= self.data.columns.len()
+            || self.data.filters.get(index).map_or(filter.is_empty(), |current| *current == filter)
+        {
+            return;
+        }
+
+        if self.data.filters.len() <= index {
+            self.data.filters.resize(index + 1, String::new());
+        }
+        self.data.filters[index] = filter.clone();
+        self.rows = self.data.rows();
+        let scroll = self.scroll;
+        self.scroll_to(scroll);
+        self.event_queue.emit_owned(TreeTableEvent::Filter(index, filter));
+    }
+
+    /// Clears the filters of every column.
+    pub fn clear_filters(&mut self) {
+        for i in 0..self.data.filters.len() {
+            self.set_filter(i, "");
+        }
+    }
+
+    /// Returns the index of the column whose filter is being typed into, if any.
+    #[inline]
+    pub fn filter_focus(&self) -> Option<usize> {
+        self.filter_focus
+    }
+
+    /// Returns the rectangle of the filter cell of a column, in the filter row beneath the header.
+    pub fn filter_rect(&self, index: usize) -> AbsoluteRect {
+        let column = self.column_rects().get(index).copied().unwrap_or_default();
+        AbsoluteRect::new(
+            AbsolutePoint::new(column.min_x(), column.min_y() + self.data.row_height),
+            Size::new(column.size.width, self.data.row_height).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle which the columns that aren't frozen are shown within, right of the frozen columns.
+    pub fn scroll_columns_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let frozen: f32 = self
+            .data
+            .columns
+            .iter()
+            .take(self.data.frozen_columns)
+            .map(|column| column.width)
+            .sum();
+        let frozen = frozen.min(rect.size.width);
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x() + frozen, rect.min_y()),
+            Size::new(rect.size.width - frozen, rect.size.height).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle which the rows that aren't frozen are shown within, below the frozen rows.
+    pub fn scroll_body_rect(&self) -> AbsoluteRect {
+        let body = self.body_rect();
+        let frozen = (self.frozen_rows() as f32 * self.data.row_height).min(body.size.height);
+        AbsoluteRect::new(
+            AbsolutePoint::new(body.min_x(), body.min_y() + frozen),
+            Size::new(body.size.width, body.size.height - frozen).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the rows, below the header (and the filter row, if shown).
+    pub fn body_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        let header_rows = if self.data.filter_row { 2.0 } else { 1.0 };
+        let header = (self.data.row_height * header_rows).min(rect.size.height);
+        AbsoluteRect::new(
+            AbsolutePoint::new(rect.min_x(), rect.min_y() + header),
+            Size::new(rect.size.width, rect.size.height - header).cast_unit(),
+        )
+    }
+
+    /// Returns the index of the column containing a given point, if any.
+    fn column_at(&self, point: AbsolutePoint) -> Option<usize> {
+        // scrolled columns are hidden beneath the frozen columns
+        let scrolled = self.scroll_columns_rect().contains(point);
+        self.column_rects().iter().enumerate().position(|(i, rect)| {
+            rect.contains(point) && (i < self.data.frozen_columns || scrolled)
+        })
+    }
+
+    /// Returns the index of the column of the cell of the node at `path` which contains a given point horizontally,
+    /// accounting for cells spanning several columns.
+    fn cell_at(&self, path: &[usize], point: AbsolutePoint) -> Option<usize> {
+        let column = self.column_at(point)?;
+        let spans = self.data.node(path)?.column_spans(self.data.columns.len());
+        (0..=column).rev().find(|&i| spans[i] > 0)
+    }
+
+    /// Returns the index of the column whose filter cell contains a given point, if any.
+    fn filter_at(&self, point: AbsolutePoint) -> Option<usize> {
+        if !self.data.filter_row {
+            return None;
+        }
+        self.column_at(point).filter(|&i| self.filter_rect(i).contains(point))
+    }
+
+    /// Returns the number of rows which are frozen.
+    fn frozen_rows(&self) -> usize {
+        self.data.frozen_rows.min(self.rows.len())
+    }
+
+    /// Returns the index of the row containing a given point, if any.
+    fn row_at(&self, point: AbsolutePoint) -> Option<usize> {
+        let body = self.body_rect();
+        if !body.contains(point) {
+            return None;
+        }
+        let y = point.y - body.min_y();
+        // scrolled rows are hidden beneath the frozen rows
+        let frozen = self.frozen_rows() as f32 * self.data.row_height;
+        let y = if y < frozen { y } else { y + self.scroll };
+        let row = (y / self.data.row_height) as usize;
+        if row < self.rows.len() {
+            Some(row)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the rectangle of a row, as scrolled.
+    fn row_rect(&self, row: usize) -> AbsoluteRect {
+        let body = self.body_rect();
+        let scroll = if row < self.frozen_rows() { 0.0 } else { self.scroll };
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                body.min_x(),
+                body.min_y() + row as f32 * self.data.row_height - scroll,
+            ),
+            Size::new(body.size.width, self.data.row_height).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the chevron of a row, which expands or collapses the node.
+    fn toggle_rect(&self, row: usize) -> AbsoluteRect {
+        let rect = self.row_rect(row);
+        let x = self.column_rects().first().map_or(rect.min_x(), |column| column.min_x());
+        AbsoluteRect::new(
+            AbsolutePoint::new(x + self.rows[row].depth as f32 * self.data.indent, rect.min_y()),
+            Size::new(self.data.indent, rect.size.height).cast_unit(),
+        )
+    }
+
+    fn selected_row(&self) -> Option<usize> {
+        let selected = self.data.selected.as_ref()?;
+        self.rows.iter().position(|row| &row.path == selected)
+    }
+
+    fn scroll_into_view(&mut self, row: usize) {
+        let frozen = self.frozen_rows();
+        if row < frozen {
+            return;
+        }
+        let frozen = frozen as f32 * self.data.row_height;
+        let top = row as f32 * self.data.row_height;
+        let height = self.body_rect().size.height;
+        if top < self.scroll + frozen {
+            self.scroll_to(top - frozen);
+        } else if top + self.data.row_height > self.scroll + height {
+            self.scroll_to(top + self.data.row_height - height);
+        }
+    }
+}
+
+/// Snapshots the widths of the columns.
+impl<U, G> history::Snapshot for TreeTableWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Snapshot = Vec<f32>;
+
+    fn snapshot(&self) -> Vec<f32> {
+        self.data.columns.iter().map(|column| column.width).collect()
+    }
+
+    /// Columns added since the snapshot keep their width, and widths of removed columns are ignored.
+    fn restore_snapshot(&mut self, snapshot: Vec<f32>) {
+        for (column, width) in self.data.columns.iter_mut().zip(snapshot) {
+            column.width = width;
+        }
+    }
+}
+
+impl<U, G> Widget for TreeTableWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let Some(feed) = &self.feed {
+            let updates = feed.drain();
+            if !updates.is_empty() {
+                // a single change of the data, handled below, rather than one for each update
+                let data = &mut *self.data;
+                for update in updates {
+                    data.apply(update);
+                }
+            }
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        if let Some(column) = self.filter_focus {
+            aux.request_text_input(base::TextInputRequest {
+                hints: base::InputHints::TEXT.without_autocorrect(),
+                area: self.filter_rect(column),
+            });
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Presentation of externally decoded video frames.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, DisplayCommand, GraphicsDisplay, RasterImageFormat, RasterImageInfo, Rect,
+            ResourceData, ResourceReference, SharedData, Size,
+        },
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Events emitted by a video surface.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum VideoSurfaceEvent {
+    /// Emitted when a new frame is presented, with the timestamp of the frame.
+    #[event_key(present)]
+    Present(Option<Duration>),
+    /// Emitted when frames are dropped, with the number of dropped frames.
+    #[event_key(drop_frames)]
+    DropFrames(u64),
+}
+
+/// Pixel data of a decoded video frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoFrameData {
+    /// 8-bit RGBA pixels in row-major order, without row padding.
+    Rgba(Arc<Vec<u8>>),
+    /// 8-bit NV12; a full resolution luma (Y) plane followed by a half resolution plane of interleaved chroma (U, V) samples,
+    /// without row padding. Converted to RGBA (BT.601, limited range) before presentation.
+    Nv12(Arc<Vec<u8>>),
+    /// Image resource already uploaded to the display (e.g. by a hardware decoder), presented as-is.
+    ///
+    /// The video surface doesn't take ownership of the resource.
+    Texture(ResourceReference),
+}
+
+/// A decoded video frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: VideoFrameData,
+    /// Time at which the frame should be presented, relative to the start of the stream.
+    /// Frames without a timestamp are presented as soon as possible.
+    pub timestamp: Option<Duration>,
+}
+
+/// How a frame is fitted into a video surface whose aspect ratio differs from the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoFit {
+    /// Scales the frame to fit entirely, filling the remaining area with bars (letterboxing/pillarboxing).
+    Contain,
+    /// Scales the frame to fill the entire area, cropping the excess.
+    Cover,
+    /// Stretches the frame to fill the entire area, ignoring the aspect ratio.
+    Stretch,
+}
+
+#[derive(Debug, Default)]
+struct FrameQueue {
+    frames: VecDeque<VideoFrame>,
+    reset_clock: bool,
+}
+
+/// Thread-safe handle through which decoded frames are submitted to a video surface.
+///
+/// Cloning the handle yields another handle to the same video surface, so it can be moved to a decoder thread.
+#[derive(Debug, Clone, Default)]
+pub struct VideoSink {
+    queue: Arc<Mutex<FrameQueue>>,
+}
+
+impl VideoSink {
+    /// Submits a decoded frame.
+    ///
+    /// Frames should be submitted in presentation order; frames which are due at the same time as a newer frame are dropped.
+    /// The video surface keeps the app updating while it has frames queued, so decoders should stay ahead of
+    /// presentation; a frame submitted once the queue has run dry is only picked up by the next update.
+    pub fn push(&self, frame: VideoFrame) {
+        self.queue.lock().unwrap().frames.push_back(frame);
+    }
+
+    /// Discards all the frames which haven't been presented yet and restarts the presentation clock,
+    /// such that the next frame is presented immediately (e.g. after seeking).
+    pub fn reset(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.frames.clear();
+        queue.reset_clock = true;
+    }
+
+    /// Returns the number of frames waiting to be presented.
+    pub fn queued(&self) -> usize {
+        self.queue.lock().unwrap().frames.len()
+    }
+}
+
+/// Converts NV12 pixels to RGBA (BT.601, limited range).
+fn nv12_to_rgba(width: u32, height: u32, nv12: &[u8]) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (luma, chroma) = nv12.split_at((width * height).min(nv12.len()));
+    let chroma_stride = (width + 1) / 2 * 2;
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let luma = luma.get(y * width + x).copied().unwrap_or(16) as f32 - 16.0;
+            let chroma_index = y / 2 * chroma_stride + x / 2 * 2;
+            let u = chroma.get(chroma_index).copied().unwrap_or(128) as f32 - 128.0;
+            let v = chroma.get(chroma_index + 1).copied().unwrap_or(128) as f32 - 128.0;
+
+            let luma = 1.164 * luma;
+            let clamp = |c: f32| c.max(0.0).min(255.0) as u8;
+            rgba.push(clamp(luma + 1.596 * v));
+            rgba.push(clamp(luma - 0.392 * u - 0.813 * v));
+            rgba.push(clamp(luma + 2.017 * u));
+            rgba.push(255);
+        }
+    }
+    rgba
+}
+
+/// Returns the portion of a frame which is shown (in frame pixels, or `None` for the entire frame) when fitted into
+/// `bounds`, and the rectangle it's shown in.
+pub(crate) fn fit_frame(
+    bounds: AbsoluteRect,
+    frame: Size,
+    fit: VideoFit,
+) -> (Option<Rect>, AbsoluteRect) {
+    if frame.width <= 0.0
+        || frame.height <= 0.0
+        || bounds.size.width <= 0.0
+        || bounds.size.height <= 0.0
+    {
+        return (None, bounds);
+    }
+
+    match fit {
+        VideoFit::Stretch => (None, bounds),
+        VideoFit::Contain => {
+            let scale = (bounds.size.width / frame.width).min(bounds.size.height / frame.height);
+            let size = frame * scale;
+            (
+                None,
+                AbsoluteRect::new(
+                    AbsolutePoint::new(
+                        bounds.origin.x + (bounds.size.width - size.width) / 2.0,
+                        bounds.origin.y + (bounds.size.height - size.height) / 2.0,
+                    ),
+                    size.cast_unit(),
+                ),
+            )
+        }
+        VideoFit::Cover => {
+            (Some(ui::fit_crop(frame, Some(bounds.size.width / bounds.size.height))), bounds)
+        }
+    }
+}
+
+/// Displays frames decoded externally (e.g. by a media framework or hardware decoder).
+///
+/// Frames are submitted through a `VideoSink` (see `VideoSurfaceWidget::sink`), possibly from another thread,
+/// and are presented according to their timestamps. Frames which are superseded by a newer due frame before being presented
+/// are dropped, as are the oldest frames whenever more than `max_queued` frames are waiting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoSurface {
+    pub fit: VideoFit,
+    /// Color of the bars around the frame, and of the surface when there is no frame.
+    pub background: Color,
+    /// Maximum number of frames waiting to be presented; older frames are dropped beyond this.
+    pub max_queued: usize,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for VideoSurface
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = VideoSurfaceWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for VideoSurface
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        // Video is conventionally shown on black, regardless of theme.
+        VideoSurface {
+            fit: VideoFit::Contain,
+            background: Color::new(0.0, 0.0, 0.0, 1.0),
+            max_queued: 8,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, _u_aux: &mut U) -> VideoSurfaceWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            VideoSurfaceWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        let painter = theme.video_surface();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::VideoSurfaceState {
+                    rect: Default::default(),
+                    data: *data,
+                    frame: None,
+                })
+                .cast_unit(),
+        );
+
+        VideoSurfaceWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            sink: Default::default(),
+            clock: None,
+            pending: None,
+            image: Default::default(),
+            texture: None,
+            frame_size: Size::zero(),
+            presented: 0,
+            dropped: 0,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::VideoSurfaceState> for VideoSurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::VideoSurfaceState {
+        state::VideoSurfaceState {
+            rect: self.abs_rect(),
+            data: *self.data,
+            frame: self.texture.or_else(|| self.image.resource()).map(|resource| {
+                let (source, rect) = fit_frame(self.abs_rect(), self.frame_size, self.data.fit);
+                state::VideoFrameState { resource, source, rect }
+            }),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct VideoSurfaceWidget {
+        widget::MAX,
+
+        <VideoSurfaceEvent> EventQueue,
+        <VideoSurface> State,
+        <state::VideoSurfaceState> Painter,
+
+        {
+            sink: VideoSink,
+            /// Instant corresponding to the timestamp of the first presented frame.
+            clock: Option<(Instant, Duration)>,
+            /// Frame to be uploaded to the display in the next draw.
+            pending: Option<VideoFrame>,
+            /// Frames given as pixels, uploaded by the video surface.
+            image: draw::UpdatableImage,
+            /// Current frame, if it was given as a texture (see `VideoFrameData::Texture`).
+            texture: Option<ResourceReference>,
+            frame_size: Size,
+            presented: u64,
+            dropped: u64,
+        },
+    }
+}
+
+impl<U, G> VideoSurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns a handle through which frames can be submitted.
+    #[inline]
+    pub fn sink(&self) -> VideoSink {
+        self.sink.clone()
+    }
+
+    /// Returns the number of frames presented so far.
+    #[inline]
+    pub fn presented_frames(&self) -> u64 {
+        self.presented
+    }
+
+    /// Returns the number of frames dropped so far.
+    #[inline]
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the size of the current frame in pixels, or zero if no frame has been presented.
+    #[inline]
+    pub fn frame_size(&self) -> Size {
+        self.frame_size
+    }
+
+    /// Uploads RGBA pixels as the current frame, returning whether the upload succeeded.
+    fn upload(
+        &mut self,
+        display: &mut dyn GraphicsDisplay,
+        width: u32,
+        height: u32,
+        pixels: Arc<Vec<u8>>,
+    ) -> bool {
+        self.image.update(
+            ResourceData::Data(SharedData::RefCount(pixels)),
+            RasterImageInfo { size: (width, height), format: RasterImageFormat::Rgba8 },
+        );
+        let uploaded = self.image.upload(display) == Some(true);
+        if uploaded {
+            self.texture = None;
+        }
+        uploaded
+    }
+
+    /// Takes the frames which are due from the sink, keeping the newest one to be presented.
+    fn receive_frames(&mut self) {
+        let now = Instant::now();
+        let mut queue = self.sink.queue.lock().unwrap();
+
+        if queue.reset_clock {
+            queue.reset_clock = false;
+            self.clock = None;
+        }
+
+        let mut dropped = 0;
+        while queue.frames.len() > self.data.max_queued.max(1) {
+            queue.frames.pop_front();
+            dropped += 1;
+        }
+
+        let mut due = None;
+        while let Some(frame) = queue.frames.front() {
+            let is_due = match (frame.timestamp, self.clock) {
+                (Some(timestamp), Some((start, start_timestamp))) => {
+                    timestamp <= start_timestamp || now - start >= timestamp - start_timestamp
+                }
+                _ => true,
+            };
+            if !is_due {
+                break;
+            }
+
+            let frame = queue.frames.pop_front().unwrap();
+            if self.clock.is_none() {
+                if let Some(timestamp) = frame.timestamp {
+                    self.clock = Some((now, timestamp));
+                }
+            }
+            if due.replace(frame).is_some() {
+                dropped += 1;
+            }
+        }
+        drop(queue);
+
+        if let Some(frame) = due {
+            // A frame which was never drawn is superseded as well.
+            if self.pending.replace(frame).is_some() {
+                dropped += 1;
+            }
+            self.command_group.repaint();
+        }
+
+        if dropped > 0 {
+            self.dropped += dropped;
+            self.event_queue.emit_owned(VideoSurfaceEvent::DropFrames(dropped));
+        }
+    }
+}
+
+impl<U, G> Widget for VideoSurfaceWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.receive_frames();
+        // frames which aren't due yet, or haven't been presented, are waited for without any input
+        if self.pending.is_some() || !self.sink.queue.lock().unwrap().frames.is_empty() {
+            aux.request_animation_frame();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        if let Some(frame) = self.pending.take() {
+            let presented = match frame.data {
+                VideoFrameData::Texture(resource) => {
+                    self.image.unload(display);
+                    self.texture = Some(resource);
+                    true
+                }
+                VideoFrameData::Rgba(ref rgba) => {
+                    self.upload(display, frame.width, frame.height, rgba.clone())
+                }
+                VideoFrameData::Nv12(ref nv12) => self.upload(
+                    display,
+                    frame.width,
+                    frame.height,
+                    Arc::new(nv12_to_rgba(frame.width, frame.height, nv12)),
+                ),
+            };
+
+            if presented {
+                self.frame_size = Size::new(frame.width as f32, frame.height as f32);
+                self.presented += 1;
+                self.event_queue.emit_owned(VideoSurfaceEvent::Present(frame.timestamp));
+            } else {
+                self.dropped += 1;
+                self.event_queue.emit_owned(VideoSurfaceEvent::DropFrames(1));
+            }
+        }
+
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
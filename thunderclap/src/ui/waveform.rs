@@ -0,0 +1,457 @@
+//! Zoomable waveform display of a sample buffer.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Events emitted by a waveform.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveformEvent {
+    /// Emitted when a range of samples has been selected by the user, with the start (inclusive) and end (exclusive).
+    #[event_key(select)]
+    Select(usize, usize),
+    /// Emitted when the selection has been cleared by the user.
+    #[event_key(deselect)]
+    Deselect,
+    /// Emitted when the view has been zoomed or scrolled by the user.
+    #[event_key(view_change)]
+    ViewChange,
+}
+
+/// Generates an unbound terminal which handles selection, zooming and scrolling.
+pub fn waveform_handler<U, G>(
+) -> vg::UnboundQueueHandler<WaveformWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        WaveformWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, _, modifiers)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && bounds.contains(*pos)
+            }) {
+                let sample = obj.sample_index_at(pos.x);
+                let anchor = match obj.data.selection {
+                    // Shift-clicking extends the selection from its furthest end.
+                    Some((start, end)) if modifiers.shift => {
+                        if sample < (start + end) / 2 { end } else { start }
+                    }
+                    _ => sample,
+                };
+                obj.selecting = Some(anchor);
+                obj.select_to(sample);
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            if obj.selecting.is_some() {
+                obj.select_to(obj.sample_index_at(pos.x));
+                event.consume();
+            }
+        }
+
+        mouse_release => {
+            if event
+                .with(|(_, button, _)| obj.selecting.is_some() && *button == base::MouseButton::Left)
+                .is_some()
+            {
+                obj.selecting = None;
+                match obj.data.selection {
+                    Some((start, end)) => {
+                        obj.event_queue.emit_owned(WaveformEvent::Select(start, end))
+                    }
+                    None => obj.event_queue.emit_owned(WaveformEvent::Deselect),
+                }
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((pos, delta, _)) =
+                event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos))
+            {
+                if delta.y != 0.0 {
+                    let zoom = obj.data.zoom * 2f64.powf(-(delta.y * obj.data.zoom_speed) as f64);
+                    obj.zoom_at(pos.x, zoom);
+                }
+                if delta.x != 0.0 {
+                    let offset = obj.data.offset - delta.x as f64 * obj.data.zoom;
+                    obj.scroll_to(offset);
+                }
+                obj.event_queue.emit_owned(WaveformEvent::ViewChange);
+            }
+        }
+    }
+}
+
+/// Displays a buffer of audio samples as a waveform, which can be zoomed (by scrolling vertically),
+/// scrolled (by scrolling horizontally), and selected from (by dragging).
+///
+/// Samples are owned by the widget (see `WaveformWidget::append`), so that streaming data in only requires
+/// measuring the new samples, and only causes a repaint if they're in view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// Index of the sample at the left edge of the view.
+    pub offset: f64,
+    /// Number of samples per logical pixel.
+    pub zoom: f64,
+    /// Smallest number of samples per logical pixel, i.e. the furthest the view can be zoomed in.
+    pub min_zoom: f64,
+    /// Zoom change, in powers of two, per logical pixel scrolled.
+    pub zoom_speed: f32,
+    /// Selected range of samples; start (inclusive) and end (exclusive).
+    pub selection: Option<(usize, usize)>,
+    /// Whether the view scrolls to keep the newest samples in view as they are appended,
+    /// provided the end of the buffer was in view beforehand.
+    pub follow: bool,
+    pub color: Color,
+    /// Color of the selection.
+    pub highlight: Color,
+    pub background: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Waveform
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = WaveformWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Waveform
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Waveform {
+            offset: 0.0,
+            zoom: 64.0,
+            min_zoom: 1.0 / 16.0,
+            zoom_speed: 0.0125,
+            selection: None,
+            follow: true,
+            color: data.scheme.primary,
+            highlight: data.scheme.focus,
+            background: data.scheme.control_inset,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> WaveformWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            WaveformWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("waveform", waveform_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.waveform();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::WaveformState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    columns: Vec::new(),
+                    selection: None,
+                })
+                .cast_unit(),
+        );
+
+        WaveformWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            samples: Vec::new(),
+            columns: Vec::new(),
+            columns_view: (0.0, 0.0, 0),
+            dirty_from: None,
+            selecting: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::WaveformState> for WaveformWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::WaveformState {
+        let bounds = self.abs_rect();
+        state::WaveformState {
+            rect: bounds,
+            data: self.data.clone(),
+            columns: self.columns.clone(),
+            selection: self.data.selection.map(|(start, end)| {
+                let (min_x, max_x) = (self.sample_to_x(start as f64), self.sample_to_x(end as f64));
+                let min_x = min_x.max(bounds.min_x());
+                let max_x = max_x.min(bounds.max_x()).max(min_x);
+                AbsoluteRect::new(
+                    AbsolutePoint::new(min_x, bounds.min_y()),
+                    Size::new(max_x - min_x, bounds.size.height).cast_unit(),
+                )
+            }),
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct WaveformWidget {
+        widget::MAX,
+
+        <WaveformEvent> EventQueue,
+        <Waveform> State,
+        <state::WaveformState> Painter,
+
+        {
+            samples: Vec<f32>,
+            /// Minimum and maximum sample of each column of pixels in view.
+            columns: Vec<(f32, f32)>,
+            /// Offset, zoom and width which `columns` were measured for.
+            columns_view: (f64, f64, usize),
+            /// Index of the first sample which changed since `columns` were measured.
+            dirty_from: Option<usize>,
+            /// Sample from which the selection being dragged started.
+            selecting: Option<usize>,
+        },
+    }
+}
+
+impl<U, G> WaveformWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the samples.
+    #[inline]
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Replaces all the samples.
+    pub fn set_samples(&mut self, samples: Vec<f32>) {
+        self.samples = samples;
+        self.dirty_from = Some(0);
+    }
+
+    /// Appends samples to the end of the buffer.
+    pub fn append(&mut self, samples: &[f32]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        let old_len = self.samples.len();
+        let end_visible = self.data.offset + self.view_samples() >= old_len as f64;
+        self.samples.extend_from_slice(samples);
+        self.dirty_from = Some(self.dirty_from.map_or(old_len, |from| from.min(old_len)));
+
+        if self.data.follow && end_visible && self.selecting.is_none() {
+            // Scroll by whole columns, so that the measured columns can be reused.
+            let columns = (self.samples.len() as f64 / self.data.zoom - self.view_width() as f64)
+                .ceil()
+                .max(0.0);
+            let offset = columns * self.data.zoom;
+            if offset > self.data.offset {
+                self.data.offset = offset;
+            }
+        }
+    }
+
+    /// Removes all the samples and the selection.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.data.offset = 0.0;
+        self.data.selection = None;
+        self.dirty_from = Some(0);
+    }
+
+    /// Returns the (fractional) sample index at a given horizontal position, in absolute coordinates.
+    pub fn sample_at(&self, x: f32) -> f64 {
+        self.data.offset + (x - self.abs_rect().min_x()) as f64 * self.data.zoom
+    }
+
+    /// Returns the horizontal position of a (fractional) sample index, in absolute coordinates.
+    pub fn sample_to_x(&self, sample: f64) -> f32 {
+        self.abs_rect().min_x() + ((sample - self.data.offset) / self.data.zoom) as f32
+    }
+
+    /// Changes the zoom while keeping the sample at a given horizontal position in place.
+    pub fn zoom_at(&mut self, x: f32, zoom: f64) {
+        let anchor = self.sample_at(x);
+        let max_zoom =
+            (self.samples.len() as f64 / self.view_width() as f64).max(self.data.min_zoom);
+        self.data.zoom = zoom.max(self.data.min_zoom).min(max_zoom);
+        let offset = anchor - (x - self.abs_rect().min_x()) as f64 * self.data.zoom;
+        self.scroll_to(offset);
+    }
+
+    /// Zooms out such that all the samples are in view.
+    pub fn zoom_to_fit(&mut self) {
+        self.data.zoom =
+            (self.samples.len() as f64 / self.view_width() as f64).max(self.data.min_zoom);
+        self.data.offset = 0.0;
+    }
+
+    /// Scrolls such that a given sample is at the left edge of the view, keeping the view within the samples.
+    pub fn scroll_to(&mut self, offset: f64) {
+        let max_offset = (self.samples.len() as f64 - self.view_samples()).max(0.0);
+        self.data.offset = offset.min(max_offset).max(0.0);
+    }
+
+    fn sample_index_at(&self, x: f32) -> usize {
+        (self.sample_at(x).round().max(0.0) as usize).min(self.samples.len())
+    }
+
+    fn select_to(&mut self, sample: usize) {
+        if let Some(anchor) = self.selecting {
+            self.data.selection = if anchor == sample {
+                None
+            } else {
+                Some((anchor.min(sample), anchor.max(sample)))
+            };
+        }
+    }
+
+    #[inline]
+    fn view_width(&self) -> usize {
+        self.abs_rect().size.width.max(1.0).ceil() as usize
+    }
+
+    #[inline]
+    fn view_samples(&self) -> f64 {
+        self.view_width() as f64 * self.data.zoom
+    }
+
+    /// Minimum and maximum of the samples covered by a column of pixels.
+    fn measure_column(&self, column: usize) -> Option<(f32, f32)> {
+        let (offset, zoom) = (self.data.offset, self.data.zoom);
+        let start = (offset + column as f64 * zoom).floor().max(0.0) as usize;
+        let end = ((offset + (column + 1) as f64 * zoom).ceil() as usize).max(start + 1);
+        let samples = self.samples.get(start..end.min(self.samples.len()))?;
+        if samples.is_empty() {
+            return None;
+        }
+
+        Some(samples.iter().fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(min, max), s| {
+            (min.min(*s), max.max(*s))
+        }))
+    }
+
+    /// Brings `columns` up to date with the view and samples, measuring as few columns as possible.
+    fn update_columns(&mut self) {
+        let view = (self.data.offset, self.data.zoom, self.view_width());
+        let (old_offset, old_zoom, old_width) = self.columns_view;
+
+        let mut first_dirty = match self.dirty_from.take() {
+            Some(sample) => ((sample as f64 - view.0) / view.1).floor().max(0.0) as usize,
+            None => std::usize::MAX,
+        };
+
+        if view != self.columns_view {
+            let shift = (view.0 - old_offset) / view.1;
+            let whole_columns = shift >= 0.0 && (shift - shift.round()).abs() < 1e-6;
+            if view.1 == old_zoom && view.2 == old_width && whole_columns {
+                // Scrolled forward by whole columns; keep the columns still in view.
+                let shift = (shift.round() as usize).min(self.columns.len());
+                self.columns.drain(..shift);
+                first_dirty = first_dirty.min(self.columns.len());
+            } else {
+                first_dirty = 0;
+            }
+            self.columns_view = view;
+        }
+
+        // Nothing changed within the view.
+        if first_dirty >= view.2 {
+            return;
+        }
+
+        let first_dirty = first_dirty.min(self.columns.len());
+        self.columns.truncate(first_dirty);
+        for column in first_dirty..view.2 {
+            match self.measure_column(column) {
+                Some(measured) => self.columns.push(measured),
+                None => break,
+            }
+        }
+
+        self.command_group.repaint();
+    }
+}
+
+impl<U, G> Widget for WaveformWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.command_group.repaint();
+        }
+
+        self.update_columns();
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,624 @@
+//! Multi-step wizard guiding the user through a sequence of pages.
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        i18n, ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// Horizontal space between the edges of a button and its label.
+const BUTTON_PADDING: f32 = 12.0;
+/// Space between buttons, and between the step indicator and the step titles.
+const SPACING: f32 = 8.0;
+
+/// Events emitted by a wizard.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum WizardEvent {
+    /// Emitted when the current step is changed by the user, with the new step.
+    #[event_key(step_changed)]
+    StepChanged(usize),
+    /// Emitted when the wizard is finished from the last step.
+    #[event_key(finished)]
+    Finished,
+    /// Emitted when the wizard is cancelled.
+    #[event_key(cancelled)]
+    Cancelled,
+}
+
+/// Button in the footer of a wizard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WizardButton {
+    Cancel,
+    Back,
+    /// Shown on every step but the last.
+    Next,
+    /// Shown on the last step.
+    Finish,
+}
+
+/// Decides whether the user may leave a step of a wizard forwards (by going to a later step or finishing).
+pub trait WizardValidator {
+    /// Returns `true` if the (zero-based) step is complete.
+    fn validate(&mut self, step: usize) -> bool;
+}
+
+impl<F: FnMut(usize) -> bool> WizardValidator for F {
+    #[inline]
+    fn validate(&mut self, step: usize) -> bool {
+        self(step)
+    }
+}
+
+struct ChildData {
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    visibility: base::LayoutVisibility,
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+    step: usize,
+}
+
+/// Generates an unbound terminal which handles pressing and hovering the buttons of the wizard.
+pub fn wizard_handler<U, G>() -> vg::UnboundQueueHandler<WizardWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        WizardWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && obj.button_at(*pos).is_some()
+            }) {
+                match obj.button_at(*pos) {
+                    Some(WizardButton::Cancel) => obj.cancel(),
+                    Some(WizardButton::Back) => obj.back(),
+                    Some(WizardButton::Next) => {
+                        obj.next();
+                    }
+                    Some(WizardButton::Finish) => {
+                        obj.finish();
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = *event.get();
+            let hovered = obj.button_at(pos);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.command_group.repaint();
+            }
+        }
+    }
+}
+
+/// Sequence of pages shown one at a time, with an indicator of the steps and back/next/finish buttons.
+///
+/// Pages are added by pushing widgets into the wizard as a layout, along with the (zero-based) step they belong to;
+/// a step can have several children, which are placed in the content area preserving their arrangement relative to
+/// each other. Only the children of the current step are visible.
+///
+/// Before going forwards, the current step is checked by the validator given to `WizardWidget::set_validator`,
+/// if any, and the wizard stays on the step if it isn't valid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wizard {
+    /// Title of each step, shown in the step indicator.
+    pub steps: Vec<String>,
+    /// Current step, from zero.
+    pub step: usize,
+    /// Whether a cancel button is shown.
+    pub cancellable: bool,
+    /// Space between the edges of the wizard and its content.
+    pub padding: f32,
+    pub back_label: String,
+    pub next_label: String,
+    pub finish_label: String,
+    pub cancel_label: String,
+    pub typeface: draw::TypefaceStyle,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of completed and current steps, and of the next and finish buttons.
+    pub accent: Color,
+    /// Color of content drawn over `accent`.
+    pub over_accent: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl Wizard {
+    /// Returns `true` if the current step is the last.
+    #[inline]
+    pub fn is_last_step(&self) -> bool {
+        self.step + 1 >= self.steps.len()
+    }
+
+    /// Returns the label of a button.
+    pub fn label(&self, button: WizardButton) -> &str {
+        match button {
+            WizardButton::Cancel => &self.cancel_label,
+            WizardButton::Back => &self.back_label,
+            WizardButton::Next => &self.next_label,
+            WizardButton::Finish => &self.finish_label,
+        }
+    }
+
+    /// Returns `true` if a button can be pressed.
+    pub fn is_enabled(&self, button: WizardButton) -> bool {
+        !self.disabled && (button != WizardButton::Back || self.step > 0)
+    }
+
+    /// Returns the buttons shown on the current step, from left to right.
+    pub fn buttons(&self) -> Vec<WizardButton> {
+        let mut buttons = Vec::with_capacity(3);
+        if self.cancellable {
+            buttons.push(WizardButton::Cancel);
+        }
+        buttons.push(WizardButton::Back);
+        buttons.push(if self.is_last_step() { WizardButton::Finish } else { WizardButton::Next });
+        buttons
+    }
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Wizard
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = WizardWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for Wizard
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        let locale = i18n::locale();
+        Wizard {
+            steps: Vec::new(),
+            step: 0,
+            cancellable: true,
+            padding: 15.0,
+            back_label: locale.localize_text("Back"),
+            next_label: locale.localize_text("Next"),
+            finish_label: locale.localize_text("Finish"),
+            cancel_label: locale.localize_text("Cancel"),
+            typeface: data.typography.button.clone(),
+            foreground: data.scheme.over_background,
+            background: data.scheme.background,
+            accent: data.scheme.primary,
+            over_accent: data.scheme.over_primary,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    fn construct(self, theme: &dyn draw::Theme, u_aux: &mut U) -> WizardWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            WizardWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.sync_step();
+                    obj.dirty = true;
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("wizard", wizard_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.wizard();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::WizardState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    indicator: Default::default(),
+                    buttons: Vec::new(),
+                    hovered: None,
+                })
+                .cast_unit(),
+        );
+
+        let step = data.step;
+        WizardWidgetBuilder {
+            rect,
+            graph: graph.into(),
+            data,
+            painter,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+            step,
+            validator: None,
+            indicator: Default::default(),
+            content: Default::default(),
+            buttons: Vec::new(),
+            hovered: None,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<state::WizardState> for WizardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) -> state::WizardState {
+        state::WizardState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            indicator: self.indicator,
+            buttons: self.buttons.clone(),
+            hovered: self.hovered,
+        }
+    }
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct WizardWidget {
+        widget::MAX,
+
+        <WizardEvent> EventQueue,
+        <Wizard> State,
+        <state::WizardState> Painter,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+            /// Step whose children are visible.
+            step: usize,
+            validator: Option<Box<dyn WizardValidator>>,
+            /// Rectangle of the step indicator.
+            indicator: AbsoluteRect,
+            /// Rectangle in which the children of the current step are placed.
+            content: AbsoluteRect,
+            /// Buttons shown and their rectangles.
+            buttons: Vec<(WizardButton, AbsoluteRect)>,
+            hovered: Option<WizardButton>,
+        },
+    }
+}
+
+impl<U, G> WizardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes the validator deciding whether the user may leave a step forwards.
+    pub fn set_validator(&mut self, validator: impl WizardValidator + 'static) {
+        self.validator = Some(Box::new(validator));
+    }
+
+    /// Removes the validator, letting the user leave every step.
+    pub fn clear_validator(&mut self) {
+        self.validator = None;
+    }
+
+    /// Goes to a step, emitting `WizardEvent::StepChanged`.
+    ///
+    /// Going forwards requires the current step to be valid. Returns whether the step was changed.
+    pub fn go_to(&mut self, step: usize) -> bool {
+        let step = step.min(self.data.steps.len().saturating_sub(1));
+        if step == self.data.step || (step > self.data.step && !self.validate()) {
+            return false;
+        }
+
+        self.data.step = step;
+        self.event_queue.emit_owned(WizardEvent::StepChanged(step));
+        true
+    }
+
+    /// Goes to the previous step, if any.
+    pub fn back(&mut self) {
+        if self.data.step > 0 {
+            let step = self.data.step - 1;
+            self.go_to(step);
+        }
+    }
+
+    /// Goes to the next step if the current step is valid, or finishes the wizard from the last step.
+    /// Returns whether the step was changed or the wizard was finished.
+    pub fn next(&mut self) -> bool {
+        if self.data.is_last_step() {
+            self.finish()
+        } else {
+            let step = self.data.step + 1;
+            self.go_to(step)
+        }
+    }
+
+    /// Emits `WizardEvent::Finished` if the current step is the last and is valid.
+    /// Returns whether the wizard was finished.
+    pub fn finish(&mut self) -> bool {
+        if self.data.is_last_step() && self.validate() {
+            self.event_queue.emit_owned(WizardEvent::Finished);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Emits `WizardEvent::Cancelled`.
+    pub fn cancel(&mut self) {
+        self.event_queue.emit_owned(WizardEvent::Cancelled);
+    }
+
+    /// Returns the enabled button containing a given point, if any.
+    pub fn button_at(&self, point: AbsolutePoint) -> Option<WizardButton> {
+        self.buttons
+            .iter()
+            .find(|(button, rect)| rect.contains(point) && self.data.is_enabled(*button))
+            .map(|(button, _)| *button)
+    }
+
+    /// Returns the rectangle in which the children of the current step are placed, in absolute coordinates.
+    #[inline]
+    pub fn content_rect(&self) -> AbsoluteRect {
+        self.content
+    }
+
+    fn validate(&mut self) -> bool {
+        let step = self.data.step;
+        self.validator.as_mut().map_or(true, |validator| validator.validate(step))
+    }
+
+    /// Shows the children of the current step and hides the rest.
+    fn sync_step(&mut self) {
+        self.step = self.data.step;
+        for (_, data) in &self.rects {
+            data.visibility.set(self.child_visibility(data.step));
+        }
+    }
+
+    fn child_visibility(&self, step: usize) -> base::Visibility {
+        if step == self.step {
+            base::Visibility::Normal
+        } else {
+            base::Visibility::None
+        }
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        if text.is_empty() {
+            return 0.0;
+        }
+
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+        }
+        .bounds()
+        .map(|bounds| bounds.size.width)
+        .unwrap_or(0.0)
+    }
+
+    /// Places the step indicator at the top, the buttons at the bottom, and the content in between.
+    fn update_layout(&mut self) {
+        let bounds = self.abs_rect();
+        let padding = self.data.padding;
+        let line = (self.data.typeface.size * 1.6).round();
+        let inner = bounds.inflate(-padding, -padding);
+
+        // a circle per step, with the title of the step below it
+        self.indicator = AbsoluteRect::new(
+            inner.origin,
+            Size::new(inner.size.width, line * 2.0 + SPACING).cast_unit(),
+        );
+
+        let button_height = (self.data.typeface.size * 2.2).round();
+        let top = inner.max_y() - button_height;
+        let mut buttons = Vec::new();
+        // the cancel button sits on the left, apart from the others on the right
+        let mut x = inner.max_x();
+        for button in self.data.buttons().into_iter().rev() {
+            let width = (self.text_width(self.data.label(button)) + BUTTON_PADDING * 2.0).max(80.0);
+            let left = if button == WizardButton::Cancel { inner.min_x() } else { x - width };
+            buttons.push((
+                button,
+                AbsoluteRect::new(
+                    AbsolutePoint::new(left, top),
+                    Size::new(width, button_height).cast_unit(),
+                ),
+            ));
+            x = left - SPACING;
+        }
+        buttons.reverse();
+        if !buttons.iter().any(|(button, _)| Some(*button) == self.hovered) {
+            self.hovered = None;
+        }
+        self.buttons = buttons;
+
+        let content_top = self.indicator.max_y() + padding;
+        self.content = AbsoluteRect::new(
+            AbsolutePoint::new(inner.min_x(), content_top),
+            Size::new(inner.size.width, (top - padding - content_top).max(0.0)).cast_unit(),
+        );
+
+        // Place the children of each step as a whole at the top-left of the content area.
+        let steps = self.rects.values().map(|data| data.step).max().map_or(0, |max| max + 1);
+        for step in 0..steps {
+            let mut origin: Option<AbsolutePoint> = None;
+            for data in self.rects.values().filter(|data| data.step == step) {
+                origin = Some(origin.map_or(data.rect.origin, |origin| {
+                    AbsolutePoint::new(
+                        origin.x.min(data.rect.origin.x),
+                        origin.y.min(data.rect.origin.y),
+                    )
+                }));
+            }
+
+            let offset = match origin {
+                Some(origin) => self.content.origin - origin,
+                None => continue,
+            };
+            if offset != Default::default() {
+                for data in self.rects.values_mut().filter(|data| data.step == step) {
+                    data.rect = data.rect.translate(offset);
+                    data.evq.emit_owned(data.rect);
+                }
+            }
+        }
+    }
+}
+
+impl<U, G> base::Layout for WizardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Step which the child belongs to; `None` for the first step.
+    type PushData = usize;
+
+    fn push(&mut self, data: Option<usize>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let step = data.unwrap_or(0);
+        let evq = BidirSingleEventQueue::new();
+        let visibility = base::LayoutVisibility::default();
+        visibility.set(self.child_visibility(step));
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: visibility.clone(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                drop_listener: child.drop_event().listen(),
+                visibility,
+                rect,
+                original_rect: rect,
+                id,
+                step,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for WizardWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.repaint();
+            self.dirty = false;
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Layout which layers children on top of each other.
+
+use {
+    crate::{base, draw, geom::*, ui},
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener},
+        prelude::*,
+        verbgraph as vg,
+    },
+};
+
+/// How a child of a `ZStack` is placed within the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZStackAlign {
+    /// Stretched over the whole stack.
+    Fill,
+    /// Centered in the stack, keeping its size.
+    Center,
+    /// Against a corner of the stack, keeping its size.
+    Corner(ui::Corner),
+}
+
+impl Default for ZStackAlign {
+    #[inline]
+    fn default() -> Self {
+        ZStackAlign::Fill
+    }
+}
+
+/// Returns the bounds of a stack without its padding. The padding is never negative, nor more than half of the stack,
+/// so the bounds never have a negative size.
+fn inner_bounds(rect: AbsoluteRect, padding: f32) -> AbsoluteRect {
+    let padding = padding.max(0.0);
+    rect.inflate(-padding.min(rect.size.width / 2.0), -padding.min(rect.size.height / 2.0))
+}
+
+/// Places a child of a given size within `bounds` (the stack without its padding).
+///
+/// The child is shrunk to fit within `bounds`, but `size` is left as is, so the child regains its size once the stack
+/// is large enough again.
+fn align(bounds: AbsoluteRect, align: ZStackAlign, size: Size) -> AbsoluteRect {
+    let size = Size::new(
+        size.width.min(bounds.size.width).max(0.0),
+        size.height.min(bounds.size.height).max(0.0),
+    );
+    let origin = match align {
+        ZStackAlign::Fill => return bounds,
+        ZStackAlign::Center => AbsolutePoint::new(
+            bounds.center().x - size.width / 2.0,
+            bounds.center().y - size.height / 2.0,
+        ),
+        ZStackAlign::Corner(corner) => AbsolutePoint::new(
+            if corner.is_left() { bounds.min_x() } else { bounds.max_x() - size.width },
+            if corner.is_top() { bounds.min_y() } else { bounds.max_y() - size.height },
+        ),
+    };
+    AbsoluteRect::new(origin, size.cast_unit())
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: ZStackAlign,
+    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    drop_listener: RcEventListener<base::DropEvent>,
+    /// Rectangle the child was placed in.
+    rect: AbsoluteRect,
+    /// Size of the child as given by itself, which may be larger than it was placed as.
+    size: Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// Layout which layers children on top of each other, in the order they were pushed; e.g. a watermark behind some
+/// content, a badge at the corner of an icon, or a loading overlay over a form.
+///
+/// Each child is either stretched over the stack (`ZStackAlign::Fill`, the default) or keeps its size and is centered
+/// or placed against a corner.
+///
+/// Widgets receive input before their older siblings and are drawn after them, so the children should also be
+/// declared in push order for the topmost child to be the first to receive (and consume) input. Likewise, the stack
+/// goes through its children from the top down, and `child_at` hit-tests the layers from the top down, for widgets
+/// which handle input on behalf of the whole stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZStack {
+    /// Inset of the children from the edges of the stack.
+    pub padding: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ZStack
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ZStackWidget<U, G>;
+}
+
+impl<U, G> ui::WidgetConstructor<U, G> for ZStack
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        ZStack { padding: 0.0 }
+    }
+
+    fn construct(self, _theme: &dyn draw::Theme, _u_aux: &mut U) -> ZStackWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            ZStackWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.dirty = true;
+                }
+            }
+        };
+
+        ZStackWidgetBuilder {
+            rect: Default::default(),
+            graph: graph.into(),
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dirty: true,
+        }
+        .build()
+    }
+}
+
+impl<U, G> ui::core::CoreWidget<()> for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn derive_state(&self) {}
+
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.layout.notify(self.abs_rect());
+    }
+}
+
+use crate as thunderclap;
+crate::widget! {
+    pub struct ZStackWidget {
+        widget::MAX,
+
+        <ZStack> State,
+
+        {
+            rects: IndexMap<u64, ChildData>,
+            next_rect_id: u64,
+            dirty: bool,
+        },
+    }
+}
+
+impl<U, G> ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Changes how a child which has been pushed is placed, by its layout ID.
+    pub fn set_align(&mut self, id: u64, align: ZStackAlign) {
+        if let Some(child) = self.rects.get_mut(&id) {
+            child.data = align;
+            self.dirty = true;
+        }
+    }
+
+    /// Returns how a child which has been pushed is placed, by its layout ID.
+    pub fn align(&self, id: u64) -> Option<ZStackAlign> {
+        self.rects.get(&id).map(|child| child.data)
+    }
+
+    /// Returns the layout ID of the topmost child at `point` (i.e. the last pushed), if any.
+    pub fn child_at(&self, point: AbsolutePoint) -> Option<u64> {
+        self.rects.values().rev().find(|child| child.rect.contains(point)).map(|child| child.id)
+    }
+
+    fn update_layout(&mut self) {
+        let bounds = inner_bounds(self.abs_rect(), self.data.padding);
+
+        for child in self.rects.values_mut().rev() {
+            let rect = align(bounds, child.data, child.size);
+            if rect != child.rect {
+                child.rect = rect;
+                child.evq.emit_owned(rect);
+            }
+        }
+    }
+}
+
+impl<U, G> base::Layout for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ZStackAlign;
+
+    fn push(&mut self, data: Option<ZStackAlign>, child: &mut impl base::LayableWidget) {
+        self.dirty = true;
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            visibility: Default::default(),
+            clip: Default::default(),
+            focus_group: Default::default(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data: data.unwrap_or_default(),
+                evq,
+                drop_listener: child.drop_event().listen(),
+                rect,
+                size: rect.size.cast_unit(),
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.shift_remove(&id)) {
+            self.dirty = true;
+            child.listen_to_layout(None);
+            if restore_original {
+                child.set_ctxt_rect(data.original_rect);
+            }
+        }
+    }
+}
+
+impl<U, G> Widget for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(rect) = self.layout.receive() {
+            self.set_ctxt_rect(rect);
+            self.dirty = true;
+        }
+
+        {
+            let mut removals = Vec::new();
+            let dirty = &mut self.dirty;
+            for data in self.rects.values_mut().rev() {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    *dirty = true;
+                    continue;
+                }
+
+                if let Some(new_ev) = data.evq.retrieve_newest() {
+                    if new_ev != data.rect {
+                        *dirty = true;
+                        data.rect = new_ev;
+                        data.size = new_ev.size.cast_unit();
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.shift_remove(&removal);
+            }
+        }
+
+        if self.dirty {
+            self.update_layout();
+            self.dirty = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> AbsoluteRect {
+        AbsoluteRect::new(AbsolutePoint::new(x, y), Size::new(width, height).cast_unit())
+    }
+
+    #[test]
+    fn padding_is_clamped() {
+        let stack = rect(0.0, 0.0, 100.0, 40.0);
+        assert_eq!(inner_bounds(stack, 10.0), rect(10.0, 10.0, 80.0, 20.0));
+        assert_eq!(inner_bounds(stack, -10.0), stack);
+        assert_eq!(inner_bounds(stack, 30.0), rect(30.0, 20.0, 40.0, 0.0));
+        assert_eq!(inner_bounds(stack, 80.0), rect(50.0, 20.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn children_keep_their_size_when_shrunk() {
+        let size = Size::new(50.0, 50.0);
+        let corner = ZStackAlign::Corner(ui::Corner::BottomRight);
+
+        let small = align(rect(0.0, 0.0, 30.0, 30.0), corner, size);
+        assert_eq!(small, rect(0.0, 0.0, 30.0, 30.0));
+        let large = align(rect(0.0, 0.0, 100.0, 100.0), corner, size);
+        assert_eq!(large, rect(50.0, 50.0, 50.0, 50.0));
+
+        assert_eq!(
+            align(rect(0.0, 0.0, 100.0, 100.0), ZStackAlign::Center, size),
+            rect(25.0, 25.0, 50.0, 50.0)
+        );
+    }
+}